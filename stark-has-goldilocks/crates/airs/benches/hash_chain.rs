@@ -0,0 +1,35 @@
+use airs::HashChainAir;
+use ark_goldilocks::Goldilocks as F;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use deep_ali::air::{min_blowup, prove_air, verify_air, AirProofOptions};
+use poseidon::params::generate_params_t17_x5;
+
+const NUM_QUERIES: usize = 32;
+
+fn bench_hash_chain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash_chain_poseidon_t17");
+    group.sample_size(10);
+
+    let params = generate_params_t17_x5(b"AIRS-HASH-CHAIN-BENCH");
+    let seed: [F; poseidon::T] = core::array::from_fn(|i| F::from(i as u64));
+
+    for &steps in &[1usize << 2, 1 << 4, 1 << 6] {
+        let air = HashChainAir::new(params.clone(), seed, steps);
+        let opts = AirProofOptions::new(min_blowup(&air), NUM_QUERIES);
+        let trace = air.build_trace();
+
+        group.bench_with_input(BenchmarkId::new("prove", steps), &steps, |b, _| {
+            b.iter(|| prove_air(&air, trace.clone(), &opts));
+        });
+
+        let proof = prove_air(&air, trace.clone(), &opts);
+        group.bench_with_input(BenchmarkId::new("verify", steps), &steps, |b, _| {
+            b.iter(|| assert!(verify_air(&air, &opts, &proof)));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_hash_chain);
+criterion_main!(benches);
@@ -0,0 +1,115 @@
+//! Fibonacci sequence: `(a, b) → (b, a + b)` from `(1, 1)`.
+
+use alloc::{vec, vec::Vec};
+use ark_ff::One;
+use ark_goldilocks::Goldilocks as F;
+use deep_ali::air::{Air, BoundaryConstraint, EvaluationFrame};
+
+/// Claims that the `b` column ends in `result` after `trace_length` rows.
+pub struct FibonacciAir {
+    trace_length: usize,
+    result: F,
+}
+
+impl FibonacciAir {
+    pub fn new(trace_length: usize, result: F) -> Self {
+        assert!(
+            trace_length.is_power_of_two(),
+            "trace length must be a power of two"
+        );
+        Self {
+            trace_length,
+            result,
+        }
+    }
+
+    /// Columns `[a, b]` with `a_0 = b_0 = 1`.
+    pub fn build_trace(trace_length: usize) -> Vec<Vec<F>> {
+        let mut a = Vec::with_capacity(trace_length);
+        let mut b = Vec::with_capacity(trace_length);
+        let (mut x, mut y) = (F::one(), F::one());
+        for _ in 0..trace_length {
+            a.push(x);
+            b.push(y);
+            (x, y) = (y, x + y);
+        }
+        vec![a, b]
+    }
+
+    /// Last `b` of [`build_trace`](Self::build_trace).
+    pub fn compute(trace_length: usize) -> F {
+        Self::build_trace(trace_length)[1][trace_length - 1]
+    }
+}
+
+impl Air for FibonacciAir {
+    fn trace_width(&self) -> usize {
+        2
+    }
+
+    fn trace_length(&self) -> usize {
+        self.trace_length
+    }
+
+    fn num_transition_constraints(&self) -> usize {
+        2
+    }
+
+    fn transition_degree(&self) -> usize {
+        1
+    }
+
+    fn evaluate_transition(&self, frame: &EvaluationFrame<'_>, out: &mut [F]) {
+        let (a, b) = (frame.current[0], frame.current[1]);
+        out[0] = frame.next[0] - b;
+        out[1] = frame.next[1] - (a + b);
+    }
+
+    fn boundary_constraints(&self, _challenges: &[F]) -> Vec<BoundaryConstraint> {
+        vec![
+            BoundaryConstraint::new(0, 0, F::one()),
+            BoundaryConstraint::new(1, 0, F::one()),
+            BoundaryConstraint::new(1, self.trace_length - 1, self.result),
+        ]
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        vec![self.result]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deep_ali::air::{first_unsatisfied_row, prove_air, verify_air, AirProofOptions};
+
+    #[test]
+    fn trace_satisfies_air() {
+        let air = FibonacciAir::new(32, FibonacciAir::compute(32));
+        assert_eq!(FibonacciAir::compute(8), F::from(34u64));
+        assert_eq!(
+            first_unsatisfied_row(&air, &FibonacciAir::build_trace(32), &[]),
+            None
+        );
+
+        let wrong = FibonacciAir::new(32, FibonacciAir::compute(32) + F::one());
+        assert_eq!(
+            first_unsatisfied_row(&wrong, &FibonacciAir::build_trace(32), &[]),
+            Some(31)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn prove_verify_roundtrip() {
+        const N: usize = 1 << 8;
+        let opts = AirProofOptions::new(4, 16);
+        let air = FibonacciAir::new(N, FibonacciAir::compute(N));
+
+        let proof = prove_air(&air, FibonacciAir::build_trace(N), &opts);
+        assert!(verify_air(&air, &opts, &proof));
+
+        let wrong = FibonacciAir::new(N, FibonacciAir::compute(N) + F::one());
+        assert!(!verify_air(&wrong, &opts, &proof));
+    }
+}
@@ -0,0 +1,175 @@
+//! Hash chain / VDF: `steps` sequential Poseidon permutations of one state.
+//!
+//! Each permutation occupies [`ROWS_PER_PERMUTATION`] rows laid out exactly
+//! as in [`PoseidonAir`](crate::PoseidonAir), so the same periodic round
+//! columns apply. The padding rows after a permutation's output copy the
+//! state forward instead of being unconstrained, which carries it into the
+//! next permutation's first row:
+//!
+//! ```text
+//! next_k = active · (M · s)_k + (1 − active) · cur_k,   active = full + partial
+//! ```
+//!
+//! Boundaries pin the seed in row 0 and the chain output in the last row.
+//! With one row per round the trace grows to `128 · steps` rows, which makes
+//! this the pipeline's large-trace benchmark.
+
+use alloc::{vec, vec::Vec};
+use ark_ff::One;
+use ark_goldilocks::Goldilocks as F;
+use deep_ali::air::{Air, BoundaryConstraint, EvaluationFrame};
+use poseidon::{permute, PoseidonParams, T};
+
+use crate::poseidon::{
+    apply_round, round_periodic_columns, round_transition, ROUNDS, ROWS_PER_PERMUTATION,
+};
+
+pub struct HashChainAir {
+    params: PoseidonParams,
+    seed: [F; T],
+    steps: usize,
+    output: [F; T],
+}
+
+impl HashChainAir {
+    /// Claim `permute^steps(seed)`; `steps` must be a power of two.
+    pub fn new(params: PoseidonParams, seed: [F; T], steps: usize) -> Self {
+        assert!(steps.is_power_of_two(), "steps must be a power of two");
+        let output = Self::evaluate(&params, seed, steps);
+        Self {
+            params,
+            seed,
+            steps,
+            output,
+        }
+    }
+
+    /// The chain computed natively, as the verifier-facing output.
+    pub fn evaluate(params: &PoseidonParams, seed: [F; T], steps: usize) -> [F; T] {
+        let mut state = seed;
+        for _ in 0..steps {
+            permute(&mut state, params);
+        }
+        state
+    }
+
+    pub fn output(&self) -> [F; T] {
+        self.output
+    }
+
+    pub fn build_trace(&self) -> Vec<Vec<F>> {
+        let len = self.trace_length();
+        let mut cols: Vec<Vec<F>> = (0..T).map(|_| Vec::with_capacity(len)).collect();
+
+        let mut state = self.seed;
+        for row in 0..len {
+            for (col, v) in cols.iter_mut().zip(state) {
+                col.push(v);
+            }
+            let r = row % ROWS_PER_PERMUTATION;
+            if r < ROUNDS {
+                state = apply_round(&self.params, r, &state);
+            }
+        }
+        debug_assert_eq!(cols[0][len - 1], self.output[0]);
+        cols
+    }
+}
+
+impl Air for HashChainAir {
+    fn trace_width(&self) -> usize {
+        T
+    }
+
+    fn trace_length(&self) -> usize {
+        self.steps * ROWS_PER_PERMUTATION
+    }
+
+    fn num_transition_constraints(&self) -> usize {
+        T
+    }
+
+    fn transition_degree(&self) -> usize {
+        7
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<F>> {
+        round_periodic_columns(&self.params)
+    }
+
+    fn evaluate_transition(&self, frame: &EvaluationFrame<'_>, out: &mut [F]) {
+        let (active, mixed) = round_transition(&self.params, frame);
+        let idle = F::one() - active;
+        for (k, o) in out.iter_mut().enumerate() {
+            *o = frame.next[k] - active * mixed[k] - idle * frame.current[k];
+        }
+    }
+
+    fn boundary_constraints(&self, _challenges: &[F]) -> Vec<BoundaryConstraint> {
+        let last = self.trace_length() - 1;
+        let seed = self
+            .seed
+            .iter()
+            .enumerate()
+            .map(|(c, v)| BoundaryConstraint::new(c, 0, *v));
+        let output = self
+            .output
+            .iter()
+            .enumerate()
+            .map(|(c, v)| BoundaryConstraint::new(c, last, *v));
+        seed.chain(output).collect()
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        let mut pi = vec![F::from(self.steps as u64)];
+        pi.extend_from_slice(&self.seed);
+        pi.extend_from_slice(&self.output);
+        pi
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deep_ali::air::{
+        first_unsatisfied_row, min_blowup, prove_air, verify_air, AirProofOptions,
+    };
+
+    fn chain(steps: usize) -> HashChainAir {
+        let params = poseidon::params::generate_params_t17_x5(b"AIRS-HASH-CHAIN-TEST");
+        let seed = core::array::from_fn(|i| F::from(3 * i as u64 + 7));
+        HashChainAir::new(params, seed, steps)
+    }
+
+    #[test]
+    fn trace_links_permutations() {
+        let air = chain(4);
+        let trace = air.build_trace();
+        assert_eq!(trace[0].len(), 4 * ROWS_PER_PERMUTATION);
+        assert_eq!(first_unsatisfied_row(&air, &trace, &[]), None);
+
+        // Breaking a padding (copy) row is caught, not just round rows.
+        let mut bad = trace;
+        bad[2][ROWS_PER_PERMUTATION + 100] += F::one();
+        assert_eq!(
+            first_unsatisfied_row(&air, &bad, &[]),
+            Some(ROWS_PER_PERMUTATION + 99)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn prove_verify_roundtrip() {
+        let air = chain(4);
+        let opts = AirProofOptions::new(min_blowup(&air), 16);
+
+        let proof = prove_air(&air, air.build_trace(), &opts);
+        assert!(verify_air(&air, &opts, &proof));
+
+        // Same seed, one permutation short: a different claimed output.
+        let short = chain(2);
+        let mut claim = chain(4);
+        claim.output = short.output();
+        assert!(!verify_air(&claim, &opts, &proof));
+    }
+}
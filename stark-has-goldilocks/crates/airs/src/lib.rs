@@ -0,0 +1,27 @@
+//! Reference AIRs for `deep_ali::air`.
+//!
+//! Each module is a complete, small example of adopting the prover for a
+//! computation: the [`Air`](deep_ali::air::Air) impl, a trace builder, and a
+//! prove/verify round trip in its tests.
+//!
+//! * [`fibonacci`] — two columns, linear transitions, boundary on the result.
+//! * [`range_check`] — values in `[0, 2^bits)` via a LogUp lookup into a
+//!   periodic table column, using an aux segment and cyclic constraints.
+//! * [`poseidon`] — one row per round of the t = 17 Poseidon permutation,
+//!   with round constants and round-type selectors as periodic columns.
+//! * [`hash_chain`] — many chained permutations with the chain output as
+//!   public input; also the large-trace end-to-end benchmark.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod fibonacci;
+pub mod hash_chain;
+pub mod poseidon;
+pub mod range_check;
+
+pub use fibonacci::FibonacciAir;
+pub use hash_chain::HashChainAir;
+pub use poseidon::PoseidonAir;
+pub use range_check::RangeCheckAir;
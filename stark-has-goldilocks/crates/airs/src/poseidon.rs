@@ -0,0 +1,243 @@
+//! One Poseidon permutation (t = 17, x⁵, RF = 8, RP = 64), one row per round.
+//!
+//! Row `r` holds the state entering round `r`; row `RF + RP` holds the
+//! output and the remaining rows up to the next power of two are padding.
+//! Periodic columns carry the per-round selectors and constants:
+//!
+//! * `full`, `partial` — 1 on full/partial rounds, 0 on padding,
+//! * `rc_0 .. rc_16` — that round's additive constants (only `rc_0` is
+//!   non-zero on partial rounds).
+//!
+//! Each state column k has the transition
+//!
+//! ```text
+//! (full + partial) · (next_k − Σ_j M_kj · s_j) = 0,
+//! s_0 = (cur_0 + rc_0)⁵,
+//! s_j = full · (cur_j + rc_j)⁵ + partial · cur_j     (j ≥ 1)
+//! ```
+//!
+//! of degree 7; boundaries pin the input and output rows.
+
+use alloc::vec::Vec;
+use ark_ff::{One, Zero};
+use ark_goldilocks::Goldilocks as F;
+use deep_ali::air::{Air, BoundaryConstraint, EvaluationFrame};
+use poseidon::{permute, sbox5, PoseidonParams, RF, RP, T};
+
+/// Rounds in one permutation.
+pub const ROUNDS: usize = RF + RP;
+
+const FULL: usize = 0;
+const PARTIAL: usize = 1;
+const RC: usize = 2;
+
+/// Rows one permutation occupies: the rounds plus the output row, padded
+/// to a power of two so the round constants form periodic columns.
+pub const ROWS_PER_PERMUTATION: usize = (ROUNDS + 1).next_power_of_two();
+
+pub(crate) fn is_full_round(r: usize) -> bool {
+    r < RF / 2 || (RF / 2 + RP..ROUNDS).contains(&r)
+}
+
+/// Round constants of round `r`, padded to width `T`.
+fn round_constants(params: &PoseidonParams, r: usize) -> [F; T] {
+    if is_full_round(r) {
+        let full_idx = if r < RF / 2 { r } else { r - RP };
+        params.rc_full[full_idx]
+    } else {
+        let mut rc = [F::zero(); T];
+        rc[0] = params.rc_partial[r - RF / 2];
+        rc
+    }
+}
+
+fn mds(params: &PoseidonParams, s: &[F; T]) -> [F; T] {
+    let mut out = [F::zero(); T];
+    for (o, row) in out.iter_mut().zip(params.mds.iter()) {
+        *o = row.iter().zip(s).map(|(m, x)| *m * x).sum();
+    }
+    out
+}
+
+/// State after round `r`; matches one iteration of `poseidon::permute`.
+pub(crate) fn apply_round(params: &PoseidonParams, r: usize, state: &[F; T]) -> [F; T] {
+    let rc = round_constants(params, r);
+    let full = is_full_round(r);
+    let mut s = *state;
+    for (j, x) in s.iter_mut().enumerate() {
+        if j == 0 || full {
+            *x = sbox5(*x + rc[j]);
+        }
+    }
+    mds(params, &s)
+}
+
+/// `full`, `partial` and `rc_0 .. rc_16` over one permutation's rows.
+pub(crate) fn round_periodic_columns(params: &PoseidonParams) -> Vec<Vec<F>> {
+    let flag = |on: bool| if on { F::one() } else { F::zero() };
+
+    let mut cols: Vec<Vec<F>> = (0..RC + T)
+        .map(|_| Vec::with_capacity(ROWS_PER_PERMUTATION))
+        .collect();
+    for r in 0..ROWS_PER_PERMUTATION {
+        let active = r < ROUNDS;
+        cols[FULL].push(flag(active && is_full_round(r)));
+        cols[PARTIAL].push(flag(active && !is_full_round(r)));
+        let rc = if active {
+            round_constants(params, r)
+        } else {
+            [F::zero(); T]
+        };
+        for (j, c) in rc.into_iter().enumerate() {
+            cols[RC + j].push(c);
+        }
+    }
+    cols
+}
+
+/// Round selectors and the round function applied to `frame.current`, as
+/// `(full + partial, M · s)` in the notation of the module docs.
+pub(crate) fn round_transition(params: &PoseidonParams, frame: &EvaluationFrame<'_>) -> (F, [F; T]) {
+    let p = frame.periodic;
+    let (full, partial) = (p[FULL], p[PARTIAL]);
+
+    let mut s = [F::zero(); T];
+    for (j, x) in s.iter_mut().enumerate() {
+        let cur = frame.current[j];
+        let boxed = sbox5(cur + p[RC + j]);
+        *x = if j == 0 {
+            boxed
+        } else {
+            full * boxed + partial * cur
+        };
+    }
+    (full + partial, mds(params, &s))
+}
+
+pub struct PoseidonAir {
+    params: PoseidonParams,
+    input: [F; T],
+    output: [F; T],
+}
+
+impl PoseidonAir {
+    pub fn new(params: PoseidonParams, input: [F; T]) -> Self {
+        let mut output = input;
+        permute(&mut output, &params);
+        Self {
+            params,
+            input,
+            output,
+        }
+    }
+
+    pub fn output(&self) -> [F; T] {
+        self.output
+    }
+
+    /// Column-major trace; padding rows repeat the output.
+    pub fn build_trace(&self) -> Vec<Vec<F>> {
+        let len = self.trace_length();
+        let mut cols: Vec<Vec<F>> = (0..T).map(|_| Vec::with_capacity(len)).collect();
+
+        let mut state = self.input;
+        for row in 0..len {
+            for (col, v) in cols.iter_mut().zip(state) {
+                col.push(v);
+            }
+            if row < ROUNDS {
+                state = apply_round(&self.params, row, &state);
+            }
+        }
+        debug_assert_eq!(cols[0][ROUNDS], self.output[0]);
+        cols
+    }
+}
+
+impl Air for PoseidonAir {
+    fn trace_width(&self) -> usize {
+        T
+    }
+
+    fn trace_length(&self) -> usize {
+        ROWS_PER_PERMUTATION
+    }
+
+    fn num_transition_constraints(&self) -> usize {
+        T
+    }
+
+    fn transition_degree(&self) -> usize {
+        7
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<F>> {
+        round_periodic_columns(&self.params)
+    }
+
+    fn evaluate_transition(&self, frame: &EvaluationFrame<'_>, out: &mut [F]) {
+        let (active, mixed) = round_transition(&self.params, frame);
+        for (k, o) in out.iter_mut().enumerate() {
+            *o = active * (frame.next[k] - mixed[k]);
+        }
+    }
+
+    fn boundary_constraints(&self, _challenges: &[F]) -> Vec<BoundaryConstraint> {
+        let input = self
+            .input
+            .iter()
+            .enumerate()
+            .map(|(c, v)| BoundaryConstraint::new(c, 0, *v));
+        let output = self
+            .output
+            .iter()
+            .enumerate()
+            .map(|(c, v)| BoundaryConstraint::new(c, ROUNDS, *v));
+        input.chain(output).collect()
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        self.input
+            .iter()
+            .chain(self.output.iter())
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deep_ali::air::{
+        first_unsatisfied_row, min_blowup, prove_air, verify_air, AirProofOptions,
+    };
+
+    fn air() -> PoseidonAir {
+        let params = poseidon::params::generate_params_t17_x5(b"AIRS-POSEIDON-TEST");
+        let input = core::array::from_fn(|i| F::from(i as u64 + 1));
+        PoseidonAir::new(params, input)
+    }
+
+    #[test]
+    fn trace_matches_permutation() {
+        let air = air();
+        let trace = air.build_trace();
+        assert_eq!(trace.len(), T);
+        assert_eq!(trace[0].len(), 128);
+        assert_eq!(first_unsatisfied_row(&air, &trace, &[]), None);
+
+        let mut bad = trace;
+        bad[5][40] += F::one();
+        assert_eq!(first_unsatisfied_row(&air, &bad, &[]), Some(39));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn prove_verify_roundtrip() {
+        let air = air();
+        let opts = AirProofOptions::new(min_blowup(&air), 16);
+
+        let proof = prove_air(&air, air.build_trace(), &opts);
+        assert!(verify_air(&air, &opts, &proof));
+    }
+}
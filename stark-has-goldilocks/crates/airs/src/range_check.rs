@@ -0,0 +1,168 @@
+//! Range check via a LogUp lookup.
+//!
+//! Main columns are the checked values `v` and the multiplicities `m`, where
+//! `m_i` counts how often `i` occurs in `v`. The table `0..2^bits` is a
+//! periodic column, so one trace of length `2^bits` checks up to `2^bits`
+//! values. After the main trace is committed the verifier samples `γ`, and
+//! the aux column `acc` accumulates
+//!
+//! ```text
+//! acc_{i+1} − acc_i = 1/(γ − v_i) − m_i/(γ − i)
+//! ```
+//!
+//! cyclically (no exempted rows). Summing around the cycle forces
+//! `Σ 1/(γ − v_i) = Σ m_i/(γ − i)`, which holds for random `γ` only if every
+//! `v_i` is a table entry.
+
+use alloc::{vec, vec::Vec};
+use ark_ff::{batch_inversion, Zero};
+use ark_goldilocks::Goldilocks as F;
+use deep_ali::air::{Air, BoundaryConstraint, EvaluationFrame};
+
+const V: usize = 0;
+const M: usize = 1;
+const ACC: usize = 2;
+
+pub struct RangeCheckAir {
+    bits: u32,
+}
+
+impl RangeCheckAir {
+    pub fn new(bits: u32) -> Self {
+        assert!((1..32).contains(&bits), "range must be 2^1 ..= 2^31");
+        Self { bits }
+    }
+
+    fn table_len(&self) -> usize {
+        1 << self.bits
+    }
+
+    /// Columns `[v, m]`; `values` is zero-padded to the table length.
+    ///
+    /// Out-of-range values are kept in `v` but have no multiplicity slot, so
+    /// the resulting trace does not satisfy the AIR.
+    pub fn build_trace(&self, values: &[u64]) -> Vec<Vec<F>> {
+        let n = self.table_len();
+        assert!(values.len() <= n, "at most 2^bits values per trace");
+
+        let mut v = vec![F::zero(); n];
+        let mut m = vec![0u64; n];
+        for (slot, &x) in v.iter_mut().zip(values) {
+            *slot = F::from(x);
+        }
+        for &x in values {
+            if (x as usize) < n {
+                m[x as usize] += 1;
+            }
+        }
+        m[0] += (n - values.len()) as u64;
+
+        vec![v, m.into_iter().map(F::from).collect()]
+    }
+}
+
+impl Air for RangeCheckAir {
+    fn trace_width(&self) -> usize {
+        2
+    }
+
+    fn trace_length(&self) -> usize {
+        self.table_len()
+    }
+
+    fn num_transition_constraints(&self) -> usize {
+        1
+    }
+
+    fn transition_degree(&self) -> usize {
+        3
+    }
+
+    fn transition_exemptions(&self) -> usize {
+        0
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<F>> {
+        vec![(0..self.table_len() as u64).map(F::from).collect()]
+    }
+
+    fn aux_width(&self) -> usize {
+        1
+    }
+
+    fn num_aux_challenges(&self) -> usize {
+        1
+    }
+
+    fn build_aux_trace(&self, main: &[Vec<F>], challenges: &[F]) -> Vec<Vec<F>> {
+        let gamma = challenges[0];
+        let n = self.table_len();
+
+        let mut den: Vec<F> = main[V].iter().map(|v| gamma - v).collect();
+        den.extend((0..n as u64).map(|i| gamma - F::from(i)));
+        batch_inversion(&mut den);
+
+        let mut acc = Vec::with_capacity(n);
+        let mut cur = F::zero();
+        for i in 0..n {
+            acc.push(cur);
+            cur += den[i] - main[M][i] * den[n + i];
+        }
+        vec![acc]
+    }
+
+    fn evaluate_transition(&self, frame: &EvaluationFrame<'_>, out: &mut [F]) {
+        let gamma = frame.challenges[0];
+        let (cur, next) = (frame.current, frame.next);
+        let gv = gamma - cur[V];
+        let gt = gamma - frame.periodic[0];
+        out[0] = (next[ACC] - cur[ACC]) * gv * gt - gt + cur[M] * gv;
+    }
+
+    fn boundary_constraints(&self, _challenges: &[F]) -> Vec<BoundaryConstraint> {
+        vec![BoundaryConstraint::new(ACC, 0, F::zero())]
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        vec![F::from(self.bits as u64)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deep_ali::air::{first_unsatisfied_row, prove_air, verify_air, AirProofOptions};
+
+    fn values() -> Vec<u64> {
+        (0..100u64).map(|i| (i * 37 + 11) % 256).collect()
+    }
+
+    #[test]
+    fn lookup_closes_only_for_in_range_values() {
+        let air = RangeCheckAir::new(8);
+        let gamma = [F::from(0xdead_beefu64)];
+        assert_eq!(
+            first_unsatisfied_row(&air, &air.build_trace(&values()), &gamma),
+            None
+        );
+
+        let mut bad = values();
+        bad[17] = 256;
+        assert!(first_unsatisfied_row(&air, &air.build_trace(&bad), &gamma).is_some());
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn prove_verify_roundtrip() {
+        let air = RangeCheckAir::new(8);
+        let opts = AirProofOptions::new(4, 16);
+
+        let proof = prove_air(&air, air.build_trace(&values()), &opts);
+        assert!(verify_air(&air, &opts, &proof));
+
+        let mut bad = values();
+        bad[3] = 1000;
+        let proof = prove_air(&air, air.build_trace(&bad), &opts);
+        assert!(!verify_air(&air, &opts, &proof));
+    }
+}
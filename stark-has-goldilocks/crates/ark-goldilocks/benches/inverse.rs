@@ -0,0 +1,39 @@
+// ark-ff 0.4's MontConfig derive implements its traits inside a const.
+#![allow(non_local_definitions)]
+
+use ark_ff::fields::{Fp64, MontBackend, MontConfig};
+use ark_ff::{Field, UniformRand};
+use ark_goldilocks::Goldilocks;
+use ark_std::test_rng;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+/// The same field on arkworks' default inversion, for comparison.
+#[derive(MontConfig)]
+#[modulus = "18446744069414584321"]
+#[generator = "7"]
+pub struct ReferenceConfig;
+type Reference = Fp64<MontBackend<ReferenceConfig, 1>>;
+
+fn bench_inverse(c: &mut Criterion) {
+    let mut rng = test_rng();
+    let a: Vec<Goldilocks> = (0..1024).map(|_| Goldilocks::rand(&mut rng)).collect();
+    let r: Vec<Reference> = (0..1024).map(|_| Reference::rand(&mut rng)).collect();
+
+    c.bench_function("goldilocks_inverse_addition_chain_x1024", |b| {
+        b.iter_batched(
+            || a.clone(),
+            |v| v.iter().map(|x| black_box(x).inverse()).collect::<Vec<_>>(),
+            BatchSize::SmallInput,
+        )
+    });
+    c.bench_function("goldilocks_inverse_arkworks_default_x1024", |b| {
+        b.iter_batched(
+            || r.clone(),
+            |v| v.iter().map(|x| black_box(x).inverse()).collect::<Vec<_>>(),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_inverse);
+criterion_main!(benches);
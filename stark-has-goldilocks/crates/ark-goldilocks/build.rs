@@ -23,6 +23,20 @@ fn main() {
     let root_native = generator.modpow(&t, &modulus);
     let root_mont = (&root_native * &r) % &modulus;
 
+    // ω_k = root^(2^(TWO_ADICITY − k)) generates the order-2^k subgroup;
+    // same squaring ladder arkworks uses in `get_root_of_unity`.
+    let mut subgroup_gens_mont = Vec::with_capacity(two_adicity as usize + 1);
+    let mut omega = root_native.clone();
+    for _ in 0..=two_adicity {
+        subgroup_gens_mont.push((&omega * &r) % &modulus);
+        omega = (&omega * &omega) % &modulus;
+    }
+    subgroup_gens_mont.reverse();
+    assert!(subgroup_gens_mont[0] == (&one * &r) % &modulus);
+
+    // R^{-1} mod p, for leaving the Montgomery domain without a mont-mul
+    let r_inv = r.modpow(&(&modulus - &one - &one), &modulus);
+
     let modulus_minus_one_div_two = &modulus_minus_one >> 1;
     let t_minus_one_div_two = (&t - &one) >> 1;
 
@@ -62,6 +76,7 @@ fn main() {
     writeln!(file, "pub const MODULUS: u64 = {};", write_u64(&modulus)).unwrap();
     writeln!(file, "pub const R_LIMB0: u64 = {};", write_u64(&r)).unwrap();
     writeln!(file, "pub const R_LIMB1: u64 = 0;").unwrap();
+    writeln!(file, "pub const R_INV_LIMB0: u64 = {};", write_u64(&r_inv)).unwrap();
     writeln!(file, "pub const R2_LIMB0: u64 = {};", write_u64(&r2)).unwrap();
     writeln!(file, "pub const R2_LIMB1: u64 = 0;").unwrap();
     writeln!(file, "pub const INV: u64 = {inv_u64};").unwrap();
@@ -72,6 +87,14 @@ fn main() {
     writeln!(file, "pub const TWO_ADIC_ROOT_OF_UNITY_NATIVE: u64 = {};", write_u64(&root_native)).unwrap();
     writeln!(file, "pub const TWO_ADIC_ROOT_OF_UNITY_MONT_LIMB0: u64 = {};", write_u64(&root_mont)).unwrap();
     writeln!(file, "pub const TWO_ADIC_ROOT_OF_UNITY_MONT_LIMB1: u64 = 0;").unwrap();
+    let gens: Vec<String> = subgroup_gens_mont.iter().map(|g| write_u64(g).to_string()).collect();
+    writeln!(
+        file,
+        "pub const TWO_ADIC_SUBGROUP_GENERATORS_MONT: [u64; {}] = [{}];",
+        gens.len(),
+        gens.join(", ")
+    )
+    .unwrap();
     writeln!(file, "pub const MODULUS_MINUS_ONE_DIV_TWO: u64 = {};", write_u64(&modulus_minus_one_div_two)).unwrap();
     writeln!(file, "pub const T_MINUS_ONE_DIV_TWO: u64 = {};", write_u64(&t_minus_one_div_two)).unwrap();
 }
\ No newline at end of file
@@ -6,12 +6,18 @@
 #![allow(unused_imports)]
 
 
-pub use field::Goldilocks;
+pub use field::{two_adic_subgroup_generator, Goldilocks, TWO_ADIC_SUBGROUP_GENERATORS};
+pub use reduce::{dot_product, reduce_u128, MulAcc};
+
+mod reduce;
+#[cfg(feature = "serde1")]
+pub mod serde_canonical;
 
 mod field {
     use ark_ff::{
         biginteger::BigInt,
         fields::models::fp::{Fp, MontBackend, MontConfig},
+        Field, Zero,
     };
 
     mod generated {
@@ -19,6 +25,7 @@ mod field {
     }
 
     pub use generated::{MODULUS, TWO_ADICITY, T};
+    pub(crate) use generated::{R_INV_LIMB0, R_LIMB0};
 
     pub const MODULUS_MINUS_ONE_DIV_TWO: u64 = generated::MODULUS_MINUS_ONE_DIV_TWO;
     pub const T_MINUS_ONE_DIV_TWO: u64 = generated::T_MINUS_ONE_DIV_TWO;
@@ -30,6 +37,30 @@ mod field {
 
     pub type Goldilocks = Fp<MontBackend<GoldilocksConfig, 2>, 2>;
 
+    /// `TWO_ADIC_SUBGROUP_GENERATORS[k]` generates the subgroup of order 2^k,
+    /// k = 0..=TWO_ADICITY, and equals arkworks' `Radix2EvaluationDomain::group_gen`
+    /// for a domain of that size.
+    pub const TWO_ADIC_SUBGROUP_GENERATORS: [Goldilocks; TWO_ADICITY as usize + 1] = {
+        let mont = generated::TWO_ADIC_SUBGROUP_GENERATORS_MONT;
+        let mut out = [Goldilocks::new_unchecked(BigInt([0, 0])); TWO_ADICITY as usize + 1];
+        let mut k = 0;
+        while k < out.len() {
+            out[k] = Goldilocks::new_unchecked(BigInt([mont[k], 0]));
+            k += 1;
+        }
+        out
+    };
+
+    /// Generator of the order-`2^log_size` subgroup.
+    #[inline]
+    pub fn two_adic_subgroup_generator(log_size: u32) -> Goldilocks {
+        assert!(
+            log_size <= TWO_ADICITY,
+            "Goldilocks has no subgroup of order 2^{log_size}"
+        );
+        TWO_ADIC_SUBGROUP_GENERATORS[log_size as usize]
+    }
+
     impl MontConfig<2> for GoldilocksConfig {
         const MODULUS: BigInt<2> = BigInt([MODULUS, 0]);
         const R: BigInt<2> = BigInt([generated::R_LIMB0, generated::R_LIMB1]);
@@ -40,13 +71,53 @@ mod field {
             Goldilocks::new_unchecked(BigInt([generated::GENERATOR_MONT_LIMB0, generated::GENERATOR_MONT_LIMB1]));
         const TWO_ADIC_ROOT_OF_UNITY: Goldilocks =
             Goldilocks::new_unchecked(BigInt([generated::TWO_ADIC_ROOT_OF_UNITY_MONT_LIMB0, generated::TWO_ADIC_ROOT_OF_UNITY_MONT_LIMB1]));
+
+        /// Fermat inversion `a^(p-2)` via a fixed addition chain.
+        ///
+        /// `p - 2 = 2^64 - 2^32 - 1` is 31 ones, a zero, then 32 ones, so the
+        /// chain builds `a^(2^k - 1)` for k = 1, 2, 3, 6, 12, 24, 30, 31, 32
+        /// and stitches the two runs together: 64 squarings + 9 multiplies,
+        /// constant time, and cheaper than arkworks' binary extended Euclid
+        /// (see `benches/inverse.rs`).
+        #[inline]
+        fn inverse(a: &Goldilocks) -> Option<Goldilocks> {
+            if a.is_zero() {
+                return None;
+            }
+            Some(inverse_addition_chain(a))
+        }
+    }
+
+    #[inline(always)]
+    fn exp_power_of_2(mut x: Goldilocks, k: usize) -> Goldilocks {
+        for _ in 0..k {
+            x.square_in_place();
+        }
+        x
+    }
+
+    /// `a^(p-2)`; callers must reject zero.
+    #[inline]
+    fn inverse_addition_chain(a: &Goldilocks) -> Goldilocks {
+        let t1 = *a;
+        let t2 = exp_power_of_2(t1, 1) * t1;
+        let t3 = exp_power_of_2(t2, 1) * t1;
+        let t6 = exp_power_of_2(t3, 3) * t3;
+        let t12 = exp_power_of_2(t6, 6) * t6;
+        let t24 = exp_power_of_2(t12, 12) * t12;
+        let t30 = exp_power_of_2(t24, 6) * t6;
+        let t31 = exp_power_of_2(t30, 1) * t1;
+        let t32 = exp_power_of_2(t31, 1) * t1;
+        // (2^31 - 1) * 2^33 + (2^32 - 1) = p - 2
+        exp_power_of_2(t31, 33) * t32
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::field::{
-        Goldilocks, GoldilocksConfig, MODULUS, T, TWO_ADICITY, TWO_ADIC_ROOT_OF_UNITY_NATIVE_VALUE,
+        two_adic_subgroup_generator, Goldilocks, GoldilocksConfig, MODULUS, T, TWO_ADICITY,
+        TWO_ADIC_ROOT_OF_UNITY_NATIVE_VALUE, TWO_ADIC_SUBGROUP_GENERATORS,
     };
     use ark_ff::{Field, LegendreSymbol, MontConfig, PrimeField, UniformRand};
     use ark_std::{test_rng, vec::Vec};
@@ -79,6 +150,24 @@ mod tests {
         assert_eq!(a * inv, Goldilocks::ONE);
     }
 
+    #[test]
+    fn addition_chain_inverse_matches_fermat() {
+        let mut rng = test_rng();
+        let edge = [1u64, 2, MODULUS - 1, MODULUS - 2, 1 << 32, (1 << 32) - 1];
+        let samples: Vec<Goldilocks> = edge
+            .iter()
+            .map(|&v| Goldilocks::from(v))
+            .chain((0..256).map(|_| Goldilocks::rand(&mut rng)))
+            .collect();
+
+        for a in samples {
+            let inv = a.inverse().unwrap();
+            assert_eq!(a * inv, Goldilocks::ONE);
+            assert_eq!(inv, a.pow([MODULUS - 2]));
+        }
+        assert!(Goldilocks::ZERO.inverse().is_none());
+    }
+
     #[test]
     fn legendre_symbols() {
         let residue = Goldilocks::from(25u64);
@@ -133,6 +222,22 @@ mod tests {
         assert_eq!(canonical, TWO_ADIC_ROOT_OF_UNITY_NATIVE_VALUE);
     }
 
+    #[test]
+    fn subgroup_generator_table_matches_arkworks() {
+        use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
+
+        assert_eq!(TWO_ADIC_SUBGROUP_GENERATORS[0], Goldilocks::ONE);
+        for k in 1..=TWO_ADICITY {
+            let g = two_adic_subgroup_generator(k);
+            assert_eq!(g.pow([1u64 << k]), Goldilocks::ONE);
+            assert_ne!(g.pow([1u64 << (k - 1)]), Goldilocks::ONE);
+            if k <= 20 {
+                let dom = Radix2EvaluationDomain::<Goldilocks>::new(1 << k).unwrap();
+                assert_eq!(g, dom.group_gen, "k = {k}");
+            }
+        }
+    }
+
     #[test]
     fn decomposition_constants() {
         assert_eq!((MODULUS - 1) >> TWO_ADICITY, T);
@@ -0,0 +1,163 @@
+//! Native u64/u128 reduction for Goldilocks hot loops.
+//!
+//! With p = 2^64 − 2^32 + 1 we have 2^64 ≡ 2^32 − 1 and 2^96 ≡ −1 (mod p),
+//! so a 128-bit value folds back below 2^64 with one subtract, one 32×32
+//! multiply and a couple of conditional corrections. Dot products can
+//! therefore accumulate raw Montgomery-limb products in u128 and leave the
+//! Montgomery domain once at the end, instead of paying a full arkworks
+//! mont-mul (and reduction) per term.
+
+use ark_ff::biginteger::BigInt;
+
+use crate::field::{Goldilocks, MODULUS, R_INV_LIMB0, R_LIMB0};
+
+/// 2^64 mod p = 2^32 − 1.
+const EPSILON: u64 = (1u64 << 32) - 1;
+
+/// `x mod p` as a canonical u64.
+#[inline(always)]
+fn reduce_u128_canonical(x: u128) -> u64 {
+    let lo = x as u64;
+    let hi = (x >> 64) as u64;
+    let hi_hi = hi >> 32;
+    let hi_lo = hi & EPSILON;
+
+    // hi_hi·2^96 ≡ −hi_hi (mod p).
+    let (mut t0, borrow) = lo.overflowing_sub(hi_hi);
+    if borrow {
+        // Wrapped by +2^64 ≡ +ε; take it back. Cannot underflow: t0 > 2^64 − 2^32.
+        t0 = t0.wrapping_sub(EPSILON);
+    }
+    // hi_lo·2^64 ≡ hi_lo·ε, which fits in u64.
+    let t1 = hi_lo * EPSILON;
+    let (res, carry) = t0.overflowing_add(t1);
+    let res = res.wrapping_add(EPSILON * carry as u64);
+
+    if res >= MODULUS {
+        res - MODULUS
+    } else {
+        res
+    }
+}
+
+/// Montgomery limb of `a` (i.e. `a·R mod p`); always < p with a zero high limb.
+#[inline(always)]
+fn mont_limb(a: &Goldilocks) -> u64 {
+    a.0 .0[0]
+}
+
+#[inline(always)]
+fn from_mont_limb(v: u64) -> Goldilocks {
+    Goldilocks::new_unchecked(BigInt([v, 0]))
+}
+
+/// The field element congruent to `x` (taken as an integer) modulo p.
+#[inline]
+pub fn reduce_u128(x: u128) -> Goldilocks {
+    let c = reduce_u128_canonical(x);
+    from_mont_limb(reduce_u128_canonical(c as u128 * R_LIMB0 as u128))
+}
+
+/// Fused multiply-accumulate over Goldilocks elements.
+///
+/// Each `mul_add` is a single 64×64→128 multiply of the Montgomery limbs
+/// plus a u128 add; overflows past 2^128 are counted and folded in by
+/// `finish`, which performs the only modular reductions.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MulAcc {
+    lo: u128,
+    carries: u64,
+}
+
+impl MulAcc {
+    #[inline]
+    pub const fn new() -> Self {
+        Self { lo: 0, carries: 0 }
+    }
+
+    /// `acc += a * b`.
+    #[inline(always)]
+    pub fn mul_add(&mut self, a: Goldilocks, b: Goldilocks) {
+        let prod = mont_limb(&a) as u128 * mont_limb(&b) as u128;
+        let (lo, carry) = self.lo.overflowing_add(prod);
+        self.lo = lo;
+        self.carries += carry as u64;
+    }
+
+    /// Reduce the accumulated sum to a field element.
+    #[inline]
+    pub fn finish(self) -> Goldilocks {
+        // Σ (aR)(bR) = (Σ ab)·R² over the integers; 2^128 ≡ R (mod p).
+        let lo = reduce_u128_canonical(self.lo);
+        let wrapped = reduce_u128_canonical(self.carries as u128 * R_LIMB0 as u128);
+        let s = reduce_u128_canonical(lo as u128 + wrapped as u128);
+        // One R^{-1} brings (Σ ab)·R² back to Montgomery form (Σ ab)·R.
+        from_mont_limb(reduce_u128_canonical(s as u128 * R_INV_LIMB0 as u128))
+    }
+}
+
+/// `Σ a[i]·b[i]` with a single final reduction.
+#[inline]
+pub fn dot_product(a: &[Goldilocks], b: &[Goldilocks]) -> Goldilocks {
+    debug_assert_eq!(a.len(), b.len());
+    let mut acc = MulAcc::new();
+    for (x, y) in a.iter().zip(b.iter()) {
+        acc.mul_add(*x, *y);
+    }
+    acc.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::{PrimeField, UniformRand, Zero};
+    use ark_std::{test_rng, vec::Vec};
+
+    #[test]
+    fn reduce_u128_matches_bigint() {
+        let p = MODULUS as u128;
+        let cases = [
+            0u128,
+            1,
+            p - 1,
+            p,
+            p + 1,
+            u64::MAX as u128,
+            1u128 << 64,
+            (1u128 << 96) - 1,
+            1u128 << 96,
+            u128::MAX,
+            u128::MAX - p,
+        ];
+        for x in cases {
+            let got = reduce_u128(x).into_bigint().0[0];
+            assert_eq!(got as u128, x % p, "x = {x:#x}");
+        }
+    }
+
+    #[test]
+    fn dot_product_matches_naive() {
+        let mut rng = test_rng();
+        for len in [0usize, 1, 2, 17, 300] {
+            let a: Vec<Goldilocks> = (0..len).map(|_| Goldilocks::rand(&mut rng)).collect();
+            let b: Vec<Goldilocks> = (0..len).map(|_| Goldilocks::rand(&mut rng)).collect();
+            let naive = a
+                .iter()
+                .zip(b.iter())
+                .fold(Goldilocks::zero(), |s, (x, y)| s + *x * *y);
+            assert_eq!(dot_product(&a, &b), naive, "len = {len}");
+        }
+    }
+
+    #[test]
+    fn mul_acc_survives_u128_overflow() {
+        // Montgomery limb p−1 squares to ≈ 2^128, so nearly every term wraps.
+        let m = from_mont_limb(MODULUS - 1);
+        let mut acc = MulAcc::new();
+        for _ in 0..1000 {
+            acc.mul_add(m, m);
+        }
+        assert!(acc.carries > 0);
+        assert_eq!(acc.finish(), m * m * Goldilocks::from(1000u64));
+    }
+}
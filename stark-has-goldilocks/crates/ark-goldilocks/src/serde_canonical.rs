@@ -0,0 +1,236 @@
+//! Serde support for Goldilocks values through their canonical encoding:
+//! the 8 little-endian bytes of the reduced representative. Decoding
+//! rejects any other length and anything not below the modulus, so every
+//! element has exactly one encoding.
+//!
+//! `Goldilocks` is an arkworks type and cannot implement the serde traits
+//! itself; fields opt in with
+//! `#[serde(with = "ark_goldilocks::serde_canonical")]`, which covers
+//! `Goldilocks` and `Option`s, `Vec`s, arrays and pairs of it.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::{fmt, marker::PhantomData};
+
+use ark_ff::PrimeField;
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeTuple, Serializer};
+
+use crate::Goldilocks;
+
+/// Types serialized through the canonical element encoding.
+pub trait Canonical: Sized {
+    fn serialize_canonical<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+    fn deserialize_canonical<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+pub fn serialize<T: Canonical, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    value.serialize_canonical(serializer)
+}
+
+pub fn deserialize<'de, T: Canonical, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+    T::deserialize_canonical(deserializer)
+}
+
+struct Ser<'a, T>(&'a T);
+
+impl<T: Canonical> Serialize for Ser<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_canonical(serializer)
+    }
+}
+
+struct De<T>(T);
+
+impl<'de, T: Canonical> Deserialize<'de> for De<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize_canonical(deserializer).map(De)
+    }
+}
+
+fn from_le_bytes<E: de::Error>(bytes: [u8; 8]) -> Result<Goldilocks, E> {
+    let v = u64::from_le_bytes(bytes);
+    if v >= Goldilocks::MODULUS.0[0] {
+        return Err(E::custom("non-canonical Goldilocks element"));
+    }
+    Ok(Goldilocks::from(v))
+}
+
+struct ElementVisitor;
+
+impl<'de> Visitor<'de> for ElementVisitor {
+    type Value = Goldilocks;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("8 little-endian bytes of a canonical Goldilocks element")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Goldilocks, E> {
+        let bytes = v.try_into().map_err(|_| E::invalid_length(v.len(), &self))?;
+        from_le_bytes(bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Goldilocks, A::Error> {
+        let mut bytes = [0u8; 8];
+        for (k, b) in bytes.iter_mut().enumerate() {
+            *b = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(k, &self))?;
+        }
+        if seq.next_element::<u8>()?.is_some() {
+            return Err(de::Error::invalid_length(9, &self));
+        }
+        from_le_bytes(bytes)
+    }
+}
+
+impl Canonical for Goldilocks {
+    fn serialize_canonical<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.into_bigint().0[0].to_le_bytes())
+    }
+
+    fn deserialize_canonical<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_bytes(ElementVisitor)
+    }
+}
+
+impl<T: Canonical> Canonical for Option<T> {
+    fn serialize_canonical<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Some(v) => serializer.serialize_some(&Ser(v)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    fn deserialize_canonical<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Option::<De<T>>::deserialize(deserializer)?.map(|De(v)| v))
+    }
+}
+
+struct SeqVisitor<T> {
+    len: Option<usize>,
+    _marker: PhantomData<T>,
+}
+
+impl<'de, T: Canonical> Visitor<'de> for SeqVisitor<T> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.len {
+            Some(n) => write!(f, "a sequence of {n} Goldilocks values"),
+            None => f.write_str("a sequence of Goldilocks values"),
+        }
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<T>, A::Error> {
+        let mut out = Vec::with_capacity(self.len.or(seq.size_hint()).unwrap_or(0).min(1 << 16));
+        while let Some(De(v)) = seq.next_element::<De<T>>()? {
+            out.push(v);
+        }
+        match self.len {
+            Some(n) if out.len() != n => Err(de::Error::invalid_length(out.len(), &self)),
+            _ => Ok(out),
+        }
+    }
+}
+
+impl<T: Canonical> Canonical for Vec<T> {
+    fn serialize_canonical<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(Ser))
+    }
+
+    fn deserialize_canonical<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(SeqVisitor {
+            len: None,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T: Canonical, const N: usize> Canonical for [T; N] {
+    fn serialize_canonical<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tup = serializer.serialize_tuple(N)?;
+        for v in self {
+            tup.serialize_element(&Ser(v))?;
+        }
+        tup.end()
+    }
+
+    fn deserialize_canonical<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let v = deserializer.deserialize_tuple(
+            N,
+            SeqVisitor {
+                len: Some(N),
+                _marker: PhantomData,
+            },
+        )?;
+        v.try_into()
+            .map_err(|_| de::Error::custom("array length mismatch"))
+    }
+}
+
+impl<A: Canonical, B: Canonical> Canonical for (A, B) {
+    fn serialize_canonical<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tup = serializer.serialize_tuple(2)?;
+        tup.serialize_element(&Ser(&self.0))?;
+        tup.serialize_element(&Ser(&self.1))?;
+        tup.end()
+    }
+
+    fn deserialize_canonical<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (De(a), De(b)) = <(De<A>, De<B>)>::deserialize(deserializer)?;
+        Ok((a, b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Sample {
+        #[serde(with = "crate::serde_canonical")]
+        x: Goldilocks,
+        #[serde(with = "crate::serde_canonical")]
+        xs: Vec<Vec<Goldilocks>>,
+        #[serde(with = "crate::serde_canonical")]
+        arr: [Goldilocks; 3],
+        #[serde(with = "crate::serde_canonical")]
+        opt: Option<Goldilocks>,
+        #[serde(with = "crate::serde_canonical")]
+        pair: (Goldilocks, Goldilocks),
+    }
+
+    fn sample() -> Sample {
+        let g = |v: u64| Goldilocks::from(v);
+        Sample {
+            x: g(Goldilocks::MODULUS.0[0] - 1),
+            xs: vec![vec![g(1), g(2)], vec![]],
+            arr: [g(3), g(4), g(5)],
+            opt: Some(g(6)),
+            pair: (g(7), g(8)),
+        }
+    }
+
+    #[test]
+    fn roundtrips_through_json_and_bincode() {
+        let s = sample();
+        let json = serde_json::to_string(&s).unwrap();
+        assert_eq!(serde_json::from_str::<Sample>(&json).unwrap(), s);
+        let bin = bincode::serialize(&s).unwrap();
+        assert_eq!(bincode::deserialize::<Sample>(&bin).unwrap(), s);
+    }
+
+    #[test]
+    fn rejects_non_canonical_encodings() {
+        #[derive(serde::Deserialize, Debug)]
+        struct One(#[serde(with = "crate::serde_canonical")] Goldilocks);
+
+        let p = Goldilocks::MODULUS.0[0].to_le_bytes();
+        assert!(serde_json::from_str::<One>(&format!("{p:?}")).is_err());
+        assert!(serde_json::from_str::<One>("[1,0,0,0,0,0,0]").is_err());
+        assert!(serde_json::from_str::<One>("[1,0,0,0,0,0,0,0,0]").is_err());
+        assert!(bincode::deserialize::<One>(&bincode::serialize(&p.to_vec()).unwrap()).is_err());
+        let one = serde_json::from_str::<One>("[1,0,0,0,0,0,0,0]").unwrap();
+        assert_eq!(one.0, Goldilocks::from(1u64));
+    }
+}
@@ -15,7 +15,7 @@ use channel::{build_vk_plain, prove_plain, verify_plain};
 
 use deep_ali::fri::{
     deep_fri_prove, deep_fri_proof_size_bytes, deep_fri_verify, AliA, AliE, AliS, AliT,
-    DeepAliRealBuilder, DeepFriParams, DeepFriProof,
+    DeepAliRealBuilder, DeepFriParams, DeepFriProof, FoldCheck, VerifierKey, FoldingMode, IndexChaining, QuerySampling, TranscriptHash,
 };
 
 // ---------------------
@@ -232,6 +232,12 @@ fn bench_e2e_mf_fri(c: &mut Criterion) {
                     schedule: schedule.clone(),
                     r,
                     seed_z,
+                    folding_mode: FoldingMode::default(),
+                    fold_check: FoldCheck::default(),
+                    grinding_bits: 0,
+                    query_sampling: QuerySampling::default(),
+                    index_chaining: IndexChaining::default(),
+                    transcript: TranscriptHash::default(),
                 };
                 let builder = DeepAliRealBuilder::default();
 
@@ -249,8 +255,9 @@ fn bench_e2e_mf_fri(c: &mut Criterion) {
                 eprintln!("mf-fri precompute proof…");
                 let pre_proof: DeepFriProof =
                     deep_fri_prove(&builder, &a, &s, &e, &t, n0, &params);
+                let key = VerifierKey::for_fri(&params, n0).expect("valid FRI parameters");
                 assert!(
-                    deep_fri_verify(&params, &pre_proof),
+                    deep_fri_verify(&key, &pre_proof),
                     "precomputed proof failed verification"
                 );
                 let proof_size_bytes = deep_fri_proof_size_bytes(&pre_proof);
@@ -277,7 +284,7 @@ fn bench_e2e_mf_fri(c: &mut Criterion) {
                 let verify_id = BenchmarkId::new(format!("verify-{}-r{}", label, r), k);
                 g.bench_with_input(verify_id, &k, |b, &_k| {
                     b.iter(|| {
-                        let ok = deep_fri_verify(&params, &pre_proof);
+                        let ok = deep_fri_verify(&key, &pre_proof);
                         assert!(ok);
                     })
                 });
@@ -288,7 +295,7 @@ fn bench_e2e_mf_fri(c: &mut Criterion) {
                 let prove_s = t0.elapsed().as_secs_f64();
 
                 let t1 = std::time::Instant::now();
-                let ok = deep_fri_verify(&params, &pre_proof);
+                let ok = deep_fri_verify(&key, &pre_proof);
                 assert!(ok);
                 let verify_ms = t1.elapsed().as_secs_f64() * 1e3;
 
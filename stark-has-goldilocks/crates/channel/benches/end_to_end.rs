@@ -10,18 +10,25 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::time::{Duration, Instant};
+use transcript::{default_params as transcript_params, Transcript};
 
 // ✅ DEEP‑ALI + MF‑FRI APIs
 use deep_ali::{
-    deep_ali_merge_evals,
-    deep_tower::Fp3,
+    sample_z_beta,
+    DomainH,
     fri::{
-        deep_fri_prove,
+        deep_fri_prove_over,
         deep_fri_proof_size_bytes,
         deep_fri_verify,
-        FriDomain,
         DeepFriParams,
+        FoldingMode,
+        FoldCheck,
+        IndexChaining,
+        QuerySampling,
+        TranscriptHash,
+        VerifierKey,
     },
+    params::normalize_schedule,
 };
 
 // ---------------------
@@ -106,20 +113,6 @@ fn ks_for_schedule(schedule: &[usize], k_lo: usize, k_hi: usize) -> Vec<usize> {
         .collect()
 }
 
-// ✅ Normalize schedule so final layer size = 1
-fn normalize_fri_schedule(n0: usize, mut schedule: Vec<usize>) -> Vec<usize> {
-    let mut n = n0;
-    for &m in &schedule {
-        assert!(n % m == 0, "schedule does not divide domain");
-        n /= m;
-    }
-    if n > 1 {
-        assert!(n.is_power_of_two(), "final layer must be power of two");
-        schedule.push(n);
-    }
-    schedule
-}
-
 // ---------------------
 // MF‑FRI benchmark (WITH DEEP‑ALI)
 // ---------------------
@@ -172,7 +165,8 @@ fn bench_e2e_mf_fri(c: &mut Criterion) {
             let n0 = 1usize << k;
             g.throughput(Throughput::Elements(n0 as u64));
 
-            let normalized_schedule = normalize_fri_schedule(n0, schedule.to_vec());
+            // Normalize schedule so final layer size = 1
+            let normalized_schedule = normalize_schedule(n0, schedule).expect("schedule does not fit the domain");
 
             eprintln!(
                 "[START] label={} schedule={} k={} (n={})",
@@ -194,25 +188,23 @@ fn bench_e2e_mf_fri(c: &mut Criterion) {
             let e_eval: Vec<F> = (0..n0).map(|_| F::rand(&mut rng)).collect();
             let t_eval: Vec<F> = (0..n0).map(|_| F::rand(&mut rng)).collect();
 
-            let domain0 = FriDomain::new_radix2(n0);
+            // H is built once for the merge and FRI's first layer.
+            let dom = DomainH::new_radix2(n0);
 
-            // ✅ Sample DEEP‑ALI challenge z ∈ Fp³
+            // ✅ Sample DEEP‑ALI challenge z ∈ Fp³ from the transcript
             // NOTE: FRI derives its *own* z_fp3 internally via Fiat–Shamir.
-            // These two z's are intentionally independent.
-            let z_fp3 = Fp3 {
-                a0: F::rand(&mut rng),
-                a1: F::rand(&mut rng),
-                a2: F::rand(&mut rng),
-            };
+            let mut tr = Transcript::new(b"DEEP-ALI/end-to-end", transcript_params());
+            tr.absorb_field(F::from(seed_z));
+            tr.absorb_field(F::from(n0 as u64));
+            let (z_fp3, _beta) = sample_z_beta(&mut tr, n0);
 
             // ✅ DEEP‑ALI merge
             let (f0_ali, _z_used, _c_star) =
-                deep_ali_merge_evals(
+                dom.merge_deep_ali(
                     &a_eval,
                     &s_eval,
                     &e_eval,
                     &t_eval,
-                    domain0.omega,
                     z_fp3,
                 );
 
@@ -220,16 +212,23 @@ fn bench_e2e_mf_fri(c: &mut Criterion) {
                 schedule: normalized_schedule.clone(),
                 r,
                 seed_z,
+                folding_mode: FoldingMode::default(),
+                fold_check: FoldCheck::default(),
+                grinding_bits: 0,
+                query_sampling: QuerySampling::default(),
+                index_chaining: IndexChaining::default(),
+                transcript: TranscriptHash::default(),
             };
 
             // ---------------- Prove ----------------
             let t0 = Instant::now();
-            let proof = deep_fri_prove(f0_ali.clone(), domain0, &params);
+            let proof = deep_fri_prove_over(f0_ali.clone(), &dom, &params, None);
             let prove_s = t0.elapsed().as_secs_f64();
 
             // ---------------- Verify ----------------
+            let key = VerifierKey::for_fri(&params, n0).expect("valid FRI parameters");
             let t1 = Instant::now();
-            assert!(deep_fri_verify(&params, &proof));
+            assert!(deep_fri_verify(&key, &proof));
             let verify_ms = t1.elapsed().as_secs_f64() * 1e3;
 
             let proof_bytes = deep_fri_proof_size_bytes(&proof);
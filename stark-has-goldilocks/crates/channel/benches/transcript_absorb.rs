@@ -0,0 +1,45 @@
+//! Absorbing a Merkle layer or trace column: `absorb_field` per element
+//! against one `absorb_field_slice`, on every backend.
+
+use ark_ff::UniformRand;
+use ark_goldilocks::Goldilocks as F;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::{rngs::StdRng, SeedableRng};
+use transcript::{FsConfig, FsHash, Transcript};
+
+const SIZES: [usize; 3] = [1 << 10, 1 << 14, 1 << 18];
+
+fn bench_absorb(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(42);
+
+    for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+        let cfg = FsConfig::new(hash);
+        let mut group = c.benchmark_group(format!("absorb/{hash:?}"));
+        group.sample_size(10);
+        for n in SIZES {
+            let column: Vec<F> = (0..n).map(|_| F::rand(&mut rng)).collect();
+            group.throughput(Throughput::Elements(n as u64));
+
+            group.bench_with_input(BenchmarkId::new("per_element", n), &column, |b, column| {
+                b.iter(|| {
+                    let mut tr = Transcript::from_config(&cfg, b"BENCH/absorb");
+                    for &x in column {
+                        tr.absorb_field(x);
+                    }
+                    tr.challenge(b"c")
+                })
+            });
+            group.bench_with_input(BenchmarkId::new("slice", n), &column, |b, column| {
+                b.iter(|| {
+                    let mut tr = Transcript::from_config(&cfg, b"BENCH/absorb");
+                    tr.absorb_field_slice(column);
+                    tr.challenge(b"c")
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_absorb);
+criterion_main!(benches);
@@ -1,9 +1,19 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::fmt;
+
 use ark_goldilocks::Goldilocks as F;
+use deep_ali::deep_tower::Fp3;
 use transcript::Transcript;
 use merkle::{
     MerkleChannelCfg,
+    MerkleError,
     MerkleTreeChannel,
     MerkleOpening,
+    VectorCommitment,
 };
 
 /// =========================
@@ -12,15 +22,26 @@ use merkle::{
 
 pub struct ProverChannel {
     tr: Transcript,
+    proof: ProofStream,
 }
 
 pub struct VerifierChannel {
     tr: Transcript,
+    proof: ProofStream,
+    next: usize,
 }
 
 impl ProverChannel {
     pub fn new(tr: Transcript) -> Self {
-        Self { tr }
+        Self {
+            tr,
+            proof: ProofStream::default(),
+        }
+    }
+
+    /// Everything sent through [`ProofChannel`] so far.
+    pub fn into_proof(self) -> ProofStream {
+        self.proof
     }
 
     pub fn transcript_mut(&mut self) -> &mut Transcript {
@@ -35,11 +56,35 @@ impl ProverChannel {
     pub fn challenge(&mut self, label: &[u8]) -> F {
         self.tr.challenge(label)
     }
+
+    pub fn absorb_ext3(&mut self, label: &[u8], x: &Fp3) {
+        self.tr.absorb_ext3(label, (*x).into());
+    }
+
+    pub fn challenge_ext3(&mut self, label: &[u8]) -> Fp3 {
+        self.tr.challenge_ext3(label)
+    }
 }
 
 impl VerifierChannel {
     pub fn new(tr: Transcript) -> Self {
-        Self { tr }
+        Self::with_proof(tr, ProofStream::default())
+    }
+
+    /// A channel reading `proof` through [`ProofChannel`].
+    pub fn with_proof(tr: Transcript, proof: ProofStream) -> Self {
+        Self { tr, proof, next: 0 }
+    }
+
+    /// Whether every item of the proof has been received.
+    pub fn is_exhausted(&self) -> bool {
+        self.next == self.proof.items.len()
+    }
+
+    fn next_item(&mut self) -> Result<&ProofItem, ChannelError> {
+        let item = self.proof.items.get(self.next).ok_or(ChannelError::EndOfProof)?;
+        self.next += 1;
+        Ok(item)
     }
 
     pub fn transcript_mut(&mut self) -> &mut Transcript {
@@ -54,6 +99,203 @@ impl VerifierChannel {
     pub fn challenge(&mut self, label: &[u8]) -> F {
         self.tr.challenge(label)
     }
+
+    pub fn absorb_ext3(&mut self, label: &[u8], x: &Fp3) {
+        self.tr.absorb_ext3(label, (*x).into());
+    }
+
+    pub fn challenge_ext3(&mut self, label: &[u8]) -> Fp3 {
+        self.tr.challenge_ext3(label)
+    }
+}
+
+// =========================
+// Duplex proof channel
+// =========================
+
+/// One prover message.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProofItem {
+    Field(#[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))] F),
+    Root(#[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))] F),
+    Opening(MerkleOpening),
+}
+
+/// Prover messages in the order they were sent.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProofStream {
+    pub items: Vec<ProofItem>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelError {
+    /// The proof has no more items.
+    EndOfProof,
+    /// The next item is not the kind being received.
+    UnexpectedItem { expected: &'static str },
+    /// A prover channel was asked to receive, or a verifier channel to send.
+    WrongDirection,
+}
+
+impl fmt::Display for ChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EndOfProof => write!(f, "proof ended early"),
+            Self::UnexpectedItem { expected } => write!(f, "expected a {expected} in the proof"),
+            Self::WrongDirection => write!(f, "channel used in the wrong direction"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ChannelError {}
+
+/// Both ends of a proof. Every message is absorbed under its label on
+/// both sides, so protocol logic written against this trait derives the
+/// same challenges whether it proves or verifies: the prover `send`s what
+/// the verifier `recv`s, and the provided [`field`](Self::field),
+/// [`root`](Self::root) and [`opening`](Self::opening) pick the direction.
+pub trait ProofChannel {
+    fn is_prover(&self) -> bool;
+
+    fn send_field(&mut self, label: &[u8], f: &F) -> Result<(), ChannelError>;
+    fn recv_field(&mut self, label: &[u8]) -> Result<F, ChannelError>;
+
+    fn send_root(&mut self, label: &[u8], root: &F) -> Result<(), ChannelError>;
+    fn recv_root(&mut self, label: &[u8]) -> Result<F, ChannelError>;
+
+    /// Openings are bound through their index and leaf; the path is fixed
+    /// by the root already absorbed.
+    fn send_opening(&mut self, label: &[u8], opening: &MerkleOpening) -> Result<(), ChannelError>;
+    fn recv_opening(&mut self, label: &[u8]) -> Result<MerkleOpening, ChannelError>;
+
+    fn challenge(&mut self, label: &[u8]) -> F;
+
+    /// The prover sends `value()`; the verifier receives it.
+    fn field(&mut self, label: &[u8], value: impl FnOnce() -> F) -> Result<F, ChannelError> {
+        if self.is_prover() {
+            let f = value();
+            self.send_field(label, &f).map(|()| f)
+        } else {
+            self.recv_field(label)
+        }
+    }
+
+    fn root(&mut self, label: &[u8], value: impl FnOnce() -> F) -> Result<F, ChannelError> {
+        if self.is_prover() {
+            let root = value();
+            self.send_root(label, &root).map(|()| root)
+        } else {
+            self.recv_root(label)
+        }
+    }
+
+    fn opening(
+        &mut self,
+        label: &[u8],
+        value: impl FnOnce() -> MerkleOpening,
+    ) -> Result<MerkleOpening, ChannelError> {
+        if self.is_prover() {
+            let opening = value();
+            self.send_opening(label, &opening).map(|()| opening)
+        } else {
+            self.recv_opening(label)
+        }
+    }
+}
+
+fn absorb_opening(tr: &mut Transcript, label: &[u8], opening: &MerkleOpening) {
+    tr.absorb_bytes(label);
+    tr.absorb_field(F::from(opening.index));
+    tr.absorb_field(opening.leaf);
+}
+
+impl ProofChannel for ProverChannel {
+    fn is_prover(&self) -> bool {
+        true
+    }
+
+    fn send_field(&mut self, label: &[u8], f: &F) -> Result<(), ChannelError> {
+        self.absorb_field(label, f);
+        self.proof.items.push(ProofItem::Field(*f));
+        Ok(())
+    }
+
+    fn recv_field(&mut self, _label: &[u8]) -> Result<F, ChannelError> {
+        Err(ChannelError::WrongDirection)
+    }
+
+    fn send_root(&mut self, label: &[u8], root: &F) -> Result<(), ChannelError> {
+        self.absorb_field(label, root);
+        self.proof.items.push(ProofItem::Root(*root));
+        Ok(())
+    }
+
+    fn recv_root(&mut self, _label: &[u8]) -> Result<F, ChannelError> {
+        Err(ChannelError::WrongDirection)
+    }
+
+    fn send_opening(&mut self, label: &[u8], opening: &MerkleOpening) -> Result<(), ChannelError> {
+        absorb_opening(&mut self.tr, label, opening);
+        self.proof.items.push(ProofItem::Opening(opening.clone()));
+        Ok(())
+    }
+
+    fn recv_opening(&mut self, _label: &[u8]) -> Result<MerkleOpening, ChannelError> {
+        Err(ChannelError::WrongDirection)
+    }
+
+    fn challenge(&mut self, label: &[u8]) -> F {
+        self.tr.challenge(label)
+    }
+}
+
+impl ProofChannel for VerifierChannel {
+    fn is_prover(&self) -> bool {
+        false
+    }
+
+    fn send_field(&mut self, _label: &[u8], _f: &F) -> Result<(), ChannelError> {
+        Err(ChannelError::WrongDirection)
+    }
+
+    fn recv_field(&mut self, label: &[u8]) -> Result<F, ChannelError> {
+        let ProofItem::Field(f) = *self.next_item()? else {
+            return Err(ChannelError::UnexpectedItem { expected: "field element" });
+        };
+        self.absorb_field(label, &f);
+        Ok(f)
+    }
+
+    fn send_root(&mut self, _label: &[u8], _root: &F) -> Result<(), ChannelError> {
+        Err(ChannelError::WrongDirection)
+    }
+
+    fn recv_root(&mut self, label: &[u8]) -> Result<F, ChannelError> {
+        let ProofItem::Root(root) = *self.next_item()? else {
+            return Err(ChannelError::UnexpectedItem { expected: "root" });
+        };
+        self.absorb_field(label, &root);
+        Ok(root)
+    }
+
+    fn send_opening(&mut self, _label: &[u8], _opening: &MerkleOpening) -> Result<(), ChannelError> {
+        Err(ChannelError::WrongDirection)
+    }
+
+    fn recv_opening(&mut self, label: &[u8]) -> Result<MerkleOpening, ChannelError> {
+        let ProofItem::Opening(opening) = self.next_item()?.clone() else {
+            return Err(ChannelError::UnexpectedItem { expected: "opening" });
+        };
+        absorb_opening(&mut self.tr, label, &opening);
+        Ok(opening)
+    }
+
+    fn challenge(&mut self, label: &[u8]) -> F {
+        self.tr.challenge(label)
+    }
 }
 
 /// =========================
@@ -81,24 +323,20 @@ impl<'a> MerkleProver<'a> {
         Self { chan, tree }
     }
 
-    /// Commit to a vector of field elements.
-    /// Each leaf is (f, s, q) = (value, 0, 0),
+    /// Commit to a vector of field elements, replacing any earlier
+    /// commitment. Each leaf is (f, s, q) = (value, 0, 0),
     /// matching your FRI base-layer convention.
     pub fn commit(&mut self, values: &[F]) -> F {
-        for v in values {
-            self.tree
-                .push_leaf(&[
-                    *v,
-                    F::from(0u64),
-                    F::from(0u64),
-                ]);
-        }
-        let root = self.tree.finalize();
+        let leaves: Vec<[F; 3]> = values
+            .iter()
+            .map(|v| [*v, F::from(0u64), F::from(0u64)])
+            .collect();
+        let root = self.tree.commit(&leaves).expect("values fit the Merkle config");
         self.chan.absorb_field(b"merkle/root", &root);
         root
     }
 
-    pub fn open(&self, index: usize) -> MerkleOpening {
+    pub fn open(&self, index: usize) -> Result<MerkleOpening, MerkleError> {
         self.tree.open(index)
     }
 
@@ -139,7 +377,7 @@ impl<'a> MerkleVerifier<'a> {
             root,
             opening,
             trace_hash,
-        )
+        ) == Ok(true)
     }
 
     pub fn challenge(&mut self, label: &[u8]) -> F {
@@ -154,7 +392,7 @@ impl<'a> MerkleVerifier<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_ff::UniformRand;
+    use ark_ff::{PrimeField, UniformRand};
     use rand::{SeedableRng, rngs::StdRng};
 
     #[test]
@@ -188,11 +426,74 @@ mod tests {
 
         // Merkle opening check
         let idx = 7usize;
-        let opening = prover.open(idx);
+        let opening = prover.open(idx).unwrap();
 
         assert!(
             verifier.verify_opening(&opening, &trace_hash),
             "Merkle opening failed"
         );
     }
+
+    /// Commit, draw a query, open it: written once for both sides.
+    fn commit_and_query<C: ProofChannel>(
+        ch: &mut C,
+        tree: Option<(&MerkleTreeChannel, F)>,
+    ) -> Result<(F, F, MerkleOpening), ChannelError> {
+        let root = ch.root(b"root", || tree.unwrap().1)?;
+        let claim = ch.field(b"claim", || F::from(7u64))?;
+        let index = ch.challenge(b"query").into_bigint().0[0] as usize % 16;
+        let opening = ch.opening(b"open", || tree.unwrap().0.open(index).unwrap())?;
+        Ok((root, claim, opening))
+    }
+
+    #[test]
+    fn proof_channel_runs_one_protocol_both_ways() {
+        let params = transcript::default_params();
+        let cfg = MerkleChannelCfg::new(vec![2, 2, 2, 2], 7);
+        let mut tree = MerkleTreeChannel::new(cfg.clone(), [0u8; 32]);
+        for i in 0..16u64 {
+            tree.push_leaf(&[F::from(i), F::from(0u64), F::from(0u64)]);
+        }
+        let tree_root = tree.finalize().unwrap();
+
+        let mut pchan = ProverChannel::new(Transcript::new(b"CHAN-TEST", params.clone()));
+        let (root, claim, opening) = commit_and_query(&mut pchan, Some((&tree, tree_root))).unwrap();
+        assert_eq!(pchan.recv_field(b"claim"), Err(ChannelError::WrongDirection));
+        let after_p = pchan.challenge(b"after");
+        let proof = pchan.into_proof();
+        assert_eq!(proof.items.len(), 3);
+
+        let mut vchan = VerifierChannel::with_proof(Transcript::new(b"CHAN-TEST", params.clone()), proof.clone());
+        let (v_root, v_claim, v_opening) = commit_and_query(&mut vchan, None).unwrap();
+        assert!(vchan.is_exhausted());
+        assert_eq!((v_root, v_claim), (root, claim));
+        assert_eq!(v_opening.index, opening.index);
+        assert_eq!(vchan.challenge(b"after"), after_p);
+        assert_eq!(MerkleTreeChannel::verify_opening(&cfg, v_root, &v_opening, &[0u8; 32]), Ok(true));
+        assert_eq!(vchan.recv_field(b"more"), Err(ChannelError::EndOfProof));
+
+        // Items must arrive as the kind the verifier expects.
+        let mut swapped = proof;
+        swapped.items.swap(0, 1);
+        let mut vchan = VerifierChannel::with_proof(Transcript::new(b"CHAN-TEST", params), swapped);
+        assert_eq!(
+            commit_and_query(&mut vchan, None).unwrap_err(),
+            ChannelError::UnexpectedItem { expected: "root" }
+        );
+    }
+
+    #[test]
+    fn ext3_challenges_agree() {
+        let params = transcript::default_params();
+        let mut pchan = ProverChannel::new(Transcript::new(b"CHAN-TEST", params.clone()));
+        let mut vchan = VerifierChannel::new(Transcript::new(b"CHAN-TEST", params));
+
+        let z = pchan.challenge_ext3(b"z");
+        assert_eq!(z, vchan.challenge_ext3(b"z"));
+        assert_ne!(z.a0, z.a1);
+
+        pchan.absorb_ext3(b"z", &z);
+        vchan.absorb_ext3(b"z", &z);
+        assert_eq!(pchan.challenge(b"beta"), vchan.challenge(b"beta"));
+    }
 }
\ No newline at end of file
@@ -1,3 +1,6 @@
+use core::cell::Cell;
+use core::ops::Range;
+
 use ark_ff::{PrimeField, Zero};
 use ark_goldilocks::Goldilocks as F;
 
@@ -18,12 +21,247 @@ use sha3::{Digest, Sha3_256};
 ///   (sha3_commit, poseidon_commit, trace_hash)
 ///
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 pub struct DualCommitment {
     pub sha_commit: [u8; 32],   // sha3_commit(encode(trace), trace_hash)
+    pub sha_row_root: [u8; 32], // ByteMerkleTree over encode_rows(trace)
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub poseidon_root: F,       // poseidon_commit(trace, trace_hash)
     pub trace_hash: [u8; 32],   // sha3_trace(trace)
 }
 
+/// =======================
+/// SHA3 byte Merkle tree
+/// =======================
+///
+/// Binary tree with the encoded trace rows as leaves, so single rows can be
+/// opened against a SHA3 root by verifiers that only handle bytes. Leaves
+/// are padded to a power of two with zero digests; the root binds the row
+/// count so padding cannot be passed off as rows.
+#[derive(Clone, Debug)]
+pub struct ByteMerkleTree {
+    num_rows: usize,
+    levels: Vec<Vec<[u8; 32]>>,
+    root: [u8; 32],
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct ByteMerkleProof {
+    pub index: usize,
+    /// Sibling digests from the leaf level up.
+    pub siblings: Vec<[u8; 32]>,
+}
+
+/// Rows `start..` under one proof; see [`merkle::range`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct ByteMerkleRangeProof {
+    pub start: usize,
+    /// Per level from the leaves up, the digests left and right of the run.
+    pub siblings: Vec<merkle::range::RangeLevel<[u8; 32]>>,
+}
+
+impl ByteMerkleTree {
+    fn leaf_hash(trace_hash: &[u8; 32], index: usize, row: &[u8]) -> [u8; 32] {
+        let mut h = Sha3_256::new();
+        h.update(b"TRACE_ROW_LEAF_V1");
+        h.update(trace_hash);
+        h.update((index as u64).to_le_bytes());
+        h.update((row.len() as u64).to_le_bytes());
+        h.update(row);
+        h.finalize().into()
+    }
+
+    fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        let mut h = Sha3_256::new();
+        h.update(b"TRACE_ROW_NODE_V1");
+        h.update(left);
+        h.update(right);
+        h.finalize().into()
+    }
+
+    fn root_hash(num_rows: usize, top: &[u8; 32]) -> [u8; 32] {
+        let mut h = Sha3_256::new();
+        h.update(b"TRACE_ROW_ROOT_V1");
+        h.update((num_rows as u64).to_le_bytes());
+        h.update(top);
+        h.finalize().into()
+    }
+
+    pub fn new(rows: &[Vec<u8>], trace_hash: &[u8; 32]) -> Self {
+        assert!(!rows.is_empty(), "empty trace");
+        let width = rows.len().next_power_of_two();
+
+        let mut level: Vec<[u8; 32]> = rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| Self::leaf_hash(trace_hash, i, row))
+            .collect();
+        level.resize(width, [0u8; 32]);
+
+        let mut levels = vec![level];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks_exact(2)
+                .map(|pair| Self::node_hash(&pair[0], &pair[1]))
+                .collect();
+            levels.push(next);
+        }
+
+        let root = Self::root_hash(rows.len(), &levels.last().unwrap()[0]);
+        Self {
+            num_rows: rows.len(),
+            levels,
+            root,
+        }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.root
+    }
+
+    pub fn open(&self, index: usize) -> ByteMerkleProof {
+        assert!(index < self.num_rows, "row index out of range");
+        let siblings = self.levels[..self.levels.len() - 1]
+            .iter()
+            .enumerate()
+            .map(|(d, level)| level[(index >> d) ^ 1])
+            .collect();
+        ByteMerkleProof { index, siblings }
+    }
+
+    /// Open rows `start..start + len` with one proof.
+    pub fn open_range(&self, start: usize, len: usize) -> ByteMerkleRangeProof {
+        assert!(
+            len > 0 && start + len <= self.num_rows,
+            "row range out of range"
+        );
+        let arities = vec![2; self.levels.len() - 1];
+        ByteMerkleRangeProof {
+            start,
+            siblings: merkle::range::range_siblings(&arities, start, len, |d, i| {
+                self.levels[d][i]
+            }),
+        }
+    }
+
+    pub fn verify_range(
+        root: &[u8; 32],
+        trace_hash: &[u8; 32],
+        num_rows: usize,
+        rows: &[Vec<u8>],
+        proof: &ByteMerkleRangeProof,
+    ) -> bool {
+        let depth = num_rows.next_power_of_two().trailing_zeros() as usize;
+        if rows.is_empty()
+            || proof.start.saturating_add(rows.len()) > num_rows
+            || proof.siblings.len() != depth
+        {
+            return false;
+        }
+
+        let leaves: Vec<[u8; 32]> = rows
+            .iter()
+            .enumerate()
+            .map(|(k, row)| Self::leaf_hash(trace_hash, proof.start + k, row))
+            .collect();
+        let top = merkle::range::range_root(
+            &vec![2; depth],
+            proof.start as u64,
+            &leaves,
+            &proof.siblings,
+            |_, _, pair| Self::node_hash(&pair[0], &pair[1]),
+        );
+        top.is_some_and(|top| Self::root_hash(num_rows, &top) == *root)
+    }
+
+    pub fn verify(
+        root: &[u8; 32],
+        trace_hash: &[u8; 32],
+        num_rows: usize,
+        row: &[u8],
+        proof: &ByteMerkleProof,
+    ) -> bool {
+        if proof.index >= num_rows
+            || proof.siblings.len() != num_rows.next_power_of_two().trailing_zeros() as usize
+        {
+            return false;
+        }
+
+        let mut acc = Self::leaf_hash(trace_hash, proof.index, row);
+        for (d, sib) in proof.siblings.iter().enumerate() {
+            acc = if (proof.index >> d) & 1 == 0 {
+                Self::node_hash(&acc, sib)
+            } else {
+                Self::node_hash(sib, &acc)
+            };
+        }
+        Self::root_hash(num_rows, &acc) == *root
+    }
+}
+
+/// =======================
+/// Poseidon row openings
+/// =======================
+///
+/// A trace kept together with its Poseidon tree, so windows of rows can be
+/// opened against `DualCommitment::poseidon_root`.
+#[derive(Clone, Debug)]
+pub struct CommittedTrace {
+    trace: Vec<Vec<F>>,
+    arity: usize,
+    levels: Vec<Vec<F>>,
+    commitment: DualCommitment,
+}
+
+/// Rows `start..` under one proof against the Poseidon root; see
+/// [`merkle::range`]. Slots past the end of a short last group are sent as
+/// zeros.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct RangeOpening {
+    pub start: usize,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub rows: Vec<Vec<F>>,
+    /// Per level from the leaves up, the digests left and right of the run.
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub siblings: Vec<merkle::range::RangeLevel<F>>,
+}
+
+impl CommittedTrace {
+    pub fn commitment(&self) -> &DualCommitment {
+        &self.commitment
+    }
+
+    pub fn trace(&self) -> &[Vec<F>] {
+        &self.trace
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.trace.len()
+    }
+
+    /// Open `rows` with one proof.
+    pub fn open_rows(&self, rows: Range<usize>) -> RangeOpening {
+        assert!(
+            rows.start < rows.end && rows.end <= self.num_rows(),
+            "row range out of range"
+        );
+        let arities = vec![self.arity; self.levels.len() - 1];
+        let siblings = merkle::range::range_siblings(&arities, rows.start, rows.len(), |l, i| {
+            self.levels[l].get(i).copied().unwrap_or_else(F::zero)
+        });
+        RangeOpening {
+            start: rows.start,
+            rows: self.trace[rows].to_vec(),
+            siblings,
+        }
+    }
+}
+
 /// Merkle commitment using Poseidon (t = 17, arity = 16)
 pub struct MerkleCommitment {
     pub arity: usize,
@@ -54,17 +292,16 @@ impl MerkleCommitment {
     // Row-wise encoding (Merkle leaves)
     // ------------------------------------------------------------
 
+    pub fn encode_row(row: &[F]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(row.len() * 8);
+        for x in row {
+            out.extend_from_slice(&Self::field_to_bytes(x));
+        }
+        out
+    }
+
     fn encode_trace_rows(trace: &[Vec<F>]) -> Vec<Vec<u8>> {
-        trace
-            .iter()
-            .map(|row| {
-                let mut out = Vec::with_capacity(row.len() * 8);
-                for x in row {
-                    out.extend_from_slice(&Self::field_to_bytes(x));
-                }
-                out
-            })
-            .collect()
+        trace.iter().map(|row| Self::encode_row(row)).collect()
     }
 
     // ------------------------------------------------------------
@@ -141,35 +378,50 @@ impl MerkleCommitment {
     }
 
     fn commit_with_hash(&self, trace: &[Vec<F>], trace_hash: &[u8; 32]) -> F {
-        let leaves_bytes = Self::encode_trace_rows(trace);
+        self.poseidon_levels(trace, trace_hash).last().unwrap()[0]
+    }
 
-        let mut level: Vec<F> = leaves_bytes
-            .iter()
-            .map(|bytes| {
-                let fields: Vec<F> = bytes
-                    .chunks_exact(8)
-                    .map(|chunk| {
-                        let mut arr = [0u8; 8];
-                        arr.copy_from_slice(chunk);
-                        F::from(u64::from_le_bytes(arr))
-                    })
-                    .collect();
-
-                Self::poseidon_hash_with_ds(&fields, &self.params, trace_hash)
+    fn leaf_hash(&self, row: &[F], trace_hash: &[u8; 32]) -> F {
+        let fields: Vec<F> = Self::encode_row(row)
+            .chunks_exact(8)
+            .map(|chunk| {
+                let mut arr = [0u8; 8];
+                arr.copy_from_slice(chunk);
+                F::from(u64::from_le_bytes(arr))
             })
             .collect();
 
-        while level.len() > 1 {
-            let mut next = Vec::new();
-            for chunk in level.chunks(self.arity) {
-                let parent =
-                    Self::poseidon_hash_with_ds(chunk, &self.params, trace_hash);
-                next.push(parent);
-            }
-            level = next;
+        Self::poseidon_hash_with_ds(&fields, &self.params, trace_hash)
+    }
+
+    /// Every level of the Poseidon tree, leaves first. Groups are not
+    /// padded: the last parent of a level may have fewer children.
+    fn poseidon_levels(&self, trace: &[Vec<F>], trace_hash: &[u8; 32]) -> Vec<Vec<F>> {
+        let mut levels = vec![trace
+            .iter()
+            .map(|row| self.leaf_hash(row, trace_hash))
+            .collect::<Vec<F>>()];
+
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(self.arity)
+                .map(|chunk| Self::poseidon_hash_with_ds(chunk, &self.params, trace_hash))
+                .collect();
+            levels.push(next);
         }
 
-        level[0]
+        levels
+    }
+
+    /// Level sizes of the Poseidon tree over `num_rows` rows.
+    fn level_widths(&self, num_rows: usize) -> Vec<usize> {
+        let mut widths = vec![num_rows];
+        while *widths.last().unwrap() > 1 {
+            widths.push(widths.last().unwrap().div_ceil(self.arity));
+        }
+        widths
     }
 
     // ============================================================
@@ -179,14 +431,133 @@ impl MerkleCommitment {
     pub fn dual_commit(&self, trace: &[Vec<F>]) -> DualCommitment {
         let trace_hash = Self::sha3_trace(trace);
         let sha_commit = Self::sha3_commit(trace, &trace_hash);
+        let sha_row_root = Self::sha3_row_tree(trace, &trace_hash).root();
         let poseidon_root = self.commit_with_hash(trace, &trace_hash);
 
         DualCommitment {
             sha_commit,
+            sha_row_root,
             poseidon_root,
             trace_hash,
         }
     }
+
+    /// Commit to `trace` and keep its Poseidon tree for
+    /// [`CommittedTrace::open_rows`].
+    pub fn commit_trace(&self, trace: Vec<Vec<F>>) -> CommittedTrace {
+        let trace_hash = Self::sha3_trace(&trace);
+        let levels = self.poseidon_levels(&trace, &trace_hash);
+        let commitment = DualCommitment {
+            sha_commit: Self::sha3_commit(&trace, &trace_hash),
+            sha_row_root: Self::sha3_row_tree(&trace, &trace_hash).root(),
+            poseidon_root: levels.last().unwrap()[0],
+            trace_hash,
+        };
+
+        CommittedTrace {
+            trace,
+            arity: self.arity,
+            levels,
+            commitment,
+        }
+    }
+
+    /// Check a [`CommittedTrace::open_rows`] opening against
+    /// `DualCommitment::poseidon_root`. That root does not bind the row
+    /// count, so `num_rows` has to be known from elsewhere.
+    pub fn verify_rows(
+        &self,
+        commitment: &DualCommitment,
+        num_rows: usize,
+        opening: &RangeOpening,
+    ) -> bool {
+        if num_rows == 0 || opening.start.saturating_add(opening.rows.len()) > num_rows {
+            return false;
+        }
+
+        let widths = self.level_widths(num_rows);
+        let leaves: Vec<F> = opening
+            .rows
+            .iter()
+            .map(|row| self.leaf_hash(row, &commitment.trace_hash))
+            .collect();
+
+        // Parents hash only the children that exist; the filler slots of a
+        // short last group must be the zeros the prover sends.
+        let fillers_ok = Cell::new(true);
+        let root = merkle::range::range_root(
+            &vec![self.arity; widths.len() - 1],
+            opening.start as u64,
+            &leaves,
+            &opening.siblings,
+            |level, position, children| {
+                let live = widths[level - 1].saturating_sub(position as usize * self.arity).min(children.len());
+                if children[live..].iter().any(|c| !c.is_zero()) {
+                    fillers_ok.set(false);
+                }
+                Self::poseidon_hash_with_ds(&children[..live], &self.params, &commitment.trace_hash)
+            },
+        );
+        fillers_ok.get() && root == Some(commitment.poseidon_root)
+    }
+
+    // ============================================================
+    // Row openings against sha_row_root
+    // ============================================================
+
+    fn sha3_row_tree(trace: &[Vec<F>], trace_hash: &[u8; 32]) -> ByteMerkleTree {
+        ByteMerkleTree::new(&Self::encode_trace_rows(trace), trace_hash)
+    }
+
+    /// Encoded row `index` with its path to `DualCommitment::sha_row_root`.
+    pub fn open_row(trace: &[Vec<F>], index: usize) -> (Vec<u8>, ByteMerkleProof) {
+        let trace_hash = Self::sha3_trace(trace);
+        let proof = Self::sha3_row_tree(trace, &trace_hash).open(index);
+        (Self::encode_row(&trace[index]), proof)
+    }
+
+    /// Encoded rows `start..start + len` with one proof against
+    /// `DualCommitment::sha_row_root`.
+    pub fn open_row_range(
+        trace: &[Vec<F>],
+        start: usize,
+        len: usize,
+    ) -> (Vec<Vec<u8>>, ByteMerkleRangeProof) {
+        let trace_hash = Self::sha3_trace(trace);
+        let proof = Self::sha3_row_tree(trace, &trace_hash).open_range(start, len);
+        let rows = trace[start..start + len].iter().map(|r| Self::encode_row(r)).collect();
+        (rows, proof)
+    }
+
+    pub fn verify_row_range(
+        commitment: &DualCommitment,
+        num_rows: usize,
+        rows: &[Vec<u8>],
+        proof: &ByteMerkleRangeProof,
+    ) -> bool {
+        ByteMerkleTree::verify_range(
+            &commitment.sha_row_root,
+            &commitment.trace_hash,
+            num_rows,
+            rows,
+            proof,
+        )
+    }
+
+    pub fn verify_row(
+        commitment: &DualCommitment,
+        num_rows: usize,
+        row: &[u8],
+        proof: &ByteMerkleProof,
+    ) -> bool {
+        ByteMerkleTree::verify(
+            &commitment.sha_row_root,
+            &commitment.trace_hash,
+            num_rows,
+            row,
+            proof,
+        )
+    }
 }
 
 #[cfg(test)]
@@ -225,6 +596,15 @@ mod tests {
         assert_eq!(c1, c2);
     }
 
+    #[test]
+    #[cfg(feature = "serde1")]
+    fn dual_commitment_roundtrips_through_serde() {
+        let mc = MerkleCommitment::with_default_params();
+        let c = mc.dual_commit(&[vec![F::from(42u64)], vec![F::from(7u64)]]);
+        let json = serde_json::to_string(&c).unwrap();
+        assert_eq!(serde_json::from_str::<DualCommitment>(&json).unwrap(), c);
+    }
+
     #[test]
     fn poseidon_commit_binds_trace_hash() {
         let mc = MerkleCommitment::with_default_params();
@@ -238,4 +618,98 @@ mod tests {
         assert_ne!(c1.poseidon_root, c2.poseidon_root);
         assert_ne!(c1.trace_hash, c2.trace_hash);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn row_openings_verify_against_sha_row_root() {
+        let mc = MerkleCommitment::with_default_params();
+        let trace: Vec<Vec<F>> = (0..5u64)
+            .map(|i| vec![F::from(i), F::from(10 * i)])
+            .collect();
+        let c = mc.dual_commit(&trace);
+
+        for i in 0..trace.len() {
+            let (row, proof) = MerkleCommitment::open_row(&trace, i);
+            assert!(MerkleCommitment::verify_row(&c, trace.len(), &row, &proof));
+        }
+
+        let (mut row, mut proof) = MerkleCommitment::open_row(&trace, 3);
+        assert!(!MerkleCommitment::verify_row(&c, trace.len() + 1, &row, &proof));
+        proof.index = 2;
+        assert!(!MerkleCommitment::verify_row(&c, trace.len(), &row, &proof));
+        proof.index = 3;
+        row[0] ^= 1;
+        assert!(!MerkleCommitment::verify_row(&c, trace.len(), &row, &proof));
+
+        // Padding slots cannot be opened as rows.
+        let (_, pad) = MerkleCommitment::open_row(&trace, 4);
+        let fake = ByteMerkleProof { index: 5, ..pad };
+        assert!(!MerkleCommitment::verify_row(&c, trace.len(), &[], &fake));
+    }
+
+    #[test]
+    fn row_range_openings_verify_against_poseidon_root() {
+        let mc = MerkleCommitment::with_default_params();
+        let trace: Vec<Vec<F>> = (0..37u64)
+            .map(|i| vec![F::from(i), F::from(3 * i + 1)])
+            .collect();
+        let committed = mc.commit_trace(trace.clone());
+        let c = committed.commitment().clone();
+        assert_eq!(c, mc.dual_commit(&trace));
+
+        for rows in [0..37, 0..1, 14..19, 30..37, 36..37, 16..32] {
+            let opening = committed.open_rows(rows.clone());
+            assert_eq!(opening.rows, trace[rows]);
+            assert!(mc.verify_rows(&c, trace.len(), &opening));
+        }
+
+        let mut opening = committed.open_rows(14..19);
+        assert!(!mc.verify_rows(&c, 18, &opening));
+        opening.start = 13;
+        assert!(!mc.verify_rows(&c, trace.len(), &opening));
+        opening.start = 14;
+        opening.rows[1][1] += F::from(1u64);
+        assert!(!mc.verify_rows(&c, trace.len(), &opening));
+
+        // The filler after the short last group is pinned to zero.
+        let mut opening = committed.open_rows(30..37);
+        let filler = opening.siblings[1].1.last_mut().unwrap();
+        assert!(filler.is_zero());
+        *filler = F::from(1u64);
+        assert!(!mc.verify_rows(&c, trace.len(), &opening));
+
+        // A single row is its own root.
+        let single = mc.commit_trace(vec![vec![F::from(9u64)]]);
+        let opening = single.open_rows(0..1);
+        assert!(opening.siblings.is_empty());
+        assert!(mc.verify_rows(single.commitment(), 1, &opening));
+    }
+
+    #[test]
+    fn row_range_openings_verify_against_sha_row_root() {
+        let mc = MerkleCommitment::with_default_params();
+        let trace: Vec<Vec<F>> = (0..11u64)
+            .map(|i| vec![F::from(i), F::from(7 * i)])
+            .collect();
+        let c = mc.dual_commit(&trace);
+
+        for (start, len) in [(0, 11), (0, 1), (3, 5), (10, 1), (4, 4)] {
+            let (rows, proof) = MerkleCommitment::open_row_range(&trace, start, len);
+            assert_eq!(rows.len(), len);
+            assert!(MerkleCommitment::verify_row_range(&c, trace.len(), &rows, &proof));
+        }
+
+        let (mut rows, mut proof) = MerkleCommitment::open_row_range(&trace, 3, 5);
+        assert!(!MerkleCommitment::verify_row_range(&c, trace.len() + 1, &rows, &proof));
+        assert!(!MerkleCommitment::verify_row_range(&c, trace.len(), &rows[..4], &proof));
+        proof.start = 2;
+        assert!(!MerkleCommitment::verify_row_range(&c, trace.len(), &rows, &proof));
+        proof.start = 3;
+        rows[2][0] ^= 1;
+        assert!(!MerkleCommitment::verify_row_range(&c, trace.len(), &rows, &proof));
+
+        // Padding slots cannot be opened as rows.
+        let (mut rows, proof) = MerkleCommitment::open_row_range(&trace, 8, 3);
+        rows.push(Vec::new());
+        assert!(!MerkleCommitment::verify_row_range(&c, trace.len(), &rows, &proof));
+    }
+}
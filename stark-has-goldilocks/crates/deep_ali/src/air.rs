@@ -0,0 +1,907 @@
+//! AIR front-end: prove that an execution trace satisfies transition and
+//! boundary constraints, with DEEP-FRI as the low-degree test.
+//!
+//! An [`Air`] describes `trace_width()` columns over `trace_length()` rows
+//! (a power of two) together with
+//!
+//! * transition constraints over consecutive rows, required on every row
+//!   but the last `transition_exemptions()`,
+//! * boundary constraints pinning single cells,
+//! * periodic columns known to both sides (round constants, selectors), and
+//! * an optional auxiliary segment built from transcript challenges once the
+//!   main trace is committed, for lookup and permutation arguments.
+//!
+//! The prover extends each segment onto the coset `g·H_n` (n = blowup · T),
+//! commits it row-wise, and folds every constraint quotient into a single
+//! composition codeword with transcript coefficients. That codeword goes
+//! through DEEP-FRI with the AIR transcript bound in as the trace root; at
+//! each FRI query the verifier opens the current and next trace rows and
+//! recomputes the composition value.
+
+use alloc::{vec, vec::Vec};
+use ark_ff::{FftField, Field, One, PrimeField, Zero};
+use ark_goldilocks::Goldilocks as F;
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+
+use merkle::{ArityPolicy, MerkleChannelCfg, MerkleTreeChannel, MerkleValueOpening};
+use transcript::{default_params as transcript_params, Transcript};
+
+use crate::fri::{
+    deep_fri_layer0_values, deep_fri_verify, prove_hooked, radix2_generator,
+    DeepFriParams, DeepFriProof, FoldCheck, FoldingMode, FriDomain, IndexChaining, ProverHook,
+    ProverPhase, QuerySampling, TranscriptHash, VerifierKey,
+};
+
+mod ds {
+    pub const AIR_INIT: &[u8] = b"AIR/FS";
+    pub const AIR_AUX: &[u8] = b"AIR/aux";
+    pub const AIR_ALPHA: &[u8] = b"AIR/alpha";
+    pub const AIR_BETA: &[u8] = b"AIR/beta";
+    pub const AIR_FRI_SEED: &[u8] = b"AIR/fri-seed";
+    pub const AIR_TRACE_ROOT: &[u8] = b"AIR/trace-root";
+}
+
+/// Merkle tree labels for the trace segments; FRI layers use `0..L`.
+const SEGMENT_TREE_LABEL: u64 = 1 << 32;
+
+/// `trace[column][row] == value`. Columns index main then aux.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoundaryConstraint {
+    pub column: usize,
+    pub row: usize,
+    pub value: F,
+}
+
+impl BoundaryConstraint {
+    pub fn new(column: usize, row: usize, value: F) -> Self {
+        Self { column, row, value }
+    }
+}
+
+/// Two consecutive rows, main columns followed by aux columns.
+pub struct EvaluationFrame<'a> {
+    pub current: &'a [F],
+    pub next: &'a [F],
+    /// Periodic column values at the current row.
+    pub periodic: &'a [F],
+    /// Aux-segment challenges (empty without an aux segment).
+    pub challenges: &'a [F],
+}
+
+/// A computation the prover can attest to.
+pub trait Air {
+    fn trace_width(&self) -> usize;
+
+    /// Number of rows; a power of two.
+    fn trace_length(&self) -> usize;
+
+    fn num_transition_constraints(&self) -> usize;
+
+    /// Largest total degree of a transition constraint, counting trace and
+    /// periodic columns alike.
+    fn transition_degree(&self) -> usize;
+
+    /// Degree of each transition constraint, in `evaluate_transition`
+    /// order. The composition degree, and with it the minimum blowup, is
+    /// taken from these; the default gives every constraint
+    /// [`transition_degree`](Air::transition_degree).
+    fn transition_degrees(&self) -> Vec<usize> {
+        vec![self.transition_degree(); self.num_transition_constraints()]
+    }
+
+    /// Trailing rows on which transitions are not enforced. Zero makes the
+    /// constraints cyclic (the last row's successor is row 0).
+    fn transition_exemptions(&self) -> usize {
+        1
+    }
+
+    /// Columns repeating with a power-of-two period dividing `trace_length`.
+    fn periodic_columns(&self) -> Vec<Vec<F>> {
+        Vec::new()
+    }
+
+    fn aux_width(&self) -> usize {
+        0
+    }
+
+    fn num_aux_challenges(&self) -> usize {
+        0
+    }
+
+    /// Aux columns, built after the main trace is committed.
+    fn build_aux_trace(&self, _main: &[Vec<F>], _challenges: &[F]) -> Vec<Vec<F>> {
+        Vec::new()
+    }
+
+    /// Write each transition constraint's value into `out`; zero on a valid
+    /// row pair.
+    fn evaluate_transition(&self, frame: &EvaluationFrame<'_>, out: &mut [F]);
+
+    fn boundary_constraints(&self, challenges: &[F]) -> Vec<BoundaryConstraint>;
+
+    /// Statement data absorbed before any commitment.
+    fn public_inputs(&self) -> Vec<F> {
+        Vec::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct AirProofOptions {
+    /// LDE size over trace length; a power of two, at least [`min_blowup`].
+    pub blowup: usize,
+    pub num_queries: usize,
+}
+
+impl AirProofOptions {
+    pub fn new(blowup: usize, num_queries: usize) -> Self {
+        Self { blowup, num_queries }
+    }
+
+    /// Check the blowup against the composition degree of `air`.
+    pub fn validate<A: Air>(&self, air: &A) -> Result<(), AirConfigError> {
+        let degrees = air.transition_degrees();
+        if degrees.len() != air.num_transition_constraints() {
+            return Err(AirConfigError::DegreeCount {
+                constraints: air.num_transition_constraints(),
+                degrees: degrees.len(),
+            });
+        }
+        if !self.blowup.is_power_of_two() {
+            return Err(AirConfigError::BlowupNotPowerOfTwo { blowup: self.blowup });
+        }
+        let required = min_blowup(air);
+        if self.blowup < required {
+            return Err(AirConfigError::BlowupTooSmall {
+                blowup: self.blowup,
+                required,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AirConfigError {
+    /// `transition_degrees()` has `degrees` entries for `constraints`
+    /// constraints.
+    DegreeCount { constraints: usize, degrees: usize },
+    BlowupNotPowerOfTwo { blowup: usize },
+    /// The composition needs a blowup of at least `required`.
+    BlowupTooSmall { blowup: usize, required: usize },
+}
+
+impl core::fmt::Display for AirConfigError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            AirConfigError::DegreeCount { constraints, degrees } => {
+                write!(f, "{degrees} transition degrees for {constraints} constraints")
+            }
+            AirConfigError::BlowupNotPowerOfTwo { blowup } => {
+                write!(f, "blowup {blowup} is not a power of two")
+            }
+            AirConfigError::BlowupTooSmall { blowup, required } => {
+                write!(f, "blowup {blowup} too small for the transition degrees, need ≥ {required}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AirConfigError {}
+
+/// One committed LDE row with its Merkle opening.
+pub type TraceRowOpening = MerkleValueOpening;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct SegmentOpening {
+    pub current: TraceRowOpening,
+    pub next: TraceRowOpening,
+}
+
+/// Trace rows behind one FRI query, one entry per segment (main, aux).
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraceQuery {
+    pub segments: Vec<SegmentOpening>,
+}
+
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct AirProof {
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub segment_roots: Vec<F>,
+    pub trace_queries: Vec<TraceQuery>,
+    pub fri: DeepFriProof,
+}
+
+/// Coefficient count of the composition polynomial.
+///
+/// Transition `k` of degree `d_k` times the exemption product, over
+/// `x^T − 1`, has degree `d_k·(T − 1) + E − T` for `E` exempted rows; the
+/// boundary quotients stay below `T − 1`.
+pub fn composition_degree_bound<A: Air>(air: &A) -> usize {
+    let t = air.trace_length();
+    let e = air.transition_exemptions();
+    air.transition_degrees()
+        .into_iter()
+        .map(|d| (d * (t - 1) + e).saturating_sub(t) + 1)
+        .fold(t - 1, usize::max)
+}
+
+/// Composition degree over trace length, rounded up to a power of two.
+fn degree_factor<A: Air>(air: &A) -> usize {
+    composition_degree_bound(air).div_ceil(air.trace_length()).max(1).next_power_of_two()
+}
+
+/// Smallest blowup leaving FRI a non-trivial final layer: the composition
+/// is then tested at rate at most 1/2.
+pub fn min_blowup<A: Air>(air: &A) -> usize {
+    2 * degree_factor(air)
+}
+
+/// Fold by 4 where possible, finishing with a 2 if needed.
+fn fri_schedule(mut fold: usize) -> Vec<usize> {
+    let mut schedule = Vec::new();
+    while fold > 1 {
+        let m = if fold.is_multiple_of(4) { 4 } else { 2 };
+        schedule.push(m);
+        fold /= m;
+    }
+    schedule
+}
+
+fn segment_cfg(n: usize, segment: usize) -> MerkleChannelCfg {
+    MerkleChannelCfg::for_leaves(n, ArityPolicy::UpTo(4), SEGMENT_TREE_LABEL + segment as u64)
+}
+
+/// Column interpolants over `H_T` evaluated on `g·H_n`, row-major.
+fn lde_rows(columns: &[Vec<F>], t: usize, n: usize) -> Vec<Vec<F>> {
+    let dom_t = GeneralEvaluationDomain::<F>::new(t).expect("power-of-two trace length");
+    let dom_n = GeneralEvaluationDomain::<F>::new(n).expect("power-of-two LDE size");
+    let g = F::GENERATOR;
+
+    let mut rows: Vec<Vec<F>> = (0..n).map(|_| Vec::with_capacity(columns.len())).collect();
+    for col in columns {
+        let mut coeffs = dom_t.ifft(col);
+        let mut shift = F::one();
+        for c in coeffs.iter_mut() {
+            *c *= shift;
+            shift *= g;
+        }
+        coeffs.resize(n, F::zero());
+        for (row, v) in rows.iter_mut().zip(dom_n.fft(&coeffs)) {
+            row.push(v);
+        }
+    }
+    rows
+}
+
+fn commit_rows(rows: &[Vec<F>], cfg: MerkleChannelCfg) -> (MerkleTreeChannel, F) {
+    let mut tree = MerkleTreeChannel::new(cfg, [0u8; 32]);
+    for row in rows {
+        tree.push_leaf(row);
+    }
+    let root = tree.finalize().expect("trace fits its Merkle config");
+    (tree, root)
+}
+
+fn air_transcript<A: Air>(air: &A, opts: &AirProofOptions) -> Transcript {
+    let mut tr = Transcript::new(ds::AIR_INIT, transcript_params());
+    for v in [
+        air.trace_length(),
+        air.trace_width(),
+        air.aux_width(),
+        opts.blowup,
+        opts.num_queries,
+    ] {
+        tr.absorb_field(F::from(v as u64));
+    }
+    for d in air.transition_degrees() {
+        tr.absorb_field(F::from(d as u64));
+    }
+    for x in air.public_inputs() {
+        tr.absorb_field(x);
+    }
+    tr
+}
+
+fn draw_aux_challenges<A: Air>(air: &A, tr: &mut Transcript) -> Vec<F> {
+    tr.challenge_many(ds::AIR_AUX, air.num_aux_challenges())
+}
+
+fn draw_fri_params<A: Air>(air: &A, opts: &AirProofOptions, tr: &mut Transcript) -> DeepFriParams {
+    let seed_z = tr.challenge(ds::AIR_FRI_SEED).into_bigint().0[0];
+    DeepFriParams {
+        schedule: fri_schedule(air.trace_length() * degree_factor(air)),
+        r: opts.num_queries,
+        seed_z,
+        folding_mode: FoldingMode::CosetInterpolation,
+        fold_check: FoldCheck::RecomputeFromCoset,
+        grinding_bits: 0,
+        query_sampling: QuerySampling::default(),
+        index_chaining: IndexChaining::default(),
+        transcript: TranscriptHash::default(),
+    }
+}
+
+/// Periodic column as coefficients over its own period.
+struct PeriodicColumn {
+    period: usize,
+    values: Vec<F>,
+    coeffs: Vec<F>,
+}
+
+/// Everything needed to evaluate the composition at a single point; shared
+/// by prover and verifier so both compute the same function.
+struct Composer<'a, A: Air> {
+    air: &'a A,
+    t: usize,
+    exempt_points: Vec<F>,
+    periodic: Vec<PeriodicColumn>,
+    challenges: Vec<F>,
+    boundaries: Vec<(BoundaryConstraint, F)>,
+    alphas: Vec<F>,
+    betas: Vec<F>,
+}
+
+impl<'a, A: Air> Composer<'a, A> {
+    fn new(air: &'a A, challenges: Vec<F>, tr: &mut Transcript) -> Self {
+        let t = air.trace_length();
+        let omega_t = radix2_generator(t);
+
+        let exempt_points = (1..=air.transition_exemptions())
+            .map(|k| omega_t.pow([(t - k) as u64]))
+            .collect();
+
+        let periodic = air
+            .periodic_columns()
+            .into_iter()
+            .map(|col| {
+                let period = col.len();
+                assert!(
+                    period.is_power_of_two() && t.is_multiple_of(period),
+                    "periodic column length must be a power of two dividing the trace length"
+                );
+                let dom = GeneralEvaluationDomain::<F>::new(period).expect("power-of-two period");
+                PeriodicColumn {
+                    period,
+                    coeffs: dom.ifft(&col),
+                    values: col,
+                }
+            })
+            .collect();
+
+        let boundaries: Vec<(BoundaryConstraint, F)> = air
+            .boundary_constraints(&challenges)
+            .into_iter()
+            .map(|b| (b, omega_t.pow([b.row as u64])))
+            .collect();
+
+        let alphas = tr.challenge_many(ds::AIR_ALPHA, air.num_transition_constraints());
+        let betas = tr.challenge_many(ds::AIR_BETA, boundaries.len());
+
+        Self {
+            air,
+            t,
+            exempt_points,
+            periodic,
+            challenges,
+            boundaries,
+            alphas,
+            betas,
+        }
+    }
+
+    /// Periodic values on the whole LDE coset, row-major (prover side).
+    ///
+    /// A column of period p tiled to length T interpolates to `P(X^{T/p})`,
+    /// so one FFT-based LDE replaces per-point Horner evaluation.
+    fn periodic_lde(&self, n: usize) -> Vec<Vec<F>> {
+        let tiled: Vec<Vec<F>> = self
+            .periodic
+            .iter()
+            .map(|col| col.values.iter().copied().cycle().take(self.t).collect())
+            .collect();
+        lde_rows(&tiled, self.t, n)
+    }
+
+    /// Periodic values at a single point (verifier side).
+    fn periodic_at(&self, x: F) -> Vec<F> {
+        self.periodic
+            .iter()
+            .map(|col| {
+                let y = x.pow([(self.t / col.period) as u64]);
+                col.coeffs.iter().rev().fold(F::zero(), |acc, c| acc * y + c)
+            })
+            .collect()
+    }
+
+    fn transitions(&self, current: &[F], next: &[F], periodic: &[F]) -> Vec<F> {
+        let frame = EvaluationFrame {
+            current,
+            next,
+            periodic,
+            challenges: &self.challenges,
+        };
+        let mut t_vals = vec![F::zero(); self.alphas.len()];
+        self.air.evaluate_transition(&frame, &mut t_vals);
+        t_vals
+    }
+
+    /// `Σ α_k·t_k(x)·E(x)/(x^T − 1) + Σ β_b·(c_b(x) − v_b)/(x − ω^{row_b})`,
+    /// with `E` the product over exempted rows.
+    fn evaluate(&self, x: F, current: &[F], next: &[F], periodic: &[F]) -> F {
+        let t_vals = self.transitions(current, next, periodic);
+        let transition: F = t_vals.iter().zip(&self.alphas).map(|(t, a)| *t * a).sum();
+        let exempt: F = self.exempt_points.iter().map(|p| x - p).product();
+        let z_inv = (x.pow([self.t as u64]) - F::one())
+            .inverse()
+            .expect("LDE coset avoids H_T");
+
+        let boundary: F = self
+            .boundaries
+            .iter()
+            .zip(&self.betas)
+            .map(|((b, point), beta)| {
+                *beta * (current[b.column] - b.value) * (x - point).inverse().expect("x ∉ H_T")
+            })
+            .sum();
+
+        transition * exempt * z_inv + boundary
+    }
+}
+
+/// Prove that `main` (column-major) satisfies `air`.
+pub fn prove_air<A: Air>(air: &A, main: Vec<Vec<F>>, opts: &AirProofOptions) -> AirProof {
+    prove_air_hooked(air, main, opts, &mut ())
+}
+
+pub(crate) fn prove_air_hooked<A: Air>(
+    air: &A,
+    main: Vec<Vec<F>>,
+    opts: &AirProofOptions,
+    hook: &mut impl ProverHook,
+) -> AirProof {
+    let t = air.trace_length();
+    assert!(t >= 2 && t.is_power_of_two(), "trace length must be a power of two");
+    assert_eq!(main.len(), air.trace_width(), "trace width mismatch");
+    assert!(main.iter().all(|c| c.len() == t), "trace length mismatch");
+    if let Err(e) = opts.validate(air) {
+        panic!("{e}");
+    }
+    let n = t * opts.blowup;
+    hook.enter(ProverPhase::Trace);
+
+    let mut tr = air_transcript(air, opts);
+
+    let main_rows = lde_rows(&main, t, n);
+    let (main_tree, main_root) = commit_rows(&main_rows, segment_cfg(n, 0));
+    tr.absorb_field(main_root);
+    let challenges = draw_aux_challenges(air, &mut tr);
+
+    let mut segments = vec![(main_rows, main_tree)];
+    let mut segment_roots = vec![main_root];
+    if air.aux_width() > 0 {
+        let aux = air.build_aux_trace(&main, &challenges);
+        assert_eq!(aux.len(), air.aux_width(), "aux trace width mismatch");
+        assert!(aux.iter().all(|c| c.len() == t), "aux trace length mismatch");
+
+        let rows = lde_rows(&aux, t, n);
+        let (tree, root) = commit_rows(&rows, segment_cfg(n, 1));
+        tr.absorb_field(root);
+        segments.push((rows, tree));
+        segment_roots.push(root);
+    }
+
+    let composer = Composer::new(air, challenges, &mut tr);
+    let fri_params = draw_fri_params(air, opts, &mut tr);
+    let trace_root = tr.challenge(ds::AIR_TRACE_ROOT);
+
+    let row_at = |j: usize| -> Vec<F> {
+        segments.iter().flat_map(|(rows, _)| rows[j].iter().copied()).collect()
+    };
+
+    let periodic = composer.periodic_lde(n);
+    #[cfg(debug_assertions)]
+    check_transition_degrees(&composer, n, opts.blowup, &row_at, &periodic);
+    let omega_n = radix2_generator(n);
+    let mut x = F::GENERATOR;
+    let mut f0 = Vec::with_capacity(n);
+    for (j, periodic_j) in periodic.iter().enumerate() {
+        f0.push(composer.evaluate(x, &row_at(j), &row_at((j + opts.blowup) % n), periodic_j));
+        x *= omega_n;
+    }
+
+    let fri = prove_hooked(f0, FriDomain::new_radix2(n), &fri_params, Some(trace_root), hook)
+        .unwrap_or_else(|e| panic!("{e}"));
+
+    let open_row = |rows: &[Vec<F>], tree: &MerkleTreeChannel, j: usize| tree.open_with_values(j, rows[j].clone()).expect("query row inside the trace");
+    let trace_queries = fri
+        .queries
+        .iter()
+        .map(|qp| {
+            let j = qp.per_layer_refs[0].i as usize;
+            TraceQuery {
+                segments: segments
+                    .iter()
+                    .map(|(rows, tree)| SegmentOpening {
+                        current: open_row(rows, tree, j),
+                        next: open_row(rows, tree, (j + opts.blowup) % n),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    AirProof {
+        segment_roots,
+        trace_queries,
+        fri,
+    }
+}
+
+/// Panics if a transition constraint, evaluated on the LDE coset, has a
+/// higher degree than `transition_degrees` declares: the composition would
+/// then outgrow the FRI bound and honest proofs would fail to verify.
+#[cfg(debug_assertions)]
+fn check_transition_degrees<A: Air>(
+    composer: &Composer<'_, A>,
+    n: usize,
+    blowup: usize,
+    row_at: &impl Fn(usize) -> Vec<F>,
+    periodic: &[Vec<F>],
+) {
+    let evals: Vec<Vec<F>> = (0..n)
+        .map(|j| composer.transitions(&row_at(j), &row_at((j + blowup) % n), &periodic[j]))
+        .collect();
+    let dom_n = GeneralEvaluationDomain::<F>::new(n).expect("power-of-two LDE size");
+    let g_inv = F::GENERATOR.inverse().expect("g ≠ 0");
+    for (k, d) in composer.air.transition_degrees().into_iter().enumerate() {
+        let mut coeffs = dom_n.ifft(&evals.iter().map(|row| row[k]).collect::<Vec<_>>());
+        let mut shift = F::one();
+        for c in coeffs.iter_mut() {
+            *c *= shift;
+            shift *= g_inv;
+        }
+        let degree = coeffs.iter().rposition(|c| !c.is_zero()).unwrap_or(0);
+        assert!(
+            degree <= d * (composer.t - 1),
+            "transition constraint {k} has degree {degree} over the trace, declared {d}"
+        );
+    }
+}
+
+fn check_row(cfg: &MerkleChannelCfg, root: F, row: &TraceRowOpening, index: u64, width: usize) -> bool {
+    row.values.len() == width
+        && row.index() == index
+        && MerkleTreeChannel::verify_value_opening(cfg, root, row, &[0u8; 32])
+}
+
+pub fn verify_air<A: Air>(air: &A, opts: &AirProofOptions, proof: &AirProof) -> bool {
+    let t = air.trace_length();
+    if t < 2 || !t.is_power_of_two() || opts.validate(air).is_err() {
+        return false;
+    }
+    let n = t * opts.blowup;
+
+    let widths: Vec<usize> = core::iter::once(air.trace_width())
+        .chain((air.aux_width() > 0).then_some(air.aux_width()))
+        .collect();
+    if proof.segment_roots.len() != widths.len() {
+        return false;
+    }
+
+    let mut tr = air_transcript(air, opts);
+    tr.absorb_field(proof.segment_roots[0]);
+    let challenges = draw_aux_challenges(air, &mut tr);
+    if let Some(aux_root) = proof.segment_roots.get(1) {
+        tr.absorb_field(*aux_root);
+    }
+
+    let composer = Composer::new(air, challenges, &mut tr);
+    let fri_params = draw_fri_params(air, opts, &mut tr);
+    let trace_root = tr.challenge(ds::AIR_TRACE_ROOT);
+
+    if proof.fri.n0 != n || proof.fri.trace_root != Some(trace_root) {
+        return false;
+    }
+    if !VerifierKey::for_fri(&fri_params, n).is_ok_and(|key| deep_fri_verify(&key, &proof.fri)) {
+        return false;
+    }
+    let Some(f0_values) = deep_fri_layer0_values(&fri_params, &proof.fri) else {
+        return false;
+    };
+    if proof.trace_queries.len() != f0_values.len() {
+        return false;
+    }
+
+    let omega_n = radix2_generator(n);
+    for ((j, f0), tq) in f0_values.into_iter().zip(&proof.trace_queries) {
+        if tq.segments.len() != widths.len() {
+            return false;
+        }
+        let next_j = (j + opts.blowup as u64) % n as u64;
+
+        let mut current = Vec::new();
+        let mut next = Vec::new();
+        for (s, seg) in tq.segments.iter().enumerate() {
+            let cfg = segment_cfg(n, s);
+            let root = proof.segment_roots[s];
+            if !check_row(&cfg, root, &seg.current, j, widths[s])
+                || !check_row(&cfg, root, &seg.next, next_j, widths[s])
+            {
+                return false;
+            }
+            current.extend_from_slice(&seg.current.values);
+            next.extend_from_slice(&seg.next.values);
+        }
+
+        let x = F::GENERATOR * omega_n.pow([j]);
+        if composer.evaluate(x, &current, &next, &composer.periodic_at(x)) != f0 {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// First row of the unextended trace violating a constraint, if any.
+///
+/// Builds the aux segment from `challenges`; meant for debugging an AIR
+/// before handing it to [`prove_air`].
+pub fn first_unsatisfied_row<A: Air>(air: &A, main: &[Vec<F>], challenges: &[F]) -> Option<usize> {
+    let t = air.trace_length();
+    let aux = air.build_aux_trace(main, challenges);
+    let row = |i: usize| -> Vec<F> {
+        main.iter().chain(aux.iter()).map(|col| col[i]).collect()
+    };
+    let periodic = air.periodic_columns();
+
+    let mut out = vec![F::zero(); air.num_transition_constraints()];
+    for i in 0..t - air.transition_exemptions() {
+        let p: Vec<F> = periodic.iter().map(|col| col[i % col.len()]).collect();
+        let (current, next) = (row(i), row((i + 1) % t));
+        let frame = EvaluationFrame {
+            current: &current,
+            next: &next,
+            periodic: &p,
+            challenges,
+        };
+        air.evaluate_transition(&frame, &mut out);
+        if out.iter().any(|v| !v.is_zero()) {
+            return Some(i);
+        }
+    }
+
+    air.boundary_constraints(challenges)
+        .into_iter()
+        .find(|b| row(b.row)[b.column] != b.value)
+        .map(|b| b.row)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `x_{i+1} = x_i + 1`, `x_0 = start`.
+    struct CounterAir {
+        len: usize,
+        start: F,
+    }
+
+    impl Air for CounterAir {
+        fn trace_width(&self) -> usize {
+            1
+        }
+        fn trace_length(&self) -> usize {
+            self.len
+        }
+        fn num_transition_constraints(&self) -> usize {
+            1
+        }
+        fn transition_degree(&self) -> usize {
+            1
+        }
+        fn evaluate_transition(&self, frame: &EvaluationFrame<'_>, out: &mut [F]) {
+            out[0] = frame.next[0] - frame.current[0] - F::one();
+        }
+        fn boundary_constraints(&self, _challenges: &[F]) -> Vec<BoundaryConstraint> {
+            vec![
+                BoundaryConstraint::new(0, 0, self.start),
+                BoundaryConstraint::new(0, self.len - 1, self.start + F::from((self.len - 1) as u64)),
+            ]
+        }
+        fn public_inputs(&self) -> Vec<F> {
+            vec![self.start]
+        }
+    }
+
+    fn counter_trace(len: usize, start: F) -> Vec<Vec<F>> {
+        vec![(0..len).map(|i| start + F::from(i as u64)).collect()]
+    }
+
+    /// `x_{i+1} = x_i³` and `y_{i+1} = y_i + x_i`, degrees 3 and 1.
+    struct CubeAir {
+        len: usize,
+        exemptions: usize,
+    }
+
+    impl Air for CubeAir {
+        fn trace_width(&self) -> usize {
+            2
+        }
+        fn trace_length(&self) -> usize {
+            self.len
+        }
+        fn num_transition_constraints(&self) -> usize {
+            2
+        }
+        fn transition_degree(&self) -> usize {
+            3
+        }
+        fn transition_degrees(&self) -> Vec<usize> {
+            vec![3, 1]
+        }
+        fn transition_exemptions(&self) -> usize {
+            self.exemptions
+        }
+        fn evaluate_transition(&self, frame: &EvaluationFrame<'_>, out: &mut [F]) {
+            let (cur, next) = (frame.current, frame.next);
+            out[0] = next[0] - cur[0] * cur[0] * cur[0];
+            out[1] = next[1] - cur[1] - cur[0];
+        }
+        fn boundary_constraints(&self, _challenges: &[F]) -> Vec<BoundaryConstraint> {
+            vec![
+                BoundaryConstraint::new(0, 0, F::from(2u64)),
+                BoundaryConstraint::new(1, 0, F::zero()),
+            ]
+        }
+    }
+
+    fn cube_trace(len: usize) -> Vec<Vec<F>> {
+        let (mut x, mut y) = (F::from(2u64), F::zero());
+        let mut cols = vec![Vec::with_capacity(len), Vec::with_capacity(len)];
+        for _ in 0..len {
+            cols[0].push(x);
+            cols[1].push(y);
+            y += x;
+            x = x * x * x;
+        }
+        cols
+    }
+
+    #[test]
+    fn blowup_follows_max_constraint_degree() {
+        let counter = CounterAir { len: 64, start: F::zero() };
+        assert_eq!(composition_degree_bound(&counter), 63);
+        assert_eq!(min_blowup(&counter), 2);
+
+        // Degree 3: 3·63 + 1 − 64 + 1 = 127 coefficients, two trace lengths.
+        let cube = CubeAir { len: 64, exemptions: 1 };
+        assert_eq!(composition_degree_bound(&cube), 127);
+        assert_eq!(min_blowup(&cube), 4);
+        assert_eq!(AirProofOptions::new(4, 16).validate(&cube), Ok(()));
+        assert_eq!(
+            AirProofOptions::new(2, 16).validate(&cube),
+            Err(AirConfigError::BlowupTooSmall { blowup: 2, required: 4 })
+        );
+        assert_eq!(
+            AirProofOptions::new(6, 16).validate(&cube),
+            Err(AirConfigError::BlowupNotPowerOfTwo { blowup: 6 })
+        );
+
+        // Exempted rows raise the quotient degree too.
+        let exempt = CubeAir { len: 64, exemptions: 2 };
+        assert_eq!(composition_degree_bound(&exempt), 128);
+        assert_eq!(min_blowup(&exempt), 4);
+        let exempt = CubeAir { len: 64, exemptions: 3 };
+        assert_eq!(min_blowup(&exempt), 8);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "transition constraint 0 has degree")]
+    fn under_declared_degree_is_caught() {
+        struct Liar(CubeAir);
+        impl Air for Liar {
+            fn trace_width(&self) -> usize {
+                2
+            }
+            fn trace_length(&self) -> usize {
+                self.0.len
+            }
+            fn num_transition_constraints(&self) -> usize {
+                2
+            }
+            fn transition_degree(&self) -> usize {
+                1
+            }
+            fn evaluate_transition(&self, frame: &EvaluationFrame<'_>, out: &mut [F]) {
+                self.0.evaluate_transition(frame, out)
+            }
+            fn boundary_constraints(&self, challenges: &[F]) -> Vec<BoundaryConstraint> {
+                self.0.boundary_constraints(challenges)
+            }
+        }
+
+        let air = Liar(CubeAir { len: 16, exemptions: 1 });
+        prove_air(&air, cube_trace(16), &AirProofOptions::new(4, 4));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn cubic_roundtrip() {
+        let air = CubeAir { len: 64, exemptions: 1 };
+        let opts = AirProofOptions::new(min_blowup(&air), 16);
+        assert_eq!(first_unsatisfied_row(&air, &cube_trace(64), &[]), None);
+
+        let proof = prove_air(&air, cube_trace(64), &opts);
+        assert!(verify_air(&air, &opts, &proof));
+        assert!(!verify_air(&air, &AirProofOptions::new(2, 16), &proof));
+
+        let mut bad = cube_trace(64);
+        bad[0][10] += F::one();
+        assert!(!verify_air(&air, &opts, &prove_air(&air, bad, &opts)));
+    }
+
+    #[test]
+    fn fri_schedule_folds_exactly() {
+        for fold in [2usize, 4, 8, 64, 128, 1 << 11] {
+            assert_eq!(fri_schedule(fold).iter().product::<usize>(), fold);
+        }
+        assert!(fri_schedule(1).is_empty());
+    }
+
+    #[test]
+    fn first_unsatisfied_row_finds_bad_row() {
+        let air = CounterAir { len: 16, start: F::from(3u64) };
+        let mut trace = counter_trace(16, air.start);
+        assert_eq!(first_unsatisfied_row(&air, &trace, &[]), None);
+
+        trace[0][9] += F::one();
+        assert_eq!(first_unsatisfied_row(&air, &trace, &[]), Some(8));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn counter_roundtrip_and_rejects_wrong_trace() {
+        let air = CounterAir { len: 64, start: F::from(5u64) };
+        let opts = AirProofOptions::new(4, 16);
+
+        let mut proof = prove_air(&air, counter_trace(64, air.start), &opts);
+        assert!(verify_air(&air, &opts, &proof));
+
+        proof.trace_queries[0].segments[0].next.values[0] += F::one();
+        assert!(!verify_air(&air, &opts, &proof));
+        proof.trace_queries[0].segments[0].next.values[0] -= F::one();
+
+        // A different statement must not accept the same proof.
+        let other = CounterAir { len: 64, start: F::from(6u64) };
+        assert!(!verify_air(&other, &opts, &proof));
+
+        let mut bad = counter_trace(64, air.start);
+        bad[0][20] += F::one();
+        let proof = prove_air(&air, bad, &opts);
+        assert!(!verify_air(&air, &opts, &proof));
+    }
+
+    #[cfg(feature = "mem_report")]
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn memory_report_starts_with_the_trace() {
+        use crate::memory::prove_air_with_memory_report;
+
+        let air = CounterAir { len: 64, start: F::from(5u64) };
+        let opts = AirProofOptions::new(4, 16);
+        let (proof, report) = prove_air_with_memory_report(&air, counter_trace(64, air.start), &opts);
+        assert!(verify_air(&air, &opts, &proof));
+        let phases: Vec<_> = report.phases.iter().map(|p| p.phase).collect();
+        assert_eq!(
+            phases,
+            [ProverPhase::Trace, ProverPhase::Layers, ProverPhase::Merkle, ProverPhase::Queries]
+        );
+        // The trace LDE is still held while FRI runs.
+        assert!(report.phase(ProverPhase::Layers).unwrap().entry_bytes >= 64 * 4 * core::mem::size_of::<F>());
+    }
+}
@@ -0,0 +1,250 @@
+//! Many DEEP-ALI codewords through one FRI run.
+//!
+//! Each instance's `f0` over `H_n` is committed on its own, the roots go
+//! into a shared transcript, and the codewords are folded into
+//! `f = Σ_k γ_k · f0_k` with transcript coefficients. Only `f` goes through
+//! DEEP-FRI, with the batch transcript bound in as the trace root; at each
+//! FRI query the verifier opens every instance at the layer-0 index and
+//! recomputes `f` there. A far instance makes `f` far except with
+//! probability about `1/|F|` over the `γ_k`, so one FRI run covers all of
+//! them and the per-instance cost drops to one opening per query.
+
+use alloc::{vec, vec::Vec};
+use ark_ff::{PrimeField, Zero};
+use ark_goldilocks::Goldilocks as F;
+
+use merkle::{ArityPolicy, MerkleChannelCfg, MerkleTreeChannel, MerkleValueOpening};
+use transcript::{default_params as transcript_params, Transcript};
+
+use crate::fri::{
+    deep_fri_layer0_values, deep_fri_prove_with_trace_root, deep_fri_verify, DeepFriParams,
+    DeepFriProof, FriDomain, VerifierKey,
+};
+use crate::params::PublicParams;
+
+mod ds {
+    pub const BATCH_INIT: &[u8] = b"BATCH/FS";
+    pub const BATCH_GAMMA: &[u8] = b"BATCH/gamma";
+    pub const BATCH_FRI_SEED: &[u8] = b"BATCH/fri-seed";
+    pub const BATCH_TRACE_ROOT: &[u8] = b"BATCH/trace-root";
+}
+
+/// Merkle tree labels for the instances; FRI layers use `0..L`, AIR
+/// segments `1 << 32 ..` and the committed DEEP-ALI trace `3 << 32`.
+const INSTANCE_TREE_LABEL: u64 = 2 << 32;
+
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct BatchProof {
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub instance_roots: Vec<F>,
+    /// `openings[q][k]`: instance `k` at FRI query `q`'s layer-0 index.
+    pub openings: Vec<Vec<MerkleValueOpening>>,
+    pub fri: DeepFriProof,
+}
+
+fn instance_cfg(n: usize, k: usize) -> MerkleChannelCfg {
+    MerkleChannelCfg::for_leaves(n, ArityPolicy::UpTo(4), INSTANCE_TREE_LABEL + k as u64)
+}
+
+fn commit_instance(f0: &[F], cfg: MerkleChannelCfg) -> (MerkleTreeChannel, F) {
+    let mut tree = MerkleTreeChannel::new(cfg, [0u8; 32]);
+    for v in f0 {
+        tree.push_leaf(&[*v]);
+    }
+    let root = tree.finalize().expect("instance fits its Merkle config");
+    (tree, root)
+}
+
+/// Transcript over the parameters and instance roots; returns the batching
+/// coefficients, the FRI parameters with a transcript-derived `seed_z`, and
+/// the trace root to bind into the FRI proof.
+fn batch_transcript(params: &DeepFriParams, n: usize, roots: &[F]) -> (Vec<F>, DeepFriParams, F) {
+    let mut tr = Transcript::new(ds::BATCH_INIT, transcript_params());
+    tr.absorb_bytes(&PublicParams::for_fri(params, n).digest());
+    tr.absorb_field(F::from(params.seed_z));
+    tr.absorb_field(F::from(roots.len() as u64));
+    for root in roots {
+        tr.absorb_field(*root);
+    }
+
+    let gammas = tr.challenge_many(ds::BATCH_GAMMA, roots.len());
+    let fri_params = DeepFriParams {
+        seed_z: tr.challenge(ds::BATCH_FRI_SEED).into_bigint().0[0],
+        ..params.clone()
+    };
+    (gammas, fri_params, tr.challenge(ds::BATCH_TRACE_ROOT))
+}
+
+/// Prove that every `instances[k]` is a low-degree codeword over `H_n`.
+///
+/// All instances must have the same power-of-two length `n`. `params.seed_z`
+/// only seeds the batch transcript; FRI runs with a seed derived from it
+/// after the instance roots.
+pub fn prove_many(instances: &[Vec<F>], params: &DeepFriParams) -> BatchProof {
+    assert!(!instances.is_empty(), "empty batch");
+    let n = instances[0].len();
+    assert!(
+        n.is_power_of_two(),
+        "instance length must be a power of two"
+    );
+    assert!(
+        instances.iter().all(|f| f.len() == n),
+        "instance length mismatch"
+    );
+
+    let (trees, instance_roots): (Vec<_>, Vec<_>) = instances
+        .iter()
+        .enumerate()
+        .map(|(k, f0)| commit_instance(f0, instance_cfg(n, k)))
+        .unzip();
+    let (gammas, fri_params, trace_root) = batch_transcript(params, n, &instance_roots);
+
+    let mut f = vec![F::zero(); n];
+    for (f0, gamma) in instances.iter().zip(&gammas) {
+        for (acc, v) in f.iter_mut().zip(f0) {
+            *acc += *gamma * v;
+        }
+    }
+
+    let fri =
+        deep_fri_prove_with_trace_root(f, FriDomain::new_radix2(n), &fri_params, Some(trace_root));
+
+    let openings = fri
+        .queries
+        .iter()
+        .map(|qp| {
+            let j = qp.per_layer_refs[0].i as usize;
+            instances
+                .iter()
+                .zip(&trees)
+                .map(|(f0, tree)| tree.open_with_values(j, vec![f0[j]]).expect("query row inside the instance"))
+                .collect()
+        })
+        .collect();
+
+    BatchProof {
+        instance_roots,
+        openings,
+        fri,
+    }
+}
+
+/// Verify a [`prove_many`] proof. Callers holding their own instance
+/// commitments should compare them against `proof.instance_roots`.
+pub fn verify_many(params: &DeepFriParams, proof: &BatchProof) -> bool {
+    let n = proof.fri.n0;
+    if proof.instance_roots.is_empty() || !n.is_power_of_two() {
+        return false;
+    }
+
+    let (gammas, fri_params, trace_root) = batch_transcript(params, n, &proof.instance_roots);
+    if proof.fri.trace_root != Some(trace_root)
+        || !VerifierKey::for_fri(&fri_params, n).is_ok_and(|key| deep_fri_verify(&key, &proof.fri))
+    {
+        return false;
+    }
+    let Some(f0_values) = deep_fri_layer0_values(&fri_params, &proof.fri) else {
+        return false;
+    };
+    if proof.openings.len() != f0_values.len() {
+        return false;
+    }
+
+    let cfgs: Vec<MerkleChannelCfg> = (0..gammas.len()).map(|k| instance_cfg(n, k)).collect();
+    for ((j, f), query) in f0_values.into_iter().zip(&proof.openings) {
+        if query.len() != gammas.len() {
+            return false;
+        }
+
+        let mut combined = F::zero();
+        for (k, io) in query.iter().enumerate() {
+            if io.index() != j
+                || io.values.len() != 1
+                || !MerkleTreeChannel::verify_value_opening(
+                    &cfgs[k],
+                    proof.instance_roots[k],
+                    io,
+                    &[0u8; 32],
+                )
+            {
+                return false;
+            }
+            combined += gammas[k] * io.values[0];
+        }
+        if combined != f {
+            return false;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fri::{FoldCheck, FoldingMode, IndexChaining, QuerySampling, TranscriptHash};
+    use ark_ff::UniformRand;
+    use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const N: usize = 256;
+
+    fn params() -> DeepFriParams {
+        DeepFriParams {
+            schedule: vec![4, 4, 4],
+            r: 16,
+            seed_z: 0xba7c,
+            folding_mode: FoldingMode::CosetInterpolation,
+            fold_check: FoldCheck::RecomputeFromCoset,
+            grinding_bits: 0,
+            query_sampling: QuerySampling::default(),
+            index_chaining: IndexChaining::default(),
+            transcript: TranscriptHash::default(),
+        }
+    }
+
+    /// Codeword of a random polynomial of degree `< N / 4`.
+    fn codeword(rng: &mut StdRng) -> Vec<F> {
+        let coeffs: Vec<F> = (0..N / 4).map(|_| F::rand(rng)).collect();
+        GeneralEvaluationDomain::<F>::new(N).unwrap().fft(&coeffs)
+    }
+
+    #[test]
+    fn gammas_depend_on_every_root() {
+        let roots = [F::from(1u64), F::from(2u64), F::from(3u64)];
+        let (g, fp, tr_root) = batch_transcript(&params(), N, &roots);
+
+        let mut other = roots;
+        other[2] += F::from(1u64);
+        let (g2, fp2, tr_root2) = batch_transcript(&params(), N, &other);
+        assert_ne!(g, g2);
+        assert_ne!(fp.seed_z, fp2.seed_z);
+        assert_ne!(tr_root, tr_root2);
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn prove_verify_many_roundtrip() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let instances: Vec<Vec<F>> = (0..3).map(|_| codeword(&mut rng)).collect();
+        let params = params();
+
+        let proof = prove_many(&instances, &params);
+        assert!(verify_many(&params, &proof));
+
+        // Swapped instance commitment.
+        let mut bad = prove_many(&instances, &params);
+        bad.instance_roots.swap(0, 1);
+        assert!(!verify_many(&params, &bad));
+
+        // Opening value that does not match its leaf.
+        let mut bad = prove_many(&instances, &params);
+        bad.openings[0][1].values[0] += F::from(1u64);
+        assert!(!verify_many(&params, &bad));
+
+        // One instance far from low degree.
+        let mut far = instances.clone();
+        far[2] = (0..N).map(|_| F::rand(&mut rng)).collect();
+        assert!(!verify_many(&params, &prove_many(&far, &params)));
+    }
+}
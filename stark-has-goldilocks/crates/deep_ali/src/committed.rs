@@ -0,0 +1,317 @@
+//! DEEP-ALI over a committed trace.
+//!
+//! [`crate::deep_ali_merge_evals`] turns the columns `(a, s, e, t)` over
+//! `H_n` into a codeword, but nothing ties the vector handed to DEEP-FRI
+//! back to a commitment of those columns. Here the rows `(a_j, s_j, e_j,
+//! t_j)` are committed first, the DEEP point `z` is drawn after the trace
+//! root, and the prover sends `c* = Φ̃(z) / Z_H(z)` for `Φ = a·s + e − t`.
+//! The FRI codeword is the DEEP quotient
+//!
+//! ```text
+//! f0(ω^j) = (Φ(ω^j) − Z_H(z)·c*) / (ω^j − z),
+//! ```
+//!
+//! which is low degree exactly when `c*` is honest. At every FRI query the
+//! verifier opens the trace row at the layer-0 index and recomputes `f0`
+//! there from the row, `z` and `c*`, so the tested codeword is the one the
+//! trace commitment fixes.
+//!
+//! Fp³ arithmetic in this crate is coordinate-wise and the merge keeps the
+//! `a0` coordinate, so only `z.a0` enters the quotient.
+
+use alloc::vec::Vec;
+use ark_ff::{Field, PrimeField};
+use ark_goldilocks::Goldilocks as F;
+
+use merkle::{ArityPolicy, MerkleChannelCfg, MerkleTreeChannel, MerkleValueOpening};
+use transcript::{default_params as transcript_params, Transcript};
+
+use crate::deep_tower::Fp3;
+use crate::fri::{
+    deep_fri_layer0_values, deep_fri_prove_over, deep_fri_verify, radix2_generator,
+    DeepFriParams, DeepFriProof, VerifierKey,
+};
+use crate::params::PublicParams;
+use crate::{sample_z_beta, zh_at, DomainH};
+
+mod ds {
+    pub const COMMITTED_INIT: &[u8] = b"DEEP-ALI/committed";
+    pub const COMMITTED_C_STAR: &[u8] = b"DEEP-ALI/committed/c-star";
+    pub const COMMITTED_FRI_SEED: &[u8] = b"DEEP-ALI/committed/fri-seed";
+    pub const COMMITTED_TRACE_ROOT: &[u8] = b"DEEP-ALI/committed/trace-root";
+}
+
+/// Merkle tree label for the trace; FRI layers use `0..L`, AIR segments
+/// `1 << 32 ..` and batch instances `2 << 32 ..`.
+const TRACE_TREE_LABEL: u64 = 3 << 32;
+
+/// Columns per trace row: `(a, s, e, t)`.
+pub const TRACE_WIDTH: usize = 4;
+
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct CommittedProof {
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub trace_root: F,
+    /// `Φ̃(z) / Z_H(z)`.
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub c_star: F,
+    /// `rows[q]`: the trace row at FRI query `q`'s layer-0 index.
+    pub rows: Vec<MerkleValueOpening>,
+    pub fri: DeepFriProof,
+}
+
+fn trace_cfg(n: usize) -> MerkleChannelCfg {
+    MerkleChannelCfg::for_leaves(n, ArityPolicy::UpTo(4), TRACE_TREE_LABEL)
+}
+
+fn commit_trace(
+    a_eval: &[F],
+    s_eval: &[F],
+    e_eval: &[F],
+    t_eval: &[F],
+) -> (MerkleTreeChannel, F) {
+    let mut tree = MerkleTreeChannel::new(trace_cfg(a_eval.len()), [0u8; 32]);
+    for j in 0..a_eval.len() {
+        tree.push_leaf(&[a_eval[j], s_eval[j], e_eval[j], t_eval[j]]);
+    }
+    let root = tree.finalize().expect("trace fits its Merkle config");
+    (tree, root)
+}
+
+fn open_rows(
+    tree: &MerkleTreeChannel,
+    cols: [&[F]; TRACE_WIDTH],
+    fri: &DeepFriProof,
+) -> Vec<MerkleValueOpening> {
+    fri.queries
+        .iter()
+        .map(|qp| {
+            let j = qp.per_layer_refs[0].i as usize;
+            tree.open_with_values(j, cols.iter().map(|c| c[j]).collect())
+                .expect("query row inside the trace")
+        })
+        .collect()
+}
+
+/// `f0` at `x` from the trace row there.
+pub fn deep_quotient_at(row: &[F], x: F, z_fp3: Fp3, c_star: F, n: usize) -> F {
+    let z = z_fp3.a0;
+    let phi = row[0] * row[1] + row[2] - row[3];
+    (phi - zh_at(z, n) * c_star) * (x - z).inverse().unwrap()
+}
+
+/// First half of the transcript: everything up to the DEEP point.
+fn draw_z(params: &DeepFriParams, n: usize, trace_root: F) -> (Transcript, Fp3) {
+    let mut tr = Transcript::new(ds::COMMITTED_INIT, transcript_params());
+    tr.absorb_bytes(&PublicParams::for_fri(params, n).digest());
+    tr.absorb_field(F::from(params.seed_z));
+    tr.absorb_field(F::from(n as u64));
+    tr.absorb_field(trace_root);
+    let (z, _beta) = sample_z_beta(&mut tr, n);
+    (tr, z)
+}
+
+/// Second half: bind `c*`, then derive the FRI parameters and the trace
+/// root to bind into the FRI proof.
+fn bind_c_star(mut tr: Transcript, params: &DeepFriParams, c_star: F) -> (DeepFriParams, F) {
+    tr.absorb_bytes(ds::COMMITTED_C_STAR);
+    tr.absorb_field(c_star);
+    let fri_params = DeepFriParams {
+        seed_z: tr.challenge(ds::COMMITTED_FRI_SEED).into_bigint().0[0],
+        ..params.clone()
+    };
+    (fri_params, tr.challenge(ds::COMMITTED_TRACE_ROOT))
+}
+
+/// Prove that the committed columns give a low-degree DEEP quotient.
+///
+/// All columns must have the same power-of-two length `n`. `params.seed_z`
+/// only seeds the transcript; FRI runs with a seed derived after the trace
+/// root and `c*`.
+pub fn prove_committed(
+    a_eval: &[F],
+    s_eval: &[F],
+    e_eval: &[F],
+    t_eval: &[F],
+    params: &DeepFriParams,
+) -> CommittedProof {
+    let n = a_eval.len();
+    assert!(n > 1 && n.is_power_of_two(), "Domain size must be power of two");
+    assert!(
+        [s_eval, e_eval, t_eval].iter().all(|c| c.len() == n),
+        "column length mismatch"
+    );
+
+    let (tree, trace_root) = commit_trace(a_eval, s_eval, e_eval, t_eval);
+
+    let (tr, z_fp3) = draw_z(params, n, trace_root);
+    let z = z_fp3.a0;
+    let dom = DomainH::new_radix2(n);
+    let phi: Vec<F> = (0..n)
+        .map(|j| a_eval[j] * s_eval[j] + e_eval[j] - t_eval[j])
+        .collect();
+
+    // c* = (1/n) Σ_j Φ(ω^j) · ω^j / (z − ω^j), the barycentric Φ̃(z) / Z_H(z)
+    let n_inv = F::from(n as u64).inverse().unwrap();
+    let c_star = n_inv
+        * phi
+            .iter()
+            .zip(&dom.omega_pows)
+            .map(|(&p, &w)| p * w * (z - w).inverse().unwrap())
+            .sum::<F>();
+
+    let phi_z = zh_at(z, n) * c_star;
+    let f0: Vec<F> = phi
+        .iter()
+        .zip(&dom.omega_pows)
+        .map(|(&p, &w)| (p - phi_z) * (w - z).inverse().unwrap())
+        .collect();
+
+    let (fri_params, fri_trace_root) = bind_c_star(tr, params, c_star);
+    let fri = deep_fri_prove_over(f0, &dom, &fri_params, Some(fri_trace_root));
+
+    let rows = open_rows(&tree, [a_eval, s_eval, e_eval, t_eval], &fri);
+
+    CommittedProof {
+        trace_root,
+        c_star,
+        rows,
+        fri,
+    }
+}
+
+/// Verify a [`prove_committed`] proof. Callers holding their own trace
+/// commitment should compare it against `proof.trace_root`.
+pub fn verify_committed(params: &DeepFriParams, proof: &CommittedProof) -> bool {
+    let n = proof.fri.n0;
+    if n < 2 || !n.is_power_of_two() {
+        return false;
+    }
+
+    let (tr, z_fp3) = draw_z(params, n, proof.trace_root);
+    let (fri_params, fri_trace_root) = bind_c_star(tr, params, proof.c_star);
+    if proof.fri.trace_root != Some(fri_trace_root)
+        || !VerifierKey::for_fri(&fri_params, n).is_ok_and(|key| deep_fri_verify(&key, &proof.fri))
+    {
+        return false;
+    }
+    let Some(f0_values) = deep_fri_layer0_values(&fri_params, &proof.fri) else {
+        return false;
+    };
+    if proof.rows.len() != f0_values.len() {
+        return false;
+    }
+
+    let cfg = trace_cfg(n);
+    let omega = radix2_generator(n);
+    for ((j, f0), row) in f0_values.into_iter().zip(&proof.rows) {
+        if row.index() != j
+            || row.values.len() != TRACE_WIDTH
+            || !MerkleTreeChannel::verify_value_opening(&cfg, proof.trace_root, row, &[0u8; 32])
+        {
+            return false;
+        }
+        let x = omega.pow([j]);
+        if deep_quotient_at(&row.values, x, z_fp3, proof.c_star, n) != f0 {
+            return false;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fri::{deep_fri_prove_with_trace_root, FoldCheck, FoldingMode, FriDomain, IndexChaining, QuerySampling, TranscriptHash};
+    use ark_ff::UniformRand;
+    use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const N: usize = 256;
+
+    fn params() -> DeepFriParams {
+        DeepFriParams {
+            schedule: vec![4, 4],
+            r: 16,
+            seed_z: 0xc0de,
+            folding_mode: FoldingMode::CosetInterpolation,
+            fold_check: FoldCheck::RecomputeFromCoset,
+            grinding_bits: 0,
+            query_sampling: QuerySampling::default(),
+            index_chaining: IndexChaining::default(),
+            transcript: TranscriptHash::default(),
+        }
+    }
+
+    /// Codeword of a random polynomial of degree `< d`.
+    fn column(rng: &mut StdRng, d: usize) -> Vec<F> {
+        let coeffs: Vec<F> = (0..d).map(|_| F::rand(rng)).collect();
+        GeneralEvaluationDomain::<F>::new(N).unwrap().fft(&coeffs)
+    }
+
+    /// Columns whose `Φ` has degree below the final-layer bound `N / 16`.
+    fn columns(rng: &mut StdRng) -> [Vec<F>; 4] {
+        [column(rng, 8), column(rng, 8), column(rng, 16), column(rng, 16)]
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn quotient_matches_prover_codeword() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let [a, s, e, t] = columns(&mut rng);
+        let proof = prove_committed(&a, &s, &e, &t, &params());
+
+        let (_, z) = draw_z(&params(), N, proof.trace_root);
+        let omega = DomainH::new_radix2(N).omega;
+        for (j, f0) in deep_fri_layer0_values(&params(), &proof.fri).unwrap() {
+            let k = j as usize;
+            let row = [a[k], s[k], e[k], t[k]];
+            assert_eq!(deep_quotient_at(&row, omega.pow([j]), z, proof.c_star, N), f0);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn prove_verify_committed_roundtrip() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let [a, s, e, t] = columns(&mut rng);
+        let params = params();
+
+        let proof = prove_committed(&a, &s, &e, &t, &params);
+        assert!(verify_committed(&params, &proof));
+
+        // Row value that does not match its leaf.
+        let mut bad = prove_committed(&a, &s, &e, &t, &params);
+        bad.rows[0].values[2] += F::from(1u64);
+        assert!(!verify_committed(&params, &bad));
+
+        // Different c*.
+        let mut bad = prove_committed(&a, &s, &e, &t, &params);
+        bad.c_star += F::from(1u64);
+        assert!(!verify_committed(&params, &bad));
+
+        // A valid FRI proof for a low-degree codeword the trace does not give.
+        let mut bad = prove_committed(&a, &s, &e, &t, &params);
+        let (tree, _) = commit_trace(&a, &s, &e, &t);
+        let (tr, z) = draw_z(&params, N, bad.trace_root);
+        let dom = DomainH::new_radix2(N);
+        let shift = column(&mut rng, 8);
+        let f0: Vec<F> = (0..N)
+            .map(|j| {
+                let row = [a[j], s[j], e[j], t[j]];
+                deep_quotient_at(&row, dom.omega_pows[j], z, bad.c_star, N) + shift[j]
+            })
+            .collect();
+        let (fri_params, fri_trace_root) = bind_c_star(tr, &params, bad.c_star);
+        bad.fri = deep_fri_prove_with_trace_root(
+            f0,
+            FriDomain::new_radix2(N),
+            &fri_params,
+            Some(fri_trace_root),
+        );
+        bad.rows = open_rows(&tree, [&a, &s, &e, &t], &bad.fri);
+        assert!(deep_fri_verify(&VerifierKey::for_fri(&fri_params, N).unwrap(), &bad.fri));
+        assert!(!verify_committed(&params, &bad));
+    }
+}
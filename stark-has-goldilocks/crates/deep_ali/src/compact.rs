@@ -0,0 +1,391 @@
+//! Deduplicated encoding of [`DeepFriProof`] query payloads.
+//!
+//! Every [`LayerOpenPayload`] repeats the layer's `f_0`, every final pair
+//! repeats `f_L[0]`, and queries that meet at a fold parent each carry its
+//! `(f, s)`. [`CompactFriProof`] keeps those once per layer and leaves each
+//! query only its own leaf values and indices:
+//!
+//! - a parent opened as some query's leaf in the next layer is read back
+//!   from that leaf, and the rest go into a per-layer table by index;
+//! - the final pair is the last layer's parent entry and the shared
+//!   constant, and the final index is the last parent index.
+//!
+//! [`compact_proof`] only succeeds when [`expand_proof`] gives back the
+//! proof bit for bit, so the verifier sees exactly what the prover sent.
+//! Both digest widths compact alike; the node type only changes what the
+//! roots and openings hold.
+//! Each layer's query openings are pooled into [`CompressedOpenings`], so
+//! siblings shared by several paths are sent once; coset leaves and
+//! re-randomized parents are carried unchanged.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec::Vec,
+};
+use ark_goldilocks::Goldilocks as F;
+
+use crate::fri::{
+    deep_fri_verify, layer_sizes_from_schedule, CosetLeafOpening, DeepFriParams, DeepFriProof,
+    FriLayerProofs, FriQueryPayload, LayerOpenPayload, LayerProof, LayerQueryRef, VerifierKey,
+};
+use crate::kat::proof_digest;
+use merkle::{CompressedOpenings, MerkleNode, MerkleValueOpening};
+use transcript::FsBinding;
+
+const FIELD_BYTES: usize = 8;
+const INDEX_BYTES: usize = 8;
+
+/// Values every query shares at one FRI layer.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompactLayer {
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub f_0: F,
+    /// Fold parents no query opens in the next layer, ascending.
+    pub parent_indices: Vec<u64>,
+    /// `(f_parent_b, s_parent_b)` for each of `parent_indices`.
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub parent_values: Vec<(F, F)>,
+}
+
+/// What is left of a [`FriQueryPayload`] once the shared values are gone.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct CompactQuery<N = F> {
+    /// Queried position per layer.
+    pub indices: Vec<u64>,
+    /// `(f_i, s_i, q_a0, q_a1, q_a2)` per layer.
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub leaves: Vec<[F; 5]>,
+    pub per_layer_cosets: Vec<Vec<CosetLeafOpening<N>>>,
+    pub per_layer_parents: Vec<MerkleValueOpening<N>>,
+}
+
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct CompactFriProof<N = F> {
+    pub proof_root: [u8; 32],
+    pub fs: FsBinding,
+    pub pow_nonce: u64,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub trace_root: Option<N>,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub roots: Vec<N>,
+    /// Per layer, the query openings of [`FriLayerProofs`].
+    pub layer_openings: Vec<CompressedOpenings<N>>,
+    pub layers: Vec<CompactLayer>,
+    /// `f_L[0]`, the second half of every final pair.
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub final_constant: F,
+    pub queries: Vec<CompactQuery<N>>,
+    pub n0: usize,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub omega0: F,
+}
+
+impl<N> CompactFriProof<N> {
+    /// Bytes of the shared and per-query payload values; the counterpart
+    /// of [`payload_bytes`].
+    pub fn payload_bytes(&self) -> usize {
+        let shared: usize = self
+            .layers
+            .iter()
+            .map(|l| FIELD_BYTES + l.parent_indices.len() * (INDEX_BYTES + 2 * FIELD_BYTES))
+            .sum();
+        let per_query: usize = self.queries.iter().map(|q| q.leaves.len() * 5 * FIELD_BYTES).sum();
+        shared + FIELD_BYTES + per_query
+    }
+}
+
+/// Bytes of the per-layer payloads and final pairs of `proof`, as counted
+/// by [`deep_fri_proof_size_bytes`](crate::fri::deep_fri_proof_size_bytes).
+pub fn payload_bytes<N>(proof: &DeepFriProof<N>) -> usize {
+    proof
+        .queries
+        .iter()
+        .map(|q| q.per_layer_payloads.len() * 8 * FIELD_BYTES + 2 * FIELD_BYTES)
+        .sum()
+}
+
+/// Deduplicate `proof`'s payloads, or `None` if the compact form would not
+/// expand back to exactly `proof`.
+pub fn compact_proof<N: MerkleNode>(params: &DeepFriParams, proof: &DeepFriProof<N>) -> Option<CompactFriProof<N>> {
+    let l = params.schedule.len();
+    let first = proof.queries.first()?;
+    if first.per_layer_payloads.len() != l {
+        return None;
+    }
+
+    let layers = (0..l)
+        .map(|ell| {
+            let opened_next: BTreeSet<u64> = if ell + 1 < l {
+                proof.queries.iter().filter_map(|q| Some(q.per_layer_refs.get(ell + 1)?.i)).collect()
+            } else {
+                BTreeSet::new()
+            };
+            let mut parents = BTreeMap::new();
+            for q in &proof.queries {
+                let (Some(rref), Some(pay)) = (q.per_layer_refs.get(ell), q.per_layer_payloads.get(ell)) else {
+                    continue;
+                };
+                if !opened_next.contains(&rref.parent_index) {
+                    parents.entry(rref.parent_index).or_insert((pay.f_parent_b, pay.s_parent_b));
+                }
+            }
+            CompactLayer {
+                f_0: first.per_layer_payloads[ell].f_0,
+                parent_indices: parents.keys().copied().collect(),
+                parent_values: parents.into_values().collect(),
+            }
+        })
+        .collect();
+
+    let queries = proof
+        .queries
+        .iter()
+        .map(|q| CompactQuery {
+            indices: q.per_layer_refs.iter().map(|r| r.i).collect(),
+            leaves: q
+                .per_layer_payloads
+                .iter()
+                .map(|p| [p.f_i, p.s_i, p.q_a0, p.q_a1, p.q_a2])
+                .collect(),
+            per_layer_cosets: q.per_layer_cosets.clone(),
+            per_layer_parents: q.per_layer_parents.clone(),
+        })
+        .collect();
+
+    let compact = CompactFriProof {
+        proof_root: proof.proof_root,
+        fs: proof.fs.clone(),
+        pow_nonce: proof.pow_nonce,
+        trace_root: proof.trace_root,
+        roots: proof.roots.clone(),
+        layer_openings: proof
+            .layer_proofs
+            .layers
+            .iter()
+            .map(|l| CompressedOpenings::compress(&l.openings))
+            .collect::<Option<_>>()?,
+        layers,
+        final_constant: first.final_pair.1,
+        queries,
+        n0: proof.n0,
+        omega0: proof.omega0,
+    };
+
+    let expanded = expand_proof(params, &compact)?;
+    (proof_digest(&expanded) == proof_digest(proof)).then_some(compact)
+}
+
+/// Rebuild the full proof, or `None` if `compact` is malformed for
+/// `params`.
+pub fn expand_proof<N: MerkleNode>(params: &DeepFriParams, compact: &CompactFriProof<N>) -> Option<DeepFriProof<N>> {
+    params.validate(compact.n0).ok()?;
+    let l = params.schedule.len();
+    let sizes = layer_sizes_from_schedule(compact.n0, &params.schedule);
+    if compact.layers.len() != l
+        || compact.layer_openings.len() != l
+        || compact
+            .queries
+            .iter()
+            .any(|q| q.indices.len() != l || q.leaves.len() != l)
+    {
+        return None;
+    }
+
+    // (f, s) of layer ℓ + 1 at each fold parent of layer ℓ.
+    let parents: Vec<BTreeMap<u64, (F, F)>> = (0..l)
+        .map(|ell| {
+            let layer = &compact.layers[ell];
+            if layer.parent_indices.len() != layer.parent_values.len() {
+                return None;
+            }
+            let mut known: BTreeMap<u64, (F, F)> =
+                layer.parent_indices.iter().copied().zip(layer.parent_values.iter().copied()).collect();
+            if ell + 1 < l {
+                for q in &compact.queries {
+                    known.insert(q.indices[ell + 1], (q.leaves[ell + 1][0], q.leaves[ell + 1][1]));
+                }
+            }
+            Some(known)
+        })
+        .collect::<Option<_>>()?;
+
+    let queries = compact
+        .queries
+        .iter()
+        .map(|q| {
+            let mut per_layer_refs = Vec::with_capacity(l);
+            let mut per_layer_payloads = Vec::with_capacity(l);
+            for (ell, (&i, leaf)) in q.indices.iter().zip(&q.leaves).enumerate() {
+                let m = params.schedule[ell];
+                let parent_index = i % (sizes[ell] / m) as u64;
+                let &(f_parent_b, s_parent_b) = parents[ell].get(&parent_index)?;
+                per_layer_refs.push(LayerQueryRef {
+                    i,
+                    child_pos: (i % m as u64) as usize,
+                    parent_index,
+                    parent_pos: 0,
+                });
+                per_layer_payloads.push(LayerOpenPayload {
+                    f_i: leaf[0],
+                    f_0: compact.layers[ell].f_0,
+                    s_i: leaf[1],
+                    q_a0: leaf[2],
+                    q_a1: leaf[3],
+                    q_a2: leaf[4],
+                    f_parent_b,
+                    s_parent_b,
+                });
+            }
+
+            let final_index = per_layer_refs.last()?.parent_index;
+            let final_f = parents[l - 1].get(&final_index)?.0;
+            Some(FriQueryPayload {
+                per_layer_refs,
+                per_layer_payloads,
+                final_index,
+                final_pair: (final_f, compact.final_constant),
+                per_layer_cosets: q.per_layer_cosets.clone(),
+                per_layer_parents: q.per_layer_parents.clone(),
+            })
+        })
+        .collect::<Option<_>>()?;
+
+    Some(DeepFriProof {
+        proof_root: compact.proof_root,
+        fs: compact.fs.clone(),
+        pow_nonce: compact.pow_nonce,
+        trace_root: compact.trace_root,
+        roots: compact.roots.clone(),
+        layer_proofs: FriLayerProofs {
+            layers: compact
+                .layer_openings
+                .iter()
+                .map(|c| Some(LayerProof { openings: c.expand()? }))
+                .collect::<Option<_>>()?,
+        },
+        queries,
+        n0: compact.n0,
+        omega0: compact.omega0,
+    })
+}
+
+/// [`deep_fri_verify`] on the expanded proof.
+pub fn deep_fri_verify_compact<N: MerkleNode>(key: &VerifierKey, compact: &CompactFriProof<N>) -> bool {
+    expand_proof(&key.params, compact).is_some_and(|proof| deep_fri_verify(key, &proof))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fri::{
+        deep_fri_prove, try_deep_fri_prove_conservative, FoldCheck, FoldingMode, FriDomain, IndexChaining,
+        QuerySampling, TranscriptHash,
+    };
+    use crate::params::PublicParams;
+    use merkle::DigestWidth;
+    use ark_ff::UniformRand;
+    use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const N: usize = 1 << 10;
+
+    fn params(r: usize) -> DeepFriParams {
+        DeepFriParams {
+            schedule: vec![4, 4, 4],
+            r,
+            seed_z: 0xd0d0,
+            folding_mode: FoldingMode::CosetInterpolation,
+            fold_check: FoldCheck::default(),
+            grinding_bits: 0,
+            query_sampling: QuerySampling::default(),
+            index_chaining: IndexChaining::default(),
+            transcript: TranscriptHash::default(),
+        }
+    }
+
+    fn key(params: &DeepFriParams) -> VerifierKey {
+        VerifierKey::for_fri(params, N).unwrap()
+    }
+
+    fn codeword(seed: u64) -> Vec<F> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let coeffs: Vec<F> = (0..N / 32).map(|_| F::rand(&mut rng)).collect();
+        GeneralEvaluationDomain::<F>::new(N).unwrap().fft(&coeffs)
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn compact_roundtrip_shrinks_payloads() {
+        let params = params(128);
+        let proof = deep_fri_prove(codeword(1), FriDomain::new_radix2(N), &params);
+
+        let compact = compact_proof(&params, &proof).unwrap();
+        let expanded = expand_proof(&params, &compact).unwrap();
+        assert_eq!(proof_digest(&expanded), proof_digest(&proof));
+        assert!(deep_fri_verify_compact(&key(&params), &compact));
+
+        let (full, small) = (payload_bytes(&proof), compact.payload_bytes());
+        assert!(small * 10 <= full * 7, "{small} of {full} payload bytes");
+
+        // 128 queries into a few hundred leaves share most of their siblings.
+        for (layer, pooled) in proof.layer_proofs.layers.iter().zip(&compact.layer_openings) {
+            let sent: usize = layer.openings.iter().map(|o| o.path.iter().map(Vec::len).sum::<usize>()).sum();
+            assert!(pooled.nodes.len() * 2 <= sent, "{} of {sent} siblings", pooled.nodes.len());
+        }
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn compact_roundtrip_with_rerandomized_cosets() {
+        let params = DeepFriParams {
+            fold_check: FoldCheck::RecomputeFromCoset,
+            index_chaining: IndexChaining::Rerandomized,
+            ..params(32)
+        };
+        let proof = deep_fri_prove(codeword(2), FriDomain::new_radix2(N), &params);
+        let compact = compact_proof(&params, &proof).unwrap();
+        assert!(deep_fri_verify_compact(&key(&params), &compact));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn compact_roundtrip_with_full_width_digests() {
+        let params = params(32);
+        let proof = try_deep_fri_prove_conservative(codeword(4), FriDomain::new_radix2(N), &params, None).unwrap();
+        let compact = compact_proof(&params, &proof).unwrap();
+        assert_eq!(proof_digest(&expand_proof(&params, &compact).unwrap()), proof_digest(&proof));
+
+        let pp = PublicParams::for_fri(&params, N).with_digest(DigestWidth::Digest256);
+        let key = VerifierKey::new(&pp, params.seed_z).unwrap();
+        assert!(deep_fri_verify_compact(&key, &compact));
+        assert!(!deep_fri_verify_compact(&self::key(&params), &compact));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn compact_rejects_what_it_cannot_reproduce() {
+        let params = params(16);
+        let proof = deep_fri_prove(codeword(3), FriDomain::new_radix2(N), &params);
+
+        // A payload whose f_0 disagrees with the other queries'.
+        let mut odd = deep_fri_prove(codeword(3), FriDomain::new_radix2(N), &params);
+        odd.queries[1].per_layer_payloads[0].f_0 += F::from(1u64);
+        assert!(compact_proof(&params, &odd).is_none());
+
+        let compact = compact_proof(&params, &proof).unwrap();
+        let mut bad = compact_proof(&params, &proof).unwrap();
+        bad.queries[0].leaves[1][0] += F::from(1u64);
+        assert!(!deep_fri_verify_compact(&key(&params), &bad));
+
+        let mut bad = compact_proof(&params, &proof).unwrap();
+        bad.layers[2].parent_values.pop();
+        assert!(expand_proof(&params, &bad).is_none());
+
+        let other = DeepFriParams { schedule: vec![4, 16], ..params.clone() };
+        assert!(!deep_fri_verify_compact(&key(&other), &compact));
+    }
+}
@@ -5,11 +5,97 @@
 //! and eliminate the Block–Tiwari small-field attack.
 //!
 //! All arithmetic is componentwise.
+//!
+//! [`DeepChallenge`] abstracts over where the DEEP point lives, so the same
+//! quotient code serves a large prime field (the point is a base-field
+//! element) and Goldilocks (the point is an [`Fp3`]).
 
-use ark_ff::{Field, One, Zero};
+use alloc::vec::Vec;
+use ark_ff::{Field, One, PrimeField, Zero};
 use ark_goldilocks::Goldilocks as F;
+use core::fmt::Debug;
 use core::ops::{Add, Sub, Mul};
 
+/// Challenge space for the DEEP point over base field `B`.
+///
+/// Fields of ~2^255 elements like Pallas sample the point from `B` itself;
+/// 64-bit fields need [`Fp3`]'s three coordinates to get past the
+/// Block–Tiwari bound.
+pub trait DeepChallenge<B: Field>:
+    Copy + PartialEq + Debug + Zero + One + Sub<Output = Self>
+{
+    /// Base-field coordinates per element; one transcript challenge each.
+    const DEGREE: usize;
+
+    fn from_base(x: B) -> Self;
+
+    /// From exactly `DEGREE` coordinates.
+    fn from_coords(coords: &[B]) -> Self;
+
+    fn coord(&self, i: usize) -> B;
+
+    /// `None` when not invertible.
+    fn try_inverse(self) -> Option<Self>;
+
+    /// DEEP quotient `(f(x) − v) / (x − z)`, `None` if `x − z` is not
+    /// invertible.
+    fn deep_quotient(f_x: B, v: Self, x: B, z: Self) -> Option<Self> {
+        let den = (Self::from_base(x) - z).try_inverse()?;
+        Some((Self::from_base(f_x) - v) * den)
+    }
+}
+
+impl<B: PrimeField> DeepChallenge<B> for B {
+    const DEGREE: usize = 1;
+
+    fn from_base(x: B) -> Self {
+        x
+    }
+
+    fn from_coords(coords: &[B]) -> Self {
+        assert_eq!(coords.len(), 1, "base-field challenge takes one coordinate");
+        coords[0]
+    }
+
+    fn coord(&self, i: usize) -> B {
+        assert_eq!(i, 0, "base-field challenge has one coordinate");
+        *self
+    }
+
+    fn try_inverse(self) -> Option<Self> {
+        self.inverse()
+    }
+}
+
+impl DeepChallenge<F> for Fp3 {
+    const DEGREE: usize = 3;
+
+    fn from_base(x: F) -> Self {
+        Fp3::from_base(x)
+    }
+
+    fn from_coords(coords: &[F]) -> Self {
+        assert_eq!(coords.len(), 3, "Fp3 challenge takes three coordinates");
+        Self {
+            a0: coords[0],
+            a1: coords[1],
+            a2: coords[2],
+        }
+    }
+
+    fn coord(&self, i: usize) -> F {
+        [self.a0, self.a1, self.a2][i]
+    }
+
+    fn try_inverse(self) -> Option<Self> {
+        Some(Self {
+            a0: self.a0.inverse()?,
+            a1: self.a1.inverse()?,
+            a2: self.a2.inverse()?,
+        })
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Fp3 {
     pub a0: F,
@@ -57,6 +143,47 @@ impl Fp3 {
 
 /* ---------- Trait impls ---------- */
 
+impl From<[F; 3]> for Fp3 {
+    #[inline]
+    fn from([a0, a1, a2]: [F; 3]) -> Self {
+        Self { a0, a1, a2 }
+    }
+}
+
+impl From<Fp3> for [F; 3] {
+    #[inline]
+    fn from(x: Fp3) -> Self {
+        [x.a0, x.a1, x.a2]
+    }
+}
+
+/// FRI leaves commit to `q` as its three base-field coordinates.
+impl merkle::LeafEncoder for Fp3 {
+    #[inline]
+    fn encode_leaf(&self, out: &mut Vec<F>) {
+        out.extend([self.a0, self.a1, self.a2]);
+    }
+}
+
+impl Zero for Fp3 {
+    #[inline]
+    fn zero() -> Self {
+        Fp3::zero()
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        *self == Fp3::zero()
+    }
+}
+
+impl One for Fp3 {
+    #[inline]
+    fn one() -> Self {
+        Fp3::one()
+    }
+}
+
 impl Add for Fp3 {
     type Output = Self;
 
@@ -190,4 +317,27 @@ mod tests {
             assert_eq!(q.mul(denom), num);
         }
     }
+
+    /// Quotient identity through the trait alone, for any challenge space.
+    fn check_deep_quotient<C: DeepChallenge<F>>(rng: &mut StdRng) {
+        for _ in 0..100 {
+            let coords: Vec<F> = (0..C::DEGREE).map(|_| F::from(rng.gen::<u64>())).collect();
+            let z = C::from_coords(&coords);
+            assert_eq!((0..C::DEGREE).map(|i| z.coord(i)).collect::<Vec<_>>(), coords);
+
+            let f_x = F::from(rng.gen::<u64>());
+            let x = F::from(rng.gen::<u64>());
+            let v = C::from_base(F::from(rng.gen::<u64>()));
+            let q = C::deep_quotient(f_x, v, x, z).unwrap();
+            assert_eq!(q * (C::from_base(x) - z) + v, C::from_base(f_x));
+        }
+        assert_eq!(C::deep_quotient(F::one(), C::zero(), F::one(), C::one()), None);
+    }
+
+    #[test]
+    fn test_deep_challenge_impls() {
+        let mut rng = StdRng::seed_from_u64(7);
+        check_deep_quotient::<F>(&mut rng);
+        check_deep_quotient::<Fp3>(&mut rng);
+    }
 }
@@ -4,47 +4,41 @@
 #![allow(unused_variables)]
 #![allow(unused_macros)]
 //use ark_pallas::Fr as F;
-use ark_goldilocks::Goldilocks as F;
+use alloc::{collections::BTreeSet, vec, vec::Vec};
+use core::sync::atomic::{AtomicBool, Ordering};
+use ark_goldilocks::{dot_product, two_adic_subgroup_generator, Goldilocks as F, MulAcc};
 use ark_serialize::CanonicalSerialize;
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand::Rng;
+use sha3::{Digest as _, Sha3_256};
 
-use ark_poly::domain::radix2::Radix2EvaluationDomain as Domain;
-use ark_ff::{Field, One, PrimeField, Zero};
-use ark_poly::{
-    EvaluationDomain, GeneralEvaluationDomain,
-};
+use ark_ff::{batch_inversion, Field, One, PrimeField, Zero};
 
 // ✅ NEW: cubic DEEP tower
-use crate::deep_tower::Fp3;
+use crate::deep_tower::{DeepChallenge, Fp3};
+use crate::parallel::{assert_deterministic, enable_parallel, map_indices, parallel_config, ParallelOp};
+use crate::params::{ParamError, PublicParams, MAX_GRINDING_BITS};
+use crate::DomainH;
+use poseidon::PoseidonParams;
 
 // ✅ REAL MERKLE API ONLY
 use merkle::{
+    meta_root,
+    ArityPolicy,
+    Digest256,
+    DigestWidth,
     MerkleChannelCfg,
+    MerkleNode,
     MerkleTreeChannel,
     MerkleOpening,
+    MerkleValueOpening,
 };
 
 // ✅ TRANSCRIPT
-use transcript::{default_params as transcript_params, Transcript};
+use transcript::{default_params as transcript_params, FsBinding, FsConfig, FsHash, Transcript};
 
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
-const PARALLEL_MIN_ELEMS: usize = 1 << 12;
-
-#[inline]
-fn enable_parallel(len: usize) -> bool {
-    #[cfg(feature = "parallel")]
-    {
-        len >= PARALLEL_MIN_ELEMS && rayon::current_num_threads() > 1
-    }
-    #[cfg(not(feature = "parallel"))]
-    {
-        let _ = len;
-        false
-    }
-}
-
 #[cfg(feature = "fri_bench_log")]
 #[allow(unused_macros)]
 macro_rules! logln {
@@ -55,17 +49,29 @@ macro_rules! logln {
     ($($tt:tt)*) => {};
 }
 
-mod ds {
+// Prover/verifier diagnostics go to stderr only when std is available.
+#[cfg(feature = "std")]
+macro_rules! diagln {
+    ($($tt:tt)*) => { eprintln!($($tt)*); }
+}
+#[cfg(not(feature = "std"))]
+macro_rules! diagln {
+    ($($tt:tt)*) => {};
+}
+
+pub(crate) mod ds {
     pub const FRI_SEED: &[u8] = b"FRI/seed";
     pub const FRI_INDEX: &[u8] = b"FRI/index";
     pub const FRI_Z_L: &[u8] = b"FRI/z/l";
     pub const FRI_Z_L_1: &[u8] = b"FRI/z/l/1";
     pub const FRI_Z_L_2: &[u8] = b"FRI/z/l/2";
     pub const FRI_LEAF: &[u8] = b"FRI/leaf";
+    pub const FRI_PROOF_ROOT: &[u8] = b"FRI/proof-root";
+    pub const FRI_POW: &[u8] = b"FRI/pow";
 }
 
-fn tr_hash_fields_tagged(tag: &[u8], fields: &[F]) -> F {
-    let mut tr = Transcript::new(b"FRI/FS", transcript_params());
+fn tr_hash_fields_tagged(hash: TranscriptHash, tag: &[u8], fields: &[F]) -> F {
+    let mut tr = hash.transcript();
     tr.absorb_bytes(tag);
     for &x in fields {
         tr.absorb_field(x);
@@ -74,15 +80,56 @@ fn tr_hash_fields_tagged(tag: &[u8], fields: &[F]) -> F {
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 pub struct FriDomain {
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub omega: F,
     pub size: usize,
 }
 
 impl FriDomain {
     pub fn new_radix2(size: usize) -> Self {
-        let dom = Domain::<F>::new(size).expect("radix-2 domain exists");
-        Self { omega: dom.group_gen, size }
+        Self { omega: radix2_generator(size), size }
+    }
+}
+
+/// Generator of the size-`n` multiplicative subgroup, read from the
+/// precomputed Goldilocks table rather than building an arkworks domain.
+#[inline]
+pub(crate) fn radix2_generator(n: usize) -> F {
+    assert!(n.is_power_of_two(), "radix-2 domain size must be a power of two");
+    two_adic_subgroup_generator(n.trailing_zeros())
+}
+
+/// Square ladder `ω₀^(2^k)` of the base-domain generator.
+///
+/// Layer ℓ's generator is `ω₀^(n₀/n_ℓ)`, so the verifier gets every
+/// `x_i = ω_ℓ^i` from this one table with at most log n₀ multiplications.
+#[derive(Clone)]
+struct OmegaLadder {
+    pow2: Vec<F>,
+}
+
+impl OmegaLadder {
+    fn new(omega0: F, n0: usize) -> Self {
+        let mut pow2 = Vec::with_capacity(n0.trailing_zeros() as usize);
+        let mut cur = omega0;
+        for _ in 0..n0.trailing_zeros() {
+            pow2.push(cur);
+            cur.square_in_place();
+        }
+        Self { pow2 }
+    }
+
+    /// `ω₀^e`, reduced mod n₀.
+    fn pow(&self, e: u64) -> F {
+        let mut acc = F::one();
+        for (k, w) in self.pow2.iter().enumerate() {
+            if (e >> k) & 1 == 1 {
+                acc *= w;
+            }
+        }
+        acc
     }
 }
 
@@ -97,59 +144,38 @@ fn build_z_pows(z_l: F, m: usize) -> Vec<F> {
 }
 
 // -----------------------------------------------------------------------------
-// ✅ NEW: Goldilocks-safe DEEP quotient (Fp³)
+// DEEP quotient over the challenge space
 // -----------------------------------------------------------------------------
 
-fn compute_q_layer_fp3(
-    f_l: &[F],
-    z: Fp3,
-    omega: F,
-) -> Vec<Fp3> {
-    let n = f_l.len();
-    let mut q = Vec::with_capacity(n);
-
-    let f0 = Fp3::from_base(f_l[0]);
-    let mut x = F::one();
-
-    for i in 0..n {
-        let num   = Fp3::from_base(f_l[i]) - f0;
-        let denom = Fp3::from_base(x) - z;
-        q.push(num * denom.inv()); // ✅ Fp³ identity
-        x *= omega;
-    }
-    q
+/// `(f(x) − f(1)) / (x − z)` at each of `xs`, in the challenge space `C`:
+/// [`Fp3`] here, the base field itself for large primes.
+fn compute_q_layer<C: DeepChallenge<F>>(f_l: &[F], z: C, xs: impl IntoIterator<Item = F>) -> Vec<C> {
+    let f0 = C::from_base(f_l[0]);
+    f_l.iter()
+        .zip(xs)
+        .map(|(&f, x)| C::deep_quotient(f, f0, x, z).expect("z lies off the layer domain"))
+        .collect()
 }
 
-// -----------------------------------------------------------------------------
-// ✅ Legacy base-field DEEP (for non-Goldilocks fields)
-// -----------------------------------------------------------------------------
-
-fn compute_q_layer_base(
-    f_l: &[F],
-    z: F,
-    omega: F,
-) -> Vec<F> {
-    let n = f_l.len();
-    let mut q = Vec::with_capacity(n);
-
-    let mut x = F::one();
-    for i in 0..n {
-        q.push((f_l[i] - f_l[0]) * (x - z).inverse().unwrap());
-        x *= omega;
-    }
-
-    q
+/// Whether `q` is the DEEP quotient at `x` of `f(x) = f_x`, `f(1) = f_0`;
+/// `false` if `x − z` is not invertible.
+fn deep_quotient_holds<C: DeepChallenge<F>>(q: C, f_x: F, f_0: F, x: F, z: C) -> bool {
+    C::deep_quotient(f_x, C::from_base(f_0), x, z) == Some(q)
 }
 
-
-
 fn dot_with_z_pows(chunk: &[F], z_pows: &[F]) -> F {
     debug_assert_eq!(chunk.len(), z_pows.len());
-    let mut s = F::zero();
-    for (val, zp) in chunk.iter().zip(z_pows.iter()) {
-        s += *val * *zp;
+    dot_product(chunk, z_pows)
+}
+
+/// `Σ_j f[b + j·stride] · z_pows[j]`: one coset of the strided fold.
+#[inline]
+fn strided_fold_at(f: &[F], b: usize, stride: usize, z_pows: &[F]) -> F {
+    let mut acc = MulAcc::new();
+    for (j, zp) in z_pows.iter().enumerate() {
+        acc.mul_add(f[b + j * stride], *zp);
     }
-    s
+    acc.finish()
 }
 
 fn fold_layer_sequential(f_l: &[F], z_pows: &[F], m: usize) -> Vec<F> {
@@ -175,21 +201,6 @@ fn fill_repeated_targets(target: &mut [F], src: &[F], m: usize) {
     }
 }
 
-fn merkle_depth(leaves: usize, arity: usize) -> usize {
-    assert!(arity >= 2, "Merkle arity must be ≥ 2");
-
-    let mut depth = 1;
-    let mut cur = leaves;
-
-    while cur > arity {
-        cur = (cur + arity - 1) / arity;
-        depth += 1;
-    }
-
-    depth
-}
-
-
 #[cfg(feature = "parallel")]
 fn fill_repeated_targets_parallel(target: &mut [F], src: &[F], m: usize) {
     target
@@ -204,13 +215,12 @@ fn fill_repeated_targets_parallel(target: &mut [F], src: &[F], m: usize) {
 }
 
 pub fn fri_sample_z_ell(seed_z: u64, level: usize, domain_size: usize) -> F {
-    let fused = tr_hash_fields_tagged(
-        ds::FRI_Z_L,
-        &[F::from(seed_z), F::from(level as u64), F::from(domain_size as u64)],
-    );
-    let mut seed_bytes = [0u8; 32];
-    fused.serialize_uncompressed(&mut seed_bytes[..]).expect("serialize");
-    let mut rng = StdRng::from_seed(seed_bytes);
+    let mut tr = Transcript::new(b"FRI/FS", transcript_params());
+    tr.absorb_bytes(ds::FRI_Z_L);
+    tr.absorb_u64(seed_z);
+    tr.absorb_usize(level);
+    tr.absorb_usize(domain_size);
+    let mut rng = tr.rng(b"z_ell");
 
     let exp_bigint = <F as PrimeField>::BigInt::from(domain_size as u64);
 
@@ -233,21 +243,16 @@ pub fn fri_sample_z_ell(seed_z: u64, level: usize, domain_size: usize) -> F {
 }
 
 pub fn compute_s_layer(f_l: &[F], z_l: F, m: usize) -> Vec<F> {
+    compute_s_layer_with_mode(f_l, z_l, m, FoldingMode::PowersOfZ)
+}
+
+pub fn compute_s_layer_with_mode(f_l: &[F], z_l: F, m: usize, mode: FoldingMode) -> Vec<F> {
     let n = f_l.len();
     assert!(n % m == 0);
     let n_next = n / m;
 
-    let z_pows = build_z_pows(z_l, m);
-
     // First compute the folded values (same as fri_fold_layer_impl)
-    let mut folded = vec![F::zero(); n_next];
-    for b in 0..n_next {
-        let mut acc = F::zero();
-        for j in 0..m {
-            acc += f_l[b + j * n_next] * z_pows[j];
-        }
-        folded[b] = acc;
-    }
+    let folded = fri_fold_layer_with_mode(f_l, z_l, m, mode);
 
     // Then repeat each folded value m times to match original domain
     let mut s_per_i = vec![F::zero(); n];
@@ -260,7 +265,7 @@ pub fn compute_s_layer(f_l: &[F], z_l: F, m: usize) -> Vec<F> {
     s_per_i
 }
 
-fn layer_sizes_from_schedule(n0: usize, schedule: &[usize]) -> Vec<usize> {
+pub(crate) fn layer_sizes_from_schedule(n0: usize, schedule: &[usize]) -> Vec<usize> {
     let mut sizes = Vec::with_capacity(schedule.len() + 1);
     let mut n = n0;
     sizes.push(n);
@@ -273,11 +278,11 @@ fn layer_sizes_from_schedule(n0: usize, schedule: &[usize]) -> Vec<usize> {
 }
 
 fn hash_leaf(f: F, s: F, q: F) -> F {
-    tr_hash_fields_tagged(ds::FRI_LEAF, &[f, s, q])
+    tr_hash_fields_tagged(TranscriptHash::Poseidon, ds::FRI_LEAF, &[f, s, q])
 }
 
 fn hash_node(children: &[F]) -> F {
-    tr_hash_fields_tagged(b"FRI/MERKLE/NODE", children)
+    tr_hash_fields_tagged(TranscriptHash::Poseidon, b"FRI/MERKLE/NODE", children)
 }
 
 fn verify_merkle_opening_explicit(
@@ -319,24 +324,283 @@ fn verify_local_check_fold(
     child_leaf_i.s == parent_f_b
 }
 
-fn fs_seed_from_roots(roots: &[F]) -> F {
-    tr_hash_fields_tagged(ds::FRI_SEED, roots)
+/// Recompute one query's fold from its m opened coset leaves.
+///
+/// Every leaf is bound to the layer root (contents → leaf digest → path),
+/// the queried position must be among them with the payload's `f_i`, and
+/// the fold of their `f` values must equal the payload's `f_parent_b`.
+#[allow(clippy::too_many_arguments)]
+fn verify_coset_fold<N: MerkleNode>(
+    layer: &VerifierLayer,
+    root: N,
+    trace_hash: &[u8; 32],
+    coset: &[CosetLeafOpening<N>],
+    pay: &LayerOpenPayload,
+    i: u64,
+    z: F,
+    mode: FoldingMode,
+) -> bool {
+    let (cfg, m) = (&layer.cfg, layer.m);
+    let n_next = (layer.n / m) as u64;
+    let b = i % n_next;
+    if coset.len() != m {
+        return false;
+    }
+
+    let mut values = Vec::with_capacity(m);
+    for (j, c) in coset.iter().enumerate() {
+        let idx = b + j as u64 * n_next;
+        if c.opening.index != idx
+            || MerkleTreeChannel::<N>::leaf_digest(cfg, idx, &c.leaf_values()) != c.opening.leaf
+            || MerkleTreeChannel::verify_opening(cfg, root, &c.opening, trace_hash) != Ok(true)
+        {
+            return false;
+        }
+        values.push(c.f);
+    }
+
+    if values[(i / n_next) as usize] != pay.f_i {
+        return false;
+    }
+
+    let folded = match mode {
+        FoldingMode::PowersOfZ => dot_product(&values, &layer.z_pows),
+        FoldingMode::CosetInterpolation => {
+            let x_b_inv = layer.omega.pow([b]).inverse().expect("ω ≠ 0");
+            fold_coset_interpolation(&values, z, x_b_inv, &layer.zetas)
+        }
+    };
+    folded == pay.f_parent_b
 }
 
-fn index_from_seed(seed_f: F, n_pow2: usize) -> usize {
-    assert!(n_pow2.is_power_of_two());
-    let mask = n_pow2 - 1;
-    let mut seed_bytes = [0u8; 32];
-    seed_f.serialize_uncompressed(&mut seed_bytes[..]).unwrap();
-    let mut rng = StdRng::from_seed(seed_bytes);
-    (rng.gen::<u64>() as usize) & mask
+/// Single 32-byte commitment to the trace root and every FRI layer root.
+///
+/// Leaves are `[trace_root, root_0, .., root_{L-1}]`; a proof without an
+/// external trace commitment uses zero in the first slot.
+pub fn deep_fri_proof_root<N: MerkleNode>(trace_root: Option<N>, layer_roots: &[N]) -> [u8; 32] {
+    let mut leaves = Vec::with_capacity(layer_roots.len() + 1);
+    leaves.push(trace_root.unwrap_or_default());
+    leaves.extend_from_slice(layer_roots);
+    meta_root(&leaves)
 }
 
-fn index_seed(roots_seed: F, ell: usize, q: usize) -> F {
-    tr_hash_fields_tagged(
-        ds::FRI_INDEX,
-        &[roots_seed, F::from(ell as u64), F::from(q as u64)],
-    )
+/// Authenticated layer-0 `(index, f₀(x_index))` for every query.
+///
+/// Ties each query's layer-0 payload to the committed leaf digest so an
+/// outer protocol (e.g. an AIR) can check its own openings against the FRI
+/// codeword. Path verification itself is left to [`deep_fri_verify`].
+pub fn deep_fri_layer0_values<N: MerkleNode>(
+    params: &DeepFriParams,
+    proof: &DeepFriProof<N>,
+) -> Option<Vec<(u64, F)>> {
+    let m0 = *params.schedule.first()?;
+    let layer0 = proof.layer_proofs.layers.first()?;
+    if layer0.openings.len() != proof.queries.len() {
+        return None;
+    }
+
+    let cfg = MerkleChannelCfg::for_leaves(proof.n0, ArityPolicy::UpTo(m0), 0).with_digest(N::WIDTH);
+
+    proof
+        .queries
+        .iter()
+        .zip(&layer0.openings)
+        .map(|(qp, opening)| {
+            let i = qp.per_layer_refs.first()?.i;
+            let pay = qp.per_layer_payloads.first()?;
+            let leaf = MerkleTreeChannel::<N>::leaf_digest(&cfg, i, &pay.leaf_values());
+            (opening.index == i && opening.leaf == leaf).then_some((i, pay.f_i))
+        })
+        .collect()
+}
+
+/// Configuration of every FRI transcript over `hash`, as proofs declare it
+/// in [`DeepFriProof::fs`].
+pub fn fri_fs_binding(hash: TranscriptHash) -> FsBinding {
+    hash.transcript().binding()
+}
+
+/// Query seed, derived from the transcript after absorbing `proof_root`.
+fn fs_seed_from_proof_root(hash: TranscriptHash, proof_root: &[u8; 32]) -> F {
+    let mut tr = hash.transcript();
+    tr.absorb_bytes(ds::FRI_PROOF_ROOT);
+    tr.absorb_bytes(proof_root);
+    tr.challenge(ds::FRI_SEED)
+}
+
+/// Query seed after `nonce`; with `grinding_bits > 0` it must have that
+/// many trailing zero bits.
+fn query_seed(hash: TranscriptHash, proof_root: &[u8; 32], grinding_bits: u32, nonce: u64) -> F {
+    let seed = fs_seed_from_proof_root(hash, proof_root);
+    if grinding_bits == 0 {
+        return seed;
+    }
+    tr_hash_fields_tagged(hash, ds::FRI_POW, &[seed, F::from(nonce)])
+}
+
+/// Seed every query index is drawn from. Proofs over `F` nodes keep the
+/// single field element the transcript squeezes, and the transcript hash
+/// their indices are drawn with; [`Digest256`] proofs use a full SHA3-256
+/// output, and derive each index from one as well.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum QuerySeed {
+    Field(F, TranscriptHash),
+    Wide([u8; 32]),
+}
+
+impl QuerySeed {
+    /// Low 64 bits, where grinding looks for zeros.
+    fn low_word(&self) -> u64 {
+        match self {
+            QuerySeed::Field(seed, _) => seed.into_bigint().0[0],
+            QuerySeed::Wide(bytes) => u64::from_le_bytes(bytes[..8].try_into().unwrap()),
+        }
+    }
+}
+
+fn wide_hash(tag: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    let mut h = Sha3_256::new();
+    h.update((tag.len() as u64).to_le_bytes());
+    h.update(tag);
+    for part in parts {
+        h.update(part);
+    }
+    h.finalize().into()
+}
+
+/// Query seed for a proof over `N` nodes. The wide seed hashes the proof
+/// root with the nonce directly rather than through the field transcript.
+fn query_seed_for<N: MerkleNode>(hash: TranscriptHash, proof_root: &[u8; 32], grinding_bits: u32, nonce: u64) -> QuerySeed {
+    match N::WIDTH {
+        DigestWidth::Digest64 => QuerySeed::Field(query_seed(hash, proof_root, grinding_bits, nonce), hash),
+        DigestWidth::Digest256 => {
+            QuerySeed::Wide(wide_hash(ds::FRI_POW, &[proof_root, &nonce.to_le_bytes()]))
+        }
+    }
+}
+
+fn meets_grinding(seed: QuerySeed, grinding_bits: u32) -> bool {
+    seed.low_word().trailing_zeros() >= grinding_bits
+}
+
+/// Smallest nonce whose [`query_seed_for`] meets the grinding target, with
+/// that seed; about `2^grinding_bits` hashes, giving up after
+/// `2^(grinding_bits + 8)`.
+fn grind_query_seed<N: MerkleNode>(
+    hash: TranscriptHash,
+    proof_root: &[u8; 32],
+    grinding_bits: u32,
+) -> Result<(u64, QuerySeed), ParamError> {
+    if grinding_bits > MAX_GRINDING_BITS {
+        return Err(ParamError::GrindingBits { bits: grinding_bits, max: MAX_GRINDING_BITS });
+    }
+    (0..1u64 << (grinding_bits + 8))
+        .map(|nonce| (nonce, query_seed_for::<N>(hash, proof_root, grinding_bits, nonce)))
+        .find(|&(_, seed)| meets_grinding(seed, grinding_bits))
+        .ok_or(ParamError::GrindingExhausted { bits: grinding_bits })
+}
+
+/// Layer-0 index of the `k`-th draw from the query seed.
+fn query_base_index(roots_seed: QuerySeed, k: usize, n0: u64) -> u64 {
+    index_below(roots_seed, 0, k, n0)
+}
+
+/// Layer-0 indices of all `r` queries.
+///
+/// Draws are numbered, so without collisions both modes give the same
+/// indices; under [`QuerySampling::Distinct`] a repeat is discarded and the
+/// next draw taken in its place. `None` if `r` distinct indices cannot exist.
+fn query_base_indices(roots_seed: QuerySeed, r: usize, n0: u64, sampling: QuerySampling) -> Option<Vec<u64>> {
+    let draws = (0..).map(|k| query_base_index(roots_seed, k, n0));
+    match sampling {
+        QuerySampling::Independent => Some(draws.take(r).collect()),
+        QuerySampling::Distinct => {
+            if r as u64 > n0 {
+                return None;
+            }
+            let mut out = Vec::with_capacity(r);
+            let mut seen = BTreeSet::new();
+            for i in draws {
+                if out.len() == r {
+                    break;
+                }
+                if seen.insert(i) {
+                    out.push(i);
+                }
+            }
+            Some(out)
+        }
+    }
+}
+
+/// Position of every query on each layer, then its final index, as
+/// [`deep_fri_verify`] derives them from the proof root and nonce; `None`
+/// if the nonce misses the grinding target.
+pub(crate) fn query_indices<N: MerkleNode>(
+    key: &VerifierKey,
+    proof_root: &[u8; 32],
+    pow_nonce: u64,
+) -> Option<Vec<Vec<u64>>> {
+    let params = &key.params;
+    let l = params.schedule.len();
+    let seed = query_seed_for::<N>(params.transcript, proof_root, params.grinding_bits, pow_nonce);
+    if !meets_grinding(seed, params.grinding_bits) {
+        return None;
+    }
+    let base = query_base_indices(seed, params.r, key.n0() as u64, params.query_sampling)?;
+    let chain = |(q, mut i): (usize, u64)| {
+        let mut path = Vec::with_capacity(l + 1);
+        for (ell, layer) in key.layers.iter().enumerate() {
+            path.push(i);
+            i = next_query_index(seed, q, ell, i, (layer.n / layer.m) as u64, l, params.index_chaining);
+        }
+        path.push(i);
+        path
+    };
+    Some(base.into_iter().enumerate().map(chain).collect())
+}
+
+/// Index of query `q` at layer `ell + 1` after checking position `i` of layer
+/// `ell`, where layer `ell + 1` has `n_next` points and there are `l` folds.
+fn next_query_index(
+    roots_seed: QuerySeed,
+    q: usize,
+    ell: usize,
+    i: u64,
+    n_next: u64,
+    l: usize,
+    chaining: IndexChaining,
+) -> u64 {
+    let parent = i % n_next;
+    match chaining {
+        IndexChaining::Rerandomized if ell + 1 < l => {
+            (parent + index_below(roots_seed, ell + 1, q, n_next)) % n_next
+        }
+        _ => parent,
+    }
+}
+
+/// Index below `n` for draw `q` at layer `ell`, kept in u64 so domains
+/// past 2^32 sample the same way on every target. Field seeds sample
+/// straight from the transcript without modulo bias.
+fn index_below(roots_seed: QuerySeed, ell: usize, q: usize, n: u64) -> u64 {
+    match roots_seed {
+        QuerySeed::Field(seed, hash) => {
+            let mut tr = hash.transcript();
+            tr.absorb_bytes(ds::FRI_INDEX);
+            for x in [seed, F::from(ell as u64), F::from(q as u64)] {
+                tr.absorb_field(x);
+            }
+            tr.challenge_index(b"out", n)
+        }
+        QuerySeed::Wide(seed) => {
+            let h = QuerySeed::Wide(wide_hash(
+                ds::FRI_INDEX,
+                &[&seed, &(ell as u64).to_le_bytes(), &(q as u64).to_le_bytes()],
+            ));
+            (h.low_word() & (n.next_power_of_two() - 1)) % n
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -346,146 +610,343 @@ pub struct CombinedLeaf {
     pub q: F,   // DEEP quotient value Qℓ(x)
 }
 
-pub struct FriLayerCommitment {
+pub struct FriLayerCommitment<N = F> {
     pub n: usize,
     pub m: usize,
-    pub root: F,
+    pub root: N,
 }
 
-pub struct FriTranscript {
+pub struct FriTranscript<N = F> {
     pub schedule: Vec<usize>,
-    pub layers: Vec<FriLayerCommitment>,
+    pub layers: Vec<FriLayerCommitment<N>>,
 }
 
 pub struct FriProverParams {
     pub schedule: Vec<usize>,
+    pub r: usize,
     pub seed_z: u64,
+    pub folding_mode: FoldingMode,
+    pub fold_check: FoldCheck,
+    pub grinding_bits: u32,
+    pub query_sampling: QuerySampling,
+    pub index_chaining: IndexChaining,
+    pub transcript: TranscriptHash,
 }
 
-pub struct FriProverState {
+pub struct FriProverState<N = F> {
     pub f_layers: Vec<Vec<F>>,
     pub s_layers: Vec<Vec<F>>,
     pub q_layers: Vec<Vec<Fp3>>,   // NEW
-    pub transcript: FriTranscript,
+    pub transcript: FriTranscript<N>,
     pub omega_layers: Vec<F>,
     pub z_layers: Vec<F>,
+    /// Finalized layer trees behind `transcript.layers[ell].root`, kept so
+    /// the query phase opens them without rehashing.
+    pub trees: Vec<MerkleTreeChannel<N>>,
+}
+
+/// Prover phases, in the order a proving run passes through them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProverPhase {
+    /// Trace LDE (FFT scratch), trace commitments and the composition
+    /// codeword. AIR proofs only.
+    Trace,
+    /// Folded codewords, s-layers and DEEP quotients for every layer.
+    Layers,
+    /// Merkle trees over the layer leaves.
+    Merkle,
+    /// Grinding, query openings and payloads.
+    Queries,
+}
+
+/// Observer told when the prover crosses a phase boundary.
+pub(crate) trait ProverHook {
+    fn enter(&mut self, phase: ProverPhase);
+    /// `done` of the phase's `total` steps are finished.
+    fn step(&mut self, _phase: ProverPhase, _done: usize, _total: usize) {}
+    /// Polled after every step; the prover gives up once this is true.
+    fn cancelled(&self) -> bool {
+        false
+    }
 }
 
-fn pick_arity_for_layer(n: usize, requested_m: usize) -> usize {
-    if requested_m >= 128 && n % 128 == 0 { return 128; }
-    if requested_m >= 64  && n % 64  == 0 { return 64; }
-    if requested_m >= 32  && n % 32  == 0 { return 32; }
-    if requested_m >= 16  && n % 16  == 0 { return 16; }
-    if requested_m >= 8   && n % 8   == 0 { return 8; }
-    if requested_m >= 4   && n % 4   == 0 { return 4; }
-    if n % 2 == 0 { return 2; }
-    1
+impl ProverHook for () {
+    fn enter(&mut self, _phase: ProverPhase) {}
 }
 
+fn checkpoint(hook: &mut impl ProverHook, phase: ProverPhase, done: usize, total: usize) -> Option<()> {
+    hook.step(phase, done, total);
+    (!hook.cancelled()).then_some(())
+}
+
+struct Progress<'a, P> {
+    on_progress: P,
+    cancel: &'a AtomicBool,
+}
+
+impl<P: FnMut(ProverPhase, f32)> ProverHook for Progress<'_, P> {
+    fn enter(&mut self, _phase: ProverPhase) {}
+
+    fn step(&mut self, phase: ProverPhase, done: usize, total: usize) {
+        (self.on_progress)(phase, done as f32 / total as f32);
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+}
+
+/// Why [`deep_fri_prove_with_progress`] returned without a proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProveError {
+    Params(ParamError),
+    /// The cancel flag was raised; the partial state has been dropped.
+    Cancelled,
+}
+
+impl ProveError {
+    /// For provers whose hook cannot cancel.
+    pub(crate) fn uncancelled(self) -> ParamError {
+        match self {
+            ProveError::Params(e) => e,
+            ProveError::Cancelled => unreachable!("proof cancelled without a cancel flag"),
+        }
+    }
+}
+
+impl From<ParamError> for ProveError {
+    fn from(e: ParamError) -> Self {
+        ProveError::Params(e)
+    }
+}
+
+impl core::fmt::Display for ProveError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ProveError::Params(e) => write!(f, "invalid FRI parameters: {e}"),
+            ProveError::Cancelled => write!(f, "proof cancelled"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ProveError {}
+
+/// Panics if the parameters do not fit the domain; see
+/// [`try_deep_fri_prove`].
 pub fn deep_fri_prove(
     f0: Vec<F>,
     domain0: FriDomain,
     params: &DeepFriParams,
 ) -> DeepFriProof {
+    deep_fri_prove_with_trace_root(f0, domain0, params, None)
+}
+
+/// As [`deep_fri_prove`], additionally binding an external trace commitment
+/// (e.g. `DualCommitment::poseidon_root`) into the proof's `proof_root`.
+pub fn deep_fri_prove_with_trace_root(
+    f0: Vec<F>,
+    domain0: FriDomain,
+    params: &DeepFriParams,
+    trace_root: Option<F>,
+) -> DeepFriProof {
+    try_deep_fri_prove_with_trace_root(f0, domain0, params, trace_root)
+        .unwrap_or_else(|e| panic!("invalid FRI parameters: {e}"))
+}
+
+/// As [`deep_fri_prove`], with the parameters checked up front by
+/// [`DeepFriParams::validate`] instead of failing deep inside a fold.
+pub fn try_deep_fri_prove(
+    f0: Vec<F>,
+    domain0: FriDomain,
+    params: &DeepFriParams,
+) -> Result<DeepFriProof, ParamError> {
+    try_deep_fri_prove_with_trace_root(f0, domain0, params, None)
+}
+
+pub fn try_deep_fri_prove_with_trace_root(
+    f0: Vec<F>,
+    domain0: FriDomain,
+    params: &DeepFriParams,
+    trace_root: Option<F>,
+) -> Result<DeepFriProof, ParamError> {
+    prove_hooked(f0, domain0, params, trace_root, &mut ()).map_err(ProveError::uncancelled)
+}
+
+/// As [`try_deep_fri_prove_with_trace_root`] over a [`DomainH`] built
+/// once for the DEEP-ALI merge: layer 0's DEEP quotient reads the lifted
+/// powers of ω cached there instead of recomputing them.
+pub fn try_deep_fri_prove_over(
+    f0: Vec<F>,
+    dom: &DomainH,
+    params: &DeepFriParams,
+    trace_root: Option<F>,
+) -> Result<DeepFriProof, ParamError> {
+    prove_hooked_over(f0, dom.fri_domain(), Some(dom), params, trace_root, &mut ()).map_err(ProveError::uncancelled)
+}
+
+/// [`try_deep_fri_prove_over`], panicking on invalid parameters.
+pub fn deep_fri_prove_over(f0: Vec<F>, dom: &DomainH, params: &DeepFriParams, trace_root: Option<F>) -> DeepFriProof {
+    try_deep_fri_prove_over(f0, dom, params, trace_root).unwrap_or_else(|e| panic!("invalid FRI parameters: {e}"))
+}
+
+/// As [`try_deep_fri_prove_with_trace_root`] under the conservative
+/// profile: layer trees keep full SHA3-256 nodes and query indices come
+/// from a 256-bit seed. Verify against a key whose Merkle shapes carry
+/// [`DigestWidth::Digest256`], e.g.
+/// `PublicParams::for_fri(params, n0).with_digest(DigestWidth::Digest256)`.
+pub fn try_deep_fri_prove_conservative(
+    f0: Vec<F>,
+    domain0: FriDomain,
+    params: &DeepFriParams,
+    trace_root: Option<Digest256>,
+) -> Result<DeepFriProof<Digest256>, ParamError> {
+    prove_hooked(f0, domain0, params, trace_root, &mut ()).map_err(ProveError::uncancelled)
+}
+
+/// As [`try_deep_fri_prove_with_trace_root`], reporting the finished
+/// fraction of the current phase to `on_progress` after every layer and
+/// giving up with [`ProveError::Cancelled`] at the next such point once
+/// `cancel` is set.
+pub fn deep_fri_prove_with_progress(
+    f0: Vec<F>,
+    domain0: FriDomain,
+    params: &DeepFriParams,
+    trace_root: Option<F>,
+    on_progress: impl FnMut(ProverPhase, f32),
+    cancel: &AtomicBool,
+) -> Result<DeepFriProof, ProveError> {
+    prove_hooked(f0, domain0, params, trace_root, &mut Progress { on_progress, cancel })
+}
+
+pub(crate) fn prove_hooked<N: MerkleNode>(
+    f0: Vec<F>,
+    domain0: FriDomain,
+    params: &DeepFriParams,
+    trace_root: Option<N>,
+    hook: &mut impl ProverHook,
+) -> Result<DeepFriProof<N>, ProveError> {
+    prove_hooked_over(f0, domain0, None, params, trace_root, hook)
+}
+
+/// [`prove_hooked`], reusing `layer0`'s powers of ω when the caller has
+/// them; `layer0` must be `domain0`.
+fn prove_hooked_over<N: MerkleNode>(
+    f0: Vec<F>,
+    domain0: FriDomain,
+    layer0: Option<&DomainH>,
+    params: &DeepFriParams,
+    trace_root: Option<N>,
+    hook: &mut impl ProverHook,
+) -> Result<DeepFriProof<N>, ProveError> {
+    debug_assert!(layer0.is_none_or(|dom| dom.n == domain0.size && dom.omega == domain0.omega));
+    let n0 = domain0.size;
+    params.validate(n0)?;
+    if f0.len() != n0 {
+        return Err(ParamError::CodewordLength { len: f0.len(), n0 }.into());
+    }
+    if domain0.omega != radix2_generator(n0) {
+        return Err(ParamError::DomainGenerator { n0 }.into());
+    }
+
     // ------------------------
     // Build prover state + transcript
     // ------------------------
 
     let prover_params = FriProverParams {
         schedule: params.schedule.clone(),
+        r: params.r,
         seed_z: params.seed_z,
+        folding_mode: params.folding_mode,
+        fold_check: params.fold_check,
+        grinding_bits: params.grinding_bits,
+        query_sampling: params.query_sampling,
+        index_chaining: params.index_chaining,
+        transcript: params.transcript,
     };
 
     // ✅ FRI internally derives z_fp3 via Fiat–Shamir
-    let st = fri_build_transcript(
+    let st = build_transcript_hooked(
         f0,
         domain0,
+        layer0,
         &prover_params,
-    );
+        hook,
+    )
+    .ok_or(ProveError::Cancelled)?;
 
     // ------------------------
-    // Fiat–Shamir seed for queries
+    // Meta-commitment + Fiat–Shamir seed for queries
     // ------------------------
 
-    let roots_seed = fs_seed_from_roots(
-        &st.transcript
-            .layers
-            .iter()
-            .map(|l| l.root)
-            .collect::<Vec<_>>(),
-    );
+    hook.enter(ProverPhase::Queries);
+    checkpoint(hook, ProverPhase::Queries, 0, 2).ok_or(ProveError::Cancelled)?;
+
+    let layer_roots: Vec<N> = st.transcript.layers.iter().map(|l| l.root).collect();
+    let proof_root = deep_fri_proof_root(trace_root, &layer_roots);
+    let (pow_nonce, roots_seed) = grind_query_seed::<N>(params.transcript, &proof_root, params.grinding_bits)?;
 
     // ------------------------
     // Generate query openings + Merkle proofs
     // ------------------------
 
     let (query_refs, roots, layer_proofs) =
-        fri_prove_queries(&st, params.r, roots_seed);
+        prove_queries(
+            &st,
+            params.r,
+            roots_seed,
+            params.fold_check,
+            params.query_sampling,
+            params.index_chaining,
+        );
+    checkpoint(hook, ProverPhase::Queries, 1, 2).ok_or(ProveError::Cancelled)?;
 
     // ------------------------
     // Materialize query payloads
     // ------------------------
 
-    let mut queries = Vec::with_capacity(params.r);
-
-    for q in query_refs {
-        let mut payloads = Vec::with_capacity(st.transcript.schedule.len());
-
-        for (ell, rref) in q.per_layer_refs.iter().enumerate() {
-            // x_i must match the prover's q_i construction
-            let omega = st.omega_layers[ell];
-            let x_i = omega.pow([rref.i as u64]);
-
-            // ✅ Extract full Fp³ quotient
-            let q_fp3 = st.q_layers[ell][rref.i];
-
-            payloads.push(LayerOpenPayload {
-                f_i: st.f_layers[ell][rref.i],
-                f_0: st.f_layers[ell][0],
-                s_i: st.s_layers[ell][rref.i],
-
-                // ✅ CHANGED: bind all Fp³ coordinates
-                q_a0: q_fp3.a0,
-                q_a1: q_fp3.a1,
-                q_a2: q_fp3.a2,
-
-                x_i,
-                f_parent_b: st.f_layers[ell + 1][rref.parent_index],
-                s_parent_b: st.s_layers[ell + 1][rref.parent_index],
-            });
-        }
-
-        queries.push(FriQueryPayload {
-            per_layer_refs: q.per_layer_refs,
-            per_layer_payloads: payloads,
-            final_index: q.final_index,
-            final_pair: q.final_pair,
-        });
-    }
+    let queries = materialize_queries(&st, query_refs);
+    hook.step(ProverPhase::Queries, 2, 2);
 
     // ------------------------
     // Return proof
     // ------------------------
 
-    DeepFriProof {
+    Ok(DeepFriProof {
+        proof_root,
+        fs: fri_fs_binding(params.transcript),
+        pow_nonce,
+        trace_root,
         roots,
         layer_proofs,
         queries,
         n0: domain0.size,
         omega0: domain0.omega,
-    }
+    })
 }
 
-pub fn deep_fri_proof_size_bytes(proof: &DeepFriProof) -> usize {
+pub fn deep_fri_proof_size_bytes<N: MerkleNode>(proof: &DeepFriProof<N>) -> usize {
     const FIELD_BYTES: usize = 8;   // Goldilocks = 64-bit field
     const INDEX_BYTES: usize = 8;   // fixed-width index serialization
+    let node_bytes = N::WIDTH.bytes();
 
     let mut bytes = 0usize;
 
+    // ----------------------------------------
+    // Header: proof_root, transcript binding (+ trace root)
+    // ----------------------------------------
+    bytes += 32;
+    bytes += 1 + 1 + proof.fs.backend.len(); // version, length-prefixed name
+    bytes += proof.fs.params_digest.map_or(1, |d| 1 + d.len());
+    bytes += 8; // pow_nonce
+    if proof.trace_root.is_some() {
+        bytes += node_bytes;
+    }
+
     // ----------------------------------------
     // Merkle roots
     // ----------------------------------------
-    bytes += proof.roots.len() * FIELD_BYTES;
+    bytes += proof.roots.len() * node_bytes;
 
     // ----------------------------------------
     // Query payloads
@@ -493,14 +954,31 @@ pub fn deep_fri_proof_size_bytes(proof: &DeepFriProof) -> usize {
     // Each payload contains:
     // f_i, f_0, s_i,
     // q_a0, q_a1, q_a2,
-    // x_i,
     // f_parent_b, s_parent_b
-    // = 9 field elements
+    // = 8 field elements
     for q in &proof.queries {
-        bytes += q.per_layer_payloads.len() * 9 * FIELD_BYTES;
+        bytes += q.per_layer_payloads.len() * 8 * FIELD_BYTES;
 
         // final_pair (2 field elements)
         bytes += 2 * FIELD_BYTES;
+
+        // Full-coset openings: 5 leaf fields + Merkle opening each
+        for coset in &q.per_layer_cosets {
+            for c in coset {
+                bytes += 5 * FIELD_BYTES + node_bytes + INDEX_BYTES;
+                for level in &c.opening.path {
+                    bytes += level.len() * node_bytes;
+                }
+            }
+        }
+
+        // Re-randomized parents: leaf values + Merkle opening each
+        for p in &q.per_layer_parents {
+            bytes += p.values.len() * FIELD_BYTES + node_bytes + INDEX_BYTES;
+            for level in &p.opening.path {
+                bytes += level.len() * node_bytes;
+            }
+        }
     }
 
     // ----------------------------------------
@@ -509,15 +987,15 @@ pub fn deep_fri_proof_size_bytes(proof: &DeepFriProof) -> usize {
     for layer in &proof.layer_proofs.layers {
         for opening in &layer.openings {
 
-            // Leaf field element
-            bytes += FIELD_BYTES;
+            // Leaf digest
+            bytes += node_bytes;
 
             // Opening index
             bytes += INDEX_BYTES;
 
             // All siblings at every level
             for level in &opening.path {
-                bytes += level.len() * FIELD_BYTES;
+                bytes += level.len() * node_bytes;
             }
         }
     }
@@ -529,56 +1007,42 @@ pub fn deep_fri_proof_size_bytes(proof: &DeepFriProof) -> usize {
 
 fn bind_statement_to_transcript(
     tr: &mut Transcript,
-    schedule: &[usize],
-    n0: usize,
+    public: &PublicParams,
     seed_z: u64,
 ) {
     // Domain separation for statement binding
     tr.absorb_bytes(b"DEEP-FRI-STATEMENT");
 
-    // Initial domain size
-    tr.absorb_field(F::from(n0 as u64));
-
-    // Folding schedule
-    tr.absorb_field(F::from(schedule.len() as u64));
-    for &m in schedule {
-        tr.absorb_field(F::from(m as u64));
-    }
+    // Field, Poseidon instance, Merkle shapes, schedule, rate, queries and
+    // fold rule, all through one canonical digest
+    tr.absorb_bytes(&public.digest());
 
     // Seed used to derive z_ℓ
     tr.absorb_field(F::from(seed_z));
 }
 
-fn compute_q_layer(
-    f_l: &[F],
+pub fn fri_fold_layer(
+    evals: &[F],
     z_l: F,
-    omega: F,
+    folding_factor: usize,
 ) -> Vec<F> {
-    let n = f_l.len();
-    let mut q = Vec::with_capacity(n);
-
-    let mut x = F::one();
-    for i in 0..n {
-        let denom = x - z_l;
-        // denom ≠ 0 because z_l ∉ Hℓ (already ensured)
-        q.push((f_l[i] - f_l[0]) * denom.inverse().unwrap());
-        x *= omega;
-    }
-
-    q
+    fri_fold_layer_with_mode(evals, z_l, folding_factor, FoldingMode::PowersOfZ)
 }
 
-pub fn fri_fold_layer(
+pub fn fri_fold_layer_with_mode(
     evals: &[F],
     z_l: F,
     folding_factor: usize,
+    mode: FoldingMode,
 ) -> Vec<F> {
-    let domain_size = evals.len();
-    let domain = GeneralEvaluationDomain::<F>::new(domain_size)
-        .expect("Domain size must be a power of two.");
-    let domain_generator = domain.group_gen();
+    let domain_generator = radix2_generator(evals.len());
 
-    fri_fold_layer_impl(evals, z_l, domain_generator, folding_factor)
+    match mode {
+        FoldingMode::PowersOfZ => fri_fold_layer_impl(evals, z_l, domain_generator, folding_factor),
+        FoldingMode::CosetInterpolation => {
+            fri_fold_layer_coset_impl(evals, z_l, domain_generator, folding_factor)
+        }
+    }
 }
 
 // -----------------------------------------------------------------------------
@@ -590,8 +1054,23 @@ pub fn fri_build_transcript(
     domain0: FriDomain,
     params: &FriProverParams,
 ) -> FriProverState {
+    build_transcript_hooked(f0, domain0, None, params, &mut ())
+        .unwrap_or_else(|| unreachable!("proof cancelled without a cancel flag"))
+}
+
+/// `None` once `hook` cancels. The layer phase has a step per fold and per
+/// s-layer, the Merkle phase one per tree.
+fn build_transcript_hooked<N: MerkleNode>(
+    f0: Vec<F>,
+    domain0: FriDomain,
+    layer0: Option<&DomainH>,
+    params: &FriProverParams,
+    hook: &mut impl ProverHook,
+) -> Option<FriProverState<N>> {
     let schedule = params.schedule.clone();
     let l = schedule.len();
+    hook.enter(ProverPhase::Layers);
+    checkpoint(hook, ProverPhase::Layers, 0, 2 * l)?;
 
     let mut f_layers = Vec::with_capacity(l + 1);
     let mut s_layers = Vec::with_capacity(l + 1);
@@ -603,24 +1082,29 @@ pub fn fri_build_transcript(
     let mut cur_size = domain0.size;
     f_layers.push(cur_f.clone());
 
-    let mut tr = Transcript::new(b"FRI/FS", transcript_params());
-
-    bind_statement_to_transcript(
-        &mut tr,
-        &schedule,
-        domain0.size,
-        params.seed_z,
-    );
+    let mut tr = params.transcript.transcript();
+
+    let public = PublicParams {
+        grinding_bits: params.grinding_bits,
+        query_sampling: params.query_sampling,
+        index_chaining: params.index_chaining,
+        transcript: params.transcript,
+        ..PublicParams::new(
+            domain0.size,
+            &schedule,
+            params.r,
+            params.folding_mode,
+            params.fold_check,
+        )
+    }
+    .with_digest(N::WIDTH);
+    bind_statement_to_transcript(&mut tr, &public, params.seed_z);
 
     // ------------------------------------------------------------
     // ✅ SINGLE DEEP CHALLENGE (Fiat–Shamir, prover == verifier)
     // ------------------------------------------------------------
 
-    let z_fp3 = Fp3 {
-        a0: tr.challenge(b"z_fp3/a0"),
-        a1: tr.challenge(b"z_fp3/a1"),
-        a2: tr.challenge(b"z_fp3/a2"),
-    };
+    let z_fp3: Fp3 = tr.challenge_ext3(b"z_fp3");
 
     // ------------------------------------------------------------
     // Build FRI layers
@@ -630,18 +1114,21 @@ pub fn fri_build_transcript(
         // ✅ Same z_fp3 reused for all layers
         z_layers_fp3.push(z_fp3);
 
-        let dom = Domain::<F>::new(cur_size).unwrap();
-        let omega = dom.group_gen;
+        let omega = radix2_generator(cur_size);
         omega_layers.push(omega);
 
         // ✅ DEEP quotient in Fp³
-        let q = compute_q_layer_fp3(&cur_f, z_fp3, omega);
+        let q = match layer0 {
+            Some(dom) if ell == 0 => compute_q_layer(&cur_f, z_fp3, dom.omega_pows.iter().copied()),
+            _ => compute_q_layer(&cur_f, z_fp3, core::iter::successors(Some(F::one()), |&x| Some(x * omega))),
+        };
         q_layers.push(q);
 
-        // ✅ Standard FRI folding using z.a0
-        cur_f = fri_fold_layer(&cur_f, z_fp3.a0, m);
+        // ✅ FRI folding using z's first coordinate under the selected fold rule
+        cur_f = fri_fold_layer_with_mode(&cur_f, z_fp3.coord(0), m, params.folding_mode);
         cur_size /= m;
         f_layers.push(cur_f.clone());
+        checkpoint(hook, ProverPhase::Layers, ell + 1, 2 * l)?;
     }
 
     // ------------------------------------------------------------
@@ -649,11 +1136,13 @@ pub fn fri_build_transcript(
     // ------------------------------------------------------------
 
     for ell in 0..l {
-        s_layers.push(compute_s_layer(
+        s_layers.push(compute_s_layer_with_mode(
             &f_layers[ell],
-            z_fp3.a0,
+            z_fp3.coord(0),
             schedule[ell],
+            params.folding_mode,
         ));
+        checkpoint(hook, ProverPhase::Layers, l + ell + 1, 2 * l)?;
     }
     s_layers.push(vec![F::zero(); f_layers[l].len()]);
 
@@ -661,6 +1150,9 @@ pub fn fri_build_transcript(
     // Merkle commitments
     // ------------------------------------------------------------
 
+    hook.enter(ProverPhase::Merkle);
+    checkpoint(hook, ProverPhase::Merkle, 0, l)?;
+
     let roots_seed = tr.challenge(ds::FRI_SEED);
 
     let mut trace_hash = [0u8; 32];
@@ -669,95 +1161,169 @@ pub fn fri_build_transcript(
         .unwrap();
 
     let mut layers = Vec::with_capacity(l + 1);
+    let mut trees = Vec::with_capacity(l);
     for ell in 0..l {
         let n = f_layers[ell].len();
         let m_ell = schedule[ell];
-        let arity = pick_arity_for_layer(n, m_ell).max(2);
-        let depth = merkle_depth(n, arity);
-
-        let cfg = MerkleChannelCfg::new(vec![arity; depth], ell as u64);
-        let mut tree = MerkleTreeChannel::new(cfg, trace_hash);
+        let cfg = MerkleChannelCfg::for_leaves(n, ArityPolicy::UpTo(m_ell), ell as u64).with_digest(N::WIDTH);
+        let mut tree = MerkleTreeChannel::<N>::new(cfg, trace_hash);
 
         for i in 0..n {
-            let q = q_layers[ell][i];
-            tree.push_leaf(&[
-                f_layers[ell][i],
-                s_layers[ell][i],
-                q.a0,
-                q.a1,
-                q.a2,
-            ]);
+            tree.push_encoded(&(f_layers[ell][i], s_layers[ell][i], q_layers[ell][i]));
         }
 
-        let root = tree.finalize();
+        let root = tree.finalize().expect("FRI layer fits its Merkle config");
         layers.push(FriLayerCommitment { n, m: m_ell, root });
+        trees.push(tree);
 
-        eprintln!("[PROVER] z_fp3 = {:?}", z_fp3);
+        diagln!("[PROVER] z_fp3 = {:?}", z_fp3);
+        checkpoint(hook, ProverPhase::Merkle, ell + 1, l)?;
     }
 
-    FriProverState {
+    Some(FriProverState {
         f_layers,
         s_layers,
         q_layers,
         transcript: FriTranscript { schedule, layers },
         omega_layers,
-        z_layers: vec![z_fp3.a0; l],
-    }
+        z_layers: vec![z_fp3.coord(0); l],
+        trees,
+    })
 }
 #[derive(Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerQueryRef {
-    pub i: usize,
+    pub i: u64,
     pub child_pos: usize,
-    pub parent_index: usize,
+    pub parent_index: u64,
     pub parent_pos: usize,
 }
 
 #[derive(Clone)]
-pub struct FriQueryOpenings {
+pub struct FriQueryOpenings<N = F> {
     pub per_layer_refs: Vec<LayerQueryRef>,
-    pub final_index: usize,
+    pub final_index: u64,
     pub final_pair: (F, F),
+    /// Merkle openings of every coset position `b + j·n_next`, j < m, per
+    /// layer; empty unless the full-coset fold check was requested.
+    pub per_layer_coset_openings: Vec<Vec<MerkleOpening<N>>>,
+    /// See [`FriQueryPayload::per_layer_parents`].
+    pub per_layer_parents: Vec<MerkleValueOpening<N>>,
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerOpenPayload {
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub f_i: F,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub f_0: F,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub s_i: F,
 
     // ✅ Full Fp³ quotient
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub q_a0: F,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub q_a1: F,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub q_a2: F,
 
-    pub x_i: F,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub f_parent_b: F,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub s_parent_b: F,
 }
 
+/// Field elements in a committed layer leaf: the `(f, s, q)` tuple each
+/// layer tree pushes, with `q`'s three Fp3 coordinates.
+pub(crate) const LAYER_LEAF_WIDTH: usize = 5;
+
+impl LayerOpenPayload {
+    /// Contents of the committed leaf at the queried position.
+    pub(crate) fn leaf_values(&self) -> [F; LAYER_LEAF_WIDTH] {
+        [self.f_i, self.s_i, self.q_a0, self.q_a1, self.q_a2]
+    }
+}
+
+/// One opened child-layer leaf of a query's coset: the full committed leaf
+/// contents `(f, s, q)` plus its Merkle opening.
 #[derive(Clone)]
-pub struct FriQueryPayload {
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct CosetLeafOpening<N = F> {
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub f: F,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub s: F,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub q_a0: F,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub q_a1: F,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub q_a2: F,
+    pub opening: MerkleOpening<N>,
+}
+
+impl<N> CosetLeafOpening<N> {
+    pub(crate) fn leaf_values(&self) -> [F; LAYER_LEAF_WIDTH] {
+        [self.f, self.s, self.q_a0, self.q_a1, self.q_a2]
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct FriQueryPayload<N = F> {
     pub per_layer_refs: Vec<LayerQueryRef>,
     pub per_layer_payloads: Vec<LayerOpenPayload>,
-    pub final_index: usize,
+    pub final_index: u64,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub final_pair: (F, F),
+    /// Per layer, the m coset leaves ordered by j; empty under
+    /// `FoldCheck::CommittedS`.
+    pub per_layer_cosets: Vec<Vec<CosetLeafOpening<N>>>,
+    /// Under [`IndexChaining::Rerandomized`], entry ℓ opens layer ℓ+1 at
+    /// the fold parent of layer ℓ's query, for ℓ + 1 < L; empty otherwise.
+    pub per_layer_parents: Vec<MerkleValueOpening<N>>,
 }
 
 #[derive(Clone)]
-pub struct LayerProof {
-    pub openings: Vec<MerkleOpening>, // one per query
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct LayerProof<N = F> {
+    pub openings: Vec<MerkleOpening<N>>, // one per query
 }
 
-pub struct FriLayerProofs {
-    pub layers: Vec<LayerProof>,
+#[derive(Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct FriLayerProofs<N = F> {
+    pub layers: Vec<LayerProof<N>>,
 }
 
 pub fn fri_prove_queries(
     st: &FriProverState,
     r: usize,
     roots_seed: F,
+    fold_check: FoldCheck,
+    sampling: QuerySampling,
+    chaining: IndexChaining,
 ) -> (Vec<FriQueryOpenings>, Vec<F>, FriLayerProofs) {
+    prove_queries(st, r, QuerySeed::Field(roots_seed, TranscriptHash::Poseidon), fold_check, sampling, chaining)
+}
+
+fn prove_queries<N: MerkleNode>(
+    st: &FriProverState<N>,
+    r: usize,
+    roots_seed: QuerySeed,
+    fold_check: FoldCheck,
+    sampling: QuerySampling,
+    chaining: IndexChaining,
+) -> (Vec<FriQueryOpenings<N>>, Vec<N>, FriLayerProofs<N>) {
     let L = st.transcript.schedule.len();
+    let base_indices = query_base_indices(roots_seed, r, st.transcript.layers[0].n as u64, sampling)
+        .expect("more queries than distinct layer-0 indices");
     let mut all_refs = Vec::with_capacity(r);
 
     // ------------------------
@@ -768,40 +1334,37 @@ pub fn fri_prove_queries(
         let mut per_layer_refs = Vec::with_capacity(L);
 
         // Sample initial index i_0
-        let mut i = {
-            let layer0 = &st.transcript.layers[0];
-            let n_pow2 = layer0.n.next_power_of_two();
-            let seed = index_seed(roots_seed, 0, q);
-            index_from_seed(seed, n_pow2) % layer0.n
-        };
+        let mut i = base_indices[q];
 
-        // Walk down the FRI layers (STRIDED)
+        // Walk down the FRI layers
         for ell in 0..L {
             let n = st.transcript.layers[ell].n;
             let m = st.transcript.schedule[ell];
-            let n_next = n / m;
+            let n_next = (n / m) as u64;
 
             per_layer_refs.push(LayerQueryRef {
                 i,
-                child_pos: i % m,          // informational only
+                child_pos: (i % m as u64) as usize, // informational only
                 parent_index: i % n_next,  // ✅ STRIDED parent
                 parent_pos: 0,
             });
 
             // ✅ Chain index correctly for next layer
-            i = i % n_next;
+            i = next_query_index(roots_seed, q, ell, i, n_next, L, chaining);
         }
 
         // ✅ FINAL CONSTANCY: compare f_L[i] with f_L[0]
-        let final_i = i;
+        let final_i = i as usize;
 
         all_refs.push(FriQueryOpenings {
             per_layer_refs,
-            final_index: final_i,
+            final_index: i,
             final_pair: (
                 st.f_layers[L][final_i], // f_L[i]
                 st.f_layers[L][0],       // f_L[0]
             ),
+            per_layer_coset_openings: Vec::new(),
+            per_layer_parents: Vec::new(),
         });
     }
 
@@ -809,40 +1372,37 @@ pub fn fri_prove_queries(
     // Merkle openings
     // ------------------------
 
-    let mut trace_hash = [0u8; 32];
-    roots_seed
-        .serialize_uncompressed(&mut trace_hash[..])
-        .unwrap();
-
     let mut layer_proofs = Vec::with_capacity(L);
 
     for ell in 0..L {
         let layer = &st.transcript.layers[ell];
+        let tree = &st.trees[ell];
 
-        let arity = pick_arity_for_layer(layer.n, layer.m).max(2);
-        let depth = merkle_depth(layer.n, arity);
-
-        let cfg = MerkleChannelCfg::new(vec![arity; depth], ell as u64);
-        let mut tree = MerkleTreeChannel::new(cfg, trace_hash);
-
-        // ✅ Commit prover state exactly
-        for i in 0..layer.n {
-            let q = st.q_layers[ell][i];
-            tree.push_leaf(&[
-                st.f_layers[ell][i],
-                st.s_layers[ell][i],
-                q.a0,
-                q.a1,
-                q.a2,
-            ]);
-        }
+        let indices: Vec<usize> = all_refs.iter().map(|q| q.per_layer_refs[ell].i as usize).collect();
+        let openings = open_indices(tree, &indices);
 
-        tree.finalize();
+        // Layer ℓ's leaf at the previous layer's fold parent
+        if chaining == IndexChaining::Rerandomized && ell > 0 {
+            for refs in all_refs.iter_mut() {
+                let p = refs.per_layer_refs[ell - 1].parent_index as usize;
+                let leaf = (st.f_layers[ell][p], st.s_layers[ell][p], st.q_layers[ell][p]);
+                refs.per_layer_parents.push(tree.open_encoded(p, &leaf).expect("parent index inside the layer"));
+            }
+        }
 
-        let mut openings = Vec::with_capacity(r);
-        for q in 0..r {
-            let idx = all_refs[q].per_layer_refs[ell].i;
-            openings.push(tree.open(idx));
+        if fold_check == FoldCheck::RecomputeFromCoset {
+            let n_next = layer.n / layer.m;
+            let coset_indices: Vec<usize> = all_refs
+                .iter()
+                .flat_map(|refs| {
+                    let b = refs.per_layer_refs[ell].parent_index as usize;
+                    (0..layer.m).map(move |j| b + j * n_next)
+                })
+                .collect();
+            let mut cosets = open_indices(tree, &coset_indices).into_iter();
+            for refs in all_refs.iter_mut() {
+                refs.per_layer_coset_openings.push(cosets.by_ref().take(layer.m).collect());
+            }
         }
 
         layer_proofs.push(LayerProof { openings });
@@ -853,55 +1413,449 @@ pub fn fri_prove_queries(
     (all_refs, roots, FriLayerProofs { layers: layer_proofs })
 }
 
-#[derive(Clone)]
-pub struct DeepFriParams {
-    pub schedule: Vec<usize>,
-    pub r: usize,
-    pub seed_z: u64,
-}
+/// Openings at `indices` in order, spread over the rayon pool once there
+/// are enough of them.
+fn open_indices<N: MerkleNode>(tree: &MerkleTreeChannel<N>, indices: &[usize]) -> Vec<MerkleOpening<N>> {
+    if enable_parallel(ParallelOp::Open, indices.len()) {
+        let out = tree.open_many(indices).expect("query indices inside the layer");
+        if parallel_config().strict_determinism {
+            let same = out.iter().zip(indices).all(|(o, &i)| {
+                let s = tree.open(i).unwrap();
+                (o.index, o.leaf, &o.path) == (s.index, s.leaf, &s.path)
+            });
+            assert_deterministic(ParallelOp::Open, same);
+        }
+        return out;
+    }
+    indices.iter().map(|&i| tree.open(i).expect("query index inside the layer")).collect()
+}
+
+/// Payloads for every query, in order.
+fn materialize_queries<N: MerkleNode>(
+    st: &FriProverState<N>,
+    query_refs: Vec<FriQueryOpenings<N>>,
+) -> Vec<FriQueryPayload<N>> {
+    if enable_parallel(ParallelOp::Open, query_refs.len()) {
+        #[cfg(feature = "parallel")]
+        {
+            return query_refs.into_par_iter().map(|q| materialize_query(st, q)).collect();
+        }
+    }
+    query_refs.into_iter().map(|q| materialize_query(st, q)).collect()
+}
+
+/// Attach layer values to one query's openings.
+fn materialize_query<N: MerkleNode>(st: &FriProverState<N>, q: FriQueryOpenings<N>) -> FriQueryPayload<N> {
+    let mut payloads = Vec::with_capacity(st.transcript.schedule.len());
+
+    for (ell, rref) in q.per_layer_refs.iter().enumerate() {
+        // ✅ Extract full Fp³ quotient
+        let (i, parent) = (rref.i as usize, rref.parent_index as usize);
+        let q_fp3 = st.q_layers[ell][i];
+
+        payloads.push(LayerOpenPayload {
+            f_i: st.f_layers[ell][i],
+            f_0: st.f_layers[ell][0],
+            s_i: st.s_layers[ell][i],
+
+            // ✅ CHANGED: bind all Fp³ coordinates
+            q_a0: q_fp3.a0,
+            q_a1: q_fp3.a1,
+            q_a2: q_fp3.a2,
+
+            f_parent_b: st.f_layers[ell + 1][parent],
+            s_parent_b: st.s_layers[ell + 1][parent],
+        });
+    }
+
+    let per_layer_cosets = q
+        .per_layer_coset_openings
+        .into_iter()
+        .enumerate()
+        .map(|(ell, openings)| {
+            openings
+                .into_iter()
+                .map(|opening| {
+                    let idx = opening.index as usize;
+                    let q_fp3 = st.q_layers[ell][idx];
+                    CosetLeafOpening {
+                        f: st.f_layers[ell][idx],
+                        s: st.s_layers[ell][idx],
+                        q_a0: q_fp3.a0,
+                        q_a1: q_fp3.a1,
+                        q_a2: q_fp3.a2,
+                        opening,
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    FriQueryPayload {
+        per_layer_refs: q.per_layer_refs,
+        per_layer_payloads: payloads,
+        final_index: q.final_index,
+        final_pair: q.final_pair,
+        per_layer_cosets,
+        per_layer_parents: q.per_layer_parents,
+    }
+}
+
+/// How a coset of `m` evaluations is folded into one value of the next layer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum FoldingMode {
+    /// `Σ_j f[b + j·n_next] · z^j` — the original production fold.
+    #[default]
+    PowersOfZ,
+    /// Interpolate `f` over the coset `x_b·⟨ζ⟩` (ζ a primitive m-th root)
+    /// and evaluate at `z·x_b⁻¹`: the fold analysed in the FRI literature.
+    CosetInterpolation,
+}
+
+impl FoldingMode {
+    pub(crate) fn id(self) -> u64 {
+        match self {
+            FoldingMode::PowersOfZ => 0,
+            FoldingMode::CosetInterpolation => 1,
+        }
+    }
+}
+
+/// How the verifier checks each query's fold step.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum FoldCheck {
+    /// Compare the committed `s_i` against the parent's `f`. Cheap, but `s`
+    /// is whatever the prover committed.
+    #[default]
+    CommittedS,
+    /// Open all m coset positions of the child layer and recompute the fold
+    /// from their `f` values.
+    RecomputeFromCoset,
+}
+
+impl FoldCheck {
+    pub(crate) fn id(self) -> u64 {
+        match self {
+            FoldCheck::CommittedS => 0,
+            FoldCheck::RecomputeFromCoset => 1,
+        }
+    }
+}
+
+/// How the r layer-0 query indices are drawn from the query seed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum QuerySampling {
+    /// One seed-derived index per query; repeats are possible and add
+    /// nothing.
+    #[default]
+    Independent,
+    /// Redraw an index that repeats an earlier one, with the next seed
+    /// challenge, until r distinct indices are found. Needs `r ≤ n₀`.
+    Distinct,
+}
+
+impl QuerySampling {
+    pub(crate) fn id(self) -> u64 {
+        match self {
+            QuerySampling::Independent => 0,
+            QuerySampling::Distinct => 1,
+        }
+    }
+}
+
+/// How a query's index moves from layer ℓ to layer ℓ+1.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum IndexChaining {
+    /// `i_{ℓ+1} = i_ℓ mod n_{ℓ+1}`: the parent of the checked fold, so one
+    /// base index fixes the whole path and each layer's opening doubles as
+    /// the previous layer's parent.
+    #[default]
+    Strided,
+    /// `i_{ℓ+1} = (i_ℓ mod n_{ℓ+1} + c_{q,ℓ+1}) mod n_{ℓ+1}` with a fresh
+    /// seed-derived offset. Each layer is spot-checked at its own position,
+    /// and the fold's parent is opened separately
+    /// ([`FriQueryPayload::per_layer_parents`]). The final layer is reached
+    /// at the last parent, without an offset.
+    Rerandomized,
+}
+
+impl IndexChaining {
+    pub(crate) fn id(self) -> u64 {
+        match self {
+            IndexChaining::Strided => 0,
+            IndexChaining::Rerandomized => 1,
+        }
+    }
+}
+
+/// Hash every FRI transcript runs over: the statement challenges, the
+/// query seed, grinding and the query indices.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum TranscriptHash {
+    /// The t = 17 Poseidon sponge: the original transcript.
+    #[default]
+    Poseidon,
+    /// Keccak-256 as the EVM computes it ([`transcript::Keccak256Backend`]),
+    /// so a contract can replay the transcript; see
+    /// [`crate::interop::solidity`].
+    Keccak256,
+}
+
+impl TranscriptHash {
+    pub(crate) fn id(self) -> u64 {
+        match self {
+            TranscriptHash::Poseidon => 0,
+            TranscriptHash::Keccak256 => 1,
+        }
+    }
+
+    /// A fresh FRI transcript over this hash.
+    pub(crate) fn transcript(self) -> Transcript {
+        let hash = match self {
+            TranscriptHash::Poseidon => FsHash::Poseidon,
+            TranscriptHash::Keccak256 => FsHash::Keccak256,
+        };
+        Transcript::from_config(&FsConfig::new(hash), b"FRI/FS")
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeepFriParams {
+    pub schedule: Vec<usize>,
+    pub r: usize,
+    pub seed_z: u64,
+    pub folding_mode: FoldingMode,
+    pub fold_check: FoldCheck,
+    /// Proof-of-work on the query seed: the seed derived after the proof
+    /// root and [`DeepFriProof::pow_nonce`] must have this many trailing
+    /// zero bits. 0 disables grinding.
+    pub grinding_bits: u32,
+    pub query_sampling: QuerySampling,
+    pub index_chaining: IndexChaining,
+    pub transcript: TranscriptHash,
+}
+
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct DeepFriProof<N = F> {
+    /// Header: `deep_fri_proof_root(trace_root, roots)`.
+    pub proof_root: [u8; 32],
+    /// Transcript configuration the challenges were drawn under, checked
+    /// against the verifier's before anything else.
+    pub fs: FsBinding,
+    /// Grinding nonce absorbed before the query seed; 0 without grinding.
+    pub pow_nonce: u64,
+    /// External trace commitment bound into `proof_root`, if any. Callers
+    /// holding their own commitment should compare it against this.
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub trace_root: Option<N>,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub roots: Vec<N>,
+    pub layer_proofs: FriLayerProofs<N>,
+    pub queries: Vec<FriQueryPayload<N>>,
+    pub n0: usize,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub omega0: F,
+}
+
+/// Per-layer data [`deep_fri_verify`] needs for every query.
+#[derive(Clone)]
+pub struct VerifierLayer {
+    /// Points in the layer's domain.
+    pub n: usize,
+    /// Fold arity out of this layer.
+    pub m: usize,
+    /// Merkle shape the layer was committed under.
+    pub cfg: MerkleChannelCfg,
+    /// Generator `ω_ℓ` of the layer's domain.
+    pub omega: F,
+    /// `ζ^j`, j < m, for coset-interpolation folds.
+    zetas: Vec<F>,
+    /// `z^j`, j < m, for powers-of-z folds.
+    z_pows: Vec<F>,
+}
+
+/// Everything the verifier derives from the public parameters alone:
+/// layer sizes and Merkle shapes, domain generators and fold tables, the
+/// transcript's Poseidon instance and the statement challenges. Build it
+/// once per parameter set and check any number of proofs against it.
+#[derive(Clone)]
+pub struct VerifierKey {
+    pub params: DeepFriParams,
+    pub public: PublicParams,
+    pub layers: Vec<VerifierLayer>,
+    pub poseidon: PoseidonParams,
+    omega_ladder: OmegaLadder,
+    pub(crate) z_fp3: Fp3,
+    trace_hash: [u8; 32],
+}
+
+impl VerifierKey {
+    /// Key for proofs under `public` with statement seed `seed_z`.
+    pub fn new(public: &PublicParams, seed_z: u64) -> Result<Self, ParamError> {
+        let params = public.fri_params(seed_z);
+        params.validate(public.n0)?;
+        if public.merkle.len() != params.schedule.len() {
+            return Err(ParamError::MerkleLayers {
+                layers: public.merkle.len(),
+                schedule: params.schedule.len(),
+            });
+        }
+
+        let poseidon = transcript_params();
+        let mut tr = public.transcript.transcript();
+        bind_statement_to_transcript(&mut tr, public, seed_z);
+        let z_fp3: Fp3 = tr.challenge_ext3(b"z_fp3");
+        let mut trace_hash = [0u8; 32];
+        tr.challenge(ds::FRI_SEED)
+            .serialize_uncompressed(&mut trace_hash[..])
+            .unwrap();
+
+        let sizes = layer_sizes_from_schedule(public.n0, &params.schedule);
+        let layers = params
+            .schedule
+            .iter()
+            .zip(&public.merkle)
+            .enumerate()
+            .map(|(ell, (&m, cfg))| {
+                let omega = radix2_generator(sizes[ell]);
+                VerifierLayer {
+                    n: sizes[ell],
+                    m,
+                    cfg: cfg.clone(),
+                    omega,
+                    zetas: coset_roots(omega, sizes[ell] / m, m),
+                    z_pows: build_z_pows(z_fp3.coord(0), m),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            omega_ladder: OmegaLadder::new(radix2_generator(public.n0), public.n0),
+            params,
+            public: public.clone(),
+            layers,
+            poseidon,
+            z_fp3,
+            trace_hash,
+        })
+    }
 
-pub struct DeepFriProof {
-    pub roots: Vec<F>,
-    pub layer_proofs: FriLayerProofs,
-    pub queries: Vec<FriQueryPayload>,
-    pub n0: usize,
-    pub omega0: F,
+    /// Key for `params` over a domain of `n0` points.
+    pub fn for_fri(params: &DeepFriParams, n0: usize) -> Result<Self, ParamError> {
+        Self::new(&PublicParams::for_fri(params, n0), params.seed_z)
+    }
+
+    pub fn n0(&self) -> usize {
+        self.public.n0
+    }
 }
 
-pub fn deep_fri_verify(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
+/// Proofs over either node width; the key's Merkle shapes must carry the
+/// proof's [`MerkleNode::WIDTH`].
+pub fn deep_fri_verify<N: MerkleNode>(key: &VerifierKey, proof: &DeepFriProof<N>) -> bool {
+    if proof.n0 != key.n0() {
+        diagln!("[FAIL][PARAMS] proof over {} points, key over {}", proof.n0, key.n0());
+        return false;
+    }
+    if key.layers.iter().any(|l| l.cfg.digest != N::WIDTH) {
+        diagln!("[FAIL][PARAMS] proof nodes are {:?}, key expects otherwise", N::WIDTH);
+        return false;
+    }
+    let params = &key.params;
+    if proof.fs != fri_fs_binding(params.transcript) {
+        diagln!(
+            "[FAIL][TRANSCRIPT] proof drawn under {}, verifier uses {}",
+            proof.fs,
+            fri_fs_binding(params.transcript)
+        );
+        return false;
+    }
     let L = params.schedule.len();
-    let sizes = layer_sizes_from_schedule(proof.n0, &params.schedule);
 
     // ----------------------------------------
-    // Reconstruct Fiat–Shamir transcript
+    // Meta-commitment header
     // ----------------------------------------
 
-    let mut tr = Transcript::new(b"FRI/FS", transcript_params());
+    if deep_fri_proof_root(proof.trace_root, &proof.roots) != proof.proof_root {
+        diagln!("[FAIL][PROOF ROOT] header does not commit to the layer roots");
+        return false;
+    }
+
+    // ----------------------------------------
+    // Query seed: grinding, then index derivation
+    // ----------------------------------------
+
+    if params.grinding_bits > MAX_GRINDING_BITS {
+        diagln!("[FAIL][POW] {} grinding bits exceed {}", params.grinding_bits, MAX_GRINDING_BITS);
+        return false;
+    }
+    if proof.queries.len() != params.r
+        || proof.roots.len() != L
+        || proof.layer_proofs.layers.len() != L
+        || proof.layer_proofs.layers.iter().any(|l| l.openings.len() != params.r)
+    {
+        diagln!("[FAIL][SHAPE] query or layer count does not match params");
+        return false;
+    }
+
+    let query_roots_seed =
+        query_seed_for::<N>(params.transcript, &proof.proof_root, params.grinding_bits, proof.pow_nonce);
+    if !meets_grinding(query_roots_seed, params.grinding_bits) {
+        diagln!("[FAIL][POW] nonce={} misses {} bits", proof.pow_nonce, params.grinding_bits);
+        return false;
+    }
 
-    bind_statement_to_transcript(
-        &mut tr,
-        &params.schedule,
-        proof.n0,
-        params.seed_z,
-    );
-
-    // ✅ Reconstruct the SINGLE DEEP challenge z_fp3
-    let z_fp3 = Fp3 {
-        a0: tr.challenge(b"z_fp3/a0"),
-        a1: tr.challenge(b"z_fp3/a1"),
-        a2: tr.challenge(b"z_fp3/a2"),
+    let Some(base_indices) =
+        query_base_indices(query_roots_seed, params.r, proof.n0 as u64, params.query_sampling)
+    else {
+        return false;
     };
+    for (q, (qp, &i0)) in proof.queries.iter().zip(&base_indices).enumerate() {
+        if qp.per_layer_refs.len() != L || qp.per_layer_payloads.len() != L {
+            return false;
+        }
+        let mut i = i0;
+        for (ell, rref) in qp.per_layer_refs.iter().enumerate() {
+            let n_next = (key.layers[ell].n / key.layers[ell].m) as u64;
+            if rref.i != i || rref.parent_index != i % n_next {
+                diagln!("[FAIL][QUERY INDEX] q={} ell={} i={} expected={}", q, ell, rref.i, i);
+                return false;
+            }
+            i = next_query_index(query_roots_seed, q, ell, i, n_next, L, params.index_chaining);
+        }
+        if qp.final_index != i {
+            return false;
+        }
+    }
 
-    let z_layers_fp3 = vec![z_fp3; L];
+    // ----------------------------------------
+    // Fiat–Shamir statement challenges, fixed by the key
+    // ----------------------------------------
 
-    let roots_seed = tr.challenge(ds::FRI_SEED);
+    let z_fp3 = key.z_fp3;
+    let trace_hash = key.trace_hash;
 
-    let mut trace_hash = [0u8; 32];
-    roots_seed
-        .serialize_uncompressed(&mut trace_hash[..])
-        .unwrap();
+    diagln!("[VERIFY] z_fp3 = {:?}", z_fp3);
+
+    // ----------------------------------------
+    // Merkle verification, one batch per layer so that query paths share
+    // the hashes above where they merge
+    // ----------------------------------------
 
-    eprintln!("[VERIFY] z_fp3 = {:?}", z_fp3);
+    for ell in 0..L {
+        let openings = &proof.layer_proofs.layers[ell].openings;
+        if !MerkleTreeChannel::verify_openings(&key.layers[ell].cfg, proof.roots[ell], openings, &trace_hash) {
+            diagln!("[FAIL][MERKLE] ell={}", ell);
+            return false;
+        }
+    }
 
     // ----------------------------------------
     // Query verification
@@ -913,26 +1867,8 @@ pub fn deep_fri_verify(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
         for ell in 0..L {
             let opening = &proof.layer_proofs.layers[ell].openings[q];
 
-            let arity = pick_arity_for_layer(sizes[ell], params.schedule[ell]).max(2);
-            let depth = merkle_depth(sizes[ell], arity);
-            let cfg = MerkleChannelCfg::new(vec![arity; depth], ell as u64);
-
-            // ------------------------
-            // Merkle verification
-            // ------------------------
-
-            if !MerkleTreeChannel::verify_opening(
-                &cfg,
-                proof.roots[ell],
-                opening,
-                &trace_hash,
-            ) {
-                eprintln!(
-                    "[FAIL][MERKLE] q={} ell={} opening_index={}",
-                    q, ell, opening.index
-                );
-                return false;
-            }
+            let layer = &key.layers[ell];
+            let cfg = &layer.cfg;
 
             let rref = &qp.per_layer_refs[ell];
             let pay = &qp.per_layer_payloads[ell];
@@ -942,13 +1878,22 @@ pub fn deep_fri_verify(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
             // ------------------------
 
             if opening.index != rref.i {
-                eprintln!(
+                diagln!(
                     "[FAIL][INDEX BINDING] q={} ell={} opening.index={} rref.i={}",
                     q, ell, opening.index, rref.i
                 );
                 return false;
             }
 
+            // ------------------------
+            // Payload binding: the checked values are the committed leaf
+            // ------------------------
+
+            if opening.leaf != MerkleTreeChannel::<N>::leaf_digest(cfg, rref.i, &pay.leaf_values()) {
+                diagln!("[FAIL][LEAF BINDING] q={} ell={} i={}", q, ell, rref.i);
+                return false;
+            }
+
             // ------------------------
             // ✅ DEEP quotient check (Fp³, SINGLE z)
             // ------------------------
@@ -960,18 +1905,18 @@ pub fn deep_fri_verify(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
                 a2: pay.q_a2,
             };
 
-            let num = Fp3::from_base(pay.f_i - pay.f_0);
-            let denom = Fp3::from_base(pay.x_i) - z_layers_fp3[ell];
+            // x_i = ω_ℓ^i from the Merkle-bound index, never from the proof
+            let x_i = key.omega_ladder.pow(opening.index * (proof.n0 / layer.n) as u64);
 
-            if q_fp3 * denom != num {
-                eprintln!(
+            if !deep_quotient_holds(q_fp3, pay.f_i, pay.f_0, x_i, z_fp3) {
+                diagln!(
                     "[FAIL][DEEP-FP3] q={} ell={}\n  f_i={:?}\n  f_0={:?}\n  q_fp3={:?}\n  x_i={:?}\n  z_fp3={:?}",
                     q,
                     ell,
                     pay.f_i,
                     pay.f_0,
                     q_fp3,
-                    pay.x_i,
+                    x_i,
                     z_fp3,
                 );
                 return false;
@@ -980,18 +1925,52 @@ pub fn deep_fri_verify(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
             // ------------------------
             // Fold consistency (STRIDED FRI)
             // ------------------------
+            // s_i was committed under `params.folding_mode`, which is bound
+            // into the transcript, so the parent check is mode-agnostic.
 
-            let n = sizes[ell];
-            let m = params.schedule[ell];
-            let n_next = n / m;
+            let m = layer.m;
+            let n_next = layer.n / m;
 
-            let b = rref.i % n_next;
+            let b = rref.i % n_next as u64;
 
             let s_child = pay.s_i;
             let f_parent_b = pay.f_parent_b;
 
+            // The f the next layer commits at b. Under strided chaining the
+            // next layer's own opening is at b; otherwise b is opened apart.
+            // Either way the fold below only means something if f_parent_b
+            // is that value.
+            let parent_f = if ell + 1 == L {
+                qp.final_pair.0
+            } else if params.index_chaining == IndexChaining::Rerandomized {
+                match qp.per_layer_parents.get(ell) {
+                    Some(po)
+                        if po.index() == b
+                            && po.values.len() == LAYER_LEAF_WIDTH
+                            && MerkleTreeChannel::verify_value_opening(
+                                &key.layers[ell + 1].cfg,
+                                proof.roots[ell + 1],
+                                po,
+                                &trace_hash,
+                            ) =>
+                    {
+                        po.values[0]
+                    }
+                    _ => {
+                        diagln!("[FAIL][PARENT] q={} ell={} b={}", q, ell, b);
+                        return false;
+                    }
+                }
+            } else {
+                qp.per_layer_payloads[ell + 1].f_i
+            };
+            if parent_f != f_parent_b {
+                diagln!("[FAIL][PARENT CHAIN] q={} ell={}", q, ell);
+                return false;
+            }
+
             if s_child != f_parent_b {
-                eprintln!(
+                diagln!(
                     "[FAIL][FOLD] q={} ell={}\n  i={}\n  m={}\n  n_next={}\n  b={}\n  s_child={:?}\n  f_parent_b={:?}",
                     q,
                     ell,
@@ -1004,6 +1983,26 @@ pub fn deep_fri_verify(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
                 );
                 return false;
             }
+
+            if params.fold_check == FoldCheck::RecomputeFromCoset {
+                let Some(coset) = qp.per_layer_cosets.get(ell) else {
+                    diagln!("[FAIL][COSET] q={} ell={} missing coset openings", q, ell);
+                    return false;
+                };
+                if !verify_coset_fold(
+                    layer,
+                    proof.roots[ell],
+                    &trace_hash,
+                    coset,
+                    pay,
+                    rref.i,
+                    z_fp3.coord(0),
+                    params.folding_mode,
+                ) {
+                    diagln!("[FAIL][COSET FOLD] q={} ell={} b={}", q, ell, b);
+                    return false;
+                }
+            }
         }
 
         // ------------------------
@@ -1011,7 +2010,7 @@ pub fn deep_fri_verify(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
         // ------------------------
 
         if qp.final_pair.0 != qp.final_pair.1 {
-            eprintln!(
+            diagln!(
                 "[FAIL][FINAL CONSTANCY] q={} f={:?} s={:?}",
                 q,
                 qp.final_pair.0,
@@ -1021,7 +2020,7 @@ pub fn deep_fri_verify(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
         }
     }
 
-    eprintln!("[VERIFY] SUCCESS");
+    diagln!("[VERIFY] SUCCESS");
     true
 }
 
@@ -1035,33 +2034,64 @@ fn fri_fold_layer_impl(
     assert!(n % folding_factor == 0);
 
     let n_next = n / folding_factor;
-    let mut out = vec![F::zero(); n_next];
-
     let z_pows = build_z_pows(z_l, folding_factor);
 
-    if enable_parallel(n_next) {
-        #[cfg(feature = "parallel")]
-        {
-            out.par_iter_mut().enumerate().for_each(|(b, out_b)| {
-                let mut acc = F::zero();
-                for j in 0..folding_factor {
-                    acc += evals[b + j * n_next] * z_pows[j];
-                }
-                *out_b = acc;
-            });
-            return out;
-        }
+    map_indices(ParallelOp::Fold, n_next, |b| strided_fold_at(evals, b, n_next, &z_pows))
+}
+
+/// Roots `ζ^j`, j < m, of the size-m subgroup each coset is a shift of.
+fn coset_roots(omega: F, n_next: usize, m: usize) -> Vec<F> {
+    build_z_pows(omega.pow([n_next as u64]), m)
+}
+
+/// Coset-interpolation fold of a single coset.
+///
+/// `values[j] = f(x_b·ζ^j)`. Writing `P` for the degree-<m interpolant in
+/// `Y = x / x_b`, returns `P(alpha·x_b⁻¹)` via the root-of-unity Lagrange
+/// form `L_j(y) = (y^m − 1)·ζ^j / (m·(y − ζ^j))`. The verifier can call
+/// this directly on an opened coset.
+pub fn fold_coset_interpolation(values: &[F], alpha: F, x_b_inv: F, zetas: &[F]) -> F {
+    let m = values.len();
+    debug_assert_eq!(zetas.len(), m);
+    let y = alpha * x_b_inv;
+
+    let mut denoms: Vec<F> = zetas.iter().map(|zj| y - *zj).collect();
+    if let Some(j) = denoms.iter().position(|d| d.is_zero()) {
+        // y is itself a coset point; the interpolant passes through it.
+        return values[j];
     }
+    batch_inversion(&mut denoms);
 
-    for b in 0..n_next {
-        let mut acc = F::zero();
-        for j in 0..folding_factor {
-            acc += evals[b + j * n_next] * z_pows[j];
-        }
-        out[b] = acc;
+    let mut acc = MulAcc::new();
+    for j in 0..m {
+        acc.mul_add(values[j], zetas[j] * denoms[j]);
     }
+    let scale = (y.pow([m as u64]) - F::one())
+        * F::from(m as u64).inverse().expect("m ≠ 0");
+    acc.finish() * scale
+}
+
+fn fri_fold_layer_coset_impl(
+    evals: &[F],
+    alpha: F,
+    omega: F,
+    folding_factor: usize,
+) -> Vec<F> {
+    let n = evals.len();
+    assert!(n.is_multiple_of(folding_factor));
+
+    let n_next = n / folding_factor;
+    let zetas = coset_roots(omega, n_next, folding_factor);
+    let x_inv = build_z_pows(omega.inverse().expect("ω ≠ 0"), n_next);
+
+    let fold_at = |b: usize| {
+        let values: Vec<F> = (0..folding_factor)
+            .map(|j| evals[b + j * n_next])
+            .collect();
+        fold_coset_interpolation(&values, alpha, x_inv[b], &zetas)
+    };
 
-    out
+    map_indices(ParallelOp::Fold, n_next, fold_at)
 }
 
 
@@ -1071,12 +2101,13 @@ mod tests {
     use ark_ff::{Field, FftField, One, Zero};
     use ark_goldilocks::Goldilocks;
     use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
-    use rand::Rng;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
     use std::collections::HashSet;
 
     // FIX: Moved these imports inside the test module where they are used.
     use ark_ff::UniformRand;
     use ark_poly::polynomial::univariate::DensePolynomial;
+    use ark_poly::{DenseUVPolynomial, Polynomial};
     use rand::seq::SliceRandom;
 
     type TestField = Goldilocks;
@@ -1084,6 +2115,11 @@ mod tests {
     fn random_polynomial<F: Field>(degree: usize, rng: &mut impl Rng) -> Vec<F> {
         (0..=degree).map(|_| F::rand(rng)).collect()
     }
+
+    /// [`deep_fri_verify`] under a key built for the proof's own domain.
+    fn verify(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
+        VerifierKey::for_fri(params, proof.n0).is_ok_and(|key| deep_fri_verify(&key, proof))
+    }
     
     fn perform_fold<F: Field + FftField>(
         evals: &[F],
@@ -1131,6 +2167,800 @@ mod tests {
         poly.evaluate(&evaluation_point)
     }
 
+    #[test]
+    fn coset_interpolation_mode_matches_reference_fold() {
+        let mut rng = rand::thread_rng();
+        let alpha = TestField::rand(&mut rng);
+
+        for &(n, m) in &[(64usize, 2usize), (256, 4), (1024, 16)] {
+            let evals: Vec<TestField> = (0..n).map(|_| TestField::rand(&mut rng)).collect();
+            let domain = GeneralEvaluationDomain::<TestField>::new(n).unwrap();
+
+            let (expected, _) = perform_fold(&evals, domain, alpha, m);
+            let got = fri_fold_layer_with_mode(&evals, alpha, m, FoldingMode::CosetInterpolation);
+            assert_eq!(got, expected, "n={n} m={m}");
+        }
+    }
+
+    #[test]
+    fn coset_interpolation_preserves_low_degree() {
+        const N: usize = 1024;
+        const M: usize = 4;
+        let mut rng = rand::thread_rng();
+        let alpha = TestField::rand(&mut rng);
+
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 8 - 1, &mut rng));
+        let evals = domain.fft(poly.coeffs());
+
+        let folded = fri_fold_layer_with_mode(&evals, alpha, M, FoldingMode::CosetInterpolation);
+        let next_domain = GeneralEvaluationDomain::<TestField>::new(N / M).unwrap();
+        let coeffs = next_domain.ifft(&folded);
+        assert!(coeffs[N / (8 * M)..].iter().all(|c| c.is_zero()));
+    }
+
+    // ark-serialize 0.4 debug-asserts on the 8-byte Goldilocks encoding used
+    // for the Merkle trace hash, so the full prover only runs in release.
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn deep_fri_roundtrip_both_folding_modes() {
+        const N: usize = 1 << 10;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let f0 = domain.fft(poly.coeffs());
+
+        for mode in [FoldingMode::PowersOfZ, FoldingMode::CosetInterpolation] {
+            let params = DeepFriParams {
+                schedule: vec![4, 4, 4, 4, 4],
+                r: 8,
+                seed_z: 7,
+                folding_mode: mode,
+                fold_check: FoldCheck::CommittedS,
+                grinding_bits: 0,
+                query_sampling: QuerySampling::default(),
+                index_chaining: IndexChaining::default(),
+                transcript: TranscriptHash::default(),
+            };
+            let proof = deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &params);
+            assert!(verify(&params, &proof), "{mode:?}");
+
+            // Verifying under the other fold rule must not be accepted.
+            let other = DeepFriParams {
+                folding_mode: match mode {
+                    FoldingMode::PowersOfZ => FoldingMode::CosetInterpolation,
+                    FoldingMode::CosetInterpolation => FoldingMode::PowersOfZ,
+                },
+                ..params.clone()
+            };
+            assert!(!verify(&other, &proof), "{mode:?}");
+
+            // s_i and f_parent_b moved together pass the fold check; only
+            // the leaf binding catches the payload.
+            let mut bad = deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &params);
+            let pay = &mut bad.queries[0].per_layer_payloads[1];
+            pay.s_i += TestField::one();
+            pay.f_parent_b += TestField::one();
+            assert!(!verify(&params, &bad), "{mode:?}");
+        }
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn coset_fold_check_recomputes_fold() {
+        const N: usize = 1 << 10;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let f0 = domain.fft(poly.coeffs());
+
+        for mode in [FoldingMode::PowersOfZ, FoldingMode::CosetInterpolation] {
+            let params = DeepFriParams {
+                schedule: vec![4, 4, 4, 4, 4],
+                r: 8,
+                seed_z: 7,
+                folding_mode: mode,
+                fold_check: FoldCheck::RecomputeFromCoset,
+                grinding_bits: 0,
+                query_sampling: QuerySampling::default(),
+                index_chaining: IndexChaining::default(),
+                transcript: TranscriptHash::default(),
+            };
+            let proof = deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &params);
+            assert!(verify(&params, &proof), "{mode:?}");
+            assert!(proof.queries.iter().all(|q| q.per_layer_cosets.len() == 5));
+
+            // A proof without coset openings is rejected under the strong check.
+            let bare = DeepFriParams { fold_check: FoldCheck::CommittedS, ..params.clone() };
+            let bare_proof = deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &bare);
+            assert!(verify(&bare, &bare_proof));
+            assert!(!verify(&params, &bare_proof));
+
+            // Tampering a sibling's f breaks its leaf binding.
+            let mut bad = deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &params);
+            bad.queries[0].per_layer_cosets[1][2].f += TestField::one();
+            assert!(!verify(&params, &bad));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn proof_root_commits_to_layer_and_trace_roots() {
+        const N: usize = 1 << 10;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let f0 = domain.fft(poly.coeffs());
+        let params = DeepFriParams {
+            schedule: vec![4, 4, 4, 4, 4],
+            r: 8,
+            seed_z: 7,
+            folding_mode: FoldingMode::default(),
+            fold_check: FoldCheck::default(),
+            grinding_bits: 0,
+            query_sampling: QuerySampling::default(),
+            index_chaining: IndexChaining::default(),
+            transcript: TranscriptHash::default(),
+        };
+
+        let trace_root = TestField::from(1234u64);
+        let proof = deep_fri_prove_with_trace_root(f0.clone(), FriDomain::new_radix2(N), &params, Some(trace_root));
+        assert!(verify(&params, &proof));
+        assert_eq!(proof.proof_root, deep_fri_proof_root(Some(trace_root), &proof.roots));
+
+        let plain = deep_fri_prove(f0, FriDomain::new_radix2(N), &params);
+        assert_ne!(plain.proof_root, proof.proof_root);
+
+        let mut swapped = proof;
+        swapped.trace_root = Some(trace_root + TestField::one());
+        assert!(!verify(&params, &swapped));
+
+        let mut bad_root = plain;
+        bad_root.roots[1] += TestField::one();
+        assert!(!verify(&params, &bad_root));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn truncated_roots_are_rejected() {
+        const N: usize = 1 << 8;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let f0 = domain.fft(poly.coeffs());
+        let params = DeepFriParams {
+            schedule: vec![4, 4, 4, 4],
+            r: 1,
+            seed_z: 7,
+            folding_mode: FoldingMode::default(),
+            fold_check: FoldCheck::default(),
+            grinding_bits: 0,
+            query_sampling: QuerySampling::default(),
+            index_chaining: IndexChaining::default(),
+            transcript: TranscriptHash::default(),
+        };
+        let key = VerifierKey::for_fri(&params, N).unwrap();
+
+        let mut proof = deep_fri_prove(f0, FriDomain::new_radix2(N), &params);
+        assert_eq!(proof.roots.len(), params.schedule.len());
+        assert!(verify(&params, &proof));
+
+        // Drop the last root and pick a trace root whose header draws the
+        // same query, so the shape check is all that stands between the
+        // verifier and the missing root.
+        let indices = query_indices::<TestField>(&key, &proof.proof_root, proof.pow_nonce);
+        proof.roots.pop();
+        let header = (0u64..)
+            .map(|t| (Some(TestField::from(t)), deep_fri_proof_root(Some(TestField::from(t)), &proof.roots)))
+            .find(|(_, root)| query_indices::<TestField>(&key, root, proof.pow_nonce) == indices)
+            .unwrap();
+        (proof.trace_root, proof.proof_root) = header;
+        assert!(!verify(&params, &proof));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn query_seed_grinding_and_index_derivation() {
+        const N: usize = 1 << 10;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let f0 = domain.fft(poly.coeffs());
+        let params = DeepFriParams {
+            schedule: vec![4, 4, 4, 4, 4],
+            r: 8,
+            seed_z: 7,
+            folding_mode: FoldingMode::default(),
+            fold_check: FoldCheck::default(),
+            grinding_bits: 8,
+            query_sampling: QuerySampling::default(),
+            index_chaining: IndexChaining::default(),
+            transcript: TranscriptHash::default(),
+        };
+
+        let proof = deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &params);
+        assert!(verify(&params, &proof));
+        let seed = query_seed(TranscriptHash::Poseidon, &proof.proof_root, 8, proof.pow_nonce);
+        assert!(seed.into_bigint().0[0].trailing_zeros() >= 8);
+
+        // Grinding is bound into the statement, not just checked.
+        let lax = DeepFriParams { grinding_bits: 0, ..params.clone() };
+        assert!(!verify(&lax, &proof));
+
+        let mut bad = deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &params);
+        bad.pow_nonce = (0u64..)
+            .find(|&n| !meets_grinding(QuerySeed::Field(query_seed(TranscriptHash::Poseidon, &bad.proof_root, 8, n), TranscriptHash::Poseidon), 8))
+            .unwrap();
+        assert!(!verify(&params, &bad));
+
+        // Query positions come from the seed, not the proof.
+        let mut moved = deep_fri_prove(f0, FriDomain::new_radix2(N), &params);
+        moved.queries.swap(0, 1);
+        moved.layer_proofs.layers.iter_mut().for_each(|l| l.openings.swap(0, 1));
+        assert!(!verify(&params, &moved));
+    }
+
+    #[test]
+    fn grinding_past_the_cap_errors_instead_of_searching() {
+        let root = [3u8; 32];
+        let (nonce, seed) = grind_query_seed::<TestField>(TranscriptHash::Poseidon, &root, 4).unwrap();
+        assert!(meets_grinding(seed, 4) && seed == query_seed_for::<TestField>(TranscriptHash::Poseidon, &root, 4, nonce));
+        assert_eq!(
+            grind_query_seed::<TestField>(TranscriptHash::Poseidon, &root, 64).err(),
+            Some(ParamError::GrindingBits { bits: 64, max: MAX_GRINDING_BITS })
+        );
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn distinct_sampling_redraws_collisions() {
+        let seed = QuerySeed::Field(TestField::from(99u64), TranscriptHash::Poseidon);
+        let (r, n0) = (40, 64);
+        let plain = query_base_indices(seed, r, n0, QuerySampling::Independent).unwrap();
+        let distinct = query_base_indices(seed, r, n0, QuerySampling::Distinct).unwrap();
+
+        let mut sorted = distinct.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted.len(), r);
+
+        // Identical up to the first repeat in the independent draws.
+        let first_repeat = (1..r).find(|&k| plain[..k].contains(&plain[k])).unwrap();
+        assert_eq!(plain[..first_repeat], distinct[..first_repeat]);
+
+        assert_eq!(query_base_indices(seed, 64, n0, QuerySampling::Distinct).unwrap().len(), 64);
+        assert!(query_base_indices(seed, 65, n0, QuerySampling::Distinct).is_none());
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn query_indices_span_domains_past_u32() {
+        let n0 = 3u64 << 32;
+        let seed = QuerySeed::Field(TestField::from(5u64), TranscriptHash::Poseidon);
+        let base = query_base_indices(seed, 64, n0, QuerySampling::Distinct).unwrap();
+        assert!(base.iter().all(|&i| i < n0));
+        assert!(base.iter().any(|&i| i > u32::MAX as u64));
+
+        // Parents stay in the folded domain without narrowing.
+        let n_next = n0 / 16;
+        for &i in &base {
+            let p = next_query_index(seed, 0, 0, i, n_next, 3, IndexChaining::Rerandomized);
+            assert!(p < n_next);
+            assert_eq!(next_query_index(seed, 0, 0, i, n_next, 3, IndexChaining::Strided), i % n_next);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn distinct_sampling_roundtrip() {
+        const N: usize = 1 << 8;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let f0 = domain.fft(poly.coeffs());
+        let params = DeepFriParams {
+            schedule: vec![4, 4, 4],
+            r: 100,
+            seed_z: 7,
+            folding_mode: FoldingMode::CosetInterpolation,
+            fold_check: FoldCheck::default(),
+            grinding_bits: 0,
+            query_sampling: QuerySampling::Distinct,
+            index_chaining: IndexChaining::default(),
+            transcript: TranscriptHash::default(),
+        };
+
+        let proof = deep_fri_prove(f0, FriDomain::new_radix2(N), &params);
+        assert!(verify(&params, &proof));
+        let mut base: Vec<u64> = proof.queries.iter().map(|q| q.per_layer_refs[0].i).collect();
+        base.sort_unstable();
+        base.dedup();
+        assert_eq!(base.len(), params.r);
+
+        let independent = DeepFriParams { query_sampling: QuerySampling::Independent, ..params.clone() };
+        assert!(!verify(&independent, &proof));
+    }
+
+    #[test]
+    #[cfg(feature = "serde1")]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn proof_and_params_roundtrip_through_serde() {
+        const N: usize = 1 << 8;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let params = DeepFriParams {
+            schedule: vec![4, 4, 4],
+            r: 8,
+            seed_z: 7,
+            folding_mode: FoldingMode::CosetInterpolation,
+            fold_check: FoldCheck::RecomputeFromCoset,
+            grinding_bits: 0,
+            query_sampling: QuerySampling::Distinct,
+            index_chaining: IndexChaining::Rerandomized,
+            transcript: TranscriptHash::default(),
+        };
+        let proof = deep_fri_prove(domain.fft(poly.coeffs()), FriDomain::new_radix2(N), &params);
+
+        let params2: DeepFriParams =
+            serde_json::from_str(&serde_json::to_string(&params).unwrap()).unwrap();
+        let proof2: DeepFriProof =
+            serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert!(verify(&params2, &proof2));
+        assert_eq!(proof2.proof_root, proof.proof_root);
+        assert_eq!(deep_fri_proof_size_bytes(&proof2), deep_fri_proof_size_bytes(&proof));
+        assert_eq!(
+            PublicParams::for_fri(&params2, N).digest(),
+            PublicParams::for_fri(&params, N).digest()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn parallel_openings_match_sequential() {
+        use crate::kat::proof_digest;
+        use crate::parallel::ParallelConfig;
+
+        const N: usize = 1 << 8;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let f0 = domain.fft(poly.coeffs());
+        let params = DeepFriParams {
+            schedule: vec![4, 4, 4],
+            r: 64,
+            seed_z: 7,
+            folding_mode: FoldingMode::CosetInterpolation,
+            fold_check: FoldCheck::RecomputeFromCoset,
+            grinding_bits: 0,
+            query_sampling: QuerySampling::default(),
+            index_chaining: IndexChaining::Rerandomized,
+            transcript: TranscriptHash::default(),
+        };
+
+        let prove = |cfg: ParallelConfig| {
+            cfg.install(|| deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &params))
+        };
+        let eager = prove(ParallelConfig { open_min_elems: 1, ..ParallelConfig::new() });
+        let sequential = prove(ParallelConfig::sequential());
+        assert!(verify(&params, &eager));
+        assert_eq!(proof_digest(&eager), proof_digest(&sequential));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn proofs_are_thread_count_independent() {
+        use crate::kat::proof_digest;
+        use crate::parallel::ParallelConfig;
+
+        const N: usize = 1 << 10;
+        let mut rng = StdRng::seed_from_u64(11);
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let f0 = domain.fft(poly.coeffs());
+
+        for folding_mode in [FoldingMode::PowersOfZ, FoldingMode::CosetInterpolation] {
+            let params = DeepFriParams {
+                schedule: vec![4, 4, 4],
+                r: 48,
+                seed_z: 3,
+                folding_mode,
+                fold_check: FoldCheck::default(),
+                grinding_bits: 0,
+                query_sampling: QuerySampling::default(),
+                index_chaining: IndexChaining::default(),
+                transcript: TranscriptHash::default(),
+            };
+            let prove = |cfg: ParallelConfig| {
+                let proof = cfg.install(|| deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &params));
+                proof_digest(&proof)
+            };
+
+            let expected = prove(ParallelConfig::sequential());
+            for threads in [2, 3, 8] {
+                let cfg = ParallelConfig { max_threads: Some(threads), ..ParallelConfig::with_min_elems(1) };
+                assert_eq!(prove(cfg.strict()), expected, "{folding_mode:?} threads={threads}");
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn rerandomized_chaining_roundtrip() {
+        const N: usize = 1 << 8;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let f0 = domain.fft(poly.coeffs());
+
+        for fold_check in [FoldCheck::CommittedS, FoldCheck::RecomputeFromCoset] {
+            let params = DeepFriParams {
+                schedule: vec![4, 4, 4],
+                r: 16,
+                seed_z: 7,
+                folding_mode: FoldingMode::CosetInterpolation,
+                fold_check,
+                grinding_bits: 0,
+                query_sampling: QuerySampling::default(),
+                index_chaining: IndexChaining::Rerandomized,
+                transcript: TranscriptHash::default(),
+            };
+            let proof = deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &params);
+            assert!(verify(&params, &proof));
+            assert!(proof.queries.iter().all(|q| q.per_layer_parents.len() == params.schedule.len() - 1));
+            assert!(proof.queries[0].per_layer_parents.iter().all(|p| p.values.len() == LAYER_LEAF_WIDTH));
+
+            // Some layer is checked away from the strided path.
+            assert!(proof.queries.iter().any(|q| q
+                .per_layer_refs
+                .windows(2)
+                .any(|w| w[1].i != w[0].parent_index)));
+
+            let strided = DeepFriParams { index_chaining: IndexChaining::Strided, ..params.clone() };
+            assert!(!verify(&strided, &proof));
+
+            // A parent value that does not match its leaf.
+            let mut bad = deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &params);
+            bad.queries[0].per_layer_parents[0].values[0] += TestField::from(1u64);
+            assert!(!verify(&params, &bad));
+
+            // A missing parent opening.
+            let mut bad = deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &params);
+            bad.queries[0].per_layer_parents.pop();
+            assert!(!verify(&params, &bad));
+
+            // A parent leaf of the wrong width.
+            let mut bad = deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &params);
+            bad.queries[0].per_layer_parents[0].values.pop();
+            assert!(!verify(&params, &bad));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn parent_values_are_checked_against_the_next_layer() {
+        const N: usize = 1 << 8;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let f0 = domain.fft(poly.coeffs());
+
+        let modes = [FoldCheck::CommittedS, FoldCheck::RecomputeFromCoset]
+            .into_iter()
+            .flat_map(|c| [IndexChaining::Strided, IndexChaining::Rerandomized].map(move |i| (c, i)));
+        for (fold_check, index_chaining) in modes {
+            let params = DeepFriParams {
+                schedule: vec![4, 4, 4, 4],
+                r: 8,
+                seed_z: 7,
+                folding_mode: FoldingMode::default(),
+                fold_check,
+                grinding_bits: 0,
+                query_sampling: QuerySampling::default(),
+                index_chaining,
+                transcript: TranscriptHash::default(),
+            };
+            let prover_params = FriProverParams {
+                schedule: params.schedule.clone(),
+                r: params.r,
+                seed_z: params.seed_z,
+                folding_mode: params.folding_mode,
+                fold_check,
+                grinding_bits: 0,
+                query_sampling: params.query_sampling,
+                index_chaining,
+                transcript: params.transcript,
+            };
+            let mut st = build_transcript_hooked::<TestField>(f0.clone(), FriDomain::new_radix2(N), None, &prover_params, &mut ())
+                .unwrap();
+
+            // Commit f_1 + 1 from layer 1 on, folded honestly from there: q
+            // only sees differences of f, so every layer but 0 is sound on
+            // its own, and only layer 0's s disagrees with layer 1's f.
+            let (z, L) = (st.z_layers[0], params.schedule.len());
+            st.f_layers[1].iter_mut().for_each(|v| *v += TestField::one());
+            for ell in 1..L {
+                let m = params.schedule[ell];
+                st.s_layers[ell] = compute_s_layer_with_mode(&st.f_layers[ell], z, m, params.folding_mode);
+                st.f_layers[ell + 1] = fri_fold_layer_with_mode(&st.f_layers[ell], z, m, params.folding_mode);
+                let n = st.f_layers[ell].len();
+                let cfg = MerkleChannelCfg::for_leaves(n, ArityPolicy::UpTo(m), ell as u64);
+                let mut tree = MerkleTreeChannel::<TestField>::new(cfg, [0u8; 32]);
+                for i in 0..n {
+                    tree.push_encoded(&(st.f_layers[ell][i], st.s_layers[ell][i], st.q_layers[ell][i]));
+                }
+                st.transcript.layers[ell].root = tree.finalize().unwrap();
+                st.trees[ell] = tree;
+            }
+
+            let layer_roots: Vec<TestField> = st.transcript.layers.iter().map(|l| l.root).collect();
+            let proof_root = deep_fri_proof_root(None, &layer_roots);
+            let (pow_nonce, seed) = grind_query_seed::<TestField>(params.transcript, &proof_root, 0).unwrap();
+            let (refs, roots, layer_proofs) =
+                prove_queries(&st, params.r, seed, fold_check, params.query_sampling, index_chaining);
+            let mut proof = DeepFriProof {
+                proof_root,
+                fs: fri_fs_binding(params.transcript),
+                pow_nonce,
+                trace_root: None,
+                roots,
+                layer_proofs,
+                queries: materialize_queries(&st, refs),
+                n0: N,
+                omega0: radix2_generator(N),
+            };
+            assert!(!verify(&params, &proof));
+
+            // Claiming layer 0's fold as the parent passes the fold check,
+            // but not the next layer's opened f.
+            for qp in &mut proof.queries {
+                qp.per_layer_payloads[0].f_parent_b = qp.per_layer_payloads[0].s_i;
+            }
+            assert!(!verify(&params, &proof), "{fold_check:?} {index_chaining:?}");
+        }
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn prover_state_keeps_committed_trees() {
+        const N: usize = 1 << 8;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let params = FriProverParams {
+            schedule: vec![4, 4, 4],
+            r: 4,
+            seed_z: 7,
+            folding_mode: FoldingMode::default(),
+            fold_check: FoldCheck::RecomputeFromCoset,
+            grinding_bits: 0,
+            query_sampling: QuerySampling::default(),
+            index_chaining: IndexChaining::default(),
+            transcript: TranscriptHash::default(),
+        };
+        let st = fri_build_transcript(domain.fft(poly.coeffs()), FriDomain::new_radix2(N), &params);
+
+        assert_eq!(st.trees.len(), params.schedule.len());
+        for (ell, (tree, layer)) in st.trees.iter().zip(&st.transcript.layers).enumerate() {
+            let cfg = MerkleChannelCfg::for_leaves(layer.n, ArityPolicy::UpTo(layer.m), ell as u64);
+            let opening = tree.open(layer.n - 1).unwrap();
+            assert_eq!(MerkleTreeChannel::verify_opening(&cfg, layer.root, &opening, &[0u8; 32]), Ok(true));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn verifier_key_is_reusable_and_domain_bound() {
+        const N: usize = 1 << 8;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let params = DeepFriParams {
+            schedule: vec![4, 4, 4],
+            r: 8,
+            seed_z: 5,
+            folding_mode: FoldingMode::CosetInterpolation,
+            fold_check: FoldCheck::RecomputeFromCoset,
+            grinding_bits: 0,
+            query_sampling: QuerySampling::default(),
+            index_chaining: IndexChaining::default(),
+            transcript: TranscriptHash::default(),
+        };
+
+        let key = VerifierKey::for_fri(&params, N).unwrap();
+        assert_eq!(key.layers.iter().map(|l| l.n).collect::<Vec<_>>(), vec![N, N / 4, N / 16]);
+        assert_eq!(key.layers[1].omega, radix2_generator(N / 4));
+        assert_eq!(key.layers[2].cfg.tree_label, 2);
+
+        for _ in 0..3 {
+            let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+            let proof = deep_fri_prove(domain.fft(poly.coeffs()), FriDomain::new_radix2(N), &params);
+            assert!(deep_fri_verify(&key, &proof));
+        }
+
+        // A key for another domain or seed rejects the same proof.
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let proof = deep_fri_prove(domain.fft(poly.coeffs()), FriDomain::new_radix2(N), &params);
+        let wide = VerifierKey::for_fri(&params, 2 * N).unwrap();
+        assert!(!deep_fri_verify(&wide, &proof));
+        let reseeded = VerifierKey::for_fri(&DeepFriParams { seed_z: 6, ..params.clone() }, N).unwrap();
+        assert!(!deep_fri_verify(&reseeded, &proof));
+
+        let mut pp = PublicParams::for_fri(&params, N);
+        pp.merkle.pop();
+        assert_eq!(
+            VerifierKey::new(&pp, params.seed_z).err(),
+            Some(ParamError::MerkleLayers { layers: 2, schedule: 3 })
+        );
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn proofs_declare_their_transcript() {
+        const N: usize = 1 << 8;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let params = DeepFriParams {
+            schedule: vec![4, 4, 4],
+            r: 8,
+            seed_z: 5,
+            folding_mode: FoldingMode::CosetInterpolation,
+            fold_check: FoldCheck::RecomputeFromCoset,
+            grinding_bits: 0,
+            query_sampling: QuerySampling::default(),
+            index_chaining: IndexChaining::default(),
+            transcript: TranscriptHash::default(),
+        };
+        let key = VerifierKey::for_fri(&params, N).unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let mut proof = deep_fri_prove(domain.fft(poly.coeffs()), FriDomain::new_radix2(N), &params);
+        assert_eq!(proof.fs, fri_fs_binding(TranscriptHash::Poseidon));
+        assert_eq!(proof.fs.backend, "poseidon");
+        assert!(deep_fri_verify(&key, &proof));
+
+        // Any other backend, parameter set or version is refused up front.
+        let honest = proof.fs.clone();
+        proof.fs.backend = "blake3".into();
+        assert!(!deep_fri_verify(&key, &proof));
+        proof.fs = FsBinding { params_digest: Some([0; 32]), ..honest.clone() };
+        assert!(!deep_fri_verify(&key, &proof));
+        proof.fs = FsBinding { version: transcript::FsVersion::V2, ..honest };
+        assert!(!deep_fri_verify(&key, &proof));
+
+        // A Keccak transcript draws other challenges and declares itself.
+        let keccak = DeepFriParams { transcript: TranscriptHash::Keccak256, ..params };
+        let keccak_key = VerifierKey::for_fri(&keccak, N).unwrap();
+        let mut proof = deep_fri_prove(domain.fft(poly.coeffs()), FriDomain::new_radix2(N), &keccak);
+        assert_eq!(proof.fs.backend, "keccak-256");
+        assert!(deep_fri_verify(&keccak_key, &proof));
+        assert!(!deep_fri_verify(&key, &proof));
+        proof.fs = fri_fs_binding(TranscriptHash::Poseidon);
+        assert!(!deep_fri_verify(&keccak_key, &proof) && !deep_fri_verify(&key, &proof));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn conservative_proofs_commit_with_full_digests() {
+        const N: usize = 1 << 8;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let params = DeepFriParams {
+            schedule: vec![4, 4, 4],
+            r: 8,
+            seed_z: 11,
+            folding_mode: FoldingMode::CosetInterpolation,
+            fold_check: FoldCheck::RecomputeFromCoset,
+            grinding_bits: 4,
+            query_sampling: QuerySampling::Distinct,
+            index_chaining: IndexChaining::Rerandomized,
+            transcript: TranscriptHash::default(),
+        };
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let f0 = domain.fft(poly.coeffs());
+        let prove = || {
+            let trace_root = Some(Digest256([7u8; 32]));
+            try_deep_fri_prove_conservative(f0.clone(), FriDomain::new_radix2(N), &params, trace_root).unwrap()
+        };
+
+        let proof = prove();
+        assert_eq!(proof.proof_root, deep_fri_proof_root(proof.trace_root, &proof.roots));
+        let seed = query_seed_for::<Digest256>(params.transcript, &proof.proof_root, 4, proof.pow_nonce);
+        assert!(matches!(seed, QuerySeed::Wide(_)) && meets_grinding(seed, 4));
+        assert_eq!(deep_fri_layer0_values(&params, &proof).map(|v| v.len()), Some(8));
+
+        let pp = PublicParams::for_fri(&params, N).with_digest(DigestWidth::Digest256);
+        let key = VerifierKey::new(&pp, params.seed_z).unwrap();
+        assert!(deep_fri_verify(&key, &proof));
+
+        // Neither width verifies under the other's key.
+        let narrow = deep_fri_prove(f0.clone(), FriDomain::new_radix2(N), &params);
+        assert!(!deep_fri_verify(&VerifierKey::for_fri(&params, N).unwrap(), &proof));
+        assert!(!deep_fri_verify(&key, &narrow));
+        assert!(deep_fri_proof_size_bytes(&proof) > deep_fri_proof_size_bytes(&narrow));
+
+        // A flipped bit past the first eight of a root is caught.
+        let mut bad = prove();
+        bad.roots[1].0[31] ^= 1;
+        assert!(!deep_fri_verify(&key, &bad));
+
+        #[cfg(feature = "serde1")]
+        {
+            let back: DeepFriProof<Digest256> =
+                serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+            assert!(deep_fri_verify(&key, &back));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn progress_reports_every_layer_and_cancels() {
+        const N: usize = 1 << 8;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<TestField>::new(N).unwrap();
+        let params = DeepFriParams {
+            schedule: vec![4, 4, 4, 4],
+            r: 8,
+            seed_z: 9,
+            folding_mode: FoldingMode::default(),
+            fold_check: FoldCheck::default(),
+            grinding_bits: 0,
+            query_sampling: QuerySampling::default(),
+            index_chaining: IndexChaining::default(),
+            transcript: TranscriptHash::default(),
+        };
+        let poly = DensePolynomial::from_coefficients_vec(random_polynomial(N / 32 - 1, &mut rng));
+        let f0 = domain.fft(poly.coeffs());
+
+        let mut seen = Vec::new();
+        let never = AtomicBool::new(false);
+        let proof = deep_fri_prove_with_progress(
+            f0.clone(),
+            FriDomain::new_radix2(N),
+            &params,
+            None,
+            |phase, done| seen.push((phase, done)),
+            &never,
+        )
+        .unwrap();
+        assert!(verify(&params, &proof));
+        // 0 on entry, then one step per fold and s-layer, per tree, and two for the queries.
+        assert_eq!(seen.len(), 9 + 5 + 3);
+        assert_eq!(seen[0], (ProverPhase::Layers, 0.0));
+        assert_eq!(seen[9], (ProverPhase::Merkle, 0.0));
+        assert_eq!(seen.last(), Some(&(ProverPhase::Queries, 1.0)));
+        assert!(seen.windows(2).all(|w| w[0].0 != w[1].0 || w[0].1 < w[1].1));
+
+        // Raising the flag from the callback stops the proof at the next step.
+        let cancel = AtomicBool::new(false);
+        let mut calls = 0;
+        let res = deep_fri_prove_with_progress(
+            f0,
+            FriDomain::new_radix2(N),
+            &params,
+            None,
+            |phase, _| {
+                calls += 1;
+                if phase == ProverPhase::Merkle {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            },
+            &cancel,
+        );
+        assert_eq!(res.err(), Some(ProveError::Cancelled));
+        assert_eq!(calls, 10);
+    }
+
+    #[test]
+    fn omega_ladder_matches_layer_generators() {
+        const N0: usize = 1 << 12;
+        let ladder = OmegaLadder::new(radix2_generator(N0), N0);
+        for n in [N0, N0 / 4, N0 / 64] {
+            let omega = radix2_generator(n);
+            for i in [0usize, 1, 5, n - 1] {
+                assert_eq!(ladder.pow((i * (N0 / n)) as u64), omega.pow([i as u64]), "n={n} i={i}");
+            }
+        }
+    }
+
     #[test]
     fn test_fri_local_consistency_check_soundness() {
         const DOMAIN_SIZE: usize = 1024;
@@ -1625,4 +3455,28 @@ mod tests {
             theoretical_rho_1
         );
     }
+
+    /// One quotient routine serves both challenge spaces: at a diagonal
+    /// point, Fp³'s quotient is the base-field one in every coordinate.
+    #[test]
+    fn deep_quotients_agree_across_challenge_spaces() {
+        let mut rng = StdRng::seed_from_u64(3718);
+        let n = 64;
+        let omega = radix2_generator(n);
+        let xs = || core::iter::successors(Some(F::one()), move |&x| Some(x * omega));
+        let f: Vec<F> = (0..n).map(|_| F::rand(&mut rng)).collect();
+        let z = F::rand(&mut rng);
+        let base: Vec<F> = compute_q_layer(&f, z, xs());
+        let ext: Vec<Fp3> = compute_q_layer(&f, Fp3::from_base(z), xs());
+        assert_eq!(ext, base.iter().map(|&q| Fp3::from_base(q)).collect::<Vec<_>>());
+
+        let z3 = Fp3 { a0: z, a1: F::rand(&mut rng), a2: F::rand(&mut rng) };
+        let q3 = compute_q_layer(&f, z3, xs());
+        for ((&q, &fx), x) in q3.iter().zip(&f).zip(xs()) {
+            assert!(deep_quotient_holds(q, fx, f[0], x, z3));
+            assert!(!deep_quotient_holds(q + Fp3::one(), fx, f[0], x, z3));
+        }
+        // A point on the domain has no quotient.
+        assert!(!deep_quotient_holds(F::zero(), f[1], f[0], omega, omega));
+    }
 }
\ No newline at end of file
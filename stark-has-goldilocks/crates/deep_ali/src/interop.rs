@@ -0,0 +1,6 @@
+//! Proofs in other systems' formats.
+
+pub mod solidity;
+
+#[cfg(feature = "winterfell")]
+pub mod winterfell;
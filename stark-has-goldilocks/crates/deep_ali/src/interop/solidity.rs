@@ -0,0 +1,1231 @@
+//! DEEP-FRI proofs checked by an EVM contract.
+//!
+//! [`SolidityVerifier::contract`] emits a Solidity contract whose
+//! `verify(bytes calldata proof) returns (bool)` accepts the proofs
+//! [`deep_fri_verify`](crate::fri::deep_fri_verify) accepts under one
+//! [`PublicParams`] and statement seed. Everything the [`VerifierKey`]
+//! fixes is baked into the bytecode: layer sizes and tree shapes, domain
+//! generators, the statement challenge `z` and the fold tables. The proof
+//! travels as [`SolidityVerifier::encode`]'s packed bytes, which leave out
+//! whatever the contract derives itself: the proof root, the query indices
+//! and the leaf digests. [`SolidityVerifier::decode`] rebuilds a
+//! [`DeepFriProof`] from them the same way. The contract checks every
+//! opened path on its own, so it also turns down proofs with a stray
+//! sibling that the batched Merkle check recomputes from another path and
+//! ignores; honest proofs have none.
+//!
+//! The contract replays the [`TranscriptHash::Keccak256`] transcript with
+//! the `keccak256` opcode. The EVM has no SHA3-256, so the Merkle paths,
+//! committed under [`MerkleHash::Sha3`], are hashed with a Keccak-f\[1600\]
+//! permutation written in Yul, at about 70k gas each. One is needed per
+//! leaf and per tree level on every opened path, so the permutations
+//! dominate: [`SolidityVerifier::permutations`] counts them. Four queries
+//! through four 4-ary layers of 256 points take 65, about 4.7M gas in
+//! all. Twenty queries through four 16-ary layers of 2^16 points take 489
+//! under [`FoldCheck::CommittedS`], some 35M gas, and over 8000 under
+//! [`FoldCheck::RecomputeFromCoset`], far more than a block holds. The
+//! bytecode grows with the layer count too, and deep schedules can pass
+//! the 24 KiB contract size limit.
+//!
+//! Encoded proofs are big-endian 8-byte words, all canonical field
+//! elements except the nonce:
+//!
+//! - the grinding nonce, the trace root (0 without one) and the L layer
+//!   roots;
+//! - per query, the final pair, then for each layer `ℓ`:
+//!   - `f_0` and `f_parent_b`;
+//!   - the leaf `[f_i, s_i, q_a0, q_a1, q_a2]` and the siblings on its
+//!     path, level by level;
+//!   - under [`FoldCheck::RecomputeFromCoset`], the m coset leaves and
+//!     paths in order of `j`;
+//!   - under [`IndexChaining::Rerandomized`] with `ℓ + 1 < L`, the fold
+//!     parent's leaf and path in layer `ℓ + 1`.
+//!
+//! `s_parent_b`, which no check reads, is not sent; decoded proofs carry
+//! zero there.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt::Write;
+
+use ark_ff::{Field, PrimeField, Zero};
+use ark_goldilocks::Goldilocks as F;
+use merkle::{DigestWidth, MerkleHash, MerkleOpening, MerkleTreeChannel, MerkleValueOpening};
+use transcript::ds as fs;
+
+use crate::fri::{
+    deep_fri_proof_root, ds, fri_fs_binding, query_indices, radix2_generator, CosetLeafOpening, DeepFriProof,
+    FoldCheck, FoldingMode, FriLayerProofs, FriQueryPayload, IndexChaining, LayerOpenPayload, LayerProof,
+    LayerQueryRef, QuerySampling, TranscriptHash, VerifierKey, LAYER_LEAF_WIDTH,
+};
+use crate::params::{ParamError, PublicParams};
+
+/// `bytes4(keccak256("verify(bytes)"))`.
+pub const VERIFY_SELECTOR: [u8; 4] = [0x8e, 0x76, 0x0a, 0xfe];
+
+const WORD: usize = 8;
+
+/// Words of a leaf: `[f, s, q_a0, q_a1, q_a2]`.
+const LEAF_WORDS: usize = LAYER_LEAF_WIDTH;
+
+/// `level` of a leaf's and of a sealed root's domain-separation label in
+/// the `merkle` crate.
+const LEAF_LEVEL: u64 = u32::MAX as u64;
+const ROOT_LEVEL: u64 = u32::MAX as u64 - 2;
+
+const META_LEAF: &[u8; 16] = b"MERKLE/META/LEAF";
+const META_NODE: &[u8; 16] = b"MERKLE/META/NODE";
+
+/// Memory the contract works in: the failure flag, the round constants,
+/// the permutation state and its scratch copy, then the hash input. It
+/// starts above the first kilobyte, which is left to the compiler; the
+/// contract reverts if the free memory pointer is already past it.
+const FAILED_AT: usize = 0x400;
+const RC_AT: usize = FAILED_AT + 32;
+const STATE_AT: usize = RC_AT + 24 * 32;
+const SCRATCH_AT: usize = STATE_AT + 25 * 32;
+const MSG_AT: usize = SCRATCH_AT + 25 * 32;
+
+/// Longest transcript string the contract hashes, with room for a
+/// counter.
+const TRANSCRIPT_BYTES: usize = 384;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolidityError {
+    Params(ParamError),
+    /// Contracts replay [`TranscriptHash::Keccak256`] transcripts only.
+    Transcript(TranscriptHash),
+    /// Layer `layer` is not committed as a plain SHA3 tree of 64-bit
+    /// nodes, without cap or salts.
+    Merkle { layer: usize },
+    /// A proof of other parameters, or with openings of another shape.
+    Shape,
+    /// `got` bytes where the parameters fix `expected`.
+    Length { expected: usize, got: usize },
+    /// A field element at or past the modulus at byte `offset`.
+    NonCanonical { offset: usize },
+    /// The nonce misses the grinding target, so no query has an index.
+    Grinding,
+}
+
+impl core::fmt::Display for SolidityError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SolidityError::Params(e) => write!(f, "invalid FRI parameters: {e}"),
+            SolidityError::Transcript(h) => write!(f, "{h:?} transcripts cannot be replayed on chain"),
+            SolidityError::Merkle { layer } => {
+                write!(f, "layer {layer} is not a SHA3 tree of 64-bit nodes without cap or salts")
+            }
+            SolidityError::Shape => write!(f, "proof does not have the parameters' shape"),
+            SolidityError::Length { expected, got } => write!(f, "{got} proof bytes, expected {expected}"),
+            SolidityError::NonCanonical { offset } => write!(f, "non-canonical field element at byte {offset}"),
+            SolidityError::Grinding => write!(f, "nonce misses the grinding target"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SolidityError {}
+
+impl From<ParamError> for SolidityError {
+    fn from(e: ParamError) -> Self {
+        SolidityError::Params(e)
+    }
+}
+
+/// Contract generator and calldata codec for one parameter set.
+#[derive(Clone)]
+pub struct SolidityVerifier {
+    key: VerifierKey,
+}
+
+impl SolidityVerifier {
+    /// Verifier of proofs under `public` with statement seed `seed_z`.
+    pub fn new(public: &PublicParams, seed_z: u64) -> Result<Self, SolidityError> {
+        if public.transcript != TranscriptHash::Keccak256 {
+            return Err(SolidityError::Transcript(public.transcript));
+        }
+        if let Some(layer) = public.merkle.iter().position(|cfg| {
+            cfg.hash != MerkleHash::Sha3 || cfg.digest != DigestWidth::Digest64 || cfg.cap_height != 0 || cfg.hiding
+        }) {
+            return Err(SolidityError::Merkle { layer });
+        }
+        Ok(Self { key: VerifierKey::new(public, seed_z)? })
+    }
+
+    pub fn key(&self) -> &VerifierKey {
+        &self.key
+    }
+
+    /// Bytes of every encoded proof.
+    pub fn proof_len(&self) -> usize {
+        let l = self.key.layers.len();
+        let query: usize = 2 + (0..l).map(|ell| self.layer_words(ell)).sum::<usize>();
+        WORD * (2 + l + self.key.params.r * query)
+    }
+
+    /// Keccak-f\[1600\] permutations `verify` runs on a valid proof: the
+    /// proof root, then a leaf and one node per level on every opened path.
+    pub fn permutations(&self) -> usize {
+        // A message of `lanes` 8-byte words, padded to 17-lane blocks.
+        let blocks = |lanes: usize| lanes / 17 + 1;
+        let path = |ell: usize| {
+            let cfg = &self.key.layers[ell].cfg;
+            let levels: usize = cfg.layer_arities.iter().map(|&a| blocks(4 + a)).sum();
+            blocks(4 + LEAF_WORDS) + levels + cfg.leaf_count.map_or(0, |_| blocks(5))
+        };
+        let l = self.key.layers.len();
+        let params = &self.key.params;
+        let query: usize = (0..l)
+            .map(|ell| {
+                let coset = match params.fold_check {
+                    FoldCheck::RecomputeFromCoset => self.key.layers[ell].m * path(ell),
+                    FoldCheck::CommittedS => 0,
+                };
+                let parent = if self.has_parent(ell) { path(ell + 1) } else { 0 };
+                path(ell) + coset + parent
+            })
+            .sum();
+        (l + 1) + l + params.r * query
+    }
+
+    fn has_parent(&self, ell: usize) -> bool {
+        self.key.params.index_chaining == IndexChaining::Rerandomized && ell + 1 < self.key.layers.len()
+    }
+
+    /// Siblings on a path through layer `ell`.
+    fn path_words(&self, ell: usize) -> usize {
+        self.key.layers[ell].cfg.layer_arities.iter().map(|&a| a - 1).sum()
+    }
+
+    fn layer_words(&self, ell: usize) -> usize {
+        let opening = |ell| LEAF_WORDS + self.path_words(ell);
+        let coset = match self.key.params.fold_check {
+            FoldCheck::RecomputeFromCoset => self.key.layers[ell].m * opening(ell),
+            FoldCheck::CommittedS => 0,
+        };
+        let parent = if self.has_parent(ell) { opening(ell + 1) } else { 0 };
+        2 + opening(ell) + coset + parent
+    }
+
+    /// `proof` as the bytes `verify` takes.
+    pub fn encode(&self, proof: &DeepFriProof) -> Result<Vec<u8>, SolidityError> {
+        let (key, params) = (&self.key, &self.key.params);
+        let l = key.layers.len();
+        if proof.n0 != key.n0()
+            || proof.fs != fri_fs_binding(TranscriptHash::Keccak256)
+            || proof.roots.len() != l
+            || proof.queries.len() != params.r
+            || proof.layer_proofs.layers.len() != l
+            || proof.layer_proofs.layers.iter().any(|layer| layer.openings.len() != params.r)
+        {
+            return Err(SolidityError::Shape);
+        }
+
+        let mut out = Vec::with_capacity(self.proof_len());
+        out.extend_from_slice(&proof.pow_nonce.to_be_bytes());
+        put(&mut out, proof.trace_root.unwrap_or_default());
+        proof.roots.iter().for_each(|&root| put(&mut out, root));
+
+        for (q, qp) in proof.queries.iter().enumerate() {
+            if qp.per_layer_payloads.len() != l {
+                return Err(SolidityError::Shape);
+            }
+            put(&mut out, qp.final_pair.0);
+            put(&mut out, qp.final_pair.1);
+            for (ell, pay) in qp.per_layer_payloads.iter().enumerate() {
+                put(&mut out, pay.f_0);
+                put(&mut out, pay.f_parent_b);
+                pay.leaf_values().into_iter().for_each(|x| put(&mut out, x));
+                self.put_path(&mut out, ell, &proof.layer_proofs.layers[ell].openings[q])?;
+
+                if params.fold_check == FoldCheck::RecomputeFromCoset {
+                    let coset = qp.per_layer_cosets.get(ell).ok_or(SolidityError::Shape)?;
+                    if coset.len() != key.layers[ell].m {
+                        return Err(SolidityError::Shape);
+                    }
+                    for c in coset {
+                        c.leaf_values().into_iter().for_each(|x| put(&mut out, x));
+                        self.put_path(&mut out, ell, &c.opening)?;
+                    }
+                }
+                if self.has_parent(ell) {
+                    match qp.per_layer_parents.get(ell) {
+                        Some(po) if po.values.len() == LEAF_WORDS && po.salt.is_none() => {
+                            po.values.iter().for_each(|&x| put(&mut out, x));
+                            self.put_path(&mut out, ell + 1, &po.opening)?;
+                        }
+                        _ => return Err(SolidityError::Shape),
+                    }
+                }
+            }
+        }
+        debug_assert_eq!(out.len(), self.proof_len());
+        Ok(out)
+    }
+
+    fn put_path(&self, out: &mut Vec<u8>, ell: usize, opening: &MerkleOpening) -> Result<(), SolidityError> {
+        let arities = &self.key.layers[ell].cfg.layer_arities;
+        if opening.path.len() != arities.len() || opening.path.iter().zip(arities).any(|(s, &a)| s.len() + 1 != a) {
+            return Err(SolidityError::Shape);
+        }
+        opening.path.iter().flatten().for_each(|&x| put(out, x));
+        Ok(())
+    }
+
+    /// ABI-encoded call of `verify` on `proof`.
+    pub fn calldata(&self, proof: &DeepFriProof) -> Result<Vec<u8>, SolidityError> {
+        let body = self.encode(proof)?;
+        let mut out = Vec::with_capacity(4 + 64 + body.len().next_multiple_of(32));
+        out.extend_from_slice(&VERIFY_SELECTOR);
+        out.extend_from_slice(&abi_word(32));
+        out.extend_from_slice(&abi_word(body.len() as u64));
+        out.extend_from_slice(&body);
+        out.resize(4 + 64 + body.len().next_multiple_of(32), 0);
+        Ok(out)
+    }
+
+    /// The proof `bytes` encode, with the indices, leaf digests and proof
+    /// root the contract derives.
+    pub fn decode(&self, bytes: &[u8]) -> Result<DeepFriProof, SolidityError> {
+        let (key, params) = (&self.key, &self.key.params);
+        if bytes.len() != self.proof_len() {
+            return Err(SolidityError::Length { expected: self.proof_len(), got: bytes.len() });
+        }
+        let l = key.layers.len();
+        let mut r = Reader { bytes, pos: 0 };
+
+        let pow_nonce = r.word();
+        let trace_root = Some(r.field()?).filter(|x| !x.is_zero());
+        let roots = (0..l).map(|_| r.field()).collect::<Result<Vec<_>, _>>()?;
+        let proof_root = deep_fri_proof_root(trace_root, &roots);
+        let indices = query_indices::<F>(key, &proof_root, pow_nonce).ok_or(SolidityError::Grinding)?;
+
+        let mut layers = vec![Vec::with_capacity(params.r); l];
+        let mut queries = Vec::with_capacity(params.r);
+        for path in &indices {
+            let final_pair = (r.field()?, r.field()?);
+            let mut per_layer_refs = Vec::with_capacity(l);
+            let mut per_layer_payloads = Vec::with_capacity(l);
+            let mut per_layer_cosets = Vec::new();
+            let mut per_layer_parents = Vec::new();
+            for (ell, layer) in key.layers.iter().enumerate() {
+                let (i, n_next) = (path[ell], (layer.n / layer.m) as u64);
+                let b = i % n_next;
+                let (f_0, f_parent_b) = (r.field()?, r.field()?);
+                let [f_i, s_i, q_a0, q_a1, q_a2] = r.leaf()?;
+                layers[ell].push(self.read_opening(&mut r, ell, i, &[f_i, s_i, q_a0, q_a1, q_a2])?);
+                per_layer_refs.push(LayerQueryRef {
+                    i,
+                    child_pos: (i % layer.m as u64) as usize,
+                    parent_index: b,
+                    parent_pos: 0,
+                });
+                per_layer_payloads.push(LayerOpenPayload {
+                    f_i,
+                    f_0,
+                    s_i,
+                    q_a0,
+                    q_a1,
+                    q_a2,
+                    f_parent_b,
+                    s_parent_b: F::zero(),
+                });
+
+                if params.fold_check == FoldCheck::RecomputeFromCoset {
+                    let coset = (0..layer.m as u64)
+                        .map(|j| {
+                            let values = r.leaf()?;
+                            let [f, s, q_a0, q_a1, q_a2] = values;
+                            let opening = self.read_opening(&mut r, ell, b + j * n_next, &values)?;
+                            Ok(CosetLeafOpening { f, s, q_a0, q_a1, q_a2, opening })
+                        })
+                        .collect::<Result<_, SolidityError>>()?;
+                    per_layer_cosets.push(coset);
+                }
+                if self.has_parent(ell) {
+                    let values = r.leaf()?;
+                    let opening = self.read_opening(&mut r, ell + 1, b, &values)?;
+                    per_layer_parents.push(MerkleValueOpening { values: values.to_vec(), salt: None, opening });
+                }
+            }
+            queries.push(FriQueryPayload {
+                per_layer_refs,
+                per_layer_payloads,
+                final_index: path[l],
+                final_pair,
+                per_layer_cosets,
+                per_layer_parents,
+            });
+        }
+
+        Ok(DeepFriProof {
+            proof_root,
+            fs: fri_fs_binding(TranscriptHash::Keccak256),
+            pow_nonce,
+            trace_root,
+            roots,
+            layer_proofs: FriLayerProofs {
+                layers: layers.into_iter().map(|openings| LayerProof { openings }).collect(),
+            },
+            queries,
+            n0: key.n0(),
+            omega0: radix2_generator(key.n0()),
+        })
+    }
+
+    fn read_opening(
+        &self,
+        r: &mut Reader<'_>,
+        ell: usize,
+        index: u64,
+        values: &[F],
+    ) -> Result<MerkleOpening, SolidityError> {
+        let cfg = &self.key.layers[ell].cfg;
+        let path = cfg
+            .layer_arities
+            .iter()
+            .map(|&a| (1..a).map(|_| r.field()).collect())
+            .collect::<Result<_, _>>()?;
+        Ok(MerkleOpening {
+            leaf: MerkleTreeChannel::leaf_digest(cfg, index, values),
+            path,
+            index,
+        })
+    }
+
+    /// Source of a contract `name` verifying these parameters' proofs.
+    pub fn contract(&self, name: &str) -> String {
+        let (key, params) = (&self.key, &self.key.params);
+        let l = key.layers.len();
+        let max_m = key.layers.iter().map(|layer| layer.m).max().unwrap_or(0);
+        let max_arity = key.layers.iter().flat_map(|layer| layer.cfg.layer_arities.iter().copied()).max().unwrap_or(0);
+        let msg_words = (4 + max_arity).max(4 + 2 * 4 + 2);
+        let transcript_at = MSG_AT + 32 * msg_words;
+        let roots_at = transcript_at + TRANSCRIPT_BYTES;
+        let meta_at = roots_at + 32 * (l + 1);
+        let coset_at = meta_at + 128 * (l + 1);
+        let index_at = coset_at + 32 * max_m;
+
+        let mut y = String::new();
+        y.push_str(&GENERIC.replace("$LEAF_LEVEL", &LEAF_LEVEL.to_string()));
+        keccak_f(&mut y);
+        transcript_functions(&mut y, params.grinding_bits > 0);
+        meta_root(&mut y, l);
+        for ell in 0..l {
+            self.open_function(&mut y, ell);
+            self.layer_function(&mut y, ell);
+        }
+
+        // The query loop.
+        let _ = writeln!(y, "function query(q, seed, cd) -> next {{");
+        let _ = writeln!(y, "    let i0 := mload(add($IDX, shl(5, q)))");
+        let _ = writeln!(y, "    let last := felt(cd)");
+        let _ = writeln!(y, "    if iszero(eq(last, felt(add(cd, 8)))) {{ fail() }}");
+        let _ = writeln!(y, "    let cd0 := add(cd, 16)");
+        let _ = writeln!(y, "    let fp0 := 0");
+        for ell in 0..l {
+            let k = ell + 1;
+            let _ = writeln!(y, "    let i{k}, cd{k}, fp{k} := layer_{ell}(q, seed, i{ell}, cd{ell}, fp{ell})");
+        }
+        let _ = writeln!(y, "    if iszero(eq(fp{l}, last)) {{ fail() }}");
+        let _ = writeln!(y, "    next := cd{l}");
+        let _ = writeln!(y, "}}");
+
+        // Entry point, run on proofs of the right length.
+        let mut main = String::new();
+        let _ = writeln!(main, "if gt(mload(0x40), $FAILED) {{ revert(0, 0) }}");
+        for (t, rc) in keccak_rc().iter().enumerate() {
+            let _ = writeln!(main, "mstore({}, {rc:#x})", RC_AT + 32 * t);
+        }
+        let _ = writeln!(main, "let cd := proof.offset");
+        let _ = writeln!(main, "let nonce := word(cd)");
+        let _ = writeln!(main, "for {{ let k := 0 }} lt(k, {}) {{ k := add(k, 1) }} {{", l + 1);
+        let _ = writeln!(main, "    mstore(add($ROOTS, shl(5, k)), felt(add(cd, add(8, shl(3, k)))))");
+        let _ = writeln!(main, "}}");
+        let _ = writeln!(main, "cd := add(cd, {})", WORD * (l + 2));
+        let _ = writeln!(main, "let seed := seed_of(proof_root())");
+        if params.grinding_bits > 0 {
+            let _ = writeln!(main, "seed := pow_seed(seed, nonce)");
+            let _ = writeln!(main, "if and(seed, {:#x}) {{ fail() }}", (1u64 << params.grinding_bits) - 1);
+        }
+        match params.query_sampling {
+            QuerySampling::Independent => {
+                let _ = writeln!(main, "for {{ let q := 0 }} lt(q, {}) {{ q := add(q, 1) }} {{", params.r);
+                let _ = writeln!(main, "    mstore(add($IDX, shl(5, q)), draw(seed, 0, q, {}))", key.n0());
+                let _ = writeln!(main, "}}");
+            }
+            QuerySampling::Distinct => {
+                let _ = writeln!(main, "let got := 0");
+                let _ = writeln!(main, "for {{ let k := 0 }} lt(got, {}) {{ k := add(k, 1) }} {{", params.r);
+                let _ = writeln!(main, "    let i := draw(seed, 0, k, {})", key.n0());
+                let _ = writeln!(main, "    let fresh := 1");
+                let _ = writeln!(main, "    for {{ let j := 0 }} lt(j, got) {{ j := add(j, 1) }} {{");
+                let _ = writeln!(main, "        if eq(mload(add($IDX, shl(5, j))), i) {{ fresh := 0 }}");
+                let _ = writeln!(main, "    }}");
+                let _ = writeln!(main, "    if fresh {{");
+                let _ = writeln!(main, "        mstore(add($IDX, shl(5, got)), i)");
+                let _ = writeln!(main, "        got := add(got, 1)");
+                let _ = writeln!(main, "    }}");
+                let _ = writeln!(main, "}}");
+            }
+        }
+        let _ = writeln!(main, "for {{ let q := 0 }} lt(q, {}) {{ q := add(q, 1) }} {{", params.r);
+        let _ = writeln!(main, "    cd := query(q, seed, cd)");
+        let _ = writeln!(main, "}}");
+        let _ = writeln!(main, "ok := iszero(mload($FAILED))");
+        let _ = writeln!(y, "if eq(proof.length, {}) {{", self.proof_len());
+        for line in main.lines() {
+            let _ = writeln!(y, "    {line}");
+        }
+        let _ = writeln!(y, "}}");
+
+        let z = key.z_fp3;
+        let yul = y
+            .replace("$P", &format!("{:#x}", F::MODULUS.0[0]))
+            .replace("$Z0", &hex_field(z.a0))
+            .replace("$Z1", &hex_field(z.a1))
+            .replace("$Z2", &hex_field(z.a2))
+            .replace("$FAILED", &format!("{FAILED_AT:#x}"))
+            .replace("$RC", &format!("{RC_AT:#x}"))
+            .replace("$STATE", &format!("{STATE_AT:#x}"))
+            .replace("$MSG", &format!("{MSG_AT:#x}"))
+            .replace("$TR", &format!("{transcript_at:#x}"))
+            .replace("$ROOTS", &format!("{roots_at:#x}"))
+            .replace("$META", &format!("{meta_at:#x}"))
+            .replace("$COSET", &format!("{coset_at:#x}"))
+            .replace("$IDX", &format!("{index_at:#x}"));
+
+        let mut sol = String::new();
+        let _ = writeln!(sol, "// SPDX-License-Identifier: Apache-2.0 OR MIT");
+        let _ = writeln!(sol, "// Generated by deep_ali::interop::solidity; do not edit.");
+        let _ = writeln!(sol, "pragma solidity ^0.8.20;");
+        let _ = writeln!(sol);
+        let _ = writeln!(sol, "/// DEEP-FRI verifier over {} points, folds {:?}, {} queries.", key.n0(), params.schedule, params.r);
+        let _ = writeln!(sol, "contract {name} {{");
+        let _ = writeln!(sol, "    /// `PublicParams::digest` of the parameters.");
+        let _ = writeln!(sol, "    bytes32 public constant PARAMS_DIGEST = 0x{};", hex_bytes(&key.public.digest()));
+        let _ = writeln!(sol, "    uint64 public constant SEED_Z = {};", params.seed_z);
+        let _ = writeln!(sol);
+        let _ = writeln!(sol, "    /// Whether `proof`, as `SolidityVerifier::encode` lays it out, is valid.");
+        let _ = writeln!(sol, "    function verify(bytes calldata proof) external pure returns (bool ok) {{");
+        let _ = writeln!(sol, "        assembly {{");
+        for line in yul.lines() {
+            let _ = writeln!(sol, "            {line}");
+        }
+        let _ = writeln!(sol, "        }}");
+        let _ = writeln!(sol, "    }}");
+        let _ = writeln!(sol, "}}");
+        sol
+    }
+
+    /// `open_ℓ(idx, cd) -> next`: checks the leaf at `cd` and the path
+    /// after it up to layer ℓ's root, failing otherwise.
+    fn open_function(&self, y: &mut String, ell: usize) {
+        let cfg = &self.key.layers[ell].cfg;
+        let label = cfg.tree_label;
+        let _ = writeln!(y, "function open_{ell}(idx, cd) -> next {{");
+        let _ = writeln!(y, "    let cur := leaf(idx, cd, {}, {label:#x})", cfg.layer_arities[0]);
+        let _ = writeln!(y, "    cd := add(cd, {})", WORD * LEAF_WORDS);
+        for (level, &a) in cfg.layer_arities.iter().enumerate() {
+            let _ = writeln!(y, "    cur := climb(cur, idx, cd, {a}, {}, {label:#x})", level + 1);
+            let _ = writeln!(y, "    cd := add(cd, {})", WORD * (a - 1));
+            let _ = writeln!(y, "    idx := div(idx, {a})");
+        }
+        if let Some(n) = cfg.leaf_count {
+            let _ = writeln!(y, "    mstore($MSG, 1)");
+            let _ = writeln!(y, "    mstore(add($MSG, 32), {ROOT_LEVEL:#x})");
+            let _ = writeln!(y, "    mstore(add($MSG, 64), {n})");
+            let _ = writeln!(y, "    mstore(add($MSG, 96), {label:#x})");
+            let _ = writeln!(y, "    mstore(add($MSG, 128), cur)");
+            let _ = writeln!(y, "    cur := node(5)");
+        }
+        let _ = writeln!(y, "    if iszero(eq(cur, mload(add($ROOTS, {})))) {{ fail() }}", 32 * (ell + 1));
+        let _ = writeln!(y, "    next := cd");
+        let _ = writeln!(y, "}}");
+    }
+
+    /// `layer_ℓ(q, seed, i, cd, prev) -> j, next, fp`: one query's checks
+    /// on layer ℓ at index `i`, given the previous layer's `f_parent_b`;
+    /// returns the next layer's index and this layer's `f_parent_b`.
+    fn layer_function(&self, y: &mut String, ell: usize) {
+        let (key, params) = (&self.key, &self.key.params);
+        let layer = &key.layers[ell];
+        let (m, n_next) = (layer.m, layer.n / layer.m);
+        let _ = writeln!(y, "function layer_{ell}(q, seed, i, cd, prev) -> j, next, fp {{");
+        let _ = writeln!(y, "    let f0 := felt(cd)");
+        let _ = writeln!(y, "    fp := felt(add(cd, 8))");
+        let _ = writeln!(y, "    let fi := word(add(cd, 16))");
+        let _ = writeln!(y, "    deep(add(cd, 16), fpow({}, i), f0)", hex_field(layer.omega));
+        let _ = writeln!(y, "    if iszero(eq(word(add(cd, 24)), fp)) {{ fail() }}");
+        if ell > 0 && params.index_chaining == IndexChaining::Strided {
+            let _ = writeln!(y, "    if iszero(eq(fi, prev)) {{ fail() }}");
+        }
+        let _ = writeln!(y, "    next := open_{ell}(i, add(cd, 16))");
+        let _ = writeln!(y, "    j := mod(i, {n_next})");
+        if params.fold_check == FoldCheck::RecomputeFromCoset {
+            let _ = writeln!(y, "    for {{ let k := 0 }} lt(k, {m}) {{ k := add(k, 1) }} {{");
+            let _ = writeln!(y, "        mstore(add($COSET, shl(5, k)), word(next))");
+            let _ = writeln!(y, "        next := open_{ell}(add(j, mul(k, {n_next})), next)");
+            let _ = writeln!(y, "    }}");
+            let _ = writeln!(y, "    if iszero(eq(mload(add($COSET, shl(5, div(i, {n_next})))), fi)) {{ fail() }}");
+            let fold = match params.folding_mode {
+                FoldingMode::PowersOfZ => format!("zfold({m})"),
+                FoldingMode::CosetInterpolation => {
+                    let omega_inv = layer.omega.inverse().expect("ω ≠ 0");
+                    let zeta = layer.omega.pow([n_next as u64]);
+                    let m_inv = F::from(m as u64).inverse().expect("m ≠ 0");
+                    format!(
+                        "interp(j, {m}, {}, {}, {})",
+                        hex_field(omega_inv),
+                        hex_field(zeta),
+                        hex_field(m_inv)
+                    )
+                }
+            };
+            let _ = writeln!(y, "    if iszero(eq({fold}, fp)) {{ fail() }}");
+        }
+        if self.has_parent(ell) {
+            let _ = writeln!(y, "    if iszero(eq(word(next), fp)) {{ fail() }}");
+            let _ = writeln!(y, "    next := open_{}(j, next)", ell + 1);
+            let _ = writeln!(y, "    j := mod(add(j, draw(seed, {}, q, {n_next})), {n_next})", ell + 1);
+        }
+        let _ = writeln!(y, "}}");
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    /// Callers check the length up front.
+    fn word(&mut self) -> u64 {
+        let w = u64::from_be_bytes(self.bytes[self.pos..self.pos + WORD].try_into().unwrap());
+        self.pos += WORD;
+        w
+    }
+
+    fn field(&mut self) -> Result<F, SolidityError> {
+        let offset = self.pos;
+        match self.word() {
+            w if w < F::MODULUS.0[0] => Ok(F::from(w)),
+            _ => Err(SolidityError::NonCanonical { offset }),
+        }
+    }
+
+    fn leaf(&mut self) -> Result<[F; LEAF_WORDS], SolidityError> {
+        let mut values = [F::zero(); LEAF_WORDS];
+        for x in &mut values {
+            *x = self.field()?;
+        }
+        Ok(values)
+    }
+}
+
+fn put(out: &mut Vec<u8>, x: F) {
+    out.extend_from_slice(&x.into_bigint().0[0].to_be_bytes());
+}
+
+fn abi_word(x: u64) -> [u8; 32] {
+    let mut w = [0u8; 32];
+    w[24..].copy_from_slice(&x.to_be_bytes());
+    w
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn hex_field(x: F) -> String {
+    format!("{:#x}", x.into_bigint().0[0])
+}
+
+/// Round constants of Keccak-f\[1600\], from its LFSR.
+fn keccak_rc() -> [u64; 24] {
+    let mut lfsr = 1u8;
+    let mut rc = [0u64; 24];
+    for c in &mut rc {
+        for j in 0..7 {
+            if lfsr & 1 == 1 {
+                *c ^= 1 << ((1 << j) - 1);
+            }
+            lfsr = (lfsr << 1) ^ if lfsr & 0x80 != 0 { 0x71 } else { 0 };
+        }
+    }
+    rc
+}
+
+/// Rotation of lane `x + 5y` in ρ.
+fn keccak_rotations() -> [u32; 25] {
+    let mut rot = [0u32; 25];
+    let (mut x, mut y) = (1, 0);
+    for t in 0..24 {
+        rot[x + 5 * y] = ((t + 1) * (t + 2) / 2) % 64;
+        (x, y) = (y, (2 * x + 3 * y) % 5);
+    }
+    rot
+}
+
+/// `keccakf()`: Keccak-f\[1600\] on the 25 lanes at `$STATE`, one per
+/// word, lane `x + 5y` at word `x + 5y`.
+fn keccak_f(y: &mut String) {
+    let lane = |base: usize, i: usize| format!("{:#x}", base + 32 * i);
+    let rot = keccak_rotations();
+    let _ = writeln!(y, "function keccakf() {{");
+    let _ = writeln!(y, "    for {{ let round := 0 }} lt(round, 24) {{ round := add(round, 1) }} {{");
+    // θ, with C and D in the first ten scratch lanes; the expressions stay
+    // shallow so the function needs few stack slots.
+    for x in 0..5 {
+        let col: Vec<String> = (0..5).map(|r| format!("mload({})", lane(STATE_AT, x + 5 * r))).collect();
+        let _ = writeln!(
+            y,
+            "        mstore({}, xor(xor(xor(xor({}, {}), {}), {}), {}))",
+            lane(SCRATCH_AT, x),
+            col[0],
+            col[1],
+            col[2],
+            col[3],
+            col[4]
+        );
+    }
+    for x in 0..5 {
+        let (prev, next) = (lane(SCRATCH_AT, (x + 4) % 5), lane(SCRATCH_AT, (x + 1) % 5));
+        let _ = writeln!(
+            y,
+            "        mstore({}, xor(mload({prev}), or(and(shl(1, mload({next})), 0xffffffffffffffff), shr(63, mload({next})))))",
+            lane(SCRATCH_AT, 5 + x)
+        );
+    }
+    for i in 0..25 {
+        let (a, d) = (lane(STATE_AT, i), lane(SCRATCH_AT, 5 + i % 5));
+        let _ = writeln!(y, "        mstore({a}, xor(mload({a}), mload({d})))");
+    }
+    // ρ and π into the scratch lanes: B[y, 2x + 3y] = rot(A[x, y]).
+    for yy in 0..5 {
+        for x in 0..5 {
+            let to = lane(SCRATCH_AT, yy + 5 * ((2 * x + 3 * yy) % 5));
+            let from = lane(STATE_AT, x + 5 * yy);
+            match rot[x + 5 * yy] {
+                0 => {
+                    let _ = writeln!(y, "        mstore({to}, mload({from}))");
+                }
+                r => {
+                    let _ = writeln!(
+                        y,
+                        "        mstore({to}, or(and(shl({r}, mload({from})), 0xffffffffffffffff), shr({}, mload({from}))))",
+                        64 - r
+                    );
+                }
+            }
+        }
+    }
+    // χ back into the state, then ι.
+    for yy in 0..5 {
+        for x in 0..5 {
+            let b = |dx: usize| lane(SCRATCH_AT, (x + dx) % 5 + 5 * yy);
+            let _ = writeln!(
+                y,
+                "        mstore({}, xor(mload({}), and(not(mload({})), mload({}))))",
+                lane(STATE_AT, x + 5 * yy),
+                b(0),
+                b(1),
+                b(2)
+            );
+        }
+    }
+    let _ = writeln!(y, "        mstore($STATE, xor(mload($STATE), mload(add($RC, shl(5, round)))))");
+    let _ = writeln!(y, "    }}");
+    let _ = writeln!(y, "}}");
+}
+
+/// One piece of a transcript string.
+enum Piece {
+    Bytes(Vec<u8>),
+    /// A Yul expression as 8 little-endian bytes.
+    Le8(String),
+    /// A Yul expression below 2^32 as 4 little-endian bytes.
+    Le4(String),
+    /// A Yul expression as 32 bytes.
+    Word(String),
+}
+
+/// Writes `pieces` one after the other from `$TR + at`; returns where they
+/// end. Each store may run past its piece, into the next one's bytes.
+fn write_pieces(y: &mut String, indent: &str, at: usize, pieces: &[Piece]) -> usize {
+    let mut off = at;
+    for piece in pieces {
+        match piece {
+            Piece::Bytes(bytes) => {
+                for chunk in bytes.chunks(32) {
+                    let mut word = [0u8; 32];
+                    word[..chunk.len()].copy_from_slice(chunk);
+                    let _ = writeln!(y, "{indent}mstore(add($TR, {off}), 0x{})", hex_bytes(&word));
+                    off += chunk.len();
+                }
+            }
+            Piece::Le8(e) => {
+                let _ = writeln!(y, "{indent}mstore(add($TR, {off}), shl(192, bswap64({e})))");
+                off += 8;
+            }
+            Piece::Le4(e) => {
+                let _ = writeln!(y, "{indent}mstore(add($TR, {off}), shl(224, shr(32, bswap64({e}))))");
+                off += 4;
+            }
+            Piece::Word(e) => {
+                let _ = writeln!(y, "{indent}mstore(add($TR, {off}), {e})");
+                off += 32;
+            }
+        }
+    }
+    off
+}
+
+/// Pieces of `Transcript::absorb_bytes` and `absorb_field` on the Keccak
+/// backend, merging constant bytes.
+#[derive(Default)]
+struct Absorb(Vec<Piece>);
+
+impl Absorb {
+    /// The FRI transcript right after construction.
+    fn fri() -> Self {
+        let mut a = Self::default();
+        a.raw(fs::TRANSCRIPT_INIT);
+        a.raw(b"FRI/FS");
+        a
+    }
+
+    fn raw(&mut self, bytes: &[u8]) -> &mut Self {
+        match self.0.last_mut() {
+            Some(Piece::Bytes(b)) => b.extend_from_slice(bytes),
+            _ => self.0.push(Piece::Bytes(bytes.to_vec())),
+        }
+        self
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.raw(fs::ABSORB_BYTES).raw(bytes)
+    }
+
+    fn field(&mut self, e: &str) -> &mut Self {
+        self.raw(fs::ABSORB_BYTES);
+        self.0.push(Piece::Le8(e.into()));
+        self
+    }
+}
+
+/// `challenge(len)`, `seed_of(root)`, `pow_seed(seed, nonce)` and
+/// `draw(seed, ell, q, n)`: the FRI transcripts of the query seed,
+/// grinding and the query indices.
+fn transcript_functions(y: &mut String, grinding: bool) {
+    let _ = writeln!(y, "function seed_of(root) -> s {{");
+    let mut a = Absorb::fri();
+    a.bytes(ds::FRI_PROOF_ROOT).raw(fs::ABSORB_BYTES);
+    a.0.push(Piece::Word("root".into()));
+    a.raw(fs::CHALLENGE).raw(ds::FRI_SEED);
+    let len = write_pieces(y, "    ", 0, &a.0);
+    let _ = writeln!(y, "    s := challenge({len})");
+    let _ = writeln!(y, "}}");
+
+    if grinding {
+        let _ = writeln!(y, "function pow_seed(seed, nonce) -> s {{");
+        let mut a = Absorb::fri();
+        a.bytes(ds::FRI_POW).field("seed").field("mod(nonce, $P)");
+        a.raw(fs::CHALLENGE).raw(b"out");
+        let len = write_pieces(y, "    ", 0, &a.0);
+        let _ = writeln!(y, "    s := challenge({len})");
+        let _ = writeln!(y, "}}");
+    }
+
+    // `Transcript::challenge_index`, rejection-sampling 8-byte draws.
+    let _ = writeln!(y, "function draw(seed, ell, q, n) -> i {{");
+    let mut a = Absorb::fri();
+    a.bytes(ds::FRI_INDEX).field("seed").field("ell").field("q");
+    a.bytes(fs::CHALLENGE_INDEX).field("and(n, 0xffffffff)").field("shr(32, n)");
+    a.raw(fs::CHALLENGE_BYTES).raw(b"out");
+    let at = write_pieces(y, "    ", 0, &a.0);
+    let _ = writeln!(y, "    let zone := sub(0xffffffffffffffff, mod(add(sub(0xffffffffffffffff, n), 1), n))");
+    let _ = writeln!(y, "    for {{ let k := 0 }} 1 {{ k := add(k, 1) }} {{");
+    let tail = [
+        Piece::Le4("k".into()),
+        Piece::Bytes([8u64.to_le_bytes(), 0u64.to_le_bytes()].concat()),
+    ];
+    let len = write_pieces(y, "        ", at, &tail);
+    let _ = writeln!(y, "        let x := bswap64(shr(192, keccak256($TR, {len})))");
+    let _ = writeln!(y, "        if iszero(gt(x, zone)) {{");
+    let _ = writeln!(y, "            i := mod(x, n)");
+    let _ = writeln!(y, "            break");
+    let _ = writeln!(y, "        }}");
+    let _ = writeln!(y, "    }}");
+    let _ = writeln!(y, "}}");
+}
+
+/// `proof_root()`: `merkle::meta_root` of the trace root and the layer
+/// roots at `$ROOTS`, as bytes32. Digests are kept as their four lanes.
+fn meta_root(y: &mut String, l: usize) {
+    let lane = |tag: &[u8; 16], i: usize| u64::from_le_bytes(tag[8 * i..8 * i + 8].try_into().unwrap());
+    let _ = writeln!(y, "function proof_root() -> root {{");
+    let n = l + 1;
+    let _ = writeln!(y, "    for {{ let k := 0 }} lt(k, {n}) {{ k := add(k, 1) }} {{");
+    let _ = writeln!(y, "        mstore($MSG, {:#x})", lane(META_LEAF, 0));
+    let _ = writeln!(y, "        mstore(add($MSG, 32), {:#x})", lane(META_LEAF, 1));
+    let _ = writeln!(y, "        mstore(add($MSG, 64), {n})");
+    let _ = writeln!(y, "        mstore(add($MSG, 96), k)");
+    let _ = writeln!(y, "        mstore(add($MSG, 128), mload(add($ROOTS, shl(5, k))))");
+    let _ = writeln!(y, "        pop(sha3(5))");
+    let _ = writeln!(y, "        copy4($STATE, add($META, shl(7, k)))");
+    let _ = writeln!(y, "    }}");
+    // One level at a time, the odd node out carried up.
+    let mut width = n;
+    while width > 1 {
+        let _ = writeln!(y, "    for {{ let j := 0 }} lt(j, {}) {{ j := add(j, 1) }} {{", width / 2);
+        let _ = writeln!(y, "        mstore($MSG, {:#x})", lane(META_NODE, 0));
+        let _ = writeln!(y, "        mstore(add($MSG, 32), {:#x})", lane(META_NODE, 1));
+        let _ = writeln!(y, "        copy4(add($META, shl(8, j)), add($MSG, 64))");
+        let _ = writeln!(y, "        copy4(add($META, add(shl(8, j), 128)), add($MSG, 192))");
+        let _ = writeln!(y, "        pop(sha3(10))");
+        let _ = writeln!(y, "        copy4($STATE, add($META, shl(7, j)))");
+        let _ = writeln!(y, "    }}");
+        if width % 2 == 1 {
+            let _ = writeln!(y, "    copy4(add($META, {}), add($META, {}))", 128 * (width - 1), 128 * (width / 2));
+        }
+        width = width.div_ceil(2);
+    }
+    let _ = writeln!(y, "    root := or(shl(192, bswap64(mload($META))), shl(128, bswap64(mload(add($META, 32)))))");
+    let _ = writeln!(y, "    root := or(root, or(shl(64, bswap64(mload(add($META, 64)))), bswap64(mload(add($META, 96)))))");
+    let _ = writeln!(y, "}}");
+}
+
+/// Functions every contract shares: failure, calldata words, field
+/// arithmetic, SHA3-256 over lanes and Merkle steps.
+const GENERIC: &str = r#"// Marks the proof invalid; the checks run on, on bounded loops, and
+// verify returns false at the end.
+function fail() {
+    mstore($FAILED, 1)
+}
+// The 8-byte big-endian word at calldata offset p.
+function word(p) -> v {
+    v := shr(192, calldataload(p))
+}
+function felt(p) -> v {
+    v := word(p)
+    if iszero(lt(v, $P)) { fail() }
+}
+function fsub(a, b) -> c {
+    c := addmod(a, sub($P, b), $P)
+}
+function fpow(x, n) -> r {
+    r := 1
+    let b := x
+    for { let e := n } gt(e, 0) { e := shr(1, e) } {
+        if and(e, 1) { r := mulmod(r, b, $P) }
+        b := mulmod(b, b, $P)
+    }
+}
+function bswap64(x) -> v {
+    x := or(shr(8, and(x, 0xff00ff00ff00ff00)), shl(8, and(x, 0x00ff00ff00ff00ff)))
+    x := or(shr(16, and(x, 0xffff0000ffff0000)), shl(16, and(x, 0x0000ffff0000ffff)))
+    v := or(shr(32, x), and(shl(32, x), 0xffffffff00000000))
+}
+function copy4(from, to) {
+    for { let k := 0 } lt(k, 4) { k := add(k, 1) } {
+        mstore(add(to, shl(5, k)), mload(add(from, shl(5, k))))
+    }
+}
+// SHA3-256 of the n little-endian lanes at $MSG: returns the first lane
+// and leaves all four at $STATE.
+function sha3(n) -> h {
+    for { let k := 0 } lt(k, 25) { k := add(k, 1) } { mstore(add($STATE, shl(5, k)), 0) }
+    let p := $MSG
+    let left := n
+    for {} gt(left, 16) { left := sub(left, 17) } {
+        absorb(p, 17)
+        keccakf()
+        p := add(p, 544)
+    }
+    absorb(p, left)
+    let t := add($STATE, shl(5, left))
+    mstore(t, xor(mload(t), 6))
+    t := add($STATE, 512)
+    mstore(t, xor(mload(t), 0x8000000000000000))
+    keccakf()
+    h := mload($STATE)
+}
+function absorb(p, n) {
+    for { let k := 0 } lt(k, n) { k := add(k, 1) } {
+        let t := add($STATE, shl(5, k))
+        mstore(t, xor(mload(t), mload(add(p, shl(5, k)))))
+    }
+}
+// A 64-bit Merkle node from its label and children.
+function node(n) -> v {
+    v := mod(sha3(n), $P)
+}
+// Digest of leaf idx holding the five values at calldata offset cd.
+function leaf(idx, cd, arity, label) -> v {
+    mstore($MSG, arity)
+    mstore(add($MSG, 32), $LEAF_LEVEL)
+    mstore(add($MSG, 64), idx)
+    mstore(add($MSG, 96), label)
+    for { let k := 0 } lt(k, 5) { k := add(k, 1) } {
+        mstore(add($MSG, shl(5, add(k, 4))), felt(add(cd, shl(3, k))))
+    }
+    v := node(9)
+}
+// Parent of node idx on level - 1, which is cur, with its arity - 1
+// siblings read from cd on.
+function climb(cur, idx, cd, arity, level, label) -> up {
+    let pos := mod(idx, arity)
+    let at := cd
+    for { let k := 0 } lt(k, arity) { k := add(k, 1) } {
+        switch eq(k, pos)
+        case 1 { mstore(add($MSG, shl(5, add(k, 4))), cur) }
+        default {
+            mstore(add($MSG, shl(5, add(k, 4))), felt(at))
+            at := add(at, 8)
+        }
+    }
+    mstore($MSG, arity)
+    mstore(add($MSG, 32), level)
+    mstore(add($MSG, 64), div(idx, arity))
+    mstore(add($MSG, 96), label)
+    up := node(add(arity, 4))
+}
+// The first 8-byte little-endian word below p of keccak256 of the len
+// transcript bytes at $TR, then of those bytes and i = 1, 2, ...
+function challenge(len) -> c {
+    let found := 0
+    for { let i := 0 } iszero(found) { i := add(i, 1) } {
+        let h := keccak256($TR, len)
+        if i {
+            mstore(add($TR, len), shl(192, bswap64(i)))
+            h := keccak256($TR, add(len, 8))
+        }
+        for { let w := 0 } lt(w, 4) { w := add(w, 1) } {
+            c := bswap64(and(shr(sub(192, shl(6, w)), h), 0xffffffffffffffff))
+            if lt(c, $P) {
+                found := 1
+                break
+            }
+        }
+    }
+}
+// The DEEP check q_j·(x − z_j) = f_i − f_0 on the leaf at p.
+function deep(p, x, f0) {
+    let num := fsub(word(p), f0)
+    if iszero(eq(mulmod(word(add(p, 16)), fsub(x, $Z0), $P), num)) { fail() }
+    if iszero(eq(mulmod(word(add(p, 24)), fsub(x, $Z1), $P), num)) { fail() }
+    if iszero(eq(mulmod(word(add(p, 32)), fsub(x, $Z2), $P), num)) { fail() }
+}
+// Powers-of-z fold of the m coset values at $COSET.
+function zfold(m) -> v {
+    let zp := 1
+    for { let j := 0 } lt(j, m) { j := add(j, 1) } {
+        v := addmod(v, mulmod(mload(add($COSET, shl(5, j))), zp, $P), $P)
+        zp := mulmod(zp, $Z0, $P)
+    }
+}
+// Coset-interpolation fold of the m coset values at $COSET over coset b,
+// summing the Lagrange terms as one fraction.
+function interp(b, m, omega_inv, zeta, m_inv) -> v {
+    let y := mulmod($Z0, fpow(omega_inv, b), $P)
+    let num := 0
+    let den := 1
+    let zj := 1
+    let hit := 0
+    for { let j := 0 } lt(j, m) { j := add(j, 1) } {
+        let d := fsub(y, zj)
+        if iszero(d) {
+            v := mload(add($COSET, shl(5, j)))
+            hit := 1
+            break
+        }
+        num := addmod(mulmod(num, d, $P), mulmod(mulmod(mload(add($COSET, shl(5, j))), zj, $P), den, $P), $P)
+        den := mulmod(den, d, $P)
+        zj := mulmod(zj, zeta, $P)
+    }
+    if iszero(hit) {
+        v := mulmod(num, fpow(den, sub($P, 2)), $P)
+        v := mulmod(v, mulmod(fsub(fpow(y, m), 1), m_inv, $P), $P)
+    }
+}
+"#;
+
+#[cfg(all(test, feature = "std"))]
+mod evm;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fri::{deep_fri_prove_with_trace_root, deep_fri_verify, DeepFriParams, FriDomain};
+    use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+    use merkle::DigestWidth;
+    use sha3::{Digest, Keccak256};
+
+    const N: usize = 1 << 8;
+
+    fn params(schedule: Vec<usize>, fold_check: FoldCheck, index_chaining: IndexChaining) -> DeepFriParams {
+        DeepFriParams {
+            schedule,
+            r: 3,
+            seed_z: 9,
+            folding_mode: FoldingMode::CosetInterpolation,
+            fold_check,
+            grinding_bits: 2,
+            query_sampling: QuerySampling::Distinct,
+            index_chaining,
+            transcript: TranscriptHash::Keccak256,
+        }
+    }
+
+    fn prove(params: &DeepFriParams, trace_root: Option<F>) -> DeepFriProof {
+        let coeffs: Vec<F> = (0..N as u64 / 32).map(|i| F::from(i * i + 7)).collect();
+        let f0 = GeneralEvaluationDomain::<F>::new(N).unwrap().fft(&coeffs);
+        deep_fri_prove_with_trace_root(f0, FriDomain::new_radix2(N), params, trace_root)
+    }
+
+    fn verifier(params: &DeepFriParams) -> SolidityVerifier {
+        SolidityVerifier::new(&PublicParams::for_fri(params, N), params.seed_z).unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn encoded_proofs_decode_to_valid_proofs() {
+        for params in [
+            params(vec![4, 4, 4, 4], FoldCheck::CommittedS, IndexChaining::Strided),
+            params(vec![8, 4, 8], FoldCheck::RecomputeFromCoset, IndexChaining::Rerandomized),
+            params(vec![16, 16], FoldCheck::RecomputeFromCoset, IndexChaining::Strided),
+        ] {
+            let v = verifier(&params);
+            let proof = prove(&params, Some(F::from(12345u64)));
+            let bytes = v.encode(&proof).unwrap();
+            assert_eq!(bytes.len(), v.proof_len());
+            let decoded = v.decode(&bytes).unwrap();
+            assert!(deep_fri_verify(v.key(), &decoded));
+            assert_eq!(decoded.proof_root, proof.proof_root);
+            assert_eq!(v.encode(&decoded).unwrap(), bytes);
+
+            let calldata = v.calldata(&proof).unwrap();
+            assert_eq!(calldata[..4], VERIFY_SELECTOR);
+            assert_eq!(calldata[4..36], abi_word(32));
+            assert_eq!(calldata[36..68], abi_word(bytes.len() as u64));
+            assert_eq!(calldata[68..68 + bytes.len()], bytes[..]);
+            assert_eq!(calldata.len() % 32, 4);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn tampered_proofs_are_rejected() {
+        // One query, so that no sibling is redundant with another path.
+        let params = DeepFriParams {
+            r: 1,
+            ..params(vec![8, 4, 8], FoldCheck::RecomputeFromCoset, IndexChaining::Rerandomized)
+        };
+        let v = verifier(&params);
+        let bytes = v.encode(&prove(&params, None)).unwrap();
+        let accepts = |b: &[u8]| v.decode(b).is_ok_and(|p| deep_fri_verify(v.key(), &p));
+        assert!(accepts(&bytes));
+        // Every word past the nonce is checked; the nonce only through
+        // grinding, which a flipped high bit fails here.
+        for at in (0..bytes.len()).step_by(WORD) {
+            let mut b = bytes.clone();
+            b[at] ^= 0x10;
+            assert!(!accepts(&b), "byte {at}");
+        }
+        assert_eq!(
+            v.decode(&bytes[1..]).err(),
+            Some(SolidityError::Length { expected: bytes.len(), got: bytes.len() - 1 })
+        );
+        let mut b = bytes.clone();
+        b[WORD..2 * WORD].copy_from_slice(&F::MODULUS.0[0].to_be_bytes());
+        assert_eq!(v.decode(&b).err(), Some(SolidityError::NonCanonical { offset: WORD }));
+
+        // Proofs of another shape or transcript are not encoded.
+        let other = params.clone();
+        let mut proof = prove(&other, None);
+        proof.queries.pop();
+        assert_eq!(v.encode(&proof).unwrap_err(), SolidityError::Shape);
+        let poseidon = DeepFriParams { transcript: TranscriptHash::Poseidon, ..params };
+        assert_eq!(v.encode(&prove(&poseidon, None)).unwrap_err(), SolidityError::Shape);
+    }
+
+    /// The contract, run on revm, accepts honest proofs and agrees with
+    /// `deep_fri_verify` on every single-word tampering of them.
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    #[cfg(feature = "std")]
+    fn contracts_accept_exactly_the_valid_proofs() {
+        let call = |code: &[u8], b: &[u8]| {
+            let mut calldata = VERIFY_SELECTOR.to_vec();
+            calldata.extend_from_slice(&abi_word(32));
+            calldata.extend_from_slice(&abi_word(b.len() as u64));
+            calldata.extend_from_slice(b);
+            calldata.resize(calldata.len().next_multiple_of(32) + 4, 0);
+            evm::call(code, &calldata) == Some(true)
+        };
+        let compile = |v: &SolidityVerifier| {
+            let sol = v.contract("FriVerifier");
+            evm::solc_runtime(&sol).unwrap_or_else(|| evm::runtime(&sol))
+        };
+        for params in [
+            params(vec![4, 4, 4, 4], FoldCheck::CommittedS, IndexChaining::Strided),
+            params(vec![8, 4, 8], FoldCheck::RecomputeFromCoset, IndexChaining::Rerandomized),
+            params(vec![16, 16], FoldCheck::RecomputeFromCoset, IndexChaining::Strided),
+        ] {
+            let v = verifier(&params);
+            let code = compile(&v);
+            let proof = prove(&params, Some(F::from(12345u64)));
+            assert_eq!(evm::call(&code, &v.calldata(&proof).unwrap()), Some(true));
+            assert!(!call(&code, &v.encode(&proof).unwrap()[WORD..]));
+
+            // One query, so that no sibling is redundant with another path.
+            let params = DeepFriParams { r: 1, ..params };
+            let v = verifier(&params);
+            let code = compile(&v);
+            let bytes = v.encode(&prove(&params, None)).unwrap();
+            let accepts = |b: &[u8]| v.decode(b).is_ok_and(|p| deep_fri_verify(v.key(), &p));
+            assert!(call(&code, &bytes));
+            for at in (0..bytes.len()).step_by(WORD) {
+                let mut b = bytes.clone();
+                b[at] ^= 0x10;
+                assert_eq!(call(&code, &b), accepts(&b), "byte {at}");
+            }
+        }
+    }
+
+    #[test]
+    fn only_keccak_transcripts_and_sha3_trees_are_supported() {
+        let keccak = params(vec![4, 4], FoldCheck::CommittedS, IndexChaining::Strided);
+        let poseidon = DeepFriParams { transcript: TranscriptHash::Poseidon, ..keccak.clone() };
+        assert_eq!(
+            SolidityVerifier::new(&PublicParams::for_fri(&poseidon, N), 9).err(),
+            Some(SolidityError::Transcript(TranscriptHash::Poseidon))
+        );
+        let mut public = PublicParams::for_fri(&keccak, N);
+        public.merkle[1] = public.merkle[1].clone().with_digest(DigestWidth::Digest256);
+        assert_eq!(SolidityVerifier::new(&public, 9).err(), Some(SolidityError::Merkle { layer: 1 }));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn contracts_embed_their_parameters() {
+        assert_eq!(Keccak256::digest(b"verify(bytes)")[..4], VERIFY_SELECTOR);
+        let params = params(vec![4, 4, 4, 4], FoldCheck::CommittedS, IndexChaining::Strided);
+        let v = verifier(&params);
+        let sol = v.contract("FriVerifier");
+        assert!(sol.contains("contract FriVerifier {"));
+        assert!(sol.contains(&format!("PARAMS_DIGEST = 0x{};", hex_bytes(&v.key().public.digest()))));
+        assert!(sol.contains(&format!("if eq(proof.length, {}) {{", v.proof_len())));
+        assert!(!sol.contains('$'));
+        assert_eq!(sol.matches('{').count(), sol.matches('}').count());
+    }
+
+    #[test]
+    fn keccak_constants_match_the_standard() {
+        let rc = keccak_rc();
+        assert_eq!((rc[0], rc[1], rc[23]), (1, 0x8082, 0x8000_0000_8000_8008));
+        let rot = keccak_rotations();
+        assert_eq!((rot[0], rot[1], rot[2 + 5 * 4], rot[4 + 5 * 4]), (0, 1, 61, 14));
+    }
+}
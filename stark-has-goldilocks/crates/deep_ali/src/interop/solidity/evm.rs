@@ -0,0 +1,697 @@
+//! Runs generated verifiers on revm.
+//!
+//! [`runtime`] compiles the Yul inside a contract's `verify` to runtime
+//! bytecode behind a `verify(bytes)` dispatcher laid out as solc lays out
+//! the call: the free memory pointer at 0x80, `proof.offset` and
+//! `proof.length` from the ABI head, `ok` returned as one word. Variables
+//! live in memory frames from [`FRAMES`] on, above everything the contract
+//! addresses, so no function runs out of stack slots. The compiler takes
+//! the Yul the generator emits and panics on anything else. With `SOLC`
+//! naming a solc binary, [`solc_runtime`] compiles the contract as
+//! written instead.
+
+use std::collections::HashMap;
+
+use revm::{
+    context::{Context, TxEnv},
+    context_interface::result::ExecutionResult,
+    database::{CacheDB, EmptyDB},
+    primitives::{Address, Bytes, TxKind, U256},
+    state::{AccountInfo, Bytecode},
+    ExecuteEvm, MainBuilder, MainContext,
+};
+
+/// First byte of the call frames; the frame pointer is kept at 0.
+const FRAMES: usize = 0x10000;
+
+/// The Yul in `contract`'s `assembly` block.
+fn assembly(contract: &str) -> String {
+    let start = contract.find("assembly {").expect("no assembly block") + "assembly {".len();
+    let mut depth = 1;
+    for (at, c) in contract[start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return contract[start..start + at].to_string();
+        }
+    }
+    panic!("unterminated assembly block")
+}
+
+/// Runtime bytecode of `contract`, compiled here.
+pub(super) fn runtime(contract: &str) -> Vec<u8> {
+    let src = format!(
+        "mstore(0x40, 0x80)
+         if lt(calldatasize(), 68) {{ revert(0, 0) }}
+         if iszero(eq(shr(224, calldataload(0)), 0x{})) {{ revert(0, 0) }}
+         let proof.offset := add(add(4, calldataload(4)), 32)
+         let proof.length := calldataload(sub(proof.offset, 32))
+         let ok := 0
+         {{ {} }}
+         mstore(0x20, ok)
+         return(0x20, 32)",
+        super::hex_bytes(&super::VERIFY_SELECTOR),
+        assembly(contract)
+    );
+    let mut parser = Parser { toks: tokens(&src), pos: 0 };
+    let mut main = Vec::new();
+    while parser.pos < parser.toks.len() {
+        main.push(parser.stmt());
+    }
+
+    let mut functions = Vec::new();
+    hoist(&mut main, &mut functions);
+    let mut gen = Codegen::default();
+    for (k, f) in functions.iter().enumerate() {
+        gen.functions.insert(f.name.clone(), (k, f.params.len(), f.rets.len()));
+    }
+    gen.labels = functions.len();
+
+    gen.push(U256::from(FRAMES));
+    gen.push(U256::ZERO);
+    gen.op(MSTORE);
+    gen.body(&main, 2);
+    gen.op(STOP);
+    for (k, f) in functions.iter().enumerate() {
+        gen.function(k, f);
+    }
+    gen.assemble()
+}
+
+/// Runtime bytecode of `contract` from the solc that `SOLC` names, if set.
+pub(super) fn solc_runtime(contract: &str) -> Option<Vec<u8>> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let solc = std::env::var_os("SOLC")?;
+    let mut child = Command::new(solc)
+        .args(["--optimize", "--bin-runtime", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("SOLC does not run");
+    child.stdin.take().unwrap().write_all(contract.as_bytes()).unwrap();
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success(), "solc rejected the contract");
+    let text = String::from_utf8(out.stdout).unwrap();
+    let hex = text.lines().skip_while(|l| !l.starts_with("Binary of the runtime part")).nth(1)?;
+    Some(hex::decode(hex.trim()).unwrap())
+}
+
+/// Calls `code` with `calldata`: the returned `ok`, or `None` if the call
+/// reverted.
+pub(super) fn call(code: &[u8], calldata: &[u8]) -> Option<bool> {
+    let (caller, contract) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+    let mut db = CacheDB::new(EmptyDB::default());
+    db.insert_account_info(caller, AccountInfo { balance: U256::from(10u128.pow(30)), ..Default::default() });
+    db.insert_account_info(contract, AccountInfo::default().with_code(Bytecode::new_raw(Bytes::copy_from_slice(code))));
+    let mut evm = Context::mainnet()
+        .with_db(db)
+        .modify_cfg_chained(|cfg| cfg.tx_gas_limit_cap = Some(u64::MAX))
+        .build_mainnet();
+    let tx = TxEnv::builder()
+        .caller(caller)
+        .kind(TxKind::Call(contract))
+        .data(Bytes::copy_from_slice(calldata))
+        .gas_limit(1 << 32)
+        .build()
+        .unwrap();
+    match evm.transact(tx).unwrap().result {
+        ExecutionResult::Success { output, .. } => {
+            let word = output.data();
+            assert_eq!(word.len(), 32);
+            Some(word[31] == 1)
+        }
+        ExecutionResult::Revert { .. } => None,
+        other => panic!("call halted: {other:?}"),
+    }
+}
+
+/* ============================================================
+   Yul
+============================================================ */
+
+enum Expr {
+    Lit(U256),
+    Var(String),
+    Call(String, Vec<Expr>),
+}
+
+enum Stmt {
+    Let(Vec<String>, Option<Expr>),
+    Assign(Vec<String>, Expr),
+    Expr(Expr),
+    If(Expr, Vec<Stmt>),
+    For(Vec<Stmt>, Expr, Vec<Stmt>, Vec<Stmt>),
+    Switch(Expr, Vec<(U256, Vec<Stmt>)>, Option<Vec<Stmt>>),
+    Break,
+    Continue,
+    Block(Vec<Stmt>),
+    Function(Function),
+}
+
+struct Function {
+    name: String,
+    params: Vec<String>,
+    rets: Vec<String>,
+    body: Vec<Stmt>,
+}
+
+fn tokens(src: &str) -> Vec<String> {
+    let word = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '$' || c == '.';
+    let mut out = Vec::new();
+    let mut chars = src.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '/' {
+            chars.by_ref().take_while(|&c| c != '\n').for_each(drop);
+        } else if word(c) {
+            let mut t = String::new();
+            while let Some(&c) = chars.peek().filter(|&&c| word(c)) {
+                t.push(c);
+                chars.next();
+            }
+            out.push(t);
+        } else if c == ':' || c == '-' {
+            chars.next();
+            out.push(format!("{c}{}", chars.next().unwrap()));
+        } else {
+            out.push(c.to_string());
+            chars.next();
+        }
+    }
+    out
+}
+
+fn literal(t: &str) -> U256 {
+    match t.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16),
+        None => U256::from_str_radix(t, 10),
+    }
+    .unwrap_or_else(|_| panic!("bad literal {t}"))
+}
+
+struct Parser {
+    toks: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self, k: usize) -> &str {
+        self.toks.get(self.pos + k).map_or("", String::as_str)
+    }
+
+    fn next(&mut self) -> String {
+        self.pos += 1;
+        self.toks[self.pos - 1].clone()
+    }
+
+    fn expect(&mut self, t: &str) {
+        let got = self.next();
+        assert_eq!(got, t, "Yul token {}", self.pos);
+    }
+
+    fn names(&mut self) -> Vec<String> {
+        let mut names = vec![self.next()];
+        while self.peek(0) == "," {
+            self.next();
+            names.push(self.next());
+        }
+        names
+    }
+
+    fn block(&mut self) -> Vec<Stmt> {
+        self.expect("{");
+        let mut out = Vec::new();
+        while self.peek(0) != "}" {
+            out.push(self.stmt());
+        }
+        self.expect("}");
+        out
+    }
+
+    fn stmt(&mut self) -> Stmt {
+        match self.peek(0) {
+            "{" => Stmt::Block(self.block()),
+            "function" => {
+                self.next();
+                let name = self.next();
+                self.expect("(");
+                let params = if self.peek(0) == ")" { Vec::new() } else { self.names() };
+                self.expect(")");
+                let rets = if self.peek(0) == "->" {
+                    self.next();
+                    self.names()
+                } else {
+                    Vec::new()
+                };
+                Stmt::Function(Function { name, params, rets, body: self.block() })
+            }
+            "let" => {
+                self.next();
+                let names = self.names();
+                let value = (self.peek(0) == ":=").then(|| {
+                    self.next();
+                    self.expr()
+                });
+                Stmt::Let(names, value)
+            }
+            "if" => {
+                self.next();
+                Stmt::If(self.expr(), self.block())
+            }
+            "for" => {
+                self.next();
+                let init = self.block();
+                let cond = self.expr();
+                let post = self.block();
+                Stmt::For(init, cond, post, self.block())
+            }
+            "switch" => {
+                self.next();
+                let on = self.expr();
+                let mut cases = Vec::new();
+                while self.peek(0) == "case" {
+                    self.next();
+                    let value = literal(&self.next());
+                    cases.push((value, self.block()));
+                }
+                let default = (self.peek(0) == "default").then(|| {
+                    self.next();
+                    self.block()
+                });
+                Stmt::Switch(on, cases, default)
+            }
+            "break" => {
+                self.next();
+                Stmt::Break
+            }
+            "continue" => {
+                self.next();
+                Stmt::Continue
+            }
+            _ if matches!(self.peek(1), ":=" | ",") => {
+                let names = self.names();
+                self.expect(":=");
+                Stmt::Assign(names, self.expr())
+            }
+            _ => Stmt::Expr(self.expr()),
+        }
+    }
+
+    fn expr(&mut self) -> Expr {
+        let t = self.next();
+        if t.starts_with(|c: char| c.is_ascii_digit()) {
+            return Expr::Lit(literal(&t));
+        }
+        if self.peek(0) != "(" {
+            return Expr::Var(t);
+        }
+        self.next();
+        let mut args = Vec::new();
+        while self.peek(0) != ")" {
+            args.push(self.expr());
+            if self.peek(0) == "," {
+                self.next();
+            }
+        }
+        self.next();
+        Expr::Call(t, args)
+    }
+}
+
+/// Moves every function definition in `block`, nested ones included, to
+/// `out`; the generator never reuses a name.
+fn hoist(block: &mut Vec<Stmt>, out: &mut Vec<Function>) {
+    for stmt in core::mem::take(block) {
+        match stmt {
+            Stmt::Function(mut f) => {
+                hoist(&mut f.body, out);
+                out.push(f);
+            }
+            Stmt::Block(mut b) => {
+                hoist(&mut b, out);
+                block.push(Stmt::Block(b));
+            }
+            other => block.push(other),
+        }
+    }
+}
+
+/* ============================================================
+   Code generation
+============================================================ */
+
+const STOP: u8 = 0x00;
+const ADD: u8 = 0x01;
+const ISZERO: u8 = 0x15;
+const POP: u8 = 0x50;
+const MLOAD: u8 = 0x51;
+const MSTORE: u8 = 0x52;
+const JUMP: u8 = 0x56;
+const JUMPI: u8 = 0x57;
+const JUMPDEST: u8 = 0x5b;
+const DUP1: u8 = 0x80;
+const DUP2: u8 = 0x81;
+const SWAP1: u8 = 0x90;
+
+/// Opcode, argument count and result count of a Yul builtin.
+fn builtin(name: &str) -> Option<(u8, usize, usize)> {
+    Some(match name {
+        "add" => (0x01, 2, 1),
+        "mul" => (0x02, 2, 1),
+        "sub" => (0x03, 2, 1),
+        "div" => (0x04, 2, 1),
+        "mod" => (0x06, 2, 1),
+        "addmod" => (0x08, 3, 1),
+        "mulmod" => (0x09, 3, 1),
+        "lt" => (0x10, 2, 1),
+        "gt" => (0x11, 2, 1),
+        "eq" => (0x14, 2, 1),
+        "iszero" => (0x15, 1, 1),
+        "and" => (0x16, 2, 1),
+        "or" => (0x17, 2, 1),
+        "xor" => (0x18, 2, 1),
+        "not" => (0x19, 1, 1),
+        "shl" => (0x1b, 2, 1),
+        "shr" => (0x1c, 2, 1),
+        "keccak256" => (0x20, 2, 1),
+        "calldataload" => (0x35, 1, 1),
+        "calldatasize" => (0x36, 0, 1),
+        "pop" => (0x50, 1, 0),
+        "mload" => (0x51, 1, 1),
+        "mstore" => (0x52, 2, 0),
+        "return" => (0xf3, 2, 0),
+        "revert" => (0xfd, 2, 0),
+        _ => return None,
+    })
+}
+
+enum Op {
+    Code(u8),
+    Push(U256),
+    /// The byte size of a frame, known once every function is compiled.
+    PushFrame,
+    PushLabel(usize),
+    Label(usize),
+}
+
+/// Frames hold the caller's frame pointer, the return address, then the
+/// parameters, return variables and locals, one word each.
+#[derive(Default)]
+struct Codegen {
+    ops: Vec<Op>,
+    labels: usize,
+    /// Label, parameter and return count of each function; a function's
+    /// label is its index.
+    functions: HashMap<String, (usize, usize, usize)>,
+    scopes: Vec<HashMap<String, usize>>,
+    slots: usize,
+    max_slots: usize,
+    /// Continue and break labels of the enclosing loops.
+    loops: Vec<(usize, usize)>,
+}
+
+impl Codegen {
+    fn op(&mut self, code: u8) {
+        self.ops.push(Op::Code(code));
+    }
+
+    fn push(&mut self, value: U256) {
+        self.ops.push(Op::Push(value));
+    }
+
+    fn label(&mut self) -> usize {
+        self.labels += 1;
+        self.labels - 1
+    }
+
+    fn jump(&mut self, label: usize, code: u8) {
+        self.ops.push(Op::PushLabel(label));
+        self.op(code);
+    }
+
+    fn place(&mut self, label: usize) {
+        self.ops.push(Op::Label(label));
+    }
+
+    fn declare(&mut self, name: &str) -> usize {
+        let slot = self.slots;
+        self.slots += 1;
+        self.max_slots = self.max_slots.max(self.slots);
+        self.scopes.last_mut().unwrap().insert(name.to_string(), slot);
+        slot
+    }
+
+    fn slot(&self, name: &str) -> usize {
+        self.scopes.iter().rev().find_map(|s| s.get(name).copied()).unwrap_or_else(|| panic!("unknown variable {name}"))
+    }
+
+    /// Pushes the address of `slot` in the current frame.
+    fn address(&mut self, slot: usize) {
+        self.push(U256::ZERO);
+        self.op(MLOAD);
+        self.push(U256::from(32 * slot));
+        self.op(ADD);
+    }
+
+    fn load(&mut self, slot: usize) {
+        self.address(slot);
+        self.op(MLOAD);
+    }
+
+    /// Stores the top of the stack in `slot`.
+    fn store(&mut self, slot: usize) {
+        self.address(slot);
+        self.op(MSTORE);
+    }
+
+    /// Compiles `body` as a function body whose variables start at `slot`.
+    fn body(&mut self, body: &[Stmt], slot: usize) {
+        self.slots = slot;
+        self.scopes.push(HashMap::new());
+        self.block(body);
+        self.scopes.pop();
+    }
+
+    fn function(&mut self, label: usize, f: &Function) {
+        self.place(label);
+        self.slots = 2;
+        self.scopes.push(HashMap::new());
+        // [.., a1, a0, ret] -> new frame, saved frame pointer and return
+        // address, then each argument into its slot.
+        self.push(U256::ZERO);
+        self.op(MLOAD);
+        self.ops.push(Op::PushFrame);
+        self.op(ADD);
+        self.push(U256::ZERO);
+        self.op(MLOAD);
+        self.op(DUP2);
+        self.op(MSTORE);
+        self.op(SWAP1);
+        self.op(DUP2);
+        self.push(U256::from(32));
+        self.op(ADD);
+        self.op(MSTORE);
+        for p in &f.params {
+            let slot = self.declare(p);
+            self.op(SWAP1);
+            self.op(DUP2);
+            self.push(U256::from(32 * slot));
+            self.op(ADD);
+            self.op(MSTORE);
+        }
+        self.push(U256::ZERO);
+        self.op(MSTORE);
+        let rets: Vec<usize> = f.rets.iter().map(|r| self.declare(r)).collect();
+        for &slot in &rets {
+            self.push(U256::ZERO);
+            self.store(slot);
+        }
+        self.body(&f.body, self.slots);
+        for &slot in &rets {
+            self.load(slot);
+        }
+        // Return address, then the caller's frame back in place.
+        self.load(1);
+        self.load(0);
+        self.push(U256::ZERO);
+        self.op(MSTORE);
+        self.op(JUMP);
+        self.scopes.pop();
+    }
+
+    fn block(&mut self, block: &[Stmt]) {
+        self.scopes.push(HashMap::new());
+        for stmt in block {
+            self.stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Let(names, value) => {
+                match value {
+                    Some(e) => assert_eq!(self.expr(e), names.len()),
+                    None => names.iter().for_each(|_| self.push(U256::ZERO)),
+                }
+                let slots: Vec<usize> = names.iter().map(|n| self.declare(n)).collect();
+                for &slot in slots.iter().rev() {
+                    self.store(slot);
+                }
+            }
+            Stmt::Assign(names, e) => {
+                assert_eq!(self.expr(e), names.len());
+                for name in names.iter().rev() {
+                    let slot = self.slot(name);
+                    self.store(slot);
+                }
+            }
+            Stmt::Expr(e) => {
+                for _ in 0..self.expr(e) {
+                    self.op(POP);
+                }
+            }
+            Stmt::If(cond, body) => {
+                let end = self.label();
+                self.value(cond);
+                self.op(ISZERO);
+                self.jump(end, JUMPI);
+                self.block(body);
+                self.place(end);
+            }
+            Stmt::For(init, cond, post, body) => {
+                let (start, next, end) = (self.label(), self.label(), self.label());
+                self.scopes.push(HashMap::new());
+                for s in init {
+                    self.stmt(s);
+                }
+                self.place(start);
+                self.value(cond);
+                self.op(ISZERO);
+                self.jump(end, JUMPI);
+                self.loops.push((next, end));
+                self.block(body);
+                self.loops.pop();
+                self.place(next);
+                self.block(post);
+                self.jump(start, JUMP);
+                self.place(end);
+                self.scopes.pop();
+            }
+            Stmt::Switch(on, cases, default) => {
+                let end = self.label();
+                let labels: Vec<usize> = cases.iter().map(|_| self.label()).collect();
+                self.value(on);
+                for ((value, _), &label) in cases.iter().zip(&labels) {
+                    self.op(DUP1);
+                    self.push(*value);
+                    self.op(0x14);
+                    self.jump(label, JUMPI);
+                }
+                self.op(POP);
+                if let Some(body) = default {
+                    self.block(body);
+                }
+                self.jump(end, JUMP);
+                for ((_, body), &label) in cases.iter().zip(&labels) {
+                    self.place(label);
+                    self.op(POP);
+                    self.block(body);
+                    self.jump(end, JUMP);
+                }
+                self.place(end);
+            }
+            Stmt::Break => {
+                let end = self.loops.last().expect("break outside a loop").1;
+                self.jump(end, JUMP);
+            }
+            Stmt::Continue => {
+                let next = self.loops.last().expect("continue outside a loop").0;
+                self.jump(next, JUMP);
+            }
+            Stmt::Block(body) => self.block(body),
+            Stmt::Function(_) => unreachable!("functions are hoisted"),
+        }
+    }
+
+    fn value(&mut self, e: &Expr) {
+        assert_eq!(self.expr(e), 1, "expression without a single value");
+    }
+
+    /// Pushes the values of `e`, the last one on top; returns their count.
+    fn expr(&mut self, e: &Expr) -> usize {
+        match e {
+            Expr::Lit(v) => {
+                self.push(*v);
+                1
+            }
+            Expr::Var(name) => {
+                let slot = self.slot(name);
+                self.load(slot);
+                1
+            }
+            Expr::Call(name, args) => {
+                // The first argument ends up on top, as the EVM reads it.
+                if let Some((code, n, rets)) = builtin(name) {
+                    assert_eq!(args.len(), n, "{name} takes {n} arguments");
+                    for a in args.iter().rev() {
+                        self.value(a);
+                    }
+                    self.op(code);
+                    return rets;
+                }
+                let (label, n, rets) = *self.functions.get(name).unwrap_or_else(|| panic!("unknown function {name}"));
+                assert_eq!(args.len(), n, "{name} takes {n} arguments");
+                let back = self.label();
+                for a in args.iter().rev() {
+                    self.value(a);
+                }
+                self.ops.push(Op::PushLabel(back));
+                self.jump(label, JUMP);
+                self.place(back);
+                rets
+            }
+        }
+    }
+
+    fn assemble(&self) -> Vec<u8> {
+        let size = |op: &Op| match op {
+            Op::Code(_) | Op::Label(_) => 1,
+            Op::Push(v) => 1 + v.byte_len().max(1),
+            Op::PushFrame | Op::PushLabel(_) => 4,
+        };
+        let mut at = vec![0; self.labels];
+        let mut pc = 0;
+        for op in &self.ops {
+            if let Op::Label(l) = op {
+                at[*l] = pc;
+            }
+            pc += size(op);
+        }
+        let mut code = Vec::with_capacity(pc);
+        let push3 = |code: &mut Vec<u8>, v: usize| {
+            assert!(v < 1 << 24);
+            code.extend_from_slice(&[0x62, (v >> 16) as u8, (v >> 8) as u8, v as u8]);
+        };
+        for op in &self.ops {
+            match op {
+                Op::Code(c) => code.push(*c),
+                Op::Label(_) => code.push(JUMPDEST),
+                Op::Push(v) => {
+                    let n = v.byte_len().max(1);
+                    code.push(0x5f + n as u8);
+                    code.extend_from_slice(&v.to_be_bytes::<32>()[32 - n..]);
+                }
+                Op::PushFrame => push3(&mut code, 32 * self.max_slots),
+                Op::PushLabel(l) => push3(&mut code, at[*l]),
+            }
+        }
+        code
+    }
+}
@@ -0,0 +1,572 @@
+//! FRI proofs in Winterfell's layout, for cross-checking against
+//! `winter-fri`.
+//!
+//! [`prove`] commits the layers of a Goldilocks codeword the way
+//! Winterfell's `FriProver` does: the `N`-point cosets of each layer are
+//! rows hashed as one leaf with `hash_elements`, under a `MerkleTree` of
+//! plain `merge`s; `DefaultRandomCoin` is reseeded with each root to draw
+//! the folding challenge and finally the query positions; the remainder is
+//! sent as coefficients. Folding and interpolation are this crate's
+//! ([`fold_coset_interpolation`]); Winterfell supplies the hashes, trees and
+//! coin. The result is a `winter_fri::FriProof` that Winterfell's
+//! `FriVerifier` accepts, and [`verify`] checks a `FriProof` from either
+//! side. Only the base field (Winterfell's `FieldExtension::None`) and a
+//! single partition are supported.
+//!
+//! Winterfell evaluates over the coset `7·⟨g⟩` with its own two-adic root,
+//! not this crate's `DomainH`; [`export`] re-evaluates a DEEP-FRI input
+//! `f0` over `H` onto that coset first.
+
+use alloc::vec::Vec;
+use ark_ff::{Field, One, PrimeField, Zero};
+use ark_poly::EvaluationDomain;
+use winter_crypto::{
+    hashers::{Blake3_256, Sha3_256},
+    Digest, DefaultRandomCoin, ElementHasher, MerkleTree, RandomCoin,
+};
+use winter_fri::{folding::fold_positions, FriOptions, FriProof, VerifierError};
+use winter_math::{fields::f64::BaseElement, StarkField};
+use winter_utils::{ByteWriter, Deserializable, DeserializationError, Serializable};
+
+use crate::fri::fold_coset_interpolation;
+use crate::DomainH;
+use ark_goldilocks::Goldilocks as F;
+
+/// Winterfell's `f64::BaseElement::TWO_ADIC_ROOT_OF_UNITY`, of order 2^32.
+const TWO_ADIC_ROOT: u64 = 7_277_203_076_849_721_926;
+const TWO_ADICITY: u32 = 32;
+
+/// Byte hash of the layer trees and the coin.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WinterHash {
+    /// `Sha3_256<BaseElement>`.
+    #[default]
+    Sha3,
+    /// `Blake3_256<BaseElement>`.
+    Blake3,
+}
+
+#[derive(Clone)]
+pub struct WinterfellFriParams {
+    pub options: FriOptions,
+    /// Positions drawn from the coin after the last commitment.
+    pub num_queries: usize,
+    pub hash: WinterHash,
+}
+
+/// A `FriProof` with what Winterfell's verifier reads from elsewhere in a
+/// STARK proof: the commitments, queried positions and the codeword's
+/// values there.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WinterfellFri {
+    /// Root of each layer, then the remainder's hash: what
+    /// `DefaultProverChannel::layer_commitments` returns.
+    pub layer_commitments: Vec<[u8; 32]>,
+    /// Drawn with nonce 0, duplicates kept.
+    pub positions: Vec<usize>,
+    /// The codeword at each of `positions`.
+    pub evaluations: Vec<F>,
+    pub proof: FriProof,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum WinterfellError {
+    /// Layer or remainder bytes that do not parse.
+    Malformed(DeserializationError),
+    /// `got` commitments for a domain that folds `expected` times, plus the
+    /// remainder.
+    LayerCount { expected: usize, got: usize },
+    /// Layers split over more than one partition, which Winterfell's FRI
+    /// prover never produces.
+    Partitions(usize),
+    /// Positions other than the ones the commitments draw.
+    Positions,
+    Verify(VerifierError),
+}
+
+impl core::fmt::Display for WinterfellError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            WinterfellError::Malformed(e) => write!(f, "malformed Winterfell FRI proof: {e}"),
+            WinterfellError::LayerCount { expected, got } => {
+                write!(f, "{got} Winterfell FRI commitments, expected {} with the remainder", expected + 1)
+            }
+            WinterfellError::Partitions(n) => write!(f, "Winterfell FRI proof over {n} partitions"),
+            WinterfellError::Positions => write!(f, "Winterfell FRI query positions not drawn from the commitments"),
+            WinterfellError::Verify(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for WinterfellError {}
+
+impl From<VerifierError> for WinterfellError {
+    fn from(e: VerifierError) -> Self {
+        WinterfellError::Verify(e)
+    }
+}
+
+impl From<DeserializationError> for WinterfellError {
+    fn from(e: DeserializationError) -> Self {
+        WinterfellError::Malformed(e)
+    }
+}
+
+/// Winterfell's generator of the subgroup of order `n`, a power of two.
+pub fn root_of_unity(n: usize) -> F {
+    assert!(n.is_power_of_two() && n.ilog2() <= TWO_ADICITY, "no subgroup of order {n}");
+    F::from(TWO_ADIC_ROOT).pow([1u64 << (TWO_ADICITY - n.ilog2())])
+}
+
+/// `FriOptions::domain_offset`: the field generator 7.
+pub fn domain_offset() -> F {
+    F::from(BaseElement::GENERATOR.as_int())
+}
+
+/// Points of Winterfell's evaluation domain of size `n`, `7·g^i` in order.
+pub fn lde_domain(n: usize) -> Vec<F> {
+    let g = root_of_unity(n);
+    core::iter::successors(Some(domain_offset()), |x| Some(*x * g)).take(n).collect()
+}
+
+/// FRI proof of `evaluations` over [`lde_domain`]. Panics where
+/// Winterfell's prover and channel do: a domain that is not a power of two
+/// of at least 8, options that leave no remainder coefficients, or no
+/// fewer positions than points.
+pub fn prove(evaluations: &[F], params: &WinterfellFriParams) -> WinterfellFri {
+    match params.hash {
+        WinterHash::Sha3 => prove_with::<Sha3_256<BaseElement>>(evaluations, params),
+        WinterHash::Blake3 => prove_with::<Blake3_256<BaseElement>>(evaluations, params),
+    }
+}
+
+/// [`prove`] for `f0` over `dom`, the input of
+/// [`deep_fri_prove_over`](crate::fri::deep_fri_prove_over): its
+/// interpolant is evaluated over Winterfell's domain of the same size.
+pub fn export(f0: &[F], dom: &DomainH, params: &WinterfellFriParams) -> WinterfellFri {
+    let coeffs = dom.domain.ifft(f0);
+    prove(&evaluate_on_coset(&coeffs, root_of_unity(f0.len()), domain_offset()), params)
+}
+
+/// Check `fri` as Winterfell's `FriVerifier` does for a codeword of degree
+/// at most `max_degree`, with the query positions redrawn from the
+/// commitments and the remainder checked against its commitment.
+pub fn verify(fri: &WinterfellFri, max_degree: usize, params: &WinterfellFriParams) -> Result<(), WinterfellError> {
+    match params.hash {
+        WinterHash::Sha3 => verify_with::<Sha3_256<BaseElement>>(fri, max_degree, params),
+        WinterHash::Blake3 => verify_with::<Blake3_256<BaseElement>>(fri, max_degree, params),
+    }
+}
+
+fn prove_with<H: ElementHasher<BaseField = BaseElement>>(evaluations: &[F], params: &WinterfellFriParams) -> WinterfellFri {
+    let n = evaluations.len();
+    assert!(n >= 8 && n.is_power_of_two(), "Winterfell FRI domain of {n} points");
+    let options = &params.options;
+    let m = options.folding_factor();
+    let mut coin = DefaultRandomCoin::<H>::new(&[]);
+    let mut layer_commitments = Vec::new();
+    let mut layers: Vec<(MerkleTree<H>, Vec<Vec<F>>)> = Vec::new();
+
+    let mut evals = evaluations.to_vec();
+    for _ in 0..options.num_fri_layers(n) {
+        let rows = coset_rows(&evals, m);
+        let leaves = rows.iter().map(|row| H::hash_elements(&to_winter(row))).collect();
+        let tree = MerkleTree::<H>::new(leaves).expect("FRI layers have at least two rows");
+        let root = *tree.root();
+        coin.reseed(root);
+        layer_commitments.push(root.as_bytes());
+        let alpha = from_winter(coin.draw::<BaseElement>().expect("the coin draws a field element"));
+        evals = fold_rows(&rows, alpha, root_of_unity(evals.len()));
+        layers.push((tree, rows));
+    }
+
+    // Coefficients of the last layer's interpolant, highest first.
+    let coeffs = interpolate_on_coset(&evals, root_of_unity(evals.len()), domain_offset());
+    let remainder: Vec<F> = coeffs[..evals.len() / options.blowup_factor()].iter().rev().copied().collect();
+    assert!(!remainder.is_empty(), "FRI options leave no remainder coefficients");
+    let remainder_commitment = H::hash_elements(&to_winter(&remainder));
+    coin.reseed(remainder_commitment);
+    layer_commitments.push(remainder_commitment.as_bytes());
+    let positions = coin
+        .draw_integers(params.num_queries, n, 0)
+        .expect("the coin draws query positions");
+
+    // `FriProof::write_into`: per layer the queried rows and a batch
+    // opening, then the remainder and log2 of the partition count.
+    let mut bytes = Vec::new();
+    bytes.write_u8(layers.len() as u8);
+    let (mut folded, mut size) = (positions.clone(), n);
+    for (tree, rows) in &layers {
+        folded = fold_positions(&folded, size, m);
+        let (_, opening) = tree.prove_batch(&folded).expect("folded positions are distinct rows");
+        let mut values = Vec::new();
+        for &p in &folded {
+            rows[p].iter().for_each(|x| values.write_u64(canonical(x)));
+        }
+        let paths = opening.to_bytes();
+        bytes.write_u32(values.len() as u32);
+        bytes.write_bytes(&values);
+        bytes.write_u32(paths.len() as u32);
+        bytes.write_bytes(&paths);
+        size /= m;
+    }
+    bytes.write_u16((remainder.len() * 8) as u16);
+    remainder.iter().for_each(|x| bytes.write_u64(canonical(x)));
+    bytes.write_u8(0);
+
+    WinterfellFri {
+        layer_commitments,
+        evaluations: positions.iter().map(|&p| evaluations[p]).collect(),
+        positions,
+        proof: FriProof::read_from_bytes(&bytes).expect("the layout is FriProof's"),
+    }
+}
+
+fn verify_with<H: ElementHasher<BaseField = BaseElement>>(
+    fri: &WinterfellFri,
+    max_degree: usize,
+    params: &WinterfellFriParams,
+) -> Result<(), WinterfellError> {
+    let options = &params.options;
+    let m = options.folding_factor();
+    if ![2, 4, 8, 16].contains(&m) {
+        return Err(VerifierError::UnsupportedFoldingFactor(m).into());
+    }
+    if fri.positions.len() != fri.evaluations.len() {
+        return Err(VerifierError::NumPositionEvaluationMismatch(fri.positions.len(), fri.evaluations.len()).into());
+    }
+    if fri.proof.num_partitions() != 1 {
+        return Err(WinterfellError::Partitions(fri.proof.num_partitions()));
+    }
+    let domain_size = max_degree.next_power_of_two() * options.blowup_factor();
+    let num_layers = options.num_fri_layers(domain_size);
+    if fri.layer_commitments.len() != num_layers + 1 || fri.proof.num_layers() != num_layers {
+        return Err(WinterfellError::LayerCount {
+            expected: num_layers,
+            got: fri.layer_commitments.len(),
+        });
+    }
+    let commitments: Vec<H::Digest> = fri
+        .layer_commitments
+        .iter()
+        .map(|c| H::Digest::read_from_bytes(c))
+        .collect::<Result<_, _>>()?;
+    let remainder: Vec<F> = fri.proof.parse_remainder::<BaseElement>()?.into_iter().map(from_winter).collect();
+    let (layer_values, layer_openings) =
+        fri.proof.clone().parse_layers::<BaseElement, H, MerkleTree<H>>(domain_size, m)?;
+
+    // FriVerifier::new: an alpha per commitment, the remainder's included.
+    let mut coin = DefaultRandomCoin::<H>::new(&[]);
+    let mut alphas = Vec::with_capacity(commitments.len());
+    let mut degree_bound = max_degree + 1;
+    for (depth, commitment) in commitments.iter().enumerate() {
+        coin.reseed(*commitment);
+        alphas.push(from_winter(coin.draw::<BaseElement>().map_err(VerifierError::RandomCoinError)?));
+        if depth != commitments.len() - 1 && !degree_bound.is_multiple_of(m) {
+            return Err(VerifierError::DegreeTruncation(degree_bound - 1, m, depth).into());
+        }
+        degree_bound /= m;
+    }
+    if fri.positions.len() >= domain_size
+        || coin.draw_integers(fri.positions.len(), domain_size, 0).ok().as_ref() != Some(&fri.positions)
+    {
+        return Err(WinterfellError::Positions);
+    }
+
+    let mut g = root_of_unity(domain_size);
+    let (mut size, mut degree_bound) = (domain_size, max_degree + 1);
+    let mut positions = fri.positions.clone();
+    let mut evaluations = fri.evaluations.clone();
+    for (depth, (values, opening)) in layer_values.into_iter().zip(&layer_openings).enumerate() {
+        let folded = fold_positions(&positions, size, m);
+        let rows: Vec<Vec<F>> = values.chunks(m).map(|row| row.iter().copied().map(from_winter).collect()).collect();
+        if rows.len() != folded.len() {
+            return Err(VerifierError::NumPositionEvaluationMismatch(folded.len(), rows.len()).into());
+        }
+        let leaves: Vec<H::Digest> = rows.iter().map(|row| H::hash_elements(&to_winter(row))).collect();
+        MerkleTree::<H>::verify_batch(&commitments[depth], &folded, &leaves, opening)
+            .map_err(|_| VerifierError::LayerCommitmentMismatch)?;
+
+        // Position p sits in row p mod size/m, column p div size/m.
+        let row_len = size / m;
+        let queried = positions.iter().map(|&p| {
+            let row = folded.iter().position(|&r| r == p % row_len).expect("folded from these positions");
+            rows[row][p / row_len]
+        });
+        if !queried.eq(evaluations.iter().copied()) {
+            return Err(VerifierError::InvalidLayerFolding(depth).into());
+        }
+        let zetas = coset_roots(g, size, m);
+        evaluations = folded
+            .iter()
+            .zip(&rows)
+            .map(|(&i, row)| fold_coset_interpolation(row, alphas[depth], row_point_inv(g, i), &zetas))
+            .collect();
+        if !degree_bound.is_multiple_of(m) {
+            return Err(VerifierError::DegreeTruncation(degree_bound - 1, m, depth).into());
+        }
+        g = g.pow([m as u64]);
+        degree_bound /= m;
+        size /= m;
+        positions = folded;
+    }
+
+    if H::hash_elements(&to_winter(&remainder)) != commitments[num_layers] {
+        return Err(VerifierError::RemainderCommitmentMismatch.into());
+    }
+    if remainder.len() > degree_bound {
+        return Err(VerifierError::RemainderDegreeMismatch(degree_bound - 1).into());
+    }
+    for (&p, &eval) in positions.iter().zip(&evaluations) {
+        let x = domain_offset() * g.pow([p as u64]);
+        if remainder.iter().fold(F::zero(), |acc, &c| acc * x + c) != eval {
+            return Err(VerifierError::InvalidRemainderFolding.into());
+        }
+    }
+    Ok(())
+}
+
+/// Row `i` of a layer of `evals`: the values at `i + j·rows`, j < m, which
+/// lie on the coset `7·g^i·⟨g^rows⟩`.
+fn coset_rows(evals: &[F], m: usize) -> Vec<Vec<F>> {
+    let rows = evals.len() / m;
+    (0..rows).map(|i| (0..m).map(|j| evals[i + j * rows]).collect()).collect()
+}
+
+/// `apply_drp`: each row's interpolant at `alpha`, for a layer over
+/// `7·⟨g⟩`.
+fn fold_rows(rows: &[Vec<F>], alpha: F, g: F) -> Vec<F> {
+    let m = rows[0].len();
+    let zetas = coset_roots(g, rows.len() * m, m);
+    rows.iter()
+        .enumerate()
+        .map(|(i, row)| fold_coset_interpolation(row, alpha, row_point_inv(g, i), &zetas))
+        .collect()
+}
+
+/// `g^(n/m·j)`, j < m, for `g` of order `n`: the coset each row is a
+/// shift of.
+fn coset_roots(g: F, n: usize, m: usize) -> Vec<F> {
+    let zeta = g.pow([(n / m) as u64]);
+    core::iter::successors(Some(F::one()), |z| Some(*z * zeta)).take(m).collect()
+}
+
+/// Inverse of row `i`'s base point `7·g^i`.
+fn row_point_inv(g: F, i: usize) -> F {
+    (domain_offset() * g.pow([i as u64])).inverse().expect("domain points are nonzero")
+}
+
+/// Evaluations of `coeffs` at `offset·g^i`, i < n, for `g` of order n,
+/// the length of `coeffs`: a radix-2 transform, since Winterfell's root is
+/// not the one `ark-poly`'s domains use.
+fn evaluate_on_coset(coeffs: &[F], g: F, offset: F) -> Vec<F> {
+    let mut shift = F::one();
+    let mut a: Vec<F> = coeffs
+        .iter()
+        .map(|&c| {
+            let x = c * shift;
+            shift *= offset;
+            x
+        })
+        .collect();
+    let n = a.len();
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            a.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let w = g.pow([(n / len) as u64]);
+        for chunk in a.chunks_mut(len) {
+            let (lo, hi) = chunk.split_at_mut(len / 2);
+            let mut x = F::one();
+            for (u, v) in lo.iter_mut().zip(hi) {
+                let t = *v * x;
+                (*u, *v) = (*u + t, *u - t);
+                x *= w;
+            }
+        }
+        len <<= 1;
+    }
+    a
+}
+
+/// Inverse of [`evaluate_on_coset`]: the coefficients of the interpolant
+/// of `evals` over `offset·⟨g⟩`.
+fn interpolate_on_coset(evals: &[F], g: F, offset: F) -> Vec<F> {
+    let n_inv = F::from(evals.len() as u64).inverse().expect("n ≠ 0");
+    let offset_inv = offset.inverse().expect("offset ≠ 0");
+    let mut shift = n_inv;
+    evaluate_on_coset(evals, g.inverse().expect("g ≠ 0"), F::one())
+        .into_iter()
+        .map(|c| {
+            let x = c * shift;
+            shift *= offset_inv;
+            x
+        })
+        .collect()
+}
+
+fn canonical(x: &F) -> u64 {
+    x.into_bigint().0[0]
+}
+
+fn to_winter(xs: &[F]) -> Vec<BaseElement> {
+    xs.iter().map(|x| BaseElement::new(canonical(x))).collect()
+}
+
+fn from_winter(x: BaseElement) -> F {
+    F::from(x.as_int())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winter_fri::{DefaultProverChannel, DefaultVerifierChannel, FriProver, FriVerifier};
+    use winter_math::{fft, FieldElement};
+
+    fn params(blowup: usize, folding: usize, remainder: usize, hash: WinterHash) -> WinterfellFriParams {
+        WinterfellFriParams {
+            options: FriOptions::new(blowup, folding, remainder),
+            num_queries: 20,
+            hash,
+        }
+    }
+
+    /// A polynomial of degree below `degree` evaluated over Winterfell's
+    /// domain by Winterfell's own FFT.
+    fn winter_codeword(degree: usize, n: usize) -> Vec<BaseElement> {
+        let mut p: Vec<BaseElement> = (0..degree as u64).map(|i| BaseElement::new(i * i + 3)).collect();
+        p.resize(n, BaseElement::ZERO);
+        let twiddles = fft::get_twiddles::<BaseElement>(n);
+        fft::evaluate_poly_with_offset(&p, &twiddles, BaseElement::GENERATOR, 1)
+    }
+
+    /// Winterfell's prover on `evals`, with its positions and commitments.
+    fn winter_prove<H: ElementHasher<BaseField = BaseElement>>(
+        evals: &[BaseElement],
+        params: &WinterfellFriParams,
+    ) -> WinterfellFri {
+        let mut channel =
+            DefaultProverChannel::<BaseElement, H, DefaultRandomCoin<H>>::new(evals.len(), params.num_queries);
+        let mut prover = FriProver::<_, _, _, MerkleTree<H>>::new(params.options.clone());
+        prover.build_layers(&mut channel, evals.to_vec());
+        let positions = channel.draw_query_positions(0);
+        WinterfellFri {
+            layer_commitments: channel.layer_commitments().iter().map(|c| c.as_bytes()).collect(),
+            evaluations: positions.iter().map(|&p| from_winter(evals[p])).collect(),
+            proof: prover.build_proof(&positions),
+            positions,
+        }
+    }
+
+    /// Winterfell's verifier on `fri`.
+    fn winter_verify<H: ElementHasher<BaseField = BaseElement>>(
+        fri: &WinterfellFri,
+        max_degree: usize,
+        params: &WinterfellFriParams,
+    ) -> Result<(), VerifierError> {
+        let domain_size = max_degree.next_power_of_two() * params.options.blowup_factor();
+        let commitments = fri.layer_commitments.iter().map(|c| H::Digest::read_from_bytes(c).unwrap()).collect();
+        let mut channel = DefaultVerifierChannel::<BaseElement, H, MerkleTree<H>>::new(
+            fri.proof.clone(),
+            commitments,
+            domain_size,
+            params.options.folding_factor(),
+        )
+        .unwrap();
+        let mut coin = DefaultRandomCoin::<H>::new(&[]);
+        let verifier = FriVerifier::new(&mut channel, &mut coin, params.options.clone(), max_degree)?;
+        verifier.verify(&mut channel, &to_winter(&fri.evaluations), &fri.positions)
+    }
+
+    fn cross_check<H: ElementHasher<BaseField = BaseElement>>(hash: WinterHash) {
+        for (folding, remainder) in [(2, 7), (4, 15), (8, 7), (16, 3)] {
+            let p = params(8, folding, remainder, hash);
+            let (degree, n) = (256, 2048);
+            let evals = winter_codeword(degree, n);
+            let ours = prove(&evals.iter().map(|&x| from_winter(x)).collect::<Vec<_>>(), &p);
+
+            // Byte for byte what Winterfell's prover sends.
+            let theirs = winter_prove::<H>(&evals, &p);
+            assert_eq!(ours, theirs, "folding {folding}");
+            assert_eq!(ours.proof.to_bytes(), theirs.proof.to_bytes());
+
+            assert_eq!(winter_verify::<H>(&ours, degree - 1, &p), Ok(()));
+            assert_eq!(verify(&theirs, degree - 1, &p), Ok(()));
+            // A lower degree bound implies a smaller domain.
+            assert!(verify(&ours, degree / 2 - 1, &p).is_err());
+        }
+    }
+
+    #[test]
+    fn sha3_proofs_match_winterfell() {
+        cross_check::<Sha3_256<BaseElement>>(WinterHash::Sha3);
+    }
+
+    #[test]
+    fn blake3_proofs_match_winterfell() {
+        cross_check::<Blake3_256<BaseElement>>(WinterHash::Blake3);
+    }
+
+    #[test]
+    fn tampered_proofs_are_rejected() {
+        let p = params(4, 4, 7, WinterHash::Blake3);
+        let evals: Vec<F> = winter_codeword(64, 256).into_iter().map(from_winter).collect();
+        let fri = prove(&evals, &p);
+        assert_eq!(verify(&fri, 63, &p), Ok(()));
+
+        let mut bad = fri.clone();
+        bad.evaluations[3] += F::one();
+        assert_eq!(verify(&bad, 63, &p), Err(WinterfellError::Verify(VerifierError::InvalidLayerFolding(0))));
+        assert!(winter_verify::<Blake3_256<BaseElement>>(&bad, 63, &p).is_err());
+
+        let mut bad = fri.clone();
+        bad.positions[0] ^= 1;
+        bad.evaluations[0] = evals[bad.positions[0]];
+        assert_eq!(verify(&bad, 63, &p), Err(WinterfellError::Positions));
+
+        let mut bad = fri.clone();
+        bad.layer_commitments[0][0] ^= 1;
+        assert!(verify(&bad, 63, &p).is_err());
+
+        let mut bad = fri.clone();
+        bad.layer_commitments.pop();
+        assert_eq!(verify(&bad, 63, &p), Err(WinterfellError::LayerCount { expected: 2, got: 2 }));
+
+        // A different hash recommits every layer.
+        assert!(verify(&fri, 63, &params(4, 4, 7, WinterHash::Sha3)).is_err());
+    }
+
+    #[test]
+    fn deep_fri_inputs_export_to_winterfell() {
+        let n = 512;
+        let dom = DomainH::new_radix2(n);
+        let coeffs: Vec<F> = (0..n / 8).map(|i| F::from(i as u64 * 7 + 1)).collect();
+        let f0 = dom.domain.fft(&coeffs);
+        let p = params(8, 4, 3, WinterHash::Sha3);
+        let fri = export(&f0, &dom, &p);
+        assert_eq!(fri.evaluations[0], evaluate_on_coset(&coeffs_padded(&coeffs, n), root_of_unity(n), domain_offset())[fri.positions[0]]);
+        assert_eq!(verify(&fri, n / 8 - 1, &p), Ok(()));
+        assert_eq!(winter_verify::<Sha3_256<BaseElement>>(&fri, n / 8 - 1, &p), Ok(()));
+    }
+
+    fn coeffs_padded(coeffs: &[F], n: usize) -> Vec<F> {
+        let mut c = coeffs.to_vec();
+        c.resize(n, F::zero());
+        c
+    }
+
+    #[test]
+    fn coset_transforms_invert() {
+        let g = root_of_unity(16);
+        let coeffs: Vec<F> = (0..16u64).map(F::from).collect();
+        let evals = evaluate_on_coset(&coeffs, g, domain_offset());
+        let x = lde_domain(16)[5];
+        assert_eq!(evals[5], coeffs.iter().rev().fold(F::zero(), |acc, &c| acc * x + c));
+        assert_eq!(interpolate_on_coset(&evals, g, domain_offset()), coeffs);
+    }
+}
@@ -0,0 +1,420 @@
+//! Known-answer vectors for the hashing and proving stack.
+//!
+//! Each vector is a fixed input and the output this tree produces for it:
+//! the transcript's Poseidon permutation and constants digest, Merkle roots
+//! and full FRI proofs under every [`HashProfile`], and challenges from
+//! every transcript backend. Any change to constant derivation (the seeded
+//! Poseidon generator), leaf or node encodings, domain separators or
+//! challenge derivation shows up as a mismatch in [`verify_kats`]; such a
+//! change breaks existing proofs and has to come with new vectors.
+
+use alloc::vec::Vec;
+use ark_ff::PrimeField;
+use ark_goldilocks::Goldilocks as F;
+
+use merkle::{Digest256, DigestWidth, MerkleChannelCfg, MerkleNode, MerkleOpening, MerkleTreeChannel};
+use poseidon::{permute, T};
+use transcript::{default_params as transcript_params, FsConfig, FsHash, Transcript};
+
+use crate::fri::{prove_hooked, DeepFriProof, FoldCheck, FoldingMode, FriDomain};
+use crate::params::{poseidon_params_digest, HashProfile, PublicParams, SECURITY_LOG_INV_RATE};
+
+const PROOF_DIGEST_DS: &[u8] = b"KAT/proof-digest/v1";
+
+/// A known-answer vector whose output no longer matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KatMismatch {
+    pub name: &'static str,
+}
+
+impl core::fmt::Display for KatMismatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "known-answer vector `{}` does not match", self.name)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KatMismatch {}
+
+// ---------------- Expected outputs ----------------
+
+/// `permute` of the state `[0, 1, .., 16]` under the transcript parameters.
+const POSEIDON_PERMUTATION: [u64; T] = [
+    0x805a3a84d86e87f0,
+    0x4fc1db20fcec1467,
+    0xc7a022c0f257d18b,
+    0x22bdd3aa3675d04e,
+    0x4eb410d5b6fd93b1,
+    0xb92f3603e5b11521,
+    0x9d1de6fe176d15ca,
+    0x9deed0c321b2a84e,
+    0x13a9b211e4585286,
+    0xe622719263d0f5e2,
+    0x0522fcb47ad7d05f,
+    0x8431a29054ccc1eb,
+    0xd81d4e4998b99ec2,
+    0x00e9ad012f6f6bb7,
+    0xffb12029c13426cd,
+    0x9cd0711090c82e4a,
+    0x4c2233b9cc5bad19,
+];
+
+/// [`poseidon_params_digest`] of the transcript parameters.
+const POSEIDON_PARAMS_DIGEST: &str =
+    "443d3b17e4cd6193e7f19154cca72e8764fbfde61bba82739995e767e1b9d5e2";
+
+/// Node encoding of [`merkle_root`] per profile, in [`PROFILES`] order.
+const MERKLE_ROOTS: [&str; 3] = [
+    "843c248d4e528d0e",
+    "b77a928891892ec6",
+    "41781d97c37d78f0bb08ada66b8019419f2487ed1424fa8f97a8a44d5088efea",
+];
+
+/// Two challenges of [`transcript_challenges`] per backend, in
+/// [`BACKENDS`] order.
+const TRANSCRIPT_CHALLENGES: [[u64; 2]; 4] = [
+    [0x4d4ce38e1b14c351, 0xed66aa591fdd4610],
+    [0xc915dd228697e021, 0x0bdd5753015a0bbe],
+    [0xfb39c3cc09fbad0e, 0x10bf0f77548fd010],
+    [0x03d4882ed923d991, 0xaaa15a2e0497c16f],
+];
+
+/// [`PublicParams::digest`] and [`proof_digest`] of [`prove`] per profile,
+/// in [`PROFILES`] order.
+const PROOF_VECTORS: [(&str, &str); 3] = [
+    (
+        "07dcdbab69fdee89dba94d5b83eaf8cbc178c4505262073dd64a53c163536f02",
+        "2e3ca04c9004fa52b45bbd729928d4ae676622d86f8d12eb41832357ce3fb7de",
+    ),
+    (
+        "fba449dae88213942367ad92a3ddab8ac3f1e429dbf9db9c68b171f3b6d9ece8",
+        "614529438fc750b6df71ca59178ebe43fbaa60c46d084d455b791656df232b1b",
+    ),
+    (
+        "f374d50660b0f665ae516761cbfb65b1b6512f757fd6acad86a8ef037a5de6dc",
+        "9aac5e05109b627080fddbd0baa0614210d85ac3fad271de57b3d09f40d3c3e3",
+    ),
+];
+
+// ---------------- Fixed inputs ----------------
+
+const PROFILES: [(HashProfile, &str); 3] = [
+    (HashProfile::Poseidon, "proof/poseidon"),
+    (HashProfile::Sha3, "proof/sha3"),
+    (HashProfile::Conservative, "proof/conservative"),
+];
+
+const BACKENDS: [(FsHash, &str); 4] = [
+    (FsHash::Poseidon, "transcript/poseidon"),
+    (FsHash::Sha3_256, "transcript/sha3-256"),
+    (FsHash::Blake3, "transcript/blake3"),
+    (FsHash::RescuePrime, "transcript/rescue-prime"),
+];
+
+/// Layer-0 domain of the proof vectors.
+const PROOF_LOG_N: u32 = 8;
+const PROOF_QUERIES: usize = 8;
+const PROOF_GRINDING_BITS: u32 = 4;
+const PROOF_SEED_Z: u64 = 0x4b41_5400;
+
+const MERKLE_LEAVES: usize = 64;
+
+fn poseidon_permutation() -> [F; T] {
+    let mut state: [F; T] = core::array::from_fn(|i| F::from(i as u64));
+    permute(&mut state, &transcript_params());
+    state
+}
+
+/// Tree of profile width and node over the leaves `[i², i + 1]` for
+/// `i < 64`, as the root's node encoding.
+fn merkle_root(profile: HashProfile) -> Vec<u8> {
+    fn root<N: MerkleNode>(cfg: MerkleChannelCfg) -> Vec<u8> {
+        let mut tree = MerkleTreeChannel::<N>::new(cfg, [0u8; 32]);
+        for i in 0..MERKLE_LEAVES as u64 {
+            tree.push_leaf(&[F::from(i * i), F::from(i + 1)]);
+        }
+        tree.finalize().unwrap().to_bytes().as_ref().to_vec()
+    }
+
+    let cfg = MerkleChannelCfg::for_leaves(MERKLE_LEAVES, profile.max_arity(), 0)
+        .with_digest(profile.digest_width());
+    match profile.digest_width() {
+        DigestWidth::Digest64 => root::<F>(cfg),
+        DigestWidth::Digest256 => root::<Digest256>(cfg),
+    }
+}
+
+fn transcript_challenges(hash: FsHash) -> [F; 2] {
+    let mut tr = Transcript::from_config(&FsConfig::new(hash), b"KAT/transcript");
+    tr.absorb_bytes(b"stark-has");
+    tr.absorb_field(F::from(42u64));
+    let c0 = tr.challenge(b"KAT/c0");
+    tr.absorb_field(c0);
+    [c0, tr.challenge(b"KAT/c1")]
+}
+
+/// Parameters folding by the profile's widest arity down to rate
+/// `2^-SECURITY_LOG_INV_RATE`, as [`PublicParams::for_security`] does, with a
+/// fixed query count and grinding.
+fn proof_params(profile: HashProfile) -> PublicParams {
+    let step = profile.max_arity().trailing_zeros();
+    let mut schedule = Vec::new();
+    let mut left = PROOF_LOG_N - SECURITY_LOG_INV_RATE;
+    while left > 0 {
+        let bits = left.min(step);
+        schedule.push(1usize << bits);
+        left -= bits;
+    }
+    PublicParams {
+        grinding_bits: PROOF_GRINDING_BITS,
+        ..PublicParams::new(
+            1 << PROOF_LOG_N,
+            &schedule,
+            PROOF_QUERIES,
+            FoldingMode::CosetInterpolation,
+            FoldCheck::RecomputeFromCoset,
+        )
+    }
+    .with_digest(profile.digest_width())
+}
+
+/// Proof for the codeword of `Σ_{i < n/8} (i + 1)·X^i` over `H_n`,
+/// evaluated directly so the vector does not depend on the FFT.
+fn prove<N: MerkleNode>(params: &PublicParams) -> DeepFriProof<N> {
+    let n = params.n0;
+    let domain = FriDomain::new_radix2(n);
+    let degree = n >> params.log_inv_rate;
+    let mut x = F::from(1u64);
+    let mut f0 = Vec::with_capacity(n);
+    for _ in 0..n {
+        f0.push(
+            (0..degree)
+                .rev()
+                .fold(F::from(0u64), |acc, i| acc * x + F::from(i as u64 + 1)),
+        );
+        x *= domain.omega;
+    }
+    prove_hooked(f0, domain, &params.fri_params(PROOF_SEED_Z), None, &mut ())
+        .unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// [`proof_digest`] of the profile's proof.
+fn profile_proof_digest(profile: HashProfile, params: &PublicParams) -> [u8; 32] {
+    match profile.digest_width() {
+        DigestWidth::Digest64 => proof_digest(&prove::<F>(params)),
+        DigestWidth::Digest256 => proof_digest(&prove::<Digest256>(params)),
+    }
+}
+
+/// Digest of every field of a proof, in declaration order. Merkle nodes
+/// go in as their encoding's 64-bit words, so `F` nodes are one word each.
+pub fn proof_digest<N: MerkleNode>(proof: &DeepFriProof<N>) -> [u8; 32] {
+    let mut h = blake3::Hasher::new();
+    h.update(PROOF_DIGEST_DS);
+    let mut put = |x: u64| {
+        h.update(&x.to_le_bytes());
+    };
+    let fe = |x: &F| x.into_bigint().0[0];
+
+    fn put_node<N: MerkleNode>(put: &mut impl FnMut(u64), x: &N) {
+        for chunk in x.to_bytes().as_ref().chunks(8) {
+            put(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+    }
+
+    fn put_opening<N: MerkleNode>(put: &mut impl FnMut(u64), o: &MerkleOpening<N>) {
+        put_node(put, &o.leaf);
+        put(o.index);
+        put(o.path.len() as u64);
+        for level in &o.path {
+            put(level.len() as u64);
+            for x in level {
+                put_node(put, x);
+            }
+        }
+    }
+
+    for chunk in proof.proof_root.chunks(8) {
+        put(u64::from_le_bytes(chunk.try_into().unwrap()));
+    }
+    put(proof.pow_nonce);
+    match &proof.trace_root {
+        Some(r) => {
+            put(1);
+            put_node(&mut put, r);
+        }
+        None => put(0),
+    }
+    put(proof.roots.len() as u64);
+    for r in &proof.roots {
+        put_node(&mut put, r);
+    }
+    put(proof.layer_proofs.layers.len() as u64);
+    for layer in &proof.layer_proofs.layers {
+        put(layer.openings.len() as u64);
+        for o in &layer.openings {
+            put_opening(&mut put, o);
+        }
+    }
+    put(proof.queries.len() as u64);
+    for q in &proof.queries {
+        put(q.per_layer_refs.len() as u64);
+        for r in &q.per_layer_refs {
+            for v in [r.i, r.child_pos as u64, r.parent_index, r.parent_pos as u64] {
+                put(v);
+            }
+        }
+        put(q.per_layer_payloads.len() as u64);
+        for p in &q.per_layer_payloads {
+            for v in [p.f_i, p.f_0, p.s_i, p.q_a0, p.q_a1, p.q_a2, p.f_parent_b, p.s_parent_b] {
+                put(fe(&v));
+            }
+        }
+        put(q.final_index);
+        put(fe(&q.final_pair.0));
+        put(fe(&q.final_pair.1));
+        put(q.per_layer_cosets.len() as u64);
+        for coset in &q.per_layer_cosets {
+            put(coset.len() as u64);
+            for leaf in coset {
+                for v in [leaf.f, leaf.s, leaf.q_a0, leaf.q_a1, leaf.q_a2] {
+                    put(fe(&v));
+                }
+                put_opening(&mut put, &leaf.opening);
+            }
+        }
+        put(q.per_layer_parents.len() as u64);
+        for p in &q.per_layer_parents {
+            put(p.values.len() as u64);
+            for v in &p.values {
+                put(fe(v));
+            }
+            put_opening(&mut put, &p.opening);
+        }
+    }
+    put(proof.n0 as u64);
+    put(fe(&proof.omega0));
+    *h.finalize().as_bytes()
+}
+
+// ---------------- Checks ----------------
+
+fn check(ok: bool, name: &'static str) -> Result<(), KatMismatch> {
+    if ok {
+        Ok(())
+    } else {
+        Err(KatMismatch { name })
+    }
+}
+
+fn digest_is(digest: &[u8; 32], expected: &str) -> bool {
+    bytes_are(digest, expected)
+}
+
+fn bytes_are(bytes: &[u8], expected: &str) -> bool {
+    let mut want = alloc::vec![0u8; bytes.len()];
+    hex::decode_to_slice(expected, &mut want).is_ok() && want == bytes
+}
+
+fn words_are(values: &[F], expected: &[u64]) -> bool {
+    values.len() == expected.len()
+        && values
+            .iter()
+            .zip(expected)
+            .all(|(v, &e)| v.into_bigint().0[0] == e)
+}
+
+/// Poseidon permutation and constants digest.
+pub fn verify_poseidon_kats() -> Result<(), KatMismatch> {
+    check(
+        words_are(&poseidon_permutation(), &POSEIDON_PERMUTATION),
+        "poseidon/permutation",
+    )?;
+    check(
+        digest_is(&poseidon_params_digest(&transcript_params()), POSEIDON_PARAMS_DIGEST),
+        "poseidon/params-digest",
+    )
+}
+
+/// Merkle roots under each profile's arity and node width.
+pub fn verify_merkle_kats() -> Result<(), KatMismatch> {
+    for ((profile, _), root) in PROFILES.iter().zip(MERKLE_ROOTS) {
+        let name = match profile {
+            HashProfile::Poseidon => "merkle/poseidon",
+            HashProfile::Sha3 => "merkle/sha3",
+            HashProfile::Conservative => "merkle/conservative",
+        };
+        check(bytes_are(&merkle_root(*profile), root), name)?;
+    }
+    Ok(())
+}
+
+/// Challenges from every transcript backend.
+pub fn verify_transcript_kats() -> Result<(), KatMismatch> {
+    for ((hash, name), expected) in BACKENDS.iter().zip(TRANSCRIPT_CHALLENGES) {
+        check(words_are(&transcript_challenges(*hash), &expected), name)?;
+    }
+    Ok(())
+}
+
+/// Public-parameter digests and full FRI proofs per profile.
+pub fn verify_proof_kats() -> Result<(), KatMismatch> {
+    for ((profile, name), (params_digest, digest)) in PROFILES.iter().zip(PROOF_VECTORS) {
+        let params = proof_params(*profile);
+        check(digest_is(&params.digest(), params_digest), name)?;
+        check(digest_is(&profile_proof_digest(*profile, &params), digest), name)?;
+    }
+    Ok(())
+}
+
+/// Run every known-answer vector, stopping at the first mismatch.
+pub fn verify_kats() -> Result<(), KatMismatch> {
+    verify_poseidon_kats()?;
+    verify_merkle_kats()?;
+    verify_transcript_kats()?;
+    verify_proof_kats()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fri::{deep_fri_verify, VerifierKey};
+
+    #[test]
+    fn hashing_kats_match() {
+        assert_eq!(verify_poseidon_kats(), Ok(()));
+        assert_eq!(verify_merkle_kats(), Ok(()));
+        assert_eq!(verify_transcript_kats(), Ok(()));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn proof_kats_match_and_verify() {
+        assert_eq!(verify_kats(), Ok(()));
+        for (profile, _) in PROFILES {
+            let params = proof_params(profile);
+            let key = VerifierKey::new(&params, PROOF_SEED_Z).unwrap();
+            let ok = match profile.digest_width() {
+                DigestWidth::Digest64 => deep_fri_verify(&key, &prove::<F>(&params)),
+                DigestWidth::Digest256 => deep_fri_verify(&key, &prove::<Digest256>(&params)),
+            };
+            assert!(ok, "{profile:?}");
+        }
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn proof_digest_covers_openings() {
+        let params = proof_params(HashProfile::Sha3);
+        let proof = prove::<F>(&params);
+        let digest = proof_digest(&proof);
+
+        let mut bad = prove::<F>(&params);
+        bad.queries[0].per_layer_cosets[0][0].opening.path[0][0] += F::from(1u64);
+        assert_ne!(proof_digest(&bad), digest);
+
+        let mut bad = prove::<F>(&params);
+        bad.pow_nonce ^= 1;
+        assert_ne!(proof_digest(&bad), digest);
+    }
+}
@@ -3,6 +3,11 @@
 #![allow(non_snake_case)]
 #![allow(unused_variables)]
 #![allow(unused_macros)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
 use ark_ff::{Field, One, Zero};
 use ark_goldilocks::Goldilocks as F;
 
@@ -14,24 +19,14 @@ use ark_poly::{
 };
 use ark_poly::polynomial::univariate::DensePolynomial;
 
-#[cfg(feature = "parallel")]
-use rayon::prelude::*;
+use transcript::Transcript;
 
 use crate::deep_tower::Fp3;
+use crate::parallel::{map_indices, ParallelOp};
 
-const PARALLEL_MIN_ELEMS: usize = 1 << 12;
-
-#[inline]
-fn enable_parallel(len: usize) -> bool {
-    #[cfg(feature = "parallel")]
-    {
-        len >= PARALLEL_MIN_ELEMS && rayon::current_num_threads() > 1
-    }
-    #[cfg(not(feature = "parallel"))]
-    {
-        let _ = len;
-        false
-    }
+mod ds {
+    pub const DEEP_ALI_Z: &[u8] = b"DEEP-ALI/z";
+    pub const DEEP_ALI_BETA: &[u8] = b"DEEP-ALI/beta";
 }
 
 fn build_omega_pows(omega: F, n: usize) -> Vec<F> {
@@ -78,6 +73,10 @@ pub fn deep_ali_merge_evals(
     )
 }
 
+/// One-off merge over `H = <omega>`, building its [`DomainH`] for this
+/// call. Provers that merge over `H` more than once, or go on to FRI over
+/// it, build the `DomainH` once and use
+/// [`DomainH::merge_deep_ali_blinded`] and [`fri::deep_fri_prove_over`].
 pub fn deep_ali_merge_evals_blinded(
     a_eval: &[F],
     s_eval: &[F],
@@ -92,6 +91,23 @@ pub fn deep_ali_merge_evals_blinded(
     assert!(n > 1);
     assert!(n.is_power_of_two(), "Domain size must be power of two");
 
+    DomainH::with_generator(n, omega)
+        .merge_deep_ali_blinded(a_eval, s_eval, e_eval, t_eval, r_eval_opt, beta, z_fp3)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn merge_with_domain(
+    dom: &DomainH,
+    a_eval: &[F],
+    s_eval: &[F],
+    e_eval: &[F],
+    t_eval: &[F],
+    r_eval_opt: Option<&[F]>,
+    beta: F,
+    z_fp3: Fp3,
+) -> (Vec<F>, Fp3, F) {
+    let n = dom.n;
+    assert_eq!(a_eval.len(), n);
     assert_eq!(s_eval.len(), n);
     assert_eq!(e_eval.len(), n);
     assert_eq!(t_eval.len(), n);
@@ -99,19 +115,17 @@ pub fn deep_ali_merge_evals_blinded(
         assert_eq!(r_eval.len(), n);
     }
 
-    // ω^j
-    let omega_pows = build_omega_pows(omega, n);
-
     // Φ̃(ω^j)
-    let mut phi_eval = vec![F::zero(); n];
-    for i in 0..n {
+    let phi_at = |i: usize| {
         let base = a_eval[i] * s_eval[i] + e_eval[i] - t_eval[i];
-        phi_eval[i] = if let Some(r) = r_eval_opt {
+        if let Some(r) = r_eval_opt {
             base + beta * r[i]
         } else {
             base
-        };
-    }
+        }
+    };
+
+    let phi_eval = map_indices(ParallelOp::Merge, n, phi_at);
 
     // -----------------------------------------------------------------------
     // ✅ Φ̃(z) / Z_H(z)  — computed in Fp³
@@ -120,8 +134,7 @@ pub fn deep_ali_merge_evals_blinded(
     let n_inv = F::from(n as u64).inverse().unwrap();
 
     // Lift into Fp³
-    let omega_fp3: Vec<Fp3> =
-        omega_pows.iter().map(|&w| Fp3::from_base(w)).collect();
+    let omega_fp3 = &dom.omega_pows_fp3;
     let phi_fp3: Vec<Fp3> =
         phi_eval.iter().map(|&v| Fp3::from_base(v)).collect();
 
@@ -140,23 +153,19 @@ pub fn deep_ali_merge_evals_blinded(
     // ✅ f₀(ω^j) = Φ̃(ω^j) / (ω^j − z)
     // -----------------------------------------------------------------------
 
-    let mut f0_eval = Vec::with_capacity(n);
-    for j in 0..n {
+    let f0_at = |j: usize| {
         let denom = omega_fp3[j] - z_fp3;
-        let val = (phi_fp3[j] * denom.inv()).a0;
-        f0_eval.push(val);
-    }
+        (phi_fp3[j] * denom.inv()).a0
+    };
+
+    let f0_eval = map_indices(ParallelOp::Merge, n, f0_at);
 
     // -----------------------------------------------------------------------
     // ✅ Enforce ρ₀ = 1/32  (degree bound)
     // -----------------------------------------------------------------------
 
-    let domain =
-        GeneralEvaluationDomain::<F>::new(n)
-            .expect("power-of-two domain");
-
     // Interpolate evaluations → coefficients
-    let mut coeffs = domain.ifft(&f0_eval);
+    let mut coeffs = dom.domain.ifft(&f0_eval);
 
     // Target degree bound
     let d0 = n / 32;
@@ -171,12 +180,223 @@ pub fn deep_ali_merge_evals_blinded(
     let poly = DensePolynomial::from_coefficients_vec(coeffs);
 
     // Re-evaluate over domain
-    let f0_low_rate = domain.fft(poly.coeffs());
+    let f0_low_rate = dom.domain.fft(poly.coeffs());
 
     debug_assert!(poly.degree() < d0);
 
     (f0_low_rate, z_fp3, c_star)
 }
 
+// ---------------------------------------------------------------------------
+// Cached domain helper
+// ---------------------------------------------------------------------------
+
+/// `H = <ω>` with its powers, their Fp³ lifts and the arkworks domain, built
+/// once and shared by every merge over `H` and by the first FRI layer.
+#[derive(Clone)]
+pub struct DomainH {
+    pub n: usize,
+    pub omega: F,
+    pub omega_pows: Vec<F>,
+    /// `omega_pows` lifted into Fp³ for the barycentric sum.
+    pub omega_pows_fp3: Vec<Fp3>,
+    pub domain: GeneralEvaluationDomain<F>,
+}
+
+impl DomainH {
+    pub fn new_radix2(n: usize) -> Self {
+        Self::with_generator(n, fri::radix2_generator(n))
+    }
+
+    fn with_generator(n: usize, omega: F) -> Self {
+        let omega_pows = build_omega_pows(omega, n);
+        let omega_pows_fp3 = omega_pows.iter().map(|&w| Fp3::from_base(w)).collect();
+        Self {
+            n,
+            omega,
+            omega_pows,
+            omega_pows_fp3,
+            domain: GeneralEvaluationDomain::<F>::new(n).expect("power-of-two domain"),
+        }
+    }
+
+    /// The same `H` as a FRI layer-0 domain.
+    pub fn fri_domain(&self) -> fri::FriDomain {
+        fri::FriDomain {
+            omega: self.omega,
+            size: self.n,
+        }
+    }
+
+    pub fn merge_deep_ali(
+        &self,
+        a_eval: &[F],
+        s_eval: &[F],
+        e_eval: &[F],
+        t_eval: &[F],
+        z_fp3: Fp3,
+    ) -> (Vec<F>, Fp3, F) {
+        self.merge_deep_ali_blinded(a_eval, s_eval, e_eval, t_eval, None, F::zero(), z_fp3)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn merge_deep_ali_blinded(
+        &self,
+        a_eval: &[F],
+        s_eval: &[F],
+        e_eval: &[F],
+        t_eval: &[F],
+        r_eval_opt: Option<&[F]>,
+        beta: F,
+        z_fp3: Fp3,
+    ) -> (Vec<F>, Fp3, F) {
+        merge_with_domain(self, a_eval, s_eval, e_eval, t_eval, r_eval_opt, beta, z_fp3)
+    }
+}
+
 pub mod fri;
-pub mod deep_tower;
\ No newline at end of file
+pub mod deep_tower;
+pub mod parallel;
+pub mod air;
+pub mod lwe;
+pub mod params;
+pub mod batch;
+pub mod compact;
+pub mod committed;
+pub mod kat;
+pub mod interop;
+#[cfg(feature = "mem_report")]
+pub mod memory;
+#[cfg(feature = "std")]
+pub mod sizing;
+// ---------------------------------------------------------------------------
+// Transcript sampling
+// ---------------------------------------------------------------------------
+
+/// DEEP point `z ∈ Fp³` and blinding coefficient `β` for a merge over
+/// `H` of size `n`, drawn from the shared transcript.
+///
+/// The merge inverts `z − ω^j` coordinate-wise, so each coordinate of `z`
+/// is redrawn until it lies outside `H`.
+pub fn sample_z_beta(tr: &mut Transcript, n: usize) -> (Fp3, F) {
+    let mut coord = || loop {
+        let c = tr.challenge(ds::DEEP_ALI_Z);
+        if !is_in_domain(c, n) {
+            break c;
+        }
+    };
+    let z = Fp3 {
+        a0: coord(),
+        a1: coord(),
+        a2: coord(),
+    };
+    (z, tr.challenge(ds::DEEP_ALI_BETA))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::UniformRand;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn domain_h_merge_matches_free_function() {
+        let n = 64;
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut col = || -> Vec<F> { (0..n).map(|_| F::rand(&mut rng)).collect() };
+        let (a, s, e, t, r) = (col(), col(), col(), col(), col());
+        let beta = F::from(9u64);
+        let z = Fp3 {
+            a0: F::from(3u64),
+            a1: F::from(5u64),
+            a2: F::from(7u64),
+        };
+
+        let dom = DomainH::new_radix2(n);
+        assert_eq!(dom.fri_domain().omega, fri::FriDomain::new_radix2(n).omega);
+        assert_eq!(
+            dom.merge_deep_ali_blinded(&a, &s, &e, &t, Some(&r), beta, z),
+            deep_ali_merge_evals_blinded(&a, &s, &e, &t, Some(&r), beta, dom.omega, z)
+        );
+        assert_eq!(
+            dom.merge_deep_ali(&a, &s, &e, &t, z),
+            deep_ali_merge_evals(&a, &s, &e, &t, dom.omega, z)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn fri_over_domain_h_matches_fri_over_its_domain() {
+        use fri::{deep_fri_prove_over, deep_fri_prove_with_trace_root, DeepFriParams, FoldCheck, FoldingMode};
+
+        let n = 1 << 8;
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut col = || -> Vec<F> { (0..n).map(|_| F::rand(&mut rng)).collect() };
+        let (a, s, e, t) = (col(), col(), col(), col());
+        let z = Fp3 {
+            a0: F::from(3u64),
+            a1: F::from(5u64),
+            a2: F::from(7u64),
+        };
+
+        // One DomainH for the merge and the first FRI layer.
+        let dom = DomainH::new_radix2(n);
+        let (f0, _, _) = dom.merge_deep_ali(&a, &s, &e, &t, z);
+        let params = DeepFriParams {
+            schedule: vec![4, 4, 4, 4],
+            r: 8,
+            seed_z: 1,
+            folding_mode: FoldingMode::default(),
+            fold_check: FoldCheck::default(),
+            grinding_bits: 0,
+            query_sampling: Default::default(),
+            index_chaining: Default::default(),
+            transcript: Default::default(),
+        };
+        let over = deep_fri_prove_over(f0.clone(), &dom, &params, None);
+        let plain = deep_fri_prove_with_trace_root(f0, dom.fri_domain(), &params, None);
+        assert_eq!(over.proof_root, plain.proof_root);
+        let key = fri::VerifierKey::for_fri(&params, n).unwrap();
+        assert!(fri::deep_fri_verify(&key, &over));
+    }
+
+    #[test]
+    fn merge_is_thread_count_independent() {
+        use crate::parallel::ParallelConfig;
+
+        let n = 1 << 9;
+        let mut rng = StdRng::seed_from_u64(6);
+        let mut col = || -> Vec<F> { (0..n).map(|_| F::rand(&mut rng)).collect() };
+        let (a, s, e, t, r) = (col(), col(), col(), col(), col());
+        let z = Fp3 {
+            a0: F::from(11u64),
+            a1: F::from(13u64),
+            a2: F::from(17u64),
+        };
+
+        let dom = DomainH::new_radix2(n);
+        let merge = || dom.merge_deep_ali_blinded(&a, &s, &e, &t, Some(&r), F::from(2u64), z);
+        let expected = ParallelConfig::sequential().install(merge);
+        for threads in [2, 4] {
+            let cfg = ParallelConfig { max_threads: Some(threads), ..ParallelConfig::with_min_elems(1) };
+            assert_eq!(cfg.strict().install(merge), expected, "threads={threads}");
+        }
+    }
+
+    #[test]
+    fn sample_z_beta_is_transcript_bound() {
+        use transcript::default_params;
+
+        let n = 1 << 4;
+        let draw = |x: u64| {
+            let mut tr = Transcript::new(b"DEEP-ALI/test", default_params());
+            tr.absorb_field(F::from(x));
+            sample_z_beta(&mut tr, n)
+        };
+
+        let (z, beta) = draw(1);
+        assert_eq!((z, beta), draw(1));
+        assert_ne!(z, draw(2).0);
+        assert!([z.a0, z.a1, z.a2].iter().all(|&c| !is_in_domain(c, n)));
+    }
+}
@@ -0,0 +1,338 @@
+//! The `a ∘ s + e = t` relation behind `deep_ali_merge_evals`, as a statement.
+//!
+//! `a` and `t` are public and `s`, `e` secret, all `n` slots long; the
+//! product is slot-wise, as in the evaluation (NTT) form of ring-LWE. The
+//! noise is additionally bounded, `|e_i| ≤ B`, which the slices API never
+//! checked. Both are proved with the AIR pipeline:
+//!
+//! * `a`, `t` and the shifted range table `min(i, 2B)` are periodic columns
+//!   fixed by the statement, so the verifier never needs them committed,
+//! * main columns `s`, `e` and the table multiplicities `m`,
+//! * one aux column running a LogUp sum of `1/(γ − (e_i + B))` against
+//!   `m_i/(γ − table_i)`, closed by making all constraints cyclic.
+//!
+//! The trace is `max(n, 2B + 1)` rows rounded up to a power of two; padding
+//! slots have `a = t = s = e = 0`.
+
+use alloc::{vec, vec::Vec};
+use ark_ff::{batch_inversion, PrimeField, Zero};
+use ark_goldilocks::Goldilocks as F;
+
+use transcript::{default_params as transcript_params, Transcript};
+
+use crate::air::{prove_air, verify_air, Air, AirProof, AirProofOptions, BoundaryConstraint, EvaluationFrame};
+
+mod ds {
+    pub const LWE_STATEMENT: &[u8] = b"LWE/statement";
+    pub const LWE_DIGEST: &[u8] = b"LWE/digest";
+}
+
+const S: usize = 0;
+const E: usize = 1;
+const M: usize = 2;
+const ACC: usize = 3;
+
+const PERIODIC_A: usize = 0;
+const PERIODIC_T: usize = 1;
+const PERIODIC_TABLE: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LweParams {
+    /// Number of slots.
+    pub n: usize,
+    /// Noise bound B: every `e_i` lies in `[−B, B]`.
+    pub noise_bound: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct LweStatement {
+    pub a: Vec<F>,
+    pub t: Vec<F>,
+    pub params: LweParams,
+}
+
+#[derive(Clone, Debug)]
+pub struct LweWitness {
+    pub s: Vec<F>,
+    /// Noise as field elements; `−x` is `p − x`.
+    pub e: Vec<F>,
+}
+
+impl LweWitness {
+    /// Witness with signed noise coefficients.
+    pub fn from_signed(s: Vec<F>, e: &[i64]) -> Self {
+        let e = e
+            .iter()
+            .map(|&x| {
+                let mag = F::from(x.unsigned_abs());
+                if x < 0 {
+                    -mag
+                } else {
+                    mag
+                }
+            })
+            .collect();
+        Self { s, e }
+    }
+}
+
+impl LweStatement {
+    pub fn new(a: Vec<F>, t: Vec<F>, noise_bound: u64) -> Self {
+        assert_eq!(a.len(), t.len(), "a and t must have the same length");
+        let params = LweParams {
+            n: a.len(),
+            noise_bound,
+        };
+        Self { a, t, params }
+    }
+
+    /// `t = a ∘ s + e` for the given secret and noise.
+    pub fn from_witness(a: Vec<F>, witness: &LweWitness, noise_bound: u64) -> Self {
+        let t = a
+            .iter()
+            .zip(&witness.s)
+            .zip(&witness.e)
+            .map(|((a, s), e)| *a * s + e)
+            .collect();
+        Self::new(a, t, noise_bound)
+    }
+
+    /// Transcript digest of the parameters, `a` and `t`.
+    pub fn digest(&self) -> F {
+        let mut tr = Transcript::new(ds::LWE_STATEMENT, transcript_params());
+        tr.absorb_field(F::from(self.params.n as u64));
+        tr.absorb_field(F::from(self.params.noise_bound));
+        for x in self.a.iter().chain(&self.t) {
+            tr.absorb_field(*x);
+        }
+        tr.challenge(ds::LWE_DIGEST)
+    }
+
+    /// Native check of the relation and the noise bound.
+    pub fn is_satisfied_by(&self, witness: &LweWitness) -> bool {
+        let n = self.params.n;
+        witness.s.len() == n
+            && witness.e.len() == n
+            && (0..n).all(|i| self.a[i] * witness.s[i] + witness.e[i] == self.t[i])
+            && witness
+                .e
+                .iter()
+                .all(|e| shifted_noise(*e, self.params.noise_bound).is_some())
+    }
+}
+
+/// `e + B` as an integer in `[0, 2B]`, if `|e| ≤ B`.
+fn shifted_noise(e: F, bound: u64) -> Option<usize> {
+    let v = (e + F::from(bound)).into_bigint().0[0];
+    (v <= 2 * bound).then_some(v as usize)
+}
+
+/// The AIR behind [`prove_lwe`] / [`verify_lwe`].
+pub struct LweAir<'a> {
+    statement: &'a LweStatement,
+    trace_length: usize,
+}
+
+impl<'a> LweAir<'a> {
+    pub fn new(statement: &'a LweStatement) -> Self {
+        assert!(statement.params.n > 0, "empty LWE statement");
+        let table = 2 * statement.params.noise_bound as usize + 1;
+        let trace_length = statement.params.n.max(table).max(2).next_power_of_two();
+        Self {
+            statement,
+            trace_length,
+        }
+    }
+
+    fn padded(&self, v: &[F]) -> Vec<F> {
+        let mut out = v.to_vec();
+        out.resize(self.trace_length, F::zero());
+        out
+    }
+
+    fn table(&self) -> Vec<F> {
+        let top = 2 * self.statement.params.noise_bound as usize;
+        (0..self.trace_length).map(|i| F::from(i.min(top) as u64)).collect()
+    }
+
+    /// Main columns `[s, e, m]`.
+    pub fn build_trace(&self, witness: &LweWitness) -> Vec<Vec<F>> {
+        let bound = self.statement.params.noise_bound;
+        let e = self.padded(&witness.e);
+
+        // Counts go on each value's first table row; the repeated top
+        // entries of the clamped table carry no multiplicity.
+        let mut m = vec![0u64; self.trace_length];
+        for x in &e {
+            if let Some(v) = shifted_noise(*x, bound) {
+                m[v] += 1;
+            }
+        }
+
+        vec![self.padded(&witness.s), e, m.into_iter().map(F::from).collect()]
+    }
+}
+
+impl Air for LweAir<'_> {
+    fn trace_width(&self) -> usize {
+        3
+    }
+
+    fn trace_length(&self) -> usize {
+        self.trace_length
+    }
+
+    fn num_transition_constraints(&self) -> usize {
+        2
+    }
+
+    fn transition_degree(&self) -> usize {
+        3
+    }
+
+    /// `a·s + e − t` is quadratic; only the lookup accumulator is cubic.
+    fn transition_degrees(&self) -> Vec<usize> {
+        vec![2, 3]
+    }
+
+    fn transition_exemptions(&self) -> usize {
+        0
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<F>> {
+        vec![
+            self.padded(&self.statement.a),
+            self.padded(&self.statement.t),
+            self.table(),
+        ]
+    }
+
+    fn aux_width(&self) -> usize {
+        1
+    }
+
+    fn num_aux_challenges(&self) -> usize {
+        1
+    }
+
+    fn build_aux_trace(&self, main: &[Vec<F>], challenges: &[F]) -> Vec<Vec<F>> {
+        let gamma = challenges[0];
+        let shift = F::from(self.statement.params.noise_bound);
+        let n = self.trace_length;
+
+        let mut den: Vec<F> = main[E].iter().map(|e| gamma - (*e + shift)).collect();
+        den.extend(self.table().into_iter().map(|v| gamma - v));
+        batch_inversion(&mut den);
+
+        let mut acc = Vec::with_capacity(n);
+        let mut cur = F::zero();
+        for i in 0..n {
+            acc.push(cur);
+            cur += den[i] - main[M][i] * den[n + i];
+        }
+        vec![acc]
+    }
+
+    fn evaluate_transition(&self, frame: &EvaluationFrame<'_>, out: &mut [F]) {
+        let (cur, next, p) = (frame.current, frame.next, frame.periodic);
+        let gamma = frame.challenges[0];
+
+        out[0] = p[PERIODIC_A] * cur[S] + cur[E] - p[PERIODIC_T];
+
+        let ge = gamma - (cur[E] + F::from(self.statement.params.noise_bound));
+        let gt = gamma - p[PERIODIC_TABLE];
+        out[1] = (next[ACC] - cur[ACC]) * ge * gt - gt + cur[M] * ge;
+    }
+
+    fn boundary_constraints(&self, _challenges: &[F]) -> Vec<BoundaryConstraint> {
+        vec![BoundaryConstraint::new(ACC, 0, F::zero())]
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        vec![self.statement.digest()]
+    }
+}
+
+pub fn prove_lwe(statement: &LweStatement, witness: &LweWitness, opts: &AirProofOptions) -> AirProof {
+    assert_eq!(witness.s.len(), statement.params.n, "secret length mismatch");
+    assert_eq!(witness.e.len(), statement.params.n, "noise length mismatch");
+    let air = LweAir::new(statement);
+    prove_air(&air, air.build_trace(witness), opts)
+}
+
+pub fn verify_lwe(statement: &LweStatement, opts: &AirProofOptions, proof: &AirProof) -> bool {
+    if statement.a.len() != statement.params.n || statement.t.len() != statement.params.n {
+        return false;
+    }
+    verify_air(&LweAir::new(statement), opts, proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::air::first_unsatisfied_row;
+    use ark_ff::UniformRand;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const B: u64 = 8;
+
+    fn instance(n: usize, rng: &mut StdRng) -> (LweStatement, LweWitness) {
+        let a: Vec<F> = (0..n).map(|_| F::rand(rng)).collect();
+        let s: Vec<F> = (0..n).map(|_| F::rand(rng)).collect();
+        let e: Vec<i64> = (0..n).map(|_| rng.gen_range(-(B as i64)..=B as i64)).collect();
+        let witness = LweWitness::from_signed(s, &e);
+        (LweStatement::from_witness(a, &witness, B), witness)
+    }
+
+    #[test]
+    fn noise_bound_is_enforced_by_lookup() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let (stmt, mut wit) = instance(32, &mut rng);
+        assert!(stmt.is_satisfied_by(&wit));
+
+        let air = LweAir::new(&stmt);
+        let gamma = [F::from(0x1234_5678u64)];
+        assert_eq!(first_unsatisfied_row(&air, &air.build_trace(&wit), &gamma), None);
+
+        // Noise B + 1 with t adjusted so only the bound is violated.
+        wit.e[5] = F::from(B + 1);
+        let stmt = LweStatement::from_witness(stmt.a.clone(), &wit, B);
+        assert!(!stmt.is_satisfied_by(&wit));
+        let air = LweAir::new(&stmt);
+        assert!(first_unsatisfied_row(&air, &air.build_trace(&wit), &gamma).is_some());
+    }
+
+    #[test]
+    fn digest_binds_statement() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let (stmt, _) = instance(16, &mut rng);
+        let mut other = stmt.clone();
+        other.t[3] += F::from(1u64);
+        assert_ne!(stmt.digest(), other.digest());
+
+        let looser = LweStatement::new(stmt.a.clone(), stmt.t.clone(), B + 1);
+        assert_ne!(stmt.digest(), looser.digest());
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn prove_verify_lwe_roundtrip() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let (stmt, wit) = instance(64, &mut rng);
+        let opts = AirProofOptions::new(4, 16);
+
+        let proof = prove_lwe(&stmt, &wit, &opts);
+        assert!(verify_lwe(&stmt, &opts, &proof));
+
+        let mut other = stmt.clone();
+        other.t[0] += F::from(1u64);
+        assert!(!verify_lwe(&other, &opts, &proof));
+
+        // Satisfies a ∘ s + e = t but exceeds the noise bound.
+        let mut big = wit.clone();
+        big.e[7] = F::from(3 * B);
+        let stmt = LweStatement::from_witness(stmt.a.clone(), &big, B);
+        let proof = prove_lwe(&stmt, &big, &opts);
+        assert!(!verify_lwe(&stmt, &opts, &proof));
+    }
+}
@@ -0,0 +1,252 @@
+//! Per-phase heap high-water marks for the prover (`mem_report` feature).
+//!
+//! [`TrackingAlloc`] wraps a global allocator and counts live heap bytes
+//! and their high-water mark. With it installed in the binary,
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOC: deep_ali::memory::TrackingAlloc = deep_ali::memory::TrackingAlloc::system();
+//! ```
+//!
+//! [`deep_fri_prove_with_memory_report`] and [`prove_air_with_memory_report`]
+//! reset the mark at every [`ProverPhase`] boundary and return, alongside
+//! the proof, what each phase held at entry and at its peak. The counters
+//! are process-wide, so allocations made by other threads in the meantime
+//! are charged to whichever phase is running. Without the allocator the
+//! report comes back with `tracked == false` and zero counts.
+
+use alloc::vec::Vec;
+use ark_goldilocks::Goldilocks as F;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::alloc::{GlobalAlloc, Layout, System};
+
+use crate::air::{prove_air_hooked, Air, AirProof, AirProofOptions};
+use crate::fri::{
+    prove_hooked, DeepFriParams, DeepFriProof, FriDomain, ProveError, ProverHook, ProverPhase,
+};
+use crate::params::ParamError;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Global allocator wrapper that keeps the counters behind
+/// [`live_bytes`] and [`peak_bytes`].
+pub struct TrackingAlloc<A = System> {
+    inner: A,
+}
+
+impl TrackingAlloc<System> {
+    pub const fn system() -> Self {
+        Self { inner: System }
+    }
+}
+
+impl<A> TrackingAlloc<A> {
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+fn grow(bytes: usize) {
+    let live = LIVE.fetch_add(bytes, Ordering::Relaxed) + bytes;
+    PEAK.fetch_max(live, Ordering::Relaxed);
+}
+
+fn shrink(bytes: usize) {
+    LIVE.fetch_sub(bytes, Ordering::Relaxed);
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackingAlloc<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let p = self.inner.alloc(layout);
+        if !p.is_null() {
+            grow(layout.size());
+        }
+        p
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let p = self.inner.alloc_zeroed(layout);
+        if !p.is_null() {
+            grow(layout.size());
+        }
+        p
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let p = self.inner.realloc(ptr, layout, new_size);
+        if !p.is_null() {
+            if new_size >= layout.size() {
+                grow(new_size - layout.size());
+            } else {
+                shrink(layout.size() - new_size);
+            }
+        }
+        p
+    }
+}
+
+/// Heap bytes currently allocated through [`TrackingAlloc`].
+pub fn live_bytes() -> usize {
+    LIVE.load(Ordering::Relaxed)
+}
+
+/// Highest [`live_bytes`] since the last phase boundary.
+pub fn peak_bytes() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+fn reset_peak() {
+    PEAK.store(live_bytes(), Ordering::Relaxed);
+}
+
+/// Heap usage over one prover phase.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PhaseMemory {
+    pub phase: ProverPhase,
+    /// Live bytes when the phase began.
+    pub entry_bytes: usize,
+    /// High-water mark of live bytes while it ran.
+    pub peak_bytes: usize,
+}
+
+impl PhaseMemory {
+    /// Bytes the phase needed on top of what it inherited.
+    pub fn growth(&self) -> usize {
+        self.peak_bytes.saturating_sub(self.entry_bytes)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Whether [`TrackingAlloc`] is the global allocator; the counts are
+    /// all zero otherwise.
+    pub tracked: bool,
+    /// Phases in the order the prover ran them.
+    pub phases: Vec<PhaseMemory>,
+}
+
+impl MemoryReport {
+    pub fn phase(&self, phase: ProverPhase) -> Option<&PhaseMemory> {
+        self.phases.iter().find(|p| p.phase == phase)
+    }
+
+    /// High-water mark over the whole run.
+    pub fn peak_bytes(&self) -> usize {
+        self.phases.iter().map(|p| p.peak_bytes).max().unwrap_or(0)
+    }
+}
+
+#[derive(Default)]
+struct MemoryMeter {
+    phases: Vec<PhaseMemory>,
+}
+
+impl MemoryMeter {
+    fn close(&mut self) {
+        if let Some(last) = self.phases.last_mut() {
+            last.peak_bytes = peak_bytes();
+        }
+    }
+
+    fn finish(mut self) -> MemoryReport {
+        self.close();
+        MemoryReport {
+            tracked: peak_bytes() > 0,
+            phases: self.phases,
+        }
+    }
+}
+
+impl ProverHook for MemoryMeter {
+    fn enter(&mut self, phase: ProverPhase) {
+        self.close();
+        // Grow the record before the mark resets so it is not charged to the phase.
+        self.phases.reserve(1);
+        reset_peak();
+        self.phases.push(PhaseMemory {
+            phase,
+            entry_bytes: live_bytes(),
+            peak_bytes: 0,
+        });
+    }
+}
+
+/// As [`crate::fri::try_deep_fri_prove_with_trace_root`], also reporting
+/// the heap high-water mark of each phase.
+pub fn deep_fri_prove_with_memory_report(
+    f0: Vec<F>,
+    domain0: FriDomain,
+    params: &DeepFriParams,
+    trace_root: Option<F>,
+) -> Result<(DeepFriProof, MemoryReport), ParamError> {
+    let mut meter = MemoryMeter::default();
+    let proof =
+        prove_hooked(f0, domain0, params, trace_root, &mut meter).map_err(ProveError::uncancelled)?;
+    Ok((proof, meter.finish()))
+}
+
+/// As [`crate::air::prove_air`], also reporting the heap high-water mark
+/// of each phase, trace LDE included.
+pub fn prove_air_with_memory_report<A: Air>(
+    air: &A,
+    main: Vec<Vec<F>>,
+    opts: &AirProofOptions,
+) -> (AirProof, MemoryReport) {
+    let mut meter = MemoryMeter::default();
+    let proof = prove_air_hooked(air, main, opts, &mut meter);
+    (proof, meter.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deep_tower::Fp3;
+    use crate::fri::{deep_fri_prove, FoldCheck, FoldingMode, IndexChaining, QuerySampling, TranscriptHash};
+    use crate::kat::proof_digest;
+    use ark_poly::{
+        univariate::DensePolynomial, DenseUVPolynomial, EvaluationDomain, GeneralEvaluationDomain,
+    };
+    use core::mem::size_of;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[global_allocator]
+    static ALLOC: TrackingAlloc = TrackingAlloc::system();
+
+    #[test]
+    fn reports_every_phase_without_changing_the_proof() {
+        const N: usize = 1 << 12;
+        let mut rng = StdRng::seed_from_u64(7);
+        let poly = DensePolynomial::<F>::rand(N / 8 - 1, &mut rng);
+        let f0 = GeneralEvaluationDomain::<F>::new(N).unwrap().fft(poly.coeffs());
+        let params = DeepFriParams {
+            schedule: vec![4, 4, 4],
+            r: 16,
+            seed_z: 0x3748,
+            folding_mode: FoldingMode::default(),
+            fold_check: FoldCheck::default(),
+            grinding_bits: 0,
+            query_sampling: QuerySampling::default(),
+            index_chaining: IndexChaining::default(),
+            transcript: TranscriptHash::default(),
+        };
+        let domain = FriDomain::new_radix2(N);
+
+        let (proof, report) =
+            deep_fri_prove_with_memory_report(f0.clone(), domain, &params, None).unwrap();
+        assert_eq!(proof_digest(&proof), proof_digest(&deep_fri_prove(f0, domain, &params)));
+
+        assert!(report.tracked);
+        let phases: Vec<_> = report.phases.iter().map(|p| p.phase).collect();
+        assert_eq!(phases, [ProverPhase::Layers, ProverPhase::Merkle, ProverPhase::Queries]);
+        // Layer 0 alone keeps f₀, its copy in `f_layers` and the Fp³ quotient alive.
+        let layers = report.phase(ProverPhase::Layers).unwrap();
+        assert!(layers.peak_bytes >= N * (2 * size_of::<F>() + size_of::<Fp3>()));
+        assert!(report.peak_bytes() >= layers.peak_bytes);
+    }
+}
@@ -0,0 +1,247 @@
+//! Runtime-tunable thresholds for when rayon kicks in.
+//!
+//! The prover used to hardcode `PARALLEL_MIN_ELEMS = 1 << 12` in both the
+//! DEEP-ALI merge and the FRI fold. On small machines (e.g. a 2 vCPU
+//! t4g.micro) that threshold is too low and the work-stealing overhead
+//! dominates, so the cut-over is now read from a thread-local
+//! `ParallelConfig` that callers can override around a proving run.
+//! Without `std` there is no thread-local storage (and no rayon), so the
+//! defaults are always in effect.
+//!
+//! # Determinism
+//!
+//! Proofs are bit-identical whatever the thread count, the thresholds
+//! below, or whether the `parallel` feature is on. Every rayon path (Φ̃ and
+//! f₀ fills, both FRI folds, query openings and payloads) is an
+//! order-preserving map over independent indices in exact field
+//! arithmetic; nothing is reduced across workers. Merkle leaf and node
+//! hashing and the transcript always run sequentially. Fiat–Shamir
+//! depends on this, so [`ParallelConfig::strict_determinism`] re-runs each
+//! parallel pass sequentially and panics on any difference, for CI and
+//! audit runs that want the guarantee checked rather than assumed.
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::cell::Cell;
+
+/// Default minimum slice length before an operation goes parallel.
+pub const DEFAULT_PARALLEL_MIN_ELEMS: usize = 1 << 12;
+
+/// Default minimum number of queries before opening goes parallel. Each
+/// query copies a full path per layer, so far fewer items pay for a split.
+pub const DEFAULT_PARALLEL_MIN_OPENINGS: usize = 32;
+
+/// Operation kinds with an independent parallel threshold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParallelOp {
+    /// Φ̃ / f₀ evaluation passes in the DEEP-ALI merge.
+    Merge,
+    /// Per-layer FRI folding.
+    Fold,
+    /// Merkle openings and payloads for the FRI queries.
+    Open,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParallelConfig {
+    /// Minimum length for the DEEP-ALI merge passes.
+    pub merge_min_elems: usize,
+    /// Minimum length (of the folded layer) for FRI folding.
+    pub fold_min_elems: usize,
+    /// Minimum query count for building openings and payloads.
+    pub open_min_elems: usize,
+    /// Upper bound on worker threads; `None` uses the global rayon pool.
+    pub max_threads: Option<usize>,
+    /// Cross-check every parallel pass against a sequential recomputation,
+    /// doubling its cost.
+    pub strict_determinism: bool,
+}
+
+impl ParallelConfig {
+    pub const fn new() -> Self {
+        Self {
+            merge_min_elems: DEFAULT_PARALLEL_MIN_ELEMS,
+            fold_min_elems: DEFAULT_PARALLEL_MIN_ELEMS,
+            open_min_elems: DEFAULT_PARALLEL_MIN_OPENINGS,
+            max_threads: None,
+            strict_determinism: false,
+        }
+    }
+
+    /// Never parallelise, regardless of input size.
+    pub const fn sequential() -> Self {
+        Self {
+            merge_min_elems: usize::MAX,
+            fold_min_elems: usize::MAX,
+            open_min_elems: usize::MAX,
+            max_threads: Some(1),
+            strict_determinism: false,
+        }
+    }
+
+    /// Same threshold for every operation kind.
+    pub const fn with_min_elems(min_elems: usize) -> Self {
+        Self {
+            merge_min_elems: min_elems,
+            fold_min_elems: min_elems,
+            open_min_elems: min_elems,
+            max_threads: None,
+            strict_determinism: false,
+        }
+    }
+
+    /// This config with [`strict_determinism`](Self::strict_determinism) on.
+    pub const fn strict(self) -> Self {
+        Self {
+            strict_determinism: true,
+            ..self
+        }
+    }
+
+    pub fn min_elems(&self, op: ParallelOp) -> usize {
+        match op {
+            ParallelOp::Merge => self.merge_min_elems,
+            ParallelOp::Fold => self.fold_min_elems,
+            ParallelOp::Open => self.open_min_elems,
+        }
+    }
+
+    /// Run `f` with this config installed on the current thread.
+    ///
+    /// With the `parallel` feature and `max_threads` set, `f` runs inside a
+    /// dedicated rayon pool of that size instead of the global one.
+    pub fn install<R: Send>(&self, f: impl FnOnce() -> R + Send) -> R {
+        #[cfg(feature = "parallel")]
+        {
+            if let Some(n) = self.max_threads {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(n.max(1))
+                    .build()
+                    .expect("build rayon thread pool");
+                let cfg = *self;
+                return pool.install(move || with_parallel_config(cfg, f));
+            }
+        }
+        with_parallel_config(*self, f)
+    }
+}
+
+impl Default for ParallelConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    static PARALLEL_CONFIG: Cell<ParallelConfig> = const { Cell::new(ParallelConfig::new()) };
+}
+
+/// Config in effect on the current thread.
+#[cfg(feature = "std")]
+pub fn parallel_config() -> ParallelConfig {
+    PARALLEL_CONFIG.with(|c| c.get())
+}
+
+#[cfg(not(feature = "std"))]
+pub fn parallel_config() -> ParallelConfig {
+    ParallelConfig::new()
+}
+
+/// Replace the current thread's config, returning the previous one.
+#[cfg(feature = "std")]
+pub fn set_parallel_config(cfg: ParallelConfig) -> ParallelConfig {
+    PARALLEL_CONFIG.with(|c| c.replace(cfg))
+}
+
+#[cfg(not(feature = "std"))]
+pub fn set_parallel_config(_cfg: ParallelConfig) -> ParallelConfig {
+    ParallelConfig::new()
+}
+
+/// Run `f` with `cfg` installed, restoring the previous config afterwards.
+pub fn with_parallel_config<R>(cfg: ParallelConfig, f: impl FnOnce() -> R) -> R {
+    struct Restore(ParallelConfig);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            set_parallel_config(self.0);
+        }
+    }
+
+    let _restore = Restore(set_parallel_config(cfg));
+    f()
+}
+
+#[inline]
+pub(crate) fn enable_parallel(op: ParallelOp, len: usize) -> bool {
+    #[cfg(feature = "parallel")]
+    {
+        let cfg = parallel_config();
+        cfg.max_threads != Some(1)
+            && len >= cfg.min_elems(op)
+            && rayon::current_num_threads() > 1
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        let _ = (op, len);
+        false
+    }
+}
+
+/// `(0..len).map(f)` in order, on the rayon pool when `op` goes parallel.
+pub(crate) fn map_indices<T, G>(op: ParallelOp, len: usize, f: G) -> Vec<T>
+where
+    T: Send + PartialEq,
+    G: Fn(usize) -> T + Sync + Send,
+{
+    if enable_parallel(op, len) {
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            let out: Vec<T> = (0..len).into_par_iter().map(&f).collect();
+            if parallel_config().strict_determinism {
+                assert_deterministic(op, (0..len).all(|i| f(i) == out[i]));
+            }
+            return out;
+        }
+    }
+    (0..len).map(f).collect()
+}
+
+/// Panic unless a parallel `op` matched its sequential recomputation.
+#[inline]
+pub(crate) fn assert_deterministic(op: ParallelOp, same: bool) {
+    assert!(same, "parallel {op:?} pass diverged from its sequential recomputation");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoped_config_is_restored() {
+        let before = parallel_config();
+        let cfg = ParallelConfig::with_min_elems(1 << 20);
+
+        let seen = with_parallel_config(cfg, parallel_config);
+        assert_eq!(seen, cfg);
+        assert_eq!(parallel_config(), before);
+    }
+
+    #[test]
+    fn map_indices_is_thread_count_independent() {
+        let f = |i: usize| (i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15).rotate_left(i as u32 % 64);
+        let expected: Vec<u64> = (0..10_000).map(f).collect();
+        for threads in [1, 2, 3, 8] {
+            let cfg = ParallelConfig { max_threads: Some(threads), ..ParallelConfig::with_min_elems(1) }.strict();
+            let got = cfg.install(|| map_indices(ParallelOp::Fold, expected.len(), f));
+            assert_eq!(got, expected, "threads={threads}");
+        }
+    }
+
+    #[test]
+    fn sequential_never_enables() {
+        let on = ParallelConfig::sequential().install(|| enable_parallel(ParallelOp::Fold, usize::MAX - 1));
+        assert!(!on);
+    }
+}
@@ -0,0 +1,578 @@
+//! Public parameters shared by the FRI prover and verifier.
+//!
+//! Everything outside the statement that both sides must agree on — the
+//! field, the transcript's Poseidon instance, the per-layer Merkle shapes,
+//! the folding schedule, rate and query count — lives in [`PublicParams`].
+//! Its canonical digest is absorbed right after transcript initialisation,
+//! so a proof made under one parameter set cannot be checked under another
+//! even where a single field would not otherwise have reached the transcript.
+
+use alloc::vec::Vec;
+use ark_ff::PrimeField;
+use ark_goldilocks::Goldilocks as F;
+use merkle::{ArityPolicy, DigestWidth, MerkleChannelCfg};
+use poseidon::PoseidonParams;
+use transcript::default_params as transcript_params;
+
+use crate::fri::{DeepFriParams, FoldCheck, FoldingMode, IndexChaining, QuerySampling, TranscriptHash};
+#[cfg(feature = "std")]
+use crate::sizing;
+
+const PUBLIC_PARAMS_DS: &[u8] = b"DEEP-FRI/public-params/v1";
+const POSEIDON_PARAMS_DS: &[u8] = b"DEEP-FRI/poseidon-params/v1";
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct PublicParams {
+    /// Base-field modulus.
+    pub field_id: u64,
+    /// [`poseidon_params_digest`] of the transcript's Poseidon instance.
+    pub poseidon_digest: [u8; 32],
+    /// Tree shape and label of every committed FRI layer.
+    pub merkle: Vec<MerkleChannelCfg>,
+    pub n0: usize,
+    pub schedule: Vec<usize>,
+    /// `log2(n0 / Π schedule)`: the final layer must be constant, so the
+    /// tested code has rate `2^-log_inv_rate`.
+    pub log_inv_rate: u32,
+    pub num_queries: usize,
+    pub folding_mode: FoldingMode,
+    pub fold_check: FoldCheck,
+    pub query_sampling: QuerySampling,
+    pub index_chaining: IndexChaining,
+    /// Proof-of-work bits on the query seed, credited against the query
+    /// budget; see [`DeepFriParams::grinding_bits`].
+    pub grinding_bits: u32,
+    /// Hash the FRI transcript draws its challenges with.
+    pub transcript: TranscriptHash,
+}
+
+/// Hash the commitments and transcript are costed for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum HashProfile {
+    /// One t = 17 permutation absorbs 16 children, so wide nodes are cheap.
+    Poseidon,
+    /// Cost grows with the node's input; narrower nodes keep both hashing
+    /// and authentication paths short.
+    Sha3,
+    /// SHA3 shapes with full 32-byte Merkle nodes and query seeds, for
+    /// deployments that cannot rely on 64-bit digests. Proofs come from
+    /// [`crate::fri::try_deep_fri_prove_conservative`].
+    Conservative,
+}
+
+impl HashProfile {
+    /// Widest fold, and hence Merkle arity, worth using.
+    pub fn max_arity(self) -> usize {
+        match self {
+            HashProfile::Poseidon => 16,
+            HashProfile::Sha3 | HashProfile::Conservative => 8,
+        }
+    }
+
+    /// Proof-of-work a prover can afford at roughly one hash per attempt.
+    pub fn grinding_bits(self) -> u32 {
+        match self {
+            HashProfile::Poseidon => 16,
+            HashProfile::Sha3 | HashProfile::Conservative => 20,
+        }
+    }
+
+    /// Width of every committed Merkle node.
+    pub fn digest_width(self) -> DigestWidth {
+        match self {
+            HashProfile::Poseidon | HashProfile::Sha3 => DigestWidth::Digest64,
+            HashProfile::Conservative => DigestWidth::Digest256,
+        }
+    }
+}
+
+/// Rate chosen by [`PublicParams::for_security`]: the committed polynomial
+/// must have degree below `2^(k − SECURITY_LOG_INV_RATE)`.
+pub const SECURITY_LOG_INV_RATE: u32 = 3;
+
+/// Most proof-of-work bits a [`DeepFriParams`] may ask for: the prover
+/// searches nonces below `2^(bits + 8)`, and grinding past this is cheaper
+/// bought with queries.
+pub const MAX_GRINDING_BITS: u32 = 32;
+
+/// A [`DeepFriParams`] the prover cannot run with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamError {
+    /// The evaluation domain must be a power of two of at least 2 points.
+    DomainSize { n0: usize },
+    /// The codeword has `len` values for a domain of `n0` points.
+    CodewordLength { len: usize, n0: usize },
+    /// The domain generator is not the radix-2 generator of its size.
+    DomainGenerator { n0: usize },
+    EmptySchedule,
+    /// Every fold must be a power of two, at least 2.
+    FoldNotPowerOfTwo { layer: usize, fold: usize },
+    /// Layer `layer` has `size` points, which `fold` does not divide.
+    ScheduleDoesNotDivide { layer: usize, fold: usize, size: usize },
+    NoQueries,
+    /// [`QuerySampling::Distinct`] cannot draw more indices than points.
+    TooManyQueries { r: usize, n0: usize },
+    /// [`PublicParams::merkle`] must have one shape per folded layer.
+    MerkleLayers { layers: usize, schedule: usize },
+    /// More grinding bits than [`MAX_GRINDING_BITS`].
+    GrindingBits { bits: u32, max: u32 },
+    /// No nonce in the search range met the grinding target; with at most
+    /// [`MAX_GRINDING_BITS`] this happens with probability about `e^-256`.
+    GrindingExhausted { bits: u32 },
+}
+
+impl core::fmt::Display for ParamError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParamError::DomainSize { n0 } => {
+                write!(f, "domain size {n0} is not a power of two ≥ 2")
+            }
+            ParamError::CodewordLength { len, n0 } => {
+                write!(f, "codeword of length {len} on a domain of {n0} points")
+            }
+            ParamError::DomainGenerator { n0 } => {
+                write!(f, "domain generator does not generate the size-{n0} subgroup")
+            }
+            ParamError::EmptySchedule => write!(f, "empty folding schedule"),
+            ParamError::FoldNotPowerOfTwo { layer, fold } => {
+                write!(f, "fold {fold} at layer {layer} is not a power of two ≥ 2")
+            }
+            ParamError::ScheduleDoesNotDivide { layer, fold, size } => {
+                write!(f, "fold {fold} at layer {layer} does not divide its {size} points")
+            }
+            ParamError::NoQueries => write!(f, "no queries"),
+            ParamError::TooManyQueries { r, n0 } => {
+                write!(f, "{r} distinct queries on a domain of {n0} points")
+            }
+            ParamError::MerkleLayers { layers, schedule } => {
+                write!(f, "{layers} Merkle shapes for a schedule of {schedule} folds")
+            }
+            ParamError::GrindingBits { bits, max } => {
+                write!(f, "{bits} grinding bits, at most {max} supported")
+            }
+            ParamError::GrindingExhausted { bits } => {
+                write!(f, "no nonce meets {bits} grinding bits")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParamError {}
+
+impl DeepFriParams {
+    /// Check the schedule and query count against a domain of `n0` points.
+    pub fn validate(&self, n0: usize) -> Result<(), ParamError> {
+        if n0 < 2 || !n0.is_power_of_two() {
+            return Err(ParamError::DomainSize { n0 });
+        }
+        if self.schedule.is_empty() {
+            return Err(ParamError::EmptySchedule);
+        }
+        fold_sizes(n0, &self.schedule)?;
+        if self.r == 0 {
+            return Err(ParamError::NoQueries);
+        }
+        if self.query_sampling == QuerySampling::Distinct && self.r > n0 {
+            return Err(ParamError::TooManyQueries { r: self.r, n0 });
+        }
+        if self.grinding_bits > MAX_GRINDING_BITS {
+            return Err(ParamError::GrindingBits { bits: self.grinding_bits, max: MAX_GRINDING_BITS });
+        }
+        Ok(())
+    }
+}
+
+/// Size of the final layer once `schedule` has folded `n0` points.
+fn fold_sizes(n0: usize, schedule: &[usize]) -> Result<usize, ParamError> {
+    schedule.iter().enumerate().try_fold(n0, |size, (layer, &fold)| {
+        if fold < 2 || !fold.is_power_of_two() {
+            return Err(ParamError::FoldNotPowerOfTwo { layer, fold });
+        }
+        if size % fold != 0 {
+            return Err(ParamError::ScheduleDoesNotDivide { layer, fold, size });
+        }
+        Ok(size / fold)
+    })
+}
+
+/// `schedule` with one more fold taking whatever is left, so that the
+/// final layer is a single point.
+pub fn normalize_schedule(n0: usize, schedule: &[usize]) -> Result<Vec<usize>, ParamError> {
+    if n0 < 2 || !n0.is_power_of_two() {
+        return Err(ParamError::DomainSize { n0 });
+    }
+    let mut schedule = schedule.to_vec();
+    let last = fold_sizes(n0, &schedule)?;
+    if last > 1 {
+        schedule.push(last);
+    }
+    Ok(schedule)
+}
+
+/// Folding challenges live in Fp3.
+#[cfg(feature = "std")]
+const CHALLENGE_FIELD_BITS: u32 = 3 * 64;
+
+impl PublicParams {
+    pub fn new(
+        n0: usize,
+        schedule: &[usize],
+        num_queries: usize,
+        folding_mode: FoldingMode,
+        fold_check: FoldCheck,
+    ) -> Self {
+        let mut merkle = Vec::with_capacity(schedule.len());
+        let mut n = n0;
+        for (ell, &m) in schedule.iter().enumerate() {
+            merkle.push(MerkleChannelCfg::for_leaves(n, ArityPolicy::UpTo(m), ell as u64));
+            n /= m.max(1);
+        }
+
+        Self {
+            field_id: F::MODULUS.0[0],
+            poseidon_digest: poseidon_params_digest(&transcript_params()),
+            merkle,
+            n0,
+            schedule: schedule.to_vec(),
+            log_inv_rate: n.max(1).trailing_zeros(),
+            num_queries,
+            folding_mode,
+            fold_check,
+            query_sampling: QuerySampling::default(),
+            index_chaining: IndexChaining::default(),
+            grinding_bits: 0,
+            transcript: TranscriptHash::default(),
+        }
+    }
+
+    /// Parameters for a `2^k` evaluation domain reaching `target_bits`.
+    ///
+    /// Folds by the profile's widest arity down to a final layer of
+    /// `2^SECURITY_LOG_INV_RATE`, the last fold taking the remainder, and
+    /// sizes the query count with [`sizing::r_for_schedule_with_grinding`]
+    /// at the profile's grinding bits. The fold is coset interpolation with
+    /// recomputed fold checks.
+    #[cfg(feature = "std")]
+    pub fn for_security(k: u32, target_bits: u32, profile: HashProfile) -> Self {
+        assert!(
+            k > SECURITY_LOG_INV_RATE,
+            "domain 2^{k} too small for rate 2^-{SECURITY_LOG_INV_RATE}"
+        );
+
+        let step = profile.max_arity().trailing_zeros();
+        let mut schedule = Vec::new();
+        let mut left = k - SECURITY_LOG_INV_RATE;
+        while left > 0 {
+            let bits = left.min(step);
+            schedule.push(1usize << bits);
+            left -= bits;
+        }
+
+        let grinding_bits = profile.grinding_bits().min(target_bits);
+        let rate = 1.0 / (1u64 << SECURITY_LOG_INV_RATE) as f64;
+        let num_queries = sizing::r_for_schedule_with_grinding(
+            &schedule,
+            rate,
+            CHALLENGE_FIELD_BITS,
+            target_bits as f64,
+            grinding_bits,
+        )
+        .expect("target_bits exceeds the commit-phase bound for this domain");
+
+        Self {
+            grinding_bits,
+            ..Self::new(
+                1 << k,
+                &schedule,
+                num_queries,
+                FoldingMode::CosetInterpolation,
+                FoldCheck::RecomputeFromCoset,
+            )
+        }
+        .with_digest(profile.digest_width())
+    }
+
+    /// FRI parameters matching these public parameters.
+    pub fn fri_params(&self, seed_z: u64) -> DeepFriParams {
+        DeepFriParams {
+            schedule: self.schedule.clone(),
+            r: self.num_queries,
+            seed_z,
+            folding_mode: self.folding_mode,
+            fold_check: self.fold_check,
+            grinding_bits: self.grinding_bits,
+            query_sampling: self.query_sampling,
+            index_chaining: self.index_chaining,
+            transcript: self.transcript,
+        }
+    }
+
+    pub fn for_fri(params: &DeepFriParams, n0: usize) -> Self {
+        Self {
+            query_sampling: params.query_sampling,
+            index_chaining: params.index_chaining,
+            grinding_bits: params.grinding_bits,
+            transcript: params.transcript,
+            ..Self::new(
+                n0,
+                &params.schedule,
+                params.r,
+                params.folding_mode,
+                params.fold_check,
+            )
+        }
+    }
+
+    /// FRI challenges drawn from a `transcript` transcript.
+    pub fn with_transcript(self, transcript: TranscriptHash) -> Self {
+        Self { transcript, ..self }
+    }
+
+    /// Every layer committed with `width` nodes.
+    pub fn with_digest(mut self, width: DigestWidth) -> Self {
+        for cfg in &mut self.merkle {
+            cfg.digest = width;
+        }
+        self
+    }
+
+    /// Canonical encoding hashed with a versioned domain tag.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut h = blake3::Hasher::new();
+        h.update(PUBLIC_PARAMS_DS);
+        let mut put = |x: u64| {
+            h.update(&x.to_le_bytes());
+        };
+
+        put(self.field_id);
+        put(self.n0 as u64);
+        put(self.schedule.len() as u64);
+        for &m in &self.schedule {
+            put(m as u64);
+        }
+        put(self.log_inv_rate as u64);
+        put(self.num_queries as u64);
+        put(self.folding_mode.id());
+        put(self.fold_check.id());
+        put(self.query_sampling.id());
+        put(self.index_chaining.id());
+        put(self.grinding_bits as u64);
+        put(self.merkle.len() as u64);
+        for cfg in &self.merkle {
+            put(cfg.tree_label);
+            put(cfg.layer_arities.len() as u64);
+            for &a in &cfg.layer_arities {
+                put(a as u64);
+            }
+            // Only wide shapes are marked, so existing digests stay put.
+            if cfg.digest == DigestWidth::Digest256 {
+                put(cfg.digest.bytes() as u64);
+            }
+        }
+
+        h.update(&self.poseidon_digest);
+        // Likewise only non-Poseidon transcripts.
+        if self.transcript != TranscriptHash::Poseidon {
+            h.update(&self.transcript.id().to_le_bytes());
+        }
+        *h.finalize().as_bytes()
+    }
+}
+
+/// Digest of a Poseidon instance's MDS matrix and round constants.
+pub fn poseidon_params_digest(params: &PoseidonParams) -> [u8; 32] {
+    let mut h = blake3::Hasher::new();
+    h.update(POSEIDON_PARAMS_DS);
+    let rows = params
+        .mds
+        .iter()
+        .chain(params.rc_full.iter())
+        .flat_map(|row| row.iter());
+    for x in rows.chain(params.rc_partial.iter()) {
+        h.update(&x.into_bigint().0[0].to_le_bytes());
+    }
+    *h.finalize().as_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pp() -> PublicParams {
+        PublicParams::new(
+            1 << 10,
+            &[4, 4, 4],
+            32,
+            FoldingMode::CosetInterpolation,
+            FoldCheck::CommittedS,
+        )
+    }
+
+    #[test]
+    fn derived_fields() {
+        let pp = pp();
+        assert_eq!(pp.field_id, 0xffff_ffff_0000_0001);
+        assert_eq!(pp.log_inv_rate, 4);
+        assert_eq!(pp.merkle.len(), 3);
+        assert_eq!(pp.merkle[2].tree_label, 2);
+    }
+
+    #[test]
+    fn digest_covers_every_field() {
+        let base = pp().digest();
+        assert_eq!(base, pp().digest());
+
+        let variants = [
+            PublicParams {
+                n0: 1 << 11,
+                ..pp()
+            },
+            PublicParams {
+                schedule: vec![4, 4, 2],
+                ..pp()
+            },
+            PublicParams {
+                num_queries: 31,
+                ..pp()
+            },
+            PublicParams {
+                folding_mode: FoldingMode::PowersOfZ,
+                ..pp()
+            },
+            PublicParams {
+                fold_check: FoldCheck::RecomputeFromCoset,
+                ..pp()
+            },
+            PublicParams {
+                log_inv_rate: 5,
+                ..pp()
+            },
+            PublicParams {
+                poseidon_digest: [0u8; 32],
+                ..pp()
+            },
+            PublicParams {
+                merkle: Vec::new(),
+                ..pp()
+            },
+            PublicParams {
+                grinding_bits: 8,
+                ..pp()
+            },
+            PublicParams {
+                query_sampling: QuerySampling::Distinct,
+                ..pp()
+            },
+            PublicParams {
+                index_chaining: IndexChaining::Rerandomized,
+                ..pp()
+            },
+            pp().with_digest(DigestWidth::Digest256),
+            pp().with_transcript(TranscriptHash::Keccak256),
+        ];
+        for v in variants {
+            assert_ne!(v.digest(), base);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde1")]
+    fn public_and_poseidon_params_roundtrip_through_serde() {
+        let pp = PublicParams {
+            query_sampling: QuerySampling::Distinct,
+            ..pp()
+        };
+        let back: PublicParams = serde_json::from_str(&serde_json::to_string(&pp).unwrap()).unwrap();
+        assert_eq!(back.digest(), pp.digest());
+
+        let poseidon = transcript_params();
+        let back: PoseidonParams =
+            serde_json::from_str(&serde_json::to_string(&poseidon).unwrap()).unwrap();
+        assert_eq!(poseidon_params_digest(&back), poseidon_params_digest(&poseidon));
+    }
+
+    #[test]
+    fn for_security_sizes_schedule_and_queries() {
+        let pp = PublicParams::for_security(20, 128, HashProfile::Poseidon);
+        assert_eq!(pp.schedule, vec![16, 16, 16, 16, 2]);
+        assert_eq!(pp.log_inv_rate, SECURITY_LOG_INV_RATE);
+        assert_eq!(pp.grinding_bits, 16);
+        assert!(pp.merkle.iter().all(|c| c.layer_arities[0] <= 16));
+
+        // Queries plus grinding reach the target under the sizing model.
+        let eps = sizing::eps_eff_from_rate(SECURITY_LOG_INV_RATE);
+        let bits = sizing::bits_from_r(eps, pp.num_queries) + pp.grinding_bits as f64;
+        assert!(bits >= 128.0);
+        assert!(bits - sizing::bits_from_r(eps, 1) < 128.0);
+
+        let sha3 = PublicParams::for_security(20, 128, HashProfile::Sha3);
+        assert_eq!(sha3.schedule, vec![8, 8, 8, 8, 8, 4]);
+        assert!(sha3.num_queries < pp.num_queries);
+
+        let wide = PublicParams::for_security(20, 128, HashProfile::Conservative);
+        assert_eq!(wide.schedule, sha3.schedule);
+        assert!(wide.merkle.iter().all(|c| c.digest == DigestWidth::Digest256));
+        assert_ne!(wide.digest(), sha3.digest());
+    }
+
+    #[test]
+    fn validate_rejects_unusable_params() {
+        use crate::fri::{try_deep_fri_prove, FriDomain};
+
+        let params = pp().fri_params(1);
+        assert_eq!(params.validate(1 << 10), Ok(()));
+        assert_eq!(params.validate(1 << 5), Err(ParamError::ScheduleDoesNotDivide { layer: 2, fold: 4, size: 2 }));
+        assert_eq!(params.validate(24), Err(ParamError::DomainSize { n0: 24 }));
+
+        let with = |schedule: Vec<usize>, r| DeepFriParams { schedule, r, ..params.clone() };
+        assert_eq!(with(vec![], 32).validate(64), Err(ParamError::EmptySchedule));
+        assert_eq!(with(vec![4, 3], 32).validate(64), Err(ParamError::FoldNotPowerOfTwo { layer: 1, fold: 3 }));
+        assert_eq!(with(vec![1], 32).validate(64), Err(ParamError::FoldNotPowerOfTwo { layer: 0, fold: 1 }));
+        assert_eq!(with(vec![4], 0).validate(64), Err(ParamError::NoQueries));
+        let distinct = DeepFriParams { query_sampling: QuerySampling::Distinct, ..with(vec![4], 65) };
+        assert_eq!(distinct.validate(64), Err(ParamError::TooManyQueries { r: 65, n0: 64 }));
+        let grinding = DeepFriParams { grinding_bits: MAX_GRINDING_BITS + 1, ..with(vec![4], 8) };
+        assert_eq!(grinding.validate(64), Err(ParamError::GrindingBits { bits: 33, max: 32 }));
+        let grinding = DeepFriParams { grinding_bits: MAX_GRINDING_BITS, ..grinding };
+        assert_eq!(grinding.validate(64), Ok(()));
+
+        // The prover also checks the codeword against its domain.
+        let f0 = vec![F::from(1u64); 1 << 9];
+        let err = try_deep_fri_prove(f0.clone(), FriDomain::new_radix2(1 << 10), &params).err();
+        assert_eq!(err, Some(ParamError::CodewordLength { len: 1 << 9, n0: 1 << 10 }));
+        let skewed = FriDomain { omega: F::from(3u64), size: 1 << 9 };
+        let err = try_deep_fri_prove(f0, skewed, &with(vec![4, 4], 32)).err();
+        assert_eq!(err, Some(ParamError::DomainGenerator { n0: 1 << 9 }));
+    }
+
+    #[test]
+    fn normalize_schedule_folds_to_one_point() {
+        assert_eq!(normalize_schedule(1 << 12, &[16, 16]), Ok(vec![16, 16, 16]));
+        assert_eq!(normalize_schedule(1 << 8, &[16, 16]), Ok(vec![16, 16]));
+        assert_eq!(normalize_schedule(1 << 11, &[16, 16, 16]), Err(ParamError::ScheduleDoesNotDivide { layer: 2, fold: 16, size: 8 }));
+        assert_eq!(normalize_schedule(0, &[2]), Err(ParamError::DomainSize { n0: 0 }));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn for_security_params_prove_and_verify() {
+        use crate::fri::{deep_fri_prove, deep_fri_verify, FriDomain, VerifierKey};
+        use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let pp = PublicParams::for_security(12, 64, HashProfile::Poseidon);
+        let n0 = pp.n0;
+        let mut rng = StdRng::seed_from_u64(7);
+        let coeffs: Vec<F> = (0..n0 >> pp.log_inv_rate)
+            .map(|_| ark_ff::UniformRand::rand(&mut rng))
+            .collect();
+        let f0 = GeneralEvaluationDomain::<F>::new(n0).unwrap().fft(&coeffs);
+
+        let params = pp.fri_params(0xfeed);
+        let proof = deep_fri_prove(f0, FriDomain::new_radix2(n0), &params);
+        assert!(deep_fri_verify(&VerifierKey::new(&pp, 0xfeed).unwrap(), &proof));
+    }
+}
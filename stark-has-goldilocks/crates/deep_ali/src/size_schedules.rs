@@ -1,13 +1,11 @@
-use deep_ali::sizing::{eps_eff_from_lambda, r_for_bits_from_lambda};
+use deep_ali::sizing::{
+    estimate_eps_eff, lambda_for_schedule_with_eps, r_for_schedule, CorruptionModel,
+};
 
 #[derive(Clone)]
 struct Sched<'a> {
     name: &'a str,
     folds: &'a [usize],
-    // Lambda at r0=32, in bits. Fill these from your estimator/logs.
-    // For any schedule where λ_32 is unknown, you can set it to None
-    // and the CLI will skip or use a conservative placeholder.
-    lambda_bits_at_32: Option<f64>,
 }
 
 fn main() {
@@ -15,60 +13,65 @@ fn main() {
     let target_bits = 128.0;
     let r0 = 32usize;
 
-    // TODO: fill lambda_bits_at_32 from your measurements/estimator per schedule.
-    // Example: baseline "paper" schedule [16,16,8] has ε_eff=0.96 ⇒
-    // λ ≈ 32 * log2(1/(1-0.96)) ≈ 148.6.
+    // Tested-code rate and the bit size of the Fp3 folding challenges.
+    let rate = 1.0 / 8.0;
+    let field_bits = 3 * 64;
+
+    // Layer-0 corruption the table is calibrated at, and Monte-Carlo trials
+    // per schedule.
+    let model = CorruptionModel::Uniform { delta: 1e-3 };
+    let trials = 200;
+
     let schedules: Vec<Sched> = vec![
-        Sched { name: "paper",         folds: &[16, 16, 8],      lambda_bits_at_32: Some(148.6) },
-        Sched { name: "mod16",         folds: &[16, 16, 16, 16], lambda_bits_at_32: None },
-        Sched { name: "uni32x3",       folds: &[32, 32, 32],     lambda_bits_at_32: None },
-        Sched { name: "uni64x2x8",     folds: &[64, 64, 8],      lambda_bits_at_32: None },
-        Sched { name: "hi64_32_8",     folds: &[64, 32, 8],      lambda_bits_at_32: None },
-        Sched { name: "hi32_32_16",    folds: &[32, 32, 16],     lambda_bits_at_32: None },
-        Sched { name: "uni128",        folds: &[128],            lambda_bits_at_32: None },
-        Sched { name: "uni128x2",      folds: &[128, 128],       lambda_bits_at_32: None },
-        Sched { name: "hi128_64",      folds: &[128, 64],        lambda_bits_at_32: None },
-        Sched { name: "hi128_32",      folds: &[128, 32],        lambda_bits_at_32: None },
-        Sched { name: "hi128_16",      folds: &[128, 16],        lambda_bits_at_32: None },
-        Sched { name: "hi128_64_8",    folds: &[128, 64, 8],     lambda_bits_at_32: None },
-        Sched { name: "hi128_32_8",    folds: &[128, 32, 8],     lambda_bits_at_32: None },
+        Sched { name: "paper",         folds: &[16, 16, 8] },
+        Sched { name: "mod16",         folds: &[16, 16, 16, 16] },
+        Sched { name: "uni32x3",       folds: &[32, 32, 32] },
+        Sched { name: "uni64x2x8",     folds: &[64, 64, 8] },
+        Sched { name: "hi64_32_8",     folds: &[64, 32, 8] },
+        Sched { name: "hi32_32_16",    folds: &[32, 32, 16] },
+        Sched { name: "uni128",        folds: &[128] },
+        Sched { name: "uni128x2",      folds: &[128, 128] },
+        Sched { name: "hi128_64",      folds: &[128, 64] },
+        Sched { name: "hi128_32",      folds: &[128, 32] },
+        Sched { name: "hi128_16",      folds: &[128, 16] },
+        Sched { name: "hi128_64_8",    folds: &[128, 64, 8] },
+        Sched { name: "hi128_32_8",    folds: &[128, 32, 8] },
     ];
 
-    println!("Schedule sizing (target ≈ {:.0} bits). r0 = {}.\n", target_bits, r0);
-    println!("{:<14}  {:<20}  {:>10}  {:>10}  {:>8}", "name", "folds", "eps_eff", "r_128", "λ@32");
-    println!("{}", "-".repeat(70));
+    println!(
+        "Schedule sizing (target ≈ {:.0} bits). r0 = {}, ρ = {}, {:?}, {} trials.\n",
+        target_bits, r0, rate, model, trials
+    );
+    println!(
+        "{:<14}  {:<20}  {:>8}  {:>19}  {:>8}  {:>8}",
+        "name", "folds", "eps_eff", "95% CI", "r_128", "λ@32"
+    );
+    println!("{}", "-".repeat(86));
 
     for s in schedules {
-        match s.lambda_bits_at_32 {
-            Some(lambda_bits) => {
-                let eps = eps_eff_from_lambda(lambda_bits, r0);
-                let r128 = r_for_bits_from_lambda(lambda_bits, r0, target_bits);
-                println!(
-                    "{:<14}  {:<20}  {:>10.5}  {:>10}  {:>8.1}",
-                    s.name,
-                    fmt_folds(s.folds),
-                    eps,
-                    r128,
-                    lambda_bits
-                );
-            }
-            None => {
-                // If you don’t have λ@32 yet, print a placeholder line.
-                println!(
-                    "{:<14}  {:<20}  {:>10}  {:>10}  {:>8}",
-                    s.name,
-                    fmt_folds(s.folds),
-                    "—",
-                    "—",
-                    "—"
-                );
-            }
-        }
+        let est = estimate_eps_eff(s.folds, rate, model, trials);
+        let lambda_bits = lambda_for_schedule_with_eps(s.folds, rate, field_bits, r0, est.ci_low);
+        let r128 = match r_for_schedule(s.folds, rate, field_bits, target_bits) {
+            Some(_) => format!("{}", est.r_for_bits(target_bits)),
+            // Commit-phase error caps λ below the target at any r.
+            None => "—".to_string(),
+        };
+        println!(
+            "{:<14}  {:<20}  {:>8.5}  [{:>8.5}, {:>8.5}]  {:>8}  {:>8.1}",
+            s.name,
+            fmt_folds(s.folds),
+            est.eps_eff,
+            est.ci_low,
+            est.ci_high,
+            r128,
+            lambda_bits
+        );
     }
 
     println!("\nNotes:");
-    println!("- Fill lambda_bits_at_32 from your estimator/logs for each schedule.");
-    println!("- eps_eff = 1 - 2^(-λ/32). Minimal r for 128 bits: r_128 = ceil(128 / (λ/32)).");
+    println!("- eps_eff from sizing::estimate_eps_eff; r_128 and λ@32 use the lower");
+    println!("  end of its interval, λ capped by the commit-phase term.");
+    println!("- r_128 = ceil(128 / log2(1/(1 - eps_eff))).");
     println!("- Proof size and verify time scale ≈ linearly with r in this design.");
 }
 
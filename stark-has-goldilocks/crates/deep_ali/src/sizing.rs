@@ -1,5 +1,15 @@
 // src/sizing.rs
 
+use alloc::vec::Vec;
+use ark_ff::{PrimeField, Zero};
+use ark_goldilocks::Goldilocks as F;
+use rand::{rngs::StdRng, seq::index, Rng, SeedableRng};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::fri::{fri_fold_layer_with_mode, FoldingMode};
+
 /// Bits from r queries given eps_eff: λ = r * log2(1/(1 - eps_eff))
 #[inline]
 pub fn bits_from_r(eps_eff: f64, r: usize) -> f64 {
@@ -7,6 +17,44 @@ pub fn bits_from_r(eps_eff: f64, r: usize) -> f64 {
     (1.0 / pe).log2() * (r as f64)
 }
 
+/// As [`bits_from_r`] for r distinct queries into a domain of `n` points
+/// (`QuerySampling::Distinct`).
+///
+/// Drawing without replacement, all r queries miss the `eps_eff · n`
+/// detecting points with probability `Π_{k<r} (n_pass − k)/(n − k)`,
+/// `n_pass = (1 − eps_eff)·n`, which never exceeds `(1 − eps_eff)^r`; the
+/// gain matters once r is a noticeable fraction of n. Infinite when
+/// `r > n_pass`.
+pub fn bits_from_r_distinct(eps_eff: f64, r: usize, n: usize) -> f64 {
+    let n = n as f64;
+    let pass = (1.0 - eps_eff.clamp(1e-12, 1.0 - 1e-12)) * n;
+    (0..r)
+        .map(|k| {
+            let k = k as f64;
+            if pass - k <= 0.0 {
+                f64::INFINITY
+            } else {
+                ((n - k) / (pass - k)).log2()
+            }
+        })
+        .sum()
+}
+
+/// As [`bits_from_r`] for a FRI run of `num_layers` folds whose query indices
+/// are re-randomized per layer (`IndexChaining::Rerandomized`).
+///
+/// With strided chaining every layer is checked along one index path, so a
+/// word `eps_eff`-far must show up somewhere on it. Re-randomized checks
+/// land on unrelated positions per layer, and a cheater can spread the same
+/// inconsistency over the layers at `eps_eff / L` each; one query then
+/// passes with `(1 − eps_eff/L)^L ≥ 1 − eps_eff`, giving
+/// `λ = r · L · log2(1/(1 − eps_eff/L))`. Equal to [`bits_from_r`] at one
+/// layer and never above it.
+pub fn bits_from_r_rerandomized(eps_eff: f64, r: usize, num_layers: usize) -> f64 {
+    let l = num_layers.max(1);
+    bits_from_r(eps_eff / l as f64, r * l)
+}
+
 /// Minimal r to reach target bits with single-instance soundness.
 #[inline]
 pub fn r_for_bits(eps_eff: f64, bits: f64) -> usize {
@@ -35,4 +83,312 @@ pub fn r_for_bits_from_lambda(lambda_bits_at_r0: f64, r0: usize, bits: f64) -> u
 #[inline]
 pub fn r_for_bits_baseline(eps_eff_baseline: f64, bits: f64) -> usize {
     r_for_bits(eps_eff_baseline, bits)
-}
\ No newline at end of file
+}
+
+/// eps_eff at rate ρ = 2^(-log_inv_rate) in the Johnson-bound regime, where
+/// a query passes a far word with probability at most √ρ.
+#[inline]
+pub fn eps_eff_from_rate(log_inv_rate: u32) -> f64 {
+    1.0 - 2f64.powf(-(log_inv_rate as f64) / 2.0)
+}
+
+/// Analytic λ for a FRI run with `r` queries.
+///
+/// `rate` is ρ of the tested code, so the final layer has `1/ρ` entries
+/// and layer ℓ has `n_ℓ = (1/ρ)·Π_{j≥ℓ} m_j`. Two terms, each in bits:
+///
+/// * query phase: a word δ-far from the code stays δ-far through each fold
+///   except with the commit-phase probability below, and a query through
+///   the chain is caught with probability δ. Up to the Johnson bound
+///   δ = 1 − √ρ, so `r · log2(1/√ρ)`,
+/// * commit phase: folding layer ℓ with a challenge from a field of
+///   `field_bits` bits collapses the distance with probability at most
+///   `m_ℓ · n_ℓ / 2^field_bits`, summed over layers.
+///
+/// λ is the smaller of the two.
+pub fn lambda_for_schedule(schedule: &[usize], rate: f64, field_bits: u32, r: usize) -> f64 {
+    lambda_for_schedule_with_eps(schedule, rate, field_bits, r, 1.0 - rate.sqrt())
+}
+
+/// As [`lambda_for_schedule`] with a measured per-query `eps_eff` (e.g. the
+/// detection rate from the Monte-Carlo tests in `fri`) replacing the
+/// Johnson-bound one.
+pub fn lambda_for_schedule_with_eps(
+    schedule: &[usize],
+    rate: f64,
+    field_bits: u32,
+    r: usize,
+    eps_eff: f64,
+) -> f64 {
+    bits_from_r(eps_eff, r).min(commit_phase_bits(schedule, rate, field_bits))
+}
+
+fn commit_phase_bits(schedule: &[usize], rate: f64, field_bits: u32) -> f64 {
+    let mut n = (1.0 / rate).round().max(1.0);
+    let mut err = 0.0;
+    for &m in schedule.iter().rev() {
+        n *= m as f64;
+        err += m as f64 * n;
+    }
+    field_bits as f64 - err.log2()
+}
+
+/// Minimal r with `lambda_for_schedule(..) ≥ bits`, or `None` when the
+/// commit-phase term caps λ below the target.
+pub fn r_for_schedule(schedule: &[usize], rate: f64, field_bits: u32, bits: f64) -> Option<usize> {
+    (commit_phase_bits(schedule, rate, field_bits) >= bits).then(|| r_for_bits(1.0 - rate.sqrt(), bits))
+}
+
+/// [`lambda_for_schedule`] with `grinding_bits` of proof-of-work on the
+/// query seed. Every re-roll of the query set costs the prover
+/// `2^grinding_bits` hashes, so the bits add to the query phase only; the
+/// commit-phase bound is unchanged.
+pub fn lambda_for_schedule_with_grinding(
+    schedule: &[usize],
+    rate: f64,
+    field_bits: u32,
+    r: usize,
+    grinding_bits: u32,
+) -> f64 {
+    (bits_from_r(1.0 - rate.sqrt(), r) + grinding_bits as f64)
+        .min(commit_phase_bits(schedule, rate, field_bits))
+}
+
+/// Minimal r with `lambda_for_schedule_with_grinding(..) ≥ bits`, or `None`
+/// when the commit-phase term caps λ below the target.
+pub fn r_for_schedule_with_grinding(
+    schedule: &[usize],
+    rate: f64,
+    field_bits: u32,
+    bits: f64,
+    grinding_bits: u32,
+) -> Option<usize> {
+    (commit_phase_bits(schedule, rate, field_bits) >= bits)
+        .then(|| r_for_bits(1.0 - rate.sqrt(), (bits - grinding_bits as f64).max(0.0)))
+}
+
+/// Layer-0 corruption fed to [`estimate_eps_eff`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CorruptionModel {
+    /// `⌈δ·n₀⌉` positions drawn uniformly without replacement.
+    Uniform { delta: f64 },
+    /// `⌈δ·n₁⌉` whole first-layer folding cosets, the same mass packed so
+    /// that the first fold does not amplify it.
+    Cosets { delta: f64 },
+}
+
+/// Monte-Carlo ε_eff with a 95% confidence interval.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EpsEstimate {
+    pub eps_eff: f64,
+    pub ci_low: f64,
+    pub ci_high: f64,
+    pub trials: usize,
+}
+
+impl EpsEstimate {
+    /// [`r_for_bits`] at the lower end of the interval.
+    pub fn r_for_bits(&self, bits: f64) -> usize {
+        r_for_bits(self.ci_low, bits)
+    }
+}
+
+/// Seed of trial 0; trial t uses `EPS_SEED + t`, so estimates are
+/// reproducible and independent of the thread count.
+const EPS_SEED: u64 = 0x3e5f_e77e;
+
+/// Estimate ε_eff for `schedule` at rate `rate` by simulating a prover that
+/// corrupts layer 0 and then folds faithfully.
+///
+/// Each trial draws a corruption pattern and fold challenges, folds the
+/// error word with the production coset-interpolation fold (folding is
+/// linear, so the honest codeword drops out) and records the fraction of
+/// final-layer positions where it survives: the chance a query reaches an
+/// inconsistency with the honest final layer. Trials run in parallel with
+/// the `parallel` feature. The interval is the normal one over trials,
+/// clamped to `[0, 1]` and kept at least `3/trials` away from either end
+/// (the 95% rule of three), so unanimous trials do not claim certainty.
+pub fn estimate_eps_eff(
+    schedule: &[usize],
+    rate: f64,
+    model: CorruptionModel,
+    trials: usize,
+) -> EpsEstimate {
+    assert!(trials > 0, "need at least one trial");
+    let n_final = (1.0 / rate).round() as usize;
+    let n0 = schedule.iter().product::<usize>() * n_final;
+    assert!(
+        n_final.is_power_of_two() && n0.is_power_of_two(),
+        "schedule and rate must give radix-2 layers"
+    );
+
+    let trial = |t: usize| {
+        let mut rng = StdRng::seed_from_u64(EPS_SEED + t as u64);
+        let mut word = corrupt(n0, schedule[0], model, &mut rng);
+        for &m in schedule {
+            let alpha = F::from(rng.gen::<u64>());
+            word = fri_fold_layer_with_mode(&word, alpha, m, FoldingMode::CosetInterpolation);
+        }
+        word.iter().filter(|v| !v.is_zero()).count() as f64 / n_final as f64
+    };
+
+    #[cfg(feature = "parallel")]
+    let detected: Vec<f64> = (0..trials).into_par_iter().map(trial).collect();
+    #[cfg(not(feature = "parallel"))]
+    let detected: Vec<f64> = (0..trials).map(trial).collect();
+
+    let t = trials as f64;
+    let mean = detected.iter().sum::<f64>() / t;
+    let var = if trials > 1 {
+        detected.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / (t - 1.0)
+    } else {
+        0.0
+    };
+    let half = 1.96 * (var / t).sqrt();
+    EpsEstimate {
+        eps_eff: mean,
+        ci_low: (mean - half).min(1.0 - 3.0 / t).max(0.0),
+        ci_high: (mean + half).max(3.0 / t).min(1.0),
+        trials,
+    }
+}
+
+/// Error word over `H_{n0}`: random nonzero values on the corrupted
+/// positions, zero elsewhere.
+fn corrupt(n0: usize, m0: usize, model: CorruptionModel, rng: &mut StdRng) -> Vec<F> {
+    let p = F::MODULUS.0[0];
+    let mut word = alloc::vec![F::zero(); n0];
+    let hit = |word: &mut [F], i: usize, rng: &mut StdRng| {
+        word[i] = F::from(rng.gen_range(1..p));
+    };
+    match model {
+        CorruptionModel::Uniform { delta } => {
+            let k = ((delta * n0 as f64).ceil() as usize).min(n0);
+            for i in index::sample(rng, n0, k) {
+                hit(&mut word, i, rng);
+            }
+        }
+        CorruptionModel::Cosets { delta } => {
+            let n1 = n0 / m0;
+            let k = ((delta * n1 as f64).ceil() as usize).min(n1);
+            for b in index::sample(rng, n1, k) {
+                for j in 0..m0 {
+                    hit(&mut word, b + j * n1, rng);
+                }
+            }
+        }
+    }
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lambda_is_query_bound_then_commit_bound() {
+        let paper = [16, 16, 8];
+        let rate = 1.0 / 8.0;
+
+        // log2(1/√ρ) = 1.5 bits per query at ρ = 1/8.
+        let l32 = lambda_for_schedule(&paper, rate, 192, 32);
+        assert!((l32 - 48.0).abs() < 1e-9);
+        assert!(lambda_for_schedule(&paper, rate, 192, 64) > l32);
+
+        // A 64-bit challenge field caps λ well below what the queries buy.
+        let capped = lambda_for_schedule(&paper, rate, 64, 1000);
+        assert!(capped < 64.0 - 15.0);
+        assert_eq!(r_for_schedule(&paper, rate, 64, 128.0), None);
+
+        let r = r_for_schedule(&paper, rate, 192, 128.0).unwrap();
+        assert!(lambda_for_schedule(&paper, rate, 192, r) >= 128.0);
+        assert!(lambda_for_schedule(&paper, rate, 192, r - 1) < 128.0);
+    }
+
+    #[test]
+    fn grinding_trades_against_queries() {
+        let paper = [16, 16, 8];
+        let rate = 1.0 / 8.0;
+        let r0 = r_for_schedule(&paper, rate, 192, 128.0).unwrap();
+        let r20 = r_for_schedule_with_grinding(&paper, rate, 192, 128.0, 20).unwrap();
+        // 1.5 bits per query: ⌈128/1.5⌉ = 86 against ⌈108/1.5⌉ = 72.
+        assert_eq!((r0, r20), (86, 72));
+        assert!(lambda_for_schedule_with_grinding(&paper, rate, 192, r20, 20) >= 128.0);
+        assert!(lambda_for_schedule_with_grinding(&paper, rate, 192, r20 - 1, 20) < 128.0);
+
+        // Grinding does not lift the commit-phase cap.
+        assert_eq!(r_for_schedule_with_grinding(&paper, rate, 64, 128.0, 24), None);
+        assert_eq!(
+            lambda_for_schedule_with_grinding(&paper, rate, 64, 1000, 24),
+            lambda_for_schedule(&paper, rate, 64, 1000)
+        );
+    }
+
+    #[test]
+    fn distinct_queries_never_lose_bits() {
+        let eps = eps_eff_from_rate(3);
+        for (r, n) in [(32, 1 << 20), (64, 1 << 10), (100, 256)] {
+            let d = bits_from_r_distinct(eps, r, n);
+            assert!(d >= bits_from_r(eps, r));
+        }
+        // Large domains reduce to the with-replacement figure.
+        let gap = bits_from_r_distinct(eps, 32, 1 << 30) - bits_from_r(eps, 32);
+        assert!(gap < 1e-3);
+        // More queries than passing points: every cheat is caught.
+        assert_eq!(bits_from_r_distinct(0.5, 9, 16), f64::INFINITY);
+    }
+
+    #[test]
+    fn rerandomized_chaining_costs_bits() {
+        let eps = eps_eff_from_rate(3);
+        let strided = bits_from_r(eps, 64);
+        assert!((bits_from_r_rerandomized(eps, 64, 1) - strided).abs() < 1e-9);
+        let mut prev = strided;
+        for l in [2, 3, 5, 8] {
+            let b = bits_from_r_rerandomized(eps, 64, l);
+            assert!(b < prev);
+            prev = b;
+        }
+        // Many layers approach r · eps · log2(e).
+        let limit = 64.0 * eps * core::f64::consts::LOG2_E;
+        assert!((bits_from_r_rerandomized(eps, 64, 1 << 20) - limit).abs() < 1e-3);
+    }
+
+    #[test]
+    fn estimated_eps_tracks_fold_amplification() {
+        let delta = 0.05;
+        let est = estimate_eps_eff(&[4, 4], 1.0 / 64.0, CorruptionModel::Uniform { delta }, 200);
+        // ρ_{ℓ+1} = 1 − (1 − ρ_ℓ)^m per fold.
+        let rho1 = 1.0 - (1.0f64 - delta).powi(4);
+        let rho2 = 1.0 - (1.0f64 - rho1).powi(4);
+        assert!(est.ci_low <= est.eps_eff && est.eps_eff <= est.ci_high);
+        assert!((est.eps_eff - rho2).abs() < 0.03, "{est:?} against {rho2}");
+        assert!(est.ci_high - est.ci_low < 0.03);
+
+        // Packing the same mass into cosets skips the first amplification.
+        let packed = estimate_eps_eff(&[4, 4], 1.0 / 64.0, CorruptionModel::Cosets { delta }, 200);
+        assert!(packed.eps_eff < est.eps_eff);
+        assert!((packed.eps_eff - rho1).abs() < 0.03, "{packed:?} against {rho1}");
+
+        // Every trial caught: the interval still leaves 3/trials of doubt.
+        let sure = estimate_eps_eff(&[4, 4], 1.0 / 8.0, CorruptionModel::Uniform { delta: 0.5 }, 100);
+        assert_eq!((sure.eps_eff, sure.ci_high), (1.0, 1.0));
+        assert!((sure.ci_low - 0.97).abs() < 1e-12);
+
+        // Sizing from the lower end never asks for fewer queries.
+        assert!(est.r_for_bits(128.0) >= r_for_bits(est.eps_eff, 128.0));
+        assert_eq!(
+            estimate_eps_eff(&[4, 4], 1.0 / 8.0, CorruptionModel::Uniform { delta }, 64),
+            estimate_eps_eff(&[4, 4], 1.0 / 8.0, CorruptionModel::Uniform { delta }, 64),
+        );
+    }
+
+    #[test]
+    fn calibrated_eps_matches_lambda_path() {
+        // The paper's measured ε_eff = 0.96 reproduces λ@32 ≈ 148.6.
+        let l = lambda_for_schedule_with_eps(&[16, 16, 8], 1.0 / 8.0, 192, 32, 0.96);
+        assert!((l - 148.6).abs() < 0.1);
+        let eps = eps_eff_from_lambda(l, 32);
+        assert!((eps - 0.96).abs() < 1e-9);
+    }
+}
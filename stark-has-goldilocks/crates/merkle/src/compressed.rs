@@ -0,0 +1,209 @@
+//! Per-query openings with shared siblings sent once.
+//!
+//! Query phases open many leaves of one tree and ship a full
+//! [`MerkleOpening`] for each, although paths that meet carry the same
+//! siblings from there up. [`CompressedOpenings`] keeps every distinct
+//! sibling once and each opening as indices into that pool, the layout of
+//! the Pallas FRI's layer proofs. Unlike a
+//! [`MerkleMultiOpening`](crate::MerkleMultiOpening) it still expands to
+//! the individual openings, so a verifier that checks them one query at a
+//! time can take it as is.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use crate::{MerkleChannelCfg, MerkleNode, MerkleOpening, MerkleTreeChannel, F};
+
+/// Openings of one tree sharing a pool of sibling nodes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct CompressedOpenings<N = F> {
+    /// Siblings per level of every path, from the leaf level up.
+    pub widths: Vec<u32>,
+    /// Distinct siblings, in order of first use.
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub nodes: Vec<N>,
+    pub indices: Vec<u64>,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub leaves: Vec<N>,
+    /// Per opening, its siblings level by level as positions in `nodes`.
+    pub paths: Vec<Vec<u32>>,
+}
+
+impl<N: MerkleNode> CompressedOpenings<N> {
+    /// Pool the siblings of `openings`, or `None` unless their paths all
+    /// have the shape of the first.
+    pub fn compress(openings: &[MerkleOpening<N>]) -> Option<Self> {
+        let widths: Vec<u32> = match openings.first() {
+            Some(o) => o.path.iter().map(|s| s.len() as u32).collect(),
+            None => Vec::new(),
+        };
+        let mut pool: BTreeMap<Vec<u8>, u32> = BTreeMap::new();
+        let mut nodes = Vec::new();
+        let paths = openings
+            .iter()
+            .map(|o| {
+                if !o.path.iter().map(|s| s.len() as u32).eq(widths.iter().copied()) {
+                    return None;
+                }
+                let refs = o
+                    .path
+                    .iter()
+                    .flatten()
+                    .map(|x| {
+                        *pool.entry(x.to_bytes().as_ref().to_vec()).or_insert_with(|| {
+                            nodes.push(*x);
+                            nodes.len() as u32 - 1
+                        })
+                    })
+                    .collect();
+                Some(refs)
+            })
+            .collect::<Option<_>>()?;
+        Some(Self {
+            widths,
+            nodes,
+            indices: openings.iter().map(|o| o.index).collect(),
+            leaves: openings.iter().map(|o| o.leaf).collect(),
+            paths,
+        })
+    }
+
+    /// The openings back, in order, or `None` if a path does not fit
+    /// `widths` or points outside `nodes`.
+    pub fn expand(&self) -> Option<Vec<MerkleOpening<N>>> {
+        if self.indices.len() != self.leaves.len() || self.paths.len() != self.indices.len() {
+            return None;
+        }
+        let per_path: usize = self.widths.iter().map(|&w| w as usize).sum();
+        self.paths
+            .iter()
+            .zip(self.indices.iter().zip(&self.leaves))
+            .map(|(refs, (&index, &leaf))| {
+                if refs.len() != per_path {
+                    return None;
+                }
+                let mut refs = refs.iter();
+                let path = self
+                    .widths
+                    .iter()
+                    .map(|&w| {
+                        refs.by_ref()
+                            .take(w as usize)
+                            .map(|&k| self.nodes.get(k as usize).copied())
+                            .collect::<Option<Vec<N>>>()
+                    })
+                    .collect::<Option<_>>()?;
+                Some(MerkleOpening { leaf, path, index })
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+impl<N: MerkleNode> MerkleTreeChannel<N> {
+    /// Pooled [`open_many`](Self::open_many).
+    pub fn open_compressed(&self, indices: &[usize]) -> Result<CompressedOpenings<N>, crate::MerkleError> {
+        let openings = self.open_many(indices)?;
+        Ok(CompressedOpenings::compress(&openings).expect("paths of one tree share a shape"))
+    }
+
+    /// [`verify_openings`](Self::verify_openings) of the expanded openings.
+    pub fn verify_compressed(
+        cfg: &MerkleChannelCfg,
+        root: N,
+        openings: &CompressedOpenings<N>,
+        trace_hash: &[u8; 32],
+    ) -> bool {
+        openings
+            .expand()
+            .is_some_and(|o| Self::verify_openings(cfg, root, &o, trace_hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Digest256, DigestWidth};
+
+    fn tree<N: MerkleNode>(cfg: &MerkleChannelCfg, n: u64) -> (MerkleTreeChannel<N>, N) {
+        let mut tree = MerkleTreeChannel::<N>::new(cfg.clone(), [0u8; 32]);
+        for i in 0..n {
+            tree.push_leaf(&[F::from(i), F::from(3 * i + 1)]);
+        }
+        let root = tree.finalize().unwrap();
+        (tree, root)
+    }
+
+    fn roundtrip<N: MerkleNode>(cfg: &MerkleChannelCfg) {
+        let (tree, root) = tree::<N>(cfg, 64);
+        let indices = [5, 6, 40, 5, 63, 0, 7];
+        let openings = tree.open_many(&indices).unwrap();
+        let c = tree.open_compressed(&indices).unwrap();
+        assert_eq!(c.len(), indices.len());
+        assert!(MerkleTreeChannel::verify_compressed(cfg, root, &c, &[0u8; 32]));
+
+        let back = c.expand().unwrap();
+        for (a, b) in back.iter().zip(&openings) {
+            assert_eq!((a.index, a.leaf, &a.path), (b.index, b.leaf, &b.path));
+        }
+        // Shared siblings, here all of the repeated 5's, are pooled.
+        let sent: usize = openings.iter().map(|o| o.path.iter().map(Vec::len).sum::<usize>()).sum();
+        assert!(c.nodes.len() < sent, "{} of {sent} siblings", c.nodes.len());
+    }
+
+    #[test]
+    fn compressed_openings_expand_and_verify() {
+        roundtrip::<F>(&MerkleChannelCfg::for_leaves(64, 4, 0));
+        roundtrip::<Digest256>(&MerkleChannelCfg::for_leaves(64, 2, 0).with_digest(DigestWidth::Digest256));
+    }
+
+    #[test]
+    fn differently_shaped_paths_do_not_compress() {
+        let cfg = MerkleChannelCfg::for_leaves(64, 4, 0);
+        let (tree, _) = tree::<F>(&cfg, 64);
+        let mut openings = tree.open_many(&[1, 2]).unwrap();
+        openings[1].path.pop();
+        assert!(CompressedOpenings::compress(&openings).is_none());
+        assert!(CompressedOpenings::<F>::compress(&[]).is_some_and(|c| c.is_empty()));
+    }
+
+    #[test]
+    fn tampered_compressed_openings_fail() {
+        let cfg = MerkleChannelCfg::for_leaves(64, 4, 0);
+        let (tree, root) = tree::<F>(&cfg, 64);
+        let c = tree.open_compressed(&[1, 2, 33]).unwrap();
+        let th = [0u8; 32];
+
+        let mut bad = c.clone();
+        bad.nodes[0] += F::from(1u64);
+        assert!(!MerkleTreeChannel::verify_compressed(&cfg, root, &bad, &th));
+
+        let mut bad = c.clone();
+        bad.indices[2] = 34;
+        assert!(!MerkleTreeChannel::verify_compressed(&cfg, root, &bad, &th));
+
+        let mut bad = c.clone();
+        bad.paths[1][0] = c.nodes.len() as u32;
+        assert!(bad.expand().is_none());
+
+        let mut bad = c.clone();
+        bad.paths[0].pop();
+        assert!(bad.expand().is_none());
+
+        let mut bad = c.clone();
+        bad.widths[0] += 1;
+        assert!(bad.expand().is_none());
+
+        let mut bad = c;
+        bad.leaves.pop();
+        assert!(!MerkleTreeChannel::verify_compressed(&cfg, root, &bad, &th));
+    }
+}
@@ -0,0 +1,310 @@
+//! Trees whose lower levels live on disk.
+//!
+//! From around 2^25 leaves the levels of a FRI base layer no longer fit
+//! in memory next to the codeword. [`MmapMerkleTree`] writes the leaf
+//! digests, and every level with more than
+//! [`resident_nodes`](MmapMerkleTree::with_resident_nodes) nodes, to a
+//! file, maps it read-only once built, and reads paths from the map on
+//! [`open`](MmapMerkleTree::open). Only the small top levels are held as
+//! vectors. Roots and openings are those of a [`MerkleTreeChannel`] over
+//! the same leaves.
+
+use alloc::vec::Vec;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::{leaf_salt, DsLabel, MerkleChannelCfg, MerkleError, MerkleNode, MerkleOpening, MerkleTreeChannel, F};
+
+/// Default for [`MmapMerkleTree::with_resident_nodes`]: levels of up to
+/// 2^16 nodes are kept in memory.
+pub const DEFAULT_RESIDENT_NODES: usize = 1 << 16;
+
+pub struct MmapMerkleTree<N = F> {
+    cfg: MerkleChannelCfg,
+    salt_seed: Option<[u8; 32]>,
+    resident_nodes: usize,
+    out: BufWriter<File>,
+    file: File,
+    leaves: u64,
+    /// Byte offset of each level written to the file, from the leaves up.
+    disk_levels: Vec<u64>,
+    /// Levels above the file's, up to the root.
+    resident: Vec<Vec<N>>,
+    /// Length of every level.
+    sizes: Vec<usize>,
+    map: Option<Mmap>,
+}
+
+impl<N: MerkleNode> MmapMerkleTree<N> {
+    /// A tree backed by the file at `path`, created or truncated. Panics
+    /// as [`MerkleTreeChannel::new`] does.
+    pub fn create(cfg: MerkleChannelCfg, path: impl AsRef<Path>) -> io::Result<Self> {
+        if let Some(e) = cfg.node_error::<N>() {
+            panic!("{e}");
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(Self {
+            cfg,
+            salt_seed: None,
+            resident_nodes: DEFAULT_RESIDENT_NODES,
+            out: BufWriter::new(file.try_clone()?),
+            file,
+            leaves: 0,
+            disk_levels: Vec::new(),
+            resident: Vec::new(),
+            sizes: Vec::new(),
+            map: None,
+        })
+    }
+
+    /// As [`MerkleTreeChannel::with_salt_seed`].
+    pub fn with_salt_seed(self, seed: [u8; 32]) -> Self {
+        assert!(self.cfg.hiding, "salt seed for a tree without hiding");
+        Self {
+            salt_seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Keep levels of at most `nodes` nodes in memory rather than on disk.
+    pub fn with_resident_nodes(self, nodes: usize) -> Self {
+        Self {
+            resident_nodes: nodes,
+            ..self
+        }
+    }
+
+    /// Write the digest of `values` as the next leaf. Panics after
+    /// [`finalize`](Self::finalize).
+    pub fn push_leaf(&mut self, values: &[F]) -> io::Result<()> {
+        assert!(self.sizes.is_empty(), "push_leaf on a finalized disk-backed tree");
+        let index = self.leaves;
+        let leaf = match self.cfg.hiding {
+            false => MerkleTreeChannel::leaf_digest(&self.cfg, index, values),
+            true => {
+                let seed = self.salt_seed.as_ref().expect("hiding tree without a salt seed");
+                MerkleTreeChannel::salted_leaf_digest(&self.cfg, index, values, leaf_salt(seed, index))
+            }
+        };
+        self.write(leaf)?;
+        self.leaves += 1;
+        Ok(())
+    }
+
+    /// Leaves pushed so far.
+    pub fn leaves(&self) -> u64 {
+        self.leaves
+    }
+
+    fn write(&mut self, node: N) -> io::Result<()> {
+        self.out.write_all(node.to_bytes().as_ref())
+    }
+
+    /// Build the levels above the leaves and return the root, as
+    /// [`MerkleTreeChannel::finalize`]. Config mismatches come back as
+    /// [`io::ErrorKind::InvalidInput`] wrapping the [`MerkleError`].
+    pub fn finalize(&mut self) -> io::Result<N> {
+        assert!(self.sizes.is_empty(), "disk-backed tree finalized twice");
+        let n = self.leaves;
+        let invalid = |e: MerkleError| io::Error::new(io::ErrorKind::InvalidInput, e);
+        if n == 0 {
+            return Err(invalid(MerkleError::NotFinalized));
+        }
+        if let Some(expected) = self.cfg.leaf_count.filter(|&c| c != n) {
+            return Err(invalid(MerkleError::LeafCountMismatch { expected, got: n }));
+        }
+        if !self.cfg.covers(n - 1) {
+            return Err(invalid(self.cfg.index_error(n - 1)));
+        }
+
+        let mut sizes = alloc::vec![n as usize];
+        let min_top = self.cfg.min_top_level();
+        while sizes[sizes.len() - 1] > 1 || sizes.len() - 1 < min_top {
+            let level = sizes.len() - 1;
+            sizes.push(sizes[level].div_ceil(self.cfg.layer_arities[level]));
+        }
+
+        // Levels shrink, so those too large to keep are the lowest ones.
+        let disk = 1 + sizes[1..].iter().take_while(|&&s| s > self.resident_nodes).count();
+        let width = N::WIDTH.bytes() as u64;
+        self.disk_levels.push(0);
+        for level in 0..sizes.len() - 1 {
+            if level < disk {
+                self.out.flush()?;
+                // SAFETY: the file is this tree's own, and the mapped
+                // levels are never written again.
+                self.map = Some(unsafe { Mmap::map(&self.file)? });
+            }
+            let parents_on_disk = level + 1 < disk;
+            if parents_on_disk {
+                self.disk_levels.push(self.disk_levels[level] + sizes[level] as u64 * width);
+            }
+
+            let arity = self.cfg.layer_arities[level];
+            let pad = self.cfg.pad_node(level, &[self.node(level, sizes[level] - 1)]);
+            let mut parents = Vec::new();
+            let mut group = Vec::with_capacity(arity);
+            for k in 0..sizes[level + 1] {
+                group.clear();
+                group.extend((k * arity..(k + 1) * arity).map(|i| match i < sizes[level] {
+                    true => self.node(level, i),
+                    false => pad,
+                }));
+                let ds = DsLabel {
+                    arity,
+                    level: level as u32 + 1,
+                    position: k as u64,
+                    tree_label: self.cfg.tree_label,
+                };
+                let parent = self.cfg.compress(ds, &group);
+                match parents_on_disk {
+                    true => self.write(parent)?,
+                    false => parents.push(parent),
+                }
+            }
+            if !parents_on_disk {
+                self.resident.push(parents);
+            }
+        }
+        self.out.flush()?;
+        // SAFETY: as above; the file is complete and no longer written.
+        self.map = Some(unsafe { Mmap::map(&self.file)? });
+        let root = self.node(sizes.len() - 1, 0);
+        self.sizes = sizes;
+        Ok(self.cfg.seal(root))
+    }
+
+    /// Node `i` of `level`: levels below `disk_levels.len()` are in the
+    /// file, the rest in `resident`.
+    fn node(&self, level: usize, i: usize) -> N {
+        match level.checked_sub(self.disk_levels.len()) {
+            Some(r) => self.resident[r][i],
+            None => {
+                let width = N::WIDTH.bytes();
+                let start = self.disk_levels[level] as usize + i * width;
+                let map = self.map.as_ref().expect("level read before it was mapped");
+                N::from_bytes(&map[start..start + width]).expect("node written by this tree")
+            }
+        }
+    }
+
+    /// As [`MerkleTreeChannel::open`], reading the lower levels from the
+    /// file.
+    pub fn open(&self, index: usize) -> Result<MerkleOpening<N>, MerkleError> {
+        if self.sizes.is_empty() {
+            return Err(MerkleError::NotFinalized);
+        }
+        if index >= self.sizes[0] {
+            return Err(MerkleError::IndexOutOfRange {
+                index: index as u64,
+                len: self.sizes[0] as u64,
+            });
+        }
+        let top = match self.cfg.cap_height {
+            0 => self.sizes.len() - 1,
+            _ => self.cfg.cap_level(),
+        };
+        let mut idx = index;
+        let mut path = Vec::with_capacity(top);
+        for level in 0..top {
+            let arity = self.cfg.layer_arities[level];
+            let len = self.sizes[level];
+            let pad = self.cfg.pad_node(level, &[self.node(level, len - 1)]);
+            let start = idx / arity * arity;
+            path.push(
+                (start..start + arity)
+                    .filter(|&i| i != idx)
+                    .map(|i| if i < len { self.node(level, i) } else { pad })
+                    .collect(),
+            );
+            idx /= arity;
+        }
+        Ok(MerkleOpening {
+            leaf: self.node(0, index),
+            path,
+            index: index as u64,
+        })
+    }
+
+    /// Levels kept in memory rather than on disk.
+    pub fn resident_levels(&self) -> usize {
+        self.resident.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Digest256, DigestWidth, Padding};
+
+    struct TempPath(std::path::PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("merkle-disk-{}-{name}", std::process::id())))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn both<N: MerkleNode>(cfg: &MerkleChannelCfg, n: u64, resident: usize) {
+        let path = TempPath::new(&format!("{n}-{resident}-{}", cfg.layer_arities.len()));
+        let mut tree = MerkleTreeChannel::<N>::new(cfg.clone(), [0u8; 32]);
+        let mut disk = MmapMerkleTree::<N>::create(cfg.clone(), &path.0)
+            .unwrap()
+            .with_resident_nodes(resident);
+        for i in 0..n {
+            let values = [F::from(i), F::from(5 * i + 2)];
+            tree.push_leaf(&values);
+            disk.push_leaf(&values).unwrap();
+        }
+        let root = tree.finalize().unwrap();
+        assert_eq!(disk.finalize().unwrap(), root, "{n} leaves, resident {resident}");
+        for i in (0..n as usize).step_by(7).chain([n as usize - 1]) {
+            let (a, b) = (tree.open(i).unwrap(), disk.open(i).unwrap());
+            assert_eq!((a.index, a.leaf, &a.path), (b.index, b.leaf, &b.path));
+        }
+        assert_eq!(disk.open(n as usize).unwrap_err(), tree.open(n as usize).unwrap_err());
+    }
+
+    #[test]
+    fn disk_trees_match_in_memory_trees() {
+        for n in [1, 2, 9, 64, 100] {
+            for resident in [0, 4, 1 << 16] {
+                both::<F>(&MerkleChannelCfg::new(vec![2; 7], 3), n, resident);
+                both::<F>(&MerkleChannelCfg::new(vec![4, 3, 2, 2, 2, 2], 3).with_padding(Padding::FixedDigest), n, resident);
+                both::<Digest256>(&MerkleChannelCfg::new(vec![8, 4, 4], 3).with_digest(DigestWidth::Digest256), n, resident);
+            }
+        }
+        let capped = MerkleChannelCfg::builder(50).level(4).fill_with(2).cap_height(2).build().unwrap();
+        both::<F>(&capped, 50, 2);
+    }
+
+    #[test]
+    fn only_small_levels_stay_in_memory() {
+        let path = TempPath::new("resident");
+        let cfg = MerkleChannelCfg::for_leaves(1 << 10, 2, 0);
+        let mut disk = MmapMerkleTree::<F>::create(cfg, &path.0).unwrap().with_resident_nodes(16);
+        assert!(disk.open(0).is_err());
+        for i in 0..1u64 << 10 {
+            disk.push_leaf(&[F::from(i)]).unwrap();
+        }
+        disk.finalize().unwrap();
+        // 16, 8, 4, 2 and 1 nodes.
+        assert_eq!(disk.resident_levels(), 5);
+        let bytes = std::fs::metadata(&path.0).unwrap().len();
+        assert_eq!(bytes, 8 * (1024 + 512 + 256 + 128 + 64 + 32));
+    }
+}
@@ -0,0 +1,192 @@
+//! Compact binary layout of openings, for proofs written to disk or sent
+//! over the wire without a serde format.
+//!
+//! Everything is little-endian and fixed-width: indices as 8 bytes,
+//! lengths as 4, nodes as their [`MerkleNode::to_bytes`] and field values
+//! as 8 canonical bytes. Nothing describes the node width, so a reader must
+//! decode with the tree's node type. In order:
+//!
+//! - [`MerkleOpening`]: index, leaf, level count, then per level its
+//!   sibling count and siblings;
+//! - [`MerkleValueOpening`]: value count, values, a salt flag byte (0 or
+//!   1) and the salt if set, then the opening;
+//! - [`MerkleMultiOpening`]: index count, indices, one leaf per index,
+//!   then the path as for a single opening.
+//!
+//! Decoding rejects non-canonical nodes and values and trailing bytes, so
+//! every opening has exactly one encoding.
+
+use alloc::vec::Vec;
+use ark_ff::PrimeField;
+
+use crate::{MerkleMultiOpening, MerkleNode, MerkleOpening, MerkleValueOpening, F};
+
+/// Bytes that are not an encoded opening.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Input ends inside the opening.
+    Truncated,
+    /// Node, field value or salt flag outside its canonical range.
+    NonCanonical,
+    /// Input continues after the opening.
+    TrailingBytes,
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DecodeError::Truncated => write!(f, "Merkle opening ends early"),
+            DecodeError::NonCanonical => write!(f, "non-canonical node or value in Merkle opening"),
+            DecodeError::TrailingBytes => write!(f, "bytes after the end of a Merkle opening"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+impl<N: MerkleNode> MerkleOpening<N> {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&self.index.to_le_bytes());
+        out.extend_from_slice(self.leaf.to_bytes().as_ref());
+        put_path(&mut out, &self.path);
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut r = Reader(bytes);
+        let opening = r.opening()?;
+        r.finish(opening)
+    }
+}
+
+impl<N: MerkleNode> MerkleValueOpening<N> {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        put_len(&mut out, self.values.len());
+        for x in &self.values {
+            out.extend_from_slice(&x.into_bigint().0[0].to_le_bytes());
+        }
+        match self.salt {
+            None => out.push(0),
+            Some(salt) => {
+                out.push(1);
+                out.extend_from_slice(&salt.into_bigint().0[0].to_le_bytes());
+            }
+        }
+        out.extend(self.opening.encode());
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut r = Reader(bytes);
+        let n = r.count()?;
+        let values = (0..n).map(|_| r.field()).collect::<Result<_, _>>()?;
+        let salt = match r.take(1)?[0] {
+            0 => None,
+            1 => Some(r.field()?),
+            _ => return Err(DecodeError::NonCanonical),
+        };
+        let opening = r.opening()?;
+        r.finish(MerkleValueOpening { values, salt, opening })
+    }
+}
+
+impl<N: MerkleNode> MerkleMultiOpening<N> {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        put_len(&mut out, self.indices.len());
+        for i in &self.indices {
+            out.extend_from_slice(&i.to_le_bytes());
+        }
+        for leaf in &self.leaves {
+            out.extend_from_slice(leaf.to_bytes().as_ref());
+        }
+        put_path(&mut out, &self.path);
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut r = Reader(bytes);
+        let n = r.count()?;
+        let indices = (0..n).map(|_| r.u64()).collect::<Result<_, _>>()?;
+        let leaves = (0..n).map(|_| r.node()).collect::<Result<_, _>>()?;
+        let path = r.path()?;
+        r.finish(MerkleMultiOpening { indices, leaves, path })
+    }
+}
+
+fn put_len(out: &mut Vec<u8>, len: usize) {
+    out.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+fn put_path<N: MerkleNode>(out: &mut Vec<u8>, path: &[Vec<N>]) {
+    put_len(out, path.len());
+    for level in path {
+        put_len(out, level.len());
+        for x in level {
+            out.extend_from_slice(x.to_bytes().as_ref());
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], DecodeError> {
+        if self.0.len() < n {
+            return Err(DecodeError::Truncated);
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    /// A length, checked against what is left so a forged count cannot
+    /// reserve more than the input could hold.
+    fn count(&mut self) -> Result<usize, DecodeError> {
+        let n = u32::from_le_bytes(self.take(4)?.try_into().unwrap()) as usize;
+        if n > self.0.len() {
+            return Err(DecodeError::Truncated);
+        }
+        Ok(n)
+    }
+
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// Field values share the layout of [`DigestWidth::Digest64`](crate::DigestWidth) nodes.
+    fn field(&mut self) -> Result<F, DecodeError> {
+        self.node::<F>()
+    }
+
+    fn node<N: MerkleNode>(&mut self) -> Result<N, DecodeError> {
+        N::from_bytes(self.take(N::WIDTH.bytes())?).ok_or(DecodeError::NonCanonical)
+    }
+
+    fn path<N: MerkleNode>(&mut self) -> Result<Vec<Vec<N>>, DecodeError> {
+        let levels = self.count()?;
+        (0..levels)
+            .map(|_| {
+                let n = self.count()?;
+                (0..n).map(|_| self.node()).collect()
+            })
+            .collect()
+    }
+
+    fn opening<N: MerkleNode>(&mut self) -> Result<MerkleOpening<N>, DecodeError> {
+        let index = self.u64()?;
+        let leaf = self.node()?;
+        let path = self.path()?;
+        Ok(MerkleOpening { leaf, path, index })
+    }
+
+    fn finish<T>(self, value: T) -> Result<T, DecodeError> {
+        match self.0 {
+            [] => Ok(value),
+            _ => Err(DecodeError::TrailingBytes),
+        }
+    }
+}
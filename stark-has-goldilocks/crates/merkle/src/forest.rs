@@ -0,0 +1,211 @@
+//! Several equal-length vectors under one root.
+//!
+//! A [`MerkleForest`] commits each vector, e.g. one FRI layer's f, s and q
+//! or one trace column apiece, in a tree of its own under a shared config,
+//! and hashes the sub-roots into a single root. Adding, dropping or
+//! reordering vectors changes only the number of sub-trees, not the leaf
+//! layout of any of them.
+
+use alloc::vec::Vec;
+
+use crate::{
+    DsLabel, LeafEncoder, MerkleChannelCfg, MerkleError, MerkleNode, MerkleOpening, MerkleTreeChannel,
+    F, FOREST_LEVEL_DS,
+};
+
+/// An opening of one sub-tree, with every sub-root to rebuild the forest
+/// root from.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct MerkleForestOpening<N = F> {
+    /// Sub-tree the leaf is in.
+    pub tree: u32,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub sub_roots: Vec<N>,
+    pub opening: MerkleOpening<N>,
+}
+
+pub struct MerkleForest<N = F> {
+    cfg: MerkleChannelCfg,
+    trees: Vec<MerkleTreeChannel<N>>,
+    /// Leaves pushed per sub-tree.
+    leaves: Vec<u64>,
+    sub_roots: Vec<N>,
+}
+
+impl<N: MerkleNode> MerkleForest<N> {
+    /// `trees` sub-trees, each shaped by `cfg`. Panics if `trees` is 0, or
+    /// as [`MerkleTreeChannel::new`] does.
+    pub fn new(cfg: MerkleChannelCfg, trees: usize, trace_hash: [u8; 32]) -> Self {
+        assert!(trees > 0, "forest without trees");
+        Self {
+            trees: (0..trees).map(|_| MerkleTreeChannel::new(cfg.clone(), trace_hash)).collect(),
+            leaves: alloc::vec![0; trees],
+            sub_roots: Vec::new(),
+            cfg,
+        }
+    }
+
+    pub fn trees(&self) -> usize {
+        self.trees.len()
+    }
+
+    /// [`MerkleTreeChannel::push_leaf`] on sub-tree `tree`. Panics if there
+    /// is no such sub-tree.
+    pub fn push_leaf(&mut self, tree: usize, values: &[F]) {
+        assert!(tree < self.trees.len(), "sub-tree {tree} of a forest of {}", self.trees.len());
+        self.trees[tree].push_leaf(values);
+        self.leaves[tree] += 1;
+        self.sub_roots.clear();
+    }
+
+    /// [`push_leaf`](Self::push_leaf) of `leaf`'s [`LeafEncoder`] values.
+    pub fn push_encoded<L: LeafEncoder + ?Sized>(&mut self, tree: usize, leaf: &L) {
+        self.push_leaf(tree, &leaf.leaf_values());
+    }
+
+    /// One leaf per entry of `column`, appended to sub-tree `tree`.
+    pub fn push_column<L: LeafEncoder>(&mut self, tree: usize, column: &[L]) {
+        column.iter().for_each(|x| self.push_encoded(tree, x));
+    }
+
+    /// Finalize every sub-tree and return the forest root. Fails as
+    /// [`MerkleTreeChannel::finalize`] does, or with
+    /// [`LeafCountMismatch`](MerkleError::LeafCountMismatch) against the
+    /// first sub-tree if the vectors differ in length.
+    pub fn finalize(&mut self) -> Result<N, MerkleError> {
+        let expected = self.leaves[0];
+        if let Some(&got) = self.leaves.iter().find(|&&n| n != expected) {
+            return Err(MerkleError::LeafCountMismatch { expected, got });
+        }
+        self.sub_roots = self.trees.iter_mut().map(|t| t.finalize()).collect::<Result<_, _>>()?;
+        Ok(Self::combine(&self.cfg, &self.sub_roots))
+    }
+
+    /// Sub-roots in tree order; empty before [`finalize`](Self::finalize).
+    pub fn sub_roots(&self) -> &[N] {
+        &self.sub_roots
+    }
+
+    /// The finalized sub-tree `tree`.
+    pub fn tree(&self, tree: usize) -> Option<&MerkleTreeChannel<N>> {
+        self.trees.get(tree)
+    }
+
+    /// Open leaf `index` of sub-tree `tree`.
+    pub fn open(&self, tree: usize, index: usize) -> Result<MerkleForestOpening<N>, MerkleError> {
+        if self.sub_roots.is_empty() {
+            return Err(MerkleError::NotFinalized);
+        }
+        let Some(t) = self.trees.get(tree) else {
+            return Err(MerkleError::NoSuchTree {
+                tree,
+                trees: self.trees.len(),
+            });
+        };
+        Ok(MerkleForestOpening {
+            tree: tree as u32,
+            sub_roots: self.sub_roots.clone(),
+            opening: t.open(index)?,
+        })
+    }
+
+    /// Forest root over `sub_roots`, hashed under a label of its own so it
+    /// is never a node of any tree.
+    pub fn combine(cfg: &MerkleChannelCfg, sub_roots: &[N]) -> N {
+        let ds = DsLabel {
+            arity: sub_roots.len(),
+            level: FOREST_LEVEL_DS,
+            position: 0,
+            tree_label: cfg.tree_label,
+        };
+        cfg.compress(ds, sub_roots)
+    }
+
+    /// Check that `opening` leads to its sub-root and the sub-roots to
+    /// `root`. A caller expecting a given number of vectors should also
+    /// check `opening.sub_roots.len()`.
+    pub fn verify_opening(
+        cfg: &MerkleChannelCfg,
+        root: N,
+        opening: &MerkleForestOpening<N>,
+        trace_hash: &[u8; 32],
+    ) -> bool {
+        let Some(&sub_root) = opening.sub_roots.get(opening.tree as usize) else {
+            return false;
+        };
+        Self::combine(cfg, &opening.sub_roots) == root
+            && MerkleTreeChannel::verify_opening(cfg, sub_root, &opening.opening, trace_hash) == Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> [Vec<F>; 3] {
+        let col = |k: u64| (0..20).map(|i| F::from(i * 7 + k)).collect();
+        [col(0), col(100), col(200)]
+    }
+
+    #[test]
+    fn forest_roots_bind_every_column() {
+        let cfg = MerkleChannelCfg::for_leaves(20, 4, 0);
+        let th = [0u8; 32];
+        let mut forest = MerkleForest::<F>::new(cfg.clone(), 3, th);
+        for (t, c) in columns().iter().enumerate() {
+            forest.push_column(t, c);
+        }
+        let root = forest.finalize().unwrap();
+
+        // Each sub-root is the column's own tree root.
+        for (t, c) in columns().iter().enumerate() {
+            let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), th);
+            c.iter().for_each(|x| tree.push_leaf(&[*x]));
+            assert_eq!(forest.sub_roots()[t], tree.finalize().unwrap());
+        }
+
+        for t in 0..3 {
+            let o = forest.open(t, 13).unwrap();
+            assert_eq!(o.opening.leaf, MerkleTreeChannel::<F>::leaf_digest(&cfg, 13, &[columns()[t][13]]));
+            assert!(MerkleForest::verify_opening(&cfg, root, &o, &th));
+
+            // Claimed from another sub-tree, or under reordered sub-roots.
+            let mut moved = o.clone();
+            moved.tree = (o.tree + 1) % 3;
+            assert!(!MerkleForest::verify_opening(&cfg, root, &moved, &th));
+            let mut swapped = o.clone();
+            swapped.sub_roots.swap(0, 2);
+            assert!(!MerkleForest::verify_opening(&cfg, root, &swapped, &th));
+            let mut beyond = o;
+            beyond.tree = 3;
+            assert!(!MerkleForest::verify_opening(&cfg, root, &beyond, &th));
+        }
+
+        // The same columns in fewer sub-trees make another root.
+        let mut two = MerkleForest::<F>::new(cfg.clone(), 2, th);
+        for (t, c) in columns()[..2].iter().enumerate() {
+            two.push_column(t, c);
+        }
+        assert_ne!(two.finalize().unwrap(), root);
+    }
+
+    #[test]
+    fn forest_misuse_is_an_error() {
+        let cfg = MerkleChannelCfg::for_leaves(20, 4, 0);
+        let mut forest = MerkleForest::<F>::new(cfg, 2, [0u8; 32]);
+        forest.push_column(0, &columns()[0]);
+        forest.push_column(1, &columns()[1][..19]);
+        assert_eq!(forest.open(0, 0).unwrap_err(), MerkleError::NotFinalized);
+        assert_eq!(
+            forest.finalize().unwrap_err(),
+            MerkleError::LeafCountMismatch { expected: 20, got: 19 }
+        );
+
+        forest.push_leaf(1, &[F::from(0u64)]);
+        forest.finalize().unwrap();
+        assert_eq!(forest.open(2, 0).unwrap_err(), MerkleError::NoSuchTree { tree: 2, trees: 2 });
+        assert!(matches!(forest.open(1, 20), Err(MerkleError::IndexOutOfRange { .. })));
+    }
+}
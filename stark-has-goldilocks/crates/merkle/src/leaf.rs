@@ -0,0 +1,69 @@
+//! Typed leaf contents.
+//!
+//! A leaf hashes a flat list of base-field values. [`LeafEncoder`] lets a
+//! caller push and open structured leaves, e.g. a FRI leaf
+//! `(f, s, q)` with `q` in an extension field, and have them flattened the
+//! same way on both sides. An extension field implements it once, as its
+//! coordinates over the base field.
+
+use alloc::vec::Vec;
+
+use crate::F;
+
+/// Leaf contents that flatten to the base-field values the leaf hashes.
+/// For a given type the flattening must be injective, which fixed-width
+/// values and tuples of them are.
+pub trait LeafEncoder {
+    /// Append the values to `out`.
+    fn encode_leaf(&self, out: &mut Vec<F>);
+
+    /// The values on their own, as [`push_leaf`](crate::MerkleTreeChannel::push_leaf)
+    /// and [`open_with_values`](crate::MerkleTreeChannel::open_with_values) take them.
+    fn leaf_values(&self) -> Vec<F> {
+        let mut out = Vec::new();
+        self.encode_leaf(&mut out);
+        out
+    }
+}
+
+impl LeafEncoder for F {
+    fn encode_leaf(&self, out: &mut Vec<F>) {
+        out.push(*self);
+    }
+}
+
+impl<T: LeafEncoder> LeafEncoder for [T] {
+    fn encode_leaf(&self, out: &mut Vec<F>) {
+        self.iter().for_each(|x| x.encode_leaf(out));
+    }
+}
+
+impl<T: LeafEncoder, const K: usize> LeafEncoder for [T; K] {
+    fn encode_leaf(&self, out: &mut Vec<F>) {
+        self[..].encode_leaf(out);
+    }
+}
+
+impl<T: LeafEncoder + ?Sized> LeafEncoder for &T {
+    fn encode_leaf(&self, out: &mut Vec<F>) {
+        (**self).encode_leaf(out);
+    }
+}
+
+macro_rules! tuple_leaf {
+    ($($t:ident),+) => {
+        impl<$($t: LeafEncoder),+> LeafEncoder for ($($t,)+) {
+            #[allow(non_snake_case)]
+            fn encode_leaf(&self, out: &mut Vec<F>) {
+                let ($($t,)+) = self;
+                $($t.encode_leaf(out);)+
+            }
+        }
+    };
+}
+
+tuple_leaf!(A);
+tuple_leaf!(A, B);
+tuple_leaf!(A, B, C);
+tuple_leaf!(A, B, C, D);
+tuple_leaf!(A, B, C, D, E);
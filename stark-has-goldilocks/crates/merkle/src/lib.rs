@@ -1,3 +1,9 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
 use ark_ff::{BigInteger, PrimeField};
 use ark_goldilocks::Goldilocks as F;
 use ark_goldilocks::Goldilocks;
@@ -6,6 +12,36 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use sha3::{Digest, Sha3_256};
 
+use once_cell::race::OnceBox;
+use poseidon::{permute, plonky2, PoseidonParams, RATE, T};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+pub mod compressed;
+#[cfg(feature = "mmap")]
+pub mod disk;
+pub mod encoding;
+pub mod forest;
+pub mod leaf;
+pub mod multi;
+pub mod range;
+pub mod scheme;
+pub mod sparse;
+pub mod stats;
+pub mod stream;
+
+pub use compressed::CompressedOpenings;
+#[cfg(feature = "mmap")]
+pub use disk::MmapMerkleTree;
+pub use encoding::DecodeError;
+pub use forest::{MerkleForest, MerkleForestOpening};
+pub use leaf::LeafEncoder;
+pub use scheme::VectorCommitment;
+pub use sparse::SparseMerkleTree;
+pub use stats::TreeStats;
+pub use stream::MerkleStreamBuilder;
+
 /// =======================
 /// Serialization helpers
 /// =======================
@@ -86,202 +122,2468 @@ impl DsLabel {
 }
 
 const LEAF_LEVEL_DS: u32 = u32::MAX;
+const PAD_LEVEL_DS: u32 = u32::MAX - 1;
+const ROOT_LEVEL_DS: u32 = u32::MAX - 2;
+const FOREST_LEVEL_DS: u32 = u32::MAX - 3;
+const SPARSE_LEVEL_DS: u32 = u32::MAX - 4;
+
+fn compress<C: MerkleNode, N: MerkleNode>(ds: DsLabel, children: &[C]) -> N {
+    let mut h = Sha3_256::new();
+    Digest::update(&mut h, ds.to_bytes());
+    for c in children {
+        Digest::update(&mut h, c.to_bytes());
+    }
+    N::from_digest(h.finalize().into())
+}
+
+/// Round constants and MDS of [`MerkleHash::Poseidon`], derived on first use.
+fn poseidon_params() -> &'static PoseidonParams {
+    static PARAMS: OnceBox<PoseidonParams> = OnceBox::new();
+    PARAMS.get_or_init(|| alloc::boxed::Box::new(poseidon::params::generate_params_t17_x5(b"MERKLE/POSEIDON-T17-X5")))
+}
+
+/// The field element a label and input count put in the sponge's capacity
+/// lane: their 32-bit limbs permuted once, so no byte hash is needed.
+fn poseidon_capacity(ds: DsLabel, inputs: usize) -> F {
+    let limbs = [
+        ds.arity as u64,
+        ds.level as u64,
+        ds.position & 0xffff_ffff,
+        ds.position >> 32,
+        ds.tree_label & 0xffff_ffff,
+        ds.tree_label >> 32,
+        inputs as u64,
+    ];
+    let mut state = [F::from(0u64); T];
+    for (lane, limb) in state.iter_mut().zip(limbs) {
+        *lane = F::from(limb);
+    }
+    permute(&mut state, poseidon_params());
+    state[0]
+}
+
+/// Poseidon counterpart of [`compress`]: the children, one element each,
+/// absorbed `RATE` at a time behind the label in the capacity lane. Two to
+/// sixteen children take a single permutation. Panics on nodes that are
+/// not field elements.
+fn poseidon_compress<C: MerkleNode, N: MerkleNode>(ds: DsLabel, children: &[C]) -> N {
+    let params = poseidon_params();
+    let mut state = [F::from(0u64); T];
+    state[T - 1] = poseidon_capacity(ds, children.len());
+    let mut chunks = children.chunks(RATE);
+    let mut chunk = chunks.next().unwrap_or(&[]);
+    loop {
+        for (lane, c) in state.iter_mut().zip(chunk) {
+            *lane += <F as MerkleNode>::from_bytes(c.to_bytes().as_ref()).expect("Poseidon Merkle nodes are field elements");
+        }
+        permute(&mut state, params);
+        match chunks.next() {
+            Some(next) => chunk = next,
+            None => break,
+        }
+    }
+    N::from_bytes(&field_to_bytes(&state[0])).expect("Poseidon Merkle nodes are field elements")
+}
+
+/// [`MerkleHash::Plonky2`] counterpart of [`compress`]: field children are
+/// leaf values, hashed with `hash_or_noop`; digest children are a pair
+/// hashed with `two_to_one`. The label is not hashed, and the padding and
+/// sealing digests that have no Plonky2 counterpart hash their children's
+/// elements with `hash_no_pad`.
+fn plonky2_compress<C: MerkleNode, N: MerkleNode>(children: &[C]) -> N {
+    let elements = |c: &C| -> Vec<F> { c.to_bytes().as_ref().chunks(8).map(|b| bytes_to_field(b.try_into().unwrap())).collect() };
+    let digest = match (C::WIDTH, children) {
+        (DigestWidth::Digest64, _) => plonky2::hash_or_noop(&children.iter().flat_map(elements).collect::<Vec<_>>()),
+        (DigestWidth::Digest256, [left, right]) => {
+            let (left, right) = (elements(left), elements(right));
+            plonky2::two_to_one(&left[..].try_into().unwrap(), &right[..].try_into().unwrap())
+        }
+        (DigestWidth::Digest256, _) => plonky2::hash_no_pad(&children.iter().flat_map(elements).collect::<Vec<_>>()),
+    };
+    N::from_bytes(&plonky2::digest_to_bytes(&digest)).expect("Plonky2 Merkle nodes are 32-byte digests")
+}
+
+/// How much of each SHA3-256 output a tree keeps per node.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum DigestWidth {
+    /// The first 8 bytes, read as a Goldilocks element: the original
+    /// encoding, with about 32 bits of collision resistance.
+    #[default]
+    Digest64,
+    /// All 32 bytes, for deployments that cannot accept a 64-bit
+    /// truncation.
+    Digest256,
+}
+
+impl DigestWidth {
+    pub const fn bytes(self) -> usize {
+        match self {
+            DigestWidth::Digest64 => 8,
+            DigestWidth::Digest256 => 32,
+        }
+    }
+}
+
+/// Hash a tree compresses its leaves and nodes with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum MerkleHash {
+    /// SHA3-256 over the label and node bytes: the original hash.
+    #[default]
+    Sha3,
+    /// The t = 17 Poseidon permutation, cheap to check in a circuit: each
+    /// group of up to 16 children takes one permutation, with the label
+    /// in the capacity lane. Nodes are single field elements, so only
+    /// [`DigestWidth::Digest64`] trees can use it.
+    Poseidon,
+    /// Plonky2's `PoseidonHash`: leaves through `hash_or_noop`, parents
+    /// through `two_to_one`, with no labels. Nodes are four field elements
+    /// in their canonical bytes, so only [`DigestWidth::Digest256`] trees
+    /// can use it. A full binary tree without a bound leaf count then has
+    /// Plonky2's root, and its cap is Plonky2's `MerkleCap`; see
+    /// [`MerkleChannelCfg::plonky2`].
+    Plonky2,
+}
+
+impl MerkleHash {
+    /// Node width the hash produces, if it fixes one.
+    pub const fn width(self) -> Option<DigestWidth> {
+        match self {
+            MerkleHash::Sha3 => None,
+            MerkleHash::Poseidon => Some(DigestWidth::Digest64),
+            MerkleHash::Plonky2 => Some(DigestWidth::Digest256),
+        }
+    }
+
+    fn admits(self, width: DigestWidth) -> bool {
+        self.width().is_none_or(|w| w == width)
+    }
+}
+
+/// Value held at a Merkle node (and leaf digest) of a tree of width
+/// [`MerkleNode::WIDTH`]. Trees, openings and roots are generic over it
+/// and default to `F`, the [`DigestWidth::Digest64`] node.
+pub trait MerkleNode: Copy + Default + Eq + core::fmt::Debug + Send + Sync + 'static {
+    const WIDTH: DigestWidth;
+    type Bytes: AsRef<[u8]>;
+
+    /// Encoding hashed into the parent.
+    fn to_bytes(&self) -> Self::Bytes;
+
+    /// Node for a SHA3-256 output.
+    fn from_digest(digest: [u8; 32]) -> Self;
+
+    /// Inverse of [`to_bytes`](Self::to_bytes); `None` for any other
+    /// length or a non-canonical encoding.
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+impl MerkleNode for F {
+    const WIDTH: DigestWidth = DigestWidth::Digest64;
+    type Bytes = [u8; 8];
+
+    fn to_bytes(&self) -> [u8; 8] {
+        field_to_bytes(self)
+    }
+
+    fn from_digest(digest: [u8; 32]) -> Self {
+        bytes_to_field(digest[..8].try_into().unwrap())
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let x = u64::from_le_bytes(bytes.try_into().ok()?);
+        (x < F::MODULUS.0[0]).then(|| F::from(x))
+    }
+}
+
+/// Full-width node of a [`DigestWidth::Digest256`] tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Digest256(pub [u8; 32]);
+
+impl MerkleNode for Digest256 {
+    const WIDTH: DigestWidth = DigestWidth::Digest256;
+    type Bytes = [u8; 32];
+
+    fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    fn from_digest(digest: [u8; 32]) -> Self {
+        Digest256(digest)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(Digest256)
+    }
+}
+
+/// Raw 32 bytes, so that `Digest256` nodes go through the same
+/// `serde(with = "ark_goldilocks::serde_canonical")` fields as `F` ones.
+#[cfg(feature = "serde1")]
+impl ark_goldilocks::serde_canonical::Canonical for Digest256 {
+    fn serialize_canonical<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+
+    fn deserialize_canonical<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+            type Value = Digest256;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                f.write_str("a 32-byte digest")
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Digest256, E> {
+                v.try_into()
+                    .map(Digest256)
+                    .map_err(|_| E::invalid_length(v.len(), &self))
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Digest256, A::Error> {
+                let mut out = [0u8; 32];
+                for (k, b) in out.iter_mut().enumerate() {
+                    *b = seq
+                        .next_element()?
+                        .ok_or_else(|| serde::de::Error::invalid_length(k, &self))?;
+                }
+                if seq.next_element::<u8>()?.is_some() {
+                    return Err(serde::de::Error::invalid_length(33, &self));
+                }
+                Ok(Digest256(out))
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
+/// How the last group of a level is filled out when the level's length is
+/// not a multiple of its arity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum Padding {
+    /// Repeat the level's last node: the original rule. Leaves `a, b, c`
+    /// then share their root with `a, b, c, c`, so the leaf count must be
+    /// fixed some other way, e.g. by [`MerkleChannelCfg::leaf_count`].
+    #[default]
+    DuplicateLast,
+    /// The all-zero node.
+    ZeroLeaf,
+    /// A digest per level that no leaf or parent hashes to.
+    FixedDigest,
+}
+
+/// How [`MerkleChannelCfg::for_leaves`] picks the arity of its levels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArityPolicy {
+    /// This arity on every level.
+    Fixed(usize),
+    /// The largest power of two from 4 to 128 that is at most the given
+    /// bound and divides the leaf count, else 2: the FRI rule, so a layer
+    /// folded by `m` commits each coset's leaves in as few groups as
+    /// possible.
+    UpTo(usize),
+}
+
+impl ArityPolicy {
+    /// Arity for a tree of `leaves` leaves.
+    pub fn arity(self, leaves: usize) -> usize {
+        match self {
+            ArityPolicy::Fixed(arity) => arity,
+            ArityPolicy::UpTo(max) => (2..=7)
+                .rev()
+                .map(|k| 1 << k)
+                .find(|&a| a <= max && leaves.is_multiple_of(a))
+                .unwrap_or(2),
+        }
+    }
+}
+
+impl From<usize> for ArityPolicy {
+    fn from(arity: usize) -> Self {
+        ArityPolicy::Fixed(arity)
+    }
+}
 
 /// =======================
 /// Merkle config
 /// =======================
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 pub struct MerkleChannelCfg {
     pub layer_arities: Vec<usize>,
     pub tree_label: u64,
+    /// Node width; a tree only builds and verifies with the matching
+    /// [`MerkleNode`] type.
+    #[cfg_attr(feature = "serde1", serde(default))]
+    pub digest: DigestWidth,
+    /// Top levels left out of openings: the commitment is the
+    /// [`cap_len`](Self::cap_len) nodes below them instead of the root.
+    /// 0 commits to the root.
+    #[cfg_attr(feature = "serde1", serde(default))]
+    pub cap_height: usize,
+    /// Mix a secret per-leaf [`leaf_salt`] into every leaf digest, so the
+    /// root and the paths of opened leaves reveal nothing about the others.
+    /// Value openings then carry the salt of their leaf.
+    #[cfg_attr(feature = "serde1", serde(default))]
+    pub hiding: bool,
+    #[cfg_attr(feature = "serde1", serde(default))]
+    pub padding: Padding,
+    /// Leaves the tree holds, if fixed: the root then hashes the top node
+    /// again under a label carrying the count, and verification rejects
+    /// indices at or past it. Caps are not relabelled.
+    #[cfg_attr(feature = "serde1", serde(default))]
+    pub leaf_count: Option<u64>,
+    #[cfg_attr(feature = "serde1", serde(default))]
+    pub hash: MerkleHash,
 }
 
 impl MerkleChannelCfg {
     pub fn new(layer_arities: Vec<usize>, tree_label: u64) -> Self {
-        Self { layer_arities, tree_label }
+        Self {
+            layer_arities,
+            tree_label,
+            digest: DigestWidth::Digest64,
+            cap_height: 0,
+            hiding: false,
+            padding: Padding::DuplicateLast,
+            leaf_count: None,
+            hash: MerkleHash::Sha3,
+        }
     }
-}
 
-/// =======================
-/// Merkle opening
-/// =======================
+    /// Uniform-arity tree just deep enough for `leaves` leaves, its arity
+    /// given directly or by an [`ArityPolicy`]. Pushing more leaves than it
+    /// was sized for makes [`finalize`](MerkleTreeChannel::finalize) fail
+    /// with [`MerkleError::IndexOutOfRange`].
+    ///
+    /// Panics if `leaves == 0` or the arity is below 2; use
+    /// [`MerkleCfgBuilder`] to get these as errors.
+    pub fn for_leaves(leaves: usize, arity: impl Into<ArityPolicy>, tree_label: u64) -> Self {
+        Self::builder(leaves)
+            .fill_with(arity.into().arity(leaves))
+            .tree_label(tree_label)
+            .build()
+            .expect("invalid Merkle tree shape")
+    }
 
-#[derive(Clone, Debug)]
-pub struct MerkleOpening {
-    pub leaf: F,
-    pub path: Vec<Vec<F>>,
-    pub index: usize,
-}
+    /// Plonky2's `MerkleTree` over `leaves` leaves, a power of two: binary,
+    /// [`MerkleHash::Plonky2`] over [`Digest256`] nodes, and the cap
+    /// `cap_height` levels below the root.
+    /// [`finalize_cap`](MerkleTreeChannel::finalize_cap) is then Plonky2's
+    /// `MerkleCap` and [`open`](MerkleTreeChannel::open) its
+    /// `MerkleProof`, a sibling per level.
+    pub fn plonky2(leaves: usize, cap_height: usize) -> Result<Self, MerkleCfgError> {
+        Self::builder(leaves)
+            .levels(2, leaves.max(1).ilog2() as usize)
+            .digest(DigestWidth::Digest256)
+            .hash(MerkleHash::Plonky2)
+            .cap_height(cap_height)
+            .build()
+    }
 
-/// =======================
-/// Merkle tree
-/// =======================
+    pub fn builder(leaves: usize) -> MerkleCfgBuilder {
+        MerkleCfgBuilder::new(leaves)
+    }
 
-pub struct MerkleTreeChannel {
-    cfg: MerkleChannelCfg,
-    levels: Vec<Vec<F>>,
-}
+    pub fn with_label(self, tree_label: u64) -> Self {
+        Self { tree_label, ..self }
+    }
 
-impl MerkleTreeChannel {
-    pub fn new(cfg: MerkleChannelCfg, _trace_hash: [u8; 32]) -> Self {
-        Self {
-            cfg,
-            levels: Vec::new(),
+    pub fn with_digest(self, digest: DigestWidth) -> Self {
+        Self { digest, ..self }
+    }
+
+    pub fn with_hiding(self, hiding: bool) -> Self {
+        Self { hiding, ..self }
+    }
+
+    pub fn with_padding(self, padding: Padding) -> Self {
+        Self { padding, ..self }
+    }
+
+    pub fn with_leaf_count(self, leaf_count: Option<u64>) -> Self {
+        Self { leaf_count, ..self }
+    }
+
+    pub fn with_hash(self, hash: MerkleHash) -> Self {
+        Self { hash, ..self }
+    }
+
+    /// Panics past the number of levels.
+    pub fn with_cap_height(self, cap_height: usize) -> Self {
+        assert!(cap_height <= self.layer_arities.len(), "cap above the root");
+        Self { cap_height, ..self }
+    }
+
+    /// Level the cap sits on, counting the leaves as 0: where openings end.
+    pub fn cap_level(&self) -> usize {
+        self.layer_arities.len() - self.cap_height
+    }
+
+    /// Level a finalized tree reaches at least: a capped tree keeps every
+    /// level up to the cap, even single nodes.
+    pub(crate) fn min_top_level(&self) -> usize {
+        match self.cap_height {
+            0 => 0,
+            _ => self.cap_level(),
         }
     }
 
-    fn compress(&self, ds: DsLabel, children: &[F]) -> F {
-        let mut h = Sha3_256::new();
-        Digest::update(&mut h, ds.to_bytes());
-        for c in children {
-            Digest::update(&mut h, field_to_bytes(c));
+    /// Nodes in the cap: the product of the left-out arities, so
+    /// `2^cap_height` for a binary tree.
+    pub fn cap_len(&self) -> usize {
+        self.layer_arities[self.cap_level()..].iter().product()
+    }
+
+    /// Number of leaves the levels can reduce to a single root.
+    pub fn capacity(&self) -> usize {
+        self.layer_arities
+            .iter()
+            .try_fold(1usize, |acc, &a| acc.checked_mul(a))
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Whether leaf `index` lies within [`capacity`](Self::capacity),
+    /// counted in u64 so that a 32-bit verifier can check trees of more
+    /// than 2^32 leaves.
+    pub fn covers(&self, index: u64) -> bool {
+        self.layer_arities
+            .iter()
+            .try_fold(1u64, |acc, &a| acc.checked_mul(a as u64))
+            .is_none_or(|capacity| index < capacity)
+    }
+
+    /// Whether leaf `index` can be opened: below the
+    /// [`leaf_count`](Self::leaf_count) if fixed, else [covered](Self::covers).
+    pub fn admits(&self, index: u64) -> bool {
+        match self.leaf_count {
+            Some(n) => index < n,
+            None => self.covers(index),
         }
-        let out = h.finalize();
-        let mut bytes = [0u8; 8];
-        bytes.copy_from_slice(&out[..8]);
-        bytes_to_field(&bytes)
     }
 
-    /// ✅ Generic leaf: caller flattens values
-    pub fn push_leaf(&mut self, values: &[F]) {
-        if self.levels.is_empty() {
-            self.levels.push(Vec::new());
+    /// Why a tree of `N` nodes cannot use this config, if it cannot.
+    pub(crate) fn node_error<N: MerkleNode>(&self) -> Option<MerkleError> {
+        if self.digest != N::WIDTH {
+            return Some(MerkleError::WidthMismatch {
+                cfg: self.digest,
+                node: N::WIDTH,
+            });
         }
+        (!self.hash.admits(N::WIDTH))
+            .then_some(MerkleError::HashWidth { hash: self.hash, node: N::WIDTH })
+    }
 
-        let idx = self.levels[0].len();
-        let ds = DsLabel {
-            arity: self.cfg.layer_arities[0],
-            level: LEAF_LEVEL_DS,
-            position: idx as u64,
-            tree_label: self.cfg.tree_label,
-        };
+    /// Node hashed from `children` under `ds`, by the config's [`MerkleHash`].
+    pub(crate) fn compress<C: MerkleNode, N: MerkleNode>(&self, ds: DsLabel, children: &[C]) -> N {
+        match self.hash {
+            MerkleHash::Sha3 => compress(ds, children),
+            MerkleHash::Poseidon => poseidon_compress(ds, children),
+            MerkleHash::Plonky2 => plonky2_compress(children),
+        }
+    }
 
-        let leaf = self.compress(ds, values);
-        self.levels[0].push(leaf);
+    fn index_error(&self, index: u64) -> MerkleError {
+        MerkleError::IndexOutOfRange {
+            index,
+            len: self.leaf_count.unwrap_or(self.capacity() as u64),
+        }
     }
 
-    pub fn finalize(&mut self) -> F {
-        let mut level = 0;
-        while self.levels[level].len() > 1 {
-            let arity = self.cfg.layer_arities[level];
-            let mut cur = self.levels[level].clone();
+    /// Node filling out the last group of `level` (0 for the leaves), whose
+    /// nodes so far are `nodes`.
+    pub(crate) fn pad_node<N: MerkleNode>(&self, level: usize, nodes: &[N]) -> N {
+        match self.padding {
+            Padding::DuplicateLast => *nodes.last().expect("padding an empty level"),
+            Padding::ZeroLeaf => N::default(),
+            Padding::FixedDigest => {
+                let ds = DsLabel {
+                    arity: self.layer_arities.get(level).copied().unwrap_or(1),
+                    level: PAD_LEVEL_DS,
+                    position: level as u64,
+                    tree_label: self.tree_label,
+                };
+                self.compress::<N, N>(ds, &[])
+            }
+        }
+    }
 
-            if cur.len() % arity != 0 {
-                let last = *cur.last().unwrap();
-                cur.resize(cur.len() + (arity - cur.len() % arity), last);
+    /// The root over top node `top`: `top` itself, or with a fixed
+    /// [`leaf_count`](Self::leaf_count) `top` hashed under a label carrying it.
+    pub(crate) fn seal<N: MerkleNode>(&self, top: N) -> N {
+        match self.leaf_count {
+            None => top,
+            Some(n) => {
+                let ds = DsLabel {
+                    arity: 1,
+                    level: ROOT_LEVEL_DS,
+                    position: n,
+                    tree_label: self.tree_label,
+                };
+                self.compress(ds, &[top])
             }
+        }
+    }
+}
 
-            let parents: Vec<F> = cur
-                .chunks(arity)
-                .enumerate()
-                .map(|(i, c)| {
-                    let ds = DsLabel {
-                        arity,
-                        level: level as u32 + 1,
-                        position: i as u64,
-                        tree_label: self.cfg.tree_label,
-                    };
-                    self.compress(ds, c)
-                })
-                .collect();
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MerkleCfgError {
+    /// A tree needs at least one leaf.
+    NoLeaves,
+    /// Level `level` was given arity `arity` < 2.
+    ArityTooSmall { level: usize, arity: usize },
+    /// The levels only cover `capacity` of the `leaves` leaves.
+    TooShallow { leaves: usize, capacity: usize },
+    /// A cap `cap_height` levels down a tree of `levels` levels.
+    CapTooHigh { cap_height: usize, levels: usize },
+    /// Nodes of width `digest` for a hash whose nodes have another.
+    HashWidth { hash: MerkleHash, digest: DigestWidth },
+}
 
-            self.levels.push(parents);
-            level += 1;
+impl core::fmt::Display for MerkleCfgError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MerkleCfgError::NoLeaves => write!(f, "Merkle tree has no leaves"),
+            MerkleCfgError::ArityTooSmall { level, arity } => {
+                write!(f, "Merkle level {level} has arity {arity}, need ≥ 2")
+            }
+            MerkleCfgError::TooShallow { leaves, capacity } => {
+                write!(f, "Merkle levels cover {capacity} leaves, need {leaves}")
+            }
+            MerkleCfgError::CapTooHigh { cap_height, levels } => {
+                write!(f, "Merkle cap {cap_height} levels down a tree of {levels}")
+            }
+            MerkleCfgError::HashWidth { hash, digest } => {
+                write!(f, "{hash:?} Merkle trees cannot have {digest:?} nodes")
+            }
         }
-        self.levels.last().unwrap()[0]
     }
+}
 
-    pub fn open(&self, index: usize) -> MerkleOpening {
-        let mut idx = index;
-        let mut path = Vec::new();
+#[cfg(feature = "std")]
+impl std::error::Error for MerkleCfgError {}
 
-        for level in 0..self.levels.len() - 1 {
-            let nodes = &self.levels[level];
-            let arity = self.cfg.layer_arities[level];
-            let group_start = (idx / arity) * arity;
+/// Misuse of a tree or an opening that does not fit the config, as opposed
+/// to a well-formed opening that does not lead to the root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MerkleError {
+    /// Leaf `index` of a tree that holds, or has room for, `len` leaves.
+    IndexOutOfRange { index: u64, len: u64 },
+    /// A path of `got` levels for a tree of at most `levels`.
+    WrongPathLength { levels: usize, got: usize },
+    /// `got` siblings on `level`, whose groups have `arity` nodes.
+    ArityMismatch { level: usize, arity: usize, got: usize },
+    /// The config's node width is not the node type's.
+    WidthMismatch { cfg: DigestWidth, node: DigestWidth },
+    /// `got` leaves for a config fixing [`leaf_count`](MerkleChannelCfg::leaf_count) at `expected`.
+    LeafCountMismatch { expected: u64, got: u64 },
+    /// No root yet: opened before `finalize`, or finalized without leaves.
+    NotFinalized,
+    /// Sub-tree `tree` of a [`MerkleForest`] of `trees`.
+    NoSuchTree { tree: usize, trees: usize },
+    /// A config's hash used with nodes of a width it does not produce.
+    HashWidth { hash: MerkleHash, node: DigestWidth },
+    /// A coset of `leaves` leaves `stride` apart: none, or repeats.
+    EmptyCoset { leaves: usize, stride: usize },
+}
 
-            let mut group = Vec::with_capacity(arity);
-            for i in 0..arity {
-                let pos = group_start + i;
-                if pos < nodes.len() {
-                    group.push(nodes[pos]);
-                } else {
-                    group.push(*nodes.last().unwrap());
-                }
+impl core::fmt::Display for MerkleError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MerkleError::IndexOutOfRange { index, len } => {
+                write!(f, "Merkle leaf {index} outside {len} leaves")
+            }
+            MerkleError::WrongPathLength { levels, got } => {
+                write!(f, "Merkle path of {got} levels, tree has {levels}")
+            }
+            MerkleError::ArityMismatch { level, arity, got } => {
+                write!(f, "{got} Merkle siblings on level {level} of arity {arity}")
+            }
+            MerkleError::WidthMismatch { cfg, node } => {
+                write!(f, "Merkle config of width {cfg:?} used with {node:?} nodes")
+            }
+            MerkleError::LeafCountMismatch { expected, got } => {
+                write!(f, "{got} Merkle leaves, config fixes {expected}")
+            }
+            MerkleError::NotFinalized => write!(f, "Merkle tree not finalized"),
+            MerkleError::NoSuchTree { tree, trees } => {
+                write!(f, "Merkle sub-tree {tree} outside a forest of {trees}")
+            }
+            MerkleError::HashWidth { hash, node } => {
+                write!(f, "{hash:?} Merkle config used with {node:?} nodes")
             }
+            MerkleError::EmptyCoset { leaves, stride } => {
+                write!(f, "Merkle coset of {leaves} leaves with stride {stride}")
+            }
+        }
+    }
+}
 
-            let siblings = group
-                .iter()
-                .enumerate()
-                .filter_map(|(i, &x)| {
-                    if group_start + i != idx {
-                        Some(x)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
+#[cfg(feature = "std")]
+impl std::error::Error for MerkleError {}
 
-            path.push(siblings);
-            idx /= arity;
-        }
+/// Checked construction of a [`MerkleChannelCfg`] for a known leaf count.
+///
+/// Levels are listed leaf-side first and may mix arities:
+///
+/// ```
+/// # use merkle::MerkleChannelCfg;
+/// let cfg = MerkleChannelCfg::builder(1 << 10)
+///     .level(16)
+///     .fill_with(4)
+///     .tree_label(7)
+///     .build()
+///     .unwrap();
+/// assert_eq!(cfg.layer_arities, vec![16, 4, 4, 4]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MerkleCfgBuilder {
+    leaves: usize,
+    layer_arities: Vec<usize>,
+    tree_label: u64,
+    digest: DigestWidth,
+    fill: Option<usize>,
+    cap_height: usize,
+    hiding: bool,
+    padding: Padding,
+    bind_leaf_count: bool,
+    hash: MerkleHash,
+}
 
-        MerkleOpening {
-            leaf: self.levels[0][index],
-            path,
-            index,
+impl MerkleCfgBuilder {
+    pub fn new(leaves: usize) -> Self {
+        Self {
+            leaves,
+            layer_arities: Vec::new(),
+            tree_label: 0,
+            digest: DigestWidth::Digest64,
+            fill: None,
+            cap_height: 0,
+            hiding: false,
+            padding: Padding::DuplicateLast,
+            bind_leaf_count: false,
+            hash: MerkleHash::Sha3,
         }
     }
 
-    pub fn verify_opening(
-        cfg: &MerkleChannelCfg,
-        root: F,
-        opening: &MerkleOpening,
-        _trace_hash: &[u8; 32],
-    ) -> bool {
-        let mut cur = opening.leaf;
-        let mut idx = opening.index;
+    /// Append one level.
+    pub fn level(mut self, arity: usize) -> Self {
+        self.layer_arities.push(arity);
+        self
+    }
 
-        for (level, siblings) in opening.path.iter().enumerate() {
-            let arity = cfg.layer_arities[level];
-            let pos = idx % arity;
+    /// Append `count` levels of the same arity.
+    pub fn levels(mut self, arity: usize, count: usize) -> Self {
+        self.layer_arities
+            .extend(core::iter::repeat_n(arity, count));
+        self
+    }
 
-            let mut children = Vec::with_capacity(arity);
-            let mut sibs = siblings.iter();
+    /// After the explicit levels, add `arity`-ary levels until the leaves
+    /// are covered (at least one level overall).
+    pub fn fill_with(mut self, arity: usize) -> Self {
+        self.fill = Some(arity);
+        self
+    }
 
-            for i in 0..arity {
-                if i == pos {
-                    children.push(cur);
-                } else {
-                    match sibs.next() {
-                        Some(x) => children.push(*x),
-                        None => return false,
-                    }
-                }
-            }
+    pub fn tree_label(mut self, tree_label: u64) -> Self {
+        self.tree_label = tree_label;
+        self
+    }
 
-            let ds = DsLabel {
-                arity,
-                level: level as u32 + 1,
-                position: (idx / arity) as u64,
-                tree_label: cfg.tree_label,
-            };
+    pub fn digest(mut self, digest: DigestWidth) -> Self {
+        self.digest = digest;
+        self
+    }
 
-            let mut h = Sha3_256::new();
-            Digest::update(&mut h, ds.to_bytes());
-            for c in &children {
-                Digest::update(&mut h, field_to_bytes(c));
-            }
+    /// Commit to the nodes `cap_height` levels below the root; see
+    /// [`MerkleChannelCfg::cap_height`].
+    pub fn cap_height(mut self, cap_height: usize) -> Self {
+        self.cap_height = cap_height;
+        self
+    }
 
-            let out = h.finalize();
-            let mut bytes = [0u8; 8];
-            bytes.copy_from_slice(&out[..8]);
-            cur = bytes_to_field(&bytes);
+    /// Salt the leaves; see [`MerkleChannelCfg::hiding`].
+    pub fn hiding(mut self, hiding: bool) -> Self {
+        self.hiding = hiding;
+        self
+    }
 
-            idx /= arity;
-        }
+    pub fn padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Compress with `hash`; [`MerkleHash::Poseidon`] needs the default
+    /// [`DigestWidth::Digest64`], [`MerkleHash::Plonky2`]
+    /// [`DigestWidth::Digest256`].
+    pub fn hash(mut self, hash: MerkleHash) -> Self {
+        self.hash = hash;
+        self
+    }
 
-        cur == root
+    /// Fix [`MerkleChannelCfg::leaf_count`] at the builder's leaf count.
+    pub fn bind_leaf_count(mut self) -> Self {
+        self.bind_leaf_count = true;
+        self
     }
-}
\ No newline at end of file
+
+    pub fn build(self) -> Result<MerkleChannelCfg, MerkleCfgError> {
+        if self.leaves == 0 {
+            return Err(MerkleCfgError::NoLeaves);
+        }
+        if !self.hash.admits(self.digest) {
+            return Err(MerkleCfgError::HashWidth {
+                hash: self.hash,
+                digest: self.digest,
+            });
+        }
+
+        let explicit = self.layer_arities.iter().copied();
+        if let Some((level, arity)) = explicit.chain(self.fill).enumerate().find(|&(_, a)| a < 2) {
+            return Err(MerkleCfgError::ArityTooSmall { level, arity });
+        }
+
+        let mut cfg = MerkleChannelCfg::new(self.layer_arities, self.tree_label)
+            .with_digest(self.digest)
+            .with_hiding(self.hiding)
+            .with_padding(self.padding)
+            .with_leaf_count(self.bind_leaf_count.then_some(self.leaves as u64))
+            .with_hash(self.hash);
+        if let Some(arity) = self.fill {
+            while cfg.layer_arities.is_empty() || cfg.capacity() < self.leaves {
+                cfg.layer_arities.push(arity);
+            }
+        }
+
+        let capacity = cfg.capacity();
+        if cfg.layer_arities.is_empty() || capacity < self.leaves {
+            return Err(MerkleCfgError::TooShallow {
+                leaves: self.leaves,
+                capacity,
+            });
+        }
+        if self.cap_height > cfg.layer_arities.len() {
+            return Err(MerkleCfgError::CapTooHigh {
+                cap_height: self.cap_height,
+                levels: cfg.layer_arities.len(),
+            });
+        }
+        Ok(cfg.with_cap_height(self.cap_height))
+    }
+}
+
+/// =======================
+/// Merkle opening
+/// =======================
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct MerkleOpening<N = F> {
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub leaf: N,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub path: Vec<Vec<N>>,
+    pub index: u64,
+}
+
+/// A [`MerkleOpening`] together with the leaf contents it authenticates.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct MerkleValueOpening<N = F> {
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub values: Vec<F>,
+    /// The leaf's salt in a [hiding](MerkleChannelCfg::hiding) tree, `None`
+    /// otherwise.
+    #[cfg_attr(feature = "serde1", serde(default, with = "ark_goldilocks::serde_canonical"))]
+    pub salt: Option<F>,
+    pub opening: MerkleOpening<N>,
+}
+
+impl<N> MerkleValueOpening<N> {
+    pub fn index(&self) -> u64 {
+        self.opening.index
+    }
+}
+
+/// Siblings of one level of a [`MerkleRangeOpening`], left and right of the
+/// opened run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct RangeSiblings<N = F> {
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub left: Vec<N>,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub right: Vec<N>,
+}
+
+/// Leaves `start..start + leaves.len()` under one proof; see [`range`].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct MerkleRangeOpening<N = F> {
+    pub start: u64,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub leaves: Vec<N>,
+    /// From the leaf level up.
+    pub path: Vec<RangeSiblings<N>>,
+}
+
+/// A [`MerkleRangeOpening`] with the contents of every opened leaf.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct MerkleRangeValueOpening<N = F> {
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub values: Vec<Vec<F>>,
+    pub opening: MerkleRangeOpening<N>,
+}
+
+/// Leaves at `indices` under one proof; see [`multi`].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct MerkleMultiOpening<N = F> {
+    /// Strictly increasing.
+    pub indices: Vec<u64>,
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub leaves: Vec<N>,
+    /// From the leaf level up.
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub path: Vec<Vec<N>>,
+}
+
+impl<N> MerkleMultiOpening<N> {
+    /// Digest of leaf `index`, if it was opened.
+    pub fn leaf(&self, index: u64) -> Option<&N> {
+        self.indices.binary_search(&index).ok().map(|k| &self.leaves[k])
+    }
+}
+
+/// Leaves `base + j·stride`, j < `leaves.len()`, under one proof: the
+/// coset a query opens in a layer folded by m, with stride n/m, or with
+/// stride 1 a block of adjacent leaves. The indices follow from `base`
+/// and `stride`, so only the siblings of a [`MerkleMultiOpening`] are
+/// sent.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(bound = "N: ark_goldilocks::serde_canonical::Canonical"))]
+pub struct MerkleCosetOpening<N = F> {
+    pub base: u64,
+    pub stride: u64,
+    /// Ordered by j.
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub leaves: Vec<N>,
+    /// From the leaf level up.
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+    pub path: Vec<Vec<N>>,
+}
+
+impl<N> MerkleCosetOpening<N> {
+    /// Index of every opened leaf, in order, or `None` if they overflow
+    /// or repeat.
+    pub fn indices(&self) -> Option<Vec<u64>> {
+        if self.stride == 0 && self.leaves.len() > 1 {
+            return None;
+        }
+        (0..self.leaves.len() as u64)
+            .map(|j| j.checked_mul(self.stride)?.checked_add(self.base))
+            .collect()
+    }
+}
+
+/// =======================
+/// Merkle tree
+/// =======================
+
+pub struct MerkleTreeChannel<N = F> {
+    cfg: MerkleChannelCfg,
+    levels: Vec<Vec<N>>,
+    salt_seed: Option<[u8; 32]>,
+}
+
+/// Salt of leaf `index` in a [hiding](MerkleChannelCfg::hiding) tree, as
+/// `utils::salt_for_node` derives node salts but over SHA3-256:
+/// `H("MT-SALT" || level || index || seed)` with level 0, reduced mod p.
+/// `seed` is the prover's secret.
+pub fn leaf_salt(seed: &[u8; 32], index: u64) -> F {
+    let mut h = Sha3_256::new();
+    Digest::update(&mut h, b"MT-SALT");
+    Digest::update(&mut h, 0u64.to_le_bytes());
+    Digest::update(&mut h, index.to_le_bytes());
+    Digest::update(&mut h, seed);
+    let wide = u128::from_le_bytes(h.finalize()[..16].try_into().unwrap());
+    F::from((wide % F::MODULUS.0[0] as u128) as u64)
+}
+
+impl<N: MerkleNode> MerkleTreeChannel<N> {
+    /// Panics if `cfg.digest` is not `N`'s width or `cfg.hash` cannot
+    /// produce `N`.
+    pub fn new(cfg: MerkleChannelCfg, _trace_hash: [u8; 32]) -> Self {
+        if let Some(e) = cfg.node_error::<N>() {
+            panic!("{e}");
+        }
+        Self {
+            cfg,
+            levels: Vec::new(),
+            salt_seed: None,
+        }
+    }
+
+    /// Seed the leaf salts of a hiding tree. Panics if `cfg.hiding` is off.
+    pub fn with_salt_seed(self, seed: [u8; 32]) -> Self {
+        assert!(self.cfg.hiding, "salt seed for a tree without hiding");
+        Self {
+            salt_seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Salt of leaf `index`, if the tree is hiding. Panics if it has no seed.
+    fn salt(&self, index: u64) -> Option<F> {
+        self.cfg.hiding.then(|| {
+            let seed = self.salt_seed.as_ref().expect("hiding tree without a salt seed");
+            leaf_salt(seed, index)
+        })
+    }
+
+    fn compress(&self, ds: DsLabel, children: &[N]) -> N {
+        self.cfg.compress(ds, children)
+    }
+
+    /// ✅ Generic leaf: caller flattens values. Drops the levels of an
+    /// earlier `finalize`; see [`append`](Self::append) to keep them.
+    pub fn push_leaf(&mut self, values: &[F]) {
+        if self.levels.is_empty() {
+            self.levels.push(Vec::new());
+        }
+        self.levels.truncate(1);
+
+        let idx = self.levels[0].len() as u64;
+        let leaf = self.leaf(idx, values);
+        self.levels[0].push(leaf);
+    }
+
+    /// [`push_leaf`](Self::push_leaf) of `leaf`'s [`LeafEncoder`] values.
+    pub fn push_encoded<L: LeafEncoder + ?Sized>(&mut self, leaf: &L) {
+        self.push_leaf(&leaf.leaf_values());
+    }
+
+    fn leaf(&self, idx: u64, values: &[F]) -> N {
+        match self.salt(idx) {
+            Some(salt) => Self::salted_leaf_digest(&self.cfg, idx, values, salt),
+            None => Self::leaf_digest(&self.cfg, idx, values),
+        }
+    }
+
+    /// Whether the levels reach the root: a single leaf needs no
+    /// `finalize` unless the tree is capped.
+    fn is_finalized(&self) -> bool {
+        self.levels.last().is_some_and(|top| top.len() == 1)
+            && self.levels.len() > self.cfg.min_top_level()
+    }
+
+    /// Digest `push_leaf` stores for `values` at position `index`, so a
+    /// verifier can bind opened leaf contents to `MerkleOpening::leaf`;
+    /// [`verify_value_opening`](Self::verify_value_opening) checks an
+    /// opening together with its raw values this way. The Pallas crate's
+    /// `compute_leaf_static`.
+    #[doc(alias = "compute_leaf_static")]
+    pub fn leaf_digest(cfg: &MerkleChannelCfg, index: u64, values: &[F]) -> N {
+        let ds = DsLabel {
+            arity: cfg.layer_arities[0],
+            level: LEAF_LEVEL_DS,
+            position: index,
+            tree_label: cfg.tree_label,
+        };
+        cfg.compress(ds, values)
+    }
+
+    /// As [`leaf_digest`](Self::leaf_digest) for a hiding tree: the salt is
+    /// hashed after the values.
+    pub fn salted_leaf_digest(cfg: &MerkleChannelCfg, index: u64, values: &[F], salt: F) -> N {
+        let salted: Vec<F> = values.iter().copied().chain([salt]).collect();
+        Self::leaf_digest(cfg, index, &salted)
+    }
+
+    /// Leaf digest an opening's values and salt stand for, or `None` if
+    /// the salt is missing from a hiding tree or present in another one.
+    fn value_leaf(cfg: &MerkleChannelCfg, opening: &MerkleValueOpening<N>) -> Option<N> {
+        let index = opening.opening.index;
+        match (cfg.hiding, opening.salt) {
+            (false, None) => Some(Self::leaf_digest(cfg, index, &opening.values)),
+            (true, Some(salt)) => Some(Self::salted_leaf_digest(cfg, index, &opening.values, salt)),
+            _ => None,
+        }
+    }
+
+    /// Hash the leaves up to the root. Fails without leaves or with more
+    /// than the config has room for.
+    pub fn finalize(&mut self) -> Result<N, MerkleError> {
+        let n = self.levels.first().map_or(0, Vec::len) as u64;
+        if n == 0 {
+            return Err(MerkleError::NotFinalized);
+        }
+        if let Some(expected) = self.cfg.leaf_count.filter(|&c| c != n) {
+            return Err(MerkleError::LeafCountMismatch { expected, got: n });
+        }
+        if !self.cfg.covers(n - 1) {
+            return Err(self.cfg.index_error(n - 1));
+        }
+        self.levels.truncate(1);
+
+        let min_top = self.cfg.min_top_level();
+        let mut level = 0;
+        while self.levels[level].len() > 1 || level < min_top {
+            let arity = self.cfg.layer_arities[level];
+            let mut cur = self.levels[level].clone();
+
+            if cur.len() % arity != 0 {
+                let pad = self.cfg.pad_node(level, &cur);
+                cur.resize(cur.len() + (arity - cur.len() % arity), pad);
+            }
+
+            let parents: Vec<N> = cur
+                .chunks(arity)
+                .enumerate()
+                .map(|(i, c)| {
+                    let ds = DsLabel {
+                        arity,
+                        level: level as u32 + 1,
+                        position: i as u64,
+                        tree_label: self.cfg.tree_label,
+                    };
+                    self.compress(ds, c)
+                })
+                .collect();
+
+            self.levels.push(parents);
+            level += 1;
+        }
+        Ok(self.cfg.seal(self.levels[level][0]))
+    }
+
+    /// Add a leaf to a finalized tree and return the new root, the one
+    /// [`finalize`](Self::finalize) would give over all the leaves. Only
+    /// the last group of each level can change, so this rehashes one group
+    /// per level instead of the tree.
+    ///
+    /// Panics before `finalize`, past the tree's capacity, or if the config
+    /// fixes the leaf count.
+    pub fn append(&mut self, values: &[F]) -> N {
+        assert!(self.is_finalized(), "append before finalize");
+        assert!(self.cfg.leaf_count.is_none(), "append to a tree of fixed leaf count");
+        let index = self.levels[0].len() as u64;
+        assert!(self.cfg.covers(index), "leaf {index} past the tree's capacity");
+        let leaf = self.leaf(index, values);
+        self.levels[0].push(leaf);
+
+        let min_top = self.cfg.min_top_level();
+        let mut level = 0;
+        while self.levels[level].len() > 1 || level < min_top {
+            let arity = self.cfg.layer_arities[level];
+            let nodes = &self.levels[level];
+            let position = (nodes.len() - 1) / arity;
+            let mut group = nodes[position * arity..].to_vec();
+            let pad = self.cfg.pad_node(level, nodes);
+            group.resize(arity, pad);
+            let ds = DsLabel {
+                arity,
+                level: level as u32 + 1,
+                position: position as u64,
+                tree_label: self.cfg.tree_label,
+            };
+            let parent = self.compress(ds, &group);
+            match self.levels.get_mut(level + 1) {
+                Some(up) if position < up.len() => up[position] = parent,
+                Some(up) => up.push(parent),
+                None => self.levels.push(alloc::vec![parent]),
+            }
+            level += 1;
+        }
+        self.levels[level][0]
+    }
+
+    /// [`finalize`](Self::finalize), committing to the cap instead: the
+    /// [`cap_len`](MerkleChannelCfg::cap_len) nodes on the cap level,
+    /// padded as the levels are. Just the top node without a cap. Only
+    /// [`open`](Self::open) stops at the cap; range and multi-openings
+    /// still reach the root.
+    pub fn finalize_cap(&mut self) -> Result<Vec<N>, MerkleError> {
+        self.finalize()?;
+        let level = self.cfg.cap_level().min(self.levels.len() - 1);
+        let nodes = &self.levels[level];
+        let pad = self.cfg.pad_node(level, nodes);
+        Ok((0..self.cfg.cap_len()).map(|i| nodes.get(i).copied().unwrap_or(pad)).collect())
+    }
+
+    /// Path of leaf `index` up to the root, or up to the cap in a capped
+    /// tree.
+    pub fn open(&self, index: usize) -> Result<MerkleOpening<N>, MerkleError> {
+        if !self.is_finalized() {
+            return Err(MerkleError::NotFinalized);
+        }
+        let leaves = self.levels[0].len();
+        if index >= leaves {
+            return Err(MerkleError::IndexOutOfRange {
+                index: index as u64,
+                len: leaves as u64,
+            });
+        }
+        let mut idx = index;
+        let mut path = Vec::new();
+        let top = match self.cfg.cap_height {
+            0 => self.levels.len() - 1,
+            _ => self.cfg.cap_level(),
+        };
+
+        for level in 0..top {
+            let nodes = &self.levels[level];
+            let arity = self.cfg.layer_arities[level];
+            let group_start = (idx / arity) * arity;
+
+            let mut group = Vec::with_capacity(arity);
+            for i in 0..arity {
+                let pos = group_start + i;
+                if pos < nodes.len() {
+                    group.push(nodes[pos]);
+                } else {
+                    group.push(self.cfg.pad_node(level, nodes));
+                }
+            }
+
+            let siblings = group
+                .iter()
+                .enumerate()
+                .filter_map(|(i, &x)| {
+                    if group_start + i != idx {
+                        Some(x)
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            path.push(siblings);
+            idx /= arity;
+        }
+
+        Ok(MerkleOpening {
+            leaf: self.levels[0][index],
+            path,
+            index: index as u64,
+        })
+    }
+
+    /// [`open`](Self::open) at each of `indices`, in order. The tree is only
+    /// read, so with the `parallel` feature the paths are built on the rayon
+    /// pool and come out identical to the sequential ones.
+    pub fn open_many(&self, indices: &[usize]) -> Result<Vec<MerkleOpening<N>>, MerkleError> {
+        #[cfg(feature = "parallel")]
+        {
+            indices.par_iter().map(|&i| self.open(i)).collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            indices.iter().map(|&i| self.open(i)).collect()
+        }
+    }
+
+    /// Open every leaf in `indices` with one proof, in any order and with
+    /// repeats; the opening lists each leaf once, by increasing index.
+    pub fn open_batch(&self, indices: &[usize]) -> MerkleMultiOpening<N> {
+        let leaves = &self.levels[0];
+        let mut sorted = indices.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert!(!sorted.is_empty(), "empty batch");
+        assert!(sorted[sorted.len() - 1] < leaves.len(), "index outside {} leaves", leaves.len());
+
+        let depth = self.levels.len() - 1;
+        let node = |level: usize, i: usize| {
+            let nodes = &self.levels[level];
+            nodes.get(i).copied().unwrap_or_else(|| self.cfg.pad_node(level, nodes))
+        };
+        MerkleMultiOpening {
+            path: multi::multi_siblings(&self.cfg.layer_arities[..depth], &sorted, node),
+            leaves: sorted.iter().map(|&i| leaves[i]).collect(),
+            indices: sorted.into_iter().map(|i| i as u64).collect(),
+        }
+    }
+
+    pub fn verify_multi_opening(
+        cfg: &MerkleChannelCfg,
+        root: N,
+        opening: &MerkleMultiOpening<N>,
+        _trace_hash: &[u8; 32],
+    ) -> bool {
+        Self::verify_multi(cfg, root, &opening.indices, &opening.leaves, &opening.path)
+    }
+
+    /// Open leaves `base + j·stride`, j < `m`, with one proof. `m` must be
+    /// positive, and `stride` too unless `m` is 1.
+    pub fn open_coset(&self, base: usize, stride: usize, m: usize) -> Result<MerkleCosetOpening<N>, MerkleError> {
+        if m == 0 || (stride == 0 && m > 1) {
+            return Err(MerkleError::EmptyCoset { leaves: m, stride });
+        }
+        if !self.is_finalized() {
+            return Err(MerkleError::NotFinalized);
+        }
+        let len = self.levels[0].len();
+        let last = (m - 1).checked_mul(stride).and_then(|d| d.checked_add(base));
+        match last {
+            Some(last) if last < len => {}
+            _ => {
+                return Err(MerkleError::IndexOutOfRange {
+                    index: last.map_or(u64::MAX, |l| l as u64),
+                    len: len as u64,
+                })
+            }
+        }
+        // Below `len` at the last leaf, so no index overflows.
+        let indices: Vec<usize> = (0..m).map(|j| base + j * stride).collect();
+        let batch = self.open_batch(&indices);
+        Ok(MerkleCosetOpening {
+            base: base as u64,
+            stride: stride as u64,
+            leaves: batch.leaves,
+            path: batch.path,
+        })
+    }
+
+    pub fn verify_coset_opening(
+        cfg: &MerkleChannelCfg,
+        root: N,
+        opening: &MerkleCosetOpening<N>,
+        _trace_hash: &[u8; 32],
+    ) -> bool {
+        opening
+            .indices()
+            .is_some_and(|indices| Self::verify_multi(cfg, root, &indices, &opening.leaves, &opening.path))
+    }
+
+    /// Root check shared by the multi-leaf openings; `indices` must be
+    /// strictly increasing.
+    fn verify_multi(cfg: &MerkleChannelCfg, root: N, indices: &[u64], leaves: &[N], path: &[Vec<N>]) -> bool {
+        if cfg.node_error::<N>().is_some()
+            || path.len() > cfg.layer_arities.len()
+            || !indices.last().is_some_and(|&last| cfg.admits(last))
+        {
+            return false;
+        }
+        let compress_at = |level: usize, position: u64, children: &[N]| {
+            let ds = DsLabel {
+                arity: cfg.layer_arities[level - 1],
+                level: level as u32,
+                position,
+                tree_label: cfg.tree_label,
+            };
+            cfg.compress(ds, children)
+        };
+        let top = multi::multi_root(&cfg.layer_arities[..path.len()], indices, leaves, path, compress_at);
+        top.map(|top| cfg.seal(top)) == Some(root)
+    }
+
+    /// Open leaves `start..start + len` with one proof.
+    pub fn open_range(&self, start: usize, len: usize) -> MerkleRangeOpening<N> {
+        let leaves = &self.levels[0];
+        assert!(
+            len > 0 && start + len <= leaves.len(),
+            "range {start}..{} outside {} leaves",
+            start + len,
+            leaves.len()
+        );
+
+        let depth = self.levels.len() - 1;
+        let node = |level: usize, i: usize| {
+            let nodes = &self.levels[level];
+            nodes.get(i).copied().unwrap_or_else(|| self.cfg.pad_node(level, nodes))
+        };
+        let path = range::range_siblings(&self.cfg.layer_arities[..depth], start, len, node)
+            .into_iter()
+            .map(|(left, right)| RangeSiblings { left, right })
+            .collect();
+
+        MerkleRangeOpening {
+            start: start as u64,
+            leaves: leaves[start..start + len].to_vec(),
+            path,
+        }
+    }
+
+    /// Open leaves `start..start + values.len()`, carrying the `values`
+    /// they were pushed with.
+    pub fn open_range_with_values(&self, start: usize, values: Vec<Vec<F>>) -> MerkleRangeValueOpening<N> {
+        let opening = self.open_range(start, values.len());
+        debug_assert!(
+            values
+                .iter()
+                .zip(&opening.leaves)
+                .enumerate()
+                .all(|(k, (v, leaf))| Self::leaf_digest(&self.cfg, (start + k) as u64, v) == *leaf),
+            "values do not match leaves {start}.."
+        );
+        MerkleRangeValueOpening { values, opening }
+    }
+
+    pub fn verify_range_opening(
+        cfg: &MerkleChannelCfg,
+        root: N,
+        opening: &MerkleRangeOpening<N>,
+        _trace_hash: &[u8; 32],
+    ) -> bool {
+        let last = (opening.leaves.len() as u64)
+            .checked_sub(1)
+            .and_then(|k| opening.start.checked_add(k));
+        if cfg.node_error::<N>().is_some()
+            || opening.path.len() > cfg.layer_arities.len()
+            || !last.is_some_and(|last| cfg.admits(last))
+        {
+            return false;
+        }
+        let siblings: Vec<range::RangeLevel<N>> = opening
+            .path
+            .iter()
+            .map(|s| (s.left.clone(), s.right.clone()))
+            .collect();
+        let compress_at = |level: usize, position: u64, children: &[N]| {
+            let ds = DsLabel {
+                arity: cfg.layer_arities[level - 1],
+                level: level as u32,
+                position,
+                tree_label: cfg.tree_label,
+            };
+            cfg.compress(ds, children)
+        };
+        let top = range::range_root(
+            &cfg.layer_arities[..siblings.len()],
+            opening.start,
+            &opening.leaves,
+            &siblings,
+            compress_at,
+        );
+        top.map(|top| cfg.seal(top)) == Some(root)
+    }
+
+    /// Verify the range and that each leaf digest is the leaf-labelled
+    /// hash of its values.
+    pub fn verify_range_value_opening(
+        cfg: &MerkleChannelCfg,
+        root: N,
+        opening: &MerkleRangeValueOpening<N>,
+        trace_hash: &[u8; 32],
+    ) -> bool {
+        let o = &opening.opening;
+        opening.values.len() == o.leaves.len()
+            && opening
+                .values
+                .iter()
+                .zip(&o.leaves)
+                .enumerate()
+                .all(|(k, (v, leaf))| *leaf == Self::leaf_digest(cfg, o.start + k as u64, v))
+            && Self::verify_range_opening(cfg, root, o, trace_hash)
+    }
+
+    /// Open leaf `index`, carrying the `values` it was pushed with.
+    pub fn open_with_values(&self, index: usize, values: Vec<F>) -> Result<MerkleValueOpening<N>, MerkleError> {
+        let opening = MerkleValueOpening {
+            opening: self.open(index)?,
+            values,
+            salt: self.salt(index as u64),
+        };
+        debug_assert_eq!(
+            Self::value_leaf(&self.cfg, &opening),
+            Some(self.levels[0][index]),
+            "values do not match leaf {index}"
+        );
+        Ok(opening)
+    }
+
+    /// [`open_with_values`](Self::open_with_values) for a leaf pushed with
+    /// [`push_encoded`](Self::push_encoded).
+    pub fn open_encoded<L: LeafEncoder + ?Sized>(
+        &self,
+        index: usize,
+        leaf: &L,
+    ) -> Result<MerkleValueOpening<N>, MerkleError> {
+        self.open_with_values(index, leaf.leaf_values())
+    }
+
+    /// Verify the path and that the leaf digest is the leaf-labelled hash
+    /// of `opening.values`, then `opening.salt` in a hiding tree, at
+    /// `opening.index`.
+    pub fn verify_value_opening(
+        cfg: &MerkleChannelCfg,
+        root: N,
+        opening: &MerkleValueOpening<N>,
+        trace_hash: &[u8; 32],
+    ) -> bool {
+        let o = &opening.opening;
+        Self::value_leaf(cfg, opening) == Some(o.leaf) && Self::verify_opening(cfg, root, o, trace_hash) == Ok(true)
+    }
+
+    /// Whether `opening` leads to `root`; an error if it could not come
+    /// from a tree of this config at all.
+    pub fn verify_opening(
+        cfg: &MerkleChannelCfg,
+        root: N,
+        opening: &MerkleOpening<N>,
+        _trace_hash: &[u8; 32],
+    ) -> Result<bool, MerkleError> {
+        Self::check_shape(cfg, opening)?;
+        Ok(Self::climb(cfg, opening).map(|top| cfg.seal(top)) == Some(root))
+    }
+
+    /// Node width, index range and, per level, sibling count.
+    fn check_shape(cfg: &MerkleChannelCfg, opening: &MerkleOpening<N>) -> Result<(), MerkleError> {
+        if let Some(e) = cfg.node_error::<N>() {
+            return Err(e);
+        }
+        let levels = cfg.layer_arities.len();
+        if opening.path.len() > levels {
+            return Err(MerkleError::WrongPathLength {
+                levels,
+                got: opening.path.len(),
+            });
+        }
+        if !cfg.admits(opening.index) {
+            return Err(cfg.index_error(opening.index));
+        }
+        let arities = cfg.layer_arities.iter().copied();
+        match arities.zip(&opening.path).enumerate().find(|(_, (a, s))| s.len() + 1 != *a) {
+            Some((level, (arity, siblings))) => Err(MerkleError::ArityMismatch {
+                level,
+                arity,
+                got: siblings.len(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// [`verify_opening`](Self::verify_opening) for any number of openings
+    /// of one tree, whose paths must all be as long. Nodes a checked path
+    /// has tied to the root are remembered, and each later path climbs only
+    /// until it meets one, so the levels where query paths merge are hashed
+    /// once per batch instead of once per opening. A leaf that an earlier
+    /// path already tied to the root is accepted without reading its path.
+    pub fn verify_openings(
+        cfg: &MerkleChannelCfg,
+        root: N,
+        openings: &[MerkleOpening<N>],
+        _trace_hash: &[u8; 32],
+    ) -> bool {
+        let Some(depth) = openings.first().map(|o| o.path.len()) else {
+            return true;
+        };
+        let mut known = BTreeMap::new();
+        openings.iter().all(|o| {
+            o.path.len() == depth
+                && Self::check_shape(cfg, o).is_ok()
+                && Self::climb_to_known(cfg, root, o, &mut known)
+        })
+    }
+
+    /// Verify a path that ends at the cap of a capped tree, against
+    /// [`finalize_cap`](Self::finalize_cap)'s nodes.
+    pub fn verify_cap_opening(
+        cfg: &MerkleChannelCfg,
+        cap: &[N],
+        opening: &MerkleOpening<N>,
+        _trace_hash: &[u8; 32],
+    ) -> bool {
+        if opening.path.len() != cfg.cap_level()
+            || cap.len() != cfg.cap_len()
+            || Self::check_shape(cfg, opening).is_err()
+        {
+            return false;
+        }
+        let below: u64 = cfg.layer_arities[..cfg.cap_level()].iter().map(|&a| a as u64).product();
+        Self::climb(cfg, opening) == Some(cap[(opening.index / below) as usize])
+    }
+
+    /// [`verify_value_opening`](Self::verify_value_opening) against a cap.
+    pub fn verify_cap_value_opening(
+        cfg: &MerkleChannelCfg,
+        cap: &[N],
+        opening: &MerkleValueOpening<N>,
+        trace_hash: &[u8; 32],
+    ) -> bool {
+        let o = &opening.opening;
+        Self::value_leaf(cfg, opening) == Some(o.leaf) && Self::verify_cap_opening(cfg, cap, o, trace_hash)
+    }
+
+    /// The node `opening.path` leads to from the leaf.
+    fn climb(cfg: &MerkleChannelCfg, opening: &MerkleOpening<N>) -> Option<N> {
+        let mut cur = opening.leaf;
+        let mut idx = opening.index;
+
+        for (level, siblings) in opening.path.iter().enumerate() {
+            let arity = cfg.layer_arities[level];
+            let children = Self::group(arity, idx, cur, siblings)?;
+            cur = Self::parent(cfg, level, idx, &children);
+            idx /= arity as u64;
+        }
+
+        Some(cur)
+    }
+
+    /// Climb `opening` until it meets a node in `known` or reaches `root`,
+    /// then add every node the climb passed, siblings included, as they are
+    /// now tied to the root too.
+    fn climb_to_known(
+        cfg: &MerkleChannelCfg,
+        root: N,
+        opening: &MerkleOpening<N>,
+        known: &mut BTreeMap<(usize, u64), N>,
+    ) -> bool {
+        let mut cur = opening.leaf;
+        let mut idx = opening.index;
+        let mut passed = Vec::new();
+
+        for level in 0..=opening.path.len() {
+            if let Some(&node) = known.get(&(level, idx)) {
+                if node != cur {
+                    return false;
+                }
+                known.extend(passed);
+                return true;
+            }
+            let Some(siblings) = opening.path.get(level) else {
+                if idx != 0 || cfg.seal(cur) != root {
+                    return false;
+                }
+                passed.push(((level, 0), cur));
+                known.extend(passed);
+                return true;
+            };
+            let arity = cfg.layer_arities[level];
+            let Some(children) = Self::group(arity, idx, cur, siblings) else {
+                return false;
+            };
+            let first = idx - idx % arity as u64;
+            passed.extend((first..).zip(&children).map(|(i, &x)| ((level, i), x)));
+            cur = Self::parent(cfg, level, idx, &children);
+            idx /= arity as u64;
+        }
+        false
+    }
+
+    /// The group of node `idx`, with `cur` in its slot and `siblings` in
+    /// the others.
+    fn group(arity: usize, idx: u64, cur: N, siblings: &[N]) -> Option<Vec<N>> {
+        let pos = (idx % arity as u64) as usize;
+        let mut sibs = siblings.iter();
+        (0..arity)
+            .map(|i| if i == pos { Some(cur) } else { sibs.next().copied() })
+            .collect()
+    }
+
+    fn parent(cfg: &MerkleChannelCfg, level: usize, idx: u64, children: &[N]) -> N {
+        let arity = children.len();
+        let ds = DsLabel {
+            arity,
+            level: level as u32 + 1,
+            position: idx / arity as u64,
+            tree_label: cfg.tree_label,
+        };
+        cfg.compress(ds, children)
+    }
+}
+
+// =======================
+// Meta-commitment
+// =======================
+
+const META_LEAF_DS: &[u8] = b"MERKLE/META/LEAF";
+const META_NODE_DS: &[u8] = b"MERKLE/META/NODE";
+
+/// Binary SHA3-256 tree over a short list of roots, with full 32-byte
+/// digests so the result can stand in for a whole proof's commitments.
+/// Odd levels carry their last node up unchanged.
+pub fn meta_root<N: MerkleNode>(roots: &[N]) -> [u8; 32] {
+    let mut level: Vec<[u8; 32]> = roots
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            let mut h = Sha3_256::new();
+            Digest::update(&mut h, META_LEAF_DS);
+            Digest::update(&mut h, (roots.len() as u64).to_le_bytes());
+            Digest::update(&mut h, (i as u64).to_le_bytes());
+            Digest::update(&mut h, r.to_bytes());
+            h.finalize().into()
+        })
+        .collect();
+
+    if level.is_empty() {
+        let mut h = Sha3_256::new();
+        Digest::update(&mut h, META_LEAF_DS);
+        return h.finalize().into();
+    }
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [l, r] => {
+                    let mut h = Sha3_256::new();
+                    Digest::update(&mut h, META_NODE_DS);
+                    Digest::update(&mut h, l);
+                    Digest::update(&mut h, r);
+                    h.finalize().into()
+                }
+                [only] => *only,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn for_leaves_matches_minimal_depth() {
+        assert_eq!(MerkleChannelCfg::for_leaves(1, 4, 0).layer_arities, vec![4]);
+        assert_eq!(
+            MerkleChannelCfg::for_leaves(16, 4, 0).layer_arities,
+            vec![4, 4]
+        );
+        assert_eq!(
+            MerkleChannelCfg::for_leaves(17, 4, 0).layer_arities,
+            vec![4, 4, 4]
+        );
+        assert_eq!(
+            MerkleChannelCfg::for_leaves(8, 2, 3).tree_label,
+            3
+        );
+    }
+
+    #[test]
+    fn arity_policies_follow_the_leaf_count() {
+        let up_to = |leaves, max| ArityPolicy::UpTo(max).arity(leaves);
+        assert_eq!(up_to(1 << 12, 16), 16);
+        assert_eq!(up_to(1 << 12, 1 << 10), 128);
+        assert_eq!(up_to(48, 64), 16);
+        assert_eq!(up_to(6, 8), 2);
+        assert_eq!(up_to(7, 8), 2);
+        assert_eq!(up_to(64, 1), 2);
+        assert_eq!(
+            MerkleChannelCfg::for_leaves(96, ArityPolicy::UpTo(8), 0).layer_arities,
+            vec![8, 8, 8]
+        );
+
+        // Sized for 16 leaves: a 17th does not fit.
+        let mut tree = MerkleTreeChannel::<F>::new(MerkleChannelCfg::for_leaves(16, 4, 0), [0u8; 32]);
+        for i in 0..17u64 {
+            tree.push_leaf(&[F::from(i)]);
+        }
+        assert_eq!(tree.finalize(), Err(MerkleError::IndexOutOfRange { index: 16, len: 16 }));
+    }
+
+    #[test]
+    fn builder_rejects_bad_shapes() {
+        let b = MerkleChannelCfg::builder;
+        assert_eq!(
+            b(0).fill_with(2).build().unwrap_err(),
+            MerkleCfgError::NoLeaves
+        );
+        assert_eq!(
+            b(8).level(4).level(1).build().unwrap_err(),
+            MerkleCfgError::ArityTooSmall { level: 1, arity: 1 }
+        );
+        assert_eq!(
+            b(8).level(2).fill_with(0).build().unwrap_err(),
+            MerkleCfgError::ArityTooSmall { level: 1, arity: 0 }
+        );
+        assert_eq!(
+            b(100).levels(4, 3).build().unwrap_err(),
+            MerkleCfgError::TooShallow {
+                leaves: 100,
+                capacity: 64
+            }
+        );
+        assert_eq!(
+            b(100).levels(4, 3).level(2).build().unwrap().capacity(),
+            128
+        );
+    }
+
+    #[test]
+    fn mixed_arity_tree_opens_and_verifies() {
+        let n = 50;
+        let cfg = MerkleChannelCfg::builder(n)
+            .level(8)
+            .fill_with(3)
+            .build()
+            .unwrap();
+        assert_eq!(cfg.layer_arities, vec![8, 3, 3]);
+
+        let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+        for i in 0..n {
+            tree.push_leaf(&[F::from(i as u64)]);
+        }
+        let root = tree.finalize().unwrap();
+        for i in [0, 7, 8, 49] {
+            assert_eq!(
+                MerkleTreeChannel::verify_opening(&cfg, root, &tree.open(i).unwrap(), &[0u8; 32]),
+                Ok(true)
+            );
+        }
+    }
+
+    #[test]
+    fn open_many_matches_single_openings() {
+        let n = 50;
+        let cfg = MerkleChannelCfg::builder(n).level(8).fill_with(3).build().unwrap();
+        let mut tree = MerkleTreeChannel::<F>::new(cfg, [0u8; 32]);
+        for i in 0..n {
+            tree.push_leaf(&[F::from(i as u64)]);
+        }
+        tree.finalize().unwrap();
+
+        let indices = [49, 0, 7, 7, 8, 23];
+        let many = tree.open_many(&indices).unwrap();
+        assert_eq!(many.len(), indices.len());
+        for (o, &i) in many.iter().zip(&indices) {
+            let single = tree.open(i).unwrap();
+            assert_eq!((o.index, o.leaf, &o.path), (single.index, single.leaf, &single.path));
+        }
+    }
+
+    #[test]
+    fn multi_opening_merges_single_openings() {
+        let n = 50;
+        let cfg = MerkleChannelCfg::builder(n).level(8).fill_with(3).build().unwrap();
+        let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+        for i in 0..n {
+            tree.push_leaf(&[F::from(i as u64)]);
+        }
+        let root = tree.finalize().unwrap();
+
+        let indices = [49, 0, 7, 7, 8, 23, 48];
+        let mo = tree.open_batch(&indices);
+        assert_eq!(mo.indices, vec![0, 7, 8, 23, 48, 49]);
+        assert!(MerkleTreeChannel::verify_multi_opening(&cfg, root, &mo, &[0u8; 32]));
+        for &i in &indices {
+            assert_eq!(mo.leaf(i as u64), Some(&tree.open(i).unwrap().leaf));
+        }
+        assert_eq!(mo.leaf(1), None);
+
+        // Fewer siblings than the single paths would carry.
+        let single: usize = mo
+            .indices
+            .iter()
+            .map(|&i| tree.open(i as usize).unwrap().path.iter().map(Vec::len).sum::<usize>())
+            .sum();
+        let merged: usize = mo.path.iter().map(Vec::len).sum();
+        assert!(merged < single, "{merged} vs {single}");
+        assert!(MerkleTreeChannel::verify_multi_opening(&cfg, root, &tree.open_batch(&[49]), &[0u8; 32]));
+
+        let mut bad = mo.clone();
+        bad.leaves[2] += F::from(1u64);
+        assert!(!MerkleTreeChannel::verify_multi_opening(&cfg, root, &bad, &[0u8; 32]));
+
+        let mut bad = mo.clone();
+        bad.indices[3] += 1;
+        assert!(!MerkleTreeChannel::verify_multi_opening(&cfg, root, &bad, &[0u8; 32]));
+
+        let mut bad = mo.clone();
+        bad.indices.swap(0, 1);
+        bad.leaves.swap(0, 1);
+        assert!(!MerkleTreeChannel::verify_multi_opening(&cfg, root, &bad, &[0u8; 32]));
+
+        let mut bad = mo.clone();
+        bad.path[1].push(F::from(0u64));
+        assert!(!MerkleTreeChannel::verify_multi_opening(&cfg, root, &bad, &[0u8; 32]));
+
+        let other = cfg.clone().with_label(1);
+        assert!(!MerkleTreeChannel::verify_multi_opening(&other, root, &mo, &[0u8; 32]));
+    }
+
+    #[test]
+    fn openings_roundtrip_through_binary_layout() {
+        fn tree<N: MerkleNode>(cfg: &MerkleChannelCfg) -> MerkleTreeChannel<N> {
+            let mut tree = MerkleTreeChannel::new(cfg.clone(), [0u8; 32]);
+            for i in 0..50u64 {
+                tree.push_leaf(&[F::from(i), F::from(i * i)]);
+            }
+            tree.finalize().unwrap();
+            tree
+        }
+        fn check<N: MerkleNode>(cfg: MerkleChannelCfg) {
+            let tree = tree::<N>(&cfg);
+            let o = tree.open(23).unwrap();
+            let bytes = o.encode();
+            assert_eq!(bytes.len(), 8 + N::WIDTH.bytes() + 4 + 3 * 4 + (7 + 2 + 2) * N::WIDTH.bytes());
+            let back = MerkleOpening::<N>::decode(&bytes).unwrap();
+            assert_eq!((back.index, back.leaf, &back.path), (o.index, o.leaf, &o.path));
+
+            let vo = tree.open_with_values(9, vec![F::from(9u64), F::from(81u64)]).unwrap();
+            let back = MerkleValueOpening::<N>::decode(&vo.encode()).unwrap();
+            assert_eq!((&back.values, back.opening.leaf), (&vo.values, vo.opening.leaf));
+
+            let mo = tree.open_batch(&[49, 0, 7, 8, 23]);
+            let back = MerkleMultiOpening::<N>::decode(&mo.encode()).unwrap();
+            assert_eq!((&back.indices, &back.leaves, &back.path), (&mo.indices, &mo.leaves, &mo.path));
+
+            // Exactly one encoding per opening.
+            let bytes = mo.encode();
+            let decode = MerkleMultiOpening::<N>::decode;
+            assert_eq!(decode(&bytes[..bytes.len() - 1]).err(), Some(DecodeError::Truncated));
+            let mut long = bytes.clone();
+            long.push(0);
+            assert_eq!(decode(&long).err(), Some(DecodeError::TrailingBytes));
+            let mut forged = bytes;
+            forged[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert_eq!(decode(&forged).err(), Some(DecodeError::Truncated));
+        }
+
+        let cfg = MerkleChannelCfg::builder(50).level(8).fill_with(3).build().unwrap();
+        check::<F>(cfg.clone());
+        check::<Digest256>(cfg.with_digest(DigestWidth::Digest256));
+
+        // Field nodes and values must be below p.
+        let mut bytes = tree::<F>(&MerkleChannelCfg::for_leaves(50, 4, 0)).open(3).unwrap().encode();
+        bytes[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(MerkleOpening::<F>::decode(&bytes).err(), Some(DecodeError::NonCanonical));
+        let opening = MerkleOpening { leaf: F::from(0u64), path: vec![], index: 0 };
+        let mut bytes = MerkleValueOpening { values: vec![F::from(1u64)], salt: None, opening }.encode();
+        bytes[4..12].copy_from_slice(&F::MODULUS.0[0].to_le_bytes());
+        assert_eq!(MerkleValueOpening::<F>::decode(&bytes).err(), Some(DecodeError::NonCanonical));
+    }
+
+    #[cfg(feature = "serde1")]
+    #[test]
+    fn openings_roundtrip_through_serde() {
+        let cfg = MerkleChannelCfg::builder(50).level(8).fill_with(3).build().unwrap();
+        let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+        for i in 0..50u64 {
+            tree.push_leaf(&[F::from(i)]);
+        }
+        let root = tree.finalize().unwrap();
+
+        let o: MerkleOpening = bincode::deserialize(&bincode::serialize(&tree.open(17).unwrap()).unwrap()).unwrap();
+        assert_eq!(MerkleTreeChannel::verify_opening(&cfg, root, &o, &[0u8; 32]), Ok(true));
+        let vo = tree.open_with_values(17, vec![F::from(17u64)]).unwrap();
+        let vo: MerkleValueOpening = bincode::deserialize(&bincode::serialize(&vo).unwrap()).unwrap();
+        assert!(MerkleTreeChannel::verify_value_opening(&cfg, root, &vo, &[0u8; 32]));
+        let mo = tree.open_batch(&[3, 17, 40]);
+        let mo: MerkleMultiOpening = bincode::deserialize(&bincode::serialize(&mo).unwrap()).unwrap();
+        assert!(MerkleTreeChannel::verify_multi_opening(&cfg, root, &mo, &[0u8; 32]));
+    }
+
+    #[test]
+    fn capped_openings_stop_at_the_cap() {
+        let n = 50;
+        let cfg = MerkleChannelCfg::builder(n).level(4).fill_with(2).cap_height(2).build().unwrap();
+        assert_eq!(cfg.layer_arities, vec![4, 2, 2, 2, 2]);
+        assert_eq!((cfg.cap_level(), cfg.cap_len()), (3, 4));
+
+        let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+        let mut full = MerkleTreeChannel::<F>::new(cfg.clone().with_cap_height(0), [0u8; 32]);
+        for i in 0..n as u64 {
+            tree.push_leaf(&[F::from(i)]);
+            full.push_leaf(&[F::from(i)]);
+        }
+        let cap = tree.finalize_cap().unwrap();
+        let root = full.finalize().unwrap();
+        assert_eq!(cap.len(), 4);
+
+        for i in [0, 15, 16, 31, 32, 49] {
+            let vo = tree.open_with_values(i, vec![F::from(i as u64)]).unwrap();
+            assert_eq!(vo.opening.path.len(), 3);
+            assert_eq!(vo.opening.path[..], full.open(i).unwrap().path[..3]);
+            assert!(MerkleTreeChannel::verify_cap_value_opening(&cfg, &cap, &vo, &[0u8; 32]));
+            assert_eq!(MerkleTreeChannel::verify_opening(&cfg, root, &vo.opening, &[0u8; 32]), Ok(false));
+
+            // The right path against the wrong cap node.
+            let mut moved = vo.opening.clone();
+            moved.index = (moved.index + 16) % 64;
+            assert!(!MerkleTreeChannel::verify_cap_opening(&cfg, &cap, &moved, &[0u8; 32]));
+        }
+
+        // Too short a cap, or a full path, does not verify.
+        let o = tree.open(7).unwrap();
+        assert!(!MerkleTreeChannel::verify_cap_opening(&cfg, &cap[..2], &o, &[0u8; 32]));
+        assert!(!MerkleTreeChannel::verify_cap_opening(&cfg, &cap, &full.open(7).unwrap(), &[0u8; 32]));
+
+        // Without a cap, the cap is the root.
+        assert_eq!(full.finalize_cap().unwrap(), vec![root]);
+        assert_eq!(
+            MerkleChannelCfg::builder(n).fill_with(2).cap_height(7).build().unwrap_err(),
+            MerkleCfgError::CapTooHigh { cap_height: 7, levels: 6 }
+        );
+    }
+
+    #[test]
+    fn hiding_trees_reveal_only_opened_salts() {
+        let n = 20;
+        let cfg = MerkleChannelCfg::builder(n).fill_with(4).hiding(true).build().unwrap();
+        let plain_cfg = cfg.clone().with_hiding(false);
+        let seed = [7u8; 32];
+        let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]).with_salt_seed(seed);
+        let mut plain = MerkleTreeChannel::<F>::new(plain_cfg.clone(), [0u8; 32]);
+        let mut other = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]).with_salt_seed([8u8; 32]);
+        let mut stream = MerkleStreamBuilder::<F>::new(cfg.clone()).with_salt_seed(seed);
+        for i in 0..n as u64 {
+            tree.push_leaf(&[F::from(i)]);
+            plain.push_leaf(&[F::from(i)]);
+            other.push_leaf(&[F::from(i)]);
+            stream.push_leaf(&[F::from(i)]);
+        }
+        let root = tree.finalize().unwrap();
+        assert_ne!(root, plain.finalize().unwrap());
+        assert_ne!(root, other.finalize().unwrap());
+        assert_eq!(stream.finish(), root);
+
+        let vo = tree.open_with_values(5, vec![F::from(5u64)]).unwrap();
+        assert_eq!(vo.salt, Some(leaf_salt(&seed, 5)));
+        assert!(MerkleTreeChannel::verify_value_opening(&cfg, root, &vo, &[0u8; 32]));
+        assert_eq!(MerkleValueOpening::<F>::decode(&vo.encode()).unwrap().salt, vo.salt);
+
+        // A missing or wrong salt, or a verifier expecting none, fails.
+        let mut unsalted = vo.clone();
+        unsalted.salt = None;
+        assert!(!MerkleTreeChannel::verify_value_opening(&cfg, root, &unsalted, &[0u8; 32]));
+        let mut wrong = vo.clone();
+        wrong.salt = Some(leaf_salt(&seed, 6));
+        assert!(!MerkleTreeChannel::verify_value_opening(&cfg, root, &wrong, &[0u8; 32]));
+        assert!(!MerkleTreeChannel::verify_value_opening(&plain_cfg, root, &vo, &[0u8; 32]));
+    }
+
+    #[test]
+    fn appended_roots_match_rebuilds() {
+        let uncapped = MerkleChannelCfg::new(vec![3, 2, 2, 2, 2], 4);
+        for cfg in [uncapped.clone(), uncapped.with_cap_height(2)] {
+            let leaf = |i: u64| [F::from(i), F::from(i + 100)];
+            let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+            tree.push_leaf(&leaf(0));
+            tree.finalize().unwrap();
+            for n in 2..=48u64 {
+                let root = tree.append(&leaf(n - 1));
+                let mut rebuilt = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+                (0..n).for_each(|i| rebuilt.push_leaf(&leaf(i)));
+                assert_eq!(root, rebuilt.finalize().unwrap(), "{n} leaves, cap {}", cfg.cap_height);
+                assert_eq!(tree.levels, rebuilt.levels);
+            }
+        }
+    }
+
+    #[test]
+    fn batched_openings_share_checked_nodes() {
+        let n = 60;
+        let cfg = MerkleChannelCfg::builder(n).level(4).fill_with(2).build().unwrap();
+        let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+        (0..n as u64).for_each(|i| tree.push_leaf(&[F::from(i)]));
+        let root = tree.finalize().unwrap();
+        let batch: Vec<_> = [9, 1, 2, 59, 9, 33, 0].iter().map(|&i| tree.open(i).unwrap()).collect();
+        let ok = |openings: &[MerkleOpening]| MerkleTreeChannel::verify_openings(&cfg, root, openings, &[0u8; 32]);
+
+        assert!(ok(&batch));
+        assert!(ok(&[]));
+        assert!(!MerkleTreeChannel::verify_openings(&cfg, root + F::from(1u64), &batch, &[0u8; 32]));
+
+        // A later path is still checked where it meets an earlier one: a
+        // wrong leaf, a wrong sibling, or a leaf moved onto an opened
+        // sibling's slot.
+        let mut leaf = batch.clone();
+        leaf[2].leaf += F::from(1u64);
+        assert!(!ok(&leaf));
+        let mut sibling = batch.clone();
+        sibling[5].path[0][1] += F::from(1u64);
+        assert!(!ok(&sibling));
+
+        // Leaf 0 was a sibling on leaf 1's path, so its own path is unread.
+        let mut unread = batch.clone();
+        unread[6].path[0][1] += F::from(1u64);
+        assert!(ok(&unread));
+        let mut moved = batch.clone();
+        moved[2].index = 3;
+        assert!(!ok(&moved));
+
+        // Paths must all reach the root.
+        let mut short = batch.clone();
+        short[3].path.pop();
+        assert!(!ok(&short));
+    }
+
+    #[test]
+    fn misuse_is_an_error_not_a_failed_check() {
+        let cfg = MerkleChannelCfg::new(vec![4, 2], 1);
+        let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+        assert_eq!(tree.finalize(), Err(MerkleError::NotFinalized));
+        (0..6u64).for_each(|i| tree.push_leaf(&[F::from(i)]));
+        assert_eq!(tree.open(0).unwrap_err(), MerkleError::NotFinalized);
+        let root = tree.finalize().unwrap();
+        assert_eq!(tree.open(6).unwrap_err(), MerkleError::IndexOutOfRange { index: 6, len: 6 });
+
+        let check = |o: &MerkleOpening| MerkleTreeChannel::verify_opening(&cfg, root, o, &[0u8; 32]);
+        let opening = tree.open(5).unwrap();
+        assert_eq!(check(&opening), Ok(true));
+        let mut forged = opening.clone();
+        forged.path[1][0] += F::from(1u64);
+        assert_eq!(check(&forged), Ok(false));
+        let mut long = opening.clone();
+        long.path.push(vec![root]);
+        assert_eq!(check(&long), Err(MerkleError::WrongPathLength { levels: 2, got: 3 }));
+        let mut short = opening.clone();
+        short.path[0].pop();
+        assert_eq!(check(&short), Err(MerkleError::ArityMismatch { level: 0, arity: 4, got: 2 }));
+
+        // New leaves drop the old levels until the next finalize.
+        tree.push_leaf(&[F::from(6u64)]);
+        assert_eq!(tree.open(0).unwrap_err(), MerkleError::NotFinalized);
+        (7..9u64).for_each(|i| tree.push_leaf(&[F::from(i)]));
+        assert_eq!(tree.finalize(), Err(MerkleError::IndexOutOfRange { index: 8, len: 8 }));
+    }
+
+    #[test]
+    fn padding_and_bound_leaf_counts() {
+        let n = 5;
+        let th = [0u8; 32];
+        let mut roots = Vec::new();
+        for padding in [Padding::DuplicateLast, Padding::ZeroLeaf, Padding::FixedDigest] {
+            for bind in [false, true] {
+                let builder = MerkleChannelCfg::builder(n).level(4).fill_with(2).padding(padding);
+                let cfg = if bind { builder.bind_leaf_count() } else { builder }.build().unwrap();
+                let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), th);
+                let mut stream = MerkleStreamBuilder::<F>::new(cfg.clone());
+                for i in 0..n as u64 {
+                    tree.push_leaf(&[F::from(i)]);
+                    stream.push_leaf(&[F::from(i)]);
+                }
+                let root = tree.finalize().unwrap();
+                assert_eq!(stream.finish(), root);
+                roots.push(root);
+
+                let all: Vec<usize> = (0..n).collect();
+                let openings = tree.open_many(&all).unwrap();
+                assert!(openings.iter().all(|o| MerkleTreeChannel::verify_opening(&cfg, root, o, &th) == Ok(true)));
+                assert!(MerkleTreeChannel::verify_openings(&cfg, root, &openings, &th));
+                assert!(MerkleTreeChannel::verify_multi_opening(&cfg, root, &tree.open_batch(&[1, 4]), &th));
+                assert!(MerkleTreeChannel::verify_range_opening(&cfg, root, &tree.open_range(3, 2), &th));
+
+                // The padding slot after leaf 4, holding the pad node,
+                // verifies as a leaf unless the leaf count is bound.
+                let last = &openings[n - 1];
+                let phantom = MerkleOpening {
+                    index: n as u64,
+                    leaf: cfg.pad_node(0, &[last.leaf]),
+                    path: {
+                        let mut path = last.path.clone();
+                        path[0][0] = last.leaf;
+                        path
+                    },
+                };
+                let verdict = MerkleTreeChannel::verify_opening(&cfg, root, &phantom, &th);
+                match bind {
+                    false => assert_eq!(verdict, Ok(true), "{padding:?}"),
+                    true => assert_eq!(verdict, Err(MerkleError::IndexOutOfRange { index: 5, len: 5 })),
+                }
+            }
+        }
+        // Every policy, bound or not, gives its own root.
+        assert!(roots.iter().enumerate().all(|(i, r)| !roots[..i].contains(r)));
+
+        let cfg = MerkleChannelCfg::builder(n).fill_with(2).bind_leaf_count().build().unwrap();
+        let mut tree = MerkleTreeChannel::<F>::new(cfg, th);
+        (0..4u64).for_each(|i| tree.push_leaf(&[F::from(i)]));
+        assert_eq!(tree.finalize(), Err(MerkleError::LeafCountMismatch { expected: 5, got: 4 }));
+    }
+
+    #[test]
+    fn encoded_leaves_flatten_in_order() {
+        let cfg = MerkleChannelCfg::for_leaves(8, 2, 0);
+        let tail = [F::from(7u64)];
+        let leaf = |i: u64| (F::from(i), [F::from(i + 1), F::from(i + 2), F::from(i + 3)], &tail[..]);
+        let mut typed = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+        let mut flat = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+        for i in 0..8 {
+            typed.push_encoded(&leaf(i));
+            flat.push_leaf(&[i, i + 1, i + 2, i + 3, 7].map(F::from));
+        }
+        let root = typed.finalize().unwrap();
+        assert_eq!(root, flat.finalize().unwrap());
+
+        let vo = typed.open_encoded(3, &leaf(3)).unwrap();
+        assert_eq!(vo.values, [3, 4, 5, 6, 7].map(F::from));
+        assert!(MerkleTreeChannel::verify_value_opening(&cfg, root, &vo, &[0u8; 32]));
+    }
+
+    #[test]
+    fn range_opening_matches_single_openings() {
+        let n = 50;
+        let cfg = MerkleChannelCfg::builder(n).level(8).fill_with(3).build().unwrap();
+        let mut tree = MerkleTreeChannel::new(cfg.clone(), [0u8; 32]);
+        let rows: Vec<Vec<F>> = (0..n as u64).map(|i| vec![F::from(i), F::from(3 * i)]).collect();
+        for row in &rows {
+            tree.push_leaf(row);
+        }
+        let root = tree.finalize().unwrap();
+
+        for (start, len) in [(0, 1), (0, n), (7, 2), (13, 20), (45, 5), (49, 1)] {
+            let ro = tree.open_range_with_values(start, rows[start..start + len].to_vec());
+            assert!(MerkleTreeChannel::verify_range_value_opening(&cfg, root, &ro, &[0u8; 32]));
+
+            // Never more siblings than the single paths would carry.
+            let single: usize = (start..start + len)
+                .map(|i| tree.open(i).unwrap().path.iter().map(Vec::len).sum::<usize>())
+                .sum();
+            let ranged: usize = ro.opening.path.iter().map(|s| s.left.len() + s.right.len()).sum();
+            assert!(ranged <= single);
+        }
+
+        let ro = tree.open_range_with_values(13, rows[13..33].to_vec());
+        let mut bad = ro.clone();
+        bad.values[4][1] += F::from(1u64);
+        assert!(!MerkleTreeChannel::verify_range_value_opening(&cfg, root, &bad, &[0u8; 32]));
+
+        let mut bad = ro.clone();
+        bad.opening.start += 1;
+        assert!(!MerkleTreeChannel::verify_range_opening(&cfg, root, &bad.opening, &[0u8; 32]));
+
+        let mut bad = ro.clone();
+        bad.opening.path[1].left.push(F::from(0u64));
+        assert!(!MerkleTreeChannel::verify_range_opening(&cfg, root, &bad.opening, &[0u8; 32]));
+
+        let other = cfg.clone().with_label(1);
+        assert!(!MerkleTreeChannel::verify_range_value_opening(&other, root, &ro, &[0u8; 32]));
+    }
+
+    #[test]
+    fn coset_openings_share_one_proof() {
+        let n = 64;
+        let cfg = MerkleChannelCfg::for_leaves(n, 4, 0);
+        let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+        (0..n as u64).for_each(|i| tree.push_leaf(&[F::from(i)]));
+        assert_eq!(tree.open_coset(0, 16, 4).unwrap_err(), MerkleError::NotFinalized);
+        let root = tree.finalize().unwrap();
+        let th = [0u8; 32];
+
+        // A query's coset in a layer folded by 4: leaves 3, 19, 35, 51.
+        let coset = tree.open_coset(3, 16, 4).unwrap();
+        assert_eq!(coset.indices(), Some(vec![3, 19, 35, 51]));
+        for (j, leaf) in coset.leaves.iter().enumerate() {
+            assert_eq!(*leaf, tree.open(3 + 16 * j).unwrap().leaf);
+        }
+        assert!(MerkleTreeChannel::verify_coset_opening(&cfg, root, &coset, &th));
+        let singles: usize = (0..4).map(|j| tree.open(3 + 16 * j).unwrap().path.concat().len()).sum();
+        assert!(coset.path.concat().len() < singles);
+
+        // Adjacent leaves cost what a range opening does.
+        let block = tree.open_coset(8, 1, 8).unwrap();
+        assert!(MerkleTreeChannel::verify_coset_opening(&cfg, root, &block, &th));
+        let range = tree.open_range(8, 8);
+        let range_len: usize = range.path.iter().map(|s| s.left.len() + s.right.len()).sum();
+        assert_eq!(block.path.concat().len(), range_len);
+
+        let mut moved = coset.clone();
+        moved.base = 4;
+        assert!(!MerkleTreeChannel::verify_coset_opening(&cfg, root, &moved, &th));
+        let mut stretched = coset.clone();
+        stretched.stride = 8;
+        assert!(!MerkleTreeChannel::verify_coset_opening(&cfg, root, &stretched, &th));
+        let mut flat = coset;
+        flat.stride = 0;
+        assert_eq!(flat.indices(), None);
+        assert!(!MerkleTreeChannel::verify_coset_opening(&cfg, root, &flat, &th));
+        assert_eq!(
+            tree.open_coset(20, 16, 4).unwrap_err(),
+            MerkleError::IndexOutOfRange { index: 68, len: 64 }
+        );
+        assert_eq!(
+            tree.open_coset(1, usize::MAX, 2).unwrap_err(),
+            MerkleError::IndexOutOfRange { index: u64::MAX, len: 64 }
+        );
+        assert_eq!(tree.open_coset(3, 16, 0).unwrap_err(), MerkleError::EmptyCoset { leaves: 0, stride: 16 });
+        assert_eq!(tree.open_coset(3, 0, 2).unwrap_err(), MerkleError::EmptyCoset { leaves: 2, stride: 0 });
+        assert!(tree.open_coset(3, 0, 1).is_ok());
+    }
+
+    #[test]
+    fn value_opening_binds_payload() {
+        let cfg = MerkleChannelCfg::for_leaves(16, 4, 5);
+        let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+        for i in 0..16u64 {
+            tree.push_leaf(&[F::from(i), F::from(2 * i)]);
+        }
+        let root = tree.finalize().unwrap();
+
+        let vo = tree.open_with_values(9, vec![F::from(9u64), F::from(18u64)]).unwrap();
+        assert_eq!(vo.index(), 9);
+        assert!(MerkleTreeChannel::verify_value_opening(&cfg, root, &vo, &[0u8; 32]));
+
+        // Payload that does not hash to the opened leaf.
+        let mut bad = vo.clone();
+        bad.values[1] += F::from(1u64);
+        assert!(!MerkleTreeChannel::verify_value_opening(&cfg, root, &bad, &[0u8; 32]));
+
+        // Same payload under another tree's leaf label.
+        let other = cfg.clone().with_label(6);
+        assert!(!MerkleTreeChannel::verify_value_opening(&other, root, &vo, &[0u8; 32]));
+    }
+
+    #[test]
+    fn digest256_tree_keeps_full_nodes() {
+        let narrow = MerkleChannelCfg::for_leaves(16, 4, 3);
+        let cfg = narrow.clone().with_digest(DigestWidth::Digest256);
+        let mut tree = MerkleTreeChannel::<Digest256>::new(cfg.clone(), [0u8; 32]);
+        let mut short = MerkleTreeChannel::<F>::new(narrow.clone(), [0u8; 32]);
+        for i in 0..16u64 {
+            tree.push_leaf(&[F::from(i)]);
+            short.push_leaf(&[F::from(i)]);
+        }
+        let root = tree.finalize().unwrap();
+        // Leaves agree with the truncated tree up to the cut; nodes above
+        // hash the full children and do not.
+        let leaf = MerkleTreeChannel::<Digest256>::leaf_digest(&cfg, 5, &[F::from(5u64)]);
+        assert_eq!(F::from_digest(leaf.0), MerkleTreeChannel::<F>::leaf_digest(&narrow, 5, &[F::from(5u64)]));
+        assert_ne!(F::from_digest(root.0), short.finalize().unwrap());
+
+        let vo = tree.open_with_values(5, vec![F::from(5u64)]).unwrap();
+        assert!(MerkleTreeChannel::verify_value_opening(&cfg, root, &vo, &[0u8; 32]));
+        let mut bad = vo.clone();
+        bad.opening.path[0][0].0[31] ^= 1;
+        assert!(!MerkleTreeChannel::verify_value_opening(&cfg, root, &bad, &[0u8; 32]));
+
+        // A config of the other width verifies nothing.
+        assert_eq!(
+            MerkleTreeChannel::verify_opening(&narrow, root, &vo.opening, &[0u8; 32]),
+            Err(MerkleError::WidthMismatch { cfg: DigestWidth::Digest64, node: DigestWidth::Digest256 })
+        );
+        let range = tree.open_range(4, 3);
+        assert!(MerkleTreeChannel::verify_range_opening(&cfg, root, &range, &[0u8; 32]));
+        assert!(!MerkleTreeChannel::verify_range_opening(&narrow, root, &range, &[0u8; 32]));
+    }
+
+    /// A leaf past 2^33 in a binary tree of 2^34 leaves, with arbitrary
+    /// siblings standing in for the rest of the tree.
+    #[test]
+    fn poseidon_trees_hash_in_the_field() {
+        let cfg = MerkleChannelCfg::builder(2).level(2).hash(MerkleHash::Poseidon).build().unwrap();
+        let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+        tree.push_leaf(&[F::from(3u64)]);
+        tree.push_leaf(&[F::from(4u64)]);
+        let root = tree.finalize().unwrap();
+
+        // One permutation per node, the label in the capacity lane and the
+        // inputs in the rate lanes.
+        let sponge = |ds: DsLabel, inputs: &[F]| {
+            let mut state = [F::from(0u64); T];
+            state[..inputs.len()].copy_from_slice(inputs);
+            state[T - 1] = poseidon_capacity(ds, inputs.len());
+            permute(&mut state, poseidon_params());
+            state[0]
+        };
+        let label = |arity, level, position| DsLabel { arity, level, position, tree_label: 0 };
+        let leaves = [
+            sponge(label(2, LEAF_LEVEL_DS, 0), &[F::from(3u64)]),
+            sponge(label(2, LEAF_LEVEL_DS, 1), &[F::from(4u64)]),
+        ];
+        assert_eq!(tree.open(1).unwrap().leaf, leaves[1]);
+        assert_eq!(root, sponge(label(2, 1, 0), &leaves));
+
+        let mut sha3 = MerkleTreeChannel::<F>::new(cfg.clone().with_hash(MerkleHash::Sha3), [0u8; 32]);
+        sha3.push_leaf(&[F::from(3u64)]);
+        sha3.push_leaf(&[F::from(4u64)]);
+        assert_ne!(sha3.finalize().unwrap(), root);
+
+        // Trailing zeros are not the same leaf.
+        assert_ne!(
+            MerkleTreeChannel::<F>::leaf_digest(&cfg, 0, &[F::from(3u64)]),
+            MerkleTreeChannel::<F>::leaf_digest(&cfg, 0, &[F::from(3u64), F::from(0u64)])
+        );
+    }
+
+    #[test]
+    fn poseidon_trees_open_and_verify() {
+        let n = 300;
+        for arity in [2, 16] {
+            let cfg = MerkleChannelCfg::builder(n)
+                .fill_with(arity)
+                .hash(MerkleHash::Poseidon)
+                .padding(Padding::FixedDigest)
+                .bind_leaf_count()
+                .build()
+                .unwrap();
+            // 17 values and a leaf index take two permutations.
+            let leaf = |i: u64| -> Vec<F> { (0..17).map(|k| F::from(i * 31 + k)).collect() };
+            let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+            let mut stream = MerkleStreamBuilder::<F>::new(cfg.clone());
+            for i in 0..n as u64 {
+                tree.push_leaf(&leaf(i));
+                stream.push_leaf(&leaf(i));
+            }
+            let root = tree.finalize().unwrap();
+            assert_eq!(stream.finish(), root);
+
+            let th = [0u8; 32];
+            for i in [0, 17, 255, 299] {
+                let o = tree.open_with_values(i, leaf(i as u64)).unwrap();
+                assert!(MerkleTreeChannel::verify_value_opening(&cfg, root, &o, &th));
+            }
+            let batch = tree.open_batch(&[3, 4, 100, 299]);
+            assert!(MerkleTreeChannel::verify_multi_opening(&cfg, root, &batch, &th));
+            let range = tree.open_range(40, 20);
+            assert!(MerkleTreeChannel::verify_range_opening(&cfg, root, &range, &th));
+
+            // A SHA3 verifier does not accept Poseidon openings.
+            let sha3 = cfg.clone().with_hash(MerkleHash::Sha3);
+            assert_eq!(MerkleTreeChannel::verify_opening(&sha3, root, &tree.open(5).unwrap(), &th), Ok(false));
+        }
+    }
+
+    #[test]
+    fn poseidon_needs_field_nodes() {
+        assert_eq!(
+            MerkleChannelCfg::builder(8)
+                .fill_with(2)
+                .digest(DigestWidth::Digest256)
+                .hash(MerkleHash::Poseidon)
+                .build()
+                .unwrap_err(),
+            MerkleCfgError::HashWidth {
+                hash: MerkleHash::Poseidon,
+                digest: DigestWidth::Digest256
+            }
+        );
+        let cfg = MerkleChannelCfg::for_leaves(8, 2, 0)
+            .with_digest(DigestWidth::Digest256)
+            .with_hash(MerkleHash::Poseidon);
+        let opening = MerkleOpening {
+            leaf: Digest256::default(),
+            path: vec![vec![Digest256::default()]; 3],
+            index: 0,
+        };
+        assert_eq!(
+            MerkleTreeChannel::verify_opening(&cfg, Digest256::default(), &opening, &[0u8; 32]),
+            Err(MerkleError::HashWidth {
+                hash: MerkleHash::Poseidon,
+                node: DigestWidth::Digest256
+            })
+        );
+    }
+
+    /// Roots, caps and paths of plonky2 1.1.0's `MerkleTree<_, PoseidonHash>`
+    /// over eight leaves of five elements.
+    #[test]
+    fn plonky2_trees_match_plonky2() {
+        let digest = |xs: [u64; 4]| Digest256(plonky2::digest_to_bytes(&xs.map(F::from)));
+        let leaf = |i: u64| -> Vec<F> { (0..5).map(|j| F::from(i * 5 + j)).collect() };
+        let build = |cap_height| {
+            let cfg = MerkleChannelCfg::plonky2(8, cap_height).unwrap();
+            let mut tree = MerkleTreeChannel::<Digest256>::new(cfg.clone(), [0u8; 32]);
+            (0..8).for_each(|i| tree.push_leaf(&leaf(i)));
+            let cap = tree.finalize_cap().unwrap();
+            (cfg, tree, cap)
+        };
+
+        let (_, _, root) = build(0);
+        assert_eq!(root, [digest([0x36d5a45f77dbccbb, 0x26f3cae76fabad5f, 0x344b4ca5d0b79bc8, 0x9a778ab298100a18])]);
+
+        let (cfg, tree, cap) = build(1);
+        assert_eq!(
+            cap,
+            [
+                digest([0x675e63433c3b0d44, 0x115c44a571f1ccc5, 0xb9fc47a2070e881a, 0x3c8e3d64e6dc0d01]),
+                digest([0x4fe1378894cfbb36, 0x7031e2aa57b680c5, 0xd80118356ae04c32, 0xcf5d457a97179d45]),
+            ]
+        );
+        let opening = tree.open(5).unwrap();
+        assert_eq!(
+            opening.path,
+            [
+                vec![digest([0x9ae1ec578c55d61f, 0x21ff4883e6f9dbfa, 0x089765f37a3167f0, 0x65692874923514f1])],
+                vec![digest([0x873560da3c77d9e0, 0x9554e8e9fb2803a1, 0xd80d902d4df09d6c, 0xd8d776d1794356ab])],
+            ]
+        );
+        assert!(MerkleTreeChannel::verify_cap_opening(&cfg, &cap, &opening, &[0u8; 32]));
+        let mut swapped = cap.clone();
+        swapped.reverse();
+        assert!(!MerkleTreeChannel::verify_cap_opening(&cfg, &swapped, &opening, &[0u8; 32]));
+
+        let (_, _, cap) = build(2);
+        assert_eq!(cap[3], digest([0x873560da3c77d9e0, 0x9554e8e9fb2803a1, 0xd80d902d4df09d6c, 0xd8d776d1794356ab]));
+
+        // Up to four values are their own zero-padded leaf digest.
+        let cfg = MerkleChannelCfg::plonky2(2, 0).unwrap();
+        assert_eq!(
+            MerkleTreeChannel::<Digest256>::leaf_digest(&cfg, 1, &[F::from(7u64)]),
+            digest([7, 0, 0, 0])
+        );
+        assert_eq!(
+            MerkleChannelCfg::builder(8).fill_with(2).hash(MerkleHash::Plonky2).build().unwrap_err(),
+            MerkleCfgError::HashWidth {
+                hash: MerkleHash::Plonky2,
+                digest: DigestWidth::Digest64
+            }
+        );
+    }
+
+    #[test]
+    fn openings_past_u32_indices() {
+        let cfg = MerkleChannelCfg::new(vec![2; 34], 9);
+        let index = (1u64 << 33) + 5;
+        let leaf = MerkleTreeChannel::leaf_digest(&cfg, index, &[F::from(7u64)]);
+        let siblings: Vec<F> = (0..34u64).map(|l| F::from(1000 + l)).collect();
+
+        let range_path: Vec<range::RangeLevel<F>> = siblings
+            .iter()
+            .enumerate()
+            .map(|(l, &s)| match (index >> l) & 1 {
+                0 => (vec![], vec![s]),
+                _ => (vec![s], vec![]),
+            })
+            .collect();
+        let root = range::range_root(&cfg.layer_arities, index, &[leaf], &range_path, |level, position, c| {
+            compress(
+                DsLabel { arity: 2, level: level as u32, position, tree_label: cfg.tree_label },
+                c,
+            )
+        })
+        .unwrap();
+
+        let opening = MerkleOpening {
+            leaf,
+            path: siblings.iter().map(|&s| vec![s]).collect(),
+            index,
+        };
+        assert_eq!(MerkleTreeChannel::verify_opening(&cfg, root, &opening, &[0u8; 32]), Ok(true));
+
+        let ranged = MerkleRangeOpening {
+            start: index,
+            leaves: vec![leaf],
+            path: range_path
+                .into_iter()
+                .map(|(left, right)| RangeSiblings { left, right })
+                .collect(),
+        };
+        assert!(MerkleTreeChannel::verify_range_opening(&cfg, root, &ranged, &[0u8; 32]));
+
+        // The low 32 bits alone must not verify: positions are bound in full.
+        let truncated = MerkleOpening { index: index as u32 as u64, ..opening.clone() };
+        assert_eq!(MerkleTreeChannel::verify_opening(&cfg, root, &truncated, &[0u8; 32]), Ok(false));
+
+        // Nor may an index beyond the tree's capacity alias back into it.
+        let beyond = MerkleOpening { index: index + (1 << 34), ..opening };
+        assert!(!cfg.covers(beyond.index));
+        assert_eq!(
+            MerkleTreeChannel::verify_opening(&cfg, root, &beyond, &[0u8; 32]),
+            Err(MerkleError::IndexOutOfRange { index: beyond.index, len: cfg.capacity() as u64 })
+        );
+
+        let mut beyond = ranged;
+        beyond.start += 1 << 34;
+        assert!(!MerkleTreeChannel::verify_range_opening(&cfg, root, &beyond, &[0u8; 32]));
+    }
+}
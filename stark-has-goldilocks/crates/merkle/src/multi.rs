@@ -0,0 +1,191 @@
+//! Openings of an arbitrary set of leaves.
+//!
+//! Query phases open tens of scattered leaves per tree, and their paths
+//! meet long before the root. A multi-opening ships, per level, only the
+//! siblings no opened path can supply: for every group holding at least
+//! one known node, the group's other members. Known nodes start as the
+//! opened leaves and move up to their parents level by level, so a sibling
+//! shared by several paths, or recomputable from them, is never sent.
+//!
+//! As in [`range`](crate::range), the helpers are generic over the digest
+//! type; [`MerkleTreeChannel::open_batch`](crate::MerkleTreeChannel::open_batch)
+//! is the field-digest instance.
+
+use alloc::vec::Vec;
+
+/// Per level, in increasing position order, the nodes needed beside the
+/// known ones to climb from the leaves at `indices` (sorted, distinct) to
+/// the root. `arities[l]` groups level `l` into parents; `node(l, i)` is
+/// node `i` of level `l`, padding included.
+pub fn multi_siblings<D: Copy>(
+    arities: &[usize],
+    indices: &[usize],
+    node: impl Fn(usize, usize) -> D,
+) -> Vec<Vec<D>> {
+    assert!(!indices.is_empty(), "empty multi-opening");
+    assert!(indices.windows(2).all(|w| w[0] < w[1]), "indices not sorted and distinct");
+    let mut known = indices.to_vec();
+    arities
+        .iter()
+        .enumerate()
+        .map(|(level, &arity)| {
+            let mut siblings = Vec::new();
+            let mut parents: Vec<usize> = Vec::new();
+            let mut k = known.iter().peekable();
+            while let Some(&first) = k.next() {
+                let parent = first / arity;
+                let mut have = alloc::vec![first];
+                while let Some(&&next) = k.peek().filter(|&&&i| i / arity == parent) {
+                    have.push(next);
+                    k.next();
+                }
+                let group = parent * arity..(parent + 1) * arity;
+                siblings.extend(group.filter(|i| !have.contains(i)).map(|i| node(level, i)));
+                parents.push(parent);
+            }
+            known = parents;
+            siblings
+        })
+        .collect()
+}
+
+/// Root recomputed from the `leaves` at `indices` and their
+/// [`multi_siblings`], or `None` if the indices are not strictly
+/// increasing or the siblings do not fit them. `compress(level, position,
+/// children)` hashes the children of node `position` on `level` (1 for the
+/// leaves' parents).
+pub fn multi_root<D: Copy>(
+    arities: &[usize],
+    indices: &[u64],
+    leaves: &[D],
+    siblings: &[Vec<D>],
+    compress: impl Fn(usize, u64, &[D]) -> D,
+) -> Option<D> {
+    if leaves.is_empty()
+        || leaves.len() != indices.len()
+        || siblings.len() != arities.len()
+        || indices.windows(2).any(|w| w[0] >= w[1])
+    {
+        return None;
+    }
+
+    let mut known: Vec<(u64, D)> = indices.iter().copied().zip(leaves.iter().copied()).collect();
+    for (level, (&arity, level_siblings)) in arities.iter().zip(siblings).enumerate() {
+        let a = arity as u64;
+        let mut sibs = level_siblings.iter();
+        let mut parents = Vec::new();
+        let mut k = known.iter().peekable();
+        while let Some(&&(first, _)) = k.peek() {
+            let parent = first / a;
+            let mut children = Vec::with_capacity(arity);
+            for slot in parent.checked_mul(a)?..parent.checked_mul(a)?.checked_add(a)? {
+                match k.peek() {
+                    Some(&&(i, x)) if i == slot => {
+                        children.push(x);
+                        k.next();
+                    }
+                    _ => children.push(*sibs.next()?),
+                }
+            }
+            parents.push((parent, compress(level + 1, parent, &children)));
+        }
+        if sibs.next().is_some() {
+            return None;
+        }
+        known = parents;
+    }
+
+    match known[..] {
+        [(0, root)] => Some(root),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sum tree tagging every node with its position, as in the range tests.
+    fn compress(level: usize, position: u64, children: &[u64]) -> u64 {
+        children
+            .iter()
+            .fold((level as u64) << 32 | position, |acc, c| {
+                acc.wrapping_mul(1_000_003).wrapping_add(*c)
+            })
+    }
+
+    fn levels(arities: &[usize], leaves: &[u64]) -> Vec<Vec<u64>> {
+        let mut levels = vec![leaves.to_vec()];
+        for (level, &arity) in arities.iter().enumerate() {
+            let next = levels[level]
+                .chunks(arity)
+                .enumerate()
+                .map(|(k, c)| compress(level + 1, k as u64, c))
+                .collect();
+            levels.push(next);
+        }
+        levels
+    }
+
+    #[test]
+    fn every_subset_recomputes_the_root() {
+        let arities = [4, 2, 3];
+        let leaves: Vec<u64> = (0..24).map(|i| i * i + 7).collect();
+        let levels = levels(&arities, &leaves);
+        let root = levels[3][0];
+
+        // Every set of up to three leaves, plus the whole tree.
+        let mut sets: Vec<Vec<usize>> = (0..24).map(|i| vec![i]).collect();
+        for i in 0..24 {
+            for j in i + 1..24 {
+                sets.push(vec![i, j]);
+                sets.extend((j + 1..24).map(|k| vec![i, j, k]));
+            }
+        }
+        sets.push((0..24).collect());
+
+        for set in sets {
+            let sibs = multi_siblings(&arities, &set, |l, i| levels[l][i]);
+            let indices: Vec<u64> = set.iter().map(|&i| i as u64).collect();
+            let opened: Vec<u64> = set.iter().map(|&i| leaves[i]).collect();
+            assert_eq!(multi_root(&arities, &indices, &opened, &sibs, compress), Some(root), "{set:?}");
+        }
+
+        // The whole tree needs no siblings at all.
+        let all: Vec<usize> = (0..24).collect();
+        assert!(multi_siblings(&arities, &all, |l, i| levels[l][i]).iter().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn shared_siblings_are_sent_once() {
+        let arities = [2, 2, 2];
+        let leaves: Vec<u64> = (0..8).collect();
+        let levels = levels(&arities, &leaves);
+
+        // Neighbours 2 and 3 need nothing at the leaves; 6 needs 7.
+        let sibs = multi_siblings(&arities, &[2, 3, 6], |l, i| levels[l][i]);
+        assert_eq!(sibs, vec![vec![7], vec![levels[1][0], levels[1][2]], vec![]]);
+    }
+
+    #[test]
+    fn misplaced_or_reshaped_openings_fail() {
+        let arities = [4, 2, 3];
+        let leaves: Vec<u64> = (0..24).collect();
+        let levels = levels(&arities, &leaves);
+        let root = levels[3][0];
+        let sibs = multi_siblings(&arities, &[1, 9, 17], |l, i| levels[l][i]);
+        let opened = [leaves[1], leaves[9], leaves[17]];
+
+        assert_eq!(multi_root(&arities, &[1, 9, 17], &opened, &sibs, compress), Some(root));
+        assert_ne!(multi_root(&arities, &[1, 9, 18], &opened, &sibs, compress), Some(root));
+        assert_eq!(multi_root(&arities, &[9, 1, 17], &opened, &sibs, compress), None);
+        assert_eq!(multi_root(&arities, &[1, 9, 9], &opened, &sibs, compress), None);
+        assert_eq!(multi_root(&arities, &[1, 9], &opened[..2], &sibs, compress), None);
+        assert_eq!(multi_root(&arities[..2], &[1, 9, 17], &opened, &sibs[..2], compress), None);
+        assert_eq!(multi_root(&arities, &[], &[], &sibs, compress), None);
+
+        let mut extra = sibs.clone();
+        extra[1].push(0);
+        assert_eq!(multi_root(&arities, &[1, 9, 17], &opened, &extra, compress), None);
+    }
+}
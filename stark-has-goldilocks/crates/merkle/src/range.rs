@@ -0,0 +1,147 @@
+//! Openings of a contiguous block of leaves.
+//!
+//! Opening `start..start + len` leaf by leaf repeats every node the paths
+//! share. A range opening ships, per level, only the siblings flanking the
+//! covered run of nodes: the ones in its first group before the run and in
+//! its last group after it. Everything in between is recomputed from the
+//! leaves, so the proof is two boundary paths that merge into a single path
+//! once the run fits in one group.
+//!
+//! The helpers here are generic over the digest type so that byte trees can
+//! share them; [`MerkleTreeChannel::open_range`](crate::MerkleTreeChannel::open_range)
+//! is the field-digest instance.
+
+use alloc::vec::Vec;
+
+/// One level's siblings, before and after the opened run.
+pub type RangeLevel<D> = (Vec<D>, Vec<D>);
+
+/// Per level, the nodes before and after the run covering `start..start +
+/// len` within its first and last group. `arities[l]` groups level `l`
+/// into parents; `node(l, i)` is node `i` of level `l`, padding included.
+pub fn range_siblings<D: Copy>(
+    arities: &[usize],
+    start: usize,
+    len: usize,
+    node: impl Fn(usize, usize) -> D,
+) -> Vec<RangeLevel<D>> {
+    assert!(len > 0, "empty range");
+    let (mut lo, mut hi) = (start, start + len);
+    arities
+        .iter()
+        .enumerate()
+        .map(|(level, &arity)| {
+            let (group_lo, group_hi) = (lo / arity * arity, hi.div_ceil(arity) * arity);
+            let left = (group_lo..lo).map(|i| node(level, i)).collect();
+            let right = (hi..group_hi).map(|i| node(level, i)).collect();
+            (lo, hi) = (lo / arity, hi.div_ceil(arity));
+            (left, right)
+        })
+        .collect()
+}
+
+/// Root recomputed from the `leaves` at `start..` and the
+/// [`range_siblings`] of that range, or `None` if the siblings do not fit
+/// the range's shape. `compress(level, position, children)` hashes the
+/// children of node `position` on `level` (1 for the leaves' parents).
+///
+/// Positions are u64 so a verifier can place ranges anywhere in a tree
+/// larger than its address space; only the opened run is held in memory.
+pub fn range_root<D: Copy>(
+    arities: &[usize],
+    start: u64,
+    leaves: &[D],
+    siblings: &[RangeLevel<D>],
+    compress: impl Fn(usize, u64, &[D]) -> D,
+) -> Option<D> {
+    if leaves.is_empty() || siblings.len() != arities.len() {
+        return None;
+    }
+
+    let mut lo = start;
+    let mut cur = leaves.to_vec();
+    for (level, (&arity, (left, right))) in arities.iter().zip(siblings).enumerate() {
+        let a = arity as u64;
+        let hi = lo.checked_add(cur.len() as u64)?;
+        let (group_lo, group_hi) = (lo / a * a, hi.div_ceil(a).checked_mul(a)?);
+        if left.len() as u64 != lo - group_lo || right.len() as u64 != group_hi - hi {
+            return None;
+        }
+
+        let mut run = Vec::with_capacity(left.len() + cur.len() + right.len());
+        run.extend_from_slice(left);
+        run.extend_from_slice(&cur);
+        run.extend_from_slice(right);
+        cur = run
+            .chunks(arity)
+            .enumerate()
+            .map(|(k, children)| compress(level + 1, group_lo / a + k as u64, children))
+            .collect();
+        lo = group_lo / a;
+    }
+
+    (cur.len() == 1 && lo == 0).then(|| cur[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sum tree tagging every node with its position, so misplaced
+    /// children change the root.
+    fn compress(level: usize, position: u64, children: &[u64]) -> u64 {
+        children
+            .iter()
+            .fold((level as u64) << 32 | position, |acc, c| {
+                acc.wrapping_mul(1_000_003).wrapping_add(*c)
+            })
+    }
+
+    fn levels(arities: &[usize], leaves: &[u64]) -> Vec<Vec<u64>> {
+        let mut levels = vec![leaves.to_vec()];
+        for (level, &arity) in arities.iter().enumerate() {
+            let next = levels[level]
+                .chunks(arity)
+                .enumerate()
+                .map(|(k, c)| compress(level + 1, k as u64, c))
+                .collect();
+            levels.push(next);
+        }
+        levels
+    }
+
+    #[test]
+    fn every_range_recomputes_the_root() {
+        let arities = [4, 2, 3];
+        let leaves: Vec<u64> = (0..24).map(|i| i * i + 7).collect();
+        let levels = levels(&arities, &leaves);
+        let root = levels[3][0];
+
+        for start in 0..24 {
+            for len in 1..=24 - start {
+                let sibs = range_siblings(&arities, start, len, |l, i| levels[l][i]);
+                let got = range_root(&arities, start as u64, &leaves[start..start + len], &sibs, compress);
+                assert_eq!(got, Some(root), "range {start}..{}", start + len);
+            }
+        }
+
+        // The whole tree needs no siblings at all.
+        let sibs = range_siblings(&arities, 0, 24, |l, i| levels[l][i]);
+        assert!(sibs.iter().all(|(l, r)| l.is_empty() && r.is_empty()));
+    }
+
+    #[test]
+    fn shifted_or_reshaped_ranges_fail() {
+        let arities = [4, 2, 3];
+        let leaves: Vec<u64> = (0..24).collect();
+        let levels = levels(&arities, &leaves);
+        let root = levels[3][0];
+        let sibs = range_siblings(&arities, 5, 6, |l, i| levels[l][i]);
+
+        assert_eq!(range_root(&arities, 5, &leaves[5..11], &sibs, compress), Some(root));
+        assert_ne!(range_root(&arities, 4, &leaves[5..11], &sibs, compress), Some(root));
+        assert_eq!(range_root(&arities, 5, &leaves[5..10], &sibs, compress), None);
+        assert_eq!(range_root(&arities[..2], 5, &leaves[5..11], &sibs[..2], compress), None);
+        assert_eq!(range_root(&arities, 5, &[], &sibs, compress), None);
+    }
+}
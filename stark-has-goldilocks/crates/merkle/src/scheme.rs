@@ -0,0 +1,143 @@
+//! Vector commitments behind one interface.
+//!
+//! [`VectorCommitment`] is what FRI and the channel need from a
+//! commitment: commit to a vector of leaves, open one or many positions,
+//! and check an opening against the leaf contents on the verifier's side.
+//! [`MerkleTreeChannel`] implements it; protocol code written against the
+//! trait can take another scheme, such as a capped tree or a polynomial
+//! commitment, without changes.
+
+use alloc::vec::Vec;
+
+use crate::{LeafEncoder, MerkleChannelCfg, MerkleError, MerkleMultiOpening, MerkleNode, MerkleOpening, MerkleTreeChannel, F};
+
+pub trait VectorCommitment {
+    /// What the verifier knows of the scheme before any commitment.
+    type Params;
+    type Commitment: Clone + Eq + core::fmt::Debug;
+    type Opening: Clone;
+    type BatchOpening: Clone;
+    type Error: core::fmt::Debug;
+
+    /// Commit to `leaves` in order, replacing any earlier commitment.
+    fn commit<L: LeafEncoder>(&mut self, leaves: &[L]) -> Result<Self::Commitment, Self::Error>;
+
+    fn open(&self, index: usize) -> Result<Self::Opening, Self::Error>;
+
+    /// One opening for every position in `indices`, in any order and with
+    /// repeats.
+    fn open_batch(&self, indices: &[usize]) -> Result<Self::BatchOpening, Self::Error>;
+
+    /// Whether `opening` shows position `index` of `commitment` holds
+    /// `leaf`, as flattened by its [`LeafEncoder`].
+    fn verify(
+        params: &Self::Params,
+        commitment: &Self::Commitment,
+        index: u64,
+        leaf: &[F],
+        opening: &Self::Opening,
+    ) -> bool;
+
+    /// [`verify`](Self::verify) for a batch: `leaves` pairs each opened
+    /// position, by increasing index and without repeats, with its values.
+    fn verify_batch(
+        params: &Self::Params,
+        commitment: &Self::Commitment,
+        leaves: &[(u64, Vec<F>)],
+        opening: &Self::BatchOpening,
+    ) -> bool;
+}
+
+/// Commits to the root. Openings carry no salt, so they verify only
+/// against configs without [`hiding`](MerkleChannelCfg::hiding); hiding
+/// trees are opened with [`open_with_values`](MerkleTreeChannel::open_with_values).
+impl<N: MerkleNode> VectorCommitment for MerkleTreeChannel<N> {
+    type Params = MerkleChannelCfg;
+    type Commitment = N;
+    type Opening = MerkleOpening<N>;
+    type BatchOpening = MerkleMultiOpening<N>;
+    type Error = MerkleError;
+
+    fn commit<L: LeafEncoder>(&mut self, leaves: &[L]) -> Result<N, MerkleError> {
+        self.levels.clear();
+        leaves.iter().for_each(|leaf| self.push_encoded(leaf));
+        self.finalize()
+    }
+
+    fn open(&self, index: usize) -> Result<MerkleOpening<N>, MerkleError> {
+        MerkleTreeChannel::open(self, index)
+    }
+
+    /// Panics on an empty batch, as [`MerkleTreeChannel::open_batch`].
+    fn open_batch(&self, indices: &[usize]) -> Result<MerkleMultiOpening<N>, MerkleError> {
+        let len = self.levels.first().map_or(0, Vec::len);
+        if !self.is_finalized() {
+            return Err(MerkleError::NotFinalized);
+        }
+        match indices.iter().find(|&&i| i >= len) {
+            Some(&index) => Err(MerkleError::IndexOutOfRange {
+                index: index as u64,
+                len: len as u64,
+            }),
+            None => Ok(MerkleTreeChannel::open_batch(self, indices)),
+        }
+    }
+
+    fn verify(cfg: &MerkleChannelCfg, root: &N, index: u64, leaf: &[F], opening: &MerkleOpening<N>) -> bool {
+        !cfg.hiding
+            && opening.index == index
+            && Self::leaf_digest(cfg, index, leaf) == opening.leaf
+            && Self::verify_opening(cfg, *root, opening, &[0u8; 32]) == Ok(true)
+    }
+
+    fn verify_batch(cfg: &MerkleChannelCfg, root: &N, leaves: &[(u64, Vec<F>)], opening: &MerkleMultiOpening<N>) -> bool {
+        !cfg.hiding
+            && leaves.len() == opening.indices.len()
+            && leaves
+                .iter()
+                .zip(opening.indices.iter().zip(&opening.leaves))
+                .all(|((i, values), (&j, &digest))| *i == j && Self::leaf_digest(cfg, j, values) == digest)
+            && Self::verify_multi_opening(cfg, *root, opening, &[0u8; 32])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Commit, open and verify through the trait alone.
+    fn commit_and_check<V: VectorCommitment>(scheme: &mut V, params: &V::Params, leaves: &[[F; 2]]) {
+        let c = scheme.commit(leaves).unwrap();
+        for i in [0, 5, leaves.len() - 1] {
+            let o = scheme.open(i).unwrap();
+            assert!(V::verify(params, &c, i as u64, &leaves[i], &o));
+            assert!(!V::verify(params, &c, i as u64, &leaves[(i + 1) % leaves.len()], &o));
+        }
+        let batch = scheme.open_batch(&[9, 2, 9, 30]).unwrap();
+        let opened: Vec<(u64, Vec<F>)> = [2, 9, 30].iter().map(|&i| (i as u64, leaves[i].to_vec())).collect();
+        assert!(V::verify_batch(params, &c, &opened, &batch));
+        assert!(!V::verify_batch(params, &c, &opened[..2], &batch));
+        let mut wrong = opened;
+        wrong[1].1[0] += F::from(1u64);
+        assert!(!V::verify_batch(params, &c, &wrong, &batch));
+    }
+
+    #[test]
+    fn merkle_trees_are_vector_commitments() {
+        let leaves: Vec<[F; 2]> = (0..40u64).map(|i| [F::from(i), F::from(i * i)]).collect();
+        let cfg = MerkleChannelCfg::for_leaves(40, 4, 0);
+        let mut tree = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+        assert_eq!(VectorCommitment::open_batch(&tree, &[0]).unwrap_err(), MerkleError::NotFinalized);
+        commit_and_check(&mut tree, &cfg, &leaves);
+
+        // Committing again starts from no leaves.
+        let root = VectorCommitment::commit(&mut tree, &leaves[..20]).unwrap();
+        let mut fresh = MerkleTreeChannel::<F>::new(cfg, [0u8; 32]);
+        leaves[..20].iter().for_each(|l| fresh.push_leaf(l));
+        assert_eq!(root, fresh.finalize().unwrap());
+        assert_eq!(
+            VectorCommitment::open_batch(&tree, &[3, 20]).unwrap_err(),
+            MerkleError::IndexOutOfRange { index: 20, len: 20 }
+        );
+    }
+}
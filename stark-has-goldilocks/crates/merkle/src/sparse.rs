@@ -0,0 +1,297 @@
+//! Key-value commitments.
+//!
+//! A [`SparseMerkleTree`] commits to a map from keys below `2^depth` to
+//! leaf values, e.g. a lookup table or a memory state, as a binary tree
+//! with one leaf per key. Only the nodes above present keys are stored: an
+//! empty subtree has one digest per level, hashed once under a label no
+//! node or leaf uses, and a node over two empty subtrees is the empty
+//! digest of its level. Everything else hashes as in a
+//! [`MerkleTreeChannel`] of the same config, so a full tree has the dense
+//! tree's root. Proofs are [`MerkleOpening`]s of the key's leaf, the
+//! empty digest in a non-membership proof, checked by the sparse
+//! verifiers here rather than [`MerkleTreeChannel::verify_opening`].
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use crate::{
+    DsLabel, LeafEncoder, MerkleChannelCfg, MerkleError, MerkleNode, MerkleOpening, MerkleTreeChannel, F,
+    SPARSE_LEVEL_DS,
+};
+
+pub struct SparseMerkleTree<N = F> {
+    cfg: MerkleChannelCfg,
+    /// Digest of an empty subtree per level, the empty leaf first.
+    empty: Vec<N>,
+    /// Nodes with a present key below them, by level and position.
+    nodes: BTreeMap<(usize, u64), N>,
+    len: usize,
+}
+
+impl<N: MerkleNode> SparseMerkleTree<N> {
+    /// An empty tree over the leaf indices of `cfg`. Panics unless every
+    /// level is binary, if `cfg` has a cap, hiding or a fixed leaf count,
+    /// or as [`MerkleTreeChannel::new`] does.
+    pub fn new(cfg: MerkleChannelCfg) -> Self {
+        if let Some(e) = cfg.node_error::<N>() {
+            panic!("{e}");
+        }
+        assert!(
+            !cfg.layer_arities.is_empty() && cfg.layer_arities.iter().all(|&a| a == 2),
+            "sparse Merkle trees are binary"
+        );
+        assert!(
+            cfg.cap_height == 0 && !cfg.hiding && cfg.leaf_count.is_none(),
+            "sparse Merkle trees have no cap, salt or fixed leaf count"
+        );
+        Self {
+            empty: Self::empty_digests(&cfg),
+            nodes: BTreeMap::new(),
+            len: 0,
+            cfg,
+        }
+    }
+
+    /// Digest of an empty subtree on each level of a tree of `cfg`, the
+    /// empty leaf first and the empty tree's root last.
+    pub fn empty_digests(cfg: &MerkleChannelCfg) -> Vec<N> {
+        let mut empty = alloc::vec![Self::empty_leaf(cfg)];
+        for level in 1..=cfg.layer_arities.len() {
+            let below = empty[level - 1];
+            empty.push(cfg.compress(empty_label(cfg, level), &[below, below]));
+        }
+        empty
+    }
+
+    /// Leaf digest of every absent key.
+    pub fn empty_leaf(cfg: &MerkleChannelCfg) -> N {
+        cfg.compress::<N, N>(empty_label(cfg, 0), &[])
+    }
+
+    pub fn cfg(&self) -> &MerkleChannelCfg {
+        &self.cfg
+    }
+
+    /// Keys present.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, key: u64) -> bool {
+        self.nodes.contains_key(&(0, key))
+    }
+
+    pub fn root(&self) -> N {
+        self.node(self.empty.len() - 1, 0)
+    }
+
+    /// Set `key` to `values`, replacing any earlier values.
+    pub fn insert(&mut self, key: u64, values: &[F]) -> Result<(), MerkleError> {
+        self.check_key(key)?;
+        let leaf = MerkleTreeChannel::leaf_digest(&self.cfg, key, values);
+        self.len += self.nodes.insert((0, key), leaf).is_none() as usize;
+        self.rehash(key);
+        Ok(())
+    }
+
+    /// [`insert`](Self::insert) of `leaf`'s [`LeafEncoder`] values.
+    pub fn insert_encoded<L: LeafEncoder + ?Sized>(&mut self, key: u64, leaf: &L) -> Result<(), MerkleError> {
+        self.insert(key, &leaf.leaf_values())
+    }
+
+    /// Empty `key`; whether it was present.
+    pub fn remove(&mut self, key: u64) -> Result<bool, MerkleError> {
+        self.check_key(key)?;
+        let present = self.nodes.remove(&(0, key)).is_some();
+        if present {
+            self.len -= 1;
+            self.rehash(key);
+        }
+        Ok(present)
+    }
+
+    /// Opening of `key`: a membership proof if it is present, else a
+    /// non-membership proof.
+    pub fn open(&self, key: u64) -> Result<MerkleOpening<N>, MerkleError> {
+        self.check_key(key)?;
+        let path = (0..self.empty.len() - 1)
+            .map(|level| alloc::vec![self.node(level, (key >> level) ^ 1)])
+            .collect();
+        Ok(MerkleOpening {
+            leaf: self.node(0, key),
+            path,
+            index: key,
+        })
+    }
+
+    /// Whether `opening` shows `key` holds `values` under `root`.
+    pub fn verify_membership(
+        cfg: &MerkleChannelCfg,
+        root: N,
+        key: u64,
+        values: &[F],
+        opening: &MerkleOpening<N>,
+    ) -> bool {
+        opening.index == key
+            && opening.leaf == MerkleTreeChannel::leaf_digest(cfg, key, values)
+            && Self::climb(cfg, &Self::empty_digests(cfg), opening) == Some(root)
+    }
+
+    /// Whether `opening` shows `key` is absent under `root`.
+    pub fn verify_non_membership(cfg: &MerkleChannelCfg, root: N, key: u64, opening: &MerkleOpening<N>) -> bool {
+        let empty = Self::empty_digests(cfg);
+        opening.index == key && opening.leaf == empty[0] && Self::climb(cfg, &empty, opening) == Some(root)
+    }
+
+    /// Root above `opening`, or `None` if it is not a path of a tree of
+    /// `cfg`.
+    fn climb(cfg: &MerkleChannelCfg, empty: &[N], opening: &MerkleOpening<N>) -> Option<N> {
+        if cfg.node_error::<N>().is_some()
+            || !cfg.covers(opening.index)
+            || opening.path.len() != cfg.layer_arities.len()
+            || cfg.layer_arities.iter().any(|&a| a != 2)
+        {
+            return None;
+        }
+        let mut cur = opening.leaf;
+        let mut position = opening.index;
+        for (level, siblings) in opening.path.iter().enumerate() {
+            let [sibling] = siblings[..] else {
+                return None;
+            };
+            let children = match position & 1 {
+                0 => [cur, sibling],
+                _ => [sibling, cur],
+            };
+            position >>= 1;
+            cur = parent(cfg, empty, level, position, &children);
+        }
+        Some(cur)
+    }
+
+    fn check_key(&self, key: u64) -> Result<(), MerkleError> {
+        match self.cfg.covers(key) {
+            true => Ok(()),
+            false => Err(self.cfg.index_error(key)),
+        }
+    }
+
+    fn node(&self, level: usize, position: u64) -> N {
+        self.nodes.get(&(level, position)).copied().unwrap_or(self.empty[level])
+    }
+
+    /// Recompute the path above leaf `key`, storing only nodes that are
+    /// not empty.
+    fn rehash(&mut self, key: u64) {
+        let mut position = key;
+        for level in 0..self.empty.len() - 1 {
+            let (left, right) = (position & !1, position | 1);
+            position >>= 1;
+            let children = [self.node(level, left), self.node(level, right)];
+            match parent(&self.cfg, &self.empty, level, position, &children) {
+                node if node == self.empty[level + 1] => self.nodes.remove(&(level + 1, position)),
+                node => self.nodes.insert((level + 1, position), node),
+            };
+        }
+    }
+}
+
+/// Node `position` on `level + 1` over `children`: the empty digest over
+/// two empty subtrees, else their hash.
+fn parent<N: MerkleNode>(cfg: &MerkleChannelCfg, empty: &[N], level: usize, position: u64, children: &[N; 2]) -> N {
+    if children.iter().all(|&c| c == empty[level]) {
+        return empty[level + 1];
+    }
+    let ds = DsLabel {
+        arity: 2,
+        level: level as u32 + 1,
+        position,
+        tree_label: cfg.tree_label,
+    };
+    cfg.compress(ds, children)
+}
+
+/// Label of the empty subtree on `level`.
+fn empty_label(cfg: &MerkleChannelCfg, level: usize) -> DsLabel {
+    DsLabel {
+        arity: 2,
+        level: SPARSE_LEVEL_DS,
+        position: level as u64,
+        tree_label: cfg.tree_label,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Digest256, DigestWidth, MerkleHash};
+
+    #[test]
+    fn full_sparse_trees_match_dense_trees() {
+        let sha3 = MerkleChannelCfg::new(vec![2; 3], 5);
+        for cfg in [sha3.clone(), sha3.with_hash(MerkleHash::Poseidon)] {
+            let mut sparse = SparseMerkleTree::<F>::new(cfg.clone());
+            let mut dense = MerkleTreeChannel::<F>::new(cfg.clone(), [0u8; 32]);
+            for key in 0..8u64 {
+                sparse.insert(7 - key, &[F::from(7 - key), F::from(1u64)]).unwrap();
+                dense.push_leaf(&[F::from(key), F::from(1u64)]);
+            }
+            assert_eq!(sparse.len(), 8);
+            assert_eq!(sparse.root(), dense.finalize().unwrap());
+            let (a, b) = (sparse.open(3).unwrap(), dense.open(3).unwrap());
+            assert_eq!((a.leaf, &a.path), (b.leaf, &b.path));
+        }
+    }
+
+    #[test]
+    fn sparse_trees_prove_membership_and_absence() {
+        let cfg = MerkleChannelCfg::new(vec![2; 40], 1);
+        let mut tree = SparseMerkleTree::<F>::new(cfg.clone());
+        let keys = [0u64, 12, 13, 1 << 39, (1 << 40) - 1];
+        for &k in &keys {
+            tree.insert(k, &[F::from(k), F::from(k + 1)]).unwrap();
+        }
+        let root = tree.root();
+        for &k in &keys {
+            let o = tree.open(k).unwrap();
+            assert!(SparseMerkleTree::verify_membership(&cfg, root, k, &[F::from(k), F::from(k + 1)], &o));
+            assert!(!SparseMerkleTree::verify_membership(&cfg, root, k, &[F::from(k)], &o));
+            assert!(!SparseMerkleTree::verify_non_membership(&cfg, root, k, &o));
+        }
+        for k in [1u64, 14, 1 << 20, (1 << 40) - 2] {
+            let o = tree.open(k).unwrap();
+            assert!(SparseMerkleTree::verify_non_membership(&cfg, root, k, &o));
+            assert!(!SparseMerkleTree::verify_non_membership(&cfg, root, k + 1, &o));
+            assert!(!SparseMerkleTree::verify_membership(&cfg, root, k, &[], &o));
+        }
+        assert_eq!(tree.open(1 << 40).unwrap_err(), MerkleError::IndexOutOfRange { index: 1 << 40, len: 1 << 40 });
+        assert!(tree.insert(1 << 40, &[]).is_err());
+
+        // Overwriting changes the root; the old opening no longer holds.
+        let old = tree.open(12).unwrap();
+        tree.insert(12, &[F::from(0u64)]).unwrap();
+        assert_eq!(tree.len(), keys.len());
+        assert!(!SparseMerkleTree::verify_membership(&cfg, tree.root(), 12, &[F::from(12u64), F::from(13u64)], &old));
+    }
+
+    #[test]
+    fn removing_every_key_empties_the_tree() {
+        let cfg = MerkleChannelCfg::new(vec![2; 16], 0).with_digest(DigestWidth::Digest256);
+        let empty = SparseMerkleTree::<Digest256>::empty_digests(&cfg);
+        let mut tree = SparseMerkleTree::<Digest256>::new(cfg);
+        assert_eq!(tree.root(), empty[16]);
+        for k in [3u64, 4, 900] {
+            tree.insert_encoded(k, &F::from(k)).unwrap();
+        }
+        assert!(tree.contains(4) && !tree.contains(5));
+        assert_eq!(tree.remove(5), Ok(false));
+        for k in [4u64, 900, 3] {
+            assert_eq!(tree.remove(k), Ok(true));
+        }
+        assert!(tree.is_empty() && tree.nodes.is_empty());
+        assert_eq!(tree.root(), empty[16]);
+    }
+}
@@ -0,0 +1,87 @@
+//! Size and cost of a built tree.
+//!
+//! Benchmarks report how much a commitment holds in memory and how many
+//! hashes it took. [`MerkleTreeChannel::stats`] reads both off the levels
+//! instead of leaving each harness to work them out from the config.
+
+use alloc::vec::Vec;
+
+use crate::{MerkleNode, MerkleTreeChannel, Padding};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct TreeStats {
+    /// Nodes held per level, the leaves first.
+    pub nodes_per_level: Vec<usize>,
+    /// Bytes the held nodes take; `utils::ByteSize::from(&stats)` prints
+    /// them.
+    pub bytes: usize,
+    /// Hash invocations that built the levels from scratch: one per leaf
+    /// and node, plus fixed padding digests and the root over a bound leaf
+    /// count. [`append`](MerkleTreeChannel::append) rehashes paths on top.
+    pub hashes: u64,
+}
+
+impl TreeStats {
+    pub fn nodes(&self) -> usize {
+        self.nodes_per_level.iter().sum()
+    }
+}
+
+impl<N: MerkleNode> MerkleTreeChannel<N> {
+    pub fn stats(&self) -> TreeStats {
+        let nodes_per_level: Vec<usize> = self.levels.iter().map(Vec::len).collect();
+        let nodes: usize = nodes_per_level.iter().sum();
+        let finalized = self.is_finalized();
+
+        // Each level below the top pads its last group once if short.
+        let pads = match self.cfg.padding {
+            Padding::FixedDigest if finalized => nodes_per_level[..nodes_per_level.len() - 1]
+                .iter()
+                .zip(&self.cfg.layer_arities)
+                .filter(|&(&len, &arity)| len % arity != 0)
+                .count(),
+            _ => 0,
+        };
+        let seal = finalized && self.cfg.leaf_count.is_some();
+        TreeStats {
+            bytes: nodes * N::WIDTH.bytes(),
+            hashes: (nodes + pads + seal as usize) as u64,
+            nodes_per_level,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Digest256, DigestWidth, MerkleChannelCfg, F};
+
+    #[test]
+    fn stats_count_nodes_bytes_and_hashes() {
+        let cfg = MerkleChannelCfg::builder(10)
+            .fill_with(2)
+            .padding(Padding::FixedDigest)
+            .bind_leaf_count()
+            .build()
+            .unwrap();
+        let mut tree = MerkleTreeChannel::<F>::new(cfg, [0u8; 32]);
+        assert_eq!(tree.stats(), TreeStats::default());
+        (0..10u64).for_each(|i| tree.push_leaf(&[F::from(i)]));
+        assert_eq!(tree.stats().hashes, 10);
+        tree.finalize().unwrap();
+
+        let stats = tree.stats();
+        assert_eq!(stats.nodes_per_level, vec![10, 5, 3, 2, 1]);
+        assert_eq!((stats.nodes(), stats.bytes), (21, 21 * 8));
+        // 21 nodes, pads on the levels of 5 and 3, and the sealed root.
+        assert_eq!(stats.hashes, 24);
+
+        let cfg = MerkleChannelCfg::for_leaves(16, 4, 0).with_digest(DigestWidth::Digest256);
+        let mut tree = MerkleTreeChannel::<Digest256>::new(cfg, [0u8; 32]);
+        (0..16u64).for_each(|i| tree.push_leaf(&[F::from(i)]));
+        tree.finalize().unwrap();
+        assert_eq!(tree.stats().bytes, 21 * 32);
+        assert_eq!(tree.stats().hashes, 21);
+    }
+}
@@ -0,0 +1,155 @@
+//! Roots of trees too large to hold.
+//!
+//! [`MerkleTreeChannel`] keeps every level so it can open paths later.
+//! When only the commitment is needed, e.g. for a trace that does not fit
+//! in memory, [`MerkleStreamBuilder`] takes the leaves one at a time and
+//! hashes each group as soon as it fills, so it holds at most one partial
+//! group per level.
+
+use alloc::vec::Vec;
+
+use crate::{leaf_salt, DsLabel, MerkleChannelCfg, MerkleNode, MerkleTreeChannel, F};
+
+/// Streaming counterpart of [`MerkleTreeChannel::push_leaf`] and
+/// [`finalize`](MerkleTreeChannel::finalize), producing the same root.
+pub struct MerkleStreamBuilder<N = F> {
+    cfg: MerkleChannelCfg,
+    /// Per level, the nodes of its open group.
+    pending: Vec<Vec<N>>,
+    /// Per level, how many nodes it has had so far.
+    counts: Vec<u64>,
+    salt_seed: Option<[u8; 32]>,
+}
+
+impl<N: MerkleNode> MerkleStreamBuilder<N> {
+    /// Panics as [`MerkleTreeChannel::new`] does.
+    pub fn new(cfg: MerkleChannelCfg) -> Self {
+        if let Some(e) = cfg.node_error::<N>() {
+            panic!("{e}");
+        }
+        Self {
+            cfg,
+            pending: Vec::new(),
+            counts: Vec::new(),
+            salt_seed: None,
+        }
+    }
+
+    /// As [`MerkleTreeChannel::with_salt_seed`].
+    pub fn with_salt_seed(self, seed: [u8; 32]) -> Self {
+        assert!(self.cfg.hiding, "salt seed for a tree without hiding");
+        Self {
+            salt_seed: Some(seed),
+            ..self
+        }
+    }
+
+    pub fn push_leaf(&mut self, values: &[F]) {
+        let index = self.leaves();
+        let leaf = match self.cfg.hiding {
+            false => MerkleTreeChannel::leaf_digest(&self.cfg, index, values),
+            true => {
+                let seed = self.salt_seed.as_ref().expect("hiding tree without a salt seed");
+                MerkleTreeChannel::salted_leaf_digest(&self.cfg, index, values, leaf_salt(seed, index))
+            }
+        };
+        self.push(0, leaf);
+    }
+
+    /// Leaves pushed so far.
+    pub fn leaves(&self) -> u64 {
+        self.counts.first().copied().unwrap_or(0)
+    }
+
+    /// Add `node` to `level`, hashing its group once full. The level above
+    /// the last arity holds the root of a full tree.
+    fn push(&mut self, level: usize, node: N) {
+        let arity = self.cfg.layer_arities.get(level).copied();
+        if self.pending.len() == level {
+            self.pending.push(Vec::with_capacity(arity.unwrap_or(1)));
+            self.counts.push(0);
+        }
+        self.pending[level].push(node);
+        self.counts[level] += 1;
+        if Some(self.pending[level].len()) == arity {
+            self.close_group(level);
+        }
+    }
+
+    fn close_group(&mut self, level: usize) {
+        let arity = self.cfg.layer_arities[level];
+        let ds = DsLabel {
+            arity,
+            level: level as u32 + 1,
+            position: (self.counts[level] - 1) / arity as u64,
+            tree_label: self.cfg.tree_label,
+        };
+        let parent = self.cfg.compress(ds, &self.pending[level]);
+        self.pending[level].clear();
+        self.push(level + 1, parent);
+    }
+
+    /// The root, as [`MerkleTreeChannel::finalize`] computes it over the
+    /// same leaves: short groups are padded as the config says, and capped
+    /// trees keep every level up to the cap.
+    ///
+    /// Panics if no leaf was pushed, or not as many as a fixed
+    /// [`leaf_count`](MerkleChannelCfg::leaf_count).
+    pub fn finish(mut self) -> N {
+        assert!(self.leaves() > 0, "no leaves");
+        assert!(
+            self.cfg.leaf_count.is_none_or(|n| n == self.leaves()),
+            "{} leaves for a config fixing {:?}",
+            self.leaves(),
+            self.cfg.leaf_count
+        );
+        let min_top = self.cfg.min_top_level();
+        let mut level = 0;
+        while self.counts[level] > 1 || level < min_top {
+            let group = &mut self.pending[level];
+            if !group.is_empty() {
+                let padding = self.cfg.layer_arities[level] - group.len();
+                let pad = self.cfg.pad_node(level, group);
+                group.resize(group.len() + padding, pad);
+                self.counts[level] += padding as u64;
+                self.close_group(level);
+            }
+            level += 1;
+        }
+        self.cfg.seal(self.pending[level][0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Digest256, DigestWidth};
+
+    fn both<N: MerkleNode + core::fmt::Debug + PartialEq>(cfg: &MerkleChannelCfg, n: u64) {
+        let mut tree = MerkleTreeChannel::<N>::new(cfg.clone(), [0u8; 32]);
+        let mut stream = MerkleStreamBuilder::<N>::new(cfg.clone());
+        for i in 0..n {
+            let values = [F::from(i), F::from(i * i + 3)];
+            tree.push_leaf(&values);
+            stream.push_leaf(&values);
+            // One open group per level, never a level of the tree.
+            assert!(stream.pending.iter().zip(&cfg.layer_arities).all(|(g, &a)| g.len() < a));
+        }
+        assert_eq!(stream.leaves(), n);
+        assert_eq!(stream.finish(), tree.finalize().unwrap(), "{n} leaves, arities {:?}", cfg.layer_arities);
+    }
+
+    #[test]
+    fn streamed_roots_match_finalize() {
+        for n in [1, 2, 3, 7, 8, 9, 31, 64, 100] {
+            both::<F>(&MerkleChannelCfg::new(vec![2; 7], 5), n);
+            both::<F>(&MerkleChannelCfg::new(vec![4, 3, 2, 2, 2, 2], 5), n);
+            both::<Digest256>(&MerkleChannelCfg::new(vec![8, 4, 4], 5).with_digest(DigestWidth::Digest256), n);
+        }
+        // Capped trees keep their forced levels.
+        let cfg = MerkleChannelCfg::builder(50).level(4).fill_with(2).cap_height(2).build().unwrap();
+        for n in [1, 5, 50] {
+            both::<F>(&cfg, n);
+        }
+    }
+}
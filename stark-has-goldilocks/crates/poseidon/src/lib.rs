@@ -1,5 +1,10 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
 use ark_ff::{Field, Zero};
-use ark_goldilocks::Goldilocks as F;
+use ark_goldilocks::{dot_product, Goldilocks as F};
 use blake3::Hasher;
 
 #[cfg(feature = "parallel")]
@@ -8,6 +13,8 @@ use once_cell::sync::OnceCell;
 use rayon::ThreadPoolBuilder;
 //use rayon::{prelude::*, ThreadPoolBuilder};
 
+pub mod plonky2;
+
 /// Poseidon permutation parameters for benchmarking and M1 scaffolding.
 /// Default static width t=17 matches Merkle arity m=16 with capacity c=1.
 pub const T: usize = 17;
@@ -39,9 +46,13 @@ fn poseidon_fr_from_hash(tag: &str, data: &[u8]) -> F {
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 pub struct PoseidonParams {
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub mds: [[F; T]; T],
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub rc_full: [[F; T]; RF],
+    #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
     pub rc_partial: [F; RP],
 }
 
@@ -95,9 +106,7 @@ pub fn permute(state: &mut [F; T], params: &PoseidonParams) {
 fn mds_mul_fixed(mds: &[[F; T]; T], state: &[F; T]) -> [F; T] {
     let mut out = [F::zero(); T];
     for i in 0..T {
-        for j in 0..T {
-            out[i] += mds[i][j] * state[j];
-        }
+        out[i] = dot_product(&mds[i], state);
     }
     out
 }
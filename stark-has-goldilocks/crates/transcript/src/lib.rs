@@ -1,18 +1,52 @@
 //! Pluggable Fiat–Shamir transcript with backward-compatible API.
-//! Goldilocks-safe field embedding (64-bit).
+//!
+//! [`Transcript`] is generic over the challenge field and defaults to
+//! Goldilocks. The byte-oriented backends (SHA3, Blake3, Keccak) serve any
+//! prime field; the Poseidon and Rescue-Prime sponges are Goldilocks-only.
 #![allow(dead_code)]
 #![allow(unused_imports)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use ark_ff::{BigInteger, PrimeField, Zero};
+extern crate alloc;
+
+use alloc::{borrow::Cow, boxed::Box, string::String, vec::Vec};
+use ark_ff::{BigInteger, Field, One, PrimeField, Zero};
+use ark_goldilocks::Goldilocks;
 use ark_goldilocks::Goldilocks as F;
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
 use std::sync::Once;
 
 // ---------------- Domain separation tags ----------------
 
 pub mod ds {
     pub const TRANSCRIPT_INIT: &[u8] = b"FSv1-TRANSCRIPT-INIT";
+    /// Init tag of session-keyed transcripts; its first eight bytes, which
+    /// the sponges absorb, differ from [`TRANSCRIPT_INIT`]'s.
+    pub const KEYED_INIT: &[u8] = b"FSv1-KEYED-INIT";
+    /// Absorbed at init by [`FsVersion::V2`](crate::FsVersion::V2)
+    /// transcripts, ahead of the backend name and parameters.
+    pub const V2_INIT: &[u8] = b"FSv2-TRANSCRIPT-INIT";
+    pub const PARAMS: &[u8] = b"FSv2-PARAMS";
     pub const ABSORB_BYTES: &[u8] = b"FSv1-ABSORB-BYTES";
+    pub const ABSORB_FIELDS: &[u8] = b"FSv1-ABSORB-FIELDS";
+    pub const ABSORB_U64: &[u8] = b"FSv1-ABSORB-U64";
+    pub const ABSORB_USIZE: &[u8] = b"FSv1-ABSORB-USIZE";
+    pub const ABSORB_BOOL: &[u8] = b"FSv1-ABSORB-BOOL";
     pub const CHALLENGE: &[u8] = b"FSv1-CHALLENGE";
+    pub const CHALLENGE_FOREIGN: &[u8] = b"FSv1-CHALLENGE-FOREIGN";
+    pub const CHALLENGE_MANY: &[u8] = b"FSv1-CHALLENGE-MANY";
+    pub const CHALLENGE_POWERS: &[u8] = b"FSv1-CHALLENGE-POWERS";
+    pub const CHALLENGE_EXT3: &[u8] = b"FSv1-CHALLENGE-EXT3";
+    pub const CHALLENGE_BYTES: &[u8] = b"FSv1-CHALLENGE-BYTES";
+    pub const CHALLENGE_INDEX: &[u8] = b"FSv1-CHALLENGE-INDEX";
+    pub const CHALLENGE_WIDE: &[u8] = b"FSv1-CHALLENGE-WIDE";
+    pub const GRIND: &[u8] = b"FSv1-GRIND";
+    pub const RNG: &[u8] = b"FSv1-RNG";
+    pub const FORK: &[u8] = b"FSv1-FORK";
+    pub const FORK_CHILD: &[u8] = b"FSv1-FORK-CHILD";
+    pub const SCOPE_BEGIN: &[u8] = b"FSv1-SCOPE-BEGIN";
+    pub const SCOPE_END: &[u8] = b"FSv1-SCOPE-END";
 }
 
 // ---------------- Helpers (Goldilocks-safe) ----------------
@@ -32,17 +66,242 @@ fn bytes_to_field_words(bytes: &[u8]) -> Vec<F> {
     bytes.chunks(8).map(bytes_to_field_u64).collect()
 }
 
+/// A session key as eight 32-bit words, so the encoding is injective.
+fn session_key_words(key: &[u8; 32]) -> [F; 8] {
+    core::array::from_fn(|i| bytes_to_field_u64(&key[4 * i..4 * i + 4]))
+}
+
+// ---------------- Helpers (byte backends, any field) ----------------
+
+/// What a byte backend hashes for `absorb_field` over each of `xs`, as
+/// one buffer.
+fn field_slice_bytes<F: PrimeField>(xs: &[F]) -> Vec<u8> {
+    let w = field_bytes::<F>();
+    let mut buf = Vec::with_capacity(xs.len() * (ds::ABSORB_BYTES.len() + w));
+    for &x in xs {
+        buf.extend_from_slice(ds::ABSORB_BYTES);
+        buf.extend_from_slice(&x.into_bigint().to_bytes_le()[..w]);
+    }
+    buf
+}
+
+/// `bytes` behind its length as 8 little-endian bytes, zero-padded to
+/// whole 8-byte words.
+fn frame(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + bytes.len().next_multiple_of(8));
+    out.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+    out.extend_from_slice(bytes);
+    out.resize(8 + bytes.len().next_multiple_of(8), 0);
+    out
+}
+
+/// SHA3-256 of `elems` as 8-byte little-endian words, behind `tag`.
+fn params_digest_of<'a>(tag: &[u8], elems: impl IntoIterator<Item = &'a F>) -> [u8; 32] {
+    use sha3::{Digest, Sha3_256};
+    let mut h = Sha3_256::new();
+    h.update(tag);
+    for x in elems {
+        h.update(x.into_bigint().0[0].to_le_bytes());
+    }
+    h.finalize().into()
+}
+
+/// Bytes of a canonical element of `F`: 8 for Goldilocks.
+fn field_bytes<F: PrimeField>() -> usize {
+    (F::MODULUS_BIT_SIZE as usize).div_ceil(8)
+}
+
+/// `x` as the byte backends absorb it, `field_bytes` little-endian bytes.
+fn field_to_bytes<F: PrimeField>(x: F) -> Vec<u8> {
+    let mut le = x.into_bigint().to_bytes_le();
+    le.truncate(field_bytes::<F>());
+    le
+}
+
+/// The element `bytes` encode little-endian, bits above the modulus width
+/// cleared, or `None` if that is not below the modulus.
+fn field_candidate<F: PrimeField>(bytes: &[u8]) -> Option<F> {
+    let mut word = bytes.to_vec();
+    let excess = 8 * word.len() - F::MODULUS_BIT_SIZE as usize;
+    *word.last_mut()? &= 0xff >> excess;
+    let mut value = F::BigInt::default();
+    for (limb, chunk) in value.as_mut().iter_mut().zip(word.chunks(8)) {
+        let mut le = [0u8; 8];
+        le[..chunk.len()].copy_from_slice(chunk);
+        *limb = u64::from_le_bytes(le);
+    }
+    (value < F::MODULUS).then(|| F::from_bigint(value)).flatten()
+}
+
+/// `K` elements uniform over `F`, by rejection sampling from the byte
+/// stream `digest(tag ‖ label) ‖ digest(tag ‖ label ‖ 1) ‖ ..`, counters
+/// as 8-byte little-endian. Each candidate is the next `field_bytes` of
+/// the stream as in [`field_candidate`]; rejected ones are skipped.
+/// Goldilocks rejects one word in 2^32, so a challenge is nearly always
+/// the first 8 bytes of the digest; a 255-bit field keeps about half.
+fn digest_words<F: PrimeField, const K: usize>(
+    digest: impl Fn(&[&[u8]]) -> [u8; 32],
+    tag: &[u8],
+    label: &[u8],
+) -> [F; K] {
+    let w = field_bytes::<F>();
+    let mut stream = digest(&[tag, label]).to_vec();
+    let mut blocks = 1u64;
+    let mut at = 0;
+    let mut out = [F::zero(); K];
+    for x in out.iter_mut() {
+        *x = loop {
+            while stream.len() < at + w {
+                stream.extend_from_slice(&digest(&[tag, label, &blocks.to_le_bytes()]));
+                blocks += 1;
+            }
+            at += w;
+            if let Some(x) = field_candidate(&stream[at - w..at]) {
+                break x;
+            }
+        };
+    }
+    out
+}
+
 // ---------------- Hash backend abstraction ----------------
 
-pub trait HashBackend {
+/// A Fiat–Shamir hash over challenge field `F`.
+pub trait HashBackend<F: PrimeField = Goldilocks> {
     fn name(&self) -> &'static str;
     fn absorb_bytes(&mut self, bytes: &[u8]);
     fn absorb_field(&mut self, x: F);
+    /// Same as [`HashBackend::absorb_field`] on each element in turn;
+    /// backends override it to skip the per-element overhead.
+    fn absorb_field_slice(&mut self, xs: &[F]) {
+        for &x in xs {
+            self.absorb_field(x);
+        }
+    }
     fn challenge(&mut self, label: &[u8]) -> F;
+    /// Coordinates of a cubic-extension challenge, from a single squeeze
+    /// under `label`.
+    fn challenge_ext3(&mut self, label: &[u8]) -> [F; 3];
+    /// Two elements from a single squeeze under `label`, carrying about
+    /// 128 bits of entropy together.
+    fn challenge_wide(&mut self, label: &[u8]) -> [F; 2];
+    /// Fill `out` with uniform bytes squeezed under `label`; the length is
+    /// part of the derivation.
+    fn challenge_bytes(&mut self, label: &[u8], out: &mut [u8]);
+    /// Everything [`HashBackend::restore_state`] needs to continue from here.
+    fn save_state(&self) -> TranscriptState;
+    fn restore_state(&mut self, state: &TranscriptState) -> Result<(), RestoreError>;
+    /// Copy of the backend in its current state.
+    fn boxed_clone(&self) -> Box<dyn HashBackend<F>>;
+    /// Digest of the permutation parameters, which [`FsVersion::V2`]
+    /// transcripts absorb; `None` for backends without any.
+    fn params_digest(&self) -> Option<[u8; 32]> {
+        None
+    }
+    /// Permutations run so far; 0 for backends that cannot count them,
+    /// such as the byte hashers.
+    fn permutations(&self) -> u64 {
+        0
+    }
+}
+
+// ---------------- Saved state ----------------
+
+/// Snapshot of a transcript's hash state, taken by
+/// [`Transcript::save_state`]. The SHA3 and Blake3 hashers cannot export
+/// their internals, so their snapshot is the byte stream absorbed since
+/// initialisation and restoring replays it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[allow(clippy::large_enum_variant)]
+pub enum TranscriptState {
+    /// Sponge state and absorb position; the parameters stay with the
+    /// restoring backend.
+    Poseidon {
+        #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+        state: [F; ::poseidon::T],
+        pos: usize,
+    },
+    Sha3 { absorbed: Vec<u8> },
+    Blake3 { absorbed: Vec<u8> },
+    RescuePrime {
+        #[cfg_attr(feature = "serde1", serde(with = "ark_goldilocks::serde_canonical"))]
+        state: [F; rescue::T],
+        pos: usize,
+    },
+    #[cfg(feature = "transcript-keccak")]
+    Keccak256 { absorbed: Vec<u8> },
+    /// Opaque state of a backend outside this crate, in whatever encoding
+    /// it restores from.
+    Custom { state: Vec<u8> },
+}
+
+impl TranscriptState {
+    /// [`HashBackend::name`] of the backend that saved it.
+    pub fn backend_name(&self) -> &'static str {
+        match self {
+            TranscriptState::Poseidon { .. } => "poseidon",
+            TranscriptState::Sha3 { .. } => "sha3-256",
+            TranscriptState::Blake3 { .. } => "blake3",
+            TranscriptState::RescuePrime { .. } => "rescue-prime",
+            #[cfg(feature = "transcript-keccak")]
+            TranscriptState::Keccak256 { .. } => "keccak-256",
+            TranscriptState::Custom { .. } => "custom",
+        }
+    }
+}
+
+/// A [`TranscriptState`] the transcript cannot resume from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RestoreError {
+    /// Saved by a `state` backend, restored into a `backend` one.
+    Backend { backend: &'static str, state: &'static str },
+    /// Poseidon absorb position past the rate.
+    Position { pos: usize },
+}
+
+impl core::fmt::Display for RestoreError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RestoreError::Backend { backend, state } => {
+                write!(f, "{state} transcript state restored into a {backend} transcript")
+            }
+            RestoreError::Position { pos } => write!(f, "sponge position {pos} past the rate"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RestoreError {}
+
+fn backend_mismatch<F: PrimeField>(
+    backend: &dyn HashBackend<F>,
+    state: &TranscriptState,
+) -> RestoreError {
+    RestoreError::Backend {
+        backend: backend.name(),
+        state: state.backend_name(),
+    }
 }
 
 // ---------------- Poseidon backend ----------------
 
+pub mod plonky2;
+pub use plonky2::Plonky2Challenger;
+
+pub mod rescue;
+
+pub mod rng;
+pub use rng::TranscriptRng;
+
+pub mod trace;
+pub use trace::{TraceOp, TranscriptTrace};
+
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "std")]
+pub use registry::{register_backend, BackendFactory};
+
 pub mod poseidon {
     pub use ::poseidon::*;
 }
@@ -51,10 +310,16 @@ mod poseidon_backend {
     use super::*;
     use ::poseidon::{permute, PoseidonParams, RATE, T};
 
+    #[derive(Clone)]
     pub struct PoseidonBackend {
         pub(crate) state: [F; T],
         pub(crate) pos: usize,
+        /// Permutations run since construction, for [`Transcript::stats`].
+        pub(crate) permutations: u64,
         pub(crate) params: PoseidonParams,
+        /// Squeeze as a padded duplex ([`FsVersion::V2`]) rather than the
+        /// original sponge.
+        pub(crate) duplex: bool,
     }
 
     impl PoseidonBackend {
@@ -63,24 +328,72 @@ mod poseidon_backend {
                 state: [F::zero(); T],
                 pos: 0,
                 params,
+                permutations: 0,
+                duplex: false,
             };
             s.state[T - 1] = super::domain_tag_to_field(super::ds::TRANSCRIPT_INIT);
             s.absorb_bytes(init_label);
             s
         }
 
+        /// As [`Self::new`], with `session_key` filling the first rate
+        /// block under the keyed init tag and permuted into the whole
+        /// state before `init_label` is absorbed.
+        pub fn new_keyed(params: PoseidonParams, init_label: &[u8], session_key: &[u8; 32]) -> Self {
+            let mut s = Self {
+                state: [F::zero(); T],
+                pos: 0,
+                params,
+                permutations: 0,
+                duplex: false,
+            };
+            s.state[T - 1] = super::domain_tag_to_field(super::ds::KEYED_INIT);
+            s.state[..8].copy_from_slice(&super::session_key_words(session_key));
+            s.permute();
+            s.absorb_bytes(init_label);
+            s
+        }
+
+        fn permute(&mut self) {
+            permute(&mut self.state, &self.params);
+            self.permutations += 1;
+        }
+
         fn absorb_field_internal(&mut self, x: F) {
             if self.pos == RATE {
-                permute(&mut self.state, &self.params);
+                self.permute();
                 self.pos = 0;
             }
             self.state[self.pos] += x;
             self.pos += 1;
         }
 
+        /// Switch to duplex squeezing, before anything is squeezed. The
+        /// original squeeze permutes and leaves `pos` at 0, so the next
+        /// absorb lands on the lane just output and input and output blocks
+        /// share a permutation; absorbing a zero is also invisible to it.
+        /// The duplex pads the input with a one, permutes, and moves `pos`
+        /// to the end of the rate so the next absorb starts on a fresh
+        /// permutation. The capacity is never written either way.
+        pub fn into_duplex(mut self) -> Self {
+            self.duplex = true;
+            self
+        }
+
+        /// Permute the absorbed input into a block of output in the rate.
+        fn squeeze_block(&mut self) {
+            if self.duplex {
+                self.absorb_field_internal(F::one());
+                self.permute();
+                self.pos = RATE;
+            } else {
+                self.permute();
+                self.pos = 0;
+            }
+        }
+
         fn squeeze(&mut self) -> F {
-            permute(&mut self.state, &self.params);
-            self.pos = 0;
+            self.squeeze_block();
             self.state[0]
         }
     }
@@ -99,11 +412,100 @@ mod poseidon_backend {
             self.absorb_field_internal(x);
         }
 
+        /// Adds whole runs of the rate at a time.
+        fn absorb_field_slice(&mut self, mut xs: &[F]) {
+            while !xs.is_empty() {
+                if self.pos == RATE {
+                    self.permute();
+                    self.pos = 0;
+                }
+                let n = xs.len().min(RATE - self.pos);
+                for (lane, x) in self.state[self.pos..self.pos + n].iter_mut().zip(&xs[..n]) {
+                    *lane += x;
+                }
+                self.pos += n;
+                xs = &xs[n..];
+            }
+        }
+
         fn challenge(&mut self, label: &[u8]) -> F {
             self.absorb_field_internal(super::domain_tag_to_field(super::ds::CHALLENGE));
             self.absorb_bytes(label);
             self.squeeze()
         }
+
+        /// The tag goes in as bytes: its first word alone would collide
+        /// with `challenge`'s.
+        fn challenge_ext3(&mut self, label: &[u8]) -> [F; 3] {
+            self.absorb_bytes(super::ds::CHALLENGE_EXT3);
+            self.absorb_bytes(label);
+            self.squeeze_block();
+            [self.state[0], self.state[1], self.state[2]]
+        }
+
+        fn challenge_wide(&mut self, label: &[u8]) -> [F; 2] {
+            self.absorb_bytes(super::ds::CHALLENGE_WIDE);
+            self.absorb_bytes(label);
+            self.squeeze_block();
+            [self.state[0], self.state[1]]
+        }
+
+        /// Four bytes from the low half of each rate element. Skipping
+        /// `p − 1` leaves `(2^32 − 1)·2^32` equally likely values, so the
+        /// low 32 bits are exactly uniform.
+        fn challenge_bytes(&mut self, label: &[u8], out: &mut [u8]) {
+            self.absorb_bytes(super::ds::CHALLENGE_BYTES);
+            self.absorb_bytes(label);
+            self.absorb_field_internal(F::from(out.len() as u64));
+            let mut words = Vec::with_capacity(out.len().div_ceil(4));
+            self.squeeze_block();
+            loop {
+                let limbs = self.state[..RATE].iter().map(|x| x.into_bigint().0[0]);
+                words.extend(limbs.filter(|&x| x != F::MODULUS.0[0] - 1).map(|x| x as u32));
+                if words.len() * 4 >= out.len() {
+                    break;
+                }
+                self.permute();
+            }
+            for (chunk, w) in out.chunks_mut(4).zip(words) {
+                chunk.copy_from_slice(&w.to_le_bytes()[..chunk.len()]);
+            }
+        }
+
+        fn permutations(&self) -> u64 {
+            self.permutations
+        }
+
+        fn params_digest(&self) -> Option<[u8; 32]> {
+            let p = &self.params;
+            let elems = p.mds.iter().flatten().chain(p.rc_full.iter().flatten()).chain(&p.rc_partial);
+            Some(params_digest_of(b"poseidon", elems))
+        }
+
+        fn boxed_clone(&self) -> Box<dyn HashBackend> {
+            Box::new(self.clone())
+        }
+
+        fn save_state(&self) -> TranscriptState {
+            TranscriptState::Poseidon {
+                state: self.state,
+                pos: self.pos,
+            }
+        }
+
+        fn restore_state(&mut self, state: &TranscriptState) -> Result<(), RestoreError> {
+            match *state {
+                TranscriptState::Poseidon { pos, .. } if pos > RATE => {
+                    Err(RestoreError::Position { pos })
+                }
+                TranscriptState::Poseidon { state, pos } => {
+                    self.state = state;
+                    self.pos = pos;
+                    Ok(())
+                }
+                _ => Err(super::backend_mismatch(self, state)),
+            }
+        }
     }
 
     pub fn default_params() -> PoseidonParams {
@@ -123,161 +525,1891 @@ mod sha3_backend {
     use super::*;
     use sha3::{Digest, Sha3_256};
 
-    #[derive(Clone)]
-    pub struct Sha3Backend {
+    /// SHA3-256 over the absorbed byte stream; challenges hash a copy, so
+    /// they do not advance the state.
+    pub struct Sha3Backend<F = Goldilocks> {
         h: Sha3_256,
+        /// Every byte fed to `h`, for [`HashBackend::save_state`].
+        absorbed: Vec<u8>,
+        _field: PhantomData<F>,
+    }
+
+    impl<F> Clone for Sha3Backend<F> {
+        fn clone(&self) -> Self {
+            Self { h: self.h.clone(), absorbed: self.absorbed.clone(), _field: PhantomData }
+        }
     }
 
-    impl Sha3Backend {
+    impl<F: PrimeField> Sha3Backend<F> {
         pub fn new(init_label: &[u8]) -> Self {
-            let mut h = Sha3_256::new();
-            h.update(super::ds::TRANSCRIPT_INIT);
-            h.update(init_label);
-            Self { h }
+            let mut s = Self {
+                h: Sha3_256::new(),
+                absorbed: Vec::new(),
+                _field: PhantomData,
+            };
+            s.update(super::ds::TRANSCRIPT_INIT);
+            s.update(init_label);
+            s
+        }
+
+        /// As [`Self::new`], with the keyed init tag and `session_key`
+        /// ahead of `init_label`.
+        pub fn new_keyed(init_label: &[u8], session_key: &[u8; 32]) -> Self {
+            let mut s = Self {
+                h: Sha3_256::new(),
+                absorbed: Vec::new(),
+                _field: PhantomData,
+            };
+            s.update(super::ds::KEYED_INIT);
+            s.update(session_key);
+            s.update(init_label);
+            s
+        }
+
+        fn update(&mut self, bytes: &[u8]) {
+            self.h.update(bytes);
+            self.absorbed.extend_from_slice(bytes);
+        }
+
+        fn digest(&self, parts: &[&[u8]]) -> [u8; 32] {
+            let mut h2 = self.h.clone();
+            for part in parts {
+                h2.update(part);
+            }
+            h2.finalize().into()
         }
     }
 
-    impl HashBackend for Sha3Backend {
+    impl<F: PrimeField> HashBackend<F> for Sha3Backend<F> {
         fn name(&self) -> &'static str { "sha3-256" }
 
         fn absorb_bytes(&mut self, bytes: &[u8]) {
-            self.h.update(super::ds::ABSORB_BYTES);
-            self.h.update(bytes);
+            self.update(super::ds::ABSORB_BYTES);
+            self.update(bytes);
         }
 
         fn absorb_field(&mut self, x: F) {
-            let le = x.into_bigint().to_bytes_le();
-            self.absorb_bytes(&le[..8.min(le.len())]);
+            self.absorb_bytes(&field_to_bytes(x));
+        }
+
+        fn absorb_field_slice(&mut self, xs: &[F]) {
+            self.update(&field_slice_bytes(xs));
         }
 
         fn challenge(&mut self, label: &[u8]) -> F {
-            let mut h2 = self.h.clone();
-            h2.update(super::ds::CHALLENGE);
-            h2.update(label);
-            let out = h2.finalize();
-            bytes_to_field_u64(&out[..8])
+            let [c] = digest_words(|p| self.digest(p), super::ds::CHALLENGE, label);
+            c
+        }
+
+        fn challenge_ext3(&mut self, label: &[u8]) -> [F; 3] {
+            digest_words(|p| self.digest(p), super::ds::CHALLENGE_EXT3, label)
+        }
+
+        /// For Goldilocks, the first 16 bytes of the digest as two words
+        /// mod p.
+        fn challenge_wide(&mut self, label: &[u8]) -> [F; 2] {
+            digest_words(|p| self.digest(p), super::ds::CHALLENGE_WIDE, label)
+        }
+
+        /// Counter mode: block `i` is the digest of the tagged prefix and
+        /// `i` as 8 little-endian bytes.
+        fn challenge_bytes(&mut self, label: &[u8], out: &mut [u8]) {
+            let len = (out.len() as u64).to_le_bytes();
+            for (i, chunk) in out.chunks_mut(32).enumerate() {
+                let block = self.digest(&[
+                    super::ds::CHALLENGE_BYTES,
+                    label,
+                    &len,
+                    &(i as u64).to_le_bytes(),
+                ]);
+                chunk.copy_from_slice(&block[..chunk.len()]);
+            }
+        }
+
+        fn boxed_clone(&self) -> Box<dyn HashBackend<F>> {
+            Box::new(self.clone())
+        }
+
+        fn save_state(&self) -> TranscriptState {
+            TranscriptState::Sha3 {
+                absorbed: self.absorbed.clone(),
+            }
+        }
+
+        fn restore_state(&mut self, state: &TranscriptState) -> Result<(), RestoreError> {
+            let TranscriptState::Sha3 { absorbed } = state else {
+                return Err(super::backend_mismatch(self, state));
+            };
+            self.h = Sha3_256::new();
+            self.absorbed.clear();
+            self.update(absorbed);
+            Ok(())
         }
     }
 
     pub fn make(label: &[u8]) -> Sha3Backend {
         Sha3Backend::new(label)
     }
-
-    pub(crate) use Sha3Backend as Backend;
 }
 
+pub use sha3_backend::Sha3Backend;
+
 // ---------------- Blake3 backend ----------------
 
 mod blake3_backend {
     use super::*;
 
-    #[derive(Clone)]
-    pub struct Blake3Backend {
+    /// Blake3 over the absorbed byte stream; challenges hash a copy.
+    pub struct Blake3Backend<F = Goldilocks> {
         h: blake3::Hasher,
+        /// Every byte fed to `h`, for [`HashBackend::save_state`].
+        absorbed: Vec<u8>,
+        _field: PhantomData<F>,
+    }
+
+    impl<F> Clone for Blake3Backend<F> {
+        fn clone(&self) -> Self {
+            Self { h: self.h.clone(), absorbed: self.absorbed.clone(), _field: PhantomData }
+        }
     }
 
-    impl Blake3Backend {
+    impl<F: PrimeField> Blake3Backend<F> {
         pub fn new(init_label: &[u8]) -> Self {
-            let mut h = blake3::Hasher::new();
-            h.update(super::ds::TRANSCRIPT_INIT);
-            h.update(init_label);
-            Self { h }
+            let mut s = Self {
+                h: blake3::Hasher::new(),
+                absorbed: Vec::new(),
+                _field: PhantomData,
+            };
+            s.update(super::ds::TRANSCRIPT_INIT);
+            s.update(init_label);
+            s
+        }
+
+        /// As [`Self::new`], with the keyed init tag and `session_key`
+        /// ahead of `init_label`.
+        pub fn new_keyed(init_label: &[u8], session_key: &[u8; 32]) -> Self {
+            let mut s = Self {
+                h: blake3::Hasher::new(),
+                absorbed: Vec::new(),
+                _field: PhantomData,
+            };
+            s.update(super::ds::KEYED_INIT);
+            s.update(session_key);
+            s.update(init_label);
+            s
+        }
+
+        fn update(&mut self, bytes: &[u8]) {
+            self.h.update(bytes);
+            self.absorbed.extend_from_slice(bytes);
+        }
+
+        fn digest(&self, parts: &[&[u8]]) -> [u8; 32] {
+            let mut h2 = self.h.clone();
+            for part in parts {
+                h2.update(part);
+            }
+            *h2.finalize().as_bytes()
         }
     }
 
-    impl HashBackend for Blake3Backend {
+    impl<F: PrimeField> HashBackend<F> for Blake3Backend<F> {
         fn name(&self) -> &'static str { "blake3" }
 
         fn absorb_bytes(&mut self, bytes: &[u8]) {
-            self.h.update(super::ds::ABSORB_BYTES);
-            self.h.update(bytes);
+            self.update(super::ds::ABSORB_BYTES);
+            self.update(bytes);
         }
 
         fn absorb_field(&mut self, x: F) {
-            let le = x.into_bigint().to_bytes_le();
-            self.absorb_bytes(&le[..8.min(le.len())]);
+            self.absorb_bytes(&field_to_bytes(x));
+        }
+
+        fn absorb_field_slice(&mut self, xs: &[F]) {
+            self.update(&field_slice_bytes(xs));
         }
 
         fn challenge(&mut self, label: &[u8]) -> F {
+            let [c] = digest_words(|p| self.digest(p), super::ds::CHALLENGE, label);
+            c
+        }
+
+        fn challenge_ext3(&mut self, label: &[u8]) -> [F; 3] {
+            digest_words(|p| self.digest(p), super::ds::CHALLENGE_EXT3, label)
+        }
+
+        fn challenge_wide(&mut self, label: &[u8]) -> [F; 2] {
+            digest_words(|p| self.digest(p), super::ds::CHALLENGE_WIDE, label)
+        }
+
+        /// Read straight from Blake3's extendable output.
+        fn challenge_bytes(&mut self, label: &[u8], out: &mut [u8]) {
             let mut h2 = self.h.clone();
-            h2.update(super::ds::CHALLENGE);
+            h2.update(super::ds::CHALLENGE_BYTES);
             h2.update(label);
-            let out = h2.finalize();
-            bytes_to_field_u64(out.as_bytes())
+            h2.update(&(out.len() as u64).to_le_bytes());
+            h2.finalize_xof().fill(out);
+        }
+
+        fn boxed_clone(&self) -> Box<dyn HashBackend<F>> {
+            Box::new(self.clone())
+        }
+
+        fn save_state(&self) -> TranscriptState {
+            TranscriptState::Blake3 {
+                absorbed: self.absorbed.clone(),
+            }
+        }
+
+        fn restore_state(&mut self, state: &TranscriptState) -> Result<(), RestoreError> {
+            let TranscriptState::Blake3 { absorbed } = state else {
+                return Err(super::backend_mismatch(self, state));
+            };
+            self.h = blake3::Hasher::new();
+            self.absorbed.clear();
+            self.update(absorbed);
+            Ok(())
         }
     }
 
     pub fn make(label: &[u8]) -> Blake3Backend {
         Blake3Backend::new(label)
     }
+}
+
+pub use blake3_backend::Blake3Backend;
+
+// ---------------- Keccak-256 backend ----------------
+
+#[cfg(feature = "transcript-keccak")]
+mod keccak_backend {
+    use super::*;
+    use tiny_keccak::{Hasher, Keccak};
+
+    /// Keccak-256 as computed by the EVM `keccak256` opcode (original Keccak
+    /// padding, not SHA3-256), so a Solidity verifier can replay the
+    /// transcript. Like the SHA3 backend it hashes one growing byte string `s`:
+    ///
+    /// - construction: `s = TRANSCRIPT_INIT ‖ init_label`;
+    /// - `absorb_bytes(b)`: `s ‖= ABSORB_BYTES ‖ b`;
+    /// - `absorb_field(x)`: `absorb_bytes` of the canonical `x` as 8
+    ///   little-endian bytes (32 for a 255-bit field);
+    /// - `challenge(label)`: `h = keccak256(s ‖ CHALLENGE ‖ label)`, and the
+    ///   challenge is `h[0..8]` read little-endian if that is below p,
+    ///   otherwise the next such 8-byte word; should `h` run out, the words
+    ///   continue into `keccak256(s ‖ CHALLENGE ‖ label ‖ i)` for
+    ///   `i = 1, 2, ..` as 8 little-endian bytes;
+    /// - `challenge_ext3(label)`: likewise under `CHALLENGE_EXT3`, taking
+    ///   three words, normally `h[0..8]`, `h[8..16]`, `h[16..24]`;
+    /// - `challenge_wide(label)`: likewise under `CHALLENGE_WIDE`, taking
+    ///   two words;
+    /// - `challenge_bytes(label, len)`: block `i` of the output is
+    ///   `keccak256(s ‖ CHALLENGE_BYTES ‖ label ‖ len ‖ i)`, with `len` and `i`
+    ///   as 8 little-endian bytes.
+    ///
+    /// Tags are the byte strings in [`ds`], without length prefixes, so `s` is
+    /// `abi.encodePacked` of the pieces. Challenges do not extend `s`; protocols
+    /// absorb what they squeezed. Solidity reads words big-endian, so the 8-byte
+    /// words must be byte-reversed on chain.
+    ///
+    /// Over fields wider than Goldilocks, words are `field_bytes` long with
+    /// the bits above the modulus width cleared; see [`digest_words`].
+    pub struct Keccak256Backend<F = Goldilocks> {
+        h: Keccak,
+        /// Every byte fed to `h`, for [`HashBackend::save_state`].
+        absorbed: Vec<u8>,
+        _field: PhantomData<F>,
+    }
+
+    impl<F> Clone for Keccak256Backend<F> {
+        fn clone(&self) -> Self {
+            Self { h: self.h.clone(), absorbed: self.absorbed.clone(), _field: PhantomData }
+        }
+    }
+
+    impl<F: PrimeField> Keccak256Backend<F> {
+        pub fn new(init_label: &[u8]) -> Self {
+            let mut s = Self {
+                h: Keccak::v256(),
+                absorbed: Vec::new(),
+                _field: PhantomData,
+            };
+            s.update(super::ds::TRANSCRIPT_INIT);
+            s.update(init_label);
+            s
+        }
+
+        /// As [`Self::new`], with the keyed init tag and `session_key`
+        /// ahead of `init_label`.
+        pub fn new_keyed(init_label: &[u8], session_key: &[u8; 32]) -> Self {
+            let mut s = Self {
+                h: Keccak::v256(),
+                absorbed: Vec::new(),
+                _field: PhantomData,
+            };
+            s.update(super::ds::KEYED_INIT);
+            s.update(session_key);
+            s.update(init_label);
+            s
+        }
+
+        fn update(&mut self, bytes: &[u8]) {
+            self.h.update(bytes);
+            self.absorbed.extend_from_slice(bytes);
+        }
+
+        fn digest(&self, parts: &[&[u8]]) -> [u8; 32] {
+            let mut h2 = self.h.clone();
+            for part in parts {
+                h2.update(part);
+            }
+            let mut out = [0u8; 32];
+            h2.finalize(&mut out);
+            out
+        }
+    }
+
+    impl<F: PrimeField> HashBackend<F> for Keccak256Backend<F> {
+        fn name(&self) -> &'static str { "keccak-256" }
+
+        fn absorb_bytes(&mut self, bytes: &[u8]) {
+            self.update(super::ds::ABSORB_BYTES);
+            self.update(bytes);
+        }
+
+        fn absorb_field(&mut self, x: F) {
+            self.absorb_bytes(&field_to_bytes(x));
+        }
+
+        fn absorb_field_slice(&mut self, xs: &[F]) {
+            self.update(&field_slice_bytes(xs));
+        }
+
+        fn challenge(&mut self, label: &[u8]) -> F {
+            let [c] = digest_words(|p| self.digest(p), super::ds::CHALLENGE, label);
+            c
+        }
+
+        fn challenge_ext3(&mut self, label: &[u8]) -> [F; 3] {
+            digest_words(|p| self.digest(p), super::ds::CHALLENGE_EXT3, label)
+        }
+
+        fn challenge_wide(&mut self, label: &[u8]) -> [F; 2] {
+            digest_words(|p| self.digest(p), super::ds::CHALLENGE_WIDE, label)
+        }
+
+        fn challenge_bytes(&mut self, label: &[u8], out: &mut [u8]) {
+            let len = (out.len() as u64).to_le_bytes();
+            for (i, chunk) in out.chunks_mut(32).enumerate() {
+                let block = self.digest(&[
+                    super::ds::CHALLENGE_BYTES,
+                    label,
+                    &len,
+                    &(i as u64).to_le_bytes(),
+                ]);
+                chunk.copy_from_slice(&block[..chunk.len()]);
+            }
+        }
+
+        fn boxed_clone(&self) -> Box<dyn HashBackend<F>> {
+            Box::new(self.clone())
+        }
+
+        fn save_state(&self) -> TranscriptState {
+            TranscriptState::Keccak256 {
+                absorbed: self.absorbed.clone(),
+            }
+        }
 
-    pub(crate) use Blake3Backend as Backend;
+        fn restore_state(&mut self, state: &TranscriptState) -> Result<(), RestoreError> {
+            let TranscriptState::Keccak256 { absorbed } = state else {
+                return Err(super::backend_mismatch(self, state));
+            };
+            self.h = Keccak::v256();
+            self.absorbed.clear();
+            self.update(absorbed);
+            Ok(())
+        }
+    }
 }
 
+#[cfg(feature = "transcript-keccak")]
+pub use keccak_backend::Keccak256Backend;
+
 // ---------------- Public Transcript API ----------------
 
+/// Work done through one [`Transcript`], from [`Transcript::stats`].
+/// Higher-level calls count as the absorbs and squeezes they make, tags
+/// included.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TranscriptStats {
+    pub absorbed_bytes: u64,
+    pub absorbed_fields: u64,
+    /// Squeezes of any kind: field, extension, wide and byte challenges.
+    pub challenges: u64,
+    /// Sponge permutations, where the backend counts them
+    /// ([`HashBackend::permutations`]).
+    pub permutations: u64,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum FsHash {
     Poseidon,
     Sha3_256,
     Blake3,
+    /// Rescue-Prime sponge with its own parameters ([`rescue::default_params`]);
+    /// the Poseidon parameters passed to the constructor are unused.
+    RescuePrime,
+    /// EVM-compatible Keccak-256; see [`Keccak256Backend`] for the byte
+    /// layout.
+    #[cfg(feature = "transcript-keccak")]
+    Keccak256,
 }
 
 pub use poseidon_backend::default_params;
 
-/// ✅ Backward-compatible Transcript wrapper
-pub struct Transcript {
-    backend: Box<dyn HashBackend>,
+/// Fiat–Shamir encoding a transcript follows. Each version fixes how
+/// challenges are derived, so a verifier must build its transcript with
+/// the version the proof declares.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum FsVersion {
+    /// The original encoding; every existing proof uses it.
+    #[default]
+    V1,
+    /// Also binds the backend name and its permutation parameters at init,
+    /// so transcripts over different hashes or constants never agree, and
+    /// frames every [`Transcript::absorb_bytes`] message: its length as 8
+    /// little-endian bytes, the bytes, then zeros up to a multiple of 8.
+    /// Under V1 `absorb_bytes(a); absorb_bytes(b)` can absorb exactly what
+    /// another split of the same bytes does. Poseidon squeezes as a padded
+    /// duplex sponge, so input and output never share a permutation.
+    V2,
 }
 
-impl Transcript {
-    /// Default = Poseidon
-    pub fn new(init_label: &[u8], params: poseidon::PoseidonParams) -> Self {
-        Self::with_backend(FsHash::Poseidon, init_label, params)
+impl FsVersion {
+    pub const ALL: [FsVersion; 2] = [FsVersion::V1, FsVersion::V2];
+
+    /// Stable identifier, for proof headers.
+    pub fn id(self) -> u8 {
+        match self {
+            FsVersion::V1 => 1,
+            FsVersion::V2 => 2,
+        }
     }
 
-    pub fn with_backend(
-        hash: FsHash,
-        init_label: &[u8],
-        params: poseidon::PoseidonParams,
-    ) -> Self {
-        emit_selected_backend_once();
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|v| v.id() == id)
+    }
+}
 
-        let backend: Box<dyn HashBackend> = match hash {
-            FsHash::Poseidon => {
-                Box::new(poseidon_backend::Backend::new(params, init_label))
-            }
-            FsHash::Sha3_256 => {
-                Box::new(sha3_backend::Backend::new(init_label))
-            }
-            FsHash::Blake3 => {
-                Box::new(blake3_backend::Backend::new(init_label))
-            }
-        };
+/// How a transcript draws its challenges, as a proof declares it. A
+/// verifier compares the proof's binding with its own transcript's before
+/// drawing anything, so a proof made over another backend or parameter
+/// set is refused outright instead of failing wherever its challenges
+/// first matter. [`FsVersion::V2`] transcripts also absorb the backend
+/// name and parameter digest at init.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct FsBinding {
+    pub version: FsVersion,
+    /// [`HashBackend::name`].
+    pub backend: String,
+    /// [`HashBackend::params_digest`].
+    pub params_digest: Option<[u8; 32]>,
+}
 
-        Self { backend }
+impl core::fmt::Display for FsBinding {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} under FSv{}", self.backend, self.version.id())?;
+        if let Some(digest) = &self.params_digest {
+            write!(f, ", params ")?;
+            digest[..4].iter().try_for_each(|b| write!(f, "{b:02x}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Everything besides the init label that fixes a Goldilocks transcript's
+/// challenges, for [`Transcript::from_config`].
+///
+/// ```
+/// # use transcript::{FsConfig, FsHash, FsVersion, Transcript};
+/// let cfg = FsConfig::builder()
+///     .hash(FsHash::Blake3)
+///     .grinding_bits(16)
+///     .version(FsVersion::V2)
+///     .build();
+/// let tr = Transcript::from_config(&cfg, b"example");
+/// assert_eq!(tr.grinding_bits(), 16);
+/// ```
+#[derive(Clone)]
+pub struct FsConfig {
+    pub hash: FsHash,
+    /// Poseidon constants, [`default_params`] if `None`. Only
+    /// [`FsHash::Poseidon`] reads them.
+    pub poseidon: Option<poseidon::PoseidonParams>,
+    /// Proof-of-work the protocol asks of [`Transcript::grind`], at most 64.
+    pub grinding_bits: u8,
+    pub version: FsVersion,
+}
+
+impl FsConfig {
+    pub fn new(hash: FsHash) -> Self {
+        Self {
+            hash,
+            poseidon: None,
+            grinding_bits: 0,
+            version: FsVersion::V1,
+        }
+    }
+
+    pub fn builder() -> FsConfigBuilder {
+        FsConfigBuilder::default()
+    }
+}
+
+/// Poseidon with its default constants, no grinding, [`FsVersion::V1`]:
+/// what [`Transcript::new`] builds with [`default_params`].
+impl Default for FsConfig {
+    fn default() -> Self {
+        Self::new(FsHash::Poseidon)
+    }
+}
+
+/// Field-by-field construction of an [`FsConfig`], starting from its
+/// [`Default`].
+#[derive(Clone, Default)]
+pub struct FsConfigBuilder {
+    cfg: FsConfig,
+}
+
+impl FsConfigBuilder {
+    pub fn hash(mut self, hash: FsHash) -> Self {
+        self.cfg.hash = hash;
+        self
+    }
+
+    pub fn poseidon(mut self, params: poseidon::PoseidonParams) -> Self {
+        self.cfg.poseidon = Some(params);
+        self
+    }
+
+    /// Panics past 64 bits, which [`Transcript::grind`] cannot meet.
+    pub fn grinding_bits(mut self, bits: u8) -> Self {
+        assert!(bits <= 64, "grinding past 64 bits");
+        self.cfg.grinding_bits = bits;
+        self
+    }
+
+    pub fn version(mut self, version: FsVersion) -> Self {
+        self.cfg.version = version;
+        self
+    }
+
+    pub fn build(self) -> FsConfig {
+        self.cfg
+    }
+}
+
+/// ✅ Backward-compatible Transcript wrapper
+///
+/// `Transcript` alone is the Goldilocks transcript, as before; other fields
+/// build one with [`Transcript::from_backend`] over a byte backend.
+pub struct Transcript<F: PrimeField = Goldilocks> {
+    backend: Box<dyn HashBackend<F>>,
+    /// Operations so far, while [`Transcript::start_trace`] is in effect.
+    trace: Option<TranscriptTrace<F>>,
+    version: FsVersion,
+    stats: TranscriptStats,
+    /// Backend permutation count when this transcript began.
+    permutations_base: u64,
+    scopes: ScopeStack,
+    /// [`FsConfig::grinding_bits`] of the config it was built from.
+    grinding_bits: u8,
+}
+
+/// Open scopes as the prefix they give challenge labels, `outer/inner/`,
+/// and where each one starts in it.
+#[derive(Clone, Default)]
+struct ScopeStack {
+    prefix: Vec<u8>,
+    starts: Vec<usize>,
+}
+
+/// The transcript the Goldilocks prover and verifier use.
+pub type GoldilocksTranscript = Transcript<Goldilocks>;
+
+/// An exact copy, trace included, that evolves independently: a verifier
+/// can try a branch on the copy and keep or drop it. Unlike
+/// [`Transcript::fork`], nothing is absorbed, so the copy draws the same
+/// challenges as the original would.
+impl<F: PrimeField> Clone for Transcript<F> {
+    fn clone(&self) -> Self {
+        Self {
+            backend: self.backend.boxed_clone(),
+            trace: self.trace.clone(),
+            version: self.version,
+            stats: self.stats,
+            permutations_base: self.permutations_base,
+            scopes: self.scopes.clone(),
+            grinding_bits: self.grinding_bits,
+        }
+    }
+}
+
+impl Transcript {
+    /// Default = Poseidon
+    pub fn new(init_label: &[u8], params: poseidon::PoseidonParams) -> Self {
+        Self::build(FsHash::Poseidon, init_label, None, params, FsVersion::V1)
     }
 
+    /// Transcript over `cfg.hash`, following `cfg.version`.
+    pub fn from_config(cfg: &FsConfig, init_label: &[u8]) -> Self {
+        let params = cfg.poseidon.clone().unwrap_or_else(default_params);
+        let mut tr = Self::build(cfg.hash, init_label, None, params, cfg.version);
+        tr.bind_version(cfg.version);
+        tr.grinding_bits = cfg.grinding_bits;
+        tr
+    }
+
+    #[deprecated(note = "use `Transcript::from_config`, which only needs Poseidon params for Poseidon")]
+    pub fn with_backend(
+        hash: FsHash,
+        init_label: &[u8],
+        params: poseidon::PoseidonParams,
+    ) -> Self {
+        Self::build(hash, init_label, None, params, FsVersion::V1)
+    }
+
+    /// As [`Transcript::with_backend`], following `version`.
+    #[deprecated(note = "use `Transcript::from_config` with `FsConfig::version`")]
+    pub fn with_backend_version(
+        hash: FsHash,
+        init_label: &[u8],
+        params: poseidon::PoseidonParams,
+        version: FsVersion,
+    ) -> Self {
+        let mut tr = Self::build(hash, init_label, None, params, version);
+        tr.bind_version(version);
+        tr
+    }
+
+    /// Poseidon transcript bound to a per-session key (nonce), so parallel
+    /// proofs under the same labels draw unrelated challenges.
+    pub fn new_keyed(init_label: &[u8], session_key: &[u8; 32], params: poseidon::PoseidonParams) -> Self {
+        Self::with_backend_keyed(FsHash::Poseidon, init_label, session_key, params)
+    }
+
+    /// As [`Transcript::from_config`] with `hash` and `params`, keyed as in
+    /// [`Transcript::new_keyed`].
+    /// The sponges take the key as their first rate block under a distinct
+    /// capacity tag; the byte backends hash it after a distinct init tag.
+    pub fn with_backend_keyed(
+        hash: FsHash,
+        init_label: &[u8],
+        session_key: &[u8; 32],
+        params: poseidon::PoseidonParams,
+    ) -> Self {
+        Self::build(hash, init_label, Some(session_key), params, FsVersion::V1)
+    }
+
+    fn build(
+        hash: FsHash,
+        init_label: &[u8],
+        key: Option<&[u8; 32]>,
+        params: poseidon::PoseidonParams,
+        version: FsVersion,
+    ) -> Self {
+        emit_selected_backend_once();
+
+        let backend: Box<dyn HashBackend> = match (hash, key) {
+            (FsHash::Poseidon, key) => {
+                let backend = match key {
+                    None => poseidon_backend::Backend::new(params, init_label),
+                    Some(key) => poseidon_backend::Backend::new_keyed(params, init_label, key),
+                };
+                match version {
+                    FsVersion::V1 => Box::new(backend),
+                    FsVersion::V2 => Box::new(backend.into_duplex()),
+                }
+            }
+            (FsHash::Sha3_256, None) => Box::new(Sha3Backend::new(init_label)),
+            (FsHash::Sha3_256, Some(key)) => Box::new(Sha3Backend::new_keyed(init_label, key)),
+            (FsHash::Blake3, None) => Box::new(Blake3Backend::new(init_label)),
+            (FsHash::Blake3, Some(key)) => Box::new(Blake3Backend::new_keyed(init_label, key)),
+            (FsHash::RescuePrime, key) => {
+                let params = rescue::default_params();
+                Box::new(match key {
+                    None => rescue::backend::RescueBackend::new(params, init_label),
+                    Some(key) => rescue::backend::RescueBackend::new_keyed(params, init_label, key),
+                })
+            }
+            #[cfg(feature = "transcript-keccak")]
+            (FsHash::Keccak256, None) => Box::new(Keccak256Backend::new(init_label)),
+            #[cfg(feature = "transcript-keccak")]
+            (FsHash::Keccak256, Some(key)) => Box::new(Keccak256Backend::new_keyed(init_label, key)),
+        };
+
+        Self {
+            backend,
+            trace: None,
+            version: FsVersion::V1,
+            stats: TranscriptStats::default(),
+            permutations_base: 0,
+            scopes: ScopeStack::default(),
+            grinding_bits: 0,
+        }
+    }
+}
+
+impl<F: PrimeField> Transcript<F> {
+    /// Transcript over any backend, e.g. `Sha3Backend::<Fr>::new(label)`
+    /// for a BLS12-381 scalar-field transcript.
+    pub fn from_backend(backend: impl HashBackend<F> + 'static) -> Self {
+        Self::with_custom_backend(Box::new(backend))
+    }
+
+    /// Transcript over a backend chosen at runtime, e.g. one a downstream
+    /// crate implements; see also [`registry`].
+    pub fn with_custom_backend(backend: Box<dyn HashBackend<F>>) -> Self {
+        emit_selected_backend_once();
+        Self {
+            backend,
+            trace: None,
+            version: FsVersion::V1,
+            stats: TranscriptStats::default(),
+            permutations_base: 0,
+            scopes: ScopeStack::default(),
+            grinding_bits: 0,
+        }
+    }
+
+    /// As [`Transcript::from_backend`], following `version`.
+    pub fn from_backend_version(backend: impl HashBackend<F> + 'static, version: FsVersion) -> Self {
+        let mut tr = Self::from_backend(backend);
+        tr.bind_version(version);
+        tr
+    }
+
+    fn bind_version(&mut self, version: FsVersion) {
+        self.version = version;
+        if version >= FsVersion::V2 {
+            self.absorb_bytes(ds::V2_INIT);
+            self.absorb_bytes(self.backend.name().as_bytes());
+            if let Some(digest) = self.backend.params_digest() {
+                self.absorb_bytes(ds::PARAMS);
+                self.absorb_bytes(&digest);
+            }
+        }
+    }
+
+    pub fn version(&self) -> FsVersion {
+        self.version
+    }
+
+    /// What a proof made with this transcript should declare.
+    pub fn binding(&self) -> FsBinding {
+        FsBinding {
+            version: self.version,
+            backend: self.backend.name().into(),
+            params_digest: self.backend.params_digest(),
+        }
+    }
+
+    /// Bits to pass to [`Transcript::grind`] and [`Transcript::check_grind`]:
+    /// the configured [`FsConfig::grinding_bits`], 0 outside
+    /// [`Transcript::from_config`].
+    pub fn grinding_bits(&self) -> u32 {
+        self.grinding_bits as u32
+    }
+
+    /// Record every operation from here on, for [`Transcript::take_trace`].
+    pub fn start_trace(&mut self) {
+        self.trace = Some(TranscriptTrace::default());
+    }
+
+    /// What was recorded since [`Transcript::start_trace`], which also
+    /// stops recording.
+    pub fn take_trace(&mut self) -> Option<TranscriptTrace<F>> {
+        self.trace.take()
+    }
+
+    /// Counts since construction, or since the fork for a child; a clone
+    /// carries the counts over.
+    pub fn stats(&self) -> TranscriptStats {
+        TranscriptStats {
+            permutations: self.backend.permutations() - self.permutations_base,
+            ..self.stats
+        }
+    }
+
+    fn record(&mut self, op: impl FnOnce() -> TraceOp<F>) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(op());
+        }
+    }
+
+    /// Absorb a byte message, framed as [`FsVersion`] prescribes. The
+    /// trace and [`Transcript::stats`] see the message, not the frame.
     #[inline]
     pub fn absorb_bytes(&mut self, bytes: &[u8]) {
-        self.backend.absorb_bytes(bytes)
+        self.stats.absorbed_bytes += bytes.len() as u64;
+        self.record(|| TraceOp::AbsorbBytes(bytes.to_vec()));
+        match self.version {
+            FsVersion::V1 => self.backend.absorb_bytes(bytes),
+            FsVersion::V2 => self.backend.absorb_bytes(&frame(bytes)),
+        }
     }
 
     #[inline]
     pub fn absorb_field(&mut self, x: F) {
+        self.stats.absorbed_fields += 1;
+        self.record(|| TraceOp::AbsorbField(x));
         self.backend.absorb_field(x)
     }
 
+    /// Same as [`Transcript::absorb_field`] on each element, for a Merkle
+    /// layer or trace column: the sponges fill the rate directly and the
+    /// byte backends hash one buffer.
+    pub fn absorb_field_slice(&mut self, xs: &[F]) {
+        self.stats.absorbed_fields += xs.len() as u64;
+        for &x in xs {
+            self.record(|| TraceOp::AbsorbField(x));
+        }
+        self.backend.absorb_field_slice(xs)
+    }
+
+    /// As two 32-bit halves: `x` may exceed the field modulus.
+    fn absorb_word(&mut self, x: u64) {
+        self.absorb_field(F::from(x & 0xffff_ffff));
+        self.absorb_field(F::from(x >> 32));
+    }
+
+    /// Absorb a slice of elements behind its length, so `[a, b]` then
+    /// `[c]` and `[a]` then `[b, c]` bind differently.
+    pub fn absorb_fields(&mut self, xs: &[F]) {
+        self.absorb_bytes(ds::ABSORB_FIELDS);
+        self.absorb_word(xs.len() as u64);
+        self.absorb_field_slice(xs);
+    }
+
+    /// Absorb any `u64`, including ones at or past the field modulus.
+    pub fn absorb_u64(&mut self, x: u64) {
+        self.absorb_bytes(ds::ABSORB_U64);
+        self.absorb_word(x);
+    }
+
+    /// Absorb a size or count; tagged apart from [`Transcript::absorb_u64`].
+    pub fn absorb_usize(&mut self, x: usize) {
+        self.absorb_bytes(ds::ABSORB_USIZE);
+        self.absorb_word(x as u64);
+    }
+
+    pub fn absorb_bool(&mut self, b: bool) {
+        self.absorb_bytes(ds::ABSORB_BOOL);
+        self.absorb_field(F::from(b as u64));
+    }
+
+    /// Open a named scope, e.g. `b"fri"` then `b"layer-3"`: until the
+    /// matching [`Transcript::end_scope`], challenge labels are drawn as
+    /// `fri/layer-3/label`. Absorbs are bound to the scope by the marker
+    /// absorbed here rather than by re-prefixing each one. Forks and
+    /// clones inherit the open scopes; saved states do not carry them.
+    pub fn begin_scope(&mut self, label: &[u8]) {
+        self.absorb_bytes(ds::SCOPE_BEGIN);
+        self.absorb_bytes(label);
+        self.scopes.starts.push(self.scopes.prefix.len());
+        self.scopes.prefix.extend_from_slice(label);
+        self.scopes.prefix.push(b'/');
+    }
+
+    /// Close the innermost scope, absorbing its end.
+    pub fn end_scope(&mut self) {
+        let start = self.scopes.starts.pop().expect("end_scope without an open scope");
+        let prefix = core::mem::take(&mut self.scopes.prefix);
+        self.absorb_bytes(ds::SCOPE_END);
+        self.absorb_bytes(&prefix[start..prefix.len() - 1]);
+        self.scopes.prefix = prefix;
+        self.scopes.prefix.truncate(start);
+    }
+
+    /// `label` under the open scopes, as the backend sees it.
+    fn scoped<'a>(&self, label: &'a [u8]) -> Cow<'a, [u8]> {
+        if self.scopes.prefix.is_empty() {
+            Cow::Borrowed(label)
+        } else {
+            Cow::Owned([&self.scopes.prefix[..], label].concat())
+        }
+    }
+
     #[inline]
     pub fn challenge(&mut self, label: &[u8]) -> F {
-        self.backend.challenge(label)
+        let label = &*self.scoped(label);
+        let value = self.backend.challenge(label);
+        self.stats.challenges += 1;
+        self.record(|| TraceOp::Challenge { label: label.to_vec(), value });
+        value
+    }
+
+    /// Checkpoint of the Fiat–Shamir state, e.g. between the trace
+    /// commitment and FRI, to resume from later or in another process.
+    pub fn save_state(&self) -> TranscriptState {
+        self.backend.save_state()
+    }
+
+    /// Continue from `state` as if everything absorbed and squeezed before
+    /// it had happened here. The transcript must use the backend that
+    /// saved it, and for Poseidon the same parameters; the current state is
+    /// discarded, including whatever the init label contributed.
+    pub fn restore_state(&mut self, state: &TranscriptState) -> Result<(), RestoreError> {
+        self.backend.restore_state(state)
+    }
+
+    /// Child transcript for a sub-protocol, e.g. one per phase proven in
+    /// parallel. The fork is absorbed here first, so the child is bound to
+    /// everything before it and two forks never coincide, even under the
+    /// same label; the child's challenges are separated from the parent's.
+    /// A traced parent gives a child tracing from an empty trace.
+    pub fn fork(&mut self, label: &[u8]) -> Transcript<F> {
+        self.absorb_bytes(ds::FORK);
+        self.absorb_bytes(label);
+        let mut child = Transcript {
+            backend: self.backend.boxed_clone(),
+            trace: self.trace.as_ref().map(|_| TranscriptTrace::default()),
+            version: self.version,
+            stats: TranscriptStats::default(),
+            permutations_base: self.backend.permutations(),
+            scopes: self.scopes.clone(),
+            grinding_bits: self.grinding_bits,
+        };
+        child.absorb_bytes(ds::FORK_CHILD);
+        child
+    }
+
+    /// `len` uniform bytes squeezed under `label`.
+    pub fn challenge_bytes(&mut self, label: &[u8], len: usize) -> Vec<u8> {
+        let mut out = alloc::vec![0u8; len];
+        self.squeeze_bytes(label, &mut out);
+        out
+    }
+
+    fn squeeze_bytes(&mut self, label: &[u8], out: &mut [u8]) {
+        let label = &*self.scoped(label);
+        self.backend.challenge_bytes(label, out);
+        self.stats.challenges += 1;
+        self.record(|| TraceOp::ChallengeBytes { label: label.to_vec(), value: out.to_vec() });
+    }
+
+    /// Challenge in another prime field, e.g. a curve scalar field for an
+    /// outer recursion layer, or Goldilocks from a transcript over a
+    /// larger field.
+    ///
+    /// Absorbs `Fout`'s modulus q, squeezes `⌈(log q + 128) / 8⌉` bytes
+    /// under `label` and reduces them mod q as a little-endian integer,
+    /// which keeps the result within 2^-128 of uniform.
+    pub fn challenge_in<Fout: PrimeField>(&mut self, label: &[u8]) -> Fout {
+        self.absorb_bytes(ds::CHALLENGE_FOREIGN);
+        // 32-bit limbs stay below the modulus of any transcript field.
+        for limb in Fout::MODULUS.to_bytes_le().chunks(4) {
+            self.absorb_field(F::from(le_u32(limb) as u64));
+        }
+        let mut wide = alloc::vec![0u8; (Fout::MODULUS_BIT_SIZE as usize + 128).div_ceil(8)];
+        self.squeeze_bytes(label, &mut wide);
+        reduce_le(&wide)
+    }
+
+    /// Challenge in an extension of another prime field, e.g. a quadratic
+    /// extension used by an outer layer: one [`Transcript::challenge_in`]
+    /// per coordinate.
+    pub fn challenge_in_ext<E: Field>(&mut self, label: &[u8]) -> E {
+        let coords: Vec<E::BasePrimeField> =
+            (0..E::extension_degree()).map(|_| self.challenge_in(label)).collect();
+        E::from_base_prime_field_elems(&coords).expect("one coordinate per degree")
+    }
+
+    /// Index uniform in `[0, n)`, for query positions. Draws 8 bytes under
+    /// `label` followed by an attempt counter and rejects values past the
+    /// largest multiple of `n`, so there is no modulo bias; a second
+    /// attempt happens with probability below `n / 2^64`.
+    pub fn challenge_index(&mut self, label: &[u8], n: u64) -> u64 {
+        assert!(n > 0, "index below zero");
+        self.absorb_bytes(ds::CHALLENGE_INDEX);
+        self.absorb_word(n);
+        // u64::MAX + 1 − ((u64::MAX + 1) mod n): draws at or past it repeat.
+        let zone = u64::MAX - (u64::MAX - n + 1) % n;
+        let mut attempt = Vec::with_capacity(label.len() + 4);
+        attempt.extend_from_slice(label);
+        attempt.extend_from_slice(&[0u8; 4]);
+        for k in 0u32.. {
+            attempt[label.len()..].copy_from_slice(&k.to_le_bytes());
+            let mut draw = [0u8; 8];
+            self.squeeze_bytes(&attempt, &mut draw);
+            let x = u64::from_le_bytes(draw);
+            if x <= zone {
+                return x % n;
+            }
+        }
+        unreachable!("2^32 rejected draws")
+    }
+
+    /// `n` independent challenges, e.g. one batching coefficient per
+    /// constraint. The count is absorbed first and challenge `i` is
+    /// squeezed under `label` followed by `i` as 8 little-endian bytes, so
+    /// no two draws share a label even on backends whose `challenge` does
+    /// not advance the state.
+    pub fn challenge_many(&mut self, label: &[u8], n: usize) -> Vec<F> {
+        self.absorb_bytes(ds::CHALLENGE_MANY);
+        self.absorb_field(F::from(n as u64));
+        let mut indexed = Vec::with_capacity(label.len() + 8);
+        indexed.extend_from_slice(label);
+        indexed.extend_from_slice(&[0u8; 8]);
+        (0..n as u64)
+            .map(|i| {
+                indexed[label.len()..].copy_from_slice(&i.to_le_bytes());
+                self.challenge(&indexed)
+            })
+            .collect()
+    }
+
+    /// `[1, α, .., α^(n-1)]` for a single challenge α squeezed under
+    /// `label` after absorbing `n`.
+    pub fn challenge_powers(&mut self, label: &[u8], n: usize) -> Vec<F> {
+        self.absorb_bytes(ds::CHALLENGE_POWERS);
+        self.absorb_field(F::from(n as u64));
+        let alpha = self.challenge(label);
+        core::iter::successors(Some(F::one()), |&p| Some(p * alpha))
+            .take(n)
+            .collect()
+    }
+
+    /// Absorb a cubic-extension element: `label`, then its coordinates.
+    pub fn absorb_ext3(&mut self, label: &[u8], x: [F; 3]) {
+        self.absorb_bytes(label);
+        for c in x {
+            self.absorb_field(c);
+        }
+    }
+
+    /// Proof of work: the smallest nonce for which the challenge squeezed
+    /// under `label` after it has `bits` trailing zero bits in its low
+    /// word, about `2^bits` squeezes. The nonce is then absorbed, so later
+    /// challenges depend on it. `bits` is at most 64.
+    pub fn grind(&mut self, label: &[u8], bits: u32) -> u64 {
+        assert!(bits <= 64, "grinding past 64 bits");
+        self.grind_prelude(label, bits);
+        let nonce = (0u64..).find(|&n| self.meets_grind(label, bits, n)).expect("nonce space exhausted");
+        self.absorb_word(nonce);
+        nonce
+    }
+
+    /// Verifier side of [`Transcript::grind`]. The transcript ends in the
+    /// same state as the prover's whether or not `nonce` does the work.
+    pub fn check_grind(&mut self, label: &[u8], bits: u32, nonce: u64) -> bool {
+        self.grind_prelude(label, bits);
+        let ok = bits <= 64 && self.meets_grind(label, bits, nonce);
+        self.absorb_word(nonce);
+        ok
+    }
+
+    fn grind_prelude(&mut self, label: &[u8], bits: u32) {
+        self.absorb_bytes(ds::GRIND);
+        self.absorb_bytes(label);
+        self.absorb_word(bits as u64);
+    }
+
+    /// Tries `nonce` on a copy of the backend, leaving `self` untouched.
+    fn meets_grind(&self, label: &[u8], bits: u32, nonce: u64) -> bool {
+        let mut probe = self.backend.boxed_clone();
+        probe.absorb_field(F::from(nonce & 0xffff_ffff));
+        probe.absorb_field(F::from(nonce >> 32));
+        probe.challenge(&self.scoped(label)).into_bigint().as_ref()[0].trailing_zeros() >= bits
+    }
+
+    /// Cubic-extension challenge from one domain-separated squeeze, as
+    /// its coordinates or any type built from them (e.g. the DEEP `Fp3`).
+    pub fn challenge_ext3<E: From<[F; 3]>>(&mut self, label: &[u8]) -> E {
+        let label = &*self.scoped(label);
+        let value = self.backend.challenge_ext3(label);
+        self.stats.challenges += 1;
+        self.record(|| TraceOp::ChallengeExt3 { label: label.to_vec(), value });
+        value.into()
+    }
+
+    /// Challenge with about 128 bits of entropy. Over Goldilocks,
+    /// [`Transcript::challenge`] keeps 64 bits of a byte backend's digest;
+    /// this keeps 128, and for the sponges takes two rate elements of one
+    /// permutation. For soundness bounds that need more than the base
+    /// field offers, e.g. an Fp2 challenge or two independent combiners.
+    pub fn challenge_wide(&mut self, label: &[u8]) -> [F; 2] {
+        let label = &*self.scoped(label);
+        let value = self.backend.challenge_wide(label);
+        self.stats.challenges += 1;
+        self.record(|| TraceOp::ChallengeWide { label: label.to_vec(), value });
+        value
     }
 }
 
 // ---------------- Internal ----------------
 
+/// `x mod q` for a field whose modulus exceeds 2^32; `From<u64>` alone
+/// panics on `x ≥ q`.
+/// Up to four bytes as a little-endian integer.
+fn le_u32(bytes: &[u8]) -> u32 {
+    let mut le = [0u8; 4];
+    le[..bytes.len()].copy_from_slice(bytes);
+    u32::from_le_bytes(le)
+}
+
+/// `bytes` as a little-endian integer, reduced mod `Fout`'s modulus one
+/// 32-bit limb at a time, so it holds for fields of any size.
+fn reduce_le<Fout: PrimeField>(bytes: &[u8]) -> Fout {
+    let base = Fout::from(1u64 << 32);
+    bytes.chunks(4).rev().fold(Fout::zero(), |acc, limb| acc * base + Fout::from(le_u32(limb) as u64))
+}
+
+#[cfg(feature = "std")]
 static PRINT_SELECTED_FS_BACKEND: Once = Once::new();
 
+#[cfg(feature = "std")]
 fn emit_selected_backend_once() {
     PRINT_SELECTED_FS_BACKEND.call_once(|| {});
-}
\ No newline at end of file
+}
+
+#[cfg(not(feature = "std"))]
+fn emit_selected_backend_once() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_ff::Field;
+
+    fn transcript() -> Transcript {
+        let mut tr = Transcript::new(b"TEST/foreign", default_params());
+        tr.absorb_field(F::from(42u64));
+        tr
+    }
+
+    #[test]
+    fn challenge_in_is_deterministic_and_bound() {
+        let a: Fr = transcript().challenge_in(b"c");
+        let b: Fr = transcript().challenge_in(b"c");
+        assert_eq!(a, b);
+
+        let mut other = transcript();
+        other.absorb_field(F::from(1u64));
+        assert_ne!(a, other.challenge_in::<Fr>(b"c"));
+
+        // Different foreign fields separate, and native challenges continue
+        // from the advanced state.
+        let mut tr = transcript();
+        let x: Fr = tr.challenge_in(b"c");
+        let g: F = tr.challenge_in(b"c");
+        assert_eq!(x, a);
+        assert_ne!(g, transcript().challenge_in::<F>(b"c"));
+    }
+
+    #[test]
+    fn foreign_challenges_reduce_wide_squeezes() {
+        // The byte path serves any pair of fields: Goldilocks out of a
+        // BLS12-381 transcript, BLS12-381 out of a Goldilocks one.
+        let mut fr = Transcript::from_backend(Sha3Backend::<Fr>::new(b"TEST/foreign"));
+        let start = fr.clone();
+        let g: F = fr.challenge_in(b"c");
+        assert_eq!(start.clone().challenge_in::<F>(b"c"), g);
+        assert_ne!(fr.challenge_in::<F>(b"c"), g);
+
+        let modulus = Fr::MODULUS.to_bytes_le();
+        assert_eq!(reduce_le::<Fr>(&modulus), Fr::zero());
+        let mut past = modulus.clone();
+        past[0] += 1;
+        past.extend([0; 16]);
+        assert_eq!(reduce_le::<Fr>(&past), Fr::one());
+        // 2^64 − 1 ≡ 2^32 − 2 mod p.
+        assert_eq!(reduce_le::<F>(&u64::MAX.to_le_bytes()), F::from((1u64 << 32) - 2));
+
+        // Extensions draw each coordinate separately.
+        let x: ark_bls12_381::Fq2 = transcript().challenge_in_ext(b"c");
+        assert_eq!(x, transcript().challenge_in_ext(b"c"));
+        assert_ne!(x.c0, x.c1);
+        let mut tr = transcript();
+        assert_eq!(x.c0, tr.challenge_in(b"c"));
+        assert_eq!(x.c1, tr.challenge_in(b"c"));
+    }
+
+    #[test]
+    fn ext3_challenges_are_one_separated_squeeze() {
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let start = || {
+                let mut tr = Transcript::from_config(&FsConfig::new(hash), b"TEST/ext3");
+                tr.absorb_field(F::from(42u64));
+                tr
+            };
+            let mut tr = start();
+            let z: [F; 3] = tr.challenge_ext3(b"z");
+            assert_eq!(z, start().challenge_ext3::<[F; 3]>(b"z"));
+            assert!(z[0] != z[1] && z[1] != z[2], "{hash:?}");
+            // No coordinate is a base-field challenge under the same label.
+            assert!(!z.contains(&start().challenge(b"z")), "{hash:?}");
+            assert_ne!(start().challenge_ext3::<[F; 3]>(b"w"), z);
+
+            let mut by_hand = start();
+            by_hand.challenge_ext3::<[F; 3]>(b"z");
+            tr.absorb_ext3(b"z", z);
+            by_hand.absorb_bytes(b"z");
+            z.iter().for_each(|&c| by_hand.absorb_field(c));
+            assert_eq!(tr.challenge(b"next"), by_hand.challenge(b"next"));
+        }
+    }
+
+    #[test]
+    fn vector_challenges_are_separated() {
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let start = || Transcript::from_config(&FsConfig::new(hash), b"TEST/many");
+            let many = start().challenge_many(b"alpha", 4);
+            assert_eq!(many.len(), 4);
+            for (i, a) in many.iter().enumerate() {
+                assert!(many[..i].iter().all(|b| b != a), "{hash:?}");
+            }
+            // The count is bound: a shorter draw is not a prefix.
+            assert_ne!(start().challenge_many(b"alpha", 3)[0], many[0]);
+            assert!(start().challenge_many(b"alpha", 0).is_empty());
+
+            let powers = start().challenge_powers(b"alpha", 4);
+            let alpha = powers[1];
+            assert_eq!(powers, [F::from(1u64), alpha, alpha * alpha, alpha * alpha * alpha]);
+            assert!(!many.contains(&alpha));
+        }
+    }
+
+    #[test]
+    fn byte_and_index_challenges() {
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let start = || Transcript::from_config(&FsConfig::new(hash), b"TEST/bytes");
+            let long = start().challenge_bytes(b"b", 100);
+            assert_eq!(long.len(), 100);
+            assert_eq!(long, start().challenge_bytes(b"b", 100));
+            // Length is bound, so a shorter draw is not a prefix.
+            assert_ne!(start().challenge_bytes(b"b", 8)[..], long[..8], "{hash:?}");
+            assert_ne!(start().challenge_bytes(b"c", 100), long);
+
+            let mut tr = start();
+            let n = (1u64 << 40) + 3;
+            let idx: Vec<u64> = (0..64).map(|_| tr.challenge_index(b"q", n)).collect();
+            assert!(idx.iter().all(|&i| i < n));
+            assert!(idx.iter().any(|&i| i >= 1 << 32), "{hash:?}");
+            assert_eq!(start().challenge_index(b"q", 1), 0);
+            assert_eq!(start().challenge_index(b"q", u64::MAX), start().challenge_index(b"q", u64::MAX));
+        }
+    }
+
+    #[test]
+    fn forks_are_bound_and_separated() {
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let start = || {
+                let mut tr = Transcript::from_config(&FsConfig::new(hash), b"TEST/fork");
+                tr.absorb_bytes(b"trace root");
+                tr
+            };
+            let mut parent = start();
+            let mut fri = parent.fork(b"fri");
+            let mut again = parent.fork(b"fri");
+            let a = fri.challenge(b"c");
+            assert_ne!(a, again.challenge(b"c"), "{hash:?}");
+            assert_ne!(a, parent.challenge(b"c"));
+            assert_ne!(a, start().fork(b"ali").challenge(b"c"));
+            assert_eq!(a, start().fork(b"fri").challenge(b"c"));
+
+            // The parent is bound to the fork having happened.
+            let mut forked = start();
+            forked.fork(b"fri");
+            assert_ne!(forked.challenge(b"c"), start().challenge(b"c"));
+        }
+    }
+
+    #[test]
+    fn rescue_sbox_inverts_and_permutation_mixes() {
+        let x = F::from(0x1234_5678_9abc_def0u64);
+        assert_eq!(x.pow([rescue::ALPHA]).pow([rescue::INV_ALPHA]), x);
+
+        let params = rescue::default_params();
+        let mut a = [F::zero(); rescue::T];
+        let mut b = a;
+        b[rescue::T - 1] = F::from(1u64);
+        rescue::permute(&mut a, &params);
+        rescue::permute(&mut b, &params);
+        assert!(a.iter().zip(&b).all(|(x, y)| x != y));
+    }
+
+    #[test]
+    #[cfg(feature = "transcript-keccak")]
+    fn keccak_challenges_follow_the_documented_layout() {
+        use tiny_keccak::{Hasher, Keccak};
+        let keccak = |s: &[u8]| {
+            let mut h = Keccak::v256();
+            h.update(s);
+            let mut out = [0u8; 32];
+            h.finalize(&mut out);
+            out
+        };
+
+        let mut tr = Transcript::from_config(&FsConfig::new(FsHash::Keccak256), b"TEST/evm");
+        tr.absorb_bytes(b"root");
+        tr.absorb_field(F::from(5u64));
+        let mut s = [ds::TRANSCRIPT_INIT, b"TEST/evm", ds::ABSORB_BYTES, b"root", ds::ABSORB_BYTES].concat();
+        s.extend_from_slice(&5u64.to_le_bytes());
+
+        let h = keccak(&[&s[..], ds::CHALLENGE, b"alpha"].concat());
+        let word = u64::from_le_bytes(h[..8].try_into().unwrap());
+        assert!(word < F::MODULUS.0[0]);
+        assert_eq!(tr.challenge(b"alpha"), F::from(word));
+        // keccak256(""), not SHA3-256.
+        assert_eq!(keccak(b"")[..4], [0xc5, 0xd2, 0x46, 0x01]);
+
+        let block = keccak(&[&s[..], ds::CHALLENGE_BYTES, b"b", &40u64.to_le_bytes(), &1u64.to_le_bytes()].concat());
+        assert_eq!(tr.challenge_bytes(b"b", 40)[32..], block[..8]);
+    }
+
+    #[test]
+    fn wide_challenges_are_separated() {
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let start = || Transcript::from_config(&FsConfig::new(hash), b"TEST/wide");
+            let [a, b] = start().challenge_wide(b"w");
+            assert_ne!(a, b, "{hash:?}");
+            assert_eq!(start().challenge_wide(b"w"), [a, b]);
+            assert_ne!(start().challenge_wide(b"v"), [a, b]);
+            assert_ne!(start().challenge(b"w"), a);
+            let ext: [F; 3] = start().challenge_ext3(b"w");
+            assert_ne!(ext[..2], [a, b]);
+        }
+    }
+
+    #[test]
+    fn typed_absorbs_are_framed() {
+        let [a, b, c] = [1u64, 2, 3].map(F::from);
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let run = |f: &dyn Fn(&mut Transcript)| {
+                let mut tr = Transcript::from_config(&FsConfig::new(hash), b"TEST/typed");
+                f(&mut tr);
+                tr.challenge(b"c")
+            };
+            let split = run(&|tr| {
+                tr.absorb_fields(&[a, b]);
+                tr.absorb_fields(&[c]);
+            });
+            let moved = run(&|tr| {
+                tr.absorb_fields(&[a]);
+                tr.absorb_fields(&[b, c]);
+            });
+            assert_ne!(split, moved, "{hash:?}");
+            assert_ne!(run(&|tr| tr.absorb_fields(&[])), run(&|_| {}));
+
+            let ints = [
+                run(&|tr| tr.absorb_u64(1)),
+                run(&|tr| tr.absorb_usize(1)),
+                run(&|tr| tr.absorb_bool(true)),
+                run(&|tr| tr.absorb_fields(&[F::one()])),
+            ];
+            for (i, x) in ints.iter().enumerate() {
+                assert!(ints[i + 1..].iter().all(|y| y != x), "{hash:?}");
+            }
+            // Past the modulus, and distinct from its reduction.
+            let reduced = u64::MAX - F::MODULUS.0[0];
+            assert_ne!(run(&|tr| tr.absorb_u64(u64::MAX)), run(&|tr| tr.absorb_u64(reduced)));
+            assert_ne!(run(&|tr| tr.absorb_bool(false)), run(&|tr| tr.absorb_bool(true)));
+        }
+    }
+
+    #[test]
+    fn session_keys_separate_transcripts() {
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let keyed = |key: &[u8; 32]| {
+                Transcript::with_backend_keyed(hash, b"TEST/keyed", key, default_params()).challenge(b"c")
+            };
+            let c = keyed(&[1; 32]);
+            assert_eq!(keyed(&[1; 32]), c, "{hash:?}");
+            assert_ne!(keyed(&[2; 32]), c, "{hash:?}");
+            let mut last_bit = [1; 32];
+            last_bit[31] ^= 0x80;
+            assert_ne!(keyed(&last_bit), c, "{hash:?}");
+            let unkeyed = Transcript::from_config(&FsConfig::new(hash), b"TEST/keyed").challenge(b"c");
+            assert_ne!(keyed(&[0; 32]), unkeyed, "{hash:?}");
+        }
+        let mut default = Transcript::new_keyed(b"TEST/keyed", &[1; 32], default_params());
+        let mut poseidon =
+            Transcript::with_backend_keyed(FsHash::Poseidon, b"TEST/keyed", &[1; 32], default_params());
+        assert_eq!(default.challenge(b"c"), poseidon.challenge(b"c"));
+    }
+
+    #[test]
+    fn grinding_nonces_verify_and_bind() {
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let start = || Transcript::from_config(&FsConfig::new(hash), b"TEST/grind");
+            let mut prover = start();
+            let nonce = prover.grind(b"pow", 6);
+            let after = prover.challenge(b"c");
+
+            let mut verifier = start();
+            assert!(verifier.check_grind(b"pow", 6, nonce), "{hash:?}");
+            assert_eq!(verifier.challenge(b"c"), after);
+            // The nonce is the smallest that works, and it is bound.
+            for smaller in nonce.saturating_sub(4)..nonce {
+                let mut tr = start();
+                assert!(!tr.check_grind(b"pow", 6, smaller));
+                assert_ne!(tr.challenge(b"c"), after);
+            }
+            assert!(!start().check_grind(b"pow", 65, nonce));
+            assert!(start().check_grind(b"pow", 0, nonce));
+        }
+    }
+
+    #[test]
+    fn clones_branch_independently() {
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let mut tr = Transcript::from_config(&FsConfig::new(hash), b"TEST/clone");
+            tr.start_trace();
+            tr.absorb_bytes(b"root");
+            let snapshot = tr.clone();
+
+            let mut branch = tr.clone();
+            branch.absorb_bytes(b"speculative");
+            let speculative = branch.challenge(b"c");
+            let c = tr.challenge(b"c");
+            assert_ne!(speculative, c, "{hash:?}");
+
+            // Rolling back is restoring the copy.
+            let mut rolled_back = snapshot;
+            assert_eq!(rolled_back.challenge(b"c"), c, "{hash:?}");
+            assert_eq!(rolled_back.take_trace(), tr.take_trace());
+        }
+    }
+
+    #[test]
+    fn rng_streams_are_bound_and_chunking_free() {
+        use rand_core::RngCore;
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let mut tr = Transcript::from_config(&FsConfig::new(hash), b"TEST/rng");
+            tr.absorb_bytes(b"root");
+            let mut whole = [0u8; 150];
+            tr.rng(b"r").fill_bytes(&mut whole);
+            let mut pieces = [0u8; 150];
+            let mut rng = tr.rng(b"r");
+            for chunk in pieces.chunks_mut(37) {
+                rng.fill_bytes(chunk);
+            }
+            assert_eq!(whole, pieces, "{hash:?}");
+            // Blocks differ from one another and from other labels.
+            assert_ne!(whole[..64], whole[64..128]);
+            assert_ne!(tr.rng(b"s").next_u64(), tr.rng(b"r").next_u64());
+
+            // Drawing leaves the transcript as it was.
+            let before = tr.clone().challenge(b"c");
+            tr.rng(b"r").next_u64();
+            assert_eq!(tr.challenge(b"c"), before);
+        }
+    }
+
+    #[test]
+    fn versions_are_declared_and_bound() {
+        for version in FsVersion::ALL {
+            assert_eq!(FsVersion::from_id(version.id()), Some(version));
+        }
+        assert_eq!(FsVersion::from_id(0), None);
+
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let start = |version| Transcript::from_config(&FsConfig { version, ..FsConfig::new(hash) }, b"TEST/version");
+            let v1 = Transcript::from_config(&FsConfig::new(hash), b"TEST/version").challenge(b"c");
+            // V1 is the encoding existing proofs use.
+            assert_eq!(start(FsVersion::V1).challenge(b"c"), v1, "{hash:?}");
+            let mut tr = start(FsVersion::V2);
+            assert_eq!(tr.fork(b"f").version(), FsVersion::V2);
+            assert_eq!(tr.clone().version(), FsVersion::V2);
+            assert_ne!(start(FsVersion::V2).challenge(b"c"), v1, "{hash:?}");
+        }
+
+        let digests = [FsHash::Poseidon, FsHash::RescuePrime]
+            .map(|hash| Transcript::from_config(&FsConfig::new(hash), b"").backend.params_digest());
+        assert!(digests[0].is_some() && digests[1].is_some() && digests[0] != digests[1]);
+        assert_eq!(Sha3Backend::<F>::new(b"").params_digest(), None);
+    }
+
+    #[test]
+    fn bindings_name_backend_params_and_version() {
+        let binding = |cfg: &FsConfig| Transcript::from_config(cfg, b"TEST/binding").binding();
+        let poseidon = binding(&FsConfig::new(FsHash::Poseidon));
+        assert_eq!((poseidon.backend.as_str(), poseidon.version), ("poseidon", FsVersion::V1));
+        assert!(poseidon.params_digest.is_some());
+        assert_eq!(binding(&FsConfig::new(FsHash::Sha3_256)).params_digest, None);
+
+        let other = poseidon::params::generate_params_t17_x5(b"TEST/other-constants");
+        let cfgs = [
+            FsConfig::new(FsHash::Poseidon),
+            FsConfig::builder().poseidon(other).build(),
+            FsConfig::builder().version(FsVersion::V2).build(),
+            FsConfig::new(FsHash::Sha3_256),
+            FsConfig::new(FsHash::Blake3),
+            FsConfig::new(FsHash::RescuePrime),
+        ];
+        let bindings: Vec<_> = cfgs.iter().map(binding).collect();
+        for (i, a) in bindings.iter().enumerate() {
+            assert!(bindings[..i].iter().all(|b| b != a), "{a}");
+        }
+
+        // Only a config change shows: absorbs, squeezes and forks keep it.
+        let mut tr = Transcript::from_config(&cfgs[2], b"TEST/binding");
+        tr.absorb_bytes(b"x");
+        tr.challenge(b"c");
+        assert_eq!(tr.fork(b"f").binding(), bindings[2]);
+        assert_eq!(tr.binding(), bindings[2]);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn configs_replace_positional_constructors() {
+        let cfg = FsConfig::builder().hash(FsHash::Blake3).grinding_bits(4).version(FsVersion::V2).build();
+        assert_eq!((cfg.hash, cfg.grinding_bits, cfg.version), (FsHash::Blake3, 4, FsVersion::V2));
+        assert!(cfg.poseidon.is_none());
+
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            for version in FsVersion::ALL {
+                let old = Transcript::with_backend_version(hash, b"TEST/cfg", default_params(), version);
+                let new = Transcript::from_config(&FsConfig { version, ..FsConfig::new(hash) }, b"TEST/cfg");
+                assert_eq!(old.clone().challenge(b"c"), new.clone().challenge(b"c"), "{hash:?}");
+            }
+            let old = Transcript::with_backend(hash, b"TEST/cfg", default_params()).challenge(b"c");
+            assert_eq!(Transcript::from_config(&FsConfig::new(hash), b"TEST/cfg").challenge(b"c"), old);
+        }
+
+        // Only Poseidon reads the params.
+        let other = poseidon::params::generate_params_t17_x5(b"TEST/other-constants");
+        let with = |hash| FsConfig::builder().hash(hash).poseidon(other.clone()).build();
+        let c = |cfg: &FsConfig| Transcript::from_config(cfg, b"TEST/cfg").challenge(b"c");
+        assert_ne!(c(&with(FsHash::Poseidon)), c(&FsConfig::new(FsHash::Poseidon)));
+        assert_eq!(c(&with(FsHash::Sha3_256)), c(&FsConfig::new(FsHash::Sha3_256)));
+
+        let mut tr = Transcript::from_config(&cfg, b"TEST/cfg");
+        assert_eq!(tr.grinding_bits(), 4);
+        assert_eq!(tr.fork(b"f").grinding_bits(), 4);
+        let bits = tr.grinding_bits();
+        let nonce = tr.clone().grind(b"pow", bits);
+        assert!(tr.check_grind(b"pow", bits, nonce));
+        assert_eq!(Transcript::new(b"TEST/cfg", default_params()).grinding_bits(), 0);
+    }
+
+    #[test]
+    fn stats_count_absorbs_squeezes_and_permutations() {
+        let mut tr = Transcript::new(b"TEST/stats", default_params());
+        let start = tr.stats();
+        tr.absorb_bytes(b"root");
+        tr.absorb_field(F::one());
+        tr.challenge_many(b"c", 3);
+        let stats = tr.stats();
+        // challenge_many adds its tag and count.
+        assert_eq!(stats.absorbed_bytes - start.absorbed_bytes, 4 + ds::CHALLENGE_MANY.len() as u64);
+        assert_eq!(stats.absorbed_fields - start.absorbed_fields, 2);
+        assert_eq!(stats.challenges - start.challenges, 3);
+        assert!(stats.permutations - start.permutations >= 3);
+        assert_eq!(tr.clone().stats(), stats);
+
+        let child = tr.fork(b"f");
+        assert_eq!(child.stats().challenges, 0);
+        assert_eq!(child.stats().permutations, 0);
+
+        let mut sha3 = Transcript::from_config(&FsConfig::new(FsHash::Sha3_256), b"TEST/stats");
+        sha3.challenge(b"c");
+        assert_eq!((sha3.stats().challenges, sha3.stats().permutations), (1, 0));
+    }
+
+    #[test]
+    fn v2_frames_byte_messages() {
+        type Messages<'a> = &'a [&'a [u8]];
+        // The byte backends absorb ABSORB_BYTES before each message; the
+        // sponges its first word, "FSv1-ABS".
+        let byte_forgery = [b"a", ds::ABSORB_BYTES, b"bc"].concat();
+        let sponge_forgery = [b"12345678", &ds::ABSORB_BYTES[..8], b"xyz"].concat();
+        let splits: [(Messages, Messages); 4] = [
+            (&[b"a", b"bc"], &[b"ab", b"c"]),
+            (&[b"", b"ab"], &[b"ab"]),
+            (&[b"a", b"bc"], &[&byte_forgery]),
+            (&[b"12345678", b"xyz"], &[&sponge_forgery]),
+        ];
+        let run = |hash, version, messages: Messages| {
+            let mut tr = Transcript::from_config(&FsConfig { version, ..FsConfig::new(hash) }, b"TEST/frame");
+            for m in messages {
+                tr.absorb_bytes(m);
+            }
+            tr.challenge(b"c")
+        };
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            for (a, b) in splits {
+                assert_ne!(run(hash, FsVersion::V2, a), run(hash, FsVersion::V2, b), "{hash:?}");
+            }
+        }
+        // The V1 collisions V2 closes.
+        let (a, b) = splits[2];
+        assert_eq!(run(FsHash::Sha3_256, FsVersion::V1, a), run(FsHash::Sha3_256, FsVersion::V1, b));
+        let (a, b) = splits[3];
+        assert_eq!(run(FsHash::Poseidon, FsVersion::V1, a), run(FsHash::Poseidon, FsVersion::V1, b));
+        assert_eq!(frame(b"abc"), [&3u64.to_le_bytes()[..], b"abc", &[0; 5]].concat());
+    }
+
+    #[test]
+    fn scopes_prefix_challenge_labels() {
+        let labels = |tr: &mut Transcript| -> Vec<Vec<u8>> {
+            let ops = tr.take_trace().unwrap().ops;
+            ops.into_iter()
+                .filter_map(|op| match op {
+                    TraceOp::Challenge { label, .. } | TraceOp::ChallengeBytes { label, .. } => Some(label),
+                    _ => None,
+                })
+                .collect()
+        };
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let mut tr = Transcript::from_config(&FsConfig::new(hash), b"TEST/scope");
+            tr.start_trace();
+            tr.begin_scope(b"fri");
+            tr.begin_scope(b"layer-1");
+            tr.challenge(b"beta");
+            tr.end_scope();
+            tr.challenge_bytes(b"seed", 4);
+            tr.end_scope();
+            tr.challenge(b"done");
+            assert_eq!(labels(&mut tr), [&b"fri/layer-1/beta"[..], b"fri/seed", b"done"]);
+
+            // The same work in different scopes draws different challenges.
+            let scoped = |name: &[u8]| {
+                let mut tr = Transcript::from_config(&FsConfig::new(hash), b"TEST/scope");
+                tr.begin_scope(name);
+                tr.absorb_field(F::one());
+                tr.challenge(b"c")
+            };
+            assert_ne!(scoped(b"a"), scoped(b"b"), "{hash:?}");
+        }
+    }
+
+    #[test]
+    fn poseidon_streams_are_pinned_per_version() {
+        let stream = |version| {
+            let mut tr =
+                Transcript::from_config(&FsConfig { version, ..FsConfig::new(FsHash::Poseidon) }, b"TEST/duplex");
+            tr.absorb_field(F::from(1u64));
+            let a = tr.challenge(b"a");
+            tr.absorb_field(F::zero());
+            let b = tr.challenge(b"b");
+            let [c, _] = tr.challenge_wide(b"c");
+            let d = tr.challenge_bytes(b"d", 8);
+            let d = u64::from_le_bytes(d.try_into().unwrap());
+            [a, b, c].map(|x| x.into_bigint().0[0]).into_iter().chain([d]).collect::<Vec<_>>()
+        };
+        // The original sponge, as every V1 proof has used it.
+        assert_eq!(
+            stream(FsVersion::V1),
+            [0x185e8038a259f404, 0xfa43eed0b03a469b, 0x904335a65822f543, 0xbe65d114ecdd88b9]
+        );
+        assert_eq!(
+            stream(FsVersion::V2),
+            [0x8bbb9aff4898af30, 0xb9daa69b096fcc09, 0x7d802ab37ea5763c, 0x43274f848f635de1]
+        );
+    }
+
+    #[test]
+    fn slice_absorption_matches_element_absorption() {
+        let xs: Vec<F> = (0..37u64).map(|i| F::from(i * 0x9e37_79b9 + 1)).collect();
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let start = || {
+                let mut tr = Transcript::from_config(&FsConfig::new(hash), b"TEST/slice");
+                // Start mid-rate, so the slice straddles permutations.
+                tr.absorb_field(F::one());
+                tr.start_trace();
+                tr
+            };
+            let mut sliced = start();
+            sliced.absorb_field_slice(&xs);
+            let mut looped = start();
+            for &x in &xs {
+                looped.absorb_field(x);
+            }
+            assert_eq!(sliced.stats(), looped.stats(), "{hash:?}");
+            assert_eq!(sliced.take_trace(), looped.take_trace());
+            assert_eq!(sliced.challenge(b"c"), looped.challenge(b"c"), "{hash:?}");
+        }
+    }
+
+    #[test]
+    fn traces_encode_and_replay() {
+        use crate::trace::{DecodeError, ReplayError};
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let start = |label: &[u8]| Transcript::from_config(&FsConfig::new(hash), label);
+            let mut tr = start(b"TEST/golden");
+            tr.start_trace();
+            tr.absorb_bytes(b"root");
+            tr.absorb_field(-F::one());
+            tr.challenge(b"alpha");
+            let _: [F; 3] = tr.challenge_ext3(b"z");
+            tr.challenge_wide(b"w");
+            tr.challenge_index(b"q", 1000);
+            let trace = tr.take_trace().unwrap();
+
+            let golden = trace.encode();
+            assert_eq!(TranscriptTrace::decode(&golden), Ok(trace.clone()));
+            assert_eq!(trace.replay(&mut start(b"TEST/golden")), Ok(()), "{hash:?}");
+            // A verifier that started elsewhere fails at the first challenge.
+            let Err(ReplayError { index, .. }) = trace.replay(&mut start(b"TEST/other")) else {
+                panic!("{hash:?}: replay under another label succeeded");
+            };
+            assert_eq!(index, 2);
+
+            let cut = &golden[..golden.len() - 1];
+            assert_eq!(TranscriptTrace::<F>::decode(cut), Err(DecodeError::Truncated));
+        }
+        assert_eq!(TranscriptTrace::<F>::decode(b"FSTR\x02"), Err(DecodeError::Header));
+        assert_eq!(TranscriptTrace::<F>::decode(b"FSTR\x01\x09"), Err(DecodeError::UnknownOp(9)));
+        let modulus = F::MODULUS.0[0].to_le_bytes();
+        let bad = [&b"FSTR\x01\x01"[..], &modulus].concat();
+        assert_eq!(TranscriptTrace::<F>::decode(&bad), Err(DecodeError::NonCanonical));
+    }
+
+    #[test]
+    fn byte_backends_serve_other_fields() {
+        // The generic backend over Goldilocks is the `FsHash` one.
+        let mut generic = Transcript::from_backend(Sha3Backend::new(b"TEST/generic"));
+        let mut fixed = Transcript::from_config(&FsConfig::new(FsHash::Sha3_256), b"TEST/generic");
+        generic.absorb_field(F::from(9u64));
+        fixed.absorb_field(F::from(9u64));
+        assert_eq!(generic.challenge(b"c"), fixed.challenge(b"c"));
+
+        let start = || {
+            let mut tr = Transcript::<Fr>::from_backend(Blake3Backend::new(b"TEST/bls"));
+            tr.absorb_field(-Fr::from(3u64));
+            tr
+        };
+        let mut tr = start();
+        let c = tr.challenge(b"c");
+        assert_eq!(c, start().challenge(b"c"));
+        // Full-width challenges: well past any 64-bit value.
+        assert!(c.into_bigint().num_bits() > 128);
+        let ext: [Fr; 3] = tr.challenge_ext3(b"e");
+        assert!(ext[0] != ext[1] && ext[1] != ext[2]);
+        assert_ne!(tr.fork(b"sub").challenge(b"c"), c);
+
+        let saved = tr.save_state();
+        let mut resumed = Transcript::<Fr>::from_backend(Blake3Backend::new(b"TEST/other"));
+        resumed.restore_state(&saved).unwrap();
+        assert_eq!(resumed.challenge_wide(b"w"), tr.challenge_wide(b"w"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn registered_backends_are_selected_by_name() {
+        fn sha3(label: &[u8]) -> Box<dyn HashBackend> {
+            Box::new(Sha3Backend::<F>::new(label))
+        }
+        register_backend("test-sha3", sha3).unwrap();
+        assert_eq!(register_backend("test-sha3", sha3), Err(registry::NameTaken("test-sha3")));
+        assert_eq!(register_backend("blake3", sha3), Err(registry::NameTaken("blake3")));
+
+        let mut custom = Transcript::with_registered("test-sha3", b"TEST/registry").unwrap();
+        let mut builtin = Transcript::from_config(&FsConfig::new(FsHash::Sha3_256), b"TEST/registry");
+        assert_eq!(custom.challenge(b"c"), builtin.challenge(b"c"));
+        assert!(Transcript::with_registered("test-missing", b"TEST/registry").is_none());
+    }
+
+    #[test]
+    fn traces_locate_the_first_divergence() {
+        let run = |claimed: u64| {
+            let mut tr = transcript();
+            tr.start_trace();
+            tr.absorb_bytes(b"root");
+            let alpha = tr.challenge(b"alpha");
+            tr.absorb_field(F::from(claimed));
+            tr.challenge_index(b"q", 1 << 20);
+            (alpha, tr.take_trace().unwrap())
+        };
+        let (alpha, prover) = run(7);
+        assert_eq!(prover.ops[1], TraceOp::Challenge { label: b"alpha".to_vec(), value: alpha });
+        assert_eq!(prover.first_divergence(&run(7).1), None);
+
+        let verifier = run(8).1;
+        let d = prover.first_divergence(&verifier).unwrap();
+        assert_eq!(d.index, 2);
+        assert_eq!(d.ours, Some(&TraceOp::AbsorbField(F::from(7u64))));
+        assert_eq!(d.theirs, Some(&TraceOp::AbsorbField(F::from(8u64))));
+
+        let mut short = prover.clone();
+        short.ops.truncate(3);
+        let d = prover.first_divergence(&short).unwrap();
+        assert_eq!((d.index, d.theirs), (3, None));
+
+        let mut untraced = transcript();
+        untraced.challenge(b"alpha");
+        assert!(untraced.take_trace().is_none());
+    }
+
+    #[test]
+    fn byte_challenges_reject_words_past_the_modulus() {
+        let digest = |parts: &[&[u8]]| {
+            // A first word of p itself, then 5, then a block of 9s.
+            let mut out = [9u8; 32];
+            if parts.len() == 2 {
+                out[..8].copy_from_slice(&F::MODULUS.0[0].to_le_bytes());
+                out[8..16].copy_from_slice(&5u64.to_le_bytes());
+                out[16..].fill(0xff);
+            }
+            out
+        };
+        let [a, b] = digest_words::<F, 2>(digest, b"tag", b"label");
+        assert_eq!(a, F::from(5u64));
+        assert_eq!(b, F::from(u64::from_le_bytes([9; 8])));
+
+        // A 255-bit field clears the top bit and keeps full-width values.
+        let [x] = digest_words::<Fr, 1>(|_| [0x11; 32], b"tag", b"label");
+        assert_eq!(x.into_bigint().to_bytes_le(), [0x11; 32]);
+        let [y] = digest_words::<Fr, 1>(|p| [if p.len() == 2 { 0xff } else { 0x22 }; 32], b"t", b"l");
+        assert_eq!(y.into_bigint().to_bytes_le(), [0x22; 32]);
+    }
+
+    #[test]
+    fn restored_state_resumes_every_backend() {
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3, FsHash::RescuePrime] {
+            let mut tr = Transcript::from_config(&FsConfig::new(hash), b"TEST/state");
+            tr.absorb_bytes(b"trace root");
+            tr.challenge(b"alpha");
+            let saved = tr.save_state();
+            tr.absorb_field(F::from(7u64));
+            let expected = tr.challenge(b"z");
+
+            // A transcript started under another label picks up where the
+            // first one was saved.
+            let mut resumed = Transcript::from_config(&FsConfig::new(hash), b"TEST/other");
+            resumed.restore_state(&saved).unwrap();
+            resumed.absorb_field(F::from(7u64));
+            assert_eq!(resumed.challenge(b"z"), expected, "{hash:?}");
+            assert_eq!(resumed.save_state(), tr.save_state());
+        }
+
+        let sha3 = Transcript::from_config(&FsConfig::new(FsHash::Sha3_256), b"TEST/state");
+        assert_eq!(
+            transcript().restore_state(&sha3.save_state()),
+            Err(RestoreError::Backend { backend: "poseidon", state: "sha3-256" })
+        );
+        let overrun = TranscriptState::Poseidon { state: [F::zero(); ::poseidon::T], pos: ::poseidon::RATE + 1 };
+        assert_eq!(
+            transcript().restore_state(&overrun),
+            Err(RestoreError::Position { pos: ::poseidon::RATE + 1 })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde1")]
+    fn saved_state_roundtrips_through_serde() {
+        for hash in [FsHash::Poseidon, FsHash::Blake3, FsHash::RescuePrime] {
+            let mut tr = Transcript::from_config(&FsConfig::new(hash), b"TEST/serde");
+            tr.absorb_field(F::from(3u64));
+            let saved = tr.save_state();
+            let back: TranscriptState =
+                serde_json::from_str(&serde_json::to_string(&saved).unwrap()).unwrap();
+            assert_eq!(back, saved);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Plonky2's `Challenger`, for proofs whose challenges a Plonky2 circuit
+//! recomputes. It is not a [`HashBackend`](crate::HashBackend): Plonky2
+//! absorbs field elements with no labels or framing, so the same calls in
+//! the same order give the same challenges as
+//! `plonky2::iop::challenger::Challenger<GoldilocksField, PoseidonHash>`.
+
+use alloc::vec::Vec;
+use ark_ff::Zero;
+use ark_goldilocks::Goldilocks as F;
+use poseidon::plonky2::{permute, Digest, RATE, WIDTH};
+
+/// Duplex sponge over [`poseidon::plonky2`] in overwrite mode. Observed
+/// elements are buffered until `RATE` of them, or a challenge, overwrite
+/// the rate; each permutation refills the output buffer, which challenges
+/// take from the back.
+#[derive(Clone, Debug)]
+pub struct Plonky2Challenger {
+    state: [F; WIDTH],
+    input: Vec<F>,
+    output: Vec<F>,
+}
+
+impl Default for Plonky2Challenger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Plonky2Challenger {
+    pub fn new() -> Self {
+        Self {
+            state: [F::zero(); WIDTH],
+            input: Vec::with_capacity(RATE),
+            output: Vec::with_capacity(RATE),
+        }
+    }
+
+    pub fn observe_element(&mut self, x: F) {
+        self.output.clear();
+        self.input.push(x);
+        if self.input.len() == RATE {
+            self.duplex();
+        }
+    }
+
+    pub fn observe_elements(&mut self, xs: &[F]) {
+        xs.iter().for_each(|&x| self.observe_element(x));
+    }
+
+    /// An extension element, by its base-field coordinates.
+    pub fn observe_extension_element<const D: usize>(&mut self, x: &[F; D]) {
+        self.observe_elements(x);
+    }
+
+    pub fn observe_hash(&mut self, digest: &Digest) {
+        self.observe_elements(digest);
+    }
+
+    /// Every digest of a Merkle cap in order, e.g. the cap of a
+    /// `MerkleHash::Plonky2` tree read back with `digest_from_bytes`.
+    pub fn observe_cap(&mut self, cap: &[Digest]) {
+        cap.iter().for_each(|d| self.observe_hash(d));
+    }
+
+    pub fn get_challenge(&mut self) -> F {
+        if !self.input.is_empty() || self.output.is_empty() {
+            self.duplex();
+        }
+        self.output.pop().expect("a permutation refills the output")
+    }
+
+    pub fn get_n_challenges(&mut self, n: usize) -> Vec<F> {
+        (0..n).map(|_| self.get_challenge()).collect()
+    }
+
+    pub fn get_hash(&mut self) -> Digest {
+        core::array::from_fn(|_| self.get_challenge())
+    }
+
+    /// An extension challenge, `D` challenges as its coordinates.
+    pub fn get_extension_challenge<const D: usize>(&mut self) -> [F; D] {
+        core::array::from_fn(|_| self.get_challenge())
+    }
+
+    /// Absorb what is buffered and drop unread outputs: the sponge state a
+    /// Plonky2 recursive verifier carries over.
+    pub fn compact(&mut self) -> [F; WIDTH] {
+        if !self.input.is_empty() {
+            self.duplex();
+        }
+        self.output.clear();
+        self.state
+    }
+
+    fn duplex(&mut self) {
+        for (lane, x) in self.state.iter_mut().zip(self.input.drain(..)) {
+            *lane = x;
+        }
+        permute(&mut self.state);
+        self.output.clear();
+        self.output.extend_from_slice(&self.state[..RATE]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Challenges of plonky2 1.1.0's `Challenger<GoldilocksField, PoseidonHash>`.
+    #[test]
+    fn challenges_match_plonky2() {
+        let fs = |xs: &[u64]| -> Vec<F> { xs.iter().map(|&x| F::from(x)).collect() };
+        let mut c = Plonky2Challenger::new();
+        c.observe_elements(&fs(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]));
+        assert_eq!(c.get_n_challenges(3), fs(&[0x7168c60611a007b0, 0xfa7595add22a1ecb, 0xbd88b7bc4056f6ec]));
+        c.observe_hash(&[1u64, 2, 3, 4].map(F::from));
+        assert_eq!(
+            c.get_hash().to_vec(),
+            fs(&[0x426eea55f693df52, 0xfa7f4a02e26d2686, 0x792dade42fa8a36d, 0x2c19b5446c5788d7])
+        );
+        assert_eq!(
+            c.get_n_challenges(9),
+            fs(&[
+                0x8406312211bbc49e, 0xfd11d67f679b4d12, 0x270a5f63d5ed724e, 0x70ec9fcf202af777, 0xafda0ad1d50fb1d3,
+                0xb629c72c93e7a7bf, 0x7aeab00012644911, 0x1ccdbb205ef27a07, 0x0e4e04798c7d4052,
+            ])
+        );
+    }
+}
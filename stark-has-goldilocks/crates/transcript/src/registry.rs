@@ -0,0 +1,56 @@
+//! Process-wide table of custom Goldilocks backends by name, so a crate
+//! can add a sponge (Tip5, Monolith, ..) once and select it by name as it
+//! would a built-in [`FsHash`](crate::FsHash).
+
+use alloc::{boxed::Box, collections::BTreeMap};
+use std::sync::{PoisonError, RwLock};
+
+use crate::{HashBackend, Transcript};
+
+/// Builds a backend from the transcript's init label.
+pub type BackendFactory = fn(init_label: &[u8]) -> Box<dyn HashBackend>;
+
+/// [`HashBackend::name`]s of the built-in backends, which cannot be
+/// registered over.
+pub const BUILTIN_NAMES: [&str; 5] = ["poseidon", "sha3-256", "blake3", "rescue-prime", "keccak-256"];
+
+static REGISTRY: RwLock<BTreeMap<&'static str, BackendFactory>> = RwLock::new(BTreeMap::new());
+
+/// A backend name already in use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NameTaken(pub &'static str);
+
+impl core::fmt::Display for NameTaken {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "transcript backend {:?} is already registered", self.0)
+    }
+}
+
+impl std::error::Error for NameTaken {}
+
+/// Make `factory` available as `name`. Names are first come, first served:
+/// neither a built-in nor an earlier registration can be replaced.
+pub fn register_backend(name: &'static str, factory: BackendFactory) -> Result<(), NameTaken> {
+    if BUILTIN_NAMES.contains(&name) {
+        return Err(NameTaken(name));
+    }
+    let mut registry = REGISTRY.write().unwrap_or_else(PoisonError::into_inner);
+    if registry.contains_key(name) {
+        return Err(NameTaken(name));
+    }
+    registry.insert(name, factory);
+    Ok(())
+}
+
+/// The factory registered as `name`, if any.
+pub fn registered_backend(name: &str) -> Option<BackendFactory> {
+    REGISTRY.read().unwrap_or_else(PoisonError::into_inner).get(name).copied()
+}
+
+impl Transcript {
+    /// Transcript over the backend registered as `name`, or `None` if
+    /// nothing is.
+    pub fn with_registered(name: &str, init_label: &[u8]) -> Option<Self> {
+        registered_backend(name).map(|factory| Self::with_custom_backend(factory(init_label)))
+    }
+}
@@ -0,0 +1,258 @@
+//! Rescue-Prime permutation over Goldilocks: width 12 (rate 8, capacity 4),
+//! `α = 7`, seven rounds, as in the 128-bit instance of the Rescue-Prime
+//! paper.
+//!
+//! The MDS matrix is the Cauchy matrix `1 / (i + (T + j))`, which is MDS
+//! because the `i` and the `T + j` are distinct and never cancel. Round
+//! constants come from SHAKE256 over [`PARAMS_SEED`], read as 8-byte
+//! little-endian words; words at or above the modulus are skipped, so each
+//! constant is uniform.
+
+use alloc::vec::Vec;
+use ark_ff::{Field, PrimeField, Zero};
+use ark_goldilocks::{dot_product, Goldilocks as F};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+
+pub const T: usize = 12;
+pub const RATE: usize = 8;
+pub const CAPACITY: usize = 4;
+pub const ROUNDS: usize = 7;
+pub const ALPHA: u64 = 7;
+/// `α⁻¹ mod (p − 1)`, the exponent of the inverse S-box.
+pub const INV_ALPHA: u64 = 10540996611094048183;
+
+pub const PARAMS_SEED: &[u8] = b"RESCUE-PRIME-GOLDILOCKS-T12-A7-R7";
+
+#[derive(Clone)]
+pub struct RescueParams {
+    pub mds: [[F; T]; T],
+    /// Two constant vectors per round, one after each half-round.
+    pub rc: [[F; T]; 2 * ROUNDS],
+}
+
+impl RescueParams {
+    pub fn derive(seed: &[u8]) -> Self {
+        let mds = core::array::from_fn(|i| {
+            core::array::from_fn(|j| F::from((i + T + j) as u64).inverse().unwrap())
+        });
+
+        let mut xof = sha3::Shake256::default();
+        xof.update(seed);
+        let mut reader = xof.finalize_xof();
+        let mut next = || loop {
+            let mut word = [0u8; 8];
+            reader.read(&mut word);
+            let w = u64::from_le_bytes(word);
+            if w < F::MODULUS.0[0] {
+                return F::from(w);
+            }
+        };
+        let mut rc = [[F::zero(); T]; 2 * ROUNDS];
+        for x in rc.iter_mut().flatten() {
+            *x = next();
+        }
+        Self { mds, rc }
+    }
+}
+
+pub fn default_params() -> RescueParams {
+    RescueParams::derive(PARAMS_SEED)
+}
+
+fn mds_mul(mds: &[[F; T]; T], state: &[F; T]) -> [F; T] {
+    core::array::from_fn(|i| dot_product(&mds[i], state))
+}
+
+fn add_constants(state: &mut [F; T], rc: &[F; T]) {
+    for (x, c) in state.iter_mut().zip(rc) {
+        *x += c;
+    }
+}
+
+pub fn permute(state: &mut [F; T], params: &RescueParams) {
+    for r in 0..ROUNDS {
+        for x in state.iter_mut() {
+            *x = x.pow([ALPHA]);
+        }
+        *state = mds_mul(&params.mds, state);
+        add_constants(state, &params.rc[2 * r]);
+
+        for x in state.iter_mut() {
+            *x = x.pow([INV_ALPHA]);
+        }
+        *state = mds_mul(&params.mds, state);
+        add_constants(state, &params.rc[2 * r + 1]);
+    }
+}
+
+/// Rescue-Prime sponge, laid out like the Poseidon backend: elements are
+/// added into the rate, the init tag sits in the last capacity element and
+/// challenges squeeze from `state[0]`.
+pub(crate) mod backend {
+    use super::*;
+    use crate::{bytes_to_field_words, domain_tag_to_field, ds, session_key_words, HashBackend, RestoreError, TranscriptState};
+    use alloc::boxed::Box;
+
+    #[derive(Clone)]
+    pub struct RescueBackend {
+        pub(crate) state: [F; T],
+        pub(crate) pos: usize,
+        /// Permutations run since construction, for [`Transcript::stats`](crate::Transcript::stats).
+        pub(crate) permutations: u64,
+        pub(crate) params: RescueParams,
+    }
+
+    impl RescueBackend {
+        pub fn new(params: RescueParams, init_label: &[u8]) -> Self {
+            let mut s = Self {
+                state: [F::zero(); T],
+                pos: 0,
+                params,
+                permutations: 0,
+            };
+            s.state[T - 1] = domain_tag_to_field(ds::TRANSCRIPT_INIT);
+            s.absorb_bytes(init_label);
+            s
+        }
+
+        /// As [`Self::new`], keyed like the Poseidon backend.
+        pub fn new_keyed(params: RescueParams, init_label: &[u8], session_key: &[u8; 32]) -> Self {
+            let mut s = Self {
+                state: [F::zero(); T],
+                pos: 0,
+                params,
+                permutations: 0,
+            };
+            s.state[T - 1] = domain_tag_to_field(ds::KEYED_INIT);
+            s.state[..8].copy_from_slice(&session_key_words(session_key));
+            s.permute();
+            s.absorb_bytes(init_label);
+            s
+        }
+
+        fn permute(&mut self) {
+            permute(&mut self.state, &self.params);
+            self.permutations += 1;
+        }
+
+        fn absorb_field_internal(&mut self, x: F) {
+            if self.pos == RATE {
+                self.permute();
+                self.pos = 0;
+            }
+            self.state[self.pos] += x;
+            self.pos += 1;
+        }
+
+        fn squeeze(&mut self) -> F {
+            self.permute();
+            self.pos = 0;
+            self.state[0]
+        }
+    }
+
+    impl HashBackend for RescueBackend {
+        fn name(&self) -> &'static str { "rescue-prime" }
+
+        fn absorb_bytes(&mut self, bytes: &[u8]) {
+            self.absorb_field_internal(domain_tag_to_field(ds::ABSORB_BYTES));
+            for w in bytes_to_field_words(bytes) {
+                self.absorb_field_internal(w);
+            }
+        }
+
+        fn absorb_field(&mut self, x: F) {
+            self.absorb_field_internal(x);
+        }
+
+        fn absorb_field_slice(&mut self, mut xs: &[F]) {
+            while !xs.is_empty() {
+                if self.pos == RATE {
+                    self.permute();
+                    self.pos = 0;
+                }
+                let n = xs.len().min(RATE - self.pos);
+                for (lane, x) in self.state[self.pos..self.pos + n].iter_mut().zip(&xs[..n]) {
+                    *lane += x;
+                }
+                self.pos += n;
+                xs = &xs[n..];
+            }
+        }
+
+        fn challenge(&mut self, label: &[u8]) -> F {
+            self.absorb_field_internal(domain_tag_to_field(ds::CHALLENGE));
+            self.absorb_bytes(label);
+            self.squeeze()
+        }
+
+        fn challenge_ext3(&mut self, label: &[u8]) -> [F; 3] {
+            self.absorb_bytes(ds::CHALLENGE_EXT3);
+            self.absorb_bytes(label);
+            self.permute();
+            self.pos = 0;
+            [self.state[0], self.state[1], self.state[2]]
+        }
+
+        fn challenge_wide(&mut self, label: &[u8]) -> [F; 2] {
+            self.absorb_bytes(ds::CHALLENGE_WIDE);
+            self.absorb_bytes(label);
+            self.permute();
+            self.pos = 0;
+            [self.state[0], self.state[1]]
+        }
+
+        /// As for Poseidon: the low 32 bits of each rate element other
+        /// than `p − 1`.
+        fn challenge_bytes(&mut self, label: &[u8], out: &mut [u8]) {
+            self.absorb_bytes(ds::CHALLENGE_BYTES);
+            self.absorb_bytes(label);
+            self.absorb_field_internal(F::from(out.len() as u64));
+            let mut words = Vec::with_capacity(out.len().div_ceil(4));
+            while words.len() * 4 < out.len() {
+                self.permute();
+                let limbs = self.state[..RATE].iter().map(|x| x.into_bigint().0[0]);
+                words.extend(limbs.filter(|&x| x != F::MODULUS.0[0] - 1).map(|x| x as u32));
+            }
+            self.pos = 0;
+            for (chunk, w) in out.chunks_mut(4).zip(words) {
+                chunk.copy_from_slice(&w.to_le_bytes()[..chunk.len()]);
+            }
+        }
+
+        fn permutations(&self) -> u64 {
+            self.permutations
+        }
+
+        fn params_digest(&self) -> Option<[u8; 32]> {
+            let p = &self.params;
+            let elems = p.mds.iter().flatten().chain(p.rc.iter().flatten());
+            Some(crate::params_digest_of(b"rescue-prime", elems))
+        }
+
+        fn boxed_clone(&self) -> Box<dyn HashBackend> {
+            Box::new(self.clone())
+        }
+
+        fn save_state(&self) -> TranscriptState {
+            TranscriptState::RescuePrime {
+                state: self.state,
+                pos: self.pos,
+            }
+        }
+
+        fn restore_state(&mut self, state: &TranscriptState) -> Result<(), RestoreError> {
+            match *state {
+                TranscriptState::RescuePrime { pos, .. } if pos > RATE => {
+                    Err(RestoreError::Position { pos })
+                }
+                TranscriptState::RescuePrime { state, pos } => {
+                    self.state = state;
+                    self.pos = pos;
+                    Ok(())
+                }
+                _ => Err(crate::backend_mismatch(self, state)),
+            }
+        }
+    }
+}
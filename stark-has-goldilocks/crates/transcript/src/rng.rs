@@ -0,0 +1,77 @@
+//! [`RngCore`] over a transcript, for code that wants a generator (e.g.
+//! `rng.gen::<u64>()`) rather than individual challenges, without
+//! reseeding a `StdRng` from serialized field elements.
+
+use ark_ff::PrimeField;
+use rand_core::{impls, Error, RngCore};
+
+use crate::{ds, Transcript};
+
+/// Bytes squeezed per refill.
+const BLOCK: usize = 64;
+
+/// Deterministic generator drawing from a copy of a transcript; see
+/// [`Transcript::rng`]. Block `i` is the challenge-bytes squeeze labelled
+/// `i` as 8 little-endian bytes.
+#[derive(Clone)]
+pub struct TranscriptRng<F: PrimeField = ark_goldilocks::Goldilocks> {
+    transcript: Transcript<F>,
+    block: u64,
+    buf: [u8; BLOCK],
+    pos: usize,
+}
+
+impl<F: PrimeField> Transcript<F> {
+    /// Generator bound to everything absorbed so far and to `label`. It
+    /// squeezes a copy, so this transcript and its challenges are
+    /// unaffected.
+    pub fn rng(&self, label: &[u8]) -> TranscriptRng<F> {
+        let mut transcript = self.clone();
+        transcript.take_trace();
+        transcript.absorb_bytes(ds::RNG);
+        transcript.absorb_bytes(label);
+        TranscriptRng {
+            transcript,
+            block: 0,
+            buf: [0; BLOCK],
+            pos: BLOCK,
+        }
+    }
+}
+
+impl<F: PrimeField> TranscriptRng<F> {
+    fn refill(&mut self) {
+        self.transcript.squeeze_bytes(&self.block.to_le_bytes(), &mut self.buf);
+        self.block += 1;
+        self.pos = 0;
+    }
+}
+
+impl<F: PrimeField> RngCore for TranscriptRng<F> {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut dest = dest;
+        while !dest.is_empty() {
+            if self.pos == BLOCK {
+                self.refill();
+            }
+            let n = dest.len().min(BLOCK - self.pos);
+            let (head, tail) = dest.split_at_mut(n);
+            head.copy_from_slice(&self.buf[self.pos..self.pos + n]);
+            self.pos += n;
+            dest = tail;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
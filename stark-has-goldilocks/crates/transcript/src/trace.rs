@@ -0,0 +1,258 @@
+//! Recording of transcript operations, for finding where a prover and a
+//! verifier stop agreeing on Fiat–Shamir.
+//!
+//! Call [`Transcript::start_trace`](crate::Transcript::start_trace) on both
+//! sides, run the protocol, then compare the two
+//! [`take_trace`](crate::Transcript::take_trace) results with
+//! [`TranscriptTrace::first_divergence`].
+//!
+//! A trace also serves as a golden file for another implementation of the
+//! same protocol (a Solidity or Python verifier, say):
+//! [`TranscriptTrace::encode`] writes it as
+//!
+//! - the magic `FSTR` and a format byte, 1;
+//! - per operation, its index in [`TraceOp`] as one byte, then its fields
+//!   in order: byte strings as a 4-byte little-endian length and the bytes,
+//!   field elements in canonical little-endian form, 8 bytes for
+//!   Goldilocks;
+//!
+//! and [`TranscriptTrace::replay`] runs it against a transcript, checking
+//! every challenge.
+
+use alloc::vec::Vec;
+use ark_ff::PrimeField;
+use ark_goldilocks::Goldilocks;
+
+use crate::{field_bytes, field_candidate, field_to_bytes, Transcript};
+
+const MAGIC: &[u8; 4] = b"FSTR";
+const FORMAT: u8 = 1;
+
+/// One primitive operation and, for challenges, what it produced.
+/// Higher-level calls appear as the absorbs and squeezes they make.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceOp<F = Goldilocks> {
+    AbsorbBytes(Vec<u8>),
+    AbsorbField(F),
+    Challenge { label: Vec<u8>, value: F },
+    ChallengeExt3 { label: Vec<u8>, value: [F; 3] },
+    ChallengeWide { label: Vec<u8>, value: [F; 2] },
+    ChallengeBytes { label: Vec<u8>, value: Vec<u8> },
+}
+
+/// Every operation since tracing started, in order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TranscriptTrace<F = Goldilocks> {
+    pub ops: Vec<TraceOp<F>>,
+}
+
+/// The first position at which two traces differ; `None` on a side means
+/// that trace ended there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Divergence<'a, F = Goldilocks> {
+    pub index: usize,
+    pub ours: Option<&'a TraceOp<F>>,
+    pub theirs: Option<&'a TraceOp<F>>,
+}
+
+impl<F: PrimeField> TranscriptTrace<F> {
+    pub(crate) fn push(&mut self, op: TraceOp<F>) {
+        self.ops.push(op);
+    }
+
+    /// Where `self` and `other` first disagree, or `None` if they are equal.
+    pub fn first_divergence<'a>(&'a self, other: &'a Self) -> Option<Divergence<'a, F>> {
+        let len = self.ops.len().max(other.ops.len());
+        (0..len)
+            .map(|index| Divergence {
+                index,
+                ours: self.ops.get(index),
+                theirs: other.ops.get(index),
+            })
+            .find(|d| d.ours != d.theirs)
+    }
+}
+
+/// Bytes that are not an encoded [`TranscriptTrace`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Missing `FSTR` or an unknown format byte.
+    Header,
+    /// Input ends inside an operation.
+    Truncated,
+    /// Operation byte with no [`TraceOp`].
+    UnknownOp(u8),
+    /// Field element at or past the modulus.
+    NonCanonical,
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DecodeError::Header => write!(f, "not an FSTR transcript trace"),
+            DecodeError::Truncated => write!(f, "transcript trace ends mid-operation"),
+            DecodeError::UnknownOp(op) => write!(f, "unknown transcript operation {op}"),
+            DecodeError::NonCanonical => write!(f, "non-canonical field element in transcript trace"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// A challenge that came out differently on replay.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayError<F = Goldilocks> {
+    pub index: usize,
+    pub expected: TraceOp<F>,
+    pub actual: TraceOp<F>,
+}
+
+impl<F: PrimeField> core::fmt::Display for ReplayError<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "replay diverges at operation {}: recorded {:?}, got {:?}",
+            self.index, self.expected, self.actual
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<F: PrimeField> std::error::Error for ReplayError<F> {}
+
+impl<F: PrimeField> TranscriptTrace<F> {
+    /// The golden-file form described in the [module docs](self).
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::from(&MAGIC[..]);
+        out.push(FORMAT);
+        let put_bytes = |out: &mut Vec<u8>, b: &[u8]| {
+            out.extend_from_slice(&(b.len() as u32).to_le_bytes());
+            out.extend_from_slice(b);
+        };
+        for op in &self.ops {
+            match op {
+                TraceOp::AbsorbBytes(b) => {
+                    out.push(0);
+                    put_bytes(&mut out, b);
+                }
+                TraceOp::AbsorbField(x) => {
+                    out.push(1);
+                    out.extend(field_to_bytes(*x));
+                }
+                TraceOp::Challenge { label, value } => {
+                    out.push(2);
+                    put_bytes(&mut out, label);
+                    out.extend(field_to_bytes(*value));
+                }
+                TraceOp::ChallengeExt3 { label, value } => {
+                    out.push(3);
+                    put_bytes(&mut out, label);
+                    value.iter().for_each(|&x| out.extend(field_to_bytes(x)));
+                }
+                TraceOp::ChallengeWide { label, value } => {
+                    out.push(4);
+                    put_bytes(&mut out, label);
+                    value.iter().for_each(|&x| out.extend(field_to_bytes(x)));
+                }
+                TraceOp::ChallengeBytes { label, value } => {
+                    out.push(5);
+                    put_bytes(&mut out, label);
+                    put_bytes(&mut out, value);
+                }
+            }
+        }
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let body = bytes
+            .strip_prefix(&MAGIC[..])
+            .and_then(|rest| rest.strip_prefix(&[FORMAT]))
+            .ok_or(DecodeError::Header)?;
+        let mut r = Reader(body);
+        let mut ops = Vec::new();
+        while let Some(&op) = r.0.first() {
+            r.0 = &r.0[1..];
+            ops.push(match op {
+                0 => TraceOp::AbsorbBytes(r.bytes()?),
+                1 => TraceOp::AbsorbField(r.field()?),
+                2 => TraceOp::Challenge { label: r.bytes()?, value: r.field()? },
+                3 => TraceOp::ChallengeExt3 {
+                    label: r.bytes()?,
+                    value: [r.field()?, r.field()?, r.field()?],
+                },
+                4 => TraceOp::ChallengeWide { label: r.bytes()?, value: [r.field()?, r.field()?] },
+                5 => TraceOp::ChallengeBytes { label: r.bytes()?, value: r.bytes()? },
+                op => return Err(DecodeError::UnknownOp(op)),
+            });
+        }
+        Ok(Self { ops })
+    }
+
+    /// Run the recorded operations on `transcript`, which must start where
+    /// the recording did (same backend, init label and version), and stop
+    /// at the first challenge that differs from the recorded one.
+    pub fn replay(&self, transcript: &mut Transcript<F>) -> Result<(), ReplayError<F>> {
+        for (index, op) in self.ops.iter().enumerate() {
+            let actual = match op {
+                TraceOp::AbsorbBytes(b) => {
+                    transcript.absorb_bytes(b);
+                    continue;
+                }
+                TraceOp::AbsorbField(x) => {
+                    transcript.absorb_field(*x);
+                    continue;
+                }
+                TraceOp::Challenge { label, .. } => {
+                    TraceOp::Challenge { label: label.clone(), value: transcript.challenge(label) }
+                }
+                TraceOp::ChallengeExt3 { label, .. } => {
+                    TraceOp::ChallengeExt3 { label: label.clone(), value: transcript.challenge_ext3(label) }
+                }
+                TraceOp::ChallengeWide { label, .. } => {
+                    TraceOp::ChallengeWide { label: label.clone(), value: transcript.challenge_wide(label) }
+                }
+                TraceOp::ChallengeBytes { label, value } => TraceOp::ChallengeBytes {
+                    label: label.clone(),
+                    value: transcript.challenge_bytes(label, value.len()),
+                },
+            };
+            if &actual != op {
+                return Err(ReplayError { index, expected: op.clone(), actual });
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], DecodeError> {
+        if self.0.len() < n {
+            return Err(DecodeError::Truncated);
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, DecodeError> {
+        let len = u32::from_le_bytes(self.take(4)?.try_into().unwrap());
+        Ok(self.take(len as usize)?.to_vec())
+    }
+
+    fn field<F: PrimeField>(&mut self) -> Result<F, DecodeError> {
+        let bytes = self.take(field_bytes::<F>())?;
+        field_candidate(bytes)
+            .filter(|&x| field_to_bytes(x) == bytes)
+            .ok_or(DecodeError::NonCanonical)
+    }
+}
+
+impl<F: PrimeField> core::fmt::Display for Divergence<'_, F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "transcripts diverge at operation {}: {:?} vs {:?}", self.index, self.ours, self.theirs)
+    }
+}
@@ -129,4 +129,10 @@ impl core::fmt::Display for ByteSize {
         };
         write!(f, "{value:.2} {suffix}")
     }
-}
\ No newline at end of file
+}
+
+impl From<&merkle::TreeStats> for ByteSize {
+    fn from(stats: &merkle::TreeStats) -> Self {
+        ByteSize(stats.bytes)
+    }
+}
@@ -78,7 +78,7 @@ impl<'a> MerkleProver<'a> {
     /// matching your FRI base-layer convention.
     pub fn commit(&mut self, values: &[F]) -> F {
         for v in values {
-            self.tree.push_leaf(*v, F::from(0u64), F::from(0u64));
+            self.tree.push_leaf(&[*v, F::from(0u64), F::from(0u64)]);
         }
         let root = self.tree.finalize();
         self.chan.absorb_field(b"merkle/root", &root);
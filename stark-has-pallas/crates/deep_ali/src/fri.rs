@@ -20,7 +20,7 @@ use transcript::{default_params as transcript_params, Transcript};
 mod ds {
     pub const FRI_SEED: &[u8] = b"FRI/seed";
     pub const FRI_INDEX: &[u8] = b"FRI/index";
-    pub const FRI_Z_L: &[u8] = b"FRI/z/l";
+    pub const FRI_Z: &[u8] = b"FRI/z";
 }
 
 /* ============================================================
@@ -43,6 +43,37 @@ impl FriDomain {
     }
 }
 
+/// Square ladder `ω₀^(2^k)` of the base-domain generator.
+///
+/// Layer ℓ's generator is `ω₀^(n₀/n_ℓ)`, so the verifier gets every
+/// `x_i = ω_ℓ^i` from this one table with at most log n₀ multiplications.
+struct OmegaLadder {
+    pow2: Vec<F>,
+}
+
+impl OmegaLadder {
+    fn new(omega0: F, n0: usize) -> Self {
+        let mut pow2 = Vec::with_capacity(n0.trailing_zeros() as usize);
+        let mut cur = omega0;
+        for _ in 0..n0.trailing_zeros() {
+            pow2.push(cur);
+            cur.square_in_place();
+        }
+        Self { pow2 }
+    }
+
+    /// `ω₀^e`, reduced mod n₀.
+    fn pow(&self, e: usize) -> F {
+        let mut acc = F::one();
+        for (k, w) in self.pow2.iter().enumerate() {
+            if (e >> k) & 1 == 1 {
+                acc *= w;
+            }
+        }
+        acc
+    }
+}
+
 /* ============================================================
    Algebra helpers
 ============================================================ */
@@ -150,11 +181,13 @@ fn merkle_depth(leaves: usize, arity: usize) -> usize {
     depth
 }
 
+/// Commit every layer under the single DEEP point `z`, which is also the
+/// folding challenge — the base-field form of goldilocks' single `z_fp3`.
 pub fn fri_build_layers(
     f0: Vec<F>,
     domain0: FriDomain,
     schedule: &[usize],
-    z_layers: &[F],
+    z: F,
 ) -> FriProverState {
     let L = schedule.len();
 
@@ -167,9 +200,7 @@ pub fn fri_build_layers(
     let mut cur_f = f_layers[0].clone();
     let mut cur_size = domain0.size;
 
-    for (ell, &m) in schedule.iter().enumerate() {
-        let z = z_layers[ell];
-
+    for &m in schedule {
         let dom = Domain::<F>::new(cur_size).unwrap();
         let omega = dom.group_gen;
         omega_layers.push(omega);
@@ -202,11 +233,11 @@ pub fn fri_build_layers(
         let mut tree = MerkleTreeChannel::new(cfg, trace_hash);
 
         for i in 0..n {
-            tree.push_leaf(
+            tree.push_leaf(&[
                 f_layers[ell][i],
                 s_layers[ell][i],
                 if ell < L { q_layers[ell][i] } else { F::zero() },
-            );
+            ]);
         }
 
         roots.push(tree.finalize());
@@ -238,7 +269,6 @@ pub struct LayerOpenPayload {
     pub f_z: F,
     pub s_i: F,
     pub q_i: F,
-    pub x_i: F,
     pub f_parent_b: F,
 }
 
@@ -352,11 +382,7 @@ fn fri_prove_queries(
         let mut tree = MerkleTreeChannel::new(cfg.clone(), trace_hash);
 
         for i in 0..n {
-            tree.push_leaf(
-                st.f_layers[ell][i],
-                st.s_layers[ell][i],
-                st.q_layers[ell][i],
-            );
+            tree.push_leaf(&[st.f_layers[ell][i], st.s_layers[ell][i], st.q_layers[ell][i]]);
         }
 
         tree.finalize();
@@ -404,15 +430,12 @@ fn fri_prove_queries(
 
         for ell in 0..L {
             let rref = &all_refs[q][ell];
-            let omega = st.omega_layers[ell];
-            let x_i = omega.pow([rref.i as u64]);
 
             payloads.push(LayerOpenPayload {
                 f_i: st.f_layers[ell][rref.i],
                 f_z: st.fz_layers[ell],
                 s_i: st.s_layers[ell][rref.i],
                 q_i: st.q_layers[ell][rref.i],
-                x_i,
                 f_parent_b: st.f_layers[ell + 1][rref.parent_index],
             });
         }
@@ -440,25 +463,37 @@ fn fri_prove_queries(
    Prover
 ============================================================ */
 
-pub fn deep_fri_prove(f0: Vec<F>, domain0: FriDomain, params: &DeepFriParams) -> DeepFriProof {
+/// Statement-bound transcript, positioned just after the DEEP challenge z
+/// (which is returned alongside it).
+fn statement_transcript(n0: usize, params: &DeepFriParams) -> (Transcript, F) {
     let L = params.schedule.len();
 
     let mut tr = Transcript::new(b"FRI/FS", transcript_params());
 
     tr.absorb_bytes(b"DEEP-FRI-STATEMENT");
-    tr.absorb_field(F::from(domain0.size as u64));
+    tr.absorb_field(F::from(n0 as u64));
     tr.absorb_field(F::from(L as u64));
     for &m in &params.schedule {
         tr.absorb_field(F::from(m as u64));
     }
     tr.absorb_field(F::from(params.seed_z));
 
-    let mut z_layers = Vec::with_capacity(L);
-    for _ in 0..L {
-        z_layers.push(tr.challenge(ds::FRI_Z_L));
-    }
+    // One challenge shared by every layer, as in the goldilocks FRI; Pallas
+    // is large enough that z needs no extension.
+    let z = tr.challenge(ds::FRI_Z);
+
+    (tr, z)
+}
+
+/// The verifier's DEEP point z for a statement of size `n0`.
+pub fn deep_fri_z_challenge(params: &DeepFriParams, n0: usize) -> F {
+    statement_transcript(n0, params).1
+}
+
+pub fn deep_fri_prove(f0: Vec<F>, domain0: FriDomain, params: &DeepFriParams) -> DeepFriProof {
+    let (mut tr, z) = statement_transcript(domain0.size, params);
 
-    let st = fri_build_layers(f0, domain0, &params.schedule, &z_layers);
+    let st = fri_build_layers(f0, domain0, &params.schedule, z);
 
     for root in &st.roots {
         tr.absorb_field(*root);
@@ -502,22 +537,7 @@ pub fn deep_fri_verify(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
     // ---------------------------------------
     // Rebuild transcript
     // ---------------------------------------
-    let mut tr = Transcript::new(b"FRI/FS", transcript_params());
-
-    tr.absorb_bytes(b"DEEP-FRI-STATEMENT");
-    tr.absorb_field(F::from(proof.n0 as u64));
-    tr.absorb_field(F::from(L as u64));
-
-    for &m in &params.schedule {
-        tr.absorb_field(F::from(m as u64));
-    }
-
-    tr.absorb_field(F::from(params.seed_z));
-
-    let mut z_layers = Vec::with_capacity(L);
-    for _ in 0..L {
-        z_layers.push(tr.challenge(ds::FRI_Z_L));
-    }
+    let (mut tr, z) = statement_transcript(proof.n0, params);
 
     for root in &proof.roots {
         tr.absorb_field(*root);
@@ -530,6 +550,15 @@ pub fn deep_fri_verify(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
     // ---------------------------------------
     let trace_hash = [0u8; 32];
 
+    // n₀ comes from the proof: past the two-adicity there is no domain.
+    if !proof.n0.is_power_of_two() {
+        return false;
+    }
+    let Some(dom0) = Domain::<F>::new(proof.n0) else {
+        return false;
+    };
+    let omega_ladder = OmegaLadder::new(dom0.group_gen, proof.n0);
+
     // ---------------------------------------
     // Verify each query
     // ---------------------------------------
@@ -574,13 +603,11 @@ pub fn deep_fri_verify(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
             // ---------------------------------------
             let pay = &qp.per_layer_payloads[ell];
 
-            let leaf = MerkleTreeChannel::compute_leaf_static(
+            let leaf = MerkleTreeChannel::leaf_digest(
                 &cfg,
                 &trace_hash,
                 idx,
-                pay.f_i,
-                pay.s_i,
-                pay.q_i,
+                &[pay.f_i, pay.s_i, pay.q_i],
             );
 
             let opening = MerkleOpening {
@@ -604,7 +631,9 @@ pub fn deep_fri_verify(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
             // ---------------------------------------
             // DEEP equation
             // ---------------------------------------
-            let lhs = pay.q_i * (pay.x_i - z_layers[ell]);
+            // x_i = ω_ℓ^i from the Merkle-bound index, never from the proof
+            let x_i = omega_ladder.pow(idx * (proof.n0 / n_layer));
+            let lhs = pay.q_i * (x_i - z);
             let rhs = pay.f_i - pay.f_z;
 
             if lhs != rhs {
@@ -643,9 +672,9 @@ pub fn deep_fri_proof_size_bytes<Ff: PrimeField>(proof: &DeepFriProof) -> usize
     // Roots
     bytes += proof.roots.len() * fb;
 
-    // Payload fields (6 per layer per query)
+    // Payload fields (5 per layer per query)
     for q in &proof.queries {
-        bytes += q.per_layer_payloads.len() * 6 * fb;
+        bytes += q.per_layer_payloads.len() * 5 * fb;
     }
 
     // Compressed Merkle proofs
@@ -664,3 +693,54 @@ pub fn deep_fri_proof_size_bytes<Ff: PrimeField>(proof: &DeepFriProof) -> usize
 
     bytes
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::UniformRand;
+
+    fn sample_proof() -> (DeepFriParams, DeepFriProof) {
+        const N: usize = 1 << 10;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<F>::new(N).unwrap();
+        let coeffs: Vec<F> = (0..N / 32).map(|_| F::rand(&mut rng)).collect();
+        let f0 = domain.fft(&coeffs);
+        let params = DeepFriParams {
+            schedule: vec![4, 4, 4, 4, 4],
+            r: 4,
+            seed_z: 7,
+        };
+        let proof = deep_fri_prove(f0, FriDomain::new_radix2(N), &params);
+        (params, proof)
+    }
+
+    #[test]
+    fn omega_ladder_matches_pow() {
+        const N: usize = 1 << 10;
+        let omega = FriDomain::new_radix2(N).omega;
+        let ladder = OmegaLadder::new(omega, N);
+        for e in [0usize, 1, 2, 37, 512, N - 1, N, N + 5] {
+            assert_eq!(ladder.pow(e), omega.pow([e as u64]));
+        }
+    }
+
+    #[test]
+    fn honest_proof_verifies() {
+        let (params, proof) = sample_proof();
+        assert!(deep_fri_verify(&params, &proof));
+    }
+
+    #[test]
+    fn tampered_quotient_is_rejected() {
+        let (params, mut proof) = sample_proof();
+        proof.queries[1].per_layer_payloads[2].q_i += F::one();
+        assert!(!deep_fri_verify(&params, &proof));
+    }
+
+    #[test]
+    fn n0_beyond_two_adicity_is_rejected() {
+        let (params, mut proof) = sample_proof();
+        proof.n0 = 1 << 33;
+        assert!(!deep_fri_verify(&params, &proof));
+    }
+}
@@ -279,15 +279,19 @@ impl DomainH {
    Deterministic sampling helper (tests)
 ============================================================ */
 
+/// z off the size-`n` domain and β, both full-width transcript challenges:
+/// a `u64` lifted into Pallas would leave them 64 bits of entropy.
 pub fn sample_z_beta_from_seed(seed: u64, n: usize) -> (F, F) {
-    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use transcript::{default_params as transcript_params, Transcript};
 
-    let mut rng = StdRng::seed_from_u64(seed);
+    let mut tr = Transcript::new(b"DEEP-ALI/SAMPLE", transcript_params());
+    tr.absorb_field(F::from(seed));
+    tr.absorb_field(F::from(n as u64));
 
-    let beta = F::from(rng.gen::<u64>());
+    let beta = tr.challenge(b"beta");
 
     let z = loop {
-        let cand = F::from(rng.gen::<u64>());
+        let cand = tr.challenge(b"z");
         if !is_in_domain(cand, n) {
             break cand;
         }
@@ -136,14 +136,13 @@ impl MerkleTreeChannel {
         Self::compress_static(ds, &self.trace_hash, children)
     }
 
-    /// ✅ Public helper for verifier leaf reconstruction
-    pub fn compute_leaf_static(
+    /// Digest `push_leaf` stores for `values` at position `index`, so a
+    /// verifier can bind opened leaf contents to `MerkleOpening::leaf`.
+    pub fn leaf_digest(
         cfg: &MerkleChannelCfg,
         trace_hash: &[u8; 32],
         index: usize,
-        f: F,
-        s: F,
-        q: F,
+        values: &[F],
     ) -> F {
         let ds = DsLabel {
             arity: cfg.layer_arities[0],
@@ -152,17 +151,18 @@ impl MerkleTreeChannel {
             tree_label: cfg.tree_label,
         };
 
-        Self::compress_static(ds, trace_hash, &[f, s, q])
+        Self::compress_static(ds, trace_hash, values)
     }
 
-    pub fn push_leaf(&mut self, f: F, s: F, q: F) {
+    /// Generic leaf: caller flattens values
+    pub fn push_leaf(&mut self, values: &[F]) {
         if self.levels.is_empty() {
             self.levels.push(Vec::new());
         }
 
         let idx = self.levels[0].len();
 
-        let leaf = Self::compute_leaf_static(&self.cfg, &self.trace_hash, idx, f, s, q);
+        let leaf = Self::leaf_digest(&self.cfg, &self.trace_hash, idx, values);
 
         self.levels[0].push(leaf);
     }
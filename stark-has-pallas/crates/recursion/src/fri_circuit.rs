@@ -0,0 +1,370 @@
+//! `deep_fri_verify` as an R1CS instance.
+//!
+//! The public inputs are the `r` layer-0 query positions; the payload values
+//! are witnesses and the DEEP point z, a function of the parameters and n₀
+//! alone, is a constant of the circuit. Each position is decomposed into bits
+//! once, and layer ℓ uses the low `log n_ℓ` of them, which is the index the
+//! prover opens there. Per query and layer the circuit enforces:
+//!
+//! * `x_i = ω_ℓ^i` from those bits (one conditional multiply per bit against
+//!   constant `ω₀^(2^k)`),
+//! * the DEEP equation `q_i · (x_i − z) = f_i − f_ℓ(z)`,
+//! * fold consistency `s_i = f_parent_b`,
+//!
+//! and final-layer constancy per query. The SHA3 Merkle openings and the
+//! transcript that draws the positions are checked natively by
+//! [`check_native`]; the crate docs give the scope. [`wrap_fri_proof`]
+//! proves the system under [`crate::wrap`].
+
+use ark_ff::{FftField, Field, One, Zero};
+use ark_pallas::Fr as F;
+use deep_ali::fri::{
+    deep_fri_verify, deep_fri_z_challenge, DeepFriParams, DeepFriProof, FriDomain,
+    LayerOpenPayload,
+};
+
+use crate::r1cs::{ConstraintSystem, LinearCombination, Variable};
+use crate::wrap::{self, WrapError, WrapKey, WrapProof};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CircuitError {
+    /// Proof shape does not match the parameters (query/layer counts, n₀).
+    MalformedProof(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WrapFriError {
+    Circuit(CircuitError),
+    Wrap(WrapError),
+}
+
+/// Counts for sizing a backend before synthesis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FriCircuitShape {
+    pub num_constraints: usize,
+    pub num_inputs: usize,
+    pub num_witnesses: usize,
+}
+
+/// Parts of verification that stay outside the circuit.
+pub fn check_native(params: &DeepFriParams, proof: &DeepFriProof) -> bool {
+    deep_fri_verify(params, proof)
+}
+
+/// Synthesize the algebraic FRI checks for `proof` into a fresh system.
+pub fn synthesize_fri_verifier(
+    params: &DeepFriParams,
+    proof: &DeepFriProof,
+) -> Result<ConstraintSystem, CircuitError> {
+    synthesize(params, proof.n0, Some(proof))
+}
+
+/// The system for proofs of size `n0` under `params`, every value zero:
+/// what [`verify_wrapped_fri`] checks a wrapped proof against. Its
+/// constraints are those of any proof's system, which only the
+/// assignment tells apart.
+pub fn fri_circuit_index(params: &DeepFriParams, n0: usize) -> Result<ConstraintSystem, CircuitError> {
+    synthesize(params, n0, None)
+}
+
+fn synthesize(
+    params: &DeepFriParams,
+    n0: usize,
+    proof: Option<&DeepFriProof>,
+) -> Result<ConstraintSystem, CircuitError> {
+    let n_layers = params.schedule.len();
+    if !n0.is_power_of_two() {
+        return Err(CircuitError::MalformedProof("n0 is not a power of two"));
+    }
+    let log_n0 = n0.trailing_zeros() as usize;
+    if log_n0 > F::TWO_ADICITY as usize {
+        return Err(CircuitError::MalformedProof("n0 has no radix-2 domain"));
+    }
+    if let Some(proof) = proof {
+        if proof.queries.len() != params.r || proof.layer_proofs.layers.len() != n_layers {
+            return Err(CircuitError::MalformedProof("query/layer count mismatch"));
+        }
+    }
+
+    let omega0 = FriDomain::new_radix2(n0).omega;
+    let mut ladder = Vec::with_capacity(log_n0);
+    let mut w = omega0;
+    for _ in 0..log_n0 {
+        ladder.push(w);
+        w.square_in_place();
+    }
+
+    let mut n_layer = n0;
+    let mut layer_logs = Vec::with_capacity(n_layers);
+    for &m in &params.schedule {
+        if m == 0 || !n_layer.is_multiple_of(m) {
+            return Err(CircuitError::MalformedProof("schedule does not divide n0"));
+        }
+        layer_logs.push(n_layer.trailing_zeros() as usize);
+        n_layer /= m;
+    }
+
+    let z = deep_fri_z_challenge(params, n0);
+    let mut cs = ConstraintSystem::new();
+
+    for q in 0..params.r {
+        let qp = proof.map(|proof| &proof.queries[q]);
+        if qp.is_some_and(|qp| qp.per_layer_payloads.len() != n_layers) {
+            return Err(CircuitError::MalformedProof("payload count mismatch"));
+        }
+
+        let idx0 = match proof {
+            Some(proof) => {
+                let mut indices = proof.layer_proofs.layers.iter().map(|layer| {
+                    layer
+                        .leaf_indices
+                        .get(q)
+                        .copied()
+                        .ok_or(CircuitError::MalformedProof("missing leaf index"))
+                });
+                let idx0 = indices.next().transpose()?.unwrap_or(0);
+                for (idx, &log_n) in indices.zip(layer_logs.iter().skip(1)) {
+                    if idx? != idx0 & ((1 << log_n) - 1) {
+                        return Err(CircuitError::MalformedProof(
+                            "leaf index does not descend from the layer-0 position",
+                        ));
+                    }
+                }
+                idx0
+            }
+            None => 0,
+        };
+        let bits = synthesize_index_bits(&mut cs, idx0, log_n0);
+
+        for (ell, &log_n) in layer_logs.iter().enumerate() {
+            let shift = log_n0 - log_n;
+            let x = synthesize_omega_pow(&mut cs, &bits[..log_n], &ladder[shift..]);
+
+            let pay = qp.map(|qp| &qp.per_layer_payloads[ell]);
+            let value = |get: fn(&LayerOpenPayload) -> F| pay.map_or(F::zero(), get);
+            let f = cs.alloc_witness(value(|p| p.f_i));
+            let f_z = cs.alloc_witness(value(|p| p.f_z));
+            let s = cs.alloc_witness(value(|p| p.s_i));
+            let q_i = cs.alloc_witness(value(|p| p.q_i));
+            let f_parent = cs.alloc_witness(value(|p| p.f_parent_b));
+
+            // DEEP: q_i · (x − z) = f − f_z
+            cs.enforce(
+                q_i,
+                LinearCombination::from(x).term(-z, Variable::One),
+                LinearCombination::from(f).minus(f_z),
+            );
+
+            // Fold: s_i − f_parent_b = 0
+            cs.enforce_zero(LinearCombination::from(s).minus(f_parent));
+        }
+
+        let (a, b) = qp.map_or((F::zero(), F::zero()), |qp| qp.final_pair);
+        let a = cs.alloc_witness(a);
+        let b = cs.alloc_witness(b);
+        cs.enforce_zero(LinearCombination::from(a).minus(b));
+    }
+
+    Ok(cs)
+}
+
+/// `proof`'s algebraic checks as a [`WrapProof`] under `key`, with the
+/// layer-0 query positions it is a statement about.
+pub fn wrap_fri_proof(
+    key: &WrapKey,
+    params: &DeepFriParams,
+    proof: &DeepFriProof,
+) -> Result<(Vec<F>, WrapProof), WrapFriError> {
+    let cs = synthesize_fri_verifier(params, proof).map_err(WrapFriError::Circuit)?;
+    let wrapped = wrap::prove(key, &cs).map_err(WrapFriError::Wrap)?;
+    Ok((cs.public_inputs().to_vec(), wrapped))
+}
+
+/// Checks a [`wrap_fri_proof`] output for proofs of size `n0` under
+/// `params`.
+pub fn verify_wrapped_fri(
+    key: &WrapKey,
+    params: &DeepFriParams,
+    n0: usize,
+    positions: &[F],
+    proof: &WrapProof,
+) -> bool {
+    fri_circuit_index(params, n0).is_ok_and(|index| wrap::verify(key, index.constraints(), positions, proof))
+}
+
+/// Shape of the system `synthesize_fri_verifier` would build.
+pub fn fri_circuit_shape(
+    params: &DeepFriParams,
+    proof: &DeepFriProof,
+) -> Result<FriCircuitShape, CircuitError> {
+    let cs = synthesize_fri_verifier(params, proof)?;
+    Ok(FriCircuitShape {
+        num_constraints: cs.num_constraints(),
+        num_inputs: cs.num_inputs(),
+        num_witnesses: cs.num_witnesses(),
+    })
+}
+
+/// The layer-0 query position `idx < 2^bits` as a public input, and its
+/// bits, least significant first, as booleans tied to it by one linear
+/// constraint.
+///
+/// Layer ℓ opens position `idx mod n_ℓ`, so its index is the low `log n_ℓ`
+/// of these bits and every layer of a query shares one decomposition.
+fn synthesize_index_bits(cs: &mut ConstraintSystem, idx: usize, bits: usize) -> Vec<Variable> {
+    let idx_var = cs.alloc_input(F::from(idx as u64));
+
+    let mut recompose = LinearCombination::zero().minus(idx_var);
+    let mut out = Vec::with_capacity(bits);
+    for k in 0..bits {
+        let bit = (idx >> k) & 1 == 1;
+        let b = cs.alloc_witness(if bit { F::one() } else { F::from(0u64) });
+        cs.enforce_boolean(b);
+        recompose = recompose.term(F::from(1u64 << k), b);
+        out.push(b);
+    }
+    cs.enforce_zero(recompose);
+    out
+}
+
+/// `ω^i` for the index whose boolean bits are `bits`, with `ladder[k] = ω^(2^k)`.
+///
+/// One `acc · (1 + b_k(ω^(2^k) − 1))` step per bit.
+fn synthesize_omega_pow(cs: &mut ConstraintSystem, bits: &[Variable], ladder: &[F]) -> Variable {
+    let mut acc_val = F::one();
+    let mut acc: LinearCombination = LinearCombination::constant(F::one());
+    let mut acc_var = None;
+
+    for (&b, w) in bits.iter().zip(ladder) {
+        if cs.value(b).is_one() {
+            acc_val *= w;
+        }
+        let next = cs.alloc_witness(acc_val);
+        cs.enforce(
+            acc,
+            LinearCombination::constant(F::one()).term(*w - F::one(), b),
+            next,
+        );
+        acc = next.into();
+        acc_var = Some(next);
+    }
+
+    acc_var.unwrap_or_else(|| {
+        let one = cs.alloc_witness(F::one());
+        cs.enforce_zero(LinearCombination::from(one).term(-F::one(), Variable::One));
+        one
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::UniformRand;
+    use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+    use deep_ali::fri::deep_fri_prove;
+
+    fn sample_proof() -> (DeepFriParams, DeepFriProof) {
+        sample_proof_with(vec![4, 4, 4, 4, 4], 4)
+    }
+
+    fn sample_proof_with(schedule: Vec<usize>, r: usize) -> (DeepFriParams, DeepFriProof) {
+        const N: usize = 1 << 10;
+        let mut rng = rand::thread_rng();
+        let domain = GeneralEvaluationDomain::<F>::new(N).unwrap();
+        let coeffs: Vec<F> = (0..N / 32).map(|_| F::rand(&mut rng)).collect();
+        let f0 = domain.fft(&coeffs);
+        let params = DeepFriParams {
+            schedule,
+            r,
+            seed_z: 7,
+        };
+        let proof = deep_fri_prove(f0, FriDomain::new_radix2(N), &params);
+        (params, proof)
+    }
+
+    #[test]
+    fn honest_proof_satisfies_circuit() {
+        let (params, proof) = sample_proof();
+        assert!(check_native(&params, &proof));
+
+        let cs = synthesize_fri_verifier(&params, &proof).unwrap();
+        assert_eq!(cs.which_is_unsatisfied(), None);
+        assert_eq!(cs.num_inputs(), params.r);
+    }
+
+    #[test]
+    fn tampered_quotient_violates_circuit() {
+        let (params, mut proof) = sample_proof();
+        proof.queries[1].per_layer_payloads[2].q_i += F::one();
+
+        let cs = synthesize_fri_verifier(&params, &proof).unwrap();
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn layer_indices_must_descend_from_layer_zero() {
+        let (params, mut proof) = sample_proof();
+        proof.layer_proofs.layers[3].leaf_indices[0] ^= 1;
+
+        assert_eq!(
+            synthesize_fri_verifier(&params, &proof).err(),
+            Some(CircuitError::MalformedProof(
+                "leaf index does not descend from the layer-0 position"
+            ))
+        );
+    }
+
+    #[test]
+    fn omega_pow_gadget() {
+        let omega = FriDomain::new_radix2(1 << 8).omega;
+        let ladder: Vec<F> = (0..8).map(|k| omega.pow([1u64 << k])).collect();
+        for idx in [0usize, 1, 37, 255] {
+            let mut cs = ConstraintSystem::new();
+            let bits = synthesize_index_bits(&mut cs, idx, 8);
+            let x = synthesize_omega_pow(&mut cs, &bits, &ladder);
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.public_inputs(), &[F::from(idx as u64)]);
+            assert_eq!(cs.value(x), omega.pow([idx as u64]));
+        }
+    }
+
+    #[test]
+    fn wrapped_proofs_do_not_grow_with_queries_or_layers() {
+        let key = WrapKey::new(10);
+        let mut sizes = Vec::new();
+        for (schedule, r) in [(vec![8, 8, 16], 2), (vec![4, 4, 4, 4, 4], 4), (vec![2; 10], 7)] {
+            let (params, proof) = sample_proof_with(schedule, r);
+            assert!(check_native(&params, &proof));
+            let (positions, wrapped) = wrap_fri_proof(&key, &params, &proof).unwrap();
+            assert_eq!(positions.len(), r);
+            assert!(verify_wrapped_fri(&key, &params, proof.n0, &positions, &wrapped));
+
+            let mut moved = positions.clone();
+            moved[r - 1] += F::one();
+            assert!(!verify_wrapped_fri(&key, &params, proof.n0, &moved, &wrapped));
+            sizes.push(wrapped.size_bytes());
+        }
+        assert!(sizes.windows(2).all(|w| w[0] == w[1]), "{sizes:?}");
+    }
+
+    #[test]
+    fn tampered_proofs_are_not_wrapped() {
+        let (params, mut proof) = sample_proof();
+        proof.queries[0].per_layer_payloads[1].s_i += F::one();
+        assert!(matches!(
+            wrap_fri_proof(&WrapKey::new(10), &params, &proof),
+            Err(WrapFriError::Wrap(WrapError::Unsatisfied(_)))
+        ));
+
+        let (params, proof) = sample_proof();
+        let shape = fri_circuit_shape(&params, &proof).unwrap();
+        assert_eq!(
+            wrap_fri_proof(&WrapKey::new(6), &params, &proof).err(),
+            Some(WrapFriError::Wrap(WrapError::Capacity {
+                constraints: shape.num_constraints,
+                witnesses: shape.num_witnesses,
+                inputs: shape.num_inputs,
+            }))
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Inner-product argument over the Pallas curve.
+//!
+//! A vector `a` is committed as `⟨a, G⟩` against generators hashed to the
+//! curve, so there is no trusted setup. [`open`] proves `⟨a, b⟩ = v` for a
+//! public `b` in `log n` halving rounds, as in Bulletproofs: the proof is
+//! `2 log n` points and one scalar. The commitment has no blinding term;
+//! it binds, it does not hide.
+
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, One, PrimeField};
+use ark_pallas::{Affine, Fq, Fr as F, Projective};
+use transcript::Transcript;
+
+mod ds {
+    pub const IPA_U: &[u8] = b"IPA/u";
+    pub const IPA_ROUND: &[u8] = b"IPA/round";
+}
+
+/// Commitment generators `G_0, …, G_{n−1}` and the value generator `U`.
+#[derive(Clone, Debug)]
+pub struct Generators {
+    pub g: Vec<Affine>,
+    pub u: Affine,
+}
+
+impl Generators {
+    /// `n` generators and `U`, each hashed to the curve from `label`.
+    pub fn new(label: &[u8], n: usize) -> Self {
+        Self {
+            g: (0..n as u64).map(|i| hash_to_curve(label, i)).collect(),
+            u: hash_to_curve(label, u64::MAX),
+        }
+    }
+
+    /// `⟨a, G⟩` over the first `a.len()` generators.
+    pub fn commit(&self, a: &[F]) -> Affine {
+        Projective::msm_unchecked(&self.g[..a.len()], a).into_affine()
+    }
+}
+
+/// The first point with `x = Blake3(label ‖ i ‖ ctr)` (mod p). Pallas has
+/// cofactor 1, so every such point lies in the group, and no discrete-log
+/// relation between two of them is known to anyone.
+fn hash_to_curve(label: &[u8], i: u64) -> Affine {
+    (0u64..)
+        .find_map(|ctr| {
+            let mut h = blake3::Hasher::new();
+            h.update(label);
+            h.update(&i.to_le_bytes());
+            h.update(&ctr.to_le_bytes());
+            let x = Fq::from_le_bytes_mod_order(h.finalize().as_bytes());
+            Affine::get_point_from_x_unchecked(x, false)
+        })
+        .unwrap()
+}
+
+/// Absorb `p` as its two coordinates, each below the base modulus and so
+/// below `Fr`'s: one word apiece, with no reduction to collide on.
+pub(crate) fn absorb_point(tr: &mut Transcript, p: &Affine) {
+    let (x, y) = p.xy().unwrap_or_default();
+    tr.absorb_field(F::from(p.is_zero()));
+    for c in [x, y] {
+        tr.absorb_field(F::from_bigint(c.into_bigint()).expect("base field fits in Fr"));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IpaProof {
+    /// Cross terms per round.
+    pub l: Vec<Affine>,
+    pub r: Vec<Affine>,
+    /// `a` folded down to one element.
+    pub a: F,
+}
+
+pub(crate) fn inner(a: &[F], b: &[F]) -> F {
+    a.iter().zip(b).map(|(x, y)| *x * y).sum()
+}
+
+/// Proves that the commitment `⟨a, G⟩` opens to `⟨a, b⟩`. The caller has
+/// bound the commitment and the value into `tr`. `a.len()` is a power of
+/// two no larger than the generator count.
+pub fn open(gens: &Generators, tr: &mut Transcript, a: &[F], b: &[F]) -> IpaProof {
+    assert!(a.len().is_power_of_two() && a.len() == b.len() && a.len() <= gens.g.len());
+    let u = gens.u * tr.challenge(ds::IPA_U);
+    let (mut a, mut b, mut g) = (a.to_vec(), b.to_vec(), gens.g[..a.len()].to_vec());
+    let (mut ls, mut rs) = (Vec::new(), Vec::new());
+
+    while a.len() > 1 {
+        let h = a.len() / 2;
+        let (a_lo, a_hi) = a.split_at(h);
+        let (b_lo, b_hi) = b.split_at(h);
+        let (g_lo, g_hi) = g.split_at(h);
+        let l = (Projective::msm_unchecked(g_hi, a_lo) + u * inner(a_lo, b_hi)).into_affine();
+        let r = (Projective::msm_unchecked(g_lo, a_hi) + u * inner(a_hi, b_lo)).into_affine();
+        absorb_point(tr, &l);
+        absorb_point(tr, &r);
+        let x = tr.challenge(ds::IPA_ROUND);
+        let x_inv = x.inverse().expect("round challenge is nonzero");
+
+        a = (0..h).map(|i| a_lo[i] * x + a_hi[i] * x_inv).collect();
+        b = (0..h).map(|i| b_lo[i] * x_inv + b_hi[i] * x).collect();
+        let folded: Vec<Projective> = (0..h).map(|i| g_lo[i] * x_inv + g_hi[i] * x).collect();
+        g = Projective::normalize_batch(&folded);
+        ls.push(l);
+        rs.push(r);
+    }
+
+    IpaProof { l: ls, r: rs, a: a[0] }
+}
+
+/// Checks `proof` for `commitment` opening to `v = ⟨a, b⟩`, with `tr` in
+/// the state [`open`] started from.
+pub fn verify(
+    gens: &Generators,
+    tr: &mut Transcript,
+    commitment: Affine,
+    b: &[F],
+    v: F,
+    proof: &IpaProof,
+) -> bool {
+    let n = b.len();
+    let rounds = n.trailing_zeros() as usize;
+    if !n.is_power_of_two() || n > gens.g.len() || proof.l.len() != rounds || proof.r.len() != rounds {
+        return false;
+    }
+
+    let u = gens.u * tr.challenge(ds::IPA_U);
+    let mut p = commitment.into_group() + u * v;
+    let mut xs = Vec::with_capacity(rounds);
+    for (l, r) in proof.l.iter().zip(&proof.r) {
+        absorb_point(tr, l);
+        absorb_point(tr, r);
+        let x = tr.challenge(ds::IPA_ROUND);
+        let Some(x_inv) = x.inverse() else {
+            return false;
+        };
+        p += *l * x.square() + *r * x_inv.square();
+        xs.push((x, x_inv));
+    }
+
+    // Round j halves on bit `rounds − 1 − j`, weighting the high half by
+    // x_j and the low half by x_j⁻¹, for G and b alike.
+    let s: Vec<F> = (0..n)
+        .map(|i| {
+            xs.iter().enumerate().fold(F::one(), |acc, (j, &(x, x_inv))| {
+                acc * if (i >> (rounds - 1 - j)) & 1 == 1 { x } else { x_inv }
+            })
+        })
+        .collect();
+    let g = Projective::msm_unchecked(&gens.g[..n], &s);
+    let b_final = inner(&s, b);
+
+    p == g * proof.a + u * (proof.a * b_final)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::UniformRand;
+    use transcript::default_params;
+
+    #[test]
+    fn openings_round_trip() {
+        let mut rng = rand::thread_rng();
+        let gens = Generators::new(b"TEST/ipa", 16);
+        let a: Vec<F> = (0..16).map(|_| F::rand(&mut rng)).collect();
+        let b: Vec<F> = (0..16).map(|_| F::rand(&mut rng)).collect();
+        let (c, v) = (gens.commit(&a), inner(&a, &b));
+        let tr = || Transcript::new(b"TEST/ipa", default_params());
+
+        let proof = open(&gens, &mut tr(), &a, &b);
+        assert_eq!(proof.l.len(), 4);
+        assert!(verify(&gens, &mut tr(), c, &b, v, &proof));
+        assert!(!verify(&gens, &mut tr(), c, &b, v + F::one(), &proof));
+        assert!(!verify(&gens, &mut tr(), gens.commit(&b), &b, v, &proof));
+
+        let mut bad = proof.clone();
+        bad.l.swap(0, 1);
+        assert!(!verify(&gens, &mut tr(), c, &b, v, &bad));
+        let mut bad = proof.clone();
+        bad.r.pop();
+        assert!(!verify(&gens, &mut tr(), c, &b, v, &bad));
+    }
+}
@@ -0,0 +1,34 @@
+//! Arithmetisation of the DEEP-FRI verifier over Pallas, and an argument
+//! that wraps it into a constant-size proof.
+//!
+//! * [`r1cs`] — a small self-checking R1CS builder over `Fr`,
+//! * [`fri_circuit`] — the algebraic part of `deep_fri_verify` (x_i
+//!   derivation, DEEP equation, fold and final-layer checks) as constraints,
+//! * [`ipa`] — a Bulletproofs inner-product argument over the Pallas curve,
+//! * [`wrap`] — a Spartan-style argument for any system within a
+//!   [`WrapKey`]'s capacity, opened through [`ipa`].
+//!
+//! [`wrap_fri_proof`] turns a FRI proof into its query positions and a
+//! [`WrapProof`] whose size depends only on the key, not on `r` or the
+//! number of layers; [`verify_wrapped_fri`] checks it against the system
+//! rebuilt from the parameters.
+//!
+//! # Scope
+//!
+//! The wrapped statement is the algebraic checks over the given positions.
+//! The SHA3 Merkle openings that bind the payload to the layer roots, and
+//! the Poseidon transcript that draws the positions from those roots, are not
+//! in the system, so a wrapped proof stands in for the FRI proof only
+//! alongside [`check_native`], which runs the full verifier.
+
+pub mod fri_circuit;
+pub mod ipa;
+pub mod r1cs;
+pub mod wrap;
+
+pub use fri_circuit::{
+    check_native, fri_circuit_index, fri_circuit_shape, synthesize_fri_verifier, verify_wrapped_fri,
+    wrap_fri_proof, CircuitError, WrapFriError,
+};
+pub use r1cs::{ConstraintSystem, LinearCombination, Variable};
+pub use wrap::{WrapError, WrapKey, WrapProof};
@@ -0,0 +1,177 @@
+//! Minimal rank-1 constraint system over Pallas `Fr`.
+//!
+//! Each constraint is `⟨a, z⟩ · ⟨b, z⟩ = ⟨c, z⟩` where `z = (1, inputs, witness)`.
+//! The system stores its own assignment, so synthesis and satisfiability
+//! checking happen in one pass; a proving backend only needs the matrices
+//! and `z`.
+
+use ark_ff::{One, Zero};
+use ark_pallas::Fr as F;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variable {
+    One,
+    Input(usize),
+    Witness(usize),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LinearCombination(pub Vec<(F, Variable)>);
+
+impl LinearCombination {
+    pub fn zero() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn constant(c: F) -> Self {
+        Self(vec![(c, Variable::One)])
+    }
+
+    pub fn term(mut self, coeff: F, var: Variable) -> Self {
+        self.0.push((coeff, var));
+        self
+    }
+
+    pub fn plus(self, var: Variable) -> Self {
+        self.term(F::one(), var)
+    }
+
+    pub fn minus(self, var: Variable) -> Self {
+        self.term(-F::one(), var)
+    }
+}
+
+impl From<Variable> for LinearCombination {
+    fn from(v: Variable) -> Self {
+        Self(vec![(F::one(), v)])
+    }
+}
+
+pub struct Constraint {
+    pub a: LinearCombination,
+    pub b: LinearCombination,
+    pub c: LinearCombination,
+}
+
+#[derive(Default)]
+pub struct ConstraintSystem {
+    inputs: Vec<F>,
+    witness: Vec<F>,
+    constraints: Vec<Constraint>,
+}
+
+impl ConstraintSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn alloc_input(&mut self, value: F) -> Variable {
+        self.inputs.push(value);
+        Variable::Input(self.inputs.len() - 1)
+    }
+
+    pub fn alloc_witness(&mut self, value: F) -> Variable {
+        self.witness.push(value);
+        Variable::Witness(self.witness.len() - 1)
+    }
+
+    pub fn enforce(
+        &mut self,
+        a: impl Into<LinearCombination>,
+        b: impl Into<LinearCombination>,
+        c: impl Into<LinearCombination>,
+    ) {
+        self.constraints.push(Constraint {
+            a: a.into(),
+            b: b.into(),
+            c: c.into(),
+        });
+    }
+
+    /// `lc = 0`, as `lc · 1 = 0`.
+    pub fn enforce_zero(&mut self, lc: LinearCombination) {
+        self.enforce(
+            lc,
+            LinearCombination::constant(F::one()),
+            LinearCombination::zero(),
+        );
+    }
+
+    /// `v ∈ {0, 1}`, as `v · (1 − v) = 0`.
+    pub fn enforce_boolean(&mut self, v: Variable) {
+        self.enforce(
+            v,
+            LinearCombination::constant(F::one()).minus(v),
+            LinearCombination::zero(),
+        );
+    }
+
+    pub fn value(&self, v: Variable) -> F {
+        match v {
+            Variable::One => F::one(),
+            Variable::Input(i) => self.inputs[i],
+            Variable::Witness(i) => self.witness[i],
+        }
+    }
+
+    pub fn eval(&self, lc: &LinearCombination) -> F {
+        lc.0.iter()
+            .fold(F::zero(), |acc, (coeff, v)| acc + *coeff * self.value(*v))
+    }
+
+    /// Index of the first violated constraint, if any.
+    pub fn which_is_unsatisfied(&self) -> Option<usize> {
+        self.constraints
+            .iter()
+            .position(|k| self.eval(&k.a) * self.eval(&k.b) != self.eval(&k.c))
+    }
+
+    pub fn is_satisfied(&self) -> bool {
+        self.which_is_unsatisfied().is_none()
+    }
+
+    pub fn public_inputs(&self) -> &[F] {
+        &self.inputs
+    }
+
+    pub fn witness(&self) -> &[F] {
+        &self.witness
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn num_witnesses(&self) -> usize {
+        self.witness.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn product_constraint() {
+        let mut cs = ConstraintSystem::new();
+        let out = cs.alloc_input(F::from(15u64));
+        let a = cs.alloc_witness(F::from(3u64));
+        let b = cs.alloc_witness(F::from(5u64));
+        cs.enforce(a, b, out);
+        let bit = cs.alloc_witness(F::one());
+        cs.enforce_boolean(bit);
+        assert!(cs.is_satisfied());
+
+        let bad = cs.alloc_witness(F::from(2u64));
+        cs.enforce_boolean(bad);
+        assert_eq!(cs.which_is_unsatisfied(), Some(2));
+    }
+}
@@ -0,0 +1,444 @@
+//! Constant-size argument of knowledge for a [`ConstraintSystem`].
+//!
+//! Spartan's two sumchecks reduce `Az ∘ Bz = Cz` to one evaluation of the
+//! witness's multilinear extension, which [`crate::ipa`] opens against a
+//! commitment to the witness. Systems are padded to a [`WrapKey`]'s
+//! capacity, `2^k` constraints and `2^k` witnesses with at most `2^k − 1`
+//! public inputs, so every proof under a key has the same shape: one
+//! commitment, `k` cubic and `k + 1` quadratic round polynomials, four
+//! claimed evaluations and a `k`-round opening. The verifier evaluates the
+//! matrices and folds the generators itself, in time linear in the
+//! capacity. The commitment does not hide: this is succinct, not
+//! zero-knowledge.
+//!
+//! `z` is laid out as `(w, 1, x)` with the witness `w` in the low half
+//! and `(1, x)` in the high half, each padded to `2^k`, so
+//! `z̃(r, r_k) = (1 − r_k)·w̃(r) + r_k·(1, x)~(r)` and the verifier computes
+//! the public half itself.
+
+use ark_ff::{BigInteger, Field, One, PrimeField, Zero};
+use ark_pallas::{Affine, Fr as F};
+use ark_serialize::CanonicalSerialize;
+use transcript::{default_params as transcript_params, Transcript};
+
+use crate::ipa::{self, absorb_point, Generators, IpaProof};
+use crate::r1cs::{Constraint, ConstraintSystem, Variable};
+
+mod ds {
+    pub const WRAP_INIT: &[u8] = b"WRAP/init";
+    pub const WRAP_GENERATORS: &[u8] = b"WRAP/generators";
+    pub const WRAP_TAU: &[u8] = b"WRAP/tau";
+    pub const WRAP_ROUND: &[u8] = b"WRAP/round";
+    pub const WRAP_RHO: &[u8] = b"WRAP/rho";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WrapError {
+    /// The system does not fit the key's capacity.
+    Capacity {
+        constraints: usize,
+        witnesses: usize,
+        inputs: usize,
+    },
+    /// Index of the first violated constraint.
+    Unsatisfied(usize),
+}
+
+/// Generators for systems of up to `2^log_capacity` constraints and
+/// witnesses. Transparent: anyone rebuilds the same key from the capacity.
+#[derive(Clone, Debug)]
+pub struct WrapKey {
+    pub log_capacity: usize,
+    pub gens: Generators,
+}
+
+impl WrapKey {
+    pub fn new(log_capacity: usize) -> Self {
+        Self {
+            log_capacity,
+            gens: Generators::new(ds::WRAP_GENERATORS, 1 << log_capacity),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        1 << self.log_capacity
+    }
+
+    fn fits(&self, constraints: usize, witnesses: usize, inputs: usize) -> Result<(), WrapError> {
+        let n = self.capacity();
+        if constraints > n || witnesses > n || inputs >= n {
+            return Err(WrapError::Capacity { constraints, witnesses, inputs });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WrapProof {
+    /// `⟨w, G⟩` over the padded witness.
+    pub witness_commitment: Affine,
+    /// Evaluations at 0, 1, 2, 3 of each round of the first sumcheck.
+    pub outer_rounds: Vec<[F; 4]>,
+    /// `Ãz`, `B̃z`, `C̃z` at the first sumcheck's point.
+    pub claims: [F; 3],
+    /// Evaluations at 0, 1, 2 of each round of the second sumcheck.
+    pub inner_rounds: Vec<[F; 3]>,
+    /// `w̃` at the second sumcheck's point, less its top coordinate.
+    pub witness_eval: F,
+    pub opening: IpaProof,
+}
+
+impl WrapProof {
+    /// Compressed size; the same for every proof under one key.
+    pub fn size_bytes(&self) -> usize {
+        let scalars = self.outer_rounds.len() * 4 + 3 + self.inner_rounds.len() * 3 + 2;
+        let points = 1 + self.opening.l.len() + self.opening.r.len();
+        scalars * F::zero().compressed_size() + points * self.witness_commitment.compressed_size()
+    }
+}
+
+/// Column of `v` in `z` for capacity `n`.
+fn column(v: Variable, n: usize) -> usize {
+    match v {
+        Variable::Witness(i) => i,
+        Variable::One => n,
+        Variable::Input(j) => n + 1 + j,
+    }
+}
+
+/// `eq(τ, x)` for every `x ∈ {0,1}^k`, bit `i` of `x` against `τ_i`.
+fn eq_table(tau: &[F]) -> Vec<F> {
+    let mut table = vec![F::one()];
+    for (i, &t) in tau.iter().enumerate() {
+        let mut next = vec![F::zero(); 2 << i];
+        for (x, &e) in table.iter().enumerate() {
+            next[x] = e * (F::one() - t);
+            next[x | (1 << i)] = e * t;
+        }
+        table = next;
+    }
+    table
+}
+
+fn eq_eval(a: &[F], b: &[F]) -> F {
+    a.iter().zip(b).map(|(&x, &y)| x * y + (F::one() - x) * (F::one() - y)).product()
+}
+
+/// Binds the lowest variable of `table` to `r`.
+fn bind(table: &mut Vec<F>, r: F) {
+    let half = table.len() / 2;
+    for j in 0..half {
+        table[j] = table[2 * j] + r * (table[2 * j + 1] - table[2 * j]);
+    }
+    table.truncate(half);
+}
+
+/// The multilinear extension of `table` at `point`, lowest variable first.
+fn mle_eval(table: &[F], point: &[F]) -> F {
+    let mut t = table.to_vec();
+    for &r in point {
+        bind(&mut t, r);
+    }
+    t[0]
+}
+
+/// The polynomial through `(i, evals[i])` at `r`.
+fn interpolate(evals: &[F], r: F) -> F {
+    let nodes: Vec<F> = (0..evals.len() as u64).map(F::from).collect();
+    evals
+        .iter()
+        .enumerate()
+        .map(|(i, &e)| {
+            let (num, den) = nodes.iter().enumerate().filter(|&(j, _)| j != i).fold(
+                (F::one(), F::one()),
+                |(num, den), (_, &xj)| (num * (r - xj), den * (nodes[i] - xj)),
+            );
+            e * num * den.inverse().unwrap()
+        })
+        .sum()
+}
+
+/// Transcript bound to the key, the matrices and the public inputs.
+fn statement_transcript(key: &WrapKey, constraints: &[Constraint], inputs: &[F]) -> Transcript {
+    let mut h = blake3::Hasher::new();
+    for (row, k) in constraints.iter().enumerate() {
+        for (m, lc) in [&k.a, &k.b, &k.c].into_iter().enumerate() {
+            for (coeff, v) in &lc.0 {
+                h.update(&(row as u64).to_le_bytes());
+                h.update(&[m as u8]);
+                h.update(&(column(*v, key.capacity()) as u64).to_le_bytes());
+                h.update(&coeff.into_bigint().to_bytes_le());
+            }
+        }
+    }
+
+    let mut tr = Transcript::new(ds::WRAP_INIT, transcript_params());
+    tr.absorb_field(F::from(key.log_capacity as u64));
+    tr.absorb_field(F::from(constraints.len() as u64));
+    tr.absorb_bytes(h.finalize().as_bytes());
+    tr.absorb_field(F::from(inputs.len() as u64));
+    for &x in inputs {
+        tr.absorb_field(x);
+    }
+    tr
+}
+
+/// `Σ_x eq(r_x, x)·(A + ρB + ρ²C)[x][·]`, the second sumcheck's matrix
+/// row; `None` if a constraint names a column outside the capacity.
+fn combined_row(constraints: &[Constraint], r_x: &[F], rho: F, n: usize, inputs: usize) -> Option<Vec<F>> {
+    let eq = eq_table(r_x);
+    let mut row = vec![F::zero(); 2 * n];
+    for (x, k) in constraints.iter().enumerate() {
+        for (weight, lc) in [(F::one(), &k.a), (rho, &k.b), (rho.square(), &k.c)] {
+            for &(coeff, v) in &lc.0 {
+                match v {
+                    Variable::Witness(i) if i >= n => return None,
+                    Variable::Input(j) if j >= inputs => return None,
+                    _ => row[column(v, n)] += eq[x] * weight * coeff,
+                }
+            }
+        }
+    }
+    Some(row)
+}
+
+/// `(1, x)` padded to `n`.
+fn public_half(inputs: &[F], n: usize) -> Vec<F> {
+    let mut io = vec![F::zero(); n];
+    io[0] = F::one();
+    io[1..=inputs.len()].copy_from_slice(inputs);
+    io
+}
+
+/// Proves that `cs`'s assignment satisfies it.
+pub fn prove(key: &WrapKey, cs: &ConstraintSystem) -> Result<WrapProof, WrapError> {
+    key.fits(cs.num_constraints(), cs.num_witnesses(), cs.num_inputs())?;
+    if let Some(i) = cs.which_is_unsatisfied() {
+        return Err(WrapError::Unsatisfied(i));
+    }
+    let (k, n) = (key.log_capacity, key.capacity());
+    let inputs = cs.public_inputs();
+    let mut w = cs.witness().to_vec();
+    w.resize(n, F::zero());
+
+    let mut tr = statement_transcript(key, cs.constraints(), inputs);
+    let witness_commitment = key.gens.commit(&w);
+    absorb_point(&mut tr, &witness_commitment);
+
+    // Σ_x eq(τ, x)·(Az·Bz − Cz)(x) = 0
+    let tau: Vec<F> = (0..k).map(|_| tr.challenge(ds::WRAP_TAU)).collect();
+    let mut eq = eq_table(&tau);
+    let [mut az, mut bz, mut cz] = [0, 1, 2].map(|m| {
+        let mut v: Vec<F> = cs.constraints().iter().map(|c| cs.eval([&c.a, &c.b, &c.c][m])).collect();
+        v.resize(n, F::zero());
+        v
+    });
+    let mut outer_rounds = Vec::with_capacity(k);
+    let mut r_x = Vec::with_capacity(k);
+    for _ in 0..k {
+        let mut e = [F::zero(); 4];
+        for j in 0..eq.len() / 2 {
+            for (t, e_t) in e.iter_mut().enumerate() {
+                let t = F::from(t as u64);
+                let at = |v: &[F]| v[2 * j] + t * (v[2 * j + 1] - v[2 * j]);
+                *e_t += at(&eq) * (at(&az) * at(&bz) - at(&cz));
+            }
+        }
+        e.iter().for_each(|&x| tr.absorb_field(x));
+        let r = tr.challenge(ds::WRAP_ROUND);
+        for table in [&mut eq, &mut az, &mut bz, &mut cz] {
+            bind(table, r);
+        }
+        outer_rounds.push(e);
+        r_x.push(r);
+    }
+    let claims = [az[0], bz[0], cz[0]];
+    claims.iter().for_each(|&x| tr.absorb_field(x));
+
+    // Σ_y M(y)·z(y) = Ãz + ρB̃z + ρ²C̃z at r_x
+    let rho = tr.challenge(ds::WRAP_RHO);
+    let mut m = combined_row(cs.constraints(), &r_x, rho, n, inputs.len()).expect("assignment fits the capacity");
+    let mut z = w.clone();
+    z.extend(public_half(inputs, n));
+    let mut inner_rounds = Vec::with_capacity(k + 1);
+    let mut r_y = Vec::with_capacity(k + 1);
+    for _ in 0..=k {
+        let mut e = [F::zero(); 3];
+        for j in 0..m.len() / 2 {
+            for (t, e_t) in e.iter_mut().enumerate() {
+                let t = F::from(t as u64);
+                let at = |v: &[F]| v[2 * j] + t * (v[2 * j + 1] - v[2 * j]);
+                *e_t += at(&m) * at(&z);
+            }
+        }
+        e.iter().for_each(|&x| tr.absorb_field(x));
+        let r = tr.challenge(ds::WRAP_ROUND);
+        bind(&mut m, r);
+        bind(&mut z, r);
+        inner_rounds.push(e);
+        r_y.push(r);
+    }
+
+    // w̃ at the low k coordinates, opened against the commitment.
+    let witness_eval = mle_eval(&w, &r_y[..k]);
+    tr.absorb_field(witness_eval);
+    let opening = ipa::open(&key.gens, &mut tr, &w, &eq_table(&r_y[..k]));
+
+    Ok(WrapProof {
+        witness_commitment,
+        outer_rounds,
+        claims,
+        inner_rounds,
+        witness_eval,
+        opening,
+    })
+}
+
+/// Checks `proof` for the system `constraints` on public `inputs`. Only
+/// the constraints are read, so any system of the same shape serves, such
+/// as one synthesized with a zero assignment.
+pub fn verify(key: &WrapKey, constraints: &[Constraint], inputs: &[F], proof: &WrapProof) -> bool {
+    let (k, n) = (key.log_capacity, key.capacity());
+    if key.fits(constraints.len(), 0, inputs.len()).is_err()
+        || proof.outer_rounds.len() != k
+        || proof.inner_rounds.len() != k + 1
+    {
+        return false;
+    }
+
+    let mut tr = statement_transcript(key, constraints, inputs);
+    absorb_point(&mut tr, &proof.witness_commitment);
+
+    let tau: Vec<F> = (0..k).map(|_| tr.challenge(ds::WRAP_TAU)).collect();
+    let mut claim = F::zero();
+    let mut r_x = Vec::with_capacity(k);
+    for e in &proof.outer_rounds {
+        if e[0] + e[1] != claim {
+            return false;
+        }
+        e.iter().for_each(|&x| tr.absorb_field(x));
+        let r = tr.challenge(ds::WRAP_ROUND);
+        claim = interpolate(e, r);
+        r_x.push(r);
+    }
+    let [a, b, c] = proof.claims;
+    if claim != eq_eval(&tau, &r_x) * (a * b - c) {
+        return false;
+    }
+    proof.claims.iter().for_each(|&x| tr.absorb_field(x));
+
+    let rho = tr.challenge(ds::WRAP_RHO);
+    let mut claim = a + rho * b + rho.square() * c;
+    let mut r_y = Vec::with_capacity(k + 1);
+    for e in &proof.inner_rounds {
+        if e[0] + e[1] != claim {
+            return false;
+        }
+        e.iter().for_each(|&x| tr.absorb_field(x));
+        let r = tr.challenge(ds::WRAP_ROUND);
+        claim = interpolate(e, r);
+        r_y.push(r);
+    }
+    let Some(m) = combined_row(constraints, &r_x, rho, n, inputs.len()) else {
+        return false;
+    };
+    let r_top = r_y[k];
+    let z_eval = (F::one() - r_top) * proof.witness_eval + r_top * mle_eval(&public_half(inputs, n), &r_y[..k]);
+    if claim != mle_eval(&m, &r_y) * z_eval {
+        return false;
+    }
+
+    tr.absorb_field(proof.witness_eval);
+    ipa::verify(
+        &key.gens,
+        &mut tr,
+        proof.witness_commitment,
+        &eq_table(&r_y[..k]),
+        proof.witness_eval,
+        &proof.opening,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r1cs::LinearCombination;
+    use ark_ec::CurveGroup;
+
+    /// `x³ + x + 5 = out` with `out` public.
+    fn cubic(x: u64) -> ConstraintSystem {
+        let mut cs = ConstraintSystem::new();
+        let out = cs.alloc_input(F::from(x * x * x + x + 5));
+        let xv = cs.alloc_witness(F::from(x));
+        let sq = cs.alloc_witness(F::from(x * x));
+        let cu = cs.alloc_witness(F::from(x * x * x));
+        cs.enforce(xv, xv, sq);
+        cs.enforce(sq, xv, cu);
+        cs.enforce(
+            LinearCombination::from(cu).plus(xv).term(F::from(5u64), Variable::One),
+            LinearCombination::constant(F::one()),
+            out,
+        );
+        cs
+    }
+
+    #[test]
+    fn round_trip() {
+        let key = WrapKey::new(3);
+        let cs = cubic(3);
+        let proof = prove(&key, &cs).unwrap();
+        assert!(verify(&key, cs.constraints(), cs.public_inputs(), &proof));
+        assert!(!verify(&key, cs.constraints(), &[F::from(36u64)], &proof));
+        assert!(!verify(&WrapKey::new(4), cs.constraints(), cs.public_inputs(), &proof));
+    }
+
+    #[test]
+    fn tampered_proofs_are_rejected() {
+        let key = WrapKey::new(3);
+        let cs = cubic(4);
+        let proof = prove(&key, &cs).unwrap();
+        let inputs = cs.public_inputs();
+
+        let mut bad = proof.clone();
+        bad.outer_rounds[1][2] += F::one();
+        assert!(!verify(&key, cs.constraints(), inputs, &bad));
+        let mut bad = proof.clone();
+        bad.claims[0] += F::one();
+        assert!(!verify(&key, cs.constraints(), inputs, &bad));
+        let mut bad = proof.clone();
+        bad.inner_rounds[3][0] += F::one();
+        assert!(!verify(&key, cs.constraints(), inputs, &bad));
+        let mut bad = proof.clone();
+        bad.witness_eval += F::one();
+        assert!(!verify(&key, cs.constraints(), inputs, &bad));
+        let mut bad = proof.clone();
+        bad.opening.a += F::one();
+        assert!(!verify(&key, cs.constraints(), inputs, &bad));
+        let mut bad = proof.clone();
+        bad.witness_commitment = (bad.witness_commitment + key.gens.g[0]).into_affine();
+        assert!(!verify(&key, cs.constraints(), inputs, &bad));
+    }
+
+    #[test]
+    fn unsatisfied_and_oversized_systems_are_not_proved() {
+        let mut cs = cubic(2);
+        let bad = cs.alloc_witness(F::from(2u64));
+        cs.enforce_boolean(bad);
+        assert_eq!(prove(&WrapKey::new(3), &cs).err(), Some(WrapError::Unsatisfied(3)));
+        assert_eq!(
+            prove(&WrapKey::new(1), &cubic(2)).err(),
+            Some(WrapError::Capacity { constraints: 3, witnesses: 3, inputs: 1 })
+        );
+    }
+
+    #[test]
+    fn interpolation_matches_the_polynomial() {
+        let p = |x: F| x * x * x - F::from(2u64) * x + F::from(7u64);
+        let evals: Vec<F> = (0..4u64).map(|t| p(F::from(t))).collect();
+        assert_eq!(interpolate(&evals, F::from(11u64)), p(F::from(11u64)));
+        let table: Vec<F> = (0..8u64).map(F::from).collect();
+        let point = [F::from(5u64), F::from(6u64), F::from(7u64)];
+        let direct: F = eq_table(&point).iter().zip(&table).map(|(e, t)| *e * t).sum();
+        assert_eq!(mle_eval(&table, &point), direct);
+    }
+}
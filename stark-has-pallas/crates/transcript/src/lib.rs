@@ -1,5 +1,5 @@
 //! Pluggable Fiat–Shamir transcript with backward-compatible API.
-//! Goldilocks-safe field embedding (64-bit).
+//! Challenges are uniform over the full Pallas field on every backend.
 #![allow(dead_code)]
 #![allow(non_snake_case)]
 #![allow(unused_variables)]
@@ -30,12 +30,44 @@ fn bytes_to_field_words(bytes: &[u8]) -> Vec<F> {
         .collect()
 }
 
+/// The element `bytes` encode little-endian, bits above the modulus width
+/// cleared, or `None` if that is not below the modulus.
+fn field_candidate(bytes: &[u8; 32]) -> Option<F> {
+    let mut word = *bytes;
+    word[31] &= 0xff >> (256 - F::MODULUS_BIT_SIZE as usize);
+    let mut value = <F as PrimeField>::BigInt::default();
+    for (limb, chunk) in value.as_mut().iter_mut().zip(word.chunks(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    (value < F::MODULUS).then(|| F::from_bigint(value)).flatten()
+}
+
+/// Challenge uniform over `F` by rejection sampling: attempt `i` is
+/// `digest(CHALLENGE ‖ label)` for `i = 0` and `digest(CHALLENGE ‖ label ‖ i)`
+/// after, `i` as 8 little-endian bytes, read as in [`field_candidate`].
+/// Reducing a 256-bit digest mod the 255-bit modulus instead would favour
+/// the low residues; about half the attempts are kept.
+fn uniform_challenge(digest: impl Fn(&[&[u8]]) -> [u8; 32], label: &[u8]) -> F {
+    (0u64..)
+        .find_map(|i| {
+            let out = match i {
+                0 => digest(&[ds::CHALLENGE, label]),
+                _ => digest(&[ds::CHALLENGE, label, &i.to_le_bytes()]),
+            };
+            field_candidate(&out)
+        })
+        .unwrap()
+}
+
 // ---------------- Hash backend abstraction ----------------
 
 pub trait HashBackend {
     fn name(&self) -> &'static str;
     fn absorb_bytes(&mut self, bytes: &[u8]);
     fn absorb_field(&mut self, x: F);
+    /// Uniform over the whole of `F`: Poseidon squeezes a Pallas element
+    /// directly and the byte backends use [`uniform_challenge`]. Nothing
+    /// here passes through a 64-bit word.
     fn challenge(&mut self, label: &[u8]) -> F;
 }
 
@@ -153,11 +185,16 @@ mod sha3_backend {
         }
 
         fn challenge(&mut self, label: &[u8]) -> F {
-            let mut h2 = self.h.clone();
-            h2.update(super::ds::CHALLENGE);
-            h2.update(label);
-            let out = h2.finalize();
-            F::from_le_bytes_mod_order(&out)
+            super::uniform_challenge(
+                |parts| {
+                    let mut h2 = self.h.clone();
+                    for part in parts {
+                        h2.update(part);
+                    }
+                    h2.finalize().into()
+                },
+                label,
+            )
         }
     }
 
@@ -203,11 +240,16 @@ mod blake3_backend {
         }
 
         fn challenge(&mut self, label: &[u8]) -> F {
-            let mut h2 = self.h.clone();
-            h2.update(super::ds::CHALLENGE);
-            h2.update(label);
-            let out = h2.finalize();
-            F::from_le_bytes_mod_order(out.as_bytes())
+            super::uniform_challenge(
+                |parts| {
+                    let mut h2 = self.h.clone();
+                    for part in parts {
+                        h2.update(part);
+                    }
+                    *h2.finalize().as_bytes()
+                },
+                label,
+            )
         }
     }
 
@@ -275,3 +317,72 @@ static PRINT_SELECTED_FS_BACKEND: Once = Once::new();
 fn emit_selected_backend_once() {
     PRINT_SELECTED_FS_BACKEND.call_once(|| {});
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::One;
+    use std::cell::RefCell;
+
+    fn transcript(hash: FsHash) -> Transcript {
+        let mut tr = Transcript::with_backend(hash, b"TEST/transcript", default_params());
+        tr.absorb_field(F::from(42u64));
+        tr
+    }
+
+    #[test]
+    fn candidates_past_the_modulus_are_rejected() {
+        let modulus: [u8; 32] = F::MODULUS.to_bytes_le().try_into().unwrap();
+        assert_eq!(field_candidate(&modulus), None);
+        let mut below = modulus;
+        below[0] -= 1;
+        assert_eq!(field_candidate(&below), Some(-F::one()));
+        // The bit above the modulus width is dropped, not reduced.
+        let mut high = [0u8; 32];
+        (high[0], high[31]) = (5, 0x80);
+        assert_eq!(field_candidate(&high), Some(F::from(5u64)));
+
+        // A rejected first attempt moves on to the counter-suffixed one.
+        let mut seven = [0u8; 32];
+        seven[0] = 7;
+        let attempts = RefCell::new(Vec::new());
+        let x = uniform_challenge(
+            |parts| {
+                attempts.borrow_mut().push(parts.concat());
+                match parts {
+                    [_, _] => modulus,
+                    _ => seven,
+                }
+            },
+            b"c",
+        );
+        assert_eq!(x, F::from(7u64));
+        assert_eq!(
+            attempts.into_inner(),
+            [[ds::CHALLENGE, b"c"].concat(), [ds::CHALLENGE, b"c", &1u64.to_le_bytes()].concat()]
+        );
+    }
+
+    #[test]
+    fn challenges_are_deterministic_per_label() {
+        for hash in [FsHash::Poseidon, FsHash::Sha3_256, FsHash::Blake3] {
+            let a = transcript(hash).challenge(b"a");
+            assert_eq!(transcript(hash).challenge(b"a"), a);
+            assert_ne!(transcript(hash).challenge(b"b"), a);
+
+            let mut other = transcript(hash);
+            other.absorb_bytes(b"x");
+            assert_ne!(other.challenge(b"a"), a);
+        }
+    }
+
+    #[test]
+    fn backends_disagree() {
+        let sha3 = transcript(FsHash::Sha3_256).challenge(b"c");
+        let blake3 = transcript(FsHash::Blake3).challenge(b"c");
+        let poseidon = transcript(FsHash::Poseidon).challenge(b"c");
+        assert_ne!(sha3, blake3);
+        assert_ne!(poseidon, sha3);
+        assert_ne!(poseidon, blake3);
+    }
+}